@@ -0,0 +1,174 @@
+/*!
+ * Run Artifacts
+ *
+ * `--run-dir=<dir>` (or AGENT_BROWSER_RUN_DIR) collects what a run produces
+ * in one place. Relative output paths (`screenshot shot.png`) land in the
+ * directory, screenshots and PDFs without a path get a generated name there,
+ * and every file written is listed in `<dir>/manifest.json` with its size,
+ * SHA-256 and when it was written:
+ *
+ * ```json
+ * {"artifacts": [{"path": "shot.png", "kind": "screenshot", "bytes": 48213,
+ *   "sha256": "9f2c...", "timestamp": 1760601600000, "command": "screenshot",
+ *   "session": "default"}]}
+ * ```
+ *
+ * Scripts pass the directory on to every step, so a `run` (including
+ * `--data`) ends up with one manifest for the whole run, plus its report.
+ */
+use std::fs::{self, OpenOptions};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+
+use crate::commands::CommandJson;
+use crate::connection::Response;
+use crate::pageerrors::now_ms;
+
+const MANIFEST: &str = "manifest.json";
+
+/// Result fields that name a file the command wrote
+const PATH_FIELDS: &[&str] = &["path", "saved", "manifest"];
+
+/// Point a file-producing command at the run directory
+pub fn prepare(cmd: &mut CommandJson, dir: &str) -> Result<(), String> {
+    let ext = match cmd.action.as_str() {
+        "screenshot" | "screenshotTiles" | "captureCanvas" => "png",
+        "pdf" => "pdf",
+        "harStart" => "har",
+        _ => return Ok(()),
+    };
+    fs::create_dir_all(dir).map_err(|e| format!("Failed to create run dir {}: {}", dir, e))?;
+    cmd.path = Some(match cmd.path.take() {
+        Some(path) if Path::new(&path).is_absolute() => path,
+        Some(path) => Path::new(dir).join(path).to_string_lossy().into_owned(),
+        None => Path::new(dir)
+            .join(format!("{}-{}.{}", cmd.action, now_ms(), ext))
+            .to_string_lossy()
+            .into_owned(),
+    });
+    Ok(())
+}
+
+/// List the files a successful command wrote
+pub fn record(dir: &str, cmd: &CommandJson, resp: &Response, session: &str) -> Result<(), String> {
+    let result = match resp.result {
+        Some(ref result) => result,
+        None => return Ok(()),
+    };
+    for field in PATH_FIELDS {
+        if let Some(path) = result.get(*field).and_then(|v| v.as_str()) {
+            if Path::new(path).is_file() {
+                add(dir, path, &kind(&cmd.action, path), &cmd.action, session)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn kind(action: &str, path: &str) -> String {
+    let ext = Path::new(path)
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    match (action, ext.as_str()) {
+        ("screenshot" | "screenshotTiles", _) => "screenshot",
+        (_, "png" | "jpg" | "jpeg" | "webp" | "gif") => "image",
+        (_, "pdf") => "pdf",
+        (_, "webm" | "mp4") => "video",
+        (_, "har") => "har",
+        (_, "zip") if action.contains("Trace") => "trace",
+        _ => "data",
+    }
+    .to_string()
+}
+
+/// Add (or refresh) one file in the run directory's manifest
+pub fn add(dir: &str, path: &str, kind: &str, command: &str, session: &str) -> Result<(), String> {
+    let bytes = fs::read(path).map_err(|e| format!("Failed to read artifact {}: {}", path, e))?;
+    let shown = relative_to(dir, path);
+    let entry = json!({
+        "path": shown,
+        "kind": kind,
+        "bytes": bytes.len(),
+        "sha256": sha256_hex(&bytes),
+        "timestamp": now_ms(),
+        "command": command,
+        "session": session,
+    });
+
+    fs::create_dir_all(dir).map_err(|e| format!("Failed to create run dir {}: {}", dir, e))?;
+    let _lock = ManifestLock::acquire(dir)?;
+    let manifest_path = Path::new(dir).join(MANIFEST);
+    let mut manifest: Value = fs::read_to_string(&manifest_path)
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_else(|| json!({"createdAt": now_ms(), "artifacts": []}));
+    if let Some(artifacts) = manifest["artifacts"].as_array_mut() {
+        artifacts.retain(|a| a["path"] != shown);
+        artifacts.push(entry);
+    }
+    manifest["updatedAt"] = json!(now_ms());
+
+    let json = serde_json::to_string_pretty(&manifest).unwrap_or_default();
+    fs::write(&manifest_path, json).map_err(|e| {
+        format!(
+            "Failed to write manifest {}: {}",
+            manifest_path.display(),
+            e
+        )
+    })
+}
+
+/// Paths inside the run directory are listed relative to it
+fn relative_to(dir: &str, path: &str) -> String {
+    let canonical = |p: &str| fs::canonicalize(p).unwrap_or_else(|_| PathBuf::from(p));
+    let (dir, path) = (canonical(dir), canonical(path));
+    path.strip_prefix(&dir)
+        .unwrap_or(&path)
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// Serializes manifest updates from parallel script steps
+struct ManifestLock(PathBuf);
+
+impl ManifestLock {
+    fn acquire(dir: &str) -> Result<ManifestLock, String> {
+        let lock = Path::new(dir).join(".manifest.lock");
+        let deadline = Instant::now() + Duration::from_secs(10);
+        loop {
+            if OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&lock)
+                .is_ok()
+            {
+                return Ok(ManifestLock(lock));
+            }
+            if Instant::now() >= deadline {
+                // Left behind by a process that died mid-update
+                fs::remove_file(&lock).ok();
+                return Err(format!("Timed out waiting for {}", lock.display()));
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+}
+
+impl Drop for ManifestLock {
+    fn drop(&mut self) {
+        fs::remove_file(&self.0).ok();
+    }
+}
+
+/// SHA-256 of `data` as lowercase hex
+pub fn sha256_hex(data: &[u8]) -> String {
+    Sha256::digest(data)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
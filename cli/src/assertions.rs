@@ -0,0 +1,527 @@
+/*!
+ * JSON Assertions
+ *
+ * `expect json <path> <matcher> [value]` checks a value in a command's JSON
+ * result. In a `run` script it checks the step before it, so API-level
+ * checks sit next to the UI steps that cause them:
+ *
+ * ```text
+ * eval "fetch('/api/cart').then(r => r.json())"
+ * expect json $.result.items[0].sku equals A-100
+ * expect json $.result.total gt 0
+ * ```
+ *
+ * On its own it checks JSON piped in, such as another command's `--json`
+ * output.
+ *
+ * Paths are `$` followed by `.key`, `['key']`, `[0]`, `[-1]` (from the end)
+ * or `[*]` / `.*` (every item; the matcher then sees the list of them).
+ * `matches` takes a pattern in the `regex` crate's syntax, which never
+ * backtracks, so a long response can't stall a script.
+ */
+use regex::Regex;
+use serde_json::{json, Value};
+
+use crate::commands::ParseError;
+
+pub const JSON_USAGE: &str = "expect json <path> <equals|not-equals|contains|not-contains|matches|gt|gte|lt|lte|type|length> <value> | expect json <path> <exists|absent>";
+
+/// Matchers that take a value, then those that don't
+const BINARY_MATCHERS: &[&str] = &[
+    "equals",
+    "not-equals",
+    "contains",
+    "not-contains",
+    "matches",
+    "gt",
+    "gte",
+    "lt",
+    "lte",
+    "type",
+    "length",
+];
+const UNARY_MATCHERS: &[&str] = &["exists", "absent"];
+
+const TYPES: &[&str] = &["string", "number", "boolean", "null", "array", "object"];
+
+#[derive(Debug, Clone)]
+enum Segment {
+    Key(String),
+    Index(i64),
+    Every,
+}
+
+#[derive(Debug, Clone)]
+pub struct JsonExpectation {
+    path: String,
+    segments: Vec<Segment>,
+    matcher: String,
+    expected: Option<String>,
+}
+
+fn canonical_matcher(name: &str) -> &str {
+    match name {
+        "eq" | "equal" | "=" | "==" => "equals",
+        "ne" | "not-equal" | "!=" => "not-equals",
+        "matches-regex" | "regex" => "matches",
+        ">" => "gt",
+        ">=" => "gte",
+        "<" => "lt",
+        "<=" => "lte",
+        other => other,
+    }
+}
+
+/// Parse a path like `$.items[0]['display name']`
+fn parse_path(path: &str) -> Result<Vec<Segment>, String> {
+    let chars: Vec<char> = path.strip_prefix('$').unwrap_or(path).chars().collect();
+    let mut segments = Vec::new();
+    let mut i = 0;
+    let key_end = |from: usize| {
+        (from..chars.len())
+            .find(|&j| chars[j] == '.' || chars[j] == '[')
+            .unwrap_or(chars.len())
+    };
+    // A path may start with a bare key: `items[0]` is `$.items[0]`
+    if chars.first().is_some_and(|&c| c != '.' && c != '[') {
+        let end = key_end(0);
+        segments.push(Segment::Key(chars[..end].iter().collect()));
+        i = end;
+    }
+    while i < chars.len() {
+        match chars[i] {
+            '.' => {
+                let end = key_end(i + 1);
+                let key: String = chars[i + 1..end].iter().collect();
+                segments.push(match key.as_str() {
+                    "" => return Err(format!("empty key at position {}", i + 1)),
+                    "*" => Segment::Every,
+                    _ => Segment::Key(key),
+                });
+                i = end;
+            }
+            '[' => {
+                let unclosed = || format!("missing ']' after position {}", i);
+                match chars.get(i + 1) {
+                    Some(&quote) if quote == '\'' || quote == '"' => {
+                        let end = (i + 2..chars.len())
+                            .find(|&j| chars[j] == quote && chars.get(j + 1) == Some(&']'))
+                            .ok_or_else(unclosed)?;
+                        segments.push(Segment::Key(chars[i + 2..end].iter().collect()));
+                        i = end + 2;
+                    }
+                    _ => {
+                        let close = (i + 1..chars.len())
+                            .find(|&j| chars[j] == ']')
+                            .ok_or_else(unclosed)?;
+                        let inner: String = chars[i + 1..close].iter().collect();
+                        segments.push(if inner == "*" {
+                            Segment::Every
+                        } else if let Ok(n) = inner.parse::<i64>() {
+                            Segment::Index(n)
+                        } else {
+                            return Err(format!(
+                                "'[{}]' is not an index, '*' or a quoted key",
+                                inner
+                            ));
+                        });
+                        i = close + 1;
+                    }
+                }
+            }
+            c => return Err(format!("unexpected '{}' at position {}", c, i)),
+        }
+    }
+    Ok(segments)
+}
+
+impl JsonExpectation {
+    /// Parse the words after `expect json`
+    pub fn parse(args: &[String]) -> Result<JsonExpectation, ParseError> {
+        let missing = || ParseError::MissingArguments {
+            context: "expect json".to_string(),
+            usage: JSON_USAGE,
+        };
+        let path = args.first().ok_or_else(missing)?;
+        let matcher = canonical_matcher(args.get(1).ok_or_else(missing)?);
+        let segments = parse_path(path).map_err(|e| ParseError::InvalidValue {
+            field: "path".to_string(),
+            value: path.clone(),
+            expected: format!("a JSON path like $.items[0].name ({})", e),
+        })?;
+
+        let expected = if BINARY_MATCHERS.contains(&matcher) {
+            if args.len() < 3 {
+                return Err(missing());
+            }
+            Some(args[2..].join(" "))
+        } else if UNARY_MATCHERS.contains(&matcher) {
+            None
+        } else {
+            return Err(ParseError::InvalidValue {
+                field: "matcher".to_string(),
+                value: args[1].clone(),
+                expected: [BINARY_MATCHERS, UNARY_MATCHERS].concat().join(", "),
+            });
+        };
+
+        let invalid = |expected: &str| ParseError::InvalidValue {
+            field: matcher.to_string(),
+            value: args[2..].join(" "),
+            expected: expected.to_string(),
+        };
+        let value = expected.as_deref().unwrap_or("");
+        match matcher {
+            "gt" | "gte" | "lt" | "lte" if value.parse::<f64>().is_err() => {
+                return Err(invalid("a number"))
+            }
+            "length" if value.parse::<usize>().is_err() => {
+                return Err(invalid("a non-negative whole number"))
+            }
+            "type" if !TYPES.contains(&value) => return Err(invalid(&TYPES.join(", "))),
+            "matches" => {
+                Regex::new(value).map_err(|e| invalid(&format!("a regex ({})", e)))?;
+            }
+            _ => {}
+        }
+
+        Ok(JsonExpectation {
+            path: path.clone(),
+            segments,
+            matcher: matcher.to_string(),
+            expected,
+        })
+    }
+
+    /// The value at the path: one value, the list a `[*]` gathered, or
+    /// None when nothing is there
+    fn select(&self, root: &Value) -> Option<Value> {
+        let mut current = vec![root];
+        let mut every = false;
+        for segment in &self.segments {
+            current = current
+                .into_iter()
+                .flat_map(|value| -> Vec<&Value> {
+                    match segment {
+                        Segment::Key(key) => value.get(key).into_iter().collect(),
+                        Segment::Index(n) => {
+                            let items = value.as_array().map(Vec::as_slice).unwrap_or(&[]);
+                            let index = if *n < 0 {
+                                items.len().checked_sub(n.unsigned_abs() as usize)
+                            } else {
+                                Some(*n as usize)
+                            };
+                            index.and_then(|i| items.get(i)).into_iter().collect()
+                        }
+                        Segment::Every => match value {
+                            Value::Array(items) => items.iter().collect(),
+                            Value::Object(map) => map.values().collect(),
+                            _ => Vec::new(),
+                        },
+                    }
+                })
+                .collect();
+            every |= matches!(segment, Segment::Every);
+        }
+        if every {
+            Some(Value::Array(current.into_iter().cloned().collect()))
+        } else {
+            current.first().map(|v| (*v).clone())
+        }
+    }
+
+    /// Check the expectation against `root`. Ok has what was found; Err
+    /// describes the mismatch.
+    pub fn check(&self, root: &Value) -> Result<Value, String> {
+        let actual = self.select(root);
+        let expected = self.expected.as_deref().unwrap_or("");
+        let passed = match (self.matcher.as_str(), &actual) {
+            ("exists", found) => found.is_some(),
+            ("absent", found) => found.is_none(),
+            (_, None) => false,
+            ("equals", Some(actual)) => equals(actual, expected),
+            ("not-equals", Some(actual)) => !equals(actual, expected),
+            ("contains", Some(actual)) => contains(actual, expected),
+            ("not-contains", Some(actual)) => !contains(actual, expected),
+            ("matches", Some(actual)) => Regex::new(expected)
+                .map(|re| re.is_match(&text_of(actual)))
+                .unwrap_or(false),
+            ("type", Some(actual)) => type_of(actual) == expected,
+            ("length", Some(actual)) => {
+                let len = match actual {
+                    Value::String(s) => Some(s.chars().count()),
+                    Value::Array(items) => Some(items.len()),
+                    Value::Object(map) => Some(map.len()),
+                    _ => None,
+                };
+                len.is_some() && len == expected.parse().ok()
+            }
+            (op, Some(actual)) => match (number_of(actual), expected.parse::<f64>()) {
+                (Some(a), Ok(b)) => match op {
+                    "gt" => a > b,
+                    "gte" => a >= b,
+                    "lt" => a < b,
+                    _ => a <= b,
+                },
+                _ => false,
+            },
+        };
+
+        if passed {
+            return Ok(json!({
+                "path": self.path,
+                "matcher": self.matcher,
+                "expected": self.expected,
+                "actual": actual,
+            }));
+        }
+        let wanted = match self.matcher.as_str() {
+            "exists" => "a value".to_string(),
+            "absent" => "no value".to_string(),
+            "equals" => expected.to_string(),
+            "not-equals" => format!("anything but {}", expected),
+            "contains" => format!("something containing {}", expected),
+            "not-contains" => format!("nothing containing {}", expected),
+            "matches" => format!("a match for /{}/", expected),
+            "type" => format!("a {}", expected),
+            "length" => format!("length {}", expected),
+            "gt" => format!("> {}", expected),
+            "gte" => format!(">= {}", expected),
+            "lt" => format!("< {}", expected),
+            _ => format!("<= {}", expected),
+        };
+        let found = match actual {
+            Some(ref value) if self.matcher == "length" => format!(
+                "{} (length {})",
+                value,
+                match value {
+                    Value::String(s) => s.chars().count().to_string(),
+                    Value::Array(items) => items.len().to_string(),
+                    Value::Object(map) => map.len().to_string(),
+                    _ => "n/a".to_string(),
+                }
+            ),
+            Some(ref value) if self.matcher == "type" => {
+                format!("{} ({})", value, type_of(value))
+            }
+            Some(value) => value.to_string(),
+            None => "nothing at this path".to_string(),
+        };
+        Err(format!(
+            "expect json {} {}\n  - expected: {}\n  + actual:   {}",
+            self.path, self.matcher, wanted, found
+        ))
+    }
+}
+
+/// The expected value as JSON when it parses as JSON, else as a string
+fn expected_value(expected: &str) -> Value {
+    serde_json::from_str(expected).unwrap_or_else(|_| Value::String(expected.to_string()))
+}
+
+fn equals(actual: &Value, expected: &str) -> bool {
+    let wanted = expected_value(expected);
+    match (actual, &wanted) {
+        (Value::Number(a), Value::Number(b)) => a.as_f64() == b.as_f64(),
+        // `equals 42` also matches the string "42"
+        (Value::String(a), _) => a == expected || *actual == wanted,
+        _ => *actual == wanted,
+    }
+}
+
+fn contains(actual: &Value, expected: &str) -> bool {
+    match actual {
+        Value::String(s) => s.contains(expected),
+        Value::Array(items) => items.iter().any(|item| equals(item, expected)),
+        Value::Object(map) => map.contains_key(expected),
+        other => other.to_string().contains(expected),
+    }
+}
+
+fn text_of(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+fn number_of(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => s.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+fn type_of(value: &Value) -> &'static str {
+    match value {
+        Value::String(_) => "string",
+        Value::Number(_) => "number",
+        Value::Bool(_) => "boolean",
+        Value::Null => "null",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn expectation(args: &[&str]) -> Result<JsonExpectation, ParseError> {
+        JsonExpectation::parse(&args.iter().map(|s| s.to_string()).collect::<Vec<_>>())
+    }
+
+    fn matches(pattern: &str, text: &str) -> bool {
+        expectation(&["$.text", "matches", pattern])
+            .unwrap()
+            .check(&json!({ "text": text }))
+            .is_ok()
+    }
+
+    #[test]
+    fn matcher_aliases() {
+        let root = json!({ "n": 3 });
+        let check = |args: &[&str]| expectation(args).unwrap().check(&root).is_ok();
+        assert!(check(&["$.n", "==", "3"]));
+        assert!(check(&["$.n", "eq", "3"]));
+        assert!(check(&["$.n", "!=", "4"]));
+        assert!(check(&["$.n", ">", "2"]));
+        assert!(check(&["$.n", "<=", "3"]));
+        assert!(!check(&["$.n", "<", "3"]));
+        assert_eq!(expectation(&["$.n", "=", "3"]).unwrap().matcher, "equals");
+    }
+
+    #[test]
+    fn values_are_checked_when_parsed() {
+        let field = |args: &[&str]| match expectation(args) {
+            Err(ParseError::InvalidValue { field, .. }) => field,
+            other => panic!(
+                "expected an invalid value, got {:?}",
+                other.map(|e| e.matcher)
+            ),
+        };
+        assert_eq!(field(&["$.n", "gt", "many"]), "gt");
+        assert_eq!(field(&["$.n", "length", "-1"]), "length");
+        assert_eq!(field(&["$.n", "type", "integer"]), "type");
+        assert_eq!(field(&["$.n", "between", "1"]), "matcher");
+        assert_eq!(field(&["$..n", "exists"]), "path");
+        assert!(matches!(
+            expectation(&["$.n", "equals"]),
+            Err(ParseError::MissingArguments { .. })
+        ));
+        assert!(matches!(
+            expectation(&["$.n"]),
+            Err(ParseError::MissingArguments { .. })
+        ));
+        // The rest of the words are the expected value
+        let spaced = expectation(&["$.title", "equals", "Order", "placed"]).unwrap();
+        assert!(spaced.check(&json!({ "title": "Order placed" })).is_ok());
+    }
+
+    #[test]
+    fn regex_anchors_groups_and_alternation() {
+        assert!(matches("^(draft|sent)$", "sent"));
+        assert!(!matches("^(draft|sent)$", "resent"));
+        assert!(matches("(?:ab)+c", "ababc"));
+        assert!(matches("^$", ""));
+        assert!(matches("(?i)^total", "TOTAL: 3"));
+    }
+
+    #[test]
+    fn equals_and_contains_compare_json_or_text() {
+        let root = json!({
+            "n": 42,
+            "id": "42",
+            "price": 1.5,
+            "ok": true,
+            "tags": ["new", 7],
+            "meta": { "sku": "A" },
+        });
+        let check = |args: &[&str]| expectation(args).unwrap().check(&root).is_ok();
+        assert!(check(&["$.n", "equals", "42.0"]));
+        assert!(check(&["$.id", "equals", "42"]));
+        assert!(!check(&["$.n", "equals", "\"42\""]));
+        assert!(check(&["$.ok", "equals", "true"]));
+        assert!(check(&["$.meta", "equals", r#"{"sku": "A"}"#]));
+        assert!(check(&["$.tags", "contains", "7"]));
+        assert!(!check(&["$.tags", "contains", "ne"]));
+        assert!(check(&["$.meta", "contains", "sku"]));
+        assert!(check(&["$.price", "contains", "."]));
+        assert!(check(&["$.id", "gt", "41"]));
+        assert!(!check(&["$.ok", "gt", "0"]));
+        assert!(!check(&["$.missing", "not-equals", "1"]));
+    }
+
+    #[test]
+    fn length_counts_characters_items_and_keys() {
+        let root = json!({ "name": "Zoë", "items": [1, 2], "meta": { "a": 1 }, "n": 5 });
+        let check = |args: &[&str]| expectation(args).unwrap().check(&root).is_ok();
+        assert!(check(&["$.name", "length", "3"]));
+        assert!(check(&["$.items", "length", "2"]));
+        assert!(check(&["$.meta", "length", "1"]));
+        assert!(!check(&["$.n", "length", "1"]));
+    }
+
+    #[test]
+    fn regex_checks_non_strings_as_json_text() {
+        let total = expectation(&["$.total", "matches", r"^\d+\.\d{2}$"]).unwrap();
+        assert!(total.check(&json!({ "total": 12.5 })).is_err());
+        assert!(total.check(&json!({ "total": "12.50" })).is_ok());
+    }
+
+    #[test]
+    fn invalid_regex_is_a_parse_error() {
+        assert!(expectation(&["$.a", "matches", "(open"]).is_err());
+        assert!(expectation(&["$.a", "matches", "[z-a]"]).is_err());
+        assert!(expectation(&["$.a", "matches", "*"]).is_err());
+    }
+
+    #[test]
+    fn failures_show_expected_and_actual() {
+        let root = json!({ "items": [1, 2], "n": 3 });
+        let failure = |args: &[&str]| expectation(args).unwrap().check(&root).unwrap_err();
+        assert_eq!(
+            failure(&["$.n", "gt", "5"]),
+            "expect json $.n gt\n  - expected: > 5\n  + actual:   3"
+        );
+        assert_eq!(
+            failure(&["$.items", "length", "3"]),
+            "expect json $.items length\n  - expected: length 3\n  + actual:   [1,2] (length 2)"
+        );
+        assert_eq!(
+            failure(&["$.n", "type", "string"]),
+            "expect json $.n type\n  - expected: a string\n  + actual:   3 (number)"
+        );
+        assert_eq!(
+            failure(&["$.total", "exists"]),
+            "expect json $.total exists\n  - expected: a value\n  + actual:   nothing at this path"
+        );
+    }
+
+    #[test]
+    fn paths() {
+        let root = json!({ "items": [{ "sku": "A" }, { "sku": "B" }], "n": 3 });
+        let check = |args: &[&str]| expectation(args).unwrap().check(&root).is_ok();
+        assert!(check(&["$.items[0].sku", "equals", "A"]));
+        assert!(check(&["$.items[-1].sku", "equals", "B"]));
+        assert!(check(&["$['items'][*].sku", "contains", "B"]));
+        assert!(check(&["$.items", "length", "2"]));
+        assert!(check(&["$.n", "gte", "3"]));
+        assert!(check(&["$.missing", "absent"]));
+        assert!(!check(&["$.n", "type", "string"]));
+        assert!(check(&["items[1].sku", "equals", "B"]));
+        assert!(check(&["$.items[-3]", "absent"]));
+        assert!(check(&["$.items.*.sku", "equals", r#"["A", "B"]"#]));
+        assert!(expectation(&["$.items[x]", "exists"]).is_err());
+        assert!(expectation(&["$.items[0", "exists"]).is_err());
+    }
+
+    #[test]
+    fn quoted_keys_hold_dots_and_brackets() {
+        let root = json!({ "display name": { "a.b": { "[x]": 1 } } });
+        let check = |args: &[&str]| expectation(args).unwrap().check(&root).is_ok();
+        assert!(check(&["$['display name'][\"a.b\"]['[x]']", "equals", "1"]));
+        assert!(expectation(&["$['open", "exists"]).is_err());
+    }
+}
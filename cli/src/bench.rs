@@ -0,0 +1,137 @@
+/*!
+ * Latency Benchmark
+ *
+ * `bench <command...>` runs a command repeatedly and reports percentiles for
+ * two costs separately: the CLI process itself (spawned with `--dry-run`, so
+ * it parses and exits without touching the daemon) and the daemon round trip.
+ */
+use std::env;
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+use serde_json::json;
+
+use crate::commands::CommandJson;
+use crate::connection::send_command;
+use crate::flags::Flags;
+
+/// Iterations when `--iterations` is not given
+pub const DEFAULT_ITERATIONS: usize = 100;
+
+struct Stats {
+    p50: f64,
+    p95: f64,
+    p99: f64,
+    mean: f64,
+}
+
+impl Stats {
+    fn from_samples(samples: &mut [Duration]) -> Self {
+        samples.sort();
+        let ms = |d: Duration| d.as_secs_f64() * 1000.0;
+        let total: Duration = samples.iter().sum();
+        Stats {
+            p50: ms(percentile(samples, 50)),
+            p95: ms(percentile(samples, 95)),
+            p99: ms(percentile(samples, 99)),
+            mean: ms(total) / samples.len().max(1) as f64,
+        }
+    }
+
+    fn to_json(&self) -> serde_json::Value {
+        json!({
+            "p50": round(self.p50),
+            "p95": round(self.p95),
+            "p99": round(self.p99),
+            "mean": round(self.mean),
+        })
+    }
+}
+
+/// Nearest-rank percentile of sorted samples
+fn percentile(sorted: &[Duration], p: usize) -> Duration {
+    if sorted.is_empty() {
+        return Duration::ZERO;
+    }
+    let rank = (p * sorted.len()).div_ceil(100).max(1);
+    sorted[rank - 1]
+}
+
+fn round(ms: f64) -> f64 {
+    (ms * 1000.0).round() / 1000.0
+}
+
+/// Benchmark `cmd` (parsed from `args`) for `iterations` runs
+pub fn run(args: &[String], cmd: &CommandJson, iterations: usize, flags: &Flags) {
+    let exe = match env::current_exe() {
+        Ok(exe) => exe,
+        Err(e) => crate::exit_error(&format!("Cannot locate CLI binary: {}", e), flags.json),
+    };
+
+    // One untimed round trip so daemon start-up and page warm-up don't skew p99
+    if let Err(e) = send_command(cmd, &flags.session) {
+        crate::exit_error(&e, flags.json);
+    }
+
+    let mut cli = Vec::with_capacity(iterations);
+    let mut daemon = Vec::with_capacity(iterations);
+    let mut failures = 0;
+
+    for _ in 0..iterations {
+        let start = Instant::now();
+        let status = Command::new(&exe)
+            .arg("--dry-run")
+            .arg(format!("--session={}", flags.session))
+            .arg("--")
+            .args(args)
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status();
+        if status.is_err() {
+            failures += 1;
+        }
+        cli.push(start.elapsed());
+
+        let start = Instant::now();
+        match send_command(cmd, &flags.session) {
+            Ok(resp) if resp.success => {}
+            _ => failures += 1,
+        }
+        daemon.push(start.elapsed());
+    }
+
+    let cli = Stats::from_samples(&mut cli);
+    let daemon = Stats::from_samples(&mut daemon);
+
+    if flags.json {
+        let out = json!({
+            "success": true,
+            "result": {
+                "command": args.join(" "),
+                "iterations": iterations,
+                "failures": failures,
+                "cli": cli.to_json(),
+                "daemon": daemon.to_json(),
+            },
+        });
+        println!("{}", serde_json::to_string_pretty(&out).unwrap_or_default());
+        return;
+    }
+
+    println!("Benchmark: {} ({} iterations)", args.join(" "), iterations);
+    println!();
+    println!(
+        "  {:<12} {:>9} {:>9} {:>9} {:>9}",
+        "", "p50", "p95", "p99", "mean"
+    );
+    for (label, stats) in [("CLI", &cli), ("Daemon", &daemon)] {
+        println!(
+            "  {:<12} {:>7.2}ms {:>7.2}ms {:>7.2}ms {:>7.2}ms",
+            label, stats.p50, stats.p95, stats.p99, stats.mean
+        );
+    }
+    if failures > 0 {
+        println!();
+        println!("\x1b[33m{} of {} runs failed\x1b[0m", failures, iterations);
+    }
+}
@@ -0,0 +1,166 @@
+/*!
+ * Result Cache for Read-Only Commands
+ *
+ * `--cache=2s` lets agents re-ask for the URL, title, a snapshot or a count
+ * between micro-steps without a round trip to the browser. Entries live in a
+ * per-session file keyed on the exact command payload, and any command that
+ * may change the page clears them.
+ */
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::connection::Response;
+use crate::registry::find_action;
+
+#[derive(Debug, Deserialize, Serialize)]
+struct Entry {
+    /// When the response was stored (ms since the epoch)
+    at: u64,
+    response: Response,
+}
+
+/// Get the cache file path for a session
+fn get_cache_path(session: &str) -> String {
+    env::temp_dir()
+        .join(format!("agentbrowser-pro-{}.cache.json", session))
+        .to_string_lossy()
+        .into_owned()
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+fn load(session: &str) -> BTreeMap<String, Entry> {
+    fs::read_to_string(get_cache_path(session))
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+/// Parse a TTL such as `2s`, `500ms`, `1m` or `1h` (a bare number is
+/// milliseconds)
+pub fn parse_ttl(spec: &str) -> Option<Duration> {
+    let spec = spec.trim();
+    let (number, scale) = if let Some(n) = spec.strip_suffix("ms") {
+        (n, 1)
+    } else if let Some(n) = spec.strip_suffix('s') {
+        (n, 1000)
+    } else if let Some(n) = spec.strip_suffix('m') {
+        (n, 60_000)
+    } else if let Some(n) = spec.strip_suffix('h') {
+        (n, 3_600_000)
+    } else {
+        (spec, 1)
+    };
+    let number: u64 = number.parse().ok()?;
+    Some(Duration::from_millis(number.checked_mul(scale)?))
+}
+
+/// A stored response for `key` that is younger than `ttl`
+pub fn lookup(session: &str, key: &str, ttl: Duration) -> Option<Response> {
+    let entry = load(session).remove(key)?;
+    if now_ms().saturating_sub(entry.at) <= ttl.as_millis() as u64 {
+        Some(entry.response)
+    } else {
+        None
+    }
+}
+
+/// Remember a successful response under `key`
+pub fn store(session: &str, key: &str, response: &Response) {
+    let mut entries = load(session);
+    entries.insert(
+        key.to_string(),
+        Entry {
+            at: now_ms(),
+            response: response.clone(),
+        },
+    );
+    if let Ok(json) = serde_json::to_string(&entries) {
+        fs::write(get_cache_path(session), json).ok();
+    }
+}
+
+/// Drop every cached response for a session
+pub fn clear(session: &str) {
+    fs::remove_file(get_cache_path(session)).ok();
+}
+
+/// Drop the session's cached responses unless `action` is one that
+/// `--cache` may answer, and so cannot have changed the page
+pub fn invalidate(session: &str, action: &str) {
+    if !find_action(action).is_some_and(|spec| spec.accepts("--cache")) {
+        clear(session);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::thread;
+
+    /// A session of its own, so parallel tests don't share a cache file
+    fn session(name: &str) -> String {
+        format!("cache-test-{}-{}", name, std::process::id())
+    }
+
+    #[test]
+    fn parses_ttls() {
+        assert_eq!(parse_ttl("500ms"), Some(Duration::from_millis(500)));
+        assert_eq!(parse_ttl("2s"), Some(Duration::from_secs(2)));
+        assert_eq!(parse_ttl("1m"), Some(Duration::from_secs(60)));
+        assert_eq!(parse_ttl(" 1h "), Some(Duration::from_secs(3600)));
+        assert_eq!(parse_ttl("250"), Some(Duration::from_millis(250)));
+        assert_eq!(parse_ttl("soon"), None);
+        assert_eq!(parse_ttl(""), None);
+        assert_eq!(parse_ttl(&format!("{}h", u64::MAX)), None);
+    }
+
+    #[test]
+    fn entries_are_served_until_their_ttl_runs_out() {
+        let session = session("ttl");
+        store(
+            &session,
+            "url",
+            &Response::ok("1", json!({"url": "about:blank"})),
+        );
+
+        let hit = lookup(&session, "url", Duration::from_secs(60)).unwrap();
+        assert_eq!(hit.result, Some(json!({"url": "about:blank"})));
+        assert!(lookup(&session, "title", Duration::from_secs(60)).is_none());
+
+        thread::sleep(Duration::from_millis(20));
+        assert!(lookup(&session, "url", Duration::from_millis(5)).is_none());
+        clear(&session);
+    }
+
+    #[test]
+    fn commands_that_may_change_the_page_invalidate() {
+        let session = session("invalidate");
+        let ttl = Duration::from_secs(60);
+        store(&session, "snapshot", &Response::ok("1", json!({})));
+
+        invalidate(&session, "snapshot");
+        assert!(lookup(&session, "snapshot", ttl).is_some());
+
+        invalidate(&session, "click");
+        assert!(lookup(&session, "snapshot", ttl).is_none());
+
+        store(&session, "snapshot", &Response::ok("1", json!({})));
+        invalidate(&session, "someDaemonOnlyAction");
+        assert!(lookup(&session, "snapshot", ttl).is_none());
+
+        store(&session, "snapshot", &Response::ok("1", json!({})));
+        clear(&session);
+        assert!(lookup(&session, "snapshot", ttl).is_none());
+    }
+}
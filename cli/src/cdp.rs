@@ -0,0 +1,256 @@
+/*!
+ * Chrome DevTools Protocol Client
+ *
+ * Launches Chromium with `--remote-debugging-port=0`, reads the browser's
+ * WebSocket endpoint from its stderr, and drives one page through a
+ * flattened target session. Calls are synchronous: `call` sends a request
+ * and reads messages until its response arrives, dismissing any JavaScript
+ * dialog that would otherwise block the page in the meantime.
+ */
+use std::env;
+use std::fs;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+use serde_json::{json, Value};
+
+use crate::websocket::WebSocket;
+
+/// Browser binaries looked up on PATH, most specific first
+const BROWSER_NAMES: &[&str] = &[
+    "google-chrome",
+    "google-chrome-stable",
+    "chromium",
+    "chromium-browser",
+    "chrome",
+    "microsoft-edge",
+];
+
+/// Fixed install locations checked after PATH
+const BROWSER_PATHS: &[&str] = &[
+    "/Applications/Google Chrome.app/Contents/MacOS/Google Chrome",
+    "/Applications/Chromium.app/Contents/MacOS/Chromium",
+    "/Applications/Microsoft Edge.app/Contents/MacOS/Microsoft Edge",
+];
+
+/// Find a Chromium-family browser: the given path, one on PATH, a standard
+/// install, or one Playwright already downloaded
+pub fn find_browser(executable_path: Option<&str>) -> Result<PathBuf, String> {
+    if let Some(path) = executable_path {
+        return Ok(PathBuf::from(path));
+    }
+    let on_path = env::var_os("PATH").into_iter().flat_map(|paths| {
+        env::split_paths(&paths)
+            .flat_map(|dir| BROWSER_NAMES.iter().map(move |name| dir.join(name)))
+            .collect::<Vec<_>>()
+    });
+    let installed = BROWSER_PATHS.iter().map(PathBuf::from);
+    let playwright = env::var_os("HOME")
+        .map(|home| Path::new(&home).join(".cache/ms-playwright"))
+        .and_then(|dir| fs::read_dir(dir).ok())
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter(|entry| entry.file_name().to_string_lossy().starts_with("chromium-"))
+        .flat_map(|entry| {
+            let dir = entry.path();
+            [
+                dir.join("chrome-linux/chrome"),
+                dir.join("chrome-mac/Chromium.app/Contents/MacOS/Chromium"),
+            ]
+        });
+    on_path
+        .chain(installed)
+        .chain(playwright)
+        .find(|path| path.is_file())
+        .ok_or_else(|| {
+            "No Chrome or Chromium found; install one or pass --executable-path".to_string()
+        })
+}
+
+pub struct Browser {
+    process: Child,
+    profile: PathBuf,
+    ws: WebSocket,
+    next_id: u64,
+    /// Flattened session of the page being driven
+    session: String,
+}
+
+impl Browser {
+    /// Start the browser with a throwaway profile and open a blank page
+    pub fn launch(
+        executable: &Path,
+        headed: bool,
+        profile: PathBuf,
+        extensions: &[String],
+    ) -> Result<Browser, String> {
+        fs::create_dir_all(&profile)
+            .map_err(|e| format!("Failed to create {}: {}", profile.display(), e))?;
+        let mut cmd = Command::new(executable);
+        cmd.arg("--remote-debugging-port=0")
+            .arg(format!("--user-data-dir={}", profile.display()))
+            .args([
+                "--no-first-run",
+                "--no-default-browser-check",
+                "--disable-background-networking",
+                "--disable-sync",
+                "--password-store=basic",
+            ]);
+        if !headed {
+            cmd.arg("--headless=new");
+        }
+        if !extensions.is_empty() {
+            cmd.arg(format!("--load-extension={}", extensions.join(",")));
+        }
+        // Chromium refuses to run as root without it, as in containers
+        #[cfg(unix)]
+        if unsafe { libc::geteuid() } == 0 {
+            cmd.arg("--no-sandbox");
+        }
+        let mut process = cmd
+            .arg("about:blank")
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| format!("Failed to start {}: {}", executable.display(), e))?;
+
+        // The endpoint is announced on stderr; keep draining it afterwards so
+        // the browser never blocks on a full pipe
+        let stderr = process.stderr.take().expect("stderr is piped");
+        let (tx, rx) = mpsc::channel();
+        thread::spawn(move || {
+            for line in BufReader::new(stderr).lines().map_while(Result::ok) {
+                if let Some(url) = line.strip_prefix("DevTools listening on ") {
+                    tx.send(url.trim().to_string()).ok();
+                }
+            }
+        });
+        let url = match rx.recv_timeout(Duration::from_secs(20)) {
+            Ok(url) => url,
+            Err(_) => {
+                process.kill().ok();
+                return Err(format!(
+                    "{} did not open a DevTools endpoint within 20 seconds",
+                    executable.display()
+                ));
+            }
+        };
+
+        let ws = WebSocket::connect(&url)?;
+        ws.set_read_timeout(Duration::from_secs(60));
+        let mut browser = Browser {
+            process,
+            profile,
+            ws,
+            next_id: 0,
+            session: String::new(),
+        };
+        let target = browser.call_browser("Target.createTarget", json!({"url": "about:blank"}))?;
+        let attached = browser.call_browser(
+            "Target.attachToTarget",
+            json!({"targetId": target["targetId"], "flatten": true}),
+        )?;
+        browser.session = attached["sessionId"]
+            .as_str()
+            .unwrap_or_default()
+            .to_string();
+        browser.call("Page.enable", json!({}))?;
+        Ok(browser)
+    }
+
+    fn send(&mut self, method: &str, params: Value, session: Option<&str>) -> Result<u64, String> {
+        self.next_id += 1;
+        let mut message = json!({"id": self.next_id, "method": method, "params": params});
+        if let Some(session) = session {
+            message["sessionId"] = json!(session);
+        }
+        self.ws.send_text(&message.to_string())?;
+        Ok(self.next_id)
+    }
+
+    fn request(&mut self, method: &str, params: Value, page: bool) -> Result<Value, String> {
+        let session = page.then(|| self.session.clone());
+        let id = self.send(method, params, session.as_deref())?;
+        loop {
+            let message: Value = serde_json::from_str(&self.ws.read_text()?)
+                .map_err(|e| format!("Invalid DevTools message: {}", e))?;
+            if message["id"].as_u64() == Some(id) {
+                if let Some(error) = message.get("error") {
+                    return Err(format!(
+                        "{}: {}",
+                        method,
+                        error["message"].as_str().unwrap_or("DevTools error")
+                    ));
+                }
+                return Ok(message["result"].clone());
+            }
+            // An open alert/confirm/prompt stalls every later call on the page
+            if message["method"] == "Page.javascriptDialogOpening" {
+                let session = message["sessionId"].as_str().map(String::from);
+                self.send(
+                    "Page.handleJavaScriptDialog",
+                    json!({"accept": false}),
+                    session.as_deref(),
+                )?;
+            }
+        }
+    }
+
+    /// Call a method on the page
+    pub fn call(&mut self, method: &str, params: Value) -> Result<Value, String> {
+        self.request(method, params, true)
+    }
+
+    /// Call a browser-level method
+    pub fn call_browser(&mut self, method: &str, params: Value) -> Result<Value, String> {
+        self.request(method, params, false)
+    }
+
+    /// Evaluate an expression in the page, awaiting promises, and return its
+    /// JSON value
+    pub fn evaluate(&mut self, expression: &str) -> Result<Value, String> {
+        let result = self.call(
+            "Runtime.evaluate",
+            json!({
+                "expression": expression,
+                "returnByValue": true,
+                "awaitPromise": true,
+                "userGesture": true,
+            }),
+        )?;
+        if let Some(details) = result.get("exceptionDetails") {
+            let message = details["exception"]["description"]
+                .as_str()
+                .or_else(|| details["text"].as_str())
+                .unwrap_or("Evaluation failed");
+            return Err(message.lines().next().unwrap_or(message).to_string());
+        }
+        Ok(result["result"]["value"].clone())
+    }
+
+    /// Leave the browser running, for someone else to use and close, and
+    /// return its profile, which is then theirs to remove
+    pub fn detach(self) -> PathBuf {
+        self.profile
+    }
+
+    /// Close the browser and remove its profile
+    pub fn close(mut self) {
+        self.call_browser("Browser.close", json!({})).ok();
+        for _ in 0..50 {
+            if matches!(self.process.try_wait(), Ok(Some(_))) {
+                break;
+            }
+            thread::sleep(Duration::from_millis(100));
+        }
+        self.process.kill().ok();
+        self.process.wait().ok();
+        fs::remove_dir_all(&self.profile).ok();
+    }
+}
@@ -1,10 +1,29 @@
-use crate::flags::Flags;
-/**
+/*!
  * Command Parsing with AI-Friendly Error Messages
  */
-use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::time::Duration;
+
+use serde::Serialize;
+
+use crate::assertions::JsonExpectation;
+use crate::cache::parse_ttl;
+use crate::crawl;
+use crate::devices;
+use crate::flags::Flags;
+use crate::i18n::{self, t, tf};
+use crate::markdown;
+use crate::monitor;
+use crate::pagediff;
+use crate::paging::PAGING_FLAGS;
+use crate::policy::{self, Violation};
+use crate::registry::{find_command, is_read_only, suggest_command, CommandSpec};
+use crate::script;
+use crate::textdiff;
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
 pub struct CommandJson {
     pub id: String,
     pub action: String,
@@ -26,6 +45,145 @@ pub struct CommandJson {
     pub full_page: Option<bool>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub timeout: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub state: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub script: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url_pattern: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub clear: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub vision: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub forced_colors: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub orientation: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reading: Option<Vec<f64>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub enabled: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub graphql: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub operation: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub include_body: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<u16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub method: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub body_contains: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub since: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub item_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub role: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exact: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub structured: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub selectors: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dwell: Option<Vec<u64>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub force: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub x: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub y: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub zoom: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub css: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub emulate_print: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub channel: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub video: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub audio: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub time: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub amount: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub percent: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub index: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub format: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub html: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub from_clipboard: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub active_modal: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub count: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub handler: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub by: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub downloads_dir: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub accept: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub prompt_text: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mode: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub to_x: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub to_y: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub response: Option<RouteResponse>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub viewport: Option<Viewport>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub device: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub device_scale_factor: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub is_mobile: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub has_touch: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user_agent: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct Viewport {
+    pub width: u32,
+    pub height: u32,
+}
+
+/// The canned response a `route` fulfills requests with
+#[derive(Debug, Default, Serialize)]
+pub struct RouteResponse {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<u16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub headers: Option<BTreeMap<String, String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub body: Option<String>,
 }
 
 impl CommandJson {
@@ -33,15 +191,7 @@ impl CommandJson {
         CommandJson {
             id: "1".to_string(),
             action: action.to_string(),
-            url: None,
-            selector: None,
-            text: None,
-            value: None,
-            key: None,
-            path: None,
-            interactive: None,
-            full_page: None,
-            timeout: None,
+            ..Default::default()
         }
     }
 
@@ -54,6 +204,7 @@ impl CommandJson {
 pub enum ParseError {
     UnknownCommand {
         command: String,
+        suggestion: Option<&'static str>,
     },
     UnknownSubcommand {
         subcommand: String,
@@ -68,31 +219,56 @@ pub enum ParseError {
         value: String,
         expected: String,
     },
+    ReadOnly {
+        command: String,
+    },
+    Policy(Violation),
 }
 
 impl ParseError {
+    /// Stable error code for JSON output
+    pub fn code(&self) -> &'static str {
+        match self {
+            ParseError::UnknownCommand { .. } => "unknown_command",
+            ParseError::UnknownSubcommand { .. } => "unknown_subcommand",
+            ParseError::MissingArguments { .. } => "missing_arguments",
+            ParseError::InvalidValue { .. } => "invalid_value",
+            ParseError::ReadOnly { .. } => "read_only",
+            ParseError::Policy(_) => "policy_violation",
+        }
+    }
+
     pub fn format(&self) -> String {
         match self {
-            ParseError::UnknownCommand { command } => {
-                format!(
-                    "Unknown command: {}\n\nRun 'agentbrowser-pro --help' to see available commands.",
-                    command
-                )
+            ParseError::UnknownCommand {
+                command,
+                suggestion: Some(suggestion),
+            } => format!(
+                "{}\n{} {}\n\n{}",
+                tf("unknown_command", &[command]),
+                t("did_you_mean"),
+                suggestion,
+                t("see_help")
+            ),
+            ParseError::UnknownCommand { command, .. } => {
+                format!("{}\n\n{}", tf("unknown_command", &[command]), t("see_help"))
             }
             ParseError::UnknownSubcommand {
                 subcommand,
                 valid_options,
             } => {
                 format!(
-                    "Unknown subcommand: {}\nValid options: {}",
-                    subcommand,
-                    valid_options.join(", ")
+                    "{}\n{}",
+                    tf("unknown_subcommand", &[subcommand]),
+                    tf("valid_options", &[&valid_options.join(", ")])
                 )
             }
             ParseError::MissingArguments { context, usage } => {
                 format!(
-                    "Missing arguments for: {}\nUsage: agentbrowser-pro {}",
-                    context, usage
+                    "{}\n{} agentbrowser-pro {}",
+                    tf("missing_arguments", &[context]),
+                    t("usage"),
+                    usage
                 )
             }
             ParseError::InvalidValue {
@@ -101,10 +277,13 @@ impl ParseError {
                 expected,
             } => {
                 format!(
-                    "Invalid value for {}: '{}'\nExpected: {}",
-                    field, value, expected
+                    "{}\n{}",
+                    tf("invalid_value", &[field, value]),
+                    tf("expected", &[expected])
                 )
             }
+            ParseError::ReadOnly { command } => tf("read_only_blocked", &[command]),
+            ParseError::Policy(violation) => violation.message(),
         }
     }
 }
@@ -117,34 +296,280 @@ pub fn parse_command(args: &[String], flags: &Flags) -> Result<CommandJson, Pars
         });
     }
 
-    let command = args[0].to_lowercase();
+    let (spec, mut cmd) = parse_known(args, flags)?;
+
+    // Apply shared flags uniformly to every command that declares them
+    if spec.accepts("--timeout") && cmd.timeout.is_none() {
+        cmd.timeout = flags.timeout.or(flags.default_timeout);
+    }
+    if let Some(ttl) = flags.value("--cache").filter(|_| spec.accepts("--cache")) {
+        if parse_ttl(ttl).is_none() {
+            return Err(ParseError::InvalidValue {
+                field: "--cache".to_string(),
+                value: ttl.to_string(),
+                expected: "a duration such as 2s, 500ms or 1m".to_string(),
+            });
+        }
+    }
+    for name in PAGING_FLAGS {
+        if let Some(value) = flags.value(name).filter(|_| spec.accepts(name)) {
+            if value.parse::<usize>().is_err() {
+                return Err(ParseError::InvalidValue {
+                    field: name.to_string(),
+                    value: value.to_string(),
+                    expected: "a non-negative number".to_string(),
+                });
+            }
+        }
+    }
+
+    Ok(cmd)
+}
+
+/// Look a command up in the registry, check it has its required arguments,
+/// and parse it
+fn parse_known(
+    args: &[String],
+    flags: &Flags,
+) -> Result<(&'static CommandSpec, CommandJson), ParseError> {
+    let Some(spec) = find_command(&args[0]) else {
+        return Err(ParseError::UnknownCommand {
+            command: args[0].to_lowercase(),
+            suggestion: suggest_command(&args[0]),
+        });
+    };
+    if args.len() - 1 < spec.required_args() {
+        return Err(ParseError::MissingArguments {
+            context: spec.name.to_string(),
+            usage: spec.usage,
+        });
+    }
+    let cmd = parse_action(spec, args, flags)?;
+    if flags.read_only && !is_read_only(&cmd.action) {
+        return Err(ParseError::ReadOnly {
+            command: spec.name.to_string(),
+        });
+    }
+    policy::check(spec.name, &cmd.action, cmd.url.as_deref(), &flags.session)
+        .map_err(ParseError::Policy)?;
+    if let Some(url) = cmd.url.as_deref() {
+        if matches!(cmd.action.as_str(), "navigate" | "newPage") {
+            policy::check_url_rules(url, &flags.allow_urls, &flags.deny_urls)
+                .map_err(ParseError::Policy)?;
+        }
+    }
+    Ok((spec, cmd))
+}
+
+/// Build the wait step for `--wait-before`/`--wait-after`: a number of
+/// milliseconds, a load state, or a selector to wait for
+pub fn parse_wait(flag: &str, spec: &str, flags: &Flags) -> Result<CommandJson, ParseError> {
+    if spec.is_empty() {
+        return Err(ParseError::InvalidValue {
+            field: flag.to_string(),
+            value: spec.to_string(),
+            expected: "milliseconds, load|domcontentloaded|networkidle, or a selector".to_string(),
+        });
+    }
+
+    if let Ok(ms) = spec.parse::<u64>() {
+        let mut cmd = CommandJson::new("wait");
+        cmd.timeout = Some(ms);
+        return Ok(cmd);
+    }
+
+    let mut cmd = match spec {
+        "load" | "domcontentloaded" | "networkidle" => {
+            let mut cmd = CommandJson::new("waitForLoadState");
+            cmd.state = Some(spec.to_string());
+            cmd
+        }
+        _ => {
+            let mut cmd = CommandJson::new("waitForSelector");
+            cmd.selector = Some(spec.to_string());
+            cmd
+        }
+    };
+    cmd.timeout = flags.timeout.or(flags.default_timeout);
+    Ok(cmd)
+}
+
+fn parse_action(
+    spec: &CommandSpec,
+    args: &[String],
+    flags: &Flags,
+) -> Result<CommandJson, ParseError> {
     let rest = &args[1..];
 
-    match command.as_str() {
+    // Plugins parse their own arguments
+    if spec.action == "plugin" {
+        let mut cmd = CommandJson::new("plugin");
+        cmd.value = Some(spec.name.to_string());
+        return Ok(cmd);
+    }
+
+    match spec.name {
         // ============ Lifecycle ============
-        "daemon" => Ok(CommandJson::new("daemon")),
+        "daemon" => match rest.first().map(|s| s.as_str()) {
+            None => Ok(CommandJson::new("daemon")),
+            Some("stats") => Ok(CommandJson::new("daemonStats")),
+            Some(other) => Err(ParseError::UnknownSubcommand {
+                subcommand: other.to_string(),
+                valid_options: &["stats"],
+            }),
+        },
 
         "mcp" => Ok(CommandJson::new("mcp")),
 
-        "launch" => {
-            let mut cmd = CommandJson::new("launch");
-            cmd.timeout = flags.timeout;
+        "spec" => Ok(CommandJson::new("spec")),
+
+        "pool" => match rest.first().map(|s| s.as_str()) {
+            Some("start") => {
+                if let Some(n) = flags.value("--size") {
+                    if n.parse::<usize>().map(|n| n == 0).unwrap_or(true) {
+                        return Err(ParseError::InvalidValue {
+                            field: "--size".to_string(),
+                            value: n.to_string(),
+                            expected: "a positive number".to_string(),
+                        });
+                    }
+                }
+                let mut cmd = CommandJson::new("poolStart");
+                cmd.path = flags.value("--template").map(String::from);
+                Ok(cmd)
+            }
+            Some("status") | None => Ok(CommandJson::new("poolStatus")),
+            Some("stop") => Ok(CommandJson::new("poolStop")),
+            Some(other) => Err(ParseError::UnknownSubcommand {
+                subcommand: other.to_string(),
+                valid_options: &["start", "status", "stop"],
+            }),
+        },
+
+        "worker" => {
+            let queue = flags.value("--queue").ok_or(ParseError::MissingArguments {
+                context: spec.name.to_string(),
+                usage: spec.usage,
+            })?;
+            if queue.is_empty() {
+                return Err(ParseError::InvalidValue {
+                    field: "--queue".to_string(),
+                    value: queue.to_string(),
+                    expected: "a directory or redis://host:port/list".to_string(),
+                });
+            }
+            if let Some(n) = flags.value("--concurrency") {
+                if n.parse::<usize>().map(|n| n == 0).unwrap_or(true) {
+                    return Err(ParseError::InvalidValue {
+                        field: "--concurrency".to_string(),
+                        value: n.to_string(),
+                        expected: "a positive number".to_string(),
+                    });
+                }
+            }
+            Ok(CommandJson::new("worker"))
+        }
+
+        "if" => {
+            script::parse_if(0, args).map_err(|_| ParseError::MissingArguments {
+                context: "if".to_string(),
+                usage: script::IF_USAGE,
+            })?;
+            let mut cmd = CommandJson::new("if");
+            cmd.selector = rest.get(1).cloned();
             Ok(cmd)
         }
 
-        "close" => Ok(CommandJson::new("close")),
+        "run" => {
+            let path = &rest[0];
+            if flags.value("--data").map(str::is_empty).unwrap_or(false) {
+                return Err(ParseError::InvalidValue {
+                    field: "--data".to_string(),
+                    value: String::new(),
+                    expected: "a .csv or .json file".to_string(),
+                });
+            }
+            if let Some(n) = flags.value("--parallel") {
+                if n.parse::<usize>().map(|n| n == 0).unwrap_or(true) {
+                    return Err(ParseError::InvalidValue {
+                        field: "--parallel".to_string(),
+                        value: n.to_string(),
+                        expected: "a positive number".to_string(),
+                    });
+                }
+            }
+            let mut cmd = CommandJson::new("runScript");
+            cmd.path = Some(path.clone());
+            Ok(cmd)
+        }
 
-        // ============ Navigation ============
-        "navigate" | "open" | "goto" => {
-            if rest.is_empty() {
+        "bench" => {
+            if let Some(n) = flags.value("--iterations") {
+                if n.parse::<usize>().map(|n| n == 0).unwrap_or(true) {
+                    return Err(ParseError::InvalidValue {
+                        field: "--iterations".to_string(),
+                        value: n.to_string(),
+                        expected: "a positive number".to_string(),
+                    });
+                }
+            }
+            // Fail early if the benchmarked command itself is invalid
+            parse_known(rest, flags)?;
+            Ok(CommandJson::new("bench"))
+        }
+
+        "alias" => match rest.first().map(|s| s.as_str()) {
+            Some("list") | None => Ok(CommandJson::new("aliasList")),
+            Some(other) => Err(ParseError::UnknownSubcommand {
+                subcommand: other.to_string(),
+                valid_options: &["list"],
+            }),
+        },
+
+        "i18n" => {
+            let action = match rest.first().map(|s| s.as_str()) {
+                Some("check") => "i18nCheck",
+                Some("template") => "i18nTemplate",
+                Some(other) => {
+                    return Err(ParseError::UnknownSubcommand {
+                        subcommand: other.to_string(),
+                        valid_options: &["check", "template"],
+                    })
+                }
+                None => {
+                    return Err(ParseError::MissingArguments {
+                        context: "i18n".to_string(),
+                        usage: "i18n <check [lang] | template <lang>>",
+                    })
+                }
+            };
+            let lang = rest.get(1);
+            if action == "i18nTemplate" && lang.is_none() {
                 return Err(ParseError::MissingArguments {
-                    context: "navigate".to_string(),
-                    usage: "navigate <url>",
+                    context: "i18n template".to_string(),
+                    usage: "i18n template <es|ja|zh|de>",
+                });
+            }
+            if let Some(lang) = lang.filter(|l| !i18n::LANGS[1..].contains(&l.as_str())) {
+                return Err(ParseError::InvalidValue {
+                    field: "lang".to_string(),
+                    value: lang.clone(),
+                    expected: i18n::LANGS[1..].join(", "),
                 });
             }
+            let mut cmd = CommandJson::new(action);
+            cmd.value = lang.cloned();
+            Ok(cmd)
+        }
+
+        "launch" => Ok(CommandJson::new("launch")),
+
+        "close" => Ok(CommandJson::new("close")),
+
+        // ============ Navigation ============
+        "navigate" => {
             let mut cmd = CommandJson::new("navigate");
             cmd.url = Some(rest[0].clone());
-            cmd.timeout = flags.timeout;
             Ok(cmd)
         }
 
@@ -152,155 +577,242 @@ pub fn parse_command(args: &[String], flags: &Flags) -> Result<CommandJson, Pars
 
         "forward" => Ok(CommandJson::new("forward")),
 
-        "reload" | "refresh" => Ok(CommandJson::new("reload")),
+        "reload" => Ok(CommandJson::new("reload")),
 
-        // ============ Interaction ============
-        "click" => {
-            if rest.is_empty() {
-                return Err(ParseError::MissingArguments {
-                    context: "click".to_string(),
-                    usage: "click <selector|ref>",
+        "handoff" => {
+            if flags.has("--print") && flags.has("--cookies") {
+                return Err(ParseError::InvalidValue {
+                    field: "--cookies".to_string(),
+                    value: "--cookies".to_string(),
+                    expected: "--cookies or --print, not both".to_string(),
                 });
             }
+            Ok(CommandJson::new("handoff"))
+        }
+
+        // ============ Interaction ============
+        "click" => {
             let mut cmd = CommandJson::new("click");
             cmd.selector = Some(rest[0].clone());
-            cmd.timeout = flags.timeout;
+            cmd.force = flags.has("--force").then_some(true);
             Ok(cmd)
         }
 
-        "dblclick" | "doubleclick" => {
-            if rest.is_empty() {
-                return Err(ParseError::MissingArguments {
-                    context: "dblclick".to_string(),
-                    usage: "dblclick <selector|ref>",
-                });
+        "clicktext" => {
+            let mut cmd = CommandJson::new("clickByText");
+            cmd.text = Some(rest.join(" "));
+            cmd.exact = flags.has("--exact").then_some(true);
+            Ok(cmd)
+        }
+
+        "clickrole" => {
+            let mut cmd = CommandJson::new("clickByRole");
+            cmd.role = Some(rest[0].to_lowercase());
+            cmd.name = Some(rest[1..].join(" "));
+            cmd.exact = flags.has("--exact").then_some(true);
+            Ok(cmd)
+        }
+
+        "tabto" => {
+            let mut cmd = CommandJson::new("tabTo");
+            cmd.text = Some(rest.join(" "));
+            if let Some(max) = flags.value("--max") {
+                match max.parse::<u32>() {
+                    Ok(n) if n > 0 => cmd.max = Some(n),
+                    _ => {
+                        return Err(ParseError::InvalidValue {
+                            field: "--max".to_string(),
+                            value: max.to_string(),
+                            expected: "a positive number of Tab presses".to_string(),
+                        })
+                    }
+                }
             }
+            Ok(cmd)
+        }
+
+        "dblclick" => {
             let mut cmd = CommandJson::new("dblclick");
             cmd.selector = Some(rest[0].clone());
-            cmd.timeout = flags.timeout;
+            cmd.force = flags.has("--force").then_some(true);
             Ok(cmd)
         }
 
         "type" => {
-            if rest.len() < 2 {
-                return Err(ParseError::MissingArguments {
-                    context: "type".to_string(),
-                    usage: "type <selector|ref> <text>",
-                });
-            }
             let mut cmd = CommandJson::new("type");
             cmd.selector = Some(rest[0].clone());
             cmd.text = Some(rest[1..].join(" "));
-            cmd.timeout = flags.timeout;
             Ok(cmd)
         }
 
         "fill" => {
-            if rest.len() < 2 {
-                return Err(ParseError::MissingArguments {
-                    context: "fill".to_string(),
-                    usage: "fill <selector|ref> <value>",
-                });
-            }
             let mut cmd = CommandJson::new("fill");
             cmd.selector = Some(rest[0].clone());
             cmd.value = Some(rest[1..].join(" "));
-            cmd.timeout = flags.timeout;
             Ok(cmd)
         }
 
-        "clear" => {
-            if rest.is_empty() {
-                return Err(ParseError::MissingArguments {
-                    context: "clear".to_string(),
-                    usage: "clear <selector|ref>",
+        "write" => {
+            let content = rest[1..].join(" ");
+            let mut cmd = CommandJson::new("write");
+            cmd.selector = Some(rest[0].clone());
+            cmd.clear = flags.has("--clear").then_some(true);
+            // Formatted content is pasted as HTML; plain text is typed
+            if flags.has("--html") {
+                cmd.html = Some(content);
+            } else {
+                cmd.html = markdown::to_html(&content);
+                cmd.text = Some(content);
+            }
+            Ok(cmd)
+        }
+
+        "paste" => {
+            let selector = &rest[0];
+            let mut cmd = CommandJson::new("paste");
+            cmd.selector = Some(selector.clone());
+            let sources = [
+                rest.len() > 1,
+                flags.has("--from-clipboard"),
+                flags.value("--from-file").is_some(),
+            ];
+            match sources.iter().filter(|s| **s).count() {
+                0 => {
+                    return Err(ParseError::MissingArguments {
+                        context: spec.name.to_string(),
+                        usage: spec.usage,
+                    })
+                }
+                1 => {}
+                _ => {
+                    return Err(ParseError::InvalidValue {
+                        field: "paste".to_string(),
+                        value: rest[1..].join(" "),
+                        expected: "one of text, --from-clipboard or --from-file".to_string(),
+                    })
+                }
+            }
+            if let Some(path) = flags.value("--from-file") {
+                let content = fs::read_to_string(path).map_err(|_| ParseError::InvalidValue {
+                    field: "--from-file".to_string(),
+                    value: path.to_string(),
+                    expected: "a readable UTF-8 text file".to_string(),
+                })?;
+                // HTML files paste as formatted content, as copying from a
+                // page would
+                if path.ends_with(".html") || path.ends_with(".htm") {
+                    cmd.html = Some(content);
+                } else {
+                    cmd.text = Some(content);
+                }
+            } else if flags.has("--from-clipboard") {
+                cmd.from_clipboard = Some(true);
+            } else {
+                cmd.text = Some(rest[1..].join(" "));
+            }
+            Ok(cmd)
+        }
+
+        "setdate" => {
+            if !is_iso_datetime(&rest[1]) {
+                return Err(ParseError::InvalidValue {
+                    field: "date".to_string(),
+                    value: rest[1].clone(),
+                    expected: "an ISO date such as 2024-03-05, 2024-03-05T14:30 or 14:30"
+                        .to_string(),
                 });
             }
+            let mut cmd = CommandJson::new("setDate");
+            cmd.selector = Some(rest[0].clone());
+            cmd.value = Some(rest[1].clone());
+            cmd.format = flags.value("--format").map(String::from);
+            Ok(cmd)
+        }
+
+        "clear" => {
             let mut cmd = CommandJson::new("clear");
             cmd.selector = Some(rest[0].clone());
-            cmd.timeout = flags.timeout;
             Ok(cmd)
         }
 
         "check" => {
-            if rest.is_empty() {
-                return Err(ParseError::MissingArguments {
-                    context: "check".to_string(),
-                    usage: "check <selector|ref>",
-                });
-            }
             let mut cmd = CommandJson::new("check");
             cmd.selector = Some(rest[0].clone());
-            cmd.timeout = flags.timeout;
             Ok(cmd)
         }
 
         "uncheck" => {
-            if rest.is_empty() {
-                return Err(ParseError::MissingArguments {
-                    context: "uncheck".to_string(),
-                    usage: "uncheck <selector|ref>",
-                });
-            }
             let mut cmd = CommandJson::new("uncheck");
             cmd.selector = Some(rest[0].clone());
-            cmd.timeout = flags.timeout;
             Ok(cmd)
         }
 
         "select" => {
-            if rest.len() < 2 {
-                return Err(ParseError::MissingArguments {
-                    context: "select".to_string(),
-                    usage: "select <selector|ref> <value|label|index>",
-                });
-            }
             let mut cmd = CommandJson::new("select");
             cmd.selector = Some(rest[0].clone());
             cmd.value = Some(rest[1].clone());
-            cmd.timeout = flags.timeout;
             Ok(cmd)
         }
 
         "hover" => {
-            if rest.is_empty() {
-                return Err(ParseError::MissingArguments {
-                    context: "hover".to_string(),
-                    usage: "hover <selector|ref>",
-                });
-            }
             let mut cmd = CommandJson::new("hover");
             cmd.selector = Some(rest[0].clone());
-            cmd.timeout = flags.timeout;
             Ok(cmd)
         }
 
-        "focus" => {
-            if rest.is_empty() {
-                return Err(ParseError::MissingArguments {
-                    context: "focus".to_string(),
-                    usage: "focus <selector|ref>",
-                });
+        "hoverpath" => {
+            let mut cmd = CommandJson::new("hoverPath");
+            cmd.selectors = Some(rest.to_vec());
+            if let Some(dwell) = flags.value("--dwell") {
+                let times = dwell
+                    .split(',')
+                    .map(|ms| ms.trim().parse::<u64>())
+                    .collect::<Result<Vec<_>, _>>();
+                match times {
+                    Ok(times) if times.len() <= rest.len() => cmd.dwell = Some(times),
+                    _ => {
+                        return Err(ParseError::InvalidValue {
+                            field: "--dwell".to_string(),
+                            value: dwell.to_string(),
+                            expected: "milliseconds, once or per element (e.g. 300 or 200,500)"
+                                .to_string(),
+                        })
+                    }
+                }
             }
+            Ok(cmd)
+        }
+
+        "focus" => {
             let mut cmd = CommandJson::new("focus");
             cmd.selector = Some(rest[0].clone());
-            cmd.timeout = flags.timeout;
             Ok(cmd)
         }
 
         "press" => {
-            if rest.is_empty() {
-                return Err(ParseError::MissingArguments {
-                    context: "press".to_string(),
-                    usage: "press <key> [selector]",
-                });
-            }
             let mut cmd = CommandJson::new("press");
-            cmd.key = Some(rest[0].clone());
+            cmd.key = Some(parse_chord(&rest[0])?);
             if rest.len() > 1 {
                 cmd.selector = Some(rest[1].clone());
             }
-            cmd.timeout = flags.timeout;
+            Ok(cmd)
+        }
+
+        "keydown" | "keyup" => {
+            let key = parse_chord(&rest[0])?;
+            if key.contains('+') && key != "+" {
+                return Err(ParseError::InvalidValue {
+                    field: "key".to_string(),
+                    value: rest[0].clone(),
+                    expected: "a single key; hold each with its own keydown".to_string(),
+                });
+            }
+            let mut cmd = CommandJson::new(if spec.name == "keydown" {
+                "keyDown"
+            } else {
+                "keyUp"
+            });
+            cmd.key = Some(key);
             Ok(cmd)
         }
 
@@ -312,47 +824,278 @@ pub fn parse_command(args: &[String], flags: &Flags) -> Result<CommandJson, Pars
             Ok(cmd)
         }
 
+        "scrollpos" => {
+            let mut cmd = CommandJson::new("getScrollPosition");
+            cmd.selector = rest.first().cloned();
+            Ok(cmd)
+        }
+
+        "scrollto" => {
+            let mut cmd = CommandJson::new("scrollTo");
+            for (i, field) in ["x", "y"].into_iter().enumerate() {
+                let value = rest[i]
+                    .parse::<u64>()
+                    .map_err(|_| ParseError::InvalidValue {
+                        field: field.to_string(),
+                        value: rest[i].clone(),
+                        expected: "a non-negative number of pixels".to_string(),
+                    })?;
+                match field {
+                    "x" => cmd.x = Some(value),
+                    _ => cmd.y = Some(value),
+                }
+            }
+            cmd.selector = rest.get(2).cloned();
+            Ok(cmd)
+        }
+
+        "mediael" => {
+            let op = rest[1].as_str();
+            if !["play", "pause", "seek", "mute", "unmute", "state"].contains(&op) {
+                return Err(ParseError::UnknownSubcommand {
+                    subcommand: op.to_string(),
+                    valid_options: &["play", "pause", "seek", "mute", "unmute", "state"],
+                });
+            }
+            let mut cmd = CommandJson::new("mediaElement");
+            cmd.selector = Some(rest[0].clone());
+            cmd.value = Some(op.to_string());
+            if op == "seek" {
+                let time = rest.get(2).ok_or_else(|| ParseError::MissingArguments {
+                    context: "mediael seek".to_string(),
+                    usage: spec.usage,
+                })?;
+                cmd.time = Some(time.parse::<f64>().ok().filter(|t| *t >= 0.0).ok_or_else(
+                    || ParseError::InvalidValue {
+                        field: "seek".to_string(),
+                        value: time.clone(),
+                        expected: "a non-negative number of seconds".to_string(),
+                    },
+                )?);
+            }
+            Ok(cmd)
+        }
+
+        "slider" => {
+            let raw = rest[1].as_str();
+            let (number, percent) = match raw.strip_suffix('%') {
+                Some(number) => (number, true),
+                None => (raw, false),
+            };
+            let amount = number
+                .parse::<f64>()
+                .ok()
+                .filter(|n| n.is_finite() && (!percent || (0.0..=100.0).contains(n)))
+                .ok_or_else(|| ParseError::InvalidValue {
+                    field: "value".to_string(),
+                    value: raw.to_string(),
+                    expected: "a number, or a percentage from 0% to 100%".to_string(),
+                })?;
+            let mut cmd = CommandJson::new("setSlider");
+            cmd.selector = Some(rest[0].clone());
+            cmd.amount = Some(amount);
+            cmd.percent = percent.then_some(true);
+            Ok(cmd)
+        }
+
+        "reorder" => {
+            let selector = &rest[0];
+            let index = flags
+                .value("--to-index")
+                .ok_or_else(|| ParseError::MissingArguments {
+                    context: spec.name.to_string(),
+                    usage: spec.usage,
+                })?;
+            let mut cmd = CommandJson::new("reorder");
+            cmd.selector = Some(selector.clone());
+            cmd.index = Some(index.parse().map_err(|_| ParseError::InvalidValue {
+                field: "--to-index".to_string(),
+                value: index.to_string(),
+                expected: "a zero-based position in the list".to_string(),
+            })?);
+            Ok(cmd)
+        }
+
+        "drag" => {
+            let mut cmd = CommandJson::new("drag");
+            cmd.source = Some(rest[0].clone());
+            let coordinate = |name: &str| {
+                flags
+                    .value(name)
+                    .map(|value| {
+                        value.parse::<f64>().map_err(|_| ParseError::InvalidValue {
+                            field: name.to_string(),
+                            value: value.to_string(),
+                            expected: "a page coordinate in pixels".to_string(),
+                        })
+                    })
+                    .transpose()
+            };
+            cmd.to_x = coordinate("--to-x")?;
+            cmd.to_y = coordinate("--to-y")?;
+            cmd.target = rest.get(1).cloned();
+            if cmd.target.is_some() && (cmd.to_x.is_some() || cmd.to_y.is_some()) {
+                return Err(ParseError::InvalidValue {
+                    field: "--to-x/--to-y".to_string(),
+                    value: rest[1].clone(),
+                    expected: "a target or a drop point, not both".to_string(),
+                });
+            }
+            if cmd.target.is_none() && (cmd.to_x.is_none() || cmd.to_y.is_none()) {
+                return Err(ParseError::MissingArguments {
+                    context: spec.name.to_string(),
+                    usage: spec.usage,
+                });
+            }
+            Ok(cmd)
+        }
+
         // ============ Information ============
         "snapshot" => {
+            if let Some(format) = flags.value("--output") {
+                if !matches!(format, "text" | "json" | "xml") {
+                    return Err(ParseError::InvalidValue {
+                        field: "--output".to_string(),
+                        value: format.to_string(),
+                        expected: "text, json or xml".to_string(),
+                    });
+                }
+            }
+            if let Some(max) = flags.value("--max-tokens") {
+                if max.parse::<usize>().map(|n| n == 0).unwrap_or(true) {
+                    return Err(ParseError::InvalidValue {
+                        field: "--max-tokens".to_string(),
+                        value: max.to_string(),
+                        expected: "a positive number of tokens".to_string(),
+                    });
+                }
+            }
             let mut cmd = CommandJson::new("snapshot");
             cmd.interactive = Some(true);
             if !rest.is_empty() {
                 cmd.selector = Some(rest[0].clone());
             }
+            if flags.has("--active-modal") {
+                if let Some(selector) = cmd.selector.take() {
+                    return Err(ParseError::InvalidValue {
+                        field: "--active-modal".to_string(),
+                        value: selector,
+                        expected: "no selector; the snapshot is scoped to the open modal"
+                            .to_string(),
+                    });
+                }
+                cmd.active_modal = Some(true);
+            }
             Ok(cmd)
         }
 
         "screenshot" => {
-            let mut cmd = CommandJson::new("screenshot");
+            let mut cmd = CommandJson::new(if flags.has("--tiled") {
+                "screenshotTiles"
+            } else {
+                "screenshot"
+            });
+            if let Some(max) = flags.value("--max-tiles") {
+                match max.parse::<u32>() {
+                    Ok(n) if n > 0 => cmd.max = Some(n),
+                    _ => {
+                        return Err(ParseError::InvalidValue {
+                            field: "--max-tiles".to_string(),
+                            value: max.to_string(),
+                            expected: "a positive number of viewports".to_string(),
+                        })
+                    }
+                }
+            }
             if !rest.is_empty() {
                 cmd.path = Some(rest[0].clone());
             }
-            // Check for --full-page flag in original args
-            if args.iter().any(|a| a == "--full-page") {
+            if flags.has("--full-page") {
                 cmd.full_page = Some(true);
             }
-            cmd.timeout = flags.timeout;
+            if flags.has("--emulate-print") {
+                cmd.emulate_print = Some(true);
+            }
             Ok(cmd)
         }
 
-        "title" | "gettitle" => Ok(CommandJson::new("getTitle")),
+        "canvas" => match rest.first().map(|s| s.as_str()) {
+            Some("capture") => {
+                let selector = &rest[1];
+                let mut cmd = CommandJson::new("captureCanvas");
+                cmd.selector = Some(selector.clone());
+                cmd.path = flags.value("--output").map(String::from);
+                Ok(cmd)
+            }
+            Some(other) => Err(ParseError::UnknownSubcommand {
+                subcommand: other.to_string(),
+                valid_options: &["capture"],
+            }),
+            None => Err(ParseError::MissingArguments {
+                context: spec.name.to_string(),
+                usage: spec.usage,
+            }),
+        },
 
-        "url" | "geturl" => Ok(CommandJson::new("getUrl")),
+        "title" => Ok(CommandJson::new("getTitle")),
 
-        "text" | "gettext" => {
-            if rest.is_empty() {
-                return Err(ParseError::MissingArguments {
-                    context: "text".to_string(),
-                    usage: "text <selector|ref>",
-                });
+        "seo" => Ok(CommandJson::new("seoAudit")),
+
+        "pwa" => Ok(CommandJson::new("pwaAudit")),
+
+        "feeds" => Ok(CommandJson::new("getFeeds")),
+
+        "pageinfo" => Ok(CommandJson::new("getPageInfo")),
+
+        "feed" => match rest.first().map(|s| s.as_str()) {
+            Some("fetch") => {
+                let url = &rest[1];
+                let mut cmd = CommandJson::new("fetchFeed");
+                cmd.url = Some(url.clone());
+                Ok(cmd)
             }
+            Some(other) => Err(ParseError::UnknownSubcommand {
+                subcommand: other.to_string(),
+                valid_options: &["fetch"],
+            }),
+            None => Err(ParseError::MissingArguments {
+                context: spec.name.to_string(),
+                usage: spec.usage,
+            }),
+        },
+
+        "favicon" => {
+            let mut cmd = CommandJson::new("getFavicon");
+            cmd.path = flags.value("--output").map(String::from);
+            Ok(cmd)
+        }
+
+        "structured" => {
+            let mut cmd = CommandJson::new("getStructuredData");
+            cmd.item_type = flags.value("--type").map(String::from);
+            Ok(cmd)
+        }
+
+        "url" => Ok(CommandJson::new("getUrl")),
+
+        "focused" => Ok(CommandJson::new("getFocused")),
+
+        "overlays" => Ok(CommandJson::new("getOverlays")),
+
+        "text" => {
             let mut cmd = CommandJson::new("getText");
             cmd.selector = Some(rest[0].clone());
-            cmd.timeout = flags.timeout;
+            cmd.structured = flags.has("--structured").then_some(true);
+            Ok(cmd)
+        }
+
+        "tooltip" => {
+            let mut cmd = CommandJson::new("getTooltip");
+            cmd.selector = Some(rest[0].clone());
             Ok(cmd)
         }
 
-        "html" | "gethtml" => {
+        "html" => {
             let mut cmd = CommandJson::new("getHtml");
             if !rest.is_empty() {
                 cmd.selector = Some(rest[0].clone());
@@ -360,63 +1103,48 @@ pub fn parse_command(args: &[String], flags: &Flags) -> Result<CommandJson, Pars
             Ok(cmd)
         }
 
-        "value" | "getvalue" => {
-            if rest.is_empty() {
-                return Err(ParseError::MissingArguments {
-                    context: "value".to_string(),
-                    usage: "value <selector|ref>",
-                });
-            }
+        "texts" => {
+            let selector = serde_json::to_string(&rest[0]).unwrap_or_default();
+            let mut cmd = CommandJson::new("evaluate");
+            cmd.script = Some(format!(
+                "Array.from(document.querySelectorAll({})).map(e => e.innerText.trim())",
+                selector
+            ));
+            Ok(cmd)
+        }
+
+        "value" => {
             let mut cmd = CommandJson::new("getValue");
             cmd.selector = Some(rest[0].clone());
-            cmd.timeout = flags.timeout;
             Ok(cmd)
         }
 
-        "count" | "getcount" => {
-            if rest.is_empty() {
-                return Err(ParseError::MissingArguments {
-                    context: "count".to_string(),
-                    usage: "count <selector>",
-                });
-            }
+        "count" => {
             let mut cmd = CommandJson::new("getCount");
             cmd.selector = Some(rest[0].clone());
             Ok(cmd)
         }
 
         // ============ State Checks ============
-        "visible" | "isvisible" => {
-            if rest.is_empty() {
-                return Err(ParseError::MissingArguments {
-                    context: "visible".to_string(),
-                    usage: "visible <selector|ref>",
-                });
-            }
+        "visible" => {
             let mut cmd = CommandJson::new("isVisible");
             cmd.selector = Some(rest[0].clone());
             Ok(cmd)
         }
 
-        "enabled" | "isenabled" => {
-            if rest.is_empty() {
-                return Err(ParseError::MissingArguments {
-                    context: "enabled".to_string(),
-                    usage: "enabled <selector|ref>",
-                });
-            }
+        "inview" => {
+            let mut cmd = CommandJson::new("isInViewport");
+            cmd.selector = Some(rest[0].clone());
+            Ok(cmd)
+        }
+
+        "enabled" => {
             let mut cmd = CommandJson::new("isEnabled");
             cmd.selector = Some(rest[0].clone());
             Ok(cmd)
         }
 
-        "checked" | "ischecked" => {
-            if rest.is_empty() {
-                return Err(ParseError::MissingArguments {
-                    context: "checked".to_string(),
-                    usage: "checked <selector|ref>",
-                });
-            }
+        "checked" => {
             let mut cmd = CommandJson::new("isChecked");
             cmd.selector = Some(rest[0].clone());
             Ok(cmd)
@@ -439,16 +1167,45 @@ pub fn parse_command(args: &[String], flags: &Flags) -> Result<CommandJson, Pars
             Ok(cmd)
         }
 
+        "waitfor" => match rest.first().map(|s| s.as_str()) {
+            Some("response") => {
+                let pattern = &rest[1];
+                let mut cmd = CommandJson::new("waitForResponse");
+                cmd.url_pattern = Some(pattern.clone());
+                if let Some(status) = flags.value("--status") {
+                    cmd.status = Some(status.parse().map_err(|_| ParseError::InvalidValue {
+                        field: "--status".to_string(),
+                        value: status.to_string(),
+                        expected: "an HTTP status code".to_string(),
+                    })?);
+                }
+                Ok(cmd)
+            }
+            Some(other) => Err(ParseError::UnknownSubcommand {
+                subcommand: other.to_string(),
+                valid_options: &["response"],
+            }),
+            None => Err(ParseError::MissingArguments {
+                context: spec.name.to_string(),
+                usage: spec.usage,
+            }),
+        },
+
+        "pause" => {
+            let mut cmd = CommandJson::new("pause");
+            cmd.message = flags.value("--message").map(String::from);
+            // Only an explicit limit: the configured default timeout is for
+            // the page, not for a person
+            cmd.timeout = flags.timeout;
+            Ok(cmd)
+        }
+
+        "resume" => Ok(CommandJson::new("resume")),
+
         // ============ Frames ============
-        "frames" | "getframes" => Ok(CommandJson::new("getFrames")),
+        "frames" => Ok(CommandJson::new("getFrames")),
 
-        "frame" | "switchtoframe" => {
-            if rest.is_empty() {
-                return Err(ParseError::MissingArguments {
-                    context: "frame".to_string(),
-                    usage: "frame <selector|name|url>",
-                });
-            }
+        "frame" => {
             let mut cmd = CommandJson::new("switchToFrame");
             cmd.selector = Some(rest[0].clone());
             Ok(cmd)
@@ -457,7 +1214,7 @@ pub fn parse_command(args: &[String], flags: &Flags) -> Result<CommandJson, Pars
         "mainframe" => Ok(CommandJson::new("switchToMainFrame")),
 
         // ============ Pages ============
-        "pages" | "getpages" => Ok(CommandJson::new("getPages")),
+        "pages" => Ok(CommandJson::new("getPages")),
 
         "newpage" => {
             let mut cmd = CommandJson::new("newPage");
@@ -468,12 +1225,6 @@ pub fn parse_command(args: &[String], flags: &Flags) -> Result<CommandJson, Pars
         }
 
         "switchpage" => {
-            if rest.is_empty() {
-                return Err(ParseError::MissingArguments {
-                    context: "switchpage".to_string(),
-                    usage: "switchpage <index|url|title>",
-                });
-            }
             let mut cmd = CommandJson::new("switchPage");
             // Try to parse as index first
             if let Ok(index) = rest[0].parse::<u32>() {
@@ -491,25 +1242,506 @@ pub fn parse_command(args: &[String], flags: &Flags) -> Result<CommandJson, Pars
         }
 
         // ============ JavaScript ============
-        "eval" | "evaluate" => {
-            if rest.is_empty() {
+        "eval" => {
+            let mut cmd = CommandJson::new("evaluate");
+            cmd.script = Some(rest.join(" "));
+            Ok(cmd)
+        }
+
+        // ============ Extensions ============
+        "extensions" => match rest.first().map(|s| s.as_str()) {
+            Some("list") | None => Ok(CommandJson::new("listExtensions")),
+            Some(other) => Err(ParseError::UnknownSubcommand {
+                subcommand: other.to_string(),
+                valid_options: &["list"],
+            }),
+        },
+
+        "extension" => {
+            let action = match rest[0].as_str() {
+                "install" => "extensionInstall",
+                "remove" => "extensionRemove",
+                "popup" => "extensionPopup",
+                other => {
+                    return Err(ParseError::UnknownSubcommand {
+                        subcommand: other.to_string(),
+                        valid_options: &["install", "remove", "popup"],
+                    })
+                }
+            };
+            let mut cmd = CommandJson::new(action);
+            cmd.value = Some(rest[1].clone());
+            Ok(cmd)
+        }
+
+        // ============ Emulation ============
+        "zoom" => {
+            let Some(factor) = rest.first() else {
                 return Err(ParseError::MissingArguments {
-                    context: "eval".to_string(),
-                    usage: "eval <script>",
+                    context: spec.name.to_string(),
+                    usage: spec.usage,
                 });
+            };
+            let zoom = match factor.as_str() {
+                "reset" => Some(1.0),
+                f => match f.strip_suffix('%') {
+                    Some(percent) => percent.parse::<f64>().ok().map(|p| p / 100.0),
+                    None => f.parse::<f64>().ok(),
+                },
+            };
+            let mut cmd = CommandJson::new("setZoom");
+            cmd.zoom = Some(zoom.filter(|z| (0.25..=5.0).contains(z)).ok_or_else(|| {
+                ParseError::InvalidValue {
+                    field: "zoom".to_string(),
+                    value: factor.clone(),
+                    expected: "a factor from 0.25 to 5, a percentage such as 150%, or reset"
+                        .to_string(),
+                }
+            })?);
+            cmd.css = flags.has("--css").then_some(true);
+            Ok(cmd)
+        }
+
+        "viewport" => {
+            let mut size = [0u32; 2];
+            for (i, field) in ["width", "height"].into_iter().enumerate() {
+                size[i] = rest[i].parse().ok().filter(|n| *n > 0).ok_or_else(|| {
+                    ParseError::InvalidValue {
+                        field: field.to_string(),
+                        value: rest[i].clone(),
+                        expected: "a positive number of CSS pixels".to_string(),
+                    }
+                })?;
             }
-            let mut cmd = CommandJson::new("evaluate");
-            cmd.text = Some(rest.join(" "));
+            let mut cmd = CommandJson::new("setViewport");
+            cmd.viewport = Some(Viewport {
+                width: size[0],
+                height: size[1],
+            });
+            Ok(cmd)
+        }
+
+        "device" => {
+            // Names have spaces, so `device iPhone 14` needs no quotes
+            let name = rest.join(" ");
+            if ["reset", "off", "none"].contains(&name.as_str()) {
+                return Ok(CommandJson::new("resetDevice"));
+            }
+            let device = devices::find(&name).ok_or_else(|| ParseError::InvalidValue {
+                field: "device".to_string(),
+                value: name.clone(),
+                expected: "a preset from 'agentbrowser-pro devices', or reset".to_string(),
+            })?;
+            let (width, height) = if flags.has("--landscape") {
+                (device.height, device.width)
+            } else {
+                (device.width, device.height)
+            };
+            let mut cmd = CommandJson::new("emulateDevice");
+            cmd.device = Some(device.name.to_string());
+            cmd.viewport = Some(Viewport { width, height });
+            cmd.device_scale_factor = Some(device.scale);
+            cmd.is_mobile = Some(device.mobile);
+            cmd.has_touch = Some(device.touch);
+            cmd.user_agent = Some(device.user_agent.to_string());
+            Ok(cmd)
+        }
+
+        "devices" => Ok(CommandJson::new("listDevices")),
+
+        "media" => match rest.first().map(|s| s.as_str()) {
+            Some("fake") => {
+                let mut cmd = CommandJson::new("mediaFake");
+                match rest.get(1).map(|s| s.as_str()) {
+                    Some("off") => cmd.enabled = Some(false),
+                    Some(other) => {
+                        return Err(ParseError::InvalidValue {
+                            field: "media fake".to_string(),
+                            value: other.to_string(),
+                            expected: "--video=<file>, --audio=<file> or off".to_string(),
+                        })
+                    }
+                    None => {
+                        cmd.enabled = Some(true);
+                        cmd.video = flags.value("--video").map(String::from);
+                        cmd.audio = flags.value("--audio").map(String::from);
+                    }
+                }
+                Ok(cmd)
+            }
+            Some("devices") => match rest.get(1).map(|s| s.as_str()) {
+                Some("list") | None => Ok(CommandJson::new("listMediaDevices")),
+                Some(other) => Err(ParseError::UnknownSubcommand {
+                    subcommand: other.to_string(),
+                    valid_options: &["list"],
+                }),
+            },
+            Some(other) => Err(ParseError::UnknownSubcommand {
+                subcommand: other.to_string(),
+                valid_options: &["fake", "devices"],
+            }),
+            None => Err(ParseError::MissingArguments {
+                context: spec.name.to_string(),
+                usage: spec.usage,
+            }),
+        },
+
+        "emulate" => match rest.first().map(|s| s.as_str()) {
+            Some("vision") => {
+                let kind = &rest[1];
+                let vision = match kind.to_lowercase().as_str() {
+                    "blurred" => "blurredVision",
+                    "reduced-contrast" => "reducedContrast",
+                    "none" | "off" => "none",
+                    "deuteranopia" => "deuteranopia",
+                    "protanopia" => "protanopia",
+                    "tritanopia" => "tritanopia",
+                    "achromatopsia" => "achromatopsia",
+                    _ => {
+                        return Err(ParseError::InvalidValue {
+                            field: "vision".to_string(),
+                            value: kind.clone(),
+                            expected: "deuteranopia, protanopia, tritanopia, achromatopsia, \
+                                       blurred, reduced-contrast or none"
+                                .to_string(),
+                        })
+                    }
+                };
+                let mut cmd = CommandJson::new("emulateVision");
+                cmd.vision = Some(vision.to_string());
+                Ok(cmd)
+            }
+            Some("forced-colors") => {
+                let mode = &rest[1];
+                let forced = match mode.as_str() {
+                    "on" | "active" => "active",
+                    "off" | "none" => "none",
+                    _ => {
+                        return Err(ParseError::InvalidValue {
+                            field: "forced-colors".to_string(),
+                            value: mode.clone(),
+                            expected: "on or off".to_string(),
+                        })
+                    }
+                };
+                let mut cmd = CommandJson::new("emulateMedia");
+                cmd.forced_colors = Some(forced.to_string());
+                Ok(cmd)
+            }
+            Some("orientation") => {
+                let value = &rest[1];
+                let mut cmd = CommandJson::new("emulateOrientation");
+                match value.as_str() {
+                    "portrait" | "landscape" => cmd.orientation = Some(value.clone()),
+                    _ => {
+                        cmd.reading = Some(parse_reading("orientation", value, "alpha,beta,gamma")?)
+                    }
+                }
+                Ok(cmd)
+            }
+            Some("accelerometer") => {
+                let value = &rest[1];
+                let mut cmd = CommandJson::new("emulateAccelerometer");
+                if value != "off" {
+                    cmd.reading = Some(parse_reading("accelerometer", value, "x,y,z")?);
+                }
+                Ok(cmd)
+            }
+            Some(other) => Err(ParseError::UnknownSubcommand {
+                subcommand: other.to_string(),
+                valid_options: &["vision", "forced-colors", "orientation", "accelerometer"],
+            }),
+            None => Err(ParseError::MissingArguments {
+                context: spec.name.to_string(),
+                usage: spec.usage,
+            }),
+        },
+
+        "errors" => {
+            let mut cmd = CommandJson::new("getErrors");
+            if flags.has("--clear") {
+                cmd.clear = Some(true);
+            }
+            Ok(cmd)
+        }
+
+        "messages" => match rest.first().map(|s| s.as_str()) {
+            Some("tap") => {
+                let mut cmd = CommandJson::new("tapMessages");
+                cmd.channel = flags.value("--channel").map(String::from);
+                Ok(cmd)
+            }
+            Some(other) => Err(ParseError::UnknownSubcommand {
+                subcommand: other.to_string(),
+                valid_options: &["tap"],
+            }),
+            None => Err(ParseError::MissingArguments {
+                context: spec.name.to_string(),
+                usage: spec.usage,
+            }),
+        },
+
+        // ============ Assertions ============
+        "expect" => match rest.first().map(|s| s.as_str()) {
+            Some("visible") => {
+                let mut cmd = CommandJson::new("expectVisible");
+                cmd.selector = Some(rest[1].clone());
+                Ok(cmd)
+            }
+            Some("text") => {
+                if rest.len() < 3 {
+                    return Err(ParseError::MissingArguments {
+                        context: "expect text".to_string(),
+                        usage: "expect text <selector|ref> <text> [--exact]",
+                    });
+                }
+                let mut cmd = CommandJson::new("expectText");
+                cmd.selector = Some(rest[1].clone());
+                cmd.text = Some(rest[2..].join(" "));
+                cmd.exact = flags.has("--exact").then_some(true);
+                Ok(cmd)
+            }
+            Some("url") => {
+                let mut cmd = CommandJson::new("expectUrl");
+                cmd.url_pattern = Some(rest[1].clone());
+                Ok(cmd)
+            }
+            Some("count") => {
+                let n = rest.get(2).ok_or_else(|| ParseError::MissingArguments {
+                    context: "expect count".to_string(),
+                    usage: "expect count <selector> <n>",
+                })?;
+                let mut cmd = CommandJson::new("expectCount");
+                cmd.selector = Some(rest[1].clone());
+                cmd.count = Some(n.parse().map_err(|_| ParseError::InvalidValue {
+                    field: "count".to_string(),
+                    value: n.clone(),
+                    expected: "a number of elements".to_string(),
+                })?);
+                Ok(cmd)
+            }
+            Some("request") => {
+                let pattern = &rest[1];
+                let mut cmd = CommandJson::new("expectRequest");
+                cmd.url_pattern = Some(pattern.clone());
+                cmd.method = flags.value("--method").map(|m| m.to_uppercase());
+                cmd.body_contains = flags.value("--body-contains").map(String::from);
+                Ok(cmd)
+            }
+            Some("json") => {
+                JsonExpectation::parse(&rest[1..])?;
+                Ok(CommandJson::new("expectJson"))
+            }
+            Some(other) => Err(ParseError::UnknownSubcommand {
+                subcommand: other.to_string(),
+                valid_options: &["visible", "text", "url", "count", "request", "json"],
+            }),
+            None => Err(ParseError::MissingArguments {
+                context: spec.name.to_string(),
+                usage: spec.usage,
+            }),
+        },
+
+        // ============ Notifications ============
+        "notifications" => match rest.first().map(|s| s.as_str()) {
+            Some("capture") => {
+                let enabled = match rest.get(1).map(|s| s.as_str()) {
+                    Some("on") => true,
+                    Some("off") => false,
+                    Some(other) => {
+                        return Err(ParseError::InvalidValue {
+                            field: "capture".to_string(),
+                            value: other.to_string(),
+                            expected: "on or off".to_string(),
+                        })
+                    }
+                    None => {
+                        return Err(ParseError::MissingArguments {
+                            context: "notifications capture".to_string(),
+                            usage: "notifications capture <on|off>",
+                        })
+                    }
+                };
+                let mut cmd = CommandJson::new("notificationCapture");
+                cmd.enabled = Some(enabled);
+                Ok(cmd)
+            }
+            Some("list") | None => {
+                let mut cmd = CommandJson::new("getNotifications");
+                if flags.has("--clear") {
+                    cmd.clear = Some(true);
+                }
+                Ok(cmd)
+            }
+            Some(other) => Err(ParseError::UnknownSubcommand {
+                subcommand: other.to_string(),
+                valid_options: &["capture", "list"],
+            }),
+        },
+
+        // ============ Network ============
+        "network" => match rest.first().map(|s| s.as_str()) {
+            Some("list") | None => {
+                let mut cmd = CommandJson::new("getRequests");
+                cmd.url_pattern = flags.value("--filter").map(String::from);
+                if flags.has("--clear") {
+                    cmd.clear = Some(true);
+                }
+                if flags.has("--graphql") {
+                    cmd.graphql = Some(true);
+                }
+                cmd.operation = flags.value("--operation").map(String::from);
+                if flags.has("--body") {
+                    if cmd.graphql.is_none() && cmd.operation.is_none() {
+                        return Err(ParseError::InvalidValue {
+                            field: "--body".to_string(),
+                            value: "--body".to_string(),
+                            expected: "--graphql or --operation (only GraphQL bodies are kept)"
+                                .to_string(),
+                        });
+                    }
+                    cmd.include_body = Some(true);
+                }
+                Ok(cmd)
+            }
+            Some(other) => Err(ParseError::UnknownSubcommand {
+                subcommand: other.to_string(),
+                valid_options: &["list"],
+            }),
+        },
+
+        "route" => {
+            let mut cmd = CommandJson::new("route");
+            cmd.url = Some(rest[0].clone());
+            let status = match flags.value("--status") {
+                Some(status) => Some(status.parse().map_err(|_| ParseError::InvalidValue {
+                    field: "--status".to_string(),
+                    value: status.to_string(),
+                    expected: "an HTTP status code".to_string(),
+                })?),
+                None => None,
+            };
+            let file = flags.value("--fulfill");
+            if flags.has("--abort") {
+                if file.is_some() || status.is_some() {
+                    return Err(ParseError::InvalidValue {
+                        field: "--abort".to_string(),
+                        value: "--abort".to_string(),
+                        expected: "--abort on its own, without --fulfill or --status".to_string(),
+                    });
+                }
+                cmd.handler = Some("abort".to_string());
+                return Ok(cmd);
+            }
+            if file.is_none() && status.is_none() {
+                return Err(ParseError::MissingArguments {
+                    context: spec.name.to_string(),
+                    usage: spec.usage,
+                });
+            }
+            let mut response = RouteResponse {
+                status,
+                ..Default::default()
+            };
+            if let Some(path) = file {
+                let body = fs::read_to_string(path).map_err(|_| ParseError::InvalidValue {
+                    field: "--fulfill".to_string(),
+                    value: path.to_string(),
+                    expected: "a readable UTF-8 text file".to_string(),
+                })?;
+                response.headers = Some(BTreeMap::from([(
+                    "content-type".to_string(),
+                    content_type(path).to_string(),
+                )]));
+                response.body = Some(body);
+            }
+            cmd.handler = Some("fulfill".to_string());
+            cmd.response = Some(response);
+            Ok(cmd)
+        }
+
+        "unroute" => {
+            let mut cmd = CommandJson::new("unroute");
+            cmd.url = rest.first().cloned();
+            Ok(cmd)
+        }
+
+        "routes" => Ok(CommandJson::new("getRoutes")),
+
+        "dialog" => match rest[0].as_str() {
+            "accept" | "dismiss" => {
+                let mut cmd = CommandJson::new("handleDialog");
+                cmd.accept = Some(rest[0] == "accept");
+                if rest[0] == "accept" {
+                    cmd.prompt_text = rest.get(1).cloned();
+                }
+                Ok(cmd)
+            }
+            "auto" => {
+                let Some(mode) = rest.get(1) else {
+                    return Err(ParseError::MissingArguments {
+                        context: "dialog auto".to_string(),
+                        usage: spec.usage,
+                    });
+                };
+                if mode != "accept" && mode != "dismiss" {
+                    return Err(ParseError::InvalidValue {
+                        field: "dialog auto".to_string(),
+                        value: mode.clone(),
+                        expected: "accept or dismiss".to_string(),
+                    });
+                }
+                let mut cmd = CommandJson::new("setDialogAuto");
+                cmd.mode = Some(mode.clone());
+                Ok(cmd)
+            }
+            other => Err(ParseError::UnknownSubcommand {
+                subcommand: other.to_string(),
+                valid_options: &["accept", "dismiss", "auto"],
+            }),
+        },
+
+        "download" => {
+            let mut cmd = CommandJson::new("download");
+            cmd.selector = Some(rest[0].clone());
+            cmd.path = rest.get(1).cloned();
+            cmd.downloads_dir = flags.downloads_dir.clone();
+            Ok(cmd)
+        }
+
+        "downloads" => {
+            let mut cmd = CommandJson::new("getDownloads");
+            cmd.downloads_dir = flags.downloads_dir.clone();
             Ok(cmd)
         }
 
+        "har" => match rest[0].as_str() {
+            "start" => {
+                let Some(path) = rest.get(1) else {
+                    return Err(ParseError::MissingArguments {
+                        context: "har start".to_string(),
+                        usage: spec.usage,
+                    });
+                };
+                let mut cmd = CommandJson::new("harStart");
+                cmd.path = Some(path.clone());
+                cmd.content = flags.has("--content").then_some(true);
+                Ok(cmd)
+            }
+            "stop" => Ok(CommandJson::new("harStop")),
+            other => Err(ParseError::UnknownSubcommand {
+                subcommand: other.to_string(),
+                valid_options: &["start", "stop"],
+            }),
+        },
+
         // ============ Cookies ============
-        "cookies" | "getcookies" => Ok(CommandJson::new("getCookies")),
+        "cookies" => Ok(CommandJson::new("getCookies")),
 
         "clearcookies" => Ok(CommandJson::new("clearCookies")),
 
         // ============ Storage ============
-        "localstorage" | "getlocalstorage" => {
+        "localstorage" => {
             let mut cmd = CommandJson::new("getLocalStorage");
             if !rest.is_empty() {
                 cmd.key = Some(rest[0].clone());
@@ -519,7 +1751,280 @@ pub fn parse_command(args: &[String], flags: &Flags) -> Result<CommandJson, Pars
 
         "clearlocalstorage" => Ok(CommandJson::new("clearLocalStorage")),
 
+        // ============ Crawling ============
+        "crawl" => {
+            for (name, zero_ok) in [
+                ("--max-pages", false),
+                ("--max-depth", true),
+                ("--delay", true),
+            ] {
+                if let Some(n) = flags.value(name) {
+                    if n.parse::<u64>().map(|n| n == 0 && !zero_ok).unwrap_or(true) {
+                        return Err(ParseError::InvalidValue {
+                            field: name.to_string(),
+                            value: n.to_string(),
+                            expected: if zero_ok {
+                                "a number"
+                            } else {
+                                "a positive number"
+                            }
+                            .to_string(),
+                        });
+                    }
+                }
+            }
+            if let Some(mode) = flags.value("--dedupe") {
+                if crawl::Dedupe::parse(mode).is_none() {
+                    return Err(ParseError::InvalidValue {
+                        field: "--dedupe".to_string(),
+                        value: mode.to_string(),
+                        expected: "content or url-normalized".to_string(),
+                    });
+                }
+            }
+            let mut cmd = CommandJson::new("crawl");
+            if flags.has("--resume") {
+                let path = rest.first().ok_or(ParseError::MissingArguments {
+                    context: "crawl --resume".to_string(),
+                    usage: spec.usage,
+                })?;
+                cmd.path = Some(path.clone());
+                return Ok(cmd);
+            }
+            let url = rest.first().ok_or(ParseError::MissingArguments {
+                context: spec.name.to_string(),
+                usage: spec.usage,
+            })?;
+            if !url.starts_with("http://") && !url.starts_with("https://") {
+                return Err(ParseError::InvalidValue {
+                    field: "url".to_string(),
+                    value: url.clone(),
+                    expected: "an http:// or https:// URL".to_string(),
+                });
+            }
+            cmd.url = Some(url.clone());
+            cmd.path = flags.value("--state").map(String::from);
+            Ok(cmd)
+        }
+
+        "repl" => Ok(CommandJson::new("repl")),
+
+        "pagediff" => {
+            let action = match rest.first().map(|s| s.as_str()) {
+                Some("save") => "pagediffSave",
+                Some("compare") => "pagediffCompare",
+                Some(other) => {
+                    return Err(ParseError::UnknownSubcommand {
+                        subcommand: other.to_string(),
+                        valid_options: &["save", "compare"],
+                    })
+                }
+                None => {
+                    return Err(ParseError::MissingArguments {
+                        context: spec.name.to_string(),
+                        usage: spec.usage,
+                    })
+                }
+            };
+            let name = &rest[1];
+            if !pagediff::valid_name(name) {
+                return Err(ParseError::InvalidValue {
+                    field: "name".to_string(),
+                    value: name.clone(),
+                    expected: "letters, digits, '_', '-' or '.'".to_string(),
+                });
+            }
+            if let Some(mode) = flags.value("--mode") {
+                if pagediff::Mode::parse(mode).is_none() {
+                    return Err(ParseError::InvalidValue {
+                        field: "--mode".to_string(),
+                        value: mode.to_string(),
+                        expected: "text, html or visual".to_string(),
+                    });
+                }
+            }
+            let mut cmd = CommandJson::new(action);
+            cmd.value = Some(name.clone());
+            cmd.selector = flags.value("--selector").map(String::from);
+            Ok(cmd)
+        }
+
+        "textdiff" => {
+            if let Some(unit) = flags.value("--by") {
+                if textdiff::Unit::parse(unit).is_none() {
+                    return Err(ParseError::InvalidValue {
+                        field: "--by".to_string(),
+                        value: unit.to_string(),
+                        expected: "word or char".to_string(),
+                    });
+                }
+            }
+            if flags.value("--against") == Some("") {
+                return Err(ParseError::InvalidValue {
+                    field: "--against".to_string(),
+                    value: String::new(),
+                    expected: "last or a file path".to_string(),
+                });
+            }
+            let mut cmd = CommandJson::new("textDiff");
+            cmd.selector = Some(rest[0].clone());
+            Ok(cmd)
+        }
+
+        "detectlang" => {
+            let mut cmd = CommandJson::new("detectLanguage");
+            cmd.selector = rest.first().cloned();
+            Ok(cmd)
+        }
+
+        "translate" => {
+            for field in ["--to", "--from"] {
+                let Some(lang) = flags.value(field) else {
+                    continue;
+                };
+                let valid = !lang.is_empty()
+                    && lang
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+                if !valid {
+                    return Err(ParseError::InvalidValue {
+                        field: field.to_string(),
+                        value: lang.to_string(),
+                        expected: "a language code like en, de or pt-BR".to_string(),
+                    });
+                }
+            }
+            if flags.value("--to").is_none() {
+                return Err(ParseError::MissingArguments {
+                    context: spec.name.to_string(),
+                    usage: spec.usage,
+                });
+            }
+            let mut cmd = CommandJson::new("translate");
+            cmd.selector = rest.first().cloned();
+            Ok(cmd)
+        }
+
+        "monitor" => match rest.first().map(|s| s.as_str()) {
+            Some("add") => {
+                let url = rest.get(1).ok_or(ParseError::MissingArguments {
+                    context: "monitor add".to_string(),
+                    usage: spec.usage,
+                })?;
+                if !url.starts_with("http://") && !url.starts_with("https://") {
+                    return Err(ParseError::InvalidValue {
+                        field: "url".to_string(),
+                        value: url.clone(),
+                        expected: "an http:// or https:// URL".to_string(),
+                    });
+                }
+                let interval = flags
+                    .value("--interval")
+                    .unwrap_or(monitor::DEFAULT_INTERVAL);
+                if parse_ttl(interval)
+                    .map(|d| d < Duration::from_secs(1))
+                    .unwrap_or(true)
+                {
+                    return Err(ParseError::InvalidValue {
+                        field: "--interval".to_string(),
+                        value: interval.to_string(),
+                        expected: "a duration of at least 1s, like 30s, 10m or 1h".to_string(),
+                    });
+                }
+                if let Some(mode) = flags.value("--mode") {
+                    if pagediff::Mode::parse(mode).is_none() {
+                        return Err(ParseError::InvalidValue {
+                            field: "--mode".to_string(),
+                            value: mode.to_string(),
+                            expected: "text, html or visual".to_string(),
+                        });
+                    }
+                }
+                let mut cmd = CommandJson::new("monitorAdd");
+                cmd.url = Some(url.clone());
+                cmd.selector = flags.value("--selector").map(String::from);
+                Ok(cmd)
+            }
+            Some("list") => Ok(CommandJson::new("monitorList")),
+            Some("remove") => {
+                let id = rest.get(1).ok_or(ParseError::MissingArguments {
+                    context: "monitor remove".to_string(),
+                    usage: spec.usage,
+                })?;
+                let mut cmd = CommandJson::new("monitorRemove");
+                cmd.value = Some(id.clone());
+                Ok(cmd)
+            }
+            Some("run") => Ok(CommandJson::new("monitorRun")),
+            Some(other) => Err(ParseError::UnknownSubcommand {
+                subcommand: other.to_string(),
+                valid_options: &["add", "list", "remove", "run"],
+            }),
+            None => Err(ParseError::MissingArguments {
+                context: spec.name.to_string(),
+                usage: spec.usage,
+            }),
+        },
+
         // ============ PDF ============
+        "storage" => match rest.first().map(|s| s.as_str()) {
+            Some("usage") => Ok(CommandJson::new("getStorageUsage")),
+            Some(other) => Err(ParseError::UnknownSubcommand {
+                subcommand: other.to_string(),
+                valid_options: &["usage"],
+            }),
+            None => Err(ParseError::MissingArguments {
+                context: spec.name.to_string(),
+                usage: spec.usage,
+            }),
+        },
+
+        "state" => match rest.first().map(|s| s.as_str()) {
+            Some(op @ ("save" | "load")) => {
+                let path = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
+                    context: format!("state {}", op),
+                    usage: spec.usage,
+                })?;
+                let mut cmd = CommandJson::new(if op == "save" {
+                    "saveState"
+                } else {
+                    "loadState"
+                });
+                cmd.path = Some(path.clone());
+                Ok(cmd)
+            }
+            Some("key") => Ok(CommandJson::new("stateKey")),
+            Some(other) => Err(ParseError::UnknownSubcommand {
+                subcommand: other.to_string(),
+                valid_options: &["save", "load", "key"],
+            }),
+            None => Err(ParseError::MissingArguments {
+                context: spec.name.to_string(),
+                usage: spec.usage,
+            }),
+        },
+
+        "cachestorage" => match rest.first().map(|s| s.as_str()) {
+            Some("list") => Ok(CommandJson::new("listCaches")),
+            Some("entries") => {
+                let name = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
+                    context: "cachestorage entries".to_string(),
+                    usage: "cachestorage entries <cache-name>",
+                })?;
+                let mut cmd = CommandJson::new("getCacheEntries");
+                cmd.name = Some(name.clone());
+                Ok(cmd)
+            }
+            Some(other) => Err(ParseError::UnknownSubcommand {
+                subcommand: other.to_string(),
+                valid_options: &["list", "entries"],
+            }),
+            None => Err(ParseError::MissingArguments {
+                context: spec.name.to_string(),
+                usage: spec.usage,
+            }),
+        },
+
         "pdf" => {
             let mut cmd = CommandJson::new("pdf");
             if !rest.is_empty() {
@@ -529,13 +2034,178 @@ pub fn parse_command(args: &[String], flags: &Flags) -> Result<CommandJson, Pars
         }
 
         // ============ Streaming ============
-        "stream" | "startstream" => Ok(CommandJson::new("startStream")),
+        "stream" => Ok(CommandJson::new("startStream")),
 
         "stopstream" => Ok(CommandJson::new("stopStream")),
 
-        // Unknown command
+        // Registered but handled before parsing, like `help`
         _ => Err(ParseError::UnknownCommand {
-            command: command.clone(),
+            command: spec.name.to_string(),
+            suggestion: None,
         }),
     }
 }
+
+/// Whether `value` is `YYYY-MM-DD`, `YYYY-MM-DDTHH:MM[:SS]` or `HH:MM[:SS]`
+fn is_iso_datetime(value: &str) -> bool {
+    let field = |s: &str, len: usize, max: u32| {
+        s.len() == len
+            && s.bytes().all(|b| b.is_ascii_digit())
+            && s.parse::<u32>().is_ok_and(|n| n <= max)
+    };
+    let time = |s: &str| {
+        let parts: Vec<&str> = s.split(':').collect();
+        matches!(parts.len(), 2 | 3)
+            && field(parts[0], 2, 23)
+            && field(parts[1], 2, 59)
+            && parts.get(2).is_none_or(|s| field(s, 2, 59))
+    };
+    let date = |s: &str| {
+        let parts: Vec<&str> = s.split('-').collect();
+        parts.len() == 3
+            && field(parts[0], 4, 9999)
+            && field(parts[1], 2, 12)
+            && field(parts[2], 2, 31)
+            && parts[1] != "00"
+            && parts[2] != "00"
+    };
+    match value.split_once('T') {
+        Some((d, t)) => date(d) && time(t),
+        None => date(value) || time(value),
+    }
+}
+
+/// Content type for a `route --fulfill` body, from the file extension
+fn content_type(path: &str) -> &'static str {
+    let extension = path.rsplit_once('.').map(|(_, e)| e.to_ascii_lowercase());
+    match extension.as_deref() {
+        Some("json") => "application/json",
+        Some("html" | "htm") => "text/html; charset=utf-8",
+        Some("js" | "mjs") => "text/javascript",
+        Some("css") => "text/css",
+        Some("svg") => "image/svg+xml",
+        Some("xml") => "application/xml",
+        Some("csv") => "text/csv",
+        _ => "text/plain; charset=utf-8",
+    }
+}
+
+/// Modifier spellings accepted in chords, with the names Playwright uses
+const MODIFIERS: &[(&str, &str)] = &[
+    ("control", "Control"),
+    ("ctrl", "Control"),
+    ("shift", "Shift"),
+    ("alt", "Alt"),
+    ("option", "Alt"),
+    ("meta", "Meta"),
+    ("cmd", "Meta"),
+    ("command", "Meta"),
+    ("controlormeta", "ControlOrMeta"),
+];
+
+/// Check a key or chord like `Control+Shift+P`, spelling its modifiers the
+/// way Playwright expects (`ctrl` becomes `Control`). The last part is the
+/// key itself, so `Shift++` presses `+`.
+fn parse_chord(chord: &str) -> Result<String, ParseError> {
+    let modifier = |name: &str| {
+        MODIFIERS
+            .iter()
+            .find(|(alias, _)| alias.eq_ignore_ascii_case(name))
+            .map(|(_, modifier)| *modifier)
+    };
+    let (held, key) = match chord.strip_suffix("++") {
+        Some(held) => (Some(held), "+"),
+        None => match chord.rsplit_once('+') {
+            Some((held, key)) if chord != "+" => (Some(held), key),
+            _ => (None, chord),
+        },
+    };
+    let invalid = |expected: &str| ParseError::InvalidValue {
+        field: "key".to_string(),
+        value: chord.to_string(),
+        expected: expected.to_string(),
+    };
+    if key.is_empty() {
+        return Err(invalid("a key after the modifiers, e.g. Control+Shift+P"));
+    }
+
+    let mut parts = Vec::new();
+    for name in held.into_iter().flat_map(|held| held.split('+')) {
+        let modifier = modifier(name)
+            .ok_or_else(|| invalid("modifiers from Control, Shift, Alt, Meta, ControlOrMeta"))?;
+        if !parts.contains(&modifier) {
+            parts.push(modifier);
+        }
+    }
+    parts.push(modifier(key).unwrap_or(key));
+    Ok(parts.join("+"))
+}
+
+/// Parse a sensor reading of three comma-separated numbers
+fn parse_reading(field: &str, value: &str, names: &str) -> Result<Vec<f64>, ParseError> {
+    let reading: Vec<f64> = value
+        .split(',')
+        .map(|v| v.trim().parse::<f64>())
+        .collect::<Result<_, _>>()
+        .unwrap_or_default();
+    if reading.len() != 3 || reading.iter().any(|v| !v.is_finite()) {
+        return Err(ParseError::InvalidValue {
+            field: field.to_string(),
+            value: value.to_string(),
+            expected: format!("three comma-separated numbers ({})", names),
+        });
+    }
+    Ok(reading)
+}
+
+/// Split a command line into words, honouring single/double quotes and
+/// backslash escapes
+pub fn split_words(input: &str) -> Result<Vec<String>, String> {
+    let mut words = Vec::new();
+    let mut current = String::new();
+    let mut in_word = false;
+    let mut quote: Option<char> = None;
+    let mut chars = input.chars();
+
+    while let Some(c) = chars.next() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some('"') if c == '\\' => {
+                if let Some(next) = chars.next() {
+                    current.push(next);
+                }
+            }
+            Some(_) => current.push(c),
+            None => match c {
+                '"' | '\'' => {
+                    quote = Some(c);
+                    in_word = true;
+                }
+                '\\' => {
+                    if let Some(next) = chars.next() {
+                        current.push(next);
+                    }
+                    in_word = true;
+                }
+                c if c.is_whitespace() => {
+                    if in_word {
+                        words.push(std::mem::take(&mut current));
+                        in_word = false;
+                    }
+                }
+                _ => {
+                    current.push(c);
+                    in_word = true;
+                }
+            },
+        }
+    }
+
+    if quote.is_some() {
+        return Err(format!("Unterminated quote in: {}", input));
+    }
+    if in_word {
+        words.push(current);
+    }
+    Ok(words)
+}
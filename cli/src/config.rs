@@ -0,0 +1,106 @@
+/*!
+ * User Configuration
+ *
+ * Loaded from $AGENT_BROWSER_CONFIG, or ~/.agentbrowser-pro/config.toml
+ */
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+use serde::Deserialize;
+
+use crate::commands::split_words;
+use crate::hooks::ResultHooks;
+use crate::plugins::PluginConfig;
+use crate::policy::Policy;
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Command aliases, e.g. `shot = "screenshot --full-page"`
+    pub aliases: BTreeMap<String, String>,
+    /// Timeout (ms) for commands run without `--timeout`
+    pub default_timeout: Option<u64>,
+    /// Language for help and messages, e.g. `lang = "de"`
+    pub lang: Option<String>,
+    /// Limits on what commands may do, see `policy`
+    pub policy: Policy,
+    /// Shell commands results are piped through before printing, see `hooks`
+    pub on_result: ResultHooks,
+    /// WASM plugins by command name, see `plugins`
+    pub plugins: BTreeMap<String, PluginConfig>,
+    /// Command that runs a WASI module, e.g. `wasmtime run`
+    pub wasm_runtime: Option<String>,
+    /// Hook `translate` sends text through: an http(s) endpoint or a shell
+    /// command, see `language`
+    pub translate: Option<String>,
+}
+
+impl Config {
+    /// Load the config file, returning defaults if none exists
+    pub fn load() -> Result<Self, String> {
+        let path = match config_path() {
+            Some(path) => path,
+            None => return Ok(Config::default()),
+        };
+
+        if !path.exists() {
+            return Ok(Config::default());
+        }
+
+        let content = fs::read_to_string(&path)
+            .map_err(|e| format!("Failed to read config {}: {}", path.display(), e))?;
+        toml::from_str(&content).map_err(|e| format!("Invalid config {}: {}", path.display(), e))
+    }
+
+    /// Expand a user alias in the command position of `args`.
+    ///
+    /// Built-in commands always win over aliases, and an alias may refer to
+    /// another alias as long as it does not loop back on itself.
+    pub fn expand_aliases(&self, args: &[String]) -> Result<Vec<String>, String> {
+        let mut args = args.to_vec();
+        let mut seen: Vec<String> = Vec::new();
+
+        loop {
+            let pos = match crate::flags::first_positional(&args) {
+                Some(pos) => pos,
+                None => return Ok(args),
+            };
+            let name = args[pos].to_lowercase();
+
+            if crate::registry::find_command(&name).is_some() {
+                return Ok(args);
+            }
+            let expansion = match self.aliases.get(&name) {
+                Some(expansion) => expansion,
+                None => return Ok(args),
+            };
+            if seen.contains(&name) {
+                return Err(format!(
+                    "Alias loop detected: {} -> {}",
+                    seen.join(" -> "),
+                    name
+                ));
+            }
+            seen.push(name);
+
+            let words = split_words(expansion)?;
+            args.splice(pos..=pos, words);
+        }
+    }
+}
+
+/// Per-user data directory (~/.agentbrowser-pro)
+pub fn data_dir() -> Option<PathBuf> {
+    let home = env::var("HOME").or_else(|_| env::var("USERPROFILE")).ok()?;
+    Some(PathBuf::from(home).join(".agentbrowser-pro"))
+}
+
+/// Resolve the config file location
+pub fn config_path() -> Option<PathBuf> {
+    if let Ok(path) = env::var("AGENT_BROWSER_CONFIG") {
+        return Some(PathBuf::from(path));
+    }
+    Some(data_dir()?.join("config.toml"))
+}
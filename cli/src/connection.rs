@@ -1,9 +1,9 @@
-/**
+/*!
  * Daemon Connection and Lifecycle Management
  */
 use std::env;
 use std::fs;
-use std::io::{BufRead, BufReader, Read, Write};
+use std::io::{BufRead, BufReader, Write};
 use std::os::unix::net::UnixStream;
 use std::path::Path;
 use std::process::{Command, Stdio};
@@ -12,14 +12,61 @@ use std::time::Duration;
 
 use serde::{Deserialize, Serialize};
 
+use crate::cache;
+use crate::cdp;
 use crate::commands::CommandJson;
+use crate::native;
+use crate::redact;
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct Response {
     pub id: String,
     pub success: bool,
     pub result: Option<serde_json::Value>,
     pub error: Option<String>,
+    /// Machine-readable error code (e.g. "ref_stale", "click_intercepted")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub code: Option<String>,
+    /// Candidate refs when a selector matched nothing (added by the CLI)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub suggestions: Option<serde_json::Value>,
+    /// Recovery hint for the error (added by the CLI)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hint: Option<String>,
+    /// Structured error context from the daemon, e.g. the element that
+    /// intercepted a click
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub details: Option<serde_json::Value>,
+}
+
+impl Response {
+    /// A successful response carrying `result`
+    pub fn ok(id: &str, result: serde_json::Value) -> Response {
+        Response {
+            id: id.to_string(),
+            success: true,
+            result: Some(result),
+            error: None,
+            code: None,
+            suggestions: None,
+            hint: None,
+            details: None,
+        }
+    }
+
+    /// An error response, as the daemon would send one
+    pub fn err(id: &str, error: String, code: Option<&str>) -> Response {
+        Response {
+            id: id.to_string(),
+            success: false,
+            result: None,
+            error: Some(error),
+            code: code.map(String::from),
+            suggestions: None,
+            hint: None,
+            details: None,
+        }
+    }
 }
 
 pub struct DaemonResult {
@@ -27,7 +74,7 @@ pub struct DaemonResult {
 }
 
 /// Get the socket path for a session
-fn get_socket_path(session: &str) -> String {
+pub fn get_socket_path(session: &str) -> String {
     let tmp_dir = env::temp_dir();
     tmp_dir
         .join(format!("agentbrowser-pro-{}.sock", session))
@@ -36,7 +83,7 @@ fn get_socket_path(session: &str) -> String {
 }
 
 /// Get the PID file path for a session
-fn get_pid_file(session: &str) -> String {
+pub fn get_pid_file(session: &str) -> String {
     let tmp_dir = env::temp_dir();
     tmp_dir
         .join(format!("agentbrowser-pro-{}.pid", session))
@@ -44,33 +91,37 @@ fn get_pid_file(session: &str) -> String {
         .into_owned()
 }
 
-/// Check if daemon is running
-fn is_daemon_running(session: &str) -> bool {
+/// PID of the session's daemon, if it is running
+pub fn daemon_pid(session: &str) -> Option<i32> {
     let pid_file = get_pid_file(session);
 
     if !Path::new(&pid_file).exists() {
-        return false;
-    }
-
-    match fs::read_to_string(&pid_file) {
-        Ok(content) => {
-            if let Ok(pid) = content.trim().parse::<i32>() {
-                // Check if process exists (signal 0)
-                #[cfg(unix)]
-                unsafe {
-                    return libc::kill(pid, 0) == 0;
-                }
-                #[cfg(not(unix))]
-                return false;
-            }
-            false
+        return None;
+    }
+
+    let pid = fs::read_to_string(&pid_file)
+        .ok()?
+        .trim()
+        .parse::<i32>()
+        .ok()?;
+
+    // Check if process exists (signal 0)
+    #[cfg(unix)]
+    unsafe {
+        if libc::kill(pid, 0) == 0 {
+            return Some(pid);
         }
-        Err(_) => false,
     }
+    None
+}
+
+/// Check if daemon is running
+fn is_daemon_running(session: &str) -> bool {
+    daemon_pid(session).is_some()
 }
 
 /// Check if daemon is ready to accept commands
-fn is_daemon_ready(session: &str) -> bool {
+pub fn is_daemon_ready(session: &str) -> bool {
     let socket_path = get_socket_path(session);
 
     match UnixStream::connect(&socket_path) {
@@ -90,10 +141,7 @@ fn is_daemon_ready(session: &str) -> bool {
             // Read response
             let mut reader = BufReader::new(stream);
             let mut line = String::new();
-            match reader.read_line(&mut line) {
-                Ok(_) => true,
-                Err(_) => false,
-            }
+            reader.read_line(&mut line).is_ok()
         }
         Err(_) => false,
     }
@@ -125,6 +173,7 @@ pub fn ensure_daemon(
     session: &str,
     headed: bool,
     executable_path: Option<&str>,
+    extensions: &[String],
 ) -> Result<DaemonResult, String> {
     // Check if already running
     if is_daemon_running(session) && is_daemon_ready(session) {
@@ -144,14 +193,25 @@ pub fn ensure_daemon(
         fs::remove_file(&pid_file).ok();
     }
 
-    // Find daemon script
-    let daemon_path =
-        find_daemon_path().ok_or_else(|| "Could not find daemon script".to_string())?;
-
-    // Build command
-    let mut cmd = Command::new("node");
-    cmd.arg(&daemon_path)
-        .env("AGENT_BROWSER_DAEMON", "1")
+    // Build command: the Node daemon, or this binary's native one when
+    // asked for or when there is no Node.js install to run the script
+    let mut cmd = match find_daemon_path().filter(|_| !native::requested()) {
+        Some(daemon_path) if native::node_available() => {
+            let mut cmd = Command::new("node");
+            cmd.arg(&daemon_path);
+            cmd
+        }
+        _ => {
+            // Fail now rather than after the startup wait
+            cdp::find_browser(executable_path)?;
+            let exe = env::current_exe()
+                .map_err(|e| format!("Could not locate the CLI binary: {}", e))?;
+            let mut cmd = Command::new(exe);
+            cmd.arg("daemon").env(native::ENV_VAR, "1");
+            cmd
+        }
+    };
+    cmd.env("AGENT_BROWSER_DAEMON", "1")
         .env("AGENT_BROWSER_SESSION", session);
 
     if headed {
@@ -162,6 +222,10 @@ pub fn ensure_daemon(
         cmd.env("AGENT_BROWSER_EXECUTABLE_PATH", path);
     }
 
+    if !extensions.is_empty() {
+        cmd.env("AGENT_BROWSER_EXTENSIONS", extensions.join(","));
+    }
+
     // Spawn as detached background process
     #[cfg(unix)]
     unsafe {
@@ -178,8 +242,9 @@ pub fn ensure_daemon(
         .spawn()
         .map_err(|e| format!("Failed to start daemon: {}", e))?;
 
-    // Wait for daemon to be ready
-    for _ in 0..50 {
+    // Wait for daemon to be ready; the native one launches the browser
+    // before it listens
+    for _ in 0..200 {
         thread::sleep(Duration::from_millis(100));
         if is_daemon_ready(session) {
             return Ok(DaemonResult {
@@ -188,35 +253,103 @@ pub fn ensure_daemon(
         }
     }
 
-    Err("Daemon failed to start within 5 seconds".to_string())
+    Err("Daemon failed to start within 20 seconds".to_string())
+}
+
+/// Ask a running daemon to shut down and wait for it to exit
+pub fn stop_daemon(session: &str) -> Result<(), String> {
+    let pid = match daemon_pid(session) {
+        Some(pid) => pid,
+        None => return Ok(()),
+    };
+
+    #[cfg(unix)]
+    unsafe {
+        libc::kill(pid, libc::SIGTERM);
+    }
+
+    for _ in 0..100 {
+        thread::sleep(Duration::from_millis(100));
+        if !is_daemon_running(session) {
+            return Ok(());
+        }
+    }
+
+    Err(format!(
+        "Daemon (pid {}) did not exit within 10 seconds",
+        pid
+    ))
 }
 
 /// Send a command to the daemon
 pub fn send_command(cmd: &CommandJson, session: &str) -> Result<Response, String> {
-    let socket_path = get_socket_path(session);
+    Connection::open(session)?.send(&cmd.to_json(), cmd.timeout)
+}
+
+/// A daemon connection kept open across commands, which the daemon answers
+/// in order, one line each
+pub struct Connection {
+    session: String,
+    reader: BufReader<UnixStream>,
+    writer: UnixStream,
+}
+
+impl Connection {
+    pub fn open(session: &str) -> Result<Connection, String> {
+        let stream = UnixStream::connect(get_socket_path(session))
+            .map_err(|e| format!("Failed to connect to daemon: {}", e))?;
+        let reader = BufReader::new(
+            stream
+                .try_clone()
+                .map_err(|e| format!("Failed to connect to daemon: {}", e))?,
+        );
+        Ok(Connection {
+            session: session.to_string(),
+            reader,
+            writer: stream,
+        })
+    }
 
-    let mut stream = UnixStream::connect(&socket_path)
-        .map_err(|e| format!("Failed to connect to daemon: {}", e))?;
-
-    stream.set_read_timeout(Some(Duration::from_secs(30))).ok();
-    stream.set_write_timeout(Some(Duration::from_secs(30))).ok();
-
-    // Send command
-    let json = cmd.to_json();
-    stream
-        .write_all(json.as_bytes())
-        .map_err(|e| format!("Failed to send command: {}", e))?;
-    stream
-        .write_all(b"\n")
-        .map_err(|e| format!("Failed to send newline: {}", e))?;
-
-    // Read response
-    let mut reader = BufReader::new(stream);
-    let mut line = String::new();
-    reader
-        .read_line(&mut line)
-        .map_err(|e| format!("Failed to read response: {}", e))?;
-
-    // Parse response
-    serde_json::from_str(&line).map_err(|e| format!("Failed to parse response: {}", e))
+    /// Send one serialized command and wait for its response. `timeout` is
+    /// the command's own timeout in milliseconds, if it has one.
+    pub fn send(&mut self, json: &str, timeout: Option<u64>) -> Result<Response, String> {
+        // Give the daemon the full action timeout plus some slack to respond
+        let wait = timeout
+            .map(|ms| Duration::from_millis(ms) + Duration::from_secs(5))
+            .unwrap_or(Duration::from_secs(30))
+            .max(Duration::from_secs(30));
+        self.writer.set_read_timeout(Some(wait)).ok();
+        self.writer.set_write_timeout(Some(wait)).ok();
+
+        // Send command
+        self.writer
+            .write_all(json.as_bytes())
+            .map_err(|e| format!("Failed to send command: {}", e))?;
+        self.writer
+            .write_all(b"\n")
+            .map_err(|e| format!("Failed to send newline: {}", e))?;
+        // Whatever the caller, a command that may change the page makes
+        // cached read-only results stale
+        let action = serde_json::from_str::<serde_json::Value>(json)
+            .ok()
+            .and_then(|command| command["action"].as_str().map(str::to_string))
+            .unwrap_or_default();
+        cache::invalidate(&self.session, &action);
+
+        // Read response
+        let mut line = String::new();
+        let read = self
+            .reader
+            .read_line(&mut line)
+            .map_err(|e| format!("Failed to read response: {}", e))?;
+        if read == 0 {
+            return Err("Daemon closed the connection".to_string());
+        }
+
+        // Parse response
+        let mut resp =
+            serde_json::from_str(&line).map_err(|e| format!("Failed to parse response: {}", e))?;
+        redact::response(&mut resp);
+        Ok(resp)
+    }
 }
@@ -0,0 +1,461 @@
+/*!
+ * Crawling
+ *
+ * `crawl <url>` walks a site breadth-first from a seed URL on the session's
+ * browser, visiting each page once and following the links it finds, and
+ * reports one line per page (NDJSON with `--json`).
+ *
+ * With `--state=<file>` the frontier and visited set are written to disk
+ * after every page, so an interrupted crawl picks up where it stopped with
+ * `crawl --resume <file>` instead of starting over from the seed. The file
+ * also keeps the crawl's options, so a resume behaves like the original run.
+ *
+ * `--seed-from-sitemap` starts the frontier with every page the site's
+ * sitemaps list (see `sitemap.rs`), waits the robots.txt `Crawl-delay`
+ * between pages unless `--delay` says otherwise, and skips pages robots.txt
+ * disallows with a `"skipped": "robots"` line.
+ *
+ * `--dedupe=url-normalized` treats URLs that differ only in tracking
+ * parameters (`utm_*`, `gclid`...), query order, host case or a default
+ * port as one page; `--dedupe=content` hashes each page's text and doesn't
+ * follow the links of a page whose text was already seen. Every decision
+ * shows up in the output: skipped URLs get a `"skipped": "duplicate"` line
+ * and content-deduped pages carry `contentHash` and `duplicateOf`.
+ *
+ * Every page goes through the policy and URL rules `navigate` is held to
+ * before it is opened; a refused one gets a `"skipped": "policy"` line and
+ * its links are never followed.
+ */
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fs;
+use std::thread;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::artifacts::sha256_hex;
+use crate::commands::CommandJson;
+use crate::connection::{ensure_daemon, send_command};
+use crate::flags::Flags;
+use crate::i18n::{t, tf};
+use crate::policy;
+use crate::sitemap::{self, Robots};
+
+/// Pages visited when `--max-pages` is not given
+pub const DEFAULT_MAX_PAGES: usize = 100;
+
+/// Link depth followed when `--max-depth` is not given
+pub const DEFAULT_MAX_DEPTH: usize = 3;
+
+/// Title and outgoing links of the loaded page
+const PAGE_SCRIPT: &str =
+    "({ title: document.title, links: Array.from(document.links, a => a.href) })";
+
+/// The same, plus the page's text for content dedupe
+const PAGE_TEXT_SCRIPT: &str = "({ title: document.title, \
+     links: Array.from(document.links, a => a.href), \
+     text: document.body ? document.body.innerText : '' })";
+
+/// Query parameters that track the visit rather than select content
+const TRACKING_PARAMS: &[&str] = &[
+    "gclid", "dclid", "fbclid", "msclkid", "yclid", "mc_cid", "mc_eid", "_ga", "_gl", "igshid",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Dedupe {
+    Content,
+    UrlNormalized,
+}
+
+impl Dedupe {
+    pub fn parse(mode: &str) -> Option<Dedupe> {
+        match mode {
+            "content" => Some(Dedupe::Content),
+            "url-normalized" => Some(Dedupe::UrlNormalized),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Options {
+    pub max_pages: usize,
+    pub max_depth: usize,
+    /// Follow links to other origins too
+    pub all_origins: bool,
+    /// Pause between pages
+    pub delay_ms: u64,
+    #[serde(default)]
+    pub dedupe: Option<Dedupe>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Pending {
+    url: String,
+    depth: usize,
+}
+
+/// Everything needed to carry on an interrupted crawl
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct State {
+    seed: String,
+    pub options: Options,
+    frontier: VecDeque<Pending>,
+    visited: Vec<String>,
+    crawled: usize,
+    /// Text hash of each page seen so far, to the first URL it came from
+    #[serde(default)]
+    content_hashes: HashMap<String, String>,
+    /// The site's robots.txt, when seeded from its sitemaps
+    #[serde(default)]
+    robots: Option<Robots>,
+}
+
+impl State {
+    pub fn new(seed: &str, options: Options) -> State {
+        let seed = normalize(seed);
+        State {
+            frontier: VecDeque::from([Pending {
+                url: seed.clone(),
+                depth: 0,
+            }]),
+            seed,
+            options,
+            visited: Vec::new(),
+            crawled: 0,
+            content_hashes: HashMap::new(),
+            robots: None,
+        }
+    }
+
+    /// Queue the pages listed in the site's sitemaps and adopt its
+    /// robots.txt Crawl-delay unless `keep_delay`
+    pub fn seed_from_sitemap(&mut self, keep_delay: bool) -> Value {
+        let site = origin(&canonical(&self.seed))
+            .unwrap_or(&self.seed)
+            .to_string();
+        let robots = sitemap::robots(&site);
+        let sitemaps = if robots.sitemaps.is_empty() {
+            vec![format!("{}/sitemap.xml", site)]
+        } else {
+            robots.sitemaps.clone()
+        };
+
+        let mut known: HashSet<String> = self.frontier.iter().map(|p| p.url.clone()).collect();
+        let mut seeded = 0;
+        for url in sitemap::urls(&sitemaps, self.options.max_pages) {
+            let url = normalize(&url);
+            let followable = match origin(&canonical(&url)) {
+                Some(o) => self.options.all_origins || o == site,
+                None => false,
+            };
+            if followable && known.insert(url.clone()) {
+                self.frontier.push_back(Pending { url, depth: 0 });
+                seeded += 1;
+            }
+        }
+
+        if let (false, Some(delay)) = (keep_delay, robots.crawl_delay) {
+            self.options.delay_ms = (delay * 1000.0) as u64;
+        }
+        let report = json!({
+            "seeded": seeded,
+            "sitemaps": sitemaps,
+            "crawlDelay": robots.crawl_delay,
+        });
+        self.robots = Some(robots);
+        report
+    }
+
+    /// Load a crawl saved with `--state`
+    pub fn load(path: &str) -> Result<State, String> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read crawl state {}: {}", path, e))?;
+        serde_json::from_str(&content).map_err(|e| format!("Corrupt crawl state {}: {}", path, e))
+    }
+
+    /// Write the state next to `path` and move it into place, so an
+    /// interrupt never leaves a half-written file
+    fn save(&self, path: &str) -> Result<(), String> {
+        let tmp = format!("{}.tmp", path);
+        let json = serde_json::to_string_pretty(self).unwrap_or_default();
+        fs::write(&tmp, json)
+            .and_then(|_| fs::rename(&tmp, path))
+            .map_err(|e| format!("Failed to save crawl state {}: {}", path, e))
+    }
+}
+
+/// Drop the fragment, which never changes what the server returns
+fn normalize(url: &str) -> String {
+    url.split('#').next().unwrap_or(url).to_string()
+}
+
+/// A URL without tracking parameters, with sorted query parameters, a
+/// lowercase scheme and host and no default port
+fn canonical(url: &str) -> String {
+    let (scheme, rest) = match url.split_once("://") {
+        Some(parts) => parts,
+        None => return url.to_string(),
+    };
+    let scheme = scheme.to_lowercase();
+    let (host, path) = rest.split_at(rest.find(['/', '?']).unwrap_or(rest.len()));
+    let mut host = host.to_lowercase();
+    let default_port = if scheme == "https" { ":443" } else { ":80" };
+    if host.ends_with(default_port) {
+        host.truncate(host.len() - default_port.len());
+    }
+    let (path, query) = path.split_once('?').unwrap_or((path, ""));
+    let path = if path.is_empty() { "/" } else { path };
+
+    let mut params: Vec<&str> = query
+        .split('&')
+        .filter(|p| !p.is_empty())
+        .filter(|p| {
+            let name = p.split('=').next().unwrap_or("").to_lowercase();
+            !name.starts_with("utm_") && !TRACKING_PARAMS.contains(&name.as_str())
+        })
+        .collect();
+    params.sort_unstable();
+    if params.is_empty() {
+        format!("{}://{}{}", scheme, host, path)
+    } else {
+        format!("{}://{}{}?{}", scheme, host, path, params.join("&"))
+    }
+}
+
+/// `scheme://host[:port]` of an http(s) URL
+fn origin(url: &str) -> Option<&str> {
+    let rest = url
+        .strip_prefix("https://")
+        .or_else(|| url.strip_prefix("http://"))?;
+    let end = url.len() - rest.len() + rest.find(['/', '?']).unwrap_or(rest.len());
+    Some(&url[..end])
+}
+
+/// Run a command and return its result, or the daemon's error
+fn call(cmd: &CommandJson, session: &str) -> Result<Value, String> {
+    let resp = send_command(cmd, session)?;
+    if resp.success {
+        Ok(resp.result.unwrap_or(Value::Null))
+    } else {
+        Err(resp
+            .error
+            .unwrap_or_else(|| format!("{} failed", cmd.action)))
+    }
+}
+
+/// Load one page and read what the crawl needs from it
+fn visit(url: &str, with_text: bool, flags: &Flags) -> Result<(Value, Value), String> {
+    let mut navigate = CommandJson::new("navigate");
+    navigate.url = Some(url.to_string());
+    navigate.timeout = flags.timeout.or(flags.default_timeout);
+    let loaded = call(&navigate, &flags.session)?;
+
+    let mut read = CommandJson::new("evaluate");
+    read.script = Some(
+        if with_text {
+            PAGE_TEXT_SCRIPT
+        } else {
+            PAGE_SCRIPT
+        }
+        .to_string(),
+    );
+    let page = call(&read, &flags.session)?;
+    Ok((loaded, page.get("result").cloned().unwrap_or(page)))
+}
+
+/// Crawl until the frontier is empty or `max_pages` is reached, saving the
+/// state after every page when `state_path` is given
+pub fn run(state: &mut State, state_path: Option<&str>, flags: &Flags) -> Result<Value, String> {
+    ensure_daemon(
+        &flags.session,
+        flags.headed,
+        flags.executable_path.as_deref(),
+        &flags.extensions,
+    )?;
+
+    let seed_origin = origin(&canonical(&state.seed)).map(String::from);
+    let dedupe = state.options.dedupe;
+    let key = |url: &str| match dedupe {
+        Some(Dedupe::UrlNormalized) => canonical(url),
+        _ => url.to_string(),
+    };
+    // Dedupe key of every URL visited or queued, to the first URL with it
+    let mut seen: HashMap<String, String> = HashMap::new();
+    for url in state
+        .visited
+        .iter()
+        .chain(state.frontier.iter().map(|p| &p.url))
+    {
+        seen.entry(key(url)).or_insert_with(|| url.clone());
+    }
+    let mut reported: HashSet<String> = HashSet::new();
+
+    while state.crawled < state.options.max_pages {
+        let Pending { url, depth } = match state.frontier.pop_front() {
+            Some(next) => next,
+            None => break,
+        };
+        if let Err(violation) = policy::check_navigation(&url, flags) {
+            let line = json!({
+                "url": url,
+                "depth": depth,
+                "skipped": "policy",
+                "error": violation.message(),
+            });
+            print_page(&line, flags.json);
+            continue;
+        }
+        if state.robots.as_ref().is_some_and(|r| !r.allows(&url)) {
+            let line = json!({"url": url, "depth": depth, "skipped": "robots"});
+            print_page(&line, flags.json);
+            continue;
+        }
+        if state.crawled > 0 && state.options.delay_ms > 0 {
+            thread::sleep(Duration::from_millis(state.options.delay_ms));
+        }
+
+        let mut line = json!({"url": url, "depth": depth});
+        match visit(&url, dedupe == Some(Dedupe::Content), flags) {
+            Ok((loaded, page)) => {
+                let mut follow = depth < state.options.max_depth;
+                if dedupe == Some(Dedupe::Content) {
+                    let text = page["text"].as_str().unwrap_or("");
+                    let words: Vec<&str> = text.split_whitespace().collect();
+                    let hash = sha256_hex(words.join(" ").as_bytes());
+                    match state.content_hashes.get(&hash) {
+                        Some(first) => {
+                            line["duplicateOf"] = json!(first);
+                            line["skipped"] = json!("duplicate");
+                            follow = false;
+                        }
+                        None => {
+                            state.content_hashes.insert(hash.clone(), url.clone());
+                        }
+                    }
+                    line["contentHash"] = json!(hash);
+                }
+
+                let links: Vec<String> = page["links"]
+                    .as_array()
+                    .map(|links| {
+                        links
+                            .iter()
+                            .filter_map(|l| l.as_str())
+                            .map(normalize)
+                            .collect()
+                    })
+                    .unwrap_or_default();
+                let mut queued = 0;
+                let mut skipped = Vec::new();
+                if follow {
+                    for link in &links {
+                        let followable = match origin(&canonical(link)) {
+                            Some(o) => {
+                                state.options.all_origins || Some(o) == seed_origin.as_deref()
+                            }
+                            None => false,
+                        };
+                        if !followable {
+                            continue;
+                        }
+                        match seen.entry(key(link)) {
+                            Entry::Vacant(entry) => {
+                                entry.insert(link.clone());
+                                state.frontier.push_back(Pending {
+                                    url: link.clone(),
+                                    depth: depth + 1,
+                                });
+                                queued += 1;
+                            }
+                            // Only a different spelling of a known URL is news
+                            Entry::Occupied(entry)
+                                if entry.get() != link && reported.insert(link.clone()) =>
+                            {
+                                skipped.push(json!({
+                                    "url": link,
+                                    "skipped": "duplicate",
+                                    "dedupe": "url-normalized",
+                                    "duplicateOf": entry.get(),
+                                    "from": url,
+                                }));
+                            }
+                            Entry::Occupied(_) => {}
+                        }
+                    }
+                }
+                line["finalUrl"] = loaded["url"].clone();
+                line["status"] = loaded["status"].clone();
+                line["title"] = page["title"].clone();
+                line["links"] = json!(links.len());
+                line["queued"] = json!(queued);
+                line["skippedLinks"] = json!(skipped);
+            }
+            Err(e) => line["error"] = json!(e),
+        }
+
+        state.visited.push(url);
+        state.crawled += 1;
+        if let Some(path) = state_path {
+            state.save(path)?;
+        }
+        let skipped = match line.as_object_mut().and_then(|l| l.remove("skippedLinks")) {
+            Some(Value::Array(skipped)) => skipped,
+            _ => Vec::new(),
+        };
+        print_page(&line, flags.json);
+        for skip in &skipped {
+            print_page(skip, flags.json);
+        }
+    }
+
+    Ok(json!({
+        "done": state.frontier.is_empty(),
+        "crawled": state.crawled,
+        "queued": state.frontier.len(),
+        "state": state_path,
+    }))
+}
+
+fn print_page(line: &Value, json: bool) {
+    if json {
+        println!("{}", line);
+        return;
+    }
+    let url = line["url"].as_str().unwrap_or("");
+    if let Some(first) = line["duplicateOf"].as_str() {
+        println!(
+            "\x1b[90m= [{}] {}\x1b[0m",
+            if line["depth"].is_null() {
+                "link".to_string()
+            } else {
+                line["depth"].to_string()
+            },
+            tf("crawl_duplicate", &[url, first])
+        );
+        return;
+    }
+    if line["skipped"] == "robots" {
+        println!(
+            "\x1b[90m- [{}] {} {}\x1b[0m",
+            line["depth"],
+            url,
+            t("crawl_robots")
+        );
+        return;
+    }
+    match line["error"].as_str() {
+        Some(error) => println!("\x1b[31m✗\x1b[0m {} \x1b[90m{}\x1b[0m", url, error),
+        None => println!(
+            "\x1b[32m✓\x1b[0m \x1b[90m[{}]\x1b[0m {} {}",
+            line["depth"],
+            url,
+            line["title"].as_str().unwrap_or("")
+        ),
+    }
+}
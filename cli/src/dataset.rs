@@ -0,0 +1,220 @@
+/*!
+ * Data-Driven Runs
+ *
+ * `run flow.txt --data=users.csv` runs a script once per row of a CSV file
+ * (first line is the header) or a JSON array of objects, with each column
+ * bound as a script variable:
+ *
+ * ```text
+ * navigate https://example.com/login
+ * fill #email $email
+ * fill #password ${password}
+ * click button[type=submit]
+ * ```
+ *
+ * `--parallel=N` spreads the rows over N sessions (`<session>-data-0` ...),
+ * each taking the next row as soon as it finishes one.
+ */
+use std::fs;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Instant;
+
+use serde_json::{json, Value};
+
+use crate::flags::Flags;
+use crate::script::{Runner, Script, Vars};
+
+/// Load the rows of a `.csv` or `.json` data file
+pub fn load(path: &str) -> Result<Vec<Vars>, String> {
+    let content =
+        fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    let is_json = Path::new(path)
+        .extension()
+        .map(|e| e.eq_ignore_ascii_case("json"))
+        .unwrap_or(false);
+    let rows = if is_json {
+        json_rows(&content)
+    } else {
+        csv_rows(&content)
+    }
+    .map_err(|e| format!("{}: {}", path, e))?;
+    if rows.is_empty() {
+        return Err(format!("{}: no rows", path));
+    }
+    Ok(rows)
+}
+
+/// A JSON array of objects; non-string values are bound as their JSON text
+fn json_rows(content: &str) -> Result<Vec<Vars>, String> {
+    let value: Value = serde_json::from_str(content).map_err(|e| e.to_string())?;
+    let rows = value
+        .as_array()
+        .ok_or_else(|| "expected an array of objects".to_string())?;
+    rows.iter()
+        .enumerate()
+        .map(|(i, row)| {
+            let row = row
+                .as_object()
+                .ok_or_else(|| format!("row {} is not an object", i + 1))?;
+            Ok(row
+                .iter()
+                .map(|(k, v)| {
+                    let v = match v {
+                        Value::String(s) => s.clone(),
+                        Value::Null => String::new(),
+                        other => other.to_string(),
+                    };
+                    (k.clone(), v)
+                })
+                .collect())
+        })
+        .collect()
+}
+
+/// A CSV file with a header line. Fields may be double-quoted, with `""`
+/// for a literal quote; quoted fields may span lines.
+fn csv_rows(content: &str) -> Result<Vec<Vars>, String> {
+    let mut records = csv_records(content)?.into_iter();
+    let header = match records.next() {
+        Some(header) => header,
+        None => return Ok(Vec::new()),
+    };
+    records
+        .enumerate()
+        .filter(|(_, record)| !(record.len() == 1 && record[0].is_empty()))
+        .map(|(i, record)| {
+            if record.len() != header.len() {
+                return Err(format!(
+                    "row {} has {} fields, header has {}",
+                    i + 1,
+                    record.len(),
+                    header.len()
+                ));
+            }
+            Ok(header.iter().cloned().zip(record).collect())
+        })
+        .collect()
+}
+
+fn csv_records(content: &str) -> Result<Vec<Vec<String>>, String> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = content.trim_start_matches('\u{feff}').chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' if quoted => quoted = false,
+            '"' if field.is_empty() => quoted = true,
+            ',' if !quoted => record.push(std::mem::take(&mut field)),
+            '\r' if !quoted => {}
+            '\n' if !quoted => {
+                record.push(std::mem::take(&mut field));
+                records.push(std::mem::take(&mut record));
+            }
+            c => field.push(c),
+        }
+    }
+    if quoted {
+        return Err("unterminated quoted field".to_string());
+    }
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        records.push(record);
+    }
+    Ok(records)
+}
+
+/// Run `script` once per row, `parallel` rows at a time. Prints a line per
+/// row as it finishes unless `flags.json`, and returns the combined report.
+pub fn run(script: &Script, rows: Vec<Vars>, parallel: usize, flags: &Flags) -> Value {
+    let started = Instant::now();
+    let total = rows.len();
+    let workers = parallel.clamp(1, total);
+    let next = AtomicUsize::new(0);
+    let results = Mutex::new(vec![Value::Null; total]);
+
+    thread::scope(|scope| {
+        for worker in 0..workers {
+            let session = if workers == 1 {
+                flags.session.clone()
+            } else {
+                format!("{}-data-{}", flags.session, worker)
+            };
+            let (rows, next, results) = (&rows, &next, &results);
+            scope.spawn(move || loop {
+                let i = next.fetch_add(1, Ordering::SeqCst);
+                let row = match rows.get(i) {
+                    Some(row) => row,
+                    None => break,
+                };
+                let report = Runner {
+                    session: &session,
+                    verbose: false,
+                }
+                .run_with(script, flags, row.clone());
+                if !flags.json {
+                    print_row(i + 1, total, &report);
+                }
+                let mut entry = json!({"row": i + 1, "data": row});
+                for key in ["success", "session", "error", "steps", "vars", "durationMs"] {
+                    if !report[key].is_null() {
+                        entry[key] = report[key].clone();
+                    }
+                }
+                results.lock().unwrap()[i] = entry;
+            });
+        }
+    });
+
+    let rows = results.into_inner().unwrap_or_default();
+    let passed = rows.iter().filter(|r| r["success"] == true).count();
+    json!({
+        "success": passed == total,
+        "passed": passed,
+        "failed": total - passed,
+        "rows": rows,
+        "durationMs": started.elapsed().as_millis() as u64,
+    })
+}
+
+fn print_row(row: usize, total: usize, report: &Value) {
+    let ms = &report["durationMs"];
+    if report["success"] == true {
+        println!(
+            "\x1b[32m✓\x1b[0m row {}/{} \x1b[90m({}ms)\x1b[0m",
+            row, total, ms
+        );
+        return;
+    }
+    println!(
+        "\x1b[31m✗\x1b[0m row {}/{} \x1b[90m({}ms)\x1b[0m",
+        row, total, ms
+    );
+    let failed = report["steps"].as_array().and_then(|steps| {
+        steps
+            .iter()
+            .rev()
+            .find(|s| s["response"]["success"] != true)
+    });
+    if let Some(step) = failed {
+        println!(
+            "      line {}: {}",
+            step["line"],
+            step["command"].as_str().unwrap_or("")
+        );
+        if let Some(error) = step["response"]["error"].as_str() {
+            println!("      {}", error);
+        }
+    } else if let Some(error) = report["error"].as_str() {
+        println!("      {}", error);
+    }
+}
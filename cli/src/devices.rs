@@ -0,0 +1,159 @@
+/*!
+ * Device Presets
+ *
+ * `device <name>` emulates a phone, tablet or desktop from this table:
+ * viewport, pixel ratio, user agent, mobile layout and touch. The table is
+ * built into the CLI so every daemon emulates a preset the same way, and
+ * `devices` lists it. Names match loosely, so `iphone-14` finds `iPhone 14`.
+ */
+use serde_json::{json, Value};
+
+pub struct Device {
+    pub name: &'static str,
+    pub width: u32,
+    pub height: u32,
+    pub scale: f64,
+    pub mobile: bool,
+    pub touch: bool,
+    pub user_agent: &'static str,
+}
+
+const IOS_16: &str = "Mozilla/5.0 (iPhone; CPU iPhone OS 16_0 like Mac OS X) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/16.0 Mobile/15E148 Safari/604.1";
+const IOS_17: &str = "Mozilla/5.0 (iPhone; CPU iPhone OS 17_0 like Mac OS X) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/17.0 Mobile/15E148 Safari/604.1";
+const IPADOS_17: &str = "Mozilla/5.0 (iPad; CPU OS 17_0 like Mac OS X) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/17.0 Mobile/15E148 Safari/604.1";
+const DESKTOP: &str = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/124.0.0.0 Safari/537.36";
+
+const fn mobile(
+    name: &'static str,
+    width: u32,
+    height: u32,
+    scale: f64,
+    user_agent: &'static str,
+) -> Device {
+    Device {
+        name,
+        width,
+        height,
+        scale,
+        mobile: true,
+        touch: true,
+        user_agent,
+    }
+}
+
+pub const DEVICES: &[Device] = &[
+    mobile("iPhone SE", 375, 667, 2.0, IOS_16),
+    mobile("iPhone 12", 390, 844, 3.0, IOS_16),
+    mobile("iPhone 13", 390, 844, 3.0, IOS_16),
+    mobile("iPhone 14", 390, 844, 3.0, IOS_16),
+    mobile("iPhone 14 Pro Max", 430, 932, 3.0, IOS_16),
+    mobile("iPhone 15", 393, 852, 3.0, IOS_17),
+    mobile("iPhone 15 Pro Max", 430, 932, 3.0, IOS_17),
+    mobile("iPad Mini", 768, 1024, 2.0, IPADOS_17),
+    mobile("iPad Air", 820, 1180, 2.0, IPADOS_17),
+    mobile("iPad Pro 11", 834, 1194, 2.0, IPADOS_17),
+    mobile(
+        "Pixel 5",
+        393,
+        851,
+        2.75,
+        "Mozilla/5.0 (Linux; Android 13; Pixel 5) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/124.0.0.0 Mobile Safari/537.36",
+    ),
+    mobile(
+        "Pixel 7",
+        412,
+        915,
+        2.625,
+        "Mozilla/5.0 (Linux; Android 14; Pixel 7) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/124.0.0.0 Mobile Safari/537.36",
+    ),
+    mobile(
+        "Pixel 8 Pro",
+        448,
+        998,
+        2.25,
+        "Mozilla/5.0 (Linux; Android 14; Pixel 8 Pro) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/124.0.0.0 Mobile Safari/537.36",
+    ),
+    mobile(
+        "Galaxy S9+",
+        320,
+        658,
+        4.5,
+        "Mozilla/5.0 (Linux; Android 10; SM-G965U) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/124.0.0.0 Mobile Safari/537.36",
+    ),
+    mobile(
+        "Galaxy S23",
+        360,
+        780,
+        3.0,
+        "Mozilla/5.0 (Linux; Android 14; SM-S911B) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/124.0.0.0 Mobile Safari/537.36",
+    ),
+    // Android tablets get the desktop layout: no "Mobile" in the user agent
+    Device {
+        name: "Galaxy Tab S4",
+        width: 712,
+        height: 1138,
+        scale: 2.25,
+        mobile: false,
+        touch: true,
+        user_agent: "Mozilla/5.0 (Linux; Android 10; SM-T837A) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/124.0.0.0 Safari/537.36",
+    },
+    Device {
+        name: "Desktop",
+        width: 1280,
+        height: 720,
+        scale: 1.0,
+        mobile: false,
+        touch: false,
+        user_agent: DESKTOP,
+    },
+    Device {
+        name: "Desktop HiDPI",
+        width: 1280,
+        height: 720,
+        scale: 2.0,
+        mobile: false,
+        touch: false,
+        user_agent: DESKTOP,
+    },
+    Device {
+        name: "Desktop 1080p",
+        width: 1920,
+        height: 1080,
+        scale: 1.0,
+        mobile: false,
+        touch: false,
+        user_agent: DESKTOP,
+    },
+];
+
+/// Lowercase with spaces, hyphens and underscores dropped
+fn loose(name: &str) -> String {
+    name.chars()
+        .filter(|c| !matches!(c, ' ' | '-' | '_'))
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// The preset called `name`, matched loosely
+pub fn find(name: &str) -> Option<&'static Device> {
+    let wanted = loose(name);
+    DEVICES.iter().find(|device| loose(device.name) == wanted)
+}
+
+/// Every preset, as `devices` reports them
+pub fn list() -> Value {
+    let devices: Vec<Value> = DEVICES
+        .iter()
+        .map(|device| {
+            json!({
+                "name": device.name,
+                "viewport": {"width": device.width, "height": device.height},
+                "deviceScaleFactor": device.scale,
+                "isMobile": device.mobile,
+                "hasTouch": device.touch,
+                "userAgent": device.user_agent,
+            })
+        })
+        .collect();
+    json!({ "devices": devices })
+}
@@ -0,0 +1,259 @@
+/*!
+ * Browser Extensions
+ *
+ * `--extensions=<dir,dir>` (or AGENT_BROWSER_EXTENSIONS) loads unpacked
+ * Chromium extensions when a session's daemon starts. The headless shell
+ * ignores extensions, so they need `--headed`.
+ *
+ * `extension install` adds to a per-session list that is loaded on top of
+ * `--extensions`; Chromium can't load extensions into a running browser, so
+ * installing or removing one restarts the session's daemon with its state.
+ */
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use serde_json::{json, Value};
+
+use crate::commands::CommandJson;
+use crate::config::data_dir;
+use crate::connection::{daemon_pid, send_command, Response};
+use crate::flags::Flags;
+use crate::recycle;
+
+/// Check that every path is an unpacked extension and make it absolute,
+/// since the daemon does not share the CLI's working directory
+pub fn validate(paths: &[String]) -> Result<Vec<String>, String> {
+    paths
+        .iter()
+        .filter(|p| !p.is_empty())
+        .map(|p| {
+            let dir = fs::canonicalize(p)
+                .map_err(|_| format!("Extension directory '{}' does not exist", p))?;
+            if !dir.is_dir() {
+                return Err(format!("Extension path '{}' is not a directory", p));
+            }
+            if !dir.join("manifest.json").is_file() {
+                return Err(format!("Extension directory '{}' has no manifest.json", p));
+            }
+            Ok(dir.to_string_lossy().into_owned())
+        })
+        .collect()
+}
+
+/// Name and version from an extension's manifest
+pub fn manifest(dir: &str) -> Option<Value> {
+    let content = fs::read_to_string(Path::new(dir).join("manifest.json")).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+/// Expand the daemon's `{paths, ids}` into one entry per extension with
+/// its manifest name and version
+pub fn describe(resp: &mut Response) {
+    let result = match resp.result.as_mut().and_then(|r| r.as_object_mut()) {
+        Some(result) => result,
+        None => return,
+    };
+    let loaded = match result.remove("extensions") {
+        Some(loaded) => loaded,
+        None => return,
+    };
+
+    let extensions: Vec<Value> = loaded["paths"]
+        .as_array()
+        .map(|paths| {
+            paths
+                .iter()
+                .filter_map(|p| p.as_str())
+                .map(|path| {
+                    let manifest = manifest(path).unwrap_or(Value::Null);
+                    json!({
+                        "path": path,
+                        "name": manifest["name"],
+                        "version": manifest["version"],
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    result.insert("extensions".to_string(), Value::Array(extensions));
+    result.insert("ids".to_string(), loaded["ids"].clone());
+}
+
+/// Get the installed-extensions list path for a session
+fn get_installed_path(session: &str) -> String {
+    env::temp_dir()
+        .join(format!("agentbrowser-pro-{}.extensions.json", session))
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// Extensions installed into a session with `extension install`
+pub fn installed(session: &str) -> Vec<String> {
+    fs::read_to_string(get_installed_path(session))
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn save_installed(session: &str, paths: &[String]) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(paths).unwrap_or_default();
+    fs::write(get_installed_path(session), json)
+        .map_err(|e| format!("Failed to save installed extensions: {}", e))
+}
+
+/// Whether `arg` looks like a Chrome Web Store extension ID
+fn is_store_id(arg: &str) -> bool {
+    arg.len() == 32 && arg.chars().all(|c| ('a'..='p').contains(&c))
+}
+
+/// Download and unpack a Chrome Web Store extension, returning its directory
+fn download(id: &str) -> Result<String, String> {
+    let dir = data_dir()
+        .ok_or_else(|| "Cannot locate home directory for extension storage".to_string())?
+        .join("extensions")
+        .join(id);
+    if dir.join("manifest.json").is_file() {
+        return Ok(dir.to_string_lossy().into_owned());
+    }
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+
+    let crx = dir.with_extension("crx");
+    let url = format!(
+        "https://clients2.google.com/service/update2/crx?response=redirect\
+         &prodversion=120.0&acceptformat=crx2,crx3&x=id%3D{}%26uc",
+        id
+    );
+    let status = Command::new("curl")
+        .args(["-fsSL", "-o"])
+        .arg(&crx)
+        .arg(&url)
+        .status()
+        .map_err(|e| format!("Failed to run curl: {}", e))?;
+    if !status.success() {
+        return Err(format!(
+            "Failed to download extension {} from the Chrome Web Store",
+            id
+        ));
+    }
+
+    // A CRX is a zip with a signed header in front; unzip skips the header
+    // and exits with 1 to warn about it
+    let status = Command::new("unzip")
+        .args(["-qo"])
+        .arg(&crx)
+        .arg("-d")
+        .arg(&dir)
+        .status()
+        .map_err(|e| format!("Failed to run unzip: {}", e))?;
+    fs::remove_file(&crx).ok();
+    if status.code().map(|c| c > 1).unwrap_or(true) || !dir.join("manifest.json").is_file() {
+        return Err(format!("Failed to unpack extension {}", id));
+    }
+    Ok(dir.to_string_lossy().into_owned())
+}
+
+/// Restart the daemon so the browser picks up a changed extension list
+fn reload(flags: &Flags) -> Result<bool, String> {
+    if daemon_pid(&flags.session).is_none() {
+        return Ok(false);
+    }
+    recycle::restart(flags).map(|_| true)
+}
+
+/// Install an unpacked extension directory or Web Store ID into a session
+pub fn install(target: &str, flags: &Flags) -> Result<Value, String> {
+    let path = if is_store_id(target) && !Path::new(target).exists() {
+        download(target)?
+    } else {
+        validate(&[target.to_string()])?.remove(0)
+    };
+
+    let mut paths = installed(&flags.session);
+    if !paths.contains(&path) {
+        paths.push(path.clone());
+        save_installed(&flags.session, &paths)?;
+    }
+
+    let mut flags_with = flags.clone();
+    if !flags_with.extensions.contains(&path) {
+        flags_with.extensions.push(path.clone());
+    }
+    let restarted = reload(&flags_with)?;
+    let manifest = manifest(&path).unwrap_or(Value::Null);
+    Ok(json!({
+        "installed": path,
+        "name": manifest["name"],
+        "version": manifest["version"],
+        "restarted": restarted,
+    }))
+}
+
+/// Remove an installed extension, matched by path, directory name
+/// (the Web Store ID for downloaded ones) or manifest name
+pub fn remove(target: &str, flags: &Flags) -> Result<Value, String> {
+    let mut paths = installed(&flags.session);
+    let matches = |path: &String| {
+        let dir = PathBuf::from(path);
+        path == target
+            || dir.file_name().map(|n| n == target).unwrap_or(false)
+            || manifest(path)
+                .and_then(|m| m["name"].as_str().map(|n| n.eq_ignore_ascii_case(target)))
+                .unwrap_or(false)
+    };
+    let index = match paths.iter().position(matches) {
+        Some(index) => index,
+        None if flags.extensions.iter().any(matches) => {
+            return Err(format!(
+                "'{}' was loaded with --extensions; drop it from that flag instead",
+                target
+            ))
+        }
+        None => return Err(format!("No installed extension matches '{}'", target)),
+    };
+    let path = paths.remove(index);
+    save_installed(&flags.session, &paths)?;
+
+    let mut flags_without = flags.clone();
+    flags_without.extensions.retain(|p| *p != path);
+    let restarted = reload(&flags_without)?;
+    Ok(json!({ "removed": path, "restarted": restarted }))
+}
+
+/// Open an extension's popup as a page. The manifest is read from the
+/// extension's own origin, so this works with any ID from `extensions list`.
+pub fn popup(id: &str, session: &str) -> Result<Value, String> {
+    let mut open = CommandJson::new("newPage");
+    open.url = Some(format!("chrome-extension://{}/manifest.json", id));
+    check(send_command(&open, session)?)?;
+
+    let mut show = CommandJson::new("evaluate");
+    show.script = Some(
+        "(() => { const m = JSON.parse(document.body.innerText); \
+         const a = m.action || m.browser_action || m.page_action || {}; \
+         if (!a.default_popup) throw new Error('Extension has no popup'); \
+         location.href = a.default_popup; return a.default_popup; })()"
+            .to_string(),
+    );
+    let popup = check(send_command(&show, session)?)?;
+
+    let mut wait = CommandJson::new("waitForLoadState");
+    wait.state = Some("load".to_string());
+    check(send_command(&wait, session)?)?;
+
+    Ok(json!({
+        "url": format!("chrome-extension://{}/{}", id, popup["result"].as_str().unwrap_or("")),
+    }))
+}
+
+fn check(resp: Response) -> Result<Value, String> {
+    if resp.success {
+        Ok(resp.result.unwrap_or(Value::Null))
+    } else {
+        Err(resp
+            .error
+            .unwrap_or_else(|| "Extension command failed".to_string()))
+    }
+}
@@ -1,7 +1,146 @@
-/**
+/*!
  * CLI Flag Parsing
  */
+use crate::registry::{commands, find_command, GLOBAL_FLAGS};
 
+/// Value flags that may also be given bare, like `--pool` for the default
+/// pool
+const OPTIONAL_VALUE_FLAGS: &[&str] = &["--pool"];
+
+/// Whether `--name` takes a value (`--session=name` or `--session name`):
+/// any value flag in the registry, global or a command's
+fn takes_value(name: &str) -> bool {
+    GLOBAL_FLAGS
+        .iter()
+        .chain(commands().flat_map(|c| c.flags.iter()))
+        .any(|f| f.name == name && f.value.is_some())
+}
+
+/// Whether an optional-value flag was given bare, being followed by another
+/// flag or by the command itself
+fn given_bare(flag: &str, next: &str) -> bool {
+    OPTIONAL_VALUE_FLAGS.contains(&flag) && (next.starts_with("--") || find_command(next).is_some())
+}
+
+/// Rewrite short, bundled and space-separated flags into canonical
+/// `--name` / `--name=value` form so the rest of the CLI only has to
+/// understand one spelling.
+pub fn normalize_args(args: &[String]) -> Vec<String> {
+    let mut out = Vec::with_capacity(args.len());
+    let mut iter = args.iter().peekable();
+
+    while let Some(arg) = iter.next() {
+        // Everything after `--` is positional and left untouched
+        if arg == "--" {
+            out.push(arg.clone());
+            out.extend(iter.cloned());
+            break;
+        }
+
+        // --session name, or a bare `--pool` before the command or a flag
+        if takes_value(arg) {
+            match iter.next_if(|next| !given_bare(arg, next)) {
+                Some(value) => out.push(format!("{}={}", arg, value)),
+                None => out.push(arg.clone()),
+            }
+            continue;
+        }
+
+        // -s name, -sname, -s=name, -jt 5000
+        if let Some(shorts) = arg.strip_prefix('-') {
+            if !shorts.is_empty() && !shorts.starts_with('-') {
+                if let Some(expanded) = expand_short(shorts, &mut iter) {
+                    out.extend(expanded);
+                    continue;
+                }
+            }
+        }
+
+        out.push(arg.clone());
+    }
+
+    out
+}
+
+/// Expand a bundle of short flags, consuming a following value if the last
+/// flag takes one. Returns None if the bundle contains an unknown letter.
+fn expand_short<'a>(
+    shorts: &str,
+    rest: &mut impl Iterator<Item = &'a String>,
+) -> Option<Vec<String>> {
+    let mut out = Vec::new();
+    for (i, c) in shorts.char_indices() {
+        let long = GLOBAL_FLAGS.iter().find(|f| f.short == Some(c))?.name;
+
+        if takes_value(long) {
+            let inline = &shorts[i + c.len_utf8()..];
+            let value = if let Some(v) = inline.strip_prefix('=') {
+                v.to_string()
+            } else if !inline.is_empty() {
+                inline.to_string()
+            } else {
+                match rest.next() {
+                    Some(v) => v.clone(),
+                    None => return Some(vec![long.to_string()]),
+                }
+            };
+            out.push(format!("{}={}", long, value));
+            return Some(out);
+        }
+
+        out.push(long.to_string());
+    }
+
+    Some(out)
+}
+
+/// Split normalized arguments into flags and positional arguments.
+///
+/// Only `--name[=value]` tokens count as flags; a lone `-`, negative numbers
+/// and other dash-prefixed text stay positional. A bare `--` ends option
+/// parsing and is dropped.
+pub fn split_args(args: &[String]) -> (Vec<String>, Vec<String>) {
+    let mut flags = Vec::new();
+    let mut positionals = Vec::new();
+    let mut iter = args.iter();
+
+    while let Some(arg) = iter.next() {
+        if arg == "--" {
+            positionals.extend(iter.cloned());
+            break;
+        }
+        if is_flag(arg) {
+            flags.push(arg.clone());
+        } else {
+            positionals.push(arg.clone());
+        }
+    }
+
+    (flags, positionals)
+}
+
+/// Index of the first positional argument in normalized arguments
+pub fn first_positional(args: &[String]) -> Option<usize> {
+    for (i, arg) in args.iter().enumerate() {
+        if arg == "--" {
+            return if i + 1 < args.len() {
+                Some(i + 1)
+            } else {
+                None
+            };
+        }
+        if !is_flag(arg) {
+            return Some(i);
+        }
+    }
+    None
+}
+
+fn is_flag(arg: &str) -> bool {
+    arg.len() > 2 && arg.starts_with("--")
+}
+
+#[derive(Clone)]
 pub struct Flags {
     pub json: bool,
     pub session: String,
@@ -9,6 +148,44 @@ pub struct Flags {
     pub executable_path: Option<String>,
     pub extensions: Vec<String>,
     pub timeout: Option<u64>,
+    /// Fallback timeout for commands run without `--timeout`
+    pub default_timeout: Option<u64>,
+    pub dry_run: bool,
+    /// Start the built-in Rust daemon instead of the Node.js one
+    pub native_daemon: bool,
+    /// Wait step run before the command (ms, load state or selector)
+    pub wait_before: Option<String>,
+    /// Wait step run after the command (ms, load state or selector)
+    pub wait_after: Option<String>,
+    /// Re-resolve stale `@eN` refs against a fresh snapshot and retry
+    pub auto_resolve_refs: bool,
+    /// Fail the command if the page raises an uncaught error while it runs
+    pub fail_on_page_error: bool,
+    /// Write the primary result payload here instead of stdout
+    pub output_file: Option<String>,
+    /// Restart the daemon after n commands, a duration or an RSS limit
+    pub recycle_after: Option<String>,
+    /// Directory that collects the run's output files and their manifest
+    pub run_dir: Option<String>,
+    /// Where `download` saves files (default: the run directory, or the
+    /// current one)
+    pub downloads_dir: Option<String>,
+    /// Route the command to a free member of this session pool
+    pub pool: Option<String>,
+    /// Language for help and messages, overriding the locale
+    pub lang: Option<String>,
+    /// Read JSON command objects from stdin instead of taking a command
+    pub stdin: bool,
+    /// Refuse commands that could change the page or site
+    pub read_only: bool,
+    /// URL patterns navigation must stay within (empty allows any)
+    pub allow_urls: Vec<String>,
+    /// URL patterns navigation may never reach
+    pub deny_urls: Vec<String>,
+    /// Mask personal data in results: a regex, or `builtin:email,cc,ssn`
+    pub redact: Option<String>,
+    /// Command-specific flags (e.g. `--full-page`) left for the parser
+    pub extra: Vec<String>,
 }
 
 impl Flags {
@@ -20,6 +197,25 @@ impl Flags {
             executable_path: None,
             extensions: Vec::new(),
             timeout: None,
+            default_timeout: None,
+            dry_run: false,
+            native_daemon: false,
+            wait_before: None,
+            wait_after: None,
+            auto_resolve_refs: false,
+            fail_on_page_error: false,
+            output_file: None,
+            recycle_after: None,
+            run_dir: None,
+            downloads_dir: None,
+            pool: None,
+            lang: None,
+            stdin: false,
+            read_only: false,
+            allow_urls: Vec::new(),
+            deny_urls: Vec::new(),
+            redact: None,
+            extra: Vec::new(),
         };
 
         for arg in args {
@@ -27,6 +223,22 @@ impl Flags {
                 flags.json = true;
             } else if arg == "--headed" {
                 flags.headed = true;
+            } else if arg == "--dry-run" {
+                flags.dry_run = true;
+            } else if arg == "--native-daemon" {
+                flags.native_daemon = true;
+            } else if arg == "--auto-resolve-refs" {
+                flags.auto_resolve_refs = true;
+            } else if arg == "--fail-on-page-error" {
+                flags.fail_on_page_error = true;
+            } else if arg == "--stdin" {
+                flags.stdin = true;
+            } else if arg == "--read-only" {
+                flags.read_only = true;
+            } else if arg == "--pool" {
+                flags.pool = Some("default".to_string());
+            } else if let Some(value) = arg.strip_prefix("--pool=") {
+                flags.pool = Some(value.to_string());
             } else if let Some(value) = arg.strip_prefix("--session=") {
                 flags.session = value.to_string();
             } else if let Some(value) = arg.strip_prefix("--executable-path=") {
@@ -35,6 +247,28 @@ impl Flags {
                 flags.extensions = value.split(',').map(|s| s.trim().to_string()).collect();
             } else if let Some(value) = arg.strip_prefix("--timeout=") {
                 flags.timeout = value.parse().ok();
+            } else if let Some(value) = arg.strip_prefix("--wait-before=") {
+                flags.wait_before = Some(value.to_string());
+            } else if let Some(value) = arg.strip_prefix("--wait-after=") {
+                flags.wait_after = Some(value.to_string());
+            } else if let Some(value) = arg.strip_prefix("--output-file=") {
+                flags.output_file = Some(value.to_string());
+            } else if let Some(value) = arg.strip_prefix("--recycle-after=") {
+                flags.recycle_after = Some(value.to_string());
+            } else if let Some(value) = arg.strip_prefix("--run-dir=") {
+                flags.run_dir = Some(value.to_string());
+            } else if let Some(value) = arg.strip_prefix("--downloads-dir=") {
+                flags.downloads_dir = Some(value.to_string());
+            } else if let Some(value) = arg.strip_prefix("--allow-urls=") {
+                flags.allow_urls = split_list(value);
+            } else if let Some(value) = arg.strip_prefix("--deny-urls=") {
+                flags.deny_urls = split_list(value);
+            } else if let Some(value) = arg.strip_prefix("--redact=") {
+                flags.redact = Some(value.to_string());
+            } else if let Some(value) = arg.strip_prefix("--lang=") {
+                flags.lang = Some(value.to_string());
+            } else {
+                flags.extra.push(arg.clone());
             }
         }
 
@@ -51,10 +285,50 @@ impl Flags {
                 .unwrap_or(false);
         }
 
+        if !flags.native_daemon {
+            flags.native_daemon = std::env::var("AGENT_BROWSER_NATIVE_DAEMON")
+                .map(|v| v == "1")
+                .unwrap_or(false);
+        }
+
+        if !flags.read_only {
+            flags.read_only = std::env::var("AGENT_BROWSER_READ_ONLY")
+                .map(|v| v == "1")
+                .unwrap_or(false);
+        }
+
+        flags.default_timeout = std::env::var("AGENT_BROWSER_DEFAULT_TIMEOUT")
+            .ok()
+            .and_then(|v| v.parse().ok());
+
         if flags.executable_path.is_none() {
             flags.executable_path = std::env::var("AGENT_BROWSER_EXECUTABLE_PATH").ok();
         }
 
+        if flags.run_dir.is_none() {
+            flags.run_dir = std::env::var("AGENT_BROWSER_RUN_DIR").ok();
+        }
+
+        if flags.downloads_dir.is_none() {
+            flags.downloads_dir = std::env::var("AGENT_BROWSER_DOWNLOADS_DIR").ok();
+        }
+
+        if flags.redact.is_none() {
+            flags.redact = std::env::var("AGENT_BROWSER_REDACT").ok();
+        }
+
+        if flags.allow_urls.is_empty() {
+            if let Ok(patterns) = std::env::var("AGENT_BROWSER_ALLOW_URLS") {
+                flags.allow_urls = split_list(&patterns);
+            }
+        }
+
+        if flags.deny_urls.is_empty() {
+            if let Ok(patterns) = std::env::var("AGENT_BROWSER_DENY_URLS") {
+                flags.deny_urls = split_list(&patterns);
+            }
+        }
+
         if flags.extensions.is_empty() {
             if let Ok(extensions) = std::env::var("AGENT_BROWSER_EXTENSIONS") {
                 flags.extensions = extensions
@@ -66,4 +340,116 @@ impl Flags {
 
         flags
     }
+
+    /// Whether a command-specific boolean flag was given
+    pub fn has(&self, name: &str) -> bool {
+        self.extra.iter().any(|a| a == name)
+    }
+
+    /// Value of a command-specific `--name=value` flag
+    pub fn value(&self, name: &str) -> Option<&str> {
+        self.extra
+            .iter()
+            .find_map(|a| a.strip_prefix(name).and_then(|rest| rest.strip_prefix('=')))
+    }
+}
+
+/// Split a comma-separated flag value, dropping empty entries
+fn split_list(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(line: &str) -> Vec<String> {
+        line.split(' ').map(String::from).collect()
+    }
+
+    #[test]
+    fn global_value_flags_take_the_next_argument() {
+        assert_eq!(
+            normalize_args(&args("--session work open https://a.b")),
+            args("--session=work open https://a.b")
+        );
+        assert_eq!(
+            normalize_args(&args("title --timeout")),
+            args("title --timeout")
+        );
+    }
+
+    #[test]
+    fn command_value_flags_take_the_next_argument() {
+        assert_eq!(
+            normalize_args(&args("bench title --iterations 100")),
+            args("bench title --iterations=100")
+        );
+        assert_eq!(
+            normalize_args(&args("text body --cache 2s --limit 10")),
+            args("text body --cache=2s --limit=10")
+        );
+        // Boolean flags leave what follows alone
+        assert_eq!(
+            normalize_args(&args("screenshot --full-page out.png")),
+            args("screenshot --full-page out.png")
+        );
+    }
+
+    #[test]
+    fn pool_name_is_optional() {
+        assert_eq!(
+            normalize_args(&args("--pool crawlers open https://a.b")),
+            args("--pool=crawlers open https://a.b")
+        );
+        assert_eq!(
+            normalize_args(&args("--pool open https://a.b")),
+            args("--pool open https://a.b")
+        );
+        assert_eq!(
+            normalize_args(&args("--pool --json title")),
+            args("--pool --json title")
+        );
+    }
+
+    #[test]
+    fn short_flags() {
+        assert_eq!(
+            normalize_args(&args("-jt 500 title")),
+            args("--json --timeout=500 title")
+        );
+        assert_eq!(
+            normalize_args(&args("-s=work -t500 title")),
+            args("--session=work --timeout=500 title")
+        );
+        // Unknown letters and negative numbers are left as they are
+        assert_eq!(
+            normalize_args(&args("scroll -x -200")),
+            args("scroll -x -200")
+        );
+    }
+
+    #[test]
+    fn double_dash_ends_flags() {
+        let normalized = normalize_args(&args("fill @e1 -- --session -j"));
+        assert_eq!(normalized, args("fill @e1 -- --session -j"));
+        let (flags, positionals) = split_args(&normalized);
+        assert!(flags.is_empty());
+        assert_eq!(positionals, args("fill @e1 --session -j"));
+        assert_eq!(first_positional(&args("--json -- title")), Some(2));
+        assert_eq!(first_positional(&args("--json --")), None);
+    }
+
+    #[test]
+    fn lone_dash_is_positional() {
+        let (flags, positionals) = split_args(&args("--json run - --"));
+        assert_eq!(flags, args("--json"));
+        assert_eq!(positionals, args("run -"));
+        assert_eq!(normalize_args(&args("run -")), args("run -"));
+        assert_eq!(first_positional(&args("--json -")), Some(1));
+    }
 }
@@ -0,0 +1,166 @@
+/*!
+ * Handing the Page to a Person
+ *
+ * `handoff` opens the session's current URL in the user's default browser,
+ * for steps an agent can't or shouldn't do itself (payment confirmation,
+ * CAPTCHAs, 2FA prompts). The default browser doesn't share the session's
+ * cookies, so `--cookies` instead starts a separate headed Chrome with a
+ * temporary profile holding a copy of them, signed in where the session
+ * is. That browser is left running when the CLI exits; closing it is up to
+ * the person, and its profile stays in the temp directory until removed.
+ */
+use std::env;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+
+use serde_json::{json, Value};
+
+use crate::cdp::{self, Browser};
+use crate::commands::CommandJson;
+use crate::connection::{ensure_daemon, send_command};
+use crate::flags::Flags;
+use crate::pageerrors::now_ms;
+
+/// Hand the current page over; `print_only` skips opening anything
+pub fn run(print_only: bool, cookies: bool, flags: &Flags) -> Result<Value, String> {
+    ensure_daemon(
+        &flags.session,
+        flags.headed,
+        flags.executable_path.as_deref(),
+        &flags.extensions,
+    )?;
+    let url = call("getUrl", flags)?["url"]
+        .as_str()
+        .unwrap_or_default()
+        .to_string();
+    if !url.starts_with("http://") && !url.starts_with("https://") {
+        return Err(format!(
+            "Nothing to hand off: the page is at {}",
+            if url.is_empty() { "no URL" } else { &url }
+        ));
+    }
+
+    if print_only {
+        return Ok(json!({"url": url, "opened": false}));
+    }
+    if cookies {
+        let cookies = call("getCookies", flags)?["cookies"].take();
+        let (profile, copied) = open_with_cookies(&url, &cookies, flags)?;
+        return Ok(json!({
+            "url": url,
+            "opened": true,
+            "profile": profile.to_string_lossy(),
+            "cookies": copied,
+        }));
+    }
+    Ok(json!({"url": url, "opened": open_default(&url)}))
+}
+
+pub fn print(result: &Value) {
+    let url = result["url"].as_str().unwrap_or_default();
+    match (result["opened"] == true, result["profile"].as_str()) {
+        (true, Some(profile)) => {
+            println!(
+                "\x1b[32m✓\x1b[0m Opened {} in a new Chrome window with {} cookies",
+                url,
+                result["cookies"].as_u64().unwrap_or(0)
+            );
+            println!("\x1b[90mProfile: {} (remove it when done)\x1b[0m", profile);
+        }
+        (true, None) => println!("\x1b[32m✓\x1b[0m Opened {} in your default browser", url),
+        (false, _) => println!("{}", url),
+    }
+}
+
+/// Run a command and return its result, or the daemon's error
+fn call(action: &str, flags: &Flags) -> Result<Value, String> {
+    let resp = send_command(&CommandJson::new(action), &flags.session)?;
+    if resp.success {
+        Ok(resp.result.unwrap_or(Value::Null))
+    } else {
+        Err(resp.error.unwrap_or_else(|| format!("{} failed", action)))
+    }
+}
+
+/// Open `url` with the desktop's URL handler; false when there is none,
+/// as on a server, so the caller still has the URL to pass on
+fn open_default(url: &str) -> bool {
+    let opener = if cfg!(target_os = "macos") {
+        "open"
+    } else {
+        "xdg-open"
+    };
+    Command::new(opener)
+        .arg(url)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .is_ok_and(|status| status.success())
+}
+
+/// Start a headed Chrome on a fresh profile, copy the session's cookies
+/// into it and open `url`. Returns the profile and how many cookies took.
+fn open_with_cookies(
+    url: &str,
+    cookies: &Value,
+    flags: &Flags,
+) -> Result<(PathBuf, usize), String> {
+    let executable = cdp::find_browser(flags.executable_path.as_deref())?;
+    let profile = env::temp_dir().join(format!(
+        "agentbrowser-pro-handoff-{}-{}",
+        flags.session,
+        now_ms()
+    ));
+    let mut browser = Browser::launch(&executable, true, profile, &[])?;
+
+    let mut copied = 0;
+    for cookie in cookies.as_array().into_iter().flatten() {
+        let mut param = json!({
+            "name": cookie["name"],
+            "value": cookie["value"],
+            "domain": cookie["domain"],
+            "path": cookie["path"],
+            "secure": cookie["secure"],
+            "httpOnly": cookie["httpOnly"],
+        });
+        if let Some(same_site) = cookie["sameSite"].as_str() {
+            param["sameSite"] = json!(same_site);
+        }
+        // Session cookies come with expires -1
+        if let Some(expires) = cookie["expires"].as_f64().filter(|e| *e > 0.0) {
+            param["expires"] = json!(expires);
+        }
+        // One cookie Chrome rejects shouldn't stop the rest
+        if browser
+            .call_browser("Storage.setCookies", json!({"cookies": [param]}))
+            .is_ok()
+        {
+            copied += 1;
+        }
+    }
+
+    if let Err(e) = browser.call("Page.navigate", json!({"url": url})) {
+        browser.close();
+        return Err(e);
+    }
+    // Chrome starts with a blank tab of its own besides the driven one
+    let own = browser
+        .call("Target.getTargetInfo", json!({}))
+        .unwrap_or_default();
+    let targets = browser
+        .call_browser("Target.getTargets", json!({}))
+        .unwrap_or_default();
+    for target in targets["targetInfos"].as_array().into_iter().flatten() {
+        let blank = target["type"] == "page" && target["url"] == "about:blank";
+        if blank && target["targetId"] != own["targetInfo"]["targetId"] {
+            browser
+                .call_browser(
+                    "Target.closeTarget",
+                    json!({"targetId": target["targetId"]}),
+                )
+                .ok();
+        }
+    }
+    Ok((browser.detach(), copied))
+}
@@ -0,0 +1,191 @@
+/*!
+ * Result Hooks
+ *
+ * `on_result` in config.toml post-processes the results of browser commands
+ * before they are printed, so a team can normalize output once instead of
+ * wrapping every call in a pipeline. It is either one shell command for every
+ * command, or a table of them by command name (`"*"` for the rest):
+ *
+ * ```toml
+ * on_result = "jq '.url'"
+ *
+ * [on_result]
+ * snapshot = "jq '{refs: (.refs | keys)}'"
+ * cookies = "jq '[.cookies[] | {name, domain}]' | tee -a cookies.log"
+ * ```
+ *
+ * The hook gets the result JSON on stdin, with AGENT_BROWSER_COMMAND and
+ * AGENT_BROWSER_SESSION set. What it prints replaces the result: JSON is
+ * kept as JSON, anything else (`jq -r`) is printed as plain text. Printing
+ * nothing leaves the result alone. A hook that fails fails the command.
+ */
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+use std::process::{Command, Output, Stdio};
+use std::thread;
+
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::connection::Response;
+
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum ResultHooks {
+    #[default]
+    None,
+    All(String),
+    ByCommand(BTreeMap<String, String>),
+}
+
+impl ResultHooks {
+    /// The hook for `command` (or its protocol `action`), if any
+    pub fn find(&self, command: &str, action: &str) -> Option<&str> {
+        match self {
+            ResultHooks::None => None,
+            ResultHooks::All(hook) => Some(hook),
+            ResultHooks::ByCommand(hooks) => [command, action, "*"]
+                .iter()
+                .find_map(|name| hooks.get(*name))
+                .map(String::as_str),
+        }
+    }
+}
+
+/// Run the hook for `command` on a successful response's result
+pub fn apply(
+    hooks: &ResultHooks,
+    command: &str,
+    action: &str,
+    resp: &mut Response,
+    session: &str,
+) -> Result<(), String> {
+    let Some(hook) = hooks.find(command, action) else {
+        return Ok(());
+    };
+    let Some(result) = resp.result.as_mut().filter(|_| resp.success) else {
+        return Ok(());
+    };
+
+    let output = pipe_through(
+        Command::new("sh")
+            .arg("-c")
+            .arg(hook)
+            .env("AGENT_BROWSER_COMMAND", command)
+            .env("AGENT_BROWSER_SESSION", session),
+        result.to_string().into_bytes(),
+    )
+    .map_err(|e| format!("Failed to run on_result hook: {}", e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "on_result hook for {} exited with {}",
+            command, output.status
+        ));
+    }
+
+    let printed = String::from_utf8_lossy(&output.stdout);
+    let printed = printed.trim_end();
+    if !printed.is_empty() {
+        *result = serde_json::from_str(printed).unwrap_or_else(|_| Value::String(printed.into()));
+    }
+    Ok(())
+}
+
+/// Run `command` with `input` on its stdin and collect its stdout. Stdin is
+/// fed from another thread: a filter like `cat` or `jq` prints while it
+/// reads, and would otherwise block on a full stdout pipe while we block
+/// writing the rest of a large result.
+pub fn pipe_through(command: &mut Command, input: Vec<u8>) -> io::Result<Output> {
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()?;
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    // A command that doesn't read all of its input closes the pipe early,
+    // which is fine
+    let writer = thread::spawn(move || stdin.write_all(&input).ok());
+    let output = child.wait_with_output();
+    writer.join().ok();
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn find_by_command_then_action_then_star() {
+        let hooks: ResultHooks = serde_json::from_value(json!({
+            "snapshot": "a",
+            "getCookies": "b",
+            "*": "c",
+        }))
+        .unwrap();
+        assert_eq!(hooks.find("snapshot", "snapshot"), Some("a"));
+        assert_eq!(hooks.find("cookies", "getCookies"), Some("b"));
+        assert_eq!(hooks.find("url", "getUrl"), Some("c"));
+        assert_eq!(ResultHooks::None.find("url", "getUrl"), None);
+    }
+
+    #[test]
+    fn json_output_replaces_the_result() {
+        let hooks = ResultHooks::All("echo '{\"n\": 2}'".to_string());
+        let mut resp = Response::ok("1", json!({ "n": 1 }));
+        apply(&hooks, "url", "getUrl", &mut resp, "default").unwrap();
+        assert_eq!(resp.result, Some(json!({ "n": 2 })));
+    }
+
+    #[test]
+    fn text_output_becomes_a_string_and_silence_keeps_the_result() {
+        let mut resp = Response::ok("1", json!({ "url": "https://a.test" }));
+        let text = ResultHooks::All("echo plain".to_string());
+        apply(&text, "url", "getUrl", &mut resp, "default").unwrap();
+        assert_eq!(resp.result, Some(json!("plain")));
+
+        let silent = ResultHooks::All("cat > /dev/null".to_string());
+        apply(&silent, "url", "getUrl", &mut resp, "default").unwrap();
+        assert_eq!(resp.result, Some(json!("plain")));
+    }
+
+    #[test]
+    fn failing_hook_fails_the_command() {
+        let hooks = ResultHooks::All("exit 3".to_string());
+        let mut resp = Response::ok("1", json!({}));
+        assert!(apply(&hooks, "url", "getUrl", &mut resp, "default").is_err());
+    }
+
+    #[test]
+    fn results_larger_than_a_pipe_buffer() {
+        let big = "x".repeat(4 << 20);
+        for hook in ["cat", "sed 's/x/y/'", "tee /dev/null"] {
+            let mut resp = Response::ok("1", json!({ "html": big }));
+            apply(
+                &ResultHooks::All(hook.to_string()),
+                "html",
+                "getHtml",
+                &mut resp,
+                "s",
+            )
+            .unwrap();
+            assert_eq!(
+                resp.result.as_ref().unwrap()["html"]
+                    .as_str()
+                    .unwrap()
+                    .len(),
+                big.len()
+            );
+        }
+    }
+
+    #[test]
+    fn hook_that_ignores_its_input() {
+        let output = pipe_through(
+            Command::new("sh").arg("-c").arg("echo done"),
+            vec![b'x'; 1 << 20],
+        )
+        .unwrap();
+        assert_eq!(output.stdout, b"done\n");
+    }
+}
@@ -0,0 +1,429 @@
+/*!
+ * Localized Messages
+ *
+ * `--lang=<code>`, then `lang` in the config file, then LC_ALL /
+ * LC_MESSAGES / LANG pick the language of help text, parse errors and
+ * status messages. English is built in; other languages have a catalog in
+ * `cli/locales/<code>.toml` with four tables:
+ *
+ * - `messages`: the strings in MESSAGES, by key
+ * - `categories`, `commands`, `flags`: command categories, command
+ *   descriptions and global option descriptions, keyed by their names in
+ *   the command registry
+ *
+ * Anything a catalog lacks is shown in English. `i18n check` lists what the
+ * catalogs are missing, or still translate for commands that are gone;
+ * `i18n template <code>` prints a catalog generated from the registry, with
+ * the English text standing in for each gap.
+ *
+ * Errors reported by the daemon are not translated.
+ */
+use std::collections::BTreeMap;
+use std::env;
+use std::sync::OnceLock;
+
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::registry::{COMMANDS, GLOBAL_FLAGS};
+
+pub const LANGS: &[&str] = &["en", "es", "ja", "zh", "de"];
+
+/// English UI strings by key; `{}` marks where a value goes
+const MESSAGES: &[(&str, &str)] = &[
+    ("success", "Success"),
+    ("command_failed", "Command failed"),
+    ("did_you_mean", "Did you mean:"),
+    ("hint", "Hint:"),
+    ("intercepted_by", "Intercepted by:"),
+    ("wrote_bytes", "Wrote {} bytes to {}"),
+    ("unknown_command", "Unknown command: {}"),
+    (
+        "see_help",
+        "Run 'agentbrowser-pro --help' to see available commands.",
+    ),
+    ("unknown_subcommand", "Unknown subcommand: {}"),
+    ("valid_options", "Valid options: {}"),
+    ("missing_arguments", "Missing arguments for: {}"),
+    ("invalid_value", "Invalid value for {}: '{}'"),
+    (
+        "policy_not_allowed",
+        "'{}' isn't among the commands the policy allows",
+    ),
+    ("policy_denied", "'{}' is denied by the policy"),
+    (
+        "policy_url_denied",
+        "{} is blocked by the policy pattern '{}'",
+    ),
+    (
+        "policy_url_not_allowed",
+        "{} isn't among the allowed URLs ({})",
+    ),
+    (
+        "read_only_blocked",
+        "'{}' can change the page or site, and --read-only only allows observing it",
+    ),
+    ("expected", "Expected: {}"),
+    ("usage", "Usage:"),
+    ("aliases", "Aliases:"),
+    ("arguments", "Arguments:"),
+    ("options", "Options:"),
+    ("examples", "Examples:"),
+    ("related", "Related:"),
+    ("optional", "(optional)"),
+    ("remaining_words", "(remaining words)"),
+    (
+        "global_options_note",
+        "Global options are listed in 'agentbrowser-pro --help'.",
+    ),
+    ("tagline", "Browser automation for AI agents"),
+    ("commands", "Commands:"),
+    ("global_options", "Global options:"),
+    (
+        "command_help_note",
+        "Run 'agentbrowser-pro help <command>' for a command's arguments, options and examples.",
+    ),
+    ("crawl_duplicate", "{} duplicate of {}"),
+    ("crawl_robots", "disallowed by robots.txt"),
+    ("monitor_baseline", "baseline saved"),
+    ("monitor_unchanged", "unchanged"),
+    ("monitor_changed", "changed, {}"),
+    ("monitor_pixels", "{} pixel(s) differ"),
+    ("monitor_lines", "{} line(s) added, {} removed"),
+    ("monitor_notified", "notified"),
+    ("monitor_notify_failed", "notify failed: {}"),
+    ("monitor_none", "No monitors"),
+    ("monitor_every", "every {}"),
+    ("monitor_last_failed", "last check failed: {}"),
+    ("worker_started", "Worker started: {} worker(s) on {}"),
+    ("worker_label", "worker {}"),
+    ("script_passed", "{} step(s) in {}ms"),
+    ("script_failed", "Script failed"),
+    ("script_line_failed", "line {} failed: {}"),
+    ("script_steps_failed", "{} steps failed, on lines {}"),
+];
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct Catalog {
+    messages: BTreeMap<String, String>,
+    categories: BTreeMap<String, String>,
+    commands: BTreeMap<String, String>,
+    flags: BTreeMap<String, String>,
+}
+
+fn source(lang: &str) -> Option<&'static str> {
+    match lang {
+        "es" => Some(include_str!("../locales/es.toml")),
+        "ja" => Some(include_str!("../locales/ja.toml")),
+        "zh" => Some(include_str!("../locales/zh.toml")),
+        "de" => Some(include_str!("../locales/de.toml")),
+        _ => None,
+    }
+}
+
+fn load(lang: &str) -> Result<Catalog, String> {
+    match source(lang) {
+        Some(text) => toml::from_str(text)
+            .map_err(|e| format!("Invalid catalog locales/{}.toml: {}", lang, e)),
+        None => Ok(Catalog::default()),
+    }
+}
+
+static ACTIVE: OnceLock<(String, Catalog)> = OnceLock::new();
+
+/// The language part of a locale like `ja_JP.UTF-8`
+fn language_of(locale: &str) -> String {
+    locale
+        .split(['_', '-', '.', '@'])
+        .next()
+        .unwrap_or("")
+        .to_lowercase()
+}
+
+/// Choose the language for this run. `requested` comes from `--lang` or
+/// the config file and must be supported; without it the locale
+/// environment decides, falling back to English.
+pub fn init(requested: Option<&str>) -> Result<(), String> {
+    let lang = match requested {
+        Some(code) => {
+            let lang = language_of(code);
+            if !LANGS.contains(&lang.as_str()) {
+                ACTIVE.set(("en".to_string(), Catalog::default())).ok();
+                return Err(format!(
+                    "Unsupported language '{}'; use one of: {}",
+                    code,
+                    LANGS.join(", ")
+                ));
+            }
+            lang
+        }
+        None => ["LC_ALL", "LC_MESSAGES", "LANG"]
+            .iter()
+            .filter_map(|name| env::var(name).ok())
+            .find(|value| !value.is_empty())
+            .map(|locale| language_of(&locale))
+            .filter(|lang| LANGS.contains(&lang.as_str()))
+            .unwrap_or_else(|| "en".to_string()),
+    };
+    // A broken catalog shows English rather than failing every command;
+    // `i18n check` reports it
+    let catalog = load(&lang).unwrap_or_default();
+    ACTIVE.set((lang, catalog)).ok();
+    Ok(())
+}
+
+/// The language in use
+pub fn lang() -> &'static str {
+    ACTIVE.get().map(|(lang, _)| lang.as_str()).unwrap_or("en")
+}
+
+fn active() -> Option<&'static Catalog> {
+    ACTIVE.get().map(|(_, catalog)| catalog)
+}
+
+/// A UI message
+pub fn t(key: &str) -> &'static str {
+    active()
+        .and_then(|c| c.messages.get(key))
+        .map(String::as_str)
+        .or_else(|| MESSAGES.iter().find(|(k, _)| *k == key).map(|(_, m)| *m))
+        .unwrap_or_default()
+}
+
+/// A UI message with each `{}` filled from `args` in turn
+pub fn tf(key: &str, args: &[&str]) -> String {
+    let mut parts = t(key).split("{}");
+    let mut out = parts.next().unwrap_or_default().to_string();
+    let mut args = args.iter();
+    for part in parts {
+        out.push_str(args.next().copied().unwrap_or_default());
+        out.push_str(part);
+    }
+    out
+}
+
+/// A command's description
+pub fn command(name: &str, english: &'static str) -> &'static str {
+    active()
+        .and_then(|c| c.commands.get(name))
+        .map(String::as_str)
+        .unwrap_or(english)
+}
+
+/// A command category's name
+pub fn category(name: &'static str) -> &'static str {
+    active()
+        .and_then(|c| c.categories.get(name))
+        .map(String::as_str)
+        .unwrap_or(name)
+}
+
+/// A global option's description
+pub fn flag(name: &str, english: &'static str) -> &'static str {
+    active()
+        .and_then(|c| c.flags.get(name))
+        .map(String::as_str)
+        .unwrap_or(english)
+}
+
+/// Categories in the order the registry first uses them, "Other" last
+pub fn categories() -> Vec<&'static str> {
+    let mut categories: Vec<&'static str> = Vec::new();
+    for spec in COMMANDS {
+        if !categories.contains(&spec.category) {
+            categories.push(spec.category);
+        }
+    }
+    categories.sort_by_key(|c| *c == "Other");
+    categories
+}
+
+/// Every key a complete catalog has, by table, in registry order
+fn expected_keys() -> [(&'static str, Vec<&'static str>); 4] {
+    [
+        ("messages", MESSAGES.iter().map(|(k, _)| *k).collect()),
+        ("categories", categories()),
+        ("commands", COMMANDS.iter().map(|c| c.name).collect()),
+        ("flags", GLOBAL_FLAGS.iter().map(|f| f.name).collect()),
+    ]
+}
+
+fn table<'a>(catalog: &'a Catalog, name: &str) -> &'a BTreeMap<String, String> {
+    match name {
+        "messages" => &catalog.messages,
+        "categories" => &catalog.categories,
+        "commands" => &catalog.commands,
+        _ => &catalog.flags,
+    }
+}
+
+/// Compare catalogs with the registry: per language, the keys each table
+/// lacks, keys it has that nothing uses, and messages whose `{}` count
+/// differs from the English
+pub fn check(only: Option<&str>) -> Value {
+    let mut report = serde_json::Map::new();
+    for lang in LANGS
+        .iter()
+        .filter(|l| **l != "en" && only.is_none_or(|o| o == **l))
+    {
+        let catalog = match load(lang) {
+            Ok(catalog) => catalog,
+            Err(e) => {
+                report.insert(lang.to_string(), json!({"complete": false, "error": e}));
+                continue;
+            }
+        };
+        let mut missing = serde_json::Map::new();
+        let mut stale = serde_json::Map::new();
+        for (name, keys) in expected_keys() {
+            let entries = table(&catalog, name);
+            let absent: Vec<&str> = keys
+                .iter()
+                .copied()
+                .filter(|k| !entries.contains_key(*k))
+                .collect();
+            let unused: Vec<&str> = entries
+                .keys()
+                .map(String::as_str)
+                .filter(|k| !keys.contains(k))
+                .collect();
+            if !absent.is_empty() {
+                missing.insert(name.to_string(), json!(absent));
+            }
+            if !unused.is_empty() {
+                stale.insert(name.to_string(), json!(unused));
+            }
+        }
+        let placeholders: Vec<&str> = MESSAGES
+            .iter()
+            .filter(|(key, english)| {
+                catalog
+                    .messages
+                    .get(*key)
+                    .is_some_and(|m| m.matches("{}").count() != english.matches("{}").count())
+            })
+            .map(|(key, _)| *key)
+            .collect();
+        let complete = missing.is_empty() && stale.is_empty() && placeholders.is_empty();
+        report.insert(
+            lang.to_string(),
+            json!({
+                "complete": complete,
+                "missing": missing,
+                "stale": stale,
+                "placeholders": placeholders,
+            }),
+        );
+    }
+    Value::Object(report)
+}
+
+fn toml_key(key: &str) -> String {
+    if key
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+    {
+        key.to_string()
+    } else {
+        json!(key).to_string()
+    }
+}
+
+/// A catalog for `lang` generated from the registry: its current
+/// translations, with the English text marked for each one it lacks
+pub fn template(lang: &str) -> Result<String, String> {
+    if lang == "en" {
+        return Err("English is built in; choose another language".to_string());
+    }
+    if !LANGS.contains(&lang) {
+        return Err(format!(
+            "Unsupported language '{}'; use one of: {}",
+            lang,
+            LANGS.join(", ")
+        ));
+    }
+    let catalog = load(lang)?;
+    let english = |name: &str, key: &str| -> String {
+        match name {
+            "messages" => t_english(key),
+            "commands" => COMMANDS
+                .iter()
+                .find(|c| c.name == key)
+                .map(|c| c.description.to_string())
+                .unwrap_or_default(),
+            "flags" => GLOBAL_FLAGS
+                .iter()
+                .find(|f| f.name == key)
+                .map(|f| f.description.to_string())
+                .unwrap_or_default(),
+            _ => key.to_string(),
+        }
+    };
+
+    let mut out = format!(
+        "# agentbrowser-pro messages: {}\n# Generated by 'agentbrowser-pro i18n template {}'; keys follow the command registry.\n",
+        lang, lang
+    );
+    for (name, keys) in expected_keys() {
+        out.push_str(&format!("\n[{}]\n", name));
+        let entries = table(&catalog, name);
+        for key in keys {
+            let value = match entries.get(key) {
+                Some(value) => value.clone(),
+                None => {
+                    out.push_str("# TODO: translate\n");
+                    english(name, key)
+                }
+            };
+            out.push_str(&format!("{} = {}\n", toml_key(key), json!(value)));
+        }
+    }
+    Ok(out)
+}
+
+fn t_english(key: &str) -> String {
+    MESSAGES
+        .iter()
+        .find(|(k, _)| *k == key)
+        .map(|(_, m)| m.to_string())
+        .unwrap_or_default()
+}
+
+/// Human-readable `i18n check`
+pub fn print_check(report: &Value) {
+    for (lang, result) in report.as_object().into_iter().flatten() {
+        if let Some(error) = result["error"].as_str() {
+            println!("\x1b[31m✗\x1b[0m {}: {}", lang, error);
+            continue;
+        }
+        if result["complete"] == true {
+            println!("\x1b[32m✓\x1b[0m {}: complete", lang);
+            continue;
+        }
+        println!("\x1b[31m✗\x1b[0m {}:", lang);
+        for (kind, label) in [("missing", "missing"), ("stale", "not in the registry")] {
+            for (table, keys) in result[kind].as_object().into_iter().flatten() {
+                let keys: Vec<&str> = keys
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|k| k.as_str())
+                    .collect();
+                println!("    {} {}: {}", table, label, keys.join(", "));
+            }
+        }
+        let placeholders: Vec<&str> = result["placeholders"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|k| k.as_str())
+            .collect();
+        if !placeholders.is_empty() {
+            println!(
+                "    messages with the wrong number of {{}}: {}",
+                placeholders.join(", ")
+            );
+        }
+    }
+}
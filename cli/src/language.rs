@@ -0,0 +1,366 @@
+/*!
+ * Language Detection and Translation
+ *
+ * `detectlang [selector]` guesses the language of the page (or an element)
+ * from its text, alongside the language the page declares in `<html lang>`.
+ * Scripts with one main language (Japanese kana, Hangul, Thai, ...) decide
+ * it outright; Latin and Cyrillic text is scored by how many of each
+ * language's most common words it uses.
+ *
+ * `translate [selector] --to=<lang>` sends the text through a translation
+ * hook, configured as `translate = "..."` in config.toml or given with
+ * `--with`:
+ * - an http(s) URL gets `{"text", "source", "target"}` POSTed as JSON and
+ *   may answer with `{"text": ...}`, `{"translation": ...}` or plain text
+ * - anything else runs as a shell command with the text on stdin,
+ *   TRANSLATE_FROM / TRANSLATE_TO set, and the translation on stdout
+ */
+use std::process::Command;
+
+use serde_json::{json, Value};
+
+use crate::commands::CommandJson;
+use crate::connection::{ensure_daemon, send_command};
+use crate::flags::Flags;
+use crate::hooks::pipe_through;
+use crate::textdiff;
+
+/// Characters of text looked at; more rarely changes the answer
+const SAMPLE_CHARS: usize = 20_000;
+
+/// The most common words of each language scored by stop words
+const STOP_WORDS: &[(&str, &[&str])] = &[
+    (
+        "en",
+        &[
+            "the", "and", "of", "to", "in", "is", "that", "for", "it", "with", "as", "was", "on",
+            "are", "you", "this", "be", "at", "by", "not", "or", "from", "have", "we", "your",
+        ],
+    ),
+    (
+        "de",
+        &[
+            "der", "die", "und", "in", "den", "von", "zu", "das", "mit", "sich", "des", "auf",
+            "für", "ist", "im", "dem", "nicht", "ein", "eine", "als", "auch", "es", "an", "sie",
+            "wir",
+        ],
+    ),
+    (
+        "fr",
+        &[
+            "le", "la", "les", "de", "des", "et", "un", "une", "du", "est", "que", "pour", "dans",
+            "en", "qui", "pas", "sur", "au", "avec", "vous", "nous", "ce", "il", "par", "sont",
+        ],
+    ),
+    (
+        "es",
+        &[
+            "el", "la", "los", "las", "de", "del", "y", "que", "en", "un", "una", "por", "con",
+            "para", "es", "se", "no", "su", "al", "lo", "como", "más", "pero", "sus", "le",
+        ],
+    ),
+    (
+        "it",
+        &[
+            "il", "di", "che", "la", "e", "le", "per", "un", "una", "del", "della", "in", "non",
+            "sono", "con", "si", "da", "gli", "al", "lo", "ma", "come", "anche", "nel", "è",
+        ],
+    ),
+    (
+        "pt",
+        &[
+            "o", "a", "os", "as", "de", "do", "da", "dos", "das", "e", "que", "em", "um", "uma",
+            "para", "com", "não", "por", "no", "na", "se", "mais", "é", "ao", "você",
+        ],
+    ),
+    (
+        "nl",
+        &[
+            "de", "het", "een", "en", "van", "is", "dat", "op", "te", "in", "voor", "niet", "met",
+            "zijn", "er", "aan", "ook", "als", "bij", "wordt", "maar", "om", "u", "je", "naar",
+        ],
+    ),
+    (
+        "sv",
+        &[
+            "och", "att", "det", "som", "en", "på", "är", "av", "för", "med", "till", "den", "har",
+            "de", "inte", "om", "ett", "var", "jag", "kan", "du", "men", "vi", "så", "från",
+        ],
+    ),
+    (
+        "pl",
+        &[
+            "i", "w", "na", "z", "się", "nie", "do", "to", "że", "jest", "o", "jak", "po", "co",
+            "ale", "od", "za", "dla", "są", "przez", "ich", "tak", "czy", "oraz", "może",
+        ],
+    ),
+    (
+        "tr",
+        &[
+            "ve", "bir", "bu", "da", "de", "için", "ile", "çok", "daha", "olarak", "gibi", "en",
+            "ne", "var", "ama", "olan", "mi", "sonra", "kadar", "her", "değil", "o", "ben", "şey",
+            "veya",
+        ],
+    ),
+    (
+        "ru",
+        &[
+            "и", "в", "не", "на", "что", "с", "по", "как", "это", "он", "к", "но", "из", "у", "за",
+            "от", "для", "то", "же", "все", "так", "его", "вы", "или", "мы",
+        ],
+    ),
+    (
+        "uk",
+        &[
+            "і", "в", "не", "на", "що", "з", "та", "до", "це", "як", "у", "за", "від", "для",
+            "але", "його", "ви", "або", "ми", "її", "є", "й", "про", "вже", "щоб",
+        ],
+    ),
+];
+
+/// Scripts that mostly mean one language: (language, first, last char)
+const SCRIPTS: &[(&str, char, char)] = &[
+    ("ja", '\u{3040}', '\u{30FF}'),
+    ("ko", '\u{AC00}', '\u{D7AF}'),
+    ("th", '\u{0E00}', '\u{0E7F}'),
+    ("el", '\u{0370}', '\u{03FF}'),
+    ("he", '\u{0590}', '\u{05FF}'),
+    ("ar", '\u{0600}', '\u{06FF}'),
+    ("hi", '\u{0900}', '\u{097F}'),
+    ("zh", '\u{4E00}', '\u{9FFF}'),
+];
+
+/// The language `text` is most likely in, with a 0-1 confidence
+pub fn detect(text: &str) -> Option<(&'static str, f64)> {
+    let sample: String = text.chars().take(SAMPLE_CHARS).collect();
+    let letters = sample.chars().filter(|c| c.is_alphabetic()).count();
+    if letters == 0 {
+        return None;
+    }
+
+    let count = |first: char, last: char| {
+        sample
+            .chars()
+            .filter(|c| (first..=last).contains(c))
+            .count()
+    };
+    let mut scripts: Vec<(&str, usize)> = SCRIPTS
+        .iter()
+        .map(|&(lang, first, last)| (lang, count(first, last)))
+        .collect();
+    // Japanese mixes kana with Han characters; any real amount of kana
+    // means Japanese rather than Chinese
+    if scripts[0].1 * 10 >= letters {
+        let han = scripts
+            .iter()
+            .find(|(lang, _)| *lang == "zh")
+            .map_or(0, |s| s.1);
+        scripts[0].1 += han;
+    }
+    let (lang, n) = scripts
+        .iter()
+        .copied()
+        .max_by_key(|&(_, n)| n)
+        .unwrap_or(("", 0));
+    if n * 2 > letters {
+        return Some((lang, n as f64 / letters as f64));
+    }
+
+    let words: Vec<String> = sample
+        .split(|c: char| !c.is_alphabetic())
+        .filter(|w| !w.is_empty())
+        .map(str::to_lowercase)
+        .collect();
+    let scores: Vec<(&str, usize)> = STOP_WORDS
+        .iter()
+        .map(|&(lang, stop)| {
+            let hits = words.iter().filter(|w| stop.contains(&w.as_str())).count();
+            (lang, hits)
+        })
+        .collect();
+    let total: usize = scores.iter().map(|s| s.1).sum();
+    let (lang, best) = scores.iter().copied().max_by_key(|&(_, n)| n)?;
+    if best == 0 {
+        return None;
+    }
+    // Shared stop words ("de", "in") count for several languages, so the
+    // winner's share of all hits is a fair confidence
+    Some((lang, best as f64 / total as f64))
+}
+
+/// Run a command and return its result, or the daemon's error
+fn call(cmd: &CommandJson, flags: &Flags) -> Result<Value, String> {
+    let resp = send_command(cmd, &flags.session)?;
+    if resp.success {
+        Ok(resp.result.unwrap_or(Value::Null))
+    } else {
+        Err(resp
+            .error
+            .unwrap_or_else(|| format!("{} failed", cmd.action)))
+    }
+}
+
+/// The text of `selector`, or of the page's body
+fn read(selector: Option<&str>, flags: &Flags) -> Result<String, String> {
+    ensure_daemon(
+        &flags.session,
+        flags.headed,
+        flags.executable_path.as_deref(),
+        &flags.extensions,
+    )?;
+    textdiff::read_text(selector.unwrap_or("body"), flags)
+}
+
+/// Detect the language of `selector` (or the page)
+pub fn detect_page(selector: Option<&str>, flags: &Flags) -> Result<Value, String> {
+    let text = read(selector, flags)?;
+    let mut cmd = CommandJson::new("evaluate");
+    cmd.script = Some("document.documentElement.lang".to_string());
+    let declared = call(&cmd, flags)?["result"]
+        .as_str()
+        .filter(|lang| !lang.is_empty())
+        .map(String::from);
+    let detected = detect(&text);
+    Ok(json!({
+        "language": detected.map(|(lang, _)| lang),
+        "confidence": detected.map(|(_, confidence)| (confidence * 100.0).round() / 100.0),
+        "declared": declared,
+        "chars": text.chars().count(),
+    }))
+}
+
+/// Translate the text of `selector` (or the page) into `to` through `hook`
+pub fn translate_page(
+    selector: Option<&str>,
+    from: Option<&str>,
+    to: &str,
+    hook: &str,
+    flags: &Flags,
+) -> Result<Value, String> {
+    let text = read(selector, flags)?;
+    let from = from
+        .map(String::from)
+        .or_else(|| detect(&text).map(|(lang, _)| lang.to_string()))
+        .unwrap_or_default();
+    let translation = if from == to {
+        text.clone()
+    } else {
+        translate(&text, &from, to, hook)?
+    };
+    Ok(json!({
+        "from": from,
+        "to": to,
+        "text": text,
+        "translation": translation,
+    }))
+}
+
+/// Send `text` through the hook and return what it answers
+pub fn translate(text: &str, from: &str, to: &str, hook: &str) -> Result<String, String> {
+    let web = hook.starts_with("http://") || hook.starts_with("https://");
+    let (mut command, input) = if web {
+        let mut curl = Command::new("curl");
+        curl.args(["-fsS", "--max-time", "60"])
+            .args([
+                "-H",
+                "Content-Type: application/json",
+                "--data-binary",
+                "@-",
+            ])
+            .arg(hook);
+        let body = json!({"text": text, "source": from, "target": to});
+        (curl, body.to_string())
+    } else {
+        let mut sh = Command::new("sh");
+        sh.arg("-c")
+            .arg(hook)
+            .env("TRANSLATE_FROM", from)
+            .env("TRANSLATE_TO", to);
+        (sh, text.to_string())
+    };
+    let output = pipe_through(&mut command, input.into_bytes())
+        .map_err(|e| format!("Failed to run translate hook: {}", e))?;
+    if !output.status.success() {
+        return Err(format!("Translate hook exited with {}", output.status));
+    }
+    let answer = String::from_utf8_lossy(&output.stdout)
+        .trim_end()
+        .to_string();
+    if !web {
+        return Ok(answer);
+    }
+    match serde_json::from_str::<Value>(&answer) {
+        Ok(Value::String(s)) => Ok(s),
+        Ok(Value::Object(body)) => ["text", "translation", "translatedText"]
+            .iter()
+            .find_map(|key| body.get(*key).and_then(|v| v.as_str()))
+            .map(String::from)
+            .ok_or_else(|| {
+                "Translate endpoint answered JSON without a text or translation field".to_string()
+            }),
+        _ => Ok(answer),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_by_script() {
+        assert_eq!(detect("これは日本語の文章です").map(|d| d.0), Some("ja"));
+        assert_eq!(detect("这是一个中文句子").map(|d| d.0), Some("zh"));
+        assert_eq!(detect("한국어 문장입니다").map(|d| d.0), Some("ko"));
+        assert_eq!(detect("Ελληνικό κείμενο").map(|d| d.0), Some("el"));
+    }
+
+    #[test]
+    fn detects_by_stop_words() {
+        let detected = |text: &str| detect(text).map(|d| d.0);
+        assert_eq!(
+            detected("The cat is on the mat and it is happy"),
+            Some("en")
+        );
+        assert_eq!(
+            detected("Der Hund ist nicht in dem Haus und die Katze auch"),
+            Some("de")
+        );
+        assert_eq!(
+            detected("Le chat est dans la maison avec les enfants"),
+            Some("fr")
+        );
+        assert_eq!(detected("Это не то, что мы хотели"), Some("ru"));
+    }
+
+    #[test]
+    fn nothing_to_detect() {
+        assert_eq!(detect(""), None);
+        assert_eq!(detect("123 456 !!!"), None);
+        assert_eq!(detect("Xyzzy plugh"), None);
+    }
+
+    #[test]
+    fn shell_hook_gets_text_and_languages() {
+        let answer = translate(
+            "hello",
+            "en",
+            "de",
+            "printf '%s>%s:' $TRANSLATE_FROM $TRANSLATE_TO; cat",
+        )
+        .unwrap();
+        assert_eq!(answer, "en>de:hello");
+    }
+
+    #[test]
+    fn shell_hook_with_a_large_page() {
+        let text = "word ".repeat(1 << 20);
+        let answer = translate(&text, "en", "fr", "tr a-z A-Z").unwrap();
+        assert_eq!(answer.len(), text.trim_end().len());
+        assert!(answer.starts_with("WORD WORD"));
+    }
+
+    #[test]
+    fn failing_hook_is_an_error() {
+        assert!(translate("hello", "en", "de", "exit 1").is_err());
+    }
+}
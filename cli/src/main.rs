@@ -1,4 +1,4 @@
-/**
+/*!
  * AgentBrowser Pro CLI - Fast Rust command parser
  * Adapted from agent-browser/cli/src/main.rs
  *
@@ -6,32 +6,123 @@
  */
 
 use std::env;
-use std::io::{Read, Write, BufRead, BufReader};
-use std::process::{exit, Command, Stdio};
-use std::time::Duration;
-use std::thread;
 use std::fs;
 use std::path::Path;
+use std::process::{exit, Command, Stdio};
+
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
 
+mod artifacts;
+mod assertions;
+mod bench;
+mod cache;
+mod cdp;
 mod commands;
+mod config;
 mod connection;
+mod crawl;
+mod dataset;
+mod devices;
+mod extensions;
 mod flags;
+mod handoff;
+mod hooks;
+mod i18n;
+mod language;
+mod markdown;
+mod media;
+mod messages;
+mod monitor;
+mod native;
 mod output;
+mod pagediff;
+mod pageerrors;
+mod paging;
+mod pause;
+mod pipe;
+mod plugins;
+mod policy;
+mod pool;
+mod procinfo;
+mod recycle;
+mod redact;
+mod refs;
+mod registry;
+mod render;
+mod repl;
+mod sanitize;
+mod script;
+mod sitemap;
+mod storagestate;
+mod textdiff;
+mod tiles;
+mod tree;
+mod vault;
+mod websocket;
+mod worker;
 
-use commands::{parse_command, ParseError};
-use connection::{ensure_daemon, send_command, DaemonResult};
+use commands::{parse_command, parse_wait, CommandJson, ParseError};
+use config::Config;
+use connection::{daemon_pid, ensure_daemon, get_socket_path, send_command, Response};
 use flags::Flags;
-use output::{print_response, print_help, print_command_help};
+use output::{
+    print_aliases, print_command_help, print_dry_run, print_help, print_page_note, print_response,
+    print_spec, write_base64_data, write_output_file,
+};
+use paging::Paging;
+use recycle::RecyclePolicy;
+use registry::find_command;
 
 fn main() {
-    let args: Vec<String> = env::args().skip(1).collect();
-    let flags = Flags::parse(&args);
-    let clean = clean_args(&args);
+    let raw_args: Vec<String> = env::args().skip(1).collect();
+
+    let config = match Config::load() {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("\x1b[31m✗\x1b[0m {}", e);
+            exit(1);
+        }
+    };
+
+    // Plugin commands resolve like built-in ones, aliases included
+    let config_dir = config::config_path()
+        .and_then(|path| path.parent().map(Path::to_path_buf))
+        .unwrap_or_default();
+    if let Err(e) = plugins::register(&config.plugins, config.wasm_runtime.as_deref(), &config_dir)
+    {
+        eprintln!("\x1b[31m✗\x1b[0m {}", e);
+        exit(1);
+    }
+
+    // Expand user aliases before anything else looks at the arguments
+    let args = match config.expand_aliases(&flags::normalize_args(&raw_args)) {
+        Ok(args) => flags::normalize_args(&args),
+        Err(e) => {
+            eprintln!("\x1b[31m✗\x1b[0m {}", e);
+            exit(1);
+        }
+    };
+    let (flag_args, clean) = flags::split_args(&args);
+    let mut flags = Flags::parse(&flag_args);
+    if flags.default_timeout.is_none() {
+        flags.default_timeout = config.default_timeout;
+    }
+    // Pick the language before anything is printed for the user
+    if let Err(e) = i18n::init(flags.lang.as_deref().or(config.lang.as_deref())) {
+        exit_error(&e, flags.json);
+    }
+    policy::init(config.policy.clone());
+    // Script steps and other child processes redact the same way
+    if let Some(ref spec) = flags.redact {
+        redact::init(spec).unwrap_or_else(|e| exit_error(&e, flags.json));
+        env::set_var("AGENT_BROWSER_REDACT", spec);
+    }
 
     // Help handling
-    let has_help = args.iter().any(|a| a == "--help" || a == "-h");
+    let has_help = flags.has("--help");
     if has_help {
-        if let Some(cmd) = clean.get(0) {
+        if let Some(cmd) = clean.first() {
             if print_command_help(cmd) {
                 return;
             }
@@ -40,40 +131,176 @@ fn main() {
         return;
     }
 
+    // `help <command>` is an alias for `<command> --help`
+    if clean.first().map(|c| c == "help").unwrap_or(false) {
+        match clean.get(1) {
+            Some(cmd) => {
+                if !print_command_help(cmd) {
+                    let error = ParseError::UnknownCommand {
+                        command: cmd.to_lowercase(),
+                        suggestion: registry::suggest_command(cmd),
+                    };
+                    exit_parse_error(&error, flags.json);
+                }
+            }
+            None => print_help(),
+        }
+        return;
+    }
+
     // Version handling
-    if args.iter().any(|a| a == "--version" || a == "-v") {
+    if flags.has("--version") {
         println!("agentbrowser-pro 1.0.0");
         return;
     }
 
+    // Daemons started from here on, including by script steps, are native
+    if flags.native_daemon {
+        env::set_var(native::ENV_VAR, "1");
+    }
+
+    // Script steps, workers and the REPL's commands stay read-only too
+    if flags.read_only {
+        env::set_var("AGENT_BROWSER_READ_ONLY", "1");
+    }
+
+    // The daemon enforces URL rules itself, popups included, so one started
+    // from here on needs them too
+    if !flags.allow_urls.is_empty() {
+        env::set_var("AGENT_BROWSER_ALLOW_URLS", flags.allow_urls.join(","));
+    }
+    if !flags.deny_urls.is_empty() {
+        env::set_var("AGENT_BROWSER_DENY_URLS", flags.deny_urls.join(","));
+    }
+
+    // Commands come from stdin as JSON lines instead of the arguments
+    if flags.stdin {
+        if let Some(command) = clean.first() {
+            exit_error(
+                &format!(
+                    "--stdin reads commands from stdin; remove '{}' or drop --stdin",
+                    command
+                ),
+                true,
+            );
+        }
+        pipe::run(&flags).unwrap_or_else(|e| exit_error(&e, true));
+        return;
+    }
+
     // No command provided
     if clean.is_empty() {
         print_help();
         return;
     }
 
+    if !flags.extensions.is_empty() {
+        flags.extensions =
+            extensions::validate(&flags.extensions).unwrap_or_else(|e| exit_error(&e, flags.json));
+    }
+
+    let recycle = flags
+        .recycle_after
+        .as_deref()
+        .map(recycle::parse_policy)
+        .transpose()
+        .unwrap_or_else(|e| exit_error(&e, flags.json));
+
     // Parse command
-    let cmd = match parse_command(&clean, &flags) {
-        Ok(c) => c,
-        Err(e) => {
-            if flags.json {
-                let error_type = match &e {
-                    ParseError::UnknownCommand { .. } => "unknown_command",
-                    ParseError::UnknownSubcommand { .. } => "unknown_subcommand",
-                    ParseError::MissingArguments { .. } => "missing_arguments",
-                    ParseError::InvalidValue { .. } => "invalid_value",
-                };
-                println!(
-                    r#"{{"success":false,"error":"{}","type":"{}"}}"#,
-                    e.format().replace('\n', " ").replace('"', "\\\""),
-                    error_type
-                );
-            } else {
-                eprintln!("\x1b[31m✗\x1b[0m {}", e.format());
-            }
-            exit(1);
+    let mut cmd =
+        parse_command(&clean, &flags).unwrap_or_else(|e| exit_parse_error(&e, flags.json));
+
+    let wait_before = flags
+        .wait_before
+        .as_deref()
+        .map(|spec| parse_wait("--wait-before", spec, &flags))
+        .transpose()
+        .unwrap_or_else(|e| exit_parse_error(&e, flags.json));
+    let wait_after = flags
+        .wait_after
+        .as_deref()
+        .map(|spec| parse_wait("--wait-after", spec, &flags))
+        .transpose()
+        .unwrap_or_else(|e| exit_parse_error(&e, flags.json));
+
+    // Local commands that never reach the daemon
+    if cmd.action == "spec" {
+        print_spec(flags.json);
+        return;
+    }
+
+    if cmd.action == "aliasList" {
+        print_aliases(&config, flags.json);
+        return;
+    }
+
+    if cmd.action == "i18nCheck" {
+        run_i18n_check(cmd.value.as_deref(), &flags);
+        return;
+    }
+
+    if cmd.action == "i18nTemplate" {
+        let lang = cmd.value.as_deref().unwrap_or_default();
+        match i18n::template(lang) {
+            Ok(catalog) => print!("{}", catalog),
+            Err(e) => exit_error(&e, flags.json),
         }
-    };
+        return;
+    }
+
+    if cmd.action == "bench" {
+        run_bench(&clean[1..], &flags);
+        return;
+    }
+
+    // Commands that produce files write straight to --output-file so the
+    // payload never travels back over the socket
+    let output_file = flags.output_file.as_deref().map(absolute_path);
+    if let Some(ref path) = output_file {
+        if matches!(
+            cmd.action.as_str(),
+            "screenshot" | "screenshotTiles" | "pdf" | "getFavicon" | "captureCanvas"
+        ) && cmd.path.is_none()
+        {
+            cmd.path = Some(path.clone());
+        }
+    }
+
+    // Script steps run as child processes and find the run directory in
+    // the environment
+    if let Some(dir) = flags.run_dir.take().map(|dir| absolute_path(&dir)) {
+        env::set_var("AGENT_BROWSER_RUN_DIR", &dir);
+        artifacts::prepare(&mut cmd, &dir).unwrap_or_else(|e| exit_error(&e, flags.json));
+        flags.run_dir = Some(dir);
+    }
+
+    // The daemon saves downloads from its own working directory
+    if cmd.action == "download" || cmd.action == "getDownloads" {
+        cmd.path = cmd.path.as_deref().map(absolute_path);
+        cmd.downloads_dir = cmd.downloads_dir.as_deref().map(absolute_path);
+        if cmd.action == "download" && cmd.downloads_dir.is_none() {
+            cmd.downloads_dir = flags.run_dir.clone().or_else(|| {
+                env::current_dir()
+                    .ok()
+                    .map(|dir| dir.to_string_lossy().into_owned())
+            });
+        }
+    }
+    if cmd.action == "saveState" || cmd.action == "loadState" {
+        cmd.path = cmd.path.as_deref().map(absolute_path);
+    }
+
+    // Dry run: show what would be sent without touching the daemon
+    if flags.dry_run {
+        print_dry_run(
+            &cmd,
+            wait_before.as_ref(),
+            wait_after.as_ref(),
+            &flags,
+            &get_socket_path(&flags.session),
+        );
+        return;
+    }
 
     // Handle special commands
     if cmd.action == "daemon" {
@@ -81,58 +308,1008 @@ fn main() {
         return;
     }
 
+    if cmd.action.starts_with("pool") {
+        run_pool_command(&cmd, &flags);
+        return;
+    }
+
+    // Workers lease pool members per job
+    if cmd.action == "worker" {
+        run_worker(&flags);
+        return;
+    }
+
+    // Borrow a free pool member for the rest of this command
+    let _lease = flags.pool.clone().map(|name| {
+        let lease = pool::acquire(&name, &flags).unwrap_or_else(|e| exit_error(&e, flags.json));
+        flags.session = lease.session.clone();
+        lease
+    });
+
+    // Extensions installed into the session load alongside --extensions
+    for path in extensions::installed(&flags.session) {
+        if !flags.extensions.contains(&path) {
+            flags.extensions.push(path);
+        }
+    }
+
+    // Fake media devices are launch flags, so a new daemon needs them too
+    if let Some(settings) = media::configured(&flags.session) {
+        env::set_var(media::ENV_VAR, settings.to_string());
+    }
+
+    if cmd.action.starts_with("extension") {
+        run_extension_command(&cmd, &flags);
+        return;
+    }
+
+    if cmd.action == "mediaFake" {
+        run_media_fake(&cmd, &flags);
+        return;
+    }
+
+    if cmd.action == "if" {
+        run_if(&clean, &flags);
+        return;
+    }
+
+    if cmd.action == "runScript" {
+        run_script(&cmd, &flags);
+        return;
+    }
+
+    if cmd.action == "crawl" {
+        run_crawl(&cmd, &flags);
+        return;
+    }
+
+    if cmd.action == "expectJson" {
+        run_expect_json(&clean[2..], &flags);
+        return;
+    }
+
+    if cmd.action == "repl" {
+        repl::run(&flags);
+        return;
+    }
+
+    if cmd.action.starts_with("pagediff") {
+        run_pagediff(&cmd, &flags);
+        return;
+    }
+
+    if cmd.action == "plugin" {
+        run_plugin(&cmd, &clean[1..], &flags);
+        return;
+    }
+
+    if cmd.action == "detectLanguage" || cmd.action == "translate" {
+        run_language(&cmd, config.translate.as_deref(), &flags);
+        return;
+    }
+
+    if cmd.action == "textDiff" {
+        run_textdiff(&cmd, &flags);
+        return;
+    }
+
+    if cmd.action.starts_with("monitor") {
+        run_monitor(&cmd, &flags);
+        return;
+    }
+
+    if cmd.action == "pause" {
+        let resp = pause::run(&cmd, &flags).unwrap_or_else(|e| exit_error(&e, flags.json));
+        print_response(&cmd.action, &resp, flags.json);
+        if !resp.success {
+            exit(1);
+        }
+        return;
+    }
+
+    if cmd.action == "handoff" {
+        run_handoff(&flags);
+        return;
+    }
+
+    if cmd.action == "stateKey" {
+        run_state_key(&cmd, &flags);
+        return;
+    }
+
+    if cmd.action == "listDevices" {
+        print_response(
+            &cmd.action,
+            &Response::ok(&cmd.id, devices::list()),
+            flags.json,
+        );
+        return;
+    }
+
+    if cmd.action == "getErrors" && flags.has("--follow") {
+        ensure_daemon(
+            &flags.session,
+            flags.headed,
+            flags.executable_path.as_deref(),
+            &flags.extensions,
+        )
+        .and_then(|_| pageerrors::follow(&flags))
+        .unwrap_or_else(|e| exit_error(&e, flags.json));
+        return;
+    }
+
+    if cmd.action == "tapMessages" && flags.has("--follow") {
+        ensure_daemon(
+            &flags.session,
+            flags.headed,
+            flags.executable_path.as_deref(),
+            &flags.extensions,
+        )
+        .and_then(|_| messages::follow(cmd.channel.as_deref(), &flags))
+        .unwrap_or_else(|e| exit_error(&e, flags.json));
+        return;
+    }
+
+    if cmd.action == "daemonStats" {
+        print_daemon_stats(&cmd, &flags);
+        return;
+    }
+
     if cmd.action == "mcp" {
         start_mcp_server(&flags);
         return;
     }
 
-    // Ensure daemon is running
-    let daemon_result = match ensure_daemon(&flags.session, flags.headed, flags.executable_path.as_deref()) {
-        Ok(result) => result,
-        Err(e) => {
-            if flags.json {
-                println!(r#"{{"success":false,"error":"{}"}}"#, e);
+    // Serve repeated read-only queries from the short-lived result cache
+    let cacheable = find_command(&clean[0])
+        .map(|spec| spec.accepts("--cache"))
+        .unwrap_or(false);
+    let cache_ttl = flags
+        .value("--cache")
+        .filter(|_| cacheable)
+        .and_then(cache::parse_ttl);
+    let cache_key = cmd.to_json();
+    let cached = cache_ttl.and_then(|ttl| cache::lookup(&flags.session, &cache_key, ttl));
+
+    // The daemon only deals in plain JSON, so sealed state goes through a
+    // private temporary copy
+    let staged = vault::prepare(&mut cmd, flags.has("--encrypt"))
+        .unwrap_or_else(|e| exit_error(&e, flags.json));
+
+    let started = pageerrors::now_ms();
+    let from_cache = cached.is_some();
+    let mut resp = match cached {
+        // Cached before --redact was given, perhaps
+        Some(mut resp) => {
+            redact::response(&mut resp);
+            resp
+        }
+        None => {
+            let resp = execute(&mut cmd, wait_before.as_ref(), recycle.as_ref(), &flags);
+            // Anything else cleared the cache as it was sent
+            if resp.success && cache_ttl.is_some() {
+                cache::store(&flags.session, &cache_key, &resp);
+            }
+            resp
+        }
+    };
+    if let Some(staged) = staged {
+        vault::finish(staged, &mut resp).unwrap_or_else(|e| exit_error(&e, flags.json));
+    }
+    if !resp.success {
+        if let Some(ref selector) = cmd.selector {
+            refs::enrich_not_found(&mut resp, selector, &flags.session);
+        }
+        if resp.code.as_deref() == Some("click_intercepted") && resp.hint.is_none() {
+            let force = find_command(&clean[0]).is_some_and(|spec| spec.accepts("--force"));
+            resp.hint = Some(if force {
+                "Dismiss it first (see 'overlays'), or pass --force to skip hit-testing."
+                    .to_string()
             } else {
-                eprintln!("\x1b[31m✗\x1b[0m {}", e);
+                "Dismiss it first; 'overlays' lists banners and modals over the page.".to_string()
+            });
+        }
+        print_response(&cmd.action, &resp, flags.json);
+        exit(1);
+    }
+
+    // Remember refs so later failures can suggest alternatives
+    if cmd.action == "snapshot" {
+        if let Some(ref result) = resp.result {
+            refs::save(&flags.session, result);
+        }
+        if let Some(max) = flags.value("--max-tokens").and_then(|v| v.parse().ok()) {
+            tree::apply_budget(&mut resp, max);
+        }
+        if let Some(format) = flags.value("--output") {
+            tree::restructure(&mut resp, format);
+        }
+    }
+
+    if cmd.action == "listExtensions" {
+        extensions::describe(&mut resp);
+    }
+
+    if cmd.action == "getHtml" && flags.has("--sanitized") {
+        if let Some(result) = resp.result.as_mut() {
+            if let Some(html) = result["html"].as_str() {
+                result["html"] = sanitize::html(html).into();
+            }
+        }
+    }
+
+    // The daemon writes the HAR itself, so it's redacted on disk
+    if cmd.action == "harStop" && redact::active().is_some() {
+        if let Some(result) = resp.result.as_mut() {
+            let path = result["path"].as_str().unwrap_or_default().to_string();
+            redact::file(&path).unwrap_or_else(|e| exit_error(&e, flags.json));
+            if let Ok(meta) = fs::metadata(&path) {
+                result["size"] = meta.len().into();
+            }
+        }
+    }
+
+    if cmd.action == "screenshotTiles" {
+        if let Err(e) = tiles::stitch(&mut resp, cmd.path.as_deref()) {
+            exit_error(&e, flags.json);
+        }
+    }
+
+    if let (Some(path), "captureCanvas") = (cmd.path.as_deref(), cmd.action.as_str()) {
+        if let Err(e) = write_base64_data(&mut resp, path) {
+            exit_error(&e, flags.json);
+        }
+    }
+
+    // Window large results for commands that support paging
+    let pageable = find_command(&clean[0])
+        .map(|spec| spec.accepts("--offset"))
+        .unwrap_or(false);
+    if let Some(paging) = Paging::from_flags(&flags).filter(|_| pageable) {
+        paging::apply(&mut resp, &paging);
+    }
+
+    let command = find_command(&clean[0]).map_or(clean[0].as_str(), |spec| spec.name);
+    if let Err(e) = plugins::transform(command, &cmd.action, &mut resp, &flags) {
+        exit_error(&e, flags.json);
+    }
+    if let Err(e) = hooks::apply(
+        &config.on_result,
+        command,
+        &cmd.action,
+        &mut resp,
+        &flags.session,
+    ) {
+        exit_error(&e, flags.json);
+    }
+
+    if let Some(ref path) = output_file {
+        if let Err(e) = write_output_file(&mut resp, path) {
+            exit_error(&e, flags.json);
+        }
+    }
+
+    if let Some(ref dir) = flags.run_dir {
+        let recorded =
+            artifacts::record(dir, &cmd, &resp, &flags.session).and_then(|_| match output_file {
+                Some(ref path) if Path::new(path).is_file() && cmd.path.as_ref() != Some(path) => {
+                    artifacts::add(dir, path, "data", &cmd.action, &flags.session)
+                }
+                _ => Ok(()),
+            });
+        if let Err(e) = recorded {
+            exit_error(&e, flags.json);
+        }
+    }
+
+    if let Some(ref wait) = wait_after {
+        run_wait_step(wait, &flags);
+    }
+
+    if flags.fail_on_page_error && !from_cache {
+        let errors = pageerrors::since(&flags.session, started)
+            .unwrap_or_else(|e| exit_error(&e, flags.json));
+        if !errors.is_empty() {
+            resp.success = false;
+            resp.error = Some(pageerrors::summary(&errors));
+            print_response(&cmd.action, &resp, flags.json);
+            if !flags.json {
+                errors.iter().for_each(pageerrors::print_error);
             }
             exit(1);
         }
+    }
+
+    print_response(&cmd.action, &resp, flags.json);
+    if !flags.json {
+        print_page_note(&resp);
+    }
+
+    // A failed audit fails the command, so CI can gate on it
+    if matches!(cmd.action.as_str(), "seoAudit" | "pwaAudit") {
+        let status = resp.result.as_ref().and_then(|r| r.get("status"));
+        if status.and_then(|s| s.as_str()) == Some("fail") {
+            exit(1);
+        }
+    }
+}
+
+/// Start the daemon if needed (recycling it when the policy says so), run the
+/// `--wait-before` step and send the command, retrying once with a re-resolved
+/// ref when allowed
+fn execute(
+    cmd: &mut CommandJson,
+    wait_before: Option<&CommandJson>,
+    recycle: Option<&RecyclePolicy>,
+    flags: &Flags,
+) -> Response {
+    // Ensure daemon is running
+    if let Err(e) = ensure_daemon(
+        &flags.session,
+        flags.headed,
+        flags.executable_path.as_deref(),
+        &flags.extensions,
+    ) {
+        exit_error(&e, flags.json);
+    }
+
+    if let Some(policy) = recycle {
+        match recycle::maybe_recycle(policy, flags) {
+            Ok(Some(reason)) if !flags.json => {
+                eprintln!("\x1b[33m↻\x1b[0m Recycled daemon after {}", reason);
+            }
+            Ok(_) => {}
+            Err(e) => exit_error(&e, flags.json),
+        }
+    }
+
+    if let Some(wait) = wait_before {
+        run_wait_step(wait, flags);
+    }
+
+    // Send command
+    let mut resp = send_command(cmd, &flags.session).unwrap_or_else(|e| exit_error(&e, flags.json));
+    if !resp.success {
+        if let Some(selector) = cmd.selector.clone() {
+            if flags.auto_resolve_refs && refs::is_stale_ref_failure(&resp, &selector) {
+                match refs::reresolve(&selector, &flags.session) {
+                    Ok(new_ref) => {
+                        if !flags.json {
+                            eprintln!(
+                                "\x1b[33m↻\x1b[0m {} was stale, retrying as {}",
+                                selector, new_ref
+                            );
+                        }
+                        cmd.selector = Some(new_ref);
+                        resp = send_command(cmd, &flags.session)
+                            .unwrap_or_else(|e| exit_error(&e, flags.json));
+                    }
+                    Err(e) => {
+                        resp.error = Some(e);
+                    }
+                }
+            }
+        }
+    }
+    resp
+}
+
+/// Benchmark a command against the daemon
+fn run_bench(args: &[String], flags: &Flags) {
+    let cmd = parse_command(args, flags).unwrap_or_else(|e| exit_parse_error(&e, flags.json));
+    if flags.dry_run {
+        print_dry_run(&cmd, None, None, flags, &get_socket_path(&flags.session));
+        return;
+    }
+
+    if let Err(e) = ensure_daemon(
+        &flags.session,
+        flags.headed,
+        flags.executable_path.as_deref(),
+        &flags.extensions,
+    ) {
+        exit_error(&e, flags.json);
+    }
+
+    let iterations = flags
+        .value("--iterations")
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(bench::DEFAULT_ITERATIONS);
+    bench::run(args, &cmd, iterations, flags);
+}
+
+/// Start, inspect or stop a session pool
+fn run_pool_command(cmd: &CommandJson, flags: &Flags) {
+    let name = flags.pool.as_deref().unwrap_or("default");
+    let result = match cmd.action.as_str() {
+        "poolStart" => {
+            let size = flags
+                .value("--size")
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(pool::DEFAULT_SIZE);
+            let template = cmd.path.as_deref().map(absolute_path);
+            pool::start(name, size, template, flags).and_then(|_| pool::status(name))
+        }
+        "poolStop" => pool::stop(name).map(|p| serde_json::json!({"stopped": p.members.len()})),
+        _ => pool::status(name),
+    };
+
+    match result {
+        Ok(result) => print_response(&cmd.action, &Response::ok(&cmd.id, result), flags.json),
+        Err(e) => exit_error(&e, flags.json),
+    }
+}
+
+/// Install or remove a session's extensions, or open an extension popup
+fn run_extension_command(cmd: &CommandJson, flags: &Flags) {
+    let target = cmd.value.as_deref().unwrap_or("");
+    let result = match cmd.action.as_str() {
+        "extensionInstall" => extensions::install(target, flags),
+        "extensionRemove" => extensions::remove(target, flags),
+        _ => ensure_daemon(
+            &flags.session,
+            flags.headed,
+            flags.executable_path.as_deref(),
+            &flags.extensions,
+        )
+        .and_then(|_| extensions::popup(target, &flags.session)),
+    };
+
+    match result {
+        Ok(result) => print_response(&cmd.action, &Response::ok(&cmd.id, result), flags.json),
+        Err(e) => exit_error(&e, flags.json),
+    }
+}
+
+fn run_media_fake(cmd: &CommandJson, flags: &Flags) {
+    let off = cmd.enabled == Some(false);
+    match media::fake(cmd.video.as_deref(), cmd.audio.as_deref(), off, flags) {
+        Ok(result) => print_response(&cmd.action, &Response::ok(&cmd.id, result), flags.json),
+        Err(e) => exit_error(&e, flags.json),
+    }
+}
+
+/// Run a command script, stopping at the first failing step unless
+/// `--continue-on-error` is given
+fn run_script(cmd: &CommandJson, flags: &Flags) {
+    let path = cmd.path.as_deref().unwrap_or("");
+    let source = fs::read_to_string(path)
+        .unwrap_or_else(|e| exit_error(&format!("Failed to read {}: {}", path, e), flags.json));
+    let parsed = script::parse(&source)
+        .unwrap_or_else(|e| exit_error(&format!("{}: {}", path, e), flags.json));
+
+    if let Some(data) = flags.value("--data") {
+        let rows = dataset::load(data).unwrap_or_else(|e| exit_error(&e, flags.json));
+        let parallel = flags
+            .value("--parallel")
+            .and_then(|n| n.parse().ok())
+            .unwrap_or(1);
+        let report = dataset::run(&parsed, rows, parallel, flags);
+        save_run_report(&report, flags);
+        if flags.json {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&report).unwrap_or_default()
+            );
+        } else {
+            println!(
+                "{} passed, {} failed in {}ms",
+                report["passed"], report["failed"], report["durationMs"]
+            );
+        }
+        if report["success"] != true {
+            exit(1);
+        }
+        return;
+    }
+
+    let report = script::Runner {
+        session: &flags.session,
+        verbose: !flags.json,
+    }
+    .run(&parsed, flags);
+    let success = report["success"].as_bool().unwrap_or(false);
+    save_run_report(&report, flags);
+
+    if flags.json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&report).unwrap_or_default()
+        );
+    } else if let Some(error) = report["error"].as_str() {
+        eprintln!("\x1b[31m✗\x1b[0m {}", error);
+    } else if !success {
+        eprintln!(
+            "\x1b[31m✗\x1b[0m {}: {}",
+            path,
+            script::failure_summary(&report)
+        );
+    } else {
+        let steps = report["steps"].as_array().map(|s| s.len()).unwrap_or(0);
+        println!(
+            "\x1b[32m✓\x1b[0m {}",
+            i18n::tf(
+                "script_passed",
+                &[&steps.to_string(), &report["durationMs"].to_string()]
+            )
+        );
+    }
+    if !success {
+        exit(1);
+    }
+}
+
+/// Keep a script's report with the rest of the run's artifacts
+fn save_run_report(report: &serde_json::Value, flags: &Flags) {
+    let dir = match flags.run_dir {
+        Some(ref dir) => dir,
+        None => return,
+    };
+    let path = Path::new(dir).join("report.json");
+    let saved = fs::create_dir_all(dir)
+        .and_then(|_| {
+            fs::write(
+                &path,
+                serde_json::to_string_pretty(report).unwrap_or_default(),
+            )
+        })
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+        .and_then(|_| {
+            artifacts::add(
+                dir,
+                &path.to_string_lossy(),
+                "report",
+                "runScript",
+                &flags.session,
+            )
+        });
+    if let Err(e) = saved {
+        exit_error(&e, flags.json);
+    }
+}
+
+/// Compare the translation catalogs with the registry; exits 1 if any is
+/// incomplete
+fn run_i18n_check(lang: Option<&str>, flags: &Flags) {
+    let report = i18n::check(lang);
+    if flags.json {
+        println!("{}", report);
+    } else {
+        i18n::print_check(&report);
+    }
+    let complete = report
+        .as_object()
+        .into_iter()
+        .flatten()
+        .all(|(_, result)| result["complete"] == true);
+    if !complete {
+        exit(1);
+    }
+}
+
+/// Run `if visible ... then ... [else ...]` as a one-line script and print
+/// the chosen command's response as if it had been run directly
+fn run_if(args: &[String], flags: &Flags) {
+    let step = script::parse_if(0, args).unwrap_or_else(|e| exit_error(&e, flags.json));
+    let report = script::Runner {
+        session: &flags.session,
+        verbose: false,
+    }
+    .run(
+        &script::Script {
+            steps: vec![step],
+            hooks: Vec::new(),
+        },
+        flags,
+    );
+
+    let steps = report["steps"].as_array().cloned().unwrap_or_default();
+    let last = steps.last().cloned().unwrap_or_default();
+    if steps.len() == 1 && last["response"]["success"] == true {
+        // No branch for how the condition came out
+        if !flags.json {
+            println!("Condition not met; nothing ran");
+            return;
+        }
+        let resp = Response::ok("1", serde_json::json!({"branch": null}));
+        print_response("if", &resp, flags.json);
+        return;
+    }
+    let resp: Response = serde_json::from_value(last["response"].clone())
+        .unwrap_or_else(|e| exit_error(&format!("Invalid response: {}", e), flags.json));
+    // Render it as the chosen command's own output
+    let action = last["command"]
+        .as_str()
+        .and_then(|text| commands::split_words(text).ok())
+        .and_then(|args| parse_command(&args, flags).ok())
+        .map(|cmd| cmd.action)
+        .unwrap_or_default();
+    print_response(&action, &resp, flags.json);
+    if !resp.success {
+        exit(1);
+    }
+}
+
+/// Process jobs from a queue until interrupted
+fn run_worker(flags: &Flags) {
+    let queue = flags
+        .value("--queue")
+        .map(worker::Queue::parse)
+        .unwrap_or_else(|| Err("Missing --queue".to_string()))
+        .unwrap_or_else(|e| exit_error(&e, flags.json));
+    let concurrency = flags
+        .value("--concurrency")
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(worker::DEFAULT_CONCURRENCY);
+    worker::run(queue, concurrency, flags.has("--drain"), flags)
+        .unwrap_or_else(|e| exit_error(&e, flags.json));
+}
+
+/// Crawl from a seed URL, or carry on a crawl saved with `--state`
+fn run_crawl(cmd: &CommandJson, flags: &Flags) {
+    let (mut state, state_path) = match cmd.url {
+        Some(ref seed) => {
+            let number = |name: &str, default: usize| {
+                flags
+                    .value(name)
+                    .and_then(|v| v.parse().ok())
+                    .unwrap_or(default)
+            };
+            let options = crawl::Options {
+                max_pages: number("--max-pages", crawl::DEFAULT_MAX_PAGES),
+                max_depth: number("--max-depth", crawl::DEFAULT_MAX_DEPTH),
+                all_origins: flags.has("--all-origins"),
+                delay_ms: number("--delay", 0) as u64,
+                dedupe: flags.value("--dedupe").and_then(crawl::Dedupe::parse),
+            };
+            let mut state = crawl::State::new(seed, options);
+            if flags.has("--seed-from-sitemap") {
+                let seeded = state.seed_from_sitemap(flags.value("--delay").is_some());
+                if flags.json {
+                    println!("{}", seeded);
+                } else {
+                    println!(
+                        "Seeded {} URL(s) from {}{}",
+                        seeded["seeded"],
+                        seeded["sitemaps"]
+                            .as_array()
+                            .map(|s| s.iter().filter_map(|s| s.as_str()).collect::<Vec<_>>())
+                            .unwrap_or_default()
+                            .join(", "),
+                        match (seeded["crawlDelay"].as_f64(), flags.value("--delay")) {
+                            (Some(delay), None) => format!(" (Crawl-delay: {}s)", delay),
+                            _ => String::new(),
+                        }
+                    );
+                }
+            }
+            (state, cmd.path.clone())
+        }
+        None => {
+            let path = cmd.path.clone().unwrap_or_default();
+            let mut state =
+                crawl::State::load(&path).unwrap_or_else(|e| exit_error(&e, flags.json));
+            // Limits given on resume replace the saved ones
+            if let Some(n) = flags.value("--max-pages").and_then(|v| v.parse().ok()) {
+                state.options.max_pages = n;
+            }
+            if let Some(ms) = flags.value("--delay").and_then(|v| v.parse().ok()) {
+                state.options.delay_ms = ms;
+            }
+            (state, Some(path))
+        }
     };
 
-    // Send command and print response
-    match send_command(&cmd, &flags.session) {
-        Ok(resp) => {
-            let success = resp.success;
-            print_response(&resp, flags.json);
-            if !success {
-                exit(1);
+    let summary = crawl::run(&mut state, state_path.as_deref(), flags)
+        .unwrap_or_else(|e| exit_error(&e, flags.json));
+    if flags.json {
+        println!("{}", summary);
+    } else if summary["done"] == true {
+        println!("Crawled {} page(s)", summary["crawled"]);
+    } else {
+        let more = if summary["crawled"].as_u64() >= Some(state.options.max_pages as u64) {
+            " --max-pages=<more>"
+        } else {
+            ""
+        };
+        println!(
+            "Crawled {} page(s), {} still queued{}",
+            summary["crawled"],
+            summary["queued"],
+            state_path
+                .map(|p| format!("; continue with 'crawl --resume {}{}'", p, more))
+                .unwrap_or_default()
+        );
+    }
+}
+
+/// Check a value in the JSON piped in, such as a command's `--json` output
+fn run_expect_json(args: &[String], flags: &Flags) {
+    let expectation = assertions::JsonExpectation::parse(args)
+        .unwrap_or_else(|e| exit_parse_error(&e, flags.json));
+    if unsafe { libc::isatty(libc::STDIN_FILENO) } == 1 {
+        exit_error(
+            "expect json checks JSON piped in (e.g. from '--json eval ...') \
+             or the previous step of a 'run' script",
+            flags.json,
+        );
+    }
+    let mut input = String::new();
+    std::io::Read::read_to_string(&mut std::io::stdin(), &mut input)
+        .unwrap_or_else(|e| exit_error(&format!("Failed to read stdin: {}", e), flags.json));
+    let value: serde_json::Value = serde_json::from_str(&input)
+        .unwrap_or_else(|e| exit_error(&format!("Input is not JSON: {}", e), flags.json));
+    // A response envelope is checked by its result, like in a script
+    let root = match value.get("success").and_then(|v| v.as_bool()) {
+        Some(false) => exit_error(
+            &format!(
+                "Input is a failed response: {}",
+                value["error"].as_str().unwrap_or("unknown error")
+            ),
+            flags.json,
+        ),
+        Some(true) => value.get("result").cloned().unwrap_or_default(),
+        None => value,
+    };
+
+    let result = expectation
+        .check(&root)
+        .unwrap_or_else(|e| exit_error(&e, flags.json));
+    let resp = Response::ok("1", result);
+    print_response("expectJson", &resp, flags.json);
+}
+
+/// Save a page snapshot, or compare the page with one (exit 1 if it changed)
+fn run_pagediff(cmd: &CommandJson, flags: &Flags) {
+    let name = cmd.value.as_deref().unwrap_or_default();
+    let result = if cmd.action == "pagediffSave" {
+        pagediff::save(name, cmd.selector.as_deref(), flags)
+    } else {
+        let mode = flags
+            .value("--mode")
+            .and_then(pagediff::Mode::parse)
+            .unwrap_or(pagediff::Mode::Text);
+        pagediff::compare(name, mode, flags.has("--update"), flags)
+    }
+    .unwrap_or_else(|e| exit_error(&e, flags.json));
+    if flags.json {
+        println!("{}", result);
+    } else {
+        pagediff::print(&result);
+    }
+    if result["changed"] == true {
+        exit(1);
+    }
+}
+
+/// Diff an element's text against its last reading or a file (exit 1 if it
+/// changed)
+fn run_textdiff(cmd: &CommandJson, flags: &Flags) {
+    let unit = flags
+        .value("--by")
+        .and_then(textdiff::Unit::parse)
+        .unwrap_or(textdiff::Unit::Word);
+    let result = textdiff::run(
+        cmd.selector.as_deref().unwrap_or_default(),
+        flags.value("--against").unwrap_or("last"),
+        unit,
+        flags.has("--update"),
+        flags,
+    )
+    .unwrap_or_else(|e| exit_error(&e, flags.json));
+    if flags.json {
+        println!("{}", result);
+    } else {
+        textdiff::print(&result);
+    }
+    if result["changed"] == true {
+        exit(1);
+    }
+}
+
+/// Run a plugin's command and print what it returns
+fn run_plugin(cmd: &CommandJson, args: &[String], flags: &Flags) {
+    let name = cmd.value.as_deref().unwrap_or_default();
+    let result = plugins::run(name, args, flags).unwrap_or_else(|e| exit_error(&e, flags.json));
+    print_response(&cmd.action, &Response::ok(&cmd.id, result), flags.json);
+}
+
+/// Detect the language of the page or an element, or translate its text
+/// through the configured hook
+fn run_language(cmd: &CommandJson, hook: Option<&str>, flags: &Flags) {
+    let selector = cmd.selector.as_deref();
+    let result = if cmd.action == "detectLanguage" {
+        language::detect_page(selector, flags)
+    } else {
+        flags
+            .value("--with")
+            .or(hook)
+            .ok_or_else(|| {
+                "No translate hook configured; set translate = \"<command or URL>\" in \
+                 config.toml or pass --with"
+                    .to_string()
+            })
+            .and_then(|hook| {
+                language::translate_page(
+                    selector,
+                    flags.value("--from"),
+                    flags.value("--to").unwrap_or_default(),
+                    hook,
+                    flags,
+                )
+            })
+    }
+    .unwrap_or_else(|e| exit_error(&e, flags.json));
+    print_response(&cmd.action, &Response::ok(&cmd.id, result), flags.json);
+}
+
+/// Open the current page for a person to take over
+fn run_handoff(flags: &Flags) {
+    let result = handoff::run(flags.has("--print"), flags.has("--cookies"), flags)
+        .unwrap_or_else(|e| exit_error(&e, flags.json));
+    if flags.json {
+        println!("{}", result);
+    } else {
+        handoff::print(&result);
+    }
+}
+
+/// Print the key `state save --encrypt` seals with, for a CI secret
+fn run_state_key(cmd: &CommandJson, flags: &Flags) {
+    let key = vault::key().unwrap_or_else(|e| exit_error(&e, flags.json));
+    print_response(
+        &cmd.action,
+        &Response::ok(&cmd.id, serde_json::json!({"key": BASE64.encode(key)})),
+        flags.json,
+    );
+}
+
+/// Manage change monitors, or run them until interrupted
+fn run_monitor(cmd: &CommandJson, flags: &Flags) {
+    let fail = |e: String| -> ! { exit_error(&e, flags.json) };
+    match cmd.action.as_str() {
+        "monitorAdd" => {
+            let interval = flags
+                .value("--interval")
+                .unwrap_or(monitor::DEFAULT_INTERVAL);
+            let mode = flags
+                .value("--mode")
+                .and_then(pagediff::Mode::parse)
+                .unwrap_or(pagediff::Mode::Text);
+            let target = monitor::add(
+                cmd.url.as_deref().unwrap_or_default(),
+                cmd.selector.as_deref(),
+                cache::parse_ttl(interval).unwrap_or_default(),
+                mode,
+                flags.value("--notify"),
+            )
+            .unwrap_or_else(|e| fail(e));
+            if flags.json {
+                println!("{}", serde_json::to_string(&target).unwrap_or_default());
+            } else {
+                println!(
+                    "\x1b[32m✓\x1b[0m Monitor [{}] watches {} every {}",
+                    target.id, target.url, interval
+                );
             }
         }
-        Err(e) => {
+        "monitorList" => {
+            let targets = monitor::list().unwrap_or_else(|e| fail(e));
             if flags.json {
-                println!(r#"{{"success":false,"error":"{}"}}"#, e);
+                println!("{}", serde_json::to_string(&targets).unwrap_or_default());
             } else {
-                eprintln!("\x1b[31m✗\x1b[0m {}", e);
+                monitor::print_list(&targets);
+            }
+        }
+        "monitorRemove" => {
+            let target = monitor::remove(cmd.value.as_deref().unwrap_or_default())
+                .unwrap_or_else(|e| fail(e));
+            if flags.json {
+                println!("{}", serde_json::to_string(&target).unwrap_or_default());
+            } else {
+                println!(
+                    "\x1b[32m✓\x1b[0m Removed monitor [{}] {}",
+                    target.id, target.url
+                );
             }
-            exit(1);
         }
+        _ => monitor::run(flags.has("--once"), flags).unwrap_or_else(|e| fail(e)),
     }
 }
 
-/// Remove flags from arguments
-fn clean_args(args: &[String]) -> Vec<String> {
-    args.iter()
-        .filter(|a| !a.starts_with("--") && !a.starts_with("-"))
-        .cloned()
-        .collect()
+/// Ask the daemon for its counters and add process usage from the OS.
+/// Never starts a daemon: stats for one that isn't running are an error.
+fn print_daemon_stats(cmd: &CommandJson, flags: &Flags) {
+    let pid = daemon_pid(&flags.session).unwrap_or_else(|| {
+        exit_error(
+            &format!("No daemon running for session '{}'", flags.session),
+            flags.json,
+        )
+    });
+
+    let mut resp = send_command(cmd, &flags.session).unwrap_or_else(|e| exit_error(&e, flags.json));
+    if let Some(result) = resp.result.as_mut().and_then(|r| r.as_object_mut()) {
+        if let serde_json::Value::Object(usage) = procinfo::report(pid) {
+            result.extend(usage);
+        }
+    }
+    print_response(&cmd.action, &resp, flags.json);
+    if !resp.success {
+        exit(1);
+    }
+}
+
+/// Run a `--wait-before`/`--wait-after` step, exiting if it fails
+fn run_wait_step(wait: &CommandJson, flags: &Flags) {
+    let resp = send_command(wait, &flags.session).unwrap_or_else(|e| exit_error(&e, flags.json));
+    if !resp.success {
+        print_response(&wait.action, &resp, flags.json);
+        exit(1);
+    }
+}
+
+/// Resolve a user-supplied path against the current directory, since the
+/// daemon may be running from somewhere else
+fn absolute_path(path: &str) -> String {
+    let path = Path::new(path);
+    if path.is_absolute() {
+        return path.to_string_lossy().into_owned();
+    }
+    env::current_dir()
+        .map(|dir| dir.join(path))
+        .unwrap_or_else(|_| path.to_path_buf())
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// Report a parse error and exit
+fn exit_parse_error(e: &ParseError, json: bool) -> ! {
+    if json {
+        let mut out = serde_json::json!({
+            "success": false,
+            "error": e.format().replace('\n', " "),
+            "type": e.code(),
+        });
+        if let ParseError::UnknownCommand {
+            suggestion: Some(suggestion),
+            ..
+        } = e
+        {
+            out["suggestions"] = serde_json::json!([suggestion]);
+        }
+        println!("{}", out);
+    } else {
+        eprintln!("\x1b[31m✗\x1b[0m {}", e.format());
+    }
+    exit(1);
+}
+
+/// Report a connection/daemon error and exit
+fn exit_error(e: &str, json: bool) -> ! {
+    if json {
+        println!("{}", serde_json::json!({ "success": false, "error": e }));
+    } else {
+        eprintln!("\x1b[31m✗\x1b[0m {}", e);
+    }
+    exit(1);
 }
 
 /// Start the daemon process
 fn start_daemon(flags: &Flags) {
-    println!("Starting AgentBrowser Pro daemon (session: {})...", flags.session);
+    println!(
+        "Starting AgentBrowser Pro daemon (session: {})...",
+        flags.session
+    );
 
-    // Get path to Node.js daemon
-    let daemon_path = find_daemon_path().expect("Could not find daemon script");
+    // Get path to Node.js daemon, or serve natively without one
+    let daemon_path = find_daemon_path().filter(|_| !native::requested());
+    let daemon_path = match daemon_path {
+        Some(path) if native::node_available() => path,
+        _ => {
+            native::serve(flags).unwrap_or_else(|e| exit_error(&e, flags.json));
+            return;
+        }
+    };
 
     let mut cmd = Command::new("node");
     cmd.arg(&daemon_path)
@@ -147,6 +1324,10 @@ fn start_daemon(flags: &Flags) {
         cmd.env("AGENT_BROWSER_EXECUTABLE_PATH", path);
     }
 
+    if !flags.extensions.is_empty() {
+        cmd.env("AGENT_BROWSER_EXTENSIONS", flags.extensions.join(","));
+    }
+
     // Run in foreground for daemon command
     let status = cmd
         .stdin(Stdio::inherit())
@@ -0,0 +1,232 @@
+/*!
+ * Markdown to HTML
+ *
+ * `write` pastes formatted content into rich-text editors as HTML, which
+ * they all know how to import. This converts the common subset people
+ * type into a command line: ATX headings, paragraphs, `-`/`*`/`+` and
+ * numbered lists, `>` quotes, fenced code, `---` rules, and inline
+ * `**bold**`, `*italic*`, `~~strike~~`, `` `code` `` and `[links](url)`.
+ * Anything else passes through as text.
+ */
+
+/// Convert markdown to HTML. Returns `None` when the text has no markup
+/// beyond paragraphs, so it can be typed instead of pasted.
+pub fn to_html(source: &str) -> Option<String> {
+    let mut html = String::new();
+    let mut formatted = false;
+    let lines: Vec<&str> = source.lines().collect();
+    let mut i = 0;
+
+    while i < lines.len() {
+        let line = lines[i].trim_end();
+        let trimmed = line.trim_start();
+
+        if trimmed.is_empty() {
+            i += 1;
+            continue;
+        }
+
+        if let Some(lang) = trimmed.strip_prefix("```") {
+            formatted = true;
+            let mut code = Vec::new();
+            i += 1;
+            while i < lines.len() && !lines[i].trim_start().starts_with("```") {
+                code.push(escape(lines[i]));
+                i += 1;
+            }
+            i += 1;
+            let class = match lang.trim() {
+                "" => String::new(),
+                lang => format!(" class=\"language-{}\"", escape(lang)),
+            };
+            html.push_str(&format!(
+                "<pre><code{}>{}</code></pre>",
+                class,
+                code.join("\n")
+            ));
+            continue;
+        }
+
+        if let Some(level) = heading_level(trimmed) {
+            formatted = true;
+            let text = trimmed[level..].trim().trim_end_matches('#').trim_end();
+            html.push_str(&format!("<h{0}>{1}</h{0}>", level, inline(text).0));
+            i += 1;
+            continue;
+        }
+
+        if trimmed.len() >= 3
+            && trimmed.chars().all(|c| c == '-' || c == '*' || c == '_')
+            && trimmed
+                .chars()
+                .all(|c| c == trimmed.chars().next().unwrap_or('-'))
+        {
+            formatted = true;
+            html.push_str("<hr>");
+            i += 1;
+            continue;
+        }
+
+        if trimmed.starts_with('>') {
+            formatted = true;
+            let mut quoted = Vec::new();
+            while i < lines.len() {
+                match lines[i].trim_start().strip_prefix('>') {
+                    Some(rest) => quoted.push(rest.strip_prefix(' ').unwrap_or(rest)),
+                    None => break,
+                }
+                i += 1;
+            }
+            let inner = to_html(&quoted.join("\n")).unwrap_or_else(|| paragraphs(&quoted));
+            html.push_str(&format!("<blockquote>{}</blockquote>", inner));
+            continue;
+        }
+
+        if let Some(ordered) = list_item(trimmed).map(|(ordered, _)| ordered) {
+            formatted = true;
+            let tag = if ordered { "ol" } else { "ul" };
+            html.push_str(&format!("<{}>", tag));
+            while i < lines.len() {
+                match list_item(lines[i].trim()) {
+                    Some((o, text)) if o == ordered => {
+                        html.push_str(&format!("<li>{}</li>", inline(text).0));
+                    }
+                    _ => break,
+                }
+                i += 1;
+            }
+            html.push_str(&format!("</{}>", tag));
+            continue;
+        }
+
+        // A paragraph runs until a blank line or another block starts
+        let mut text = Vec::new();
+        while i < lines.len() {
+            let next = lines[i].trim();
+            let starts_block = next.starts_with("```")
+                || next.starts_with('>')
+                || heading_level(next).is_some()
+                || list_item(next).is_some();
+            if next.is_empty() || (!text.is_empty() && starts_block) {
+                break;
+            }
+            text.push(next);
+            i += 1;
+        }
+        let (body, marked) = inline(&text.join(" "));
+        formatted |= marked;
+        html.push_str(&format!("<p>{}</p>", body));
+    }
+
+    formatted.then_some(html)
+}
+
+fn paragraphs(lines: &[&str]) -> String {
+    lines
+        .iter()
+        .filter(|l| !l.trim().is_empty())
+        .map(|l| format!("<p>{}</p>", escape(l.trim())))
+        .collect()
+}
+
+fn heading_level(line: &str) -> Option<usize> {
+    let level = line.chars().take_while(|&c| c == '#').count();
+    let rest = &line[level..];
+    ((1..=6).contains(&level) && (rest.is_empty() || rest.starts_with(' '))).then_some(level)
+}
+
+/// `(ordered, text)` for a list item line
+fn list_item(line: &str) -> Option<(bool, &str)> {
+    for marker in ["- ", "* ", "+ "] {
+        if let Some(text) = line.strip_prefix(marker) {
+            return Some((false, text));
+        }
+    }
+    let digits = line.chars().take_while(|c| c.is_ascii_digit()).count();
+    if digits > 0 {
+        let rest = &line[digits..];
+        if let Some(text) = rest.strip_prefix(". ").or_else(|| rest.strip_prefix(") ")) {
+            return Some((true, text));
+        }
+    }
+    None
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Inline markup to HTML, and whether there was any
+fn inline(text: &str) -> (String, bool) {
+    let mut out = String::new();
+    let mut marked = false;
+    let mut rest = text;
+    // Intraword underscores (snake_case) aren't emphasis
+    let mut after_word = false;
+
+    while let Some(c) = rest.chars().next() {
+        // `code`
+        if c == '`' {
+            if let Some(end) = rest[1..].find('`') {
+                out.push_str(&format!("<code>{}</code>", escape(&rest[1..1 + end])));
+                rest = &rest[end + 2..];
+                marked = true;
+                continue;
+            }
+        }
+        // [text](url)
+        if c == '[' {
+            if let Some((label, url, len)) = link(rest) {
+                out.push_str(&format!(
+                    "<a href=\"{}\">{}</a>",
+                    escape(url),
+                    inline(label).0
+                ));
+                rest = &rest[len..];
+                marked = true;
+                continue;
+            }
+        }
+        // **bold**, __bold__, ~~strike~~, *italic*, _italic_
+        let spans = [
+            ("**", "strong"),
+            ("__", "strong"),
+            ("~~", "s"),
+            ("*", "em"),
+            ("_", "em"),
+        ];
+        if let Some((inner, tag, len)) = spans.iter().find_map(|(delim, tag)| {
+            if after_word && delim.starts_with('_') {
+                return None;
+            }
+            let body = rest.strip_prefix(delim)?;
+            let end = body.find(delim)?;
+            let inner = &body[..end];
+            // Not `a * b * c`
+            let spaced = inner.starts_with(' ') || inner.ends_with(' ');
+            (!inner.is_empty() && !spaced).then_some((inner, *tag, delim.len() * 2 + end))
+        }) {
+            out.push_str(&format!("<{0}>{1}</{0}>", tag, inline(inner).0));
+            rest = &rest[len..];
+            marked = true;
+            continue;
+        }
+        out.push_str(&escape(&rest[..c.len_utf8()]));
+        rest = &rest[c.len_utf8()..];
+        after_word = c.is_alphanumeric();
+    }
+    (out, marked)
+}
+
+/// `[label](url)` at the start of `text`: label, url and total length
+fn link(text: &str) -> Option<(&str, &str, usize)> {
+    let close = text.find("](")?;
+    let label = &text[1..close];
+    let after = &text[close + 2..];
+    let end = after.find(')')?;
+    let url = &after[..end];
+    (!label.is_empty() && !url.contains(' ')).then_some((label, url, close + 3 + end))
+}
@@ -0,0 +1,98 @@
+/*!
+ * Fake Media Devices
+ *
+ * `media fake` gives a session a fake camera and microphone so flows that
+ * call getUserMedia (video KYC, conferencing) run headlessly. Chromium only
+ * takes fake devices as launch flags, so the setting is saved per session,
+ * handed to the daemon in AGENT_BROWSER_FAKE_MEDIA, and changing it restarts
+ * a running daemon with its state, like `extension install`. Without files
+ * Chromium's built-in test pattern and beep are used.
+ */
+use std::env;
+use std::fs;
+
+use serde_json::{json, Value};
+
+use crate::connection::daemon_pid;
+use crate::flags::Flags;
+use crate::recycle;
+
+/// Environment variable the daemon reads its fake media settings from
+pub const ENV_VAR: &str = "AGENT_BROWSER_FAKE_MEDIA";
+
+/// Get the fake media settings path for a session
+fn get_settings_path(session: &str) -> String {
+    env::temp_dir()
+        .join(format!("agentbrowser-pro-{}.media.json", session))
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// The session's fake media settings, `{video?, audio?}`, if enabled
+pub fn configured(session: &str) -> Option<Value> {
+    fs::read_to_string(get_settings_path(session))
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+}
+
+/// Check a capture file exists and has a format Chromium can play, and make
+/// it absolute since the daemon does not share the CLI's working directory
+fn validate(path: &str, extensions: &[&str], what: &str) -> Result<String, String> {
+    let file =
+        fs::canonicalize(path).map_err(|_| format!("{} file '{}' does not exist", what, path))?;
+    let ext = file
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    if !extensions.contains(&ext.as_str()) {
+        return Err(format!(
+            "{} file '{}' must be {}",
+            what,
+            path,
+            extensions
+                .iter()
+                .map(|e| format!(".{}", e))
+                .collect::<Vec<_>>()
+                .join(" or ")
+        ));
+    }
+    Ok(file.to_string_lossy().into_owned())
+}
+
+/// Turn fake devices on (optionally with capture files) or off
+pub fn fake(
+    video: Option<&str>,
+    audio: Option<&str>,
+    off: bool,
+    flags: &Flags,
+) -> Result<Value, String> {
+    let path = get_settings_path(&flags.session);
+    let settings = if off {
+        fs::remove_file(&path).ok();
+        env::remove_var(ENV_VAR);
+        Value::Null
+    } else {
+        let settings = json!({
+            "video": video.map(|v| validate(v, &["y4m", "mjpeg"], "Video")).transpose()?,
+            "audio": audio.map(|a| validate(a, &["wav"], "Audio")).transpose()?,
+        });
+        fs::write(&path, settings.to_string())
+            .map_err(|e| format!("Failed to save fake media settings: {}", e))?;
+        env::set_var(ENV_VAR, settings.to_string());
+        settings
+    };
+
+    // Restart so the browser relaunches with (or without) the flags
+    let restarted = if daemon_pid(&flags.session).is_some() {
+        recycle::restart(flags)?;
+        true
+    } else {
+        false
+    };
+    Ok(json!({
+        "fake": !off,
+        "video": settings["video"],
+        "audio": settings["audio"],
+        "restarted": restarted,
+    }))
+}
@@ -0,0 +1,62 @@
+/*!
+ * Message Tap
+ *
+ * Embedded widgets (payment forms, auth iframes) talk to the page hosting
+ * them with window.postMessage and BroadcastChannel, which nothing else
+ * shows. `messages tap` has the daemon record that traffic in every frame;
+ * `--follow` keeps printing new messages until interrupted.
+ */
+use std::thread;
+use std::time::Duration;
+
+use serde_json::Value;
+
+use crate::commands::CommandJson;
+use crate::connection::send_command;
+use crate::flags::Flags;
+
+/// How often `messages tap --follow` polls the daemon
+const FOLLOW_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Print messages as they are recorded until interrupted
+pub fn follow(channel: Option<&str>, flags: &Flags) -> Result<(), String> {
+    let mut cmd = CommandJson::new("tapMessages");
+    cmd.channel = channel.map(String::from);
+    loop {
+        let resp = send_command(&cmd, &flags.session)?;
+        if !resp.success {
+            return Err(resp
+                .error
+                .unwrap_or_else(|| "Message tap failed".to_string()));
+        }
+        let messages = resp
+            .result
+            .and_then(|r| r.get("messages").and_then(|m| m.as_array()).cloned())
+            .unwrap_or_default();
+        for message in &messages {
+            if flags.json {
+                println!("{}", message);
+            } else {
+                print_message(message);
+            }
+            let next = message["timestamp"].as_u64().unwrap_or(0) + 1;
+            cmd.since = Some(cmd.since.unwrap_or(0).max(next));
+        }
+        thread::sleep(FOLLOW_INTERVAL);
+    }
+}
+
+/// One message: where it was seen, who sent it, then the data
+pub fn print_message(message: &Value) {
+    let text = |key: &str| message[key].as_str().unwrap_or("");
+    let source = match message["kind"].as_str() {
+        Some("broadcast") => format!("broadcast:{}", text("channel")),
+        _ => format!("from {}", text("origin")),
+    };
+    println!(
+        "\x1b[36m{}\x1b[0m \x1b[90m→ {}\x1b[0m\n  {}",
+        source,
+        text("frameUrl"),
+        text("data")
+    );
+}
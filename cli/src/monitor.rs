@@ -0,0 +1,395 @@
+/*!
+ * Change Monitoring
+ *
+ * `monitor add <url>` registers a page (or `--selector` element) to watch,
+ * kept in `~/.agentbrowser-pro/monitors.json`. `monitor run` stays up and
+ * checks each target once its `--interval` has passed: the first check
+ * saves a pagediff snapshot, later ones compare against it and move it
+ * forward, so each change is reported once. Checks run in a browser
+ * session of their own, `<session>-monitor`, so they never navigate the
+ * page an agent is working on, and each target is held to the policy and
+ * URL rules `navigate` is.
+ *
+ * On a change, `--notify` is told about it with the check's JSON:
+ * - an http(s) URL gets it POSTed as the request body, by curl run
+ *   directly rather than through a shell
+ * - anything else runs as a shell command with it on stdin, and
+ *   MONITOR_ID / MONITOR_URL set
+ */
+use std::fs;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+use std::process::Command;
+use std::thread;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::commands::CommandJson;
+use crate::config::data_dir;
+use crate::connection::{ensure_daemon, send_command};
+use crate::flags::Flags;
+use crate::hooks::pipe_through;
+use crate::i18n::{t, tf};
+use crate::pagediff::{self, Mode};
+use crate::pageerrors::now_ms;
+use crate::policy;
+
+pub const DEFAULT_INTERVAL: &str = "10m";
+
+/// Longest `monitor run` sleeps before re-reading the target list, so
+/// targets added or removed meanwhile are picked up
+const MAX_IDLE: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Target {
+    pub id: String,
+    pub url: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub selector: Option<String>,
+    pub interval_ms: u64,
+    pub mode: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notify: Option<String>,
+    pub created_at: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_check: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_change: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+}
+
+impl Target {
+    /// Name of the pagediff snapshot the target is compared against
+    fn snapshot(&self) -> String {
+        format!("monitor-{}", self.id)
+    }
+
+    fn due(&self, now: u64) -> bool {
+        self.last_check
+            .map(|at| now.saturating_sub(at) >= self.interval_ms)
+            .unwrap_or(true)
+    }
+}
+
+fn targets_path() -> Result<PathBuf, String> {
+    Ok(data_dir()
+        .ok_or_else(|| "Cannot locate home directory for monitor storage".to_string())?
+        .join("monitors.json"))
+}
+
+pub fn list() -> Result<Vec<Target>, String> {
+    let path = targets_path()?;
+    match fs::read_to_string(&path) {
+        Ok(content) => serde_json::from_str(&content)
+            .map_err(|e| format!("Failed to parse {}: {}", path.display(), e)),
+        Err(_) => Ok(Vec::new()),
+    }
+}
+
+fn save(targets: &[Target]) -> Result<(), String> {
+    let path = targets_path()?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)
+            .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    }
+    let tmp = path.with_extension("json.tmp");
+    let json = serde_json::to_string_pretty(targets).unwrap_or_default();
+    fs::write(&tmp, json)
+        .and_then(|_| fs::rename(&tmp, &path))
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+/// Register a new target and return it
+pub fn add(
+    url: &str,
+    selector: Option<&str>,
+    interval: Duration,
+    mode: Mode,
+    notify: Option<&str>,
+) -> Result<Target, String> {
+    let mut targets = list()?;
+    let id = targets
+        .iter()
+        .filter_map(|t| t.id.parse::<u64>().ok())
+        .max()
+        .unwrap_or(0)
+        + 1;
+    let target = Target {
+        id: id.to_string(),
+        url: url.to_string(),
+        selector: selector.map(String::from),
+        interval_ms: interval.as_millis() as u64,
+        mode: mode.as_str().to_string(),
+        notify: notify.map(String::from),
+        created_at: now_ms(),
+        last_check: None,
+        last_change: None,
+        last_error: None,
+    };
+    targets.push(target.clone());
+    save(&targets)?;
+    Ok(target)
+}
+
+/// Stop watching target `id` and drop its snapshot
+pub fn remove(id: &str) -> Result<Target, String> {
+    let mut targets = list()?;
+    let index = targets
+        .iter()
+        .position(|t| t.id == id)
+        .ok_or_else(|| format!("No monitor with id '{}'; see 'monitor list'", id))?;
+    let target = targets.remove(index);
+    save(&targets)?;
+    pagediff::remove(&target.snapshot());
+    Ok(target)
+}
+
+/// Load the target's page and compare it with the last check
+fn check(target: &Target, flags: &Flags) -> Result<Value, String> {
+    policy::check_navigation(&target.url, flags).map_err(|violation| violation.message())?;
+    let mut navigate = CommandJson::new("navigate");
+    navigate.url = Some(target.url.clone());
+    navigate.timeout = flags.timeout.or(flags.default_timeout);
+    let resp = send_command(&navigate, &flags.session)?;
+    if !resp.success {
+        return Err(resp
+            .error
+            .unwrap_or_else(|| format!("Failed to load {}", target.url)));
+    }
+
+    let name = target.snapshot();
+    let selector = target.selector.as_deref();
+    if !pagediff::exists(&name) {
+        pagediff::save(&name, selector, flags)?;
+        return Ok(json!({"baseline": true, "changed": false}));
+    }
+    let mode = Mode::parse(&target.mode).unwrap_or(Mode::Text);
+    pagediff::compare(&name, mode, true, flags)
+}
+
+/// Send a change to the target's `--notify` webhook or command
+fn notify(target: &Target, event: &Value) -> Result<(), String> {
+    let notify = match target.notify {
+        Some(ref notify) => notify,
+        None => return Ok(()),
+    };
+    let webhook = notify.starts_with("http://") || notify.starts_with("https://");
+    let mut command = if webhook {
+        // -q skips ~/.curlrc, so only these arguments apply
+        let mut curl = Command::new("curl");
+        curl.args(["-q", "-fsS", "--proto", "=http,https", "--max-time", "30"])
+            .args(["-o", "/dev/null"])
+            .args([
+                "-H",
+                "Content-Type: application/json",
+                "--data-binary",
+                "@-",
+            ])
+            .arg(notify);
+        curl
+    } else {
+        let mut sh = Command::new("sh");
+        sh.arg("-c")
+            .arg(notify)
+            .env("MONITOR_ID", &target.id)
+            .env("MONITOR_URL", &target.url);
+        sh
+    };
+    let output = pipe_through(&mut command, event.to_string().into_bytes()).map_err(|e| {
+        if webhook && e.kind() == ErrorKind::NotFound {
+            format!(
+                "curl is needed to POST to {}; install it or give --notify a command",
+                notify
+            )
+        } else {
+            format!("Failed to run notifier: {}", e)
+        }
+    })?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(format!("Notifier exited with {}", output.status))
+    }
+}
+
+/// The monitor's own session, `<session>-monitor`
+fn monitor_flags(flags: &Flags) -> Flags {
+    Flags {
+        session: format!("{}-monitor", flags.session),
+        ..flags.clone()
+    }
+}
+
+/// Check targets as they come due, printing a line per check. With `once`,
+/// every target is checked right away and the run ends.
+pub fn run(once: bool, flags: &Flags) -> Result<(), String> {
+    let flags = &monitor_flags(flags);
+    ensure_daemon(
+        &flags.session,
+        flags.headed,
+        flags.executable_path.as_deref(),
+        &flags.extensions,
+    )?;
+    loop {
+        let now = now_ms();
+        let targets = list()?;
+        if targets.is_empty() && once {
+            return Err("No monitors; add one with 'monitor add <url>'".to_string());
+        }
+        for target in targets.iter().filter(|t| once || t.due(now)) {
+            let checked_at = now_ms();
+            let mut event = json!({
+                "id": target.id,
+                "url": target.url,
+                "checkedAt": checked_at,
+            });
+            match check(target, flags) {
+                Ok(Value::Object(result)) => {
+                    for (key, value) in result {
+                        if !matches!(key.as_str(), "name" | "url" | "savedAt" | "savedUrl") {
+                            event[key] = value;
+                        }
+                    }
+                }
+                Ok(_) => {}
+                Err(e) => event["error"] = json!(e),
+            }
+            if event["changed"] == true {
+                event["notified"] = json!(target.notify.is_some());
+                if let Err(e) = notify(target, &event) {
+                    event["notified"] = json!(false);
+                    event["notifyError"] = json!(e);
+                }
+            }
+            record(target, &event)?;
+            print_event(&event, flags.json);
+        }
+        if once {
+            return Ok(());
+        }
+
+        let now = now_ms();
+        let next = list()?
+            .iter()
+            .map(|t| {
+                t.last_check
+                    .map(|at| (at + t.interval_ms).saturating_sub(now))
+                    .unwrap_or(0)
+            })
+            .min()
+            .map(Duration::from_millis)
+            .unwrap_or(MAX_IDLE);
+        thread::sleep(next.clamp(Duration::from_millis(100), MAX_IDLE));
+    }
+}
+
+/// Store a check's outcome on the target, re-reading the list so edits
+/// made while the check ran aren't lost
+fn record(target: &Target, event: &Value) -> Result<(), String> {
+    let mut targets = list()?;
+    if let Some(saved) = targets.iter_mut().find(|t| t.id == target.id) {
+        saved.last_check = event["checkedAt"].as_u64();
+        saved.last_error = event["error"].as_str().map(String::from);
+        if event["changed"] == true {
+            saved.last_change = saved.last_check;
+        }
+        save(&targets)?;
+    }
+    Ok(())
+}
+
+fn print_event(event: &Value, json: bool) {
+    if json {
+        println!("{}", event);
+        return;
+    }
+    let id = event["id"].as_str().unwrap_or("");
+    let url = event["url"].as_str().unwrap_or("");
+    if let Some(error) = event["error"].as_str() {
+        println!("\x1b[31m✗\x1b[0m [{}] {}: {}", id, url, error);
+        return;
+    }
+    if event["baseline"] == true {
+        println!(
+            "\x1b[32m✓\x1b[0m [{}] {}: {}",
+            id,
+            url,
+            t("monitor_baseline")
+        );
+        return;
+    }
+    if event["changed"] != true {
+        println!(
+            "\x1b[32m✓\x1b[0m [{}] {}: {}",
+            id,
+            url,
+            t("monitor_unchanged")
+        );
+        return;
+    }
+    let what = if event["mode"] == "visual" {
+        tf("monitor_pixels", &[&event["diffPixels"].to_string()])
+    } else {
+        let count = |field: &str| {
+            event[field]
+                .as_array()
+                .map(|a| a.len())
+                .unwrap_or(0)
+                .to_string()
+        };
+        tf("monitor_lines", &[&count("added"), &count("removed")])
+    };
+    let notified = match event["notifyError"].as_str() {
+        Some(e) => format!(" \x1b[31m({})\x1b[0m", tf("monitor_notify_failed", &[e])),
+        None if event["notified"] == true => format!(" → {}", t("monitor_notified")),
+        None => String::new(),
+    };
+    println!(
+        "\x1b[33m≠\x1b[0m [{}] {}: {}{}",
+        id,
+        url,
+        tf("monitor_changed", &[&what]),
+        notified
+    );
+}
+
+/// Human-readable `monitor list`
+pub fn print_list(targets: &[Target]) {
+    if targets.is_empty() {
+        println!("{}", t("monitor_none"));
+        return;
+    }
+    for t in targets {
+        let every = match t.interval_ms {
+            ms if ms % 3_600_000 == 0 => format!("{}h", ms / 3_600_000),
+            ms if ms % 60_000 == 0 => format!("{}m", ms / 60_000),
+            ms if ms % 1000 == 0 => format!("{}s", ms / 1000),
+            ms => format!("{}ms", ms),
+        };
+        println!(
+            "[{}] {}{} {} ({}){}",
+            t.id,
+            t.url,
+            t.selector
+                .as_deref()
+                .map(|s| format!(" {}", s))
+                .unwrap_or_default(),
+            tf("monitor_every", &[&every]),
+            t.mode,
+            t.notify
+                .as_deref()
+                .map(|n| format!(" → {}", n))
+                .unwrap_or_default()
+        );
+        if let Some(ref error) = t.last_error {
+            println!(
+                "      \x1b[31m{}\x1b[0m",
+                tf("monitor_last_failed", &[error])
+            );
+        }
+    }
+}
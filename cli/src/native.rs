@@ -0,0 +1,779 @@
+/*!
+ * Native Daemon
+ *
+ * A Node-free daemon for standalone installs: it launches Chromium itself
+ * (see `cdp.rs`), listens on the session's socket and answers the same
+ * line-delimited JSON protocol as the Node daemon, so the rest of the CLI
+ * can't tell them apart. It covers the core commands (navigation, clicks
+ * and typing on CSS selectors, text (plain or outlined) and HTML, eval,
+ * screenshots, viewport and device presets); anything else fails with code
+ * `unsupported_native` and a pointer to the full daemon. Snapshot refs
+ * (`@e1`) come from the Node daemon's accessibility tree and aren't
+ * available here.
+ *
+ * Each client connection gets its own thread, so a client holding its
+ * connection open (a script, the REPL) doesn't lock others out; their
+ * commands take turns on the one page.
+ *
+ * It runs when `--native-daemon` (or AGENT_BROWSER_NATIVE_DAEMON=1) is
+ * given, and whenever Node.js or the daemon script can't be found.
+ */
+use std::env;
+use std::fs;
+use std::io::{BufRead, BufReader, ErrorKind, Write};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use serde_json::{json, Value};
+
+use crate::cdp::{self, Browser};
+use crate::connection::{get_pid_file, get_socket_path, Response};
+use crate::flags::Flags;
+use crate::policy;
+
+pub const ENV_VAR: &str = "AGENT_BROWSER_NATIVE_DAEMON";
+
+/// Playwright's default action timeout
+const DEFAULT_TIMEOUT_MS: u64 = 30_000;
+
+/// Whether the native daemon was asked for explicitly
+pub fn requested() -> bool {
+    env::var(ENV_VAR).is_ok_and(|v| v == "1")
+}
+
+/// Whether `node` is on PATH
+pub fn node_available() -> bool {
+    env::var_os("PATH")
+        .map(|paths| env::split_paths(&paths).any(|dir| dir.join("node").is_file()))
+        .unwrap_or(false)
+}
+
+/// Set by SIGTERM/SIGINT, by `close` and when the browser goes away
+static STOP: AtomicBool = AtomicBool::new(false);
+
+/// The browser, until the daemon shuts it down
+type Shared = Arc<Mutex<Option<Browser>>>;
+
+fn lock(browser: &Shared) -> MutexGuard<'_, Option<Browser>> {
+    browser.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+extern "C" fn on_signal(_: libc::c_int) {
+    STOP.store(true, Ordering::SeqCst);
+}
+
+/// Run the daemon in the foreground until `close` or SIGTERM
+pub fn serve(flags: &Flags) -> Result<(), String> {
+    let executable = cdp::find_browser(flags.executable_path.as_deref())?;
+    // A throwaway profile per session, removed again on exit
+    let profile = env::temp_dir().join(format!("agentbrowser-pro-{}.profile", flags.session));
+    let browser = Browser::launch(&executable, flags.headed, profile, &flags.extensions)?;
+
+    let socket_path = get_socket_path(&flags.session);
+    fs::remove_file(&socket_path).ok();
+    let listener = UnixListener::bind(&socket_path)
+        .map_err(|e| format!("Failed to listen on {}: {}", socket_path, e))?;
+    // Poll so a signal can stop the loop between connections
+    listener.set_nonblocking(true).ok();
+    let pid_file = get_pid_file(&flags.session);
+    fs::write(&pid_file, std::process::id().to_string())
+        .map_err(|e| format!("Failed to write {}: {}", pid_file, e))?;
+    let handler = on_signal as extern "C" fn(libc::c_int) as libc::sighandler_t;
+    unsafe {
+        libc::signal(libc::SIGTERM, handler);
+        libc::signal(libc::SIGINT, handler);
+    }
+
+    let browser: Shared = Arc::new(Mutex::new(Some(browser)));
+    while !STOP.load(Ordering::SeqCst) {
+        match listener.accept() {
+            Ok((stream, _)) => {
+                let browser = Arc::clone(&browser);
+                let flags = flags.clone();
+                thread::spawn(move || serve_connection(stream, &browser, &flags));
+            }
+            Err(e) if e.kind() == ErrorKind::WouldBlock => {
+                thread::sleep(Duration::from_millis(50));
+            }
+            Err(e) => return Err(format!("Socket error: {}", e)),
+        }
+    }
+
+    if let Some(browser) = lock(&browser).take() {
+        browser.close();
+    }
+    fs::remove_file(&socket_path).ok();
+    fs::remove_file(&pid_file).ok();
+    Ok(())
+}
+
+/// Answer each command line on a connection, stopping the daemon on
+/// `close` or when the browser connection is lost
+fn serve_connection(stream: UnixStream, browser: &Shared, flags: &Flags) {
+    stream.set_nonblocking(false).ok();
+    let Ok(mut writer) = stream.try_clone() else {
+        return;
+    };
+    for line in BufReader::new(stream).lines() {
+        let line = match line {
+            Ok(line) if line.trim().is_empty() => continue,
+            Ok(line) => line,
+            Err(_) => break,
+        };
+        let command: Value = match serde_json::from_str(&line) {
+            Ok(command) => command,
+            Err(e) => json!({"id": "unknown", "action": "", "parseError": e.to_string()}),
+        };
+        let id = command["id"].as_str().unwrap_or("unknown").to_string();
+        let action = command["action"].as_str().unwrap_or("").to_string();
+        let outcome = match command.get("parseError") {
+            Some(e) => Err(Failure::new(format!("Invalid command: {}", e))),
+            None => check_url_rules(&action, &command, flags).and_then(|_| {
+                match lock(browser).as_mut() {
+                    Some(browser) => execute(browser, &action, &command),
+                    None => Err(Failure::new("The daemon is shutting down".to_string())),
+                }
+            }),
+        };
+        let response = match outcome {
+            Ok(result) => Response::ok(&id, result),
+            Err(failure) => {
+                if failure.fatal {
+                    // The browser went away; nothing more to serve
+                    eprintln!("{}", failure.message);
+                    STOP.store(true, Ordering::SeqCst);
+                    return;
+                }
+                Response::err(&id, failure.message, failure.code)
+            }
+        };
+        let text = serde_json::to_string(&response).unwrap_or_default();
+        if writer.write_all(format!("{}\n", text).as_bytes()).is_err() {
+            break;
+        }
+        if action == "close" {
+            STOP.store(true, Ordering::SeqCst);
+            return;
+        }
+    }
+}
+
+struct Failure {
+    message: String,
+    code: Option<&'static str>,
+    /// The DevTools connection itself failed
+    fatal: bool,
+}
+
+impl Failure {
+    fn new(message: String) -> Failure {
+        Failure {
+            message,
+            code: None,
+            fatal: false,
+        }
+    }
+}
+
+/// Refuse a navigation that `--allow-urls`/`--deny-urls` rule out
+fn check_url_rules(action: &str, command: &Value, flags: &Flags) -> Result<(), Failure> {
+    let Some(url) = command["url"].as_str().filter(|_| action == "navigate") else {
+        return Ok(());
+    };
+    policy::check_url_rules(url, &flags.allow_urls, &flags.deny_urls).map_err(|violation| Failure {
+        message: violation.message(),
+        code: Some("policy_violation"),
+        fatal: false,
+    })
+}
+
+/// Errors from the browser connection are fatal; evaluation errors aren't
+fn devtools(message: String) -> Failure {
+    let fatal = message.starts_with("WebSocket") || message.starts_with("Browser closed");
+    Failure {
+        message,
+        code: None,
+        fatal,
+    }
+}
+
+/// A JavaScript string literal
+fn js(text: &str) -> String {
+    serde_json::to_string(text).unwrap_or_default()
+}
+
+fn str_arg<'a>(command: &'a Value, field: &str) -> Result<&'a str, Failure> {
+    command[field]
+        .as_str()
+        .ok_or_else(|| Failure::new(format!("Missing '{}'", field)))
+}
+
+/// The command's selector, which has to be plain CSS here
+fn selector(command: &Value) -> Result<&str, Failure> {
+    let selector = str_arg(command, "selector")?;
+    if selector.starts_with('@') {
+        return Err(Failure {
+            message: format!(
+                "{} is a snapshot ref, which the native daemon doesn't support; use a CSS selector",
+                selector
+            ),
+            code: Some("unsupported_native"),
+            fatal: false,
+        });
+    }
+    Ok(selector)
+}
+
+fn deadline(command: &Value) -> Instant {
+    Instant::now()
+        + Duration::from_millis(command["timeout"].as_u64().unwrap_or(DEFAULT_TIMEOUT_MS))
+}
+
+/// Evaluate `expression` until it returns something other than null or
+/// false, polling like Playwright's auto-waiting
+fn wait_for(
+    browser: &mut Browser,
+    expression: &str,
+    until: Instant,
+    what: &str,
+) -> Result<Value, Failure> {
+    loop {
+        let value = browser.evaluate(expression).map_err(devtools)?;
+        if !value.is_null() && value != false {
+            return Ok(value);
+        }
+        if Instant::now() >= until {
+            return Err(Failure {
+                message: format!("Timed out waiting for {}", what),
+                code: Some("timeout"),
+                fatal: false,
+            });
+        }
+        thread::sleep(Duration::from_millis(100));
+    }
+}
+
+/// Wait for the element to be attached and visible, scroll it into view
+/// and return its centre
+fn element_center(browser: &mut Browser, command: &Value) -> Result<(f64, f64), Failure> {
+    let selector = selector(command)?;
+    let expression = format!(
+        "(() => {{
+            const el = document.querySelector({});
+            if (!el) return null;
+            el.scrollIntoView({{ block: 'center', inline: 'center' }});
+            const r = el.getBoundingClientRect();
+            if (!r.width || !r.height) return null;
+            return [r.left + r.width / 2, r.top + r.height / 2];
+        }})()",
+        js(selector)
+    );
+    let point = wait_for(
+        browser,
+        &expression,
+        deadline(command),
+        &format!("{} to be visible", selector),
+    )?;
+    Ok((
+        point[0].as_f64().unwrap_or(0.0),
+        point[1].as_f64().unwrap_or(0.0),
+    ))
+}
+
+/// `text --structured`: the `outlineText` page function the Node daemon
+/// uses too, kept in one file for both
+const OUTLINE_TEXT: &str = include_str!("../../src/dom/outline-text.js");
+
+/// Wait for the element, then evaluate `body` with it bound to `el`
+fn with_element(browser: &mut Browser, command: &Value, body: &str) -> Result<Value, Failure> {
+    let selector = selector(command)?;
+    wait_for(
+        browser,
+        &format!("!!document.querySelector({})", js(selector)),
+        deadline(command),
+        selector,
+    )?;
+    browser
+        .evaluate(&format!(
+            "(() => {{ const el = document.querySelector({}); {} }})()",
+            js(selector),
+            body
+        ))
+        .map_err(devtools)
+}
+
+/// Wait for the document to finish loading after a navigation
+fn wait_for_load(browser: &mut Browser, command: &Value) -> Result<Value, Failure> {
+    // Give the navigation a moment to replace the old document
+    thread::sleep(Duration::from_millis(50));
+    wait_for(
+        browser,
+        "document.readyState === 'complete'",
+        deadline(command),
+        "the page to load",
+    )?;
+    Ok(json!({"url": browser.evaluate("location.href").map_err(devtools)?}))
+}
+
+fn mouse(browser: &mut Browser, kind: &str, x: f64, y: f64) -> Result<(), Failure> {
+    browser
+        .call(
+            "Input.dispatchMouseEvent",
+            json!({"type": kind, "x": x, "y": y, "button": "left", "clickCount": 1}),
+        )
+        .map(|_| ())
+        .map_err(devtools)
+}
+
+/// DOM key names with their virtual key codes and the text they type
+const KEYS: &[(&str, u32, &str)] = &[
+    ("Enter", 13, "\r"),
+    ("Tab", 9, ""),
+    ("Escape", 27, ""),
+    ("Backspace", 8, ""),
+    ("Delete", 46, ""),
+    ("ArrowLeft", 37, ""),
+    ("ArrowUp", 38, ""),
+    ("ArrowRight", 39, ""),
+    ("ArrowDown", 40, ""),
+    ("Home", 36, ""),
+    ("End", 35, ""),
+    ("PageUp", 33, ""),
+    ("PageDown", 34, ""),
+    ("Space", 32, " "),
+];
+
+/// Modifier keys with their virtual key codes and CDP modifier bits
+const MODIFIER_KEYS: &[(&str, u32, u32)] = &[
+    ("Alt", 18, 1),
+    ("Control", 17, 2),
+    ("Meta", 91, 4),
+    ("Shift", 16, 8),
+];
+
+/// Press a key or a chord like `Control+Shift+P`
+fn press(browser: &mut Browser, chord: &str) -> Result<(), Failure> {
+    let (held, key) = match chord.strip_suffix("++") {
+        Some(held) => (held, "+"),
+        None => match chord.rsplit_once('+') {
+            Some((held, key)) if chord != "+" => (held, key),
+            _ => ("", chord),
+        },
+    };
+    let mut modifiers = Vec::new();
+    for name in held.split('+').filter(|name| !name.is_empty()) {
+        let name = match name {
+            "ControlOrMeta" if cfg!(target_os = "macos") => "Meta",
+            "ControlOrMeta" => "Control",
+            name => name,
+        };
+        let modifier = MODIFIER_KEYS
+            .iter()
+            .find(|(modifier, _, _)| *modifier == name)
+            .ok_or_else(|| Failure::new(format!("Unknown modifier '{}' in '{}'", name, chord)))?;
+        modifiers.push(*modifier);
+    }
+
+    let mut bits = 0;
+    for &(name, code, bit) in &modifiers {
+        bits |= bit;
+        dispatch_key(browser, "keyDown", name, code, bits, "")?;
+    }
+    // Shortcuts don't type; Shift alone still does
+    let typing = bits & !8 == 0;
+    press_key(browser, key, bits, typing)?;
+    for &(name, code, bit) in modifiers.iter().rev() {
+        bits &= !bit;
+        dispatch_key(browser, "keyUp", name, code, bits, "")?;
+    }
+    Ok(())
+}
+
+fn dispatch_key(
+    browser: &mut Browser,
+    kind: &str,
+    key: &str,
+    code: u32,
+    modifiers: u32,
+    text: &str,
+) -> Result<(), Failure> {
+    let mut event = json!({
+        "type": kind,
+        "key": key,
+        "windowsVirtualKeyCode": code,
+        "modifiers": modifiers,
+    });
+    if !text.is_empty() {
+        event["text"] = json!(text);
+    }
+    browser
+        .call("Input.dispatchKeyEvent", event)
+        .map(|_| ())
+        .map_err(devtools)
+}
+
+fn press_key(
+    browser: &mut Browser,
+    key: &str,
+    modifiers: u32,
+    typing: bool,
+) -> Result<(), Failure> {
+    let (name, code, text) = match KEYS.iter().find(|(name, _, _)| *name == key) {
+        Some(&(name, code, text)) => (
+            if name == "Space" { " " } else { name },
+            code,
+            text.to_string(),
+        ),
+        None if key.chars().count() == 1 => {
+            let c = key.chars().next().unwrap_or_default();
+            (key, c.to_ascii_uppercase() as u32, key.to_string())
+        }
+        None => match MODIFIER_KEYS.iter().find(|(name, _, _)| *name == key) {
+            Some(&(name, code, _)) => (name, code, String::new()),
+            None => {
+                return Err(Failure {
+                    message: format!("Key '{}' is not supported by the native daemon", key),
+                    code: Some("unsupported_native"),
+                    fatal: false,
+                })
+            }
+        },
+    };
+    let text = if typing { text } else { String::new() };
+    dispatch_key(browser, "keyDown", name, code, modifiers, &text)?;
+    dispatch_key(browser, "keyUp", name, code, modifiers, "")
+}
+
+/// Drop the touch and user agent overrides of `device`
+fn clear_device(browser: &mut Browser) -> Result<(), Failure> {
+    let version = browser
+        .call_browser("Browser.getVersion", json!({}))
+        .map_err(devtools)?;
+    browser
+        .call(
+            "Emulation.setTouchEmulationEnabled",
+            json!({"enabled": false}),
+        )
+        .and_then(|_| {
+            browser.call(
+                "Emulation.setUserAgentOverride",
+                json!({"userAgent": version["userAgent"]}),
+            )
+        })
+        .map(|_| ())
+        .map_err(devtools)
+}
+
+fn insert_text(browser: &mut Browser, text: &str) -> Result<(), Failure> {
+    browser
+        .call("Input.insertText", json!({"text": text}))
+        .map(|_| ())
+        .map_err(devtools)
+}
+
+fn screenshot(browser: &mut Browser, command: &Value) -> Result<Value, Failure> {
+    let mut params = json!({"format": "png"});
+    if command["type"] == "jpeg" {
+        params["format"] = json!("jpeg");
+        if let Some(quality) = command["quality"].as_u64() {
+            params["quality"] = json!(quality);
+        }
+    }
+    if command["fullPage"] == true {
+        let metrics = browser
+            .call("Page.getLayoutMetrics", json!({}))
+            .map_err(devtools)?;
+        let size = &metrics["cssContentSize"];
+        params["captureBeyondViewport"] = json!(true);
+        params["clip"] = json!({
+            "x": 0, "y": 0,
+            "width": size["width"], "height": size["height"],
+            "scale": 1,
+        });
+    }
+    let shot = browser
+        .call("Page.captureScreenshot", params)
+        .map_err(devtools)?;
+    let data = shot["data"].as_str().unwrap_or_default();
+    match command["path"].as_str() {
+        Some(path) => {
+            let bytes = BASE64
+                .decode(data)
+                .map_err(|e| Failure::new(format!("Invalid screenshot data: {}", e)))?;
+            fs::write(path, bytes)
+                .map_err(|e| Failure::new(format!("Failed to write {}: {}", path, e)))?;
+            Ok(json!({"path": path}))
+        }
+        None => Ok(json!({"data": data})),
+    }
+}
+
+fn execute(browser: &mut Browser, action: &str, command: &Value) -> Result<Value, Failure> {
+    match action {
+        "launch" => Ok(json!({"launched": true})),
+        "close" => Ok(json!({"closed": true})),
+
+        "navigate" => {
+            let url = str_arg(command, "url")?;
+            let result = browser
+                .call("Page.navigate", json!({"url": url}))
+                .map_err(devtools)?;
+            if let Some(error) = result["errorText"].as_str() {
+                return Err(Failure::new(format!("{} at {}", error, url)));
+            }
+            wait_for_load(browser, command)
+        }
+        "back" | "forward" => {
+            let step = if action == "back" { -1 } else { 1 };
+            browser
+                .evaluate(&format!("history.go({})", step))
+                .map_err(devtools)?;
+            wait_for_load(browser, command)
+        }
+        "reload" => {
+            browser.call("Page.reload", json!({})).map_err(devtools)?;
+            wait_for_load(browser, command)
+        }
+        "getUrl" => Ok(json!({"url": browser.evaluate("location.href").map_err(devtools)?})),
+        "getTitle" => Ok(json!({"title": browser.evaluate("document.title").map_err(devtools)?})),
+
+        "click" | "dblclick" => {
+            let (x, y) = element_center(browser, command)?;
+            mouse(browser, "mouseMoved", x, y)?;
+            let clicks = if action == "dblclick" { 2 } else { 1 };
+            for count in 1..=clicks {
+                for kind in ["mousePressed", "mouseReleased"] {
+                    browser
+                        .call(
+                            "Input.dispatchMouseEvent",
+                            json!({"type": kind, "x": x, "y": y, "button": "left", "clickCount": count}),
+                        )
+                        .map_err(devtools)?;
+                }
+            }
+            let selector = selector(command)?;
+            Ok(if action == "click" {
+                json!({"clicked": selector})
+            } else {
+                json!({"doubleClicked": selector})
+            })
+        }
+        "hover" => {
+            let (x, y) = element_center(browser, command)?;
+            mouse(browser, "mouseMoved", x, y)?;
+            Ok(json!({"hovered": selector(command)?}))
+        }
+        "focus" => {
+            with_element(browser, command, "el.focus();")?;
+            Ok(json!({"focused": selector(command)?}))
+        }
+        "fill" | "clear" => {
+            // Select the current contents so the inserted text replaces them,
+            // as typing would
+            with_element(
+                browser,
+                command,
+                "el.focus();
+                 if ('select' in el) el.select();
+                 else document.getSelection().selectAllChildren(el);",
+            )?;
+            let value = if action == "fill" {
+                str_arg(command, "value")?
+            } else {
+                ""
+            };
+            if value.is_empty() {
+                press(browser, "Delete")?;
+            } else {
+                insert_text(browser, value)?;
+            }
+            Ok(if action == "fill" {
+                json!({"filled": value})
+            } else {
+                json!({"cleared": selector(command)?})
+            })
+        }
+        "type" => {
+            with_element(browser, command, "el.focus();")?;
+            let text = str_arg(command, "text")?;
+            for c in text.chars() {
+                insert_text(browser, &c.to_string())?;
+            }
+            Ok(json!({"typed": text}))
+        }
+        "press" => {
+            if command["selector"].is_string() {
+                with_element(browser, command, "el.focus();")?;
+            }
+            let key = str_arg(command, "key")?;
+            press(browser, key)?;
+            Ok(json!({"pressed": key}))
+        }
+        "check" | "uncheck" => {
+            let want = action == "check";
+            with_element(
+                browser,
+                command,
+                &format!(
+                    "if (el.checked !== {}) el.click(); return el.checked;",
+                    want
+                ),
+            )?;
+            let selector = selector(command)?;
+            Ok(if want {
+                json!({"checked": selector})
+            } else {
+                json!({"unchecked": selector})
+            })
+        }
+
+        "setViewport" => {
+            let viewport = &command["viewport"];
+            clear_device(browser)?;
+            browser
+                .call(
+                    "Emulation.setDeviceMetricsOverride",
+                    json!({
+                        "width": viewport["width"],
+                        "height": viewport["height"],
+                        "deviceScaleFactor": 0,
+                        "mobile": false,
+                    }),
+                )
+                .map_err(devtools)?;
+            Ok(json!({ "viewport": viewport }))
+        }
+        "emulateDevice" => {
+            if !command["viewport"].is_object() {
+                return Err(Failure::new(
+                    "The native daemon only emulates the CLI's device presets".to_string(),
+                ));
+            }
+            let touch = command["hasTouch"] == true;
+            browser
+                .call(
+                    "Emulation.setDeviceMetricsOverride",
+                    json!({
+                        "width": command["viewport"]["width"],
+                        "height": command["viewport"]["height"],
+                        "deviceScaleFactor": command["deviceScaleFactor"],
+                        "mobile": command["isMobile"] == true,
+                    }),
+                )
+                .and_then(|_| {
+                    browser.call(
+                        "Emulation.setTouchEmulationEnabled",
+                        json!({"enabled": touch, "maxTouchPoints": if touch { 5 } else { 0 }}),
+                    )
+                })
+                .and_then(|_| {
+                    browser.call(
+                        "Emulation.setUserAgentOverride",
+                        json!({"userAgent": command["userAgent"]}),
+                    )
+                })
+                .map_err(devtools)?;
+            Ok(json!({
+                "device": command["device"],
+                "viewport": command["viewport"],
+                "deviceScaleFactor": command["deviceScaleFactor"],
+                "isMobile": command["isMobile"],
+                "hasTouch": command["hasTouch"],
+                "userAgent": command["userAgent"],
+            }))
+        }
+        "resetDevice" => {
+            clear_device(browser)?;
+            browser
+                .call("Emulation.clearDeviceMetricsOverride", json!({}))
+                .map_err(devtools)?;
+            Ok(json!({ "device": null }))
+        }
+        "getText" if command["structured"] == true => {
+            let body = format!("{}\nreturn outlineText(el);", OUTLINE_TEXT);
+            Ok(json!({"outline": with_element(browser, command, &body)?}))
+        }
+        "getText" => Ok(json!({
+            "text": with_element(browser, command, "return el.textContent ?? '';")?
+        })),
+        "getHtml" => {
+            let html = if command["selector"].is_string() {
+                let body = if command["outer"] == true {
+                    "return el.outerHTML;"
+                } else {
+                    "return el.innerHTML;"
+                };
+                with_element(browser, command, body)?
+            } else {
+                browser
+                    .evaluate("document.documentElement.outerHTML")
+                    .map_err(devtools)?
+            };
+            Ok(json!({ "html": html }))
+        }
+        "getValue" => Ok(json!({
+            "value": with_element(browser, command, "return el.value ?? '';")?
+        })),
+        "getAttribute" => {
+            let name = str_arg(command, "name")?;
+            let body = format!("return el.getAttribute({});", js(name));
+            Ok(json!({"value": with_element(browser, command, &body)?}))
+        }
+        "getCount" => {
+            let expression = format!(
+                "document.querySelectorAll({}).length",
+                js(selector(command)?)
+            );
+            Ok(json!({"count": browser.evaluate(&expression).map_err(devtools)?}))
+        }
+        "isVisible" => {
+            let expression = format!(
+                "(() => {{
+                    const el = document.querySelector({});
+                    return !!el && el.getClientRects().length > 0
+                        && getComputedStyle(el).visibility !== 'hidden';
+                }})()",
+                js(selector(command)?)
+            );
+            Ok(json!({"visible": browser.evaluate(&expression).map_err(devtools)?}))
+        }
+
+        "evaluate" => {
+            let script = str_arg(command, "script")?;
+            Ok(json!({"result": browser.evaluate(script).map_err(devtools)?}))
+        }
+        "screenshot" if command["selector"].is_null() => screenshot(browser, command),
+        "wait" => {
+            let ms = command["timeout"].as_u64().unwrap_or(0);
+            thread::sleep(Duration::from_millis(ms));
+            Ok(json!({"waited": ms}))
+        }
+        "waitForSelector" => {
+            let selector = selector(command)?;
+            let found = format!("document.querySelector({})", js(selector));
+            let condition = match command["state"].as_str().unwrap_or("visible") {
+                "attached" => format!("!!{}", found),
+                "detached" => format!("!{}", found),
+                "hidden" => format!("!({0}?.getClientRects().length)", found),
+                _ => format!("!!{}?.getClientRects().length", found),
+            };
+            wait_for(browser, &condition, deadline(command), selector)?;
+            Ok(json!({"found": selector}))
+        }
+
+        _ => Err(Failure {
+            message: format!(
+                "'{}' is not supported by the native daemon; install Node.js and run without \
+                 --native-daemon for the full command set",
+                action
+            ),
+            code: Some("unsupported_native"),
+            fatal: false,
+        }),
+    }
+}
@@ -1,250 +1,338 @@
-/**
+/*!
  * Output Formatting for CLI
  */
-use crate::connection::Response;
+use std::fs;
+
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use serde_json::{Map, Value};
 
-/// Print response in human-readable or JSON format
-pub fn print_response(resp: &Response, json: bool) {
+use crate::commands::CommandJson;
+use crate::config::Config;
+use crate::connection::Response;
+use crate::flags::Flags;
+use crate::i18n::{self, t, tf};
+use crate::registry::{commands, find_command, FlagSpec, COMMANDS, GLOBAL_FLAGS};
+use crate::render::renderer;
+
+/// Print response in human-readable or JSON format. Successful results are
+/// printed by the renderer registered for `action`.
+pub fn print_response(action: &str, resp: &Response, json: bool) {
     if json {
         println!("{}", serde_json::to_string_pretty(resp).unwrap_or_default());
         return;
     }
 
     if resp.success {
-        if let Some(ref result) = resp.result {
-            // Handle snapshot output specially
-            if let Some(tree) = result.get("tree") {
-                if let Some(tree_str) = tree.as_str() {
-                    println!("{}", tree_str);
-                    println!();
-                }
-                if let Some(url) = result.get("url").and_then(|v| v.as_str()) {
-                    println!("\x1b[90mURL:\x1b[0m {}", url);
-                }
-                if let Some(title) = result.get("title").and_then(|v| v.as_str()) {
-                    println!("\x1b[90mTitle:\x1b[0m {}", title);
-                }
-                return;
-            }
-
-            // Handle screenshot output
-            if result.get("data").is_some() {
-                if let Some(path) = result.get("path").and_then(|v| v.as_str()) {
-                    println!("\x1b[32m✓\x1b[0m Screenshot saved to: {}", path);
-                } else {
-                    println!("\x1b[32m✓\x1b[0m Screenshot captured (base64 data available)");
-                }
+        let result = match resp.result {
+            Some(ref result) => result,
+            None => {
+                println!("\x1b[32m✓\x1b[0m {}", t("success"));
                 return;
             }
-
-            // Handle simple values
-            if let Some(url) = result.get("url").and_then(|v| v.as_str()) {
-                println!("{}", url);
-                return;
-            }
-            if let Some(title) = result.get("title").and_then(|v| v.as_str()) {
-                println!("{}", title);
-                return;
-            }
-            if let Some(text) = result.get("text").and_then(|v| v.as_str()) {
-                println!("{}", text);
-                return;
-            }
-            if let Some(value) = result.get("value") {
-                if let Some(s) = value.as_str() {
-                    println!("{}", s);
-                } else {
-                    println!("{}", value);
-                }
-                return;
+        };
+        // An on_result hook may have swapped the result for text or a bare value
+        if !result.is_object() {
+            match result.as_str() {
+                Some(text) => println!("{}", text),
+                None => println!("{}", result),
             }
-            if let Some(html) = result.get("html").and_then(|v| v.as_str()) {
-                println!("{}", html);
-                return;
-            }
-
-            // Handle boolean results
-            if let Some(visible) = result.get("visible").and_then(|v| v.as_bool()) {
-                println!("{}", if visible { "true" } else { "false" });
-                return;
-            }
-            if let Some(enabled) = result.get("enabled").and_then(|v| v.as_bool()) {
-                println!("{}", if enabled { "true" } else { "false" });
-                return;
-            }
-            if let Some(checked) = result.get("checked").and_then(|v| v.as_bool()) {
-                println!("{}", if checked { "true" } else { "false" });
-                return;
-            }
-
-            // Handle count
-            if let Some(count) = result.get("count").and_then(|v| v.as_i64()) {
-                println!("{}", count);
-                return;
-            }
-
-            // Handle cookies
-            if let Some(cookies) = result.get("cookies").and_then(|v| v.as_array()) {
-                for cookie in cookies {
-                    if let Some(name) = cookie.get("name").and_then(|v| v.as_str()) {
-                        let value = cookie.get("value").and_then(|v| v.as_str()).unwrap_or("");
-                        println!("{}: {}", name, value);
-                    }
-                }
-                return;
-            }
-
-            // Handle pages list
-            if let Some(pages) = result.get("pages").and_then(|v| v.as_array()) {
-                for (i, page) in pages.iter().enumerate() {
-                    let url = page.get("url").and_then(|v| v.as_str()).unwrap_or("");
-                    let title = page.get("title").and_then(|v| v.as_str()).unwrap_or("");
-                    println!("[{}] {} - {}", i, title, url);
-                }
-                return;
-            }
-
-            // Handle frames list
-            if let Some(frames) = result.get("frames").and_then(|v| v.as_array()) {
-                for frame in frames {
-                    let name = frame
-                        .get("name")
-                        .and_then(|v| v.as_str())
-                        .unwrap_or("(unnamed)");
-                    let url = frame.get("url").and_then(|v| v.as_str()).unwrap_or("");
-                    println!("{}: {}", name, url);
-                }
-                return;
-            }
-
-            // Handle storage
-            if let Some(storage) = result.get("storage").and_then(|v| v.as_object()) {
-                for (key, value) in storage {
-                    println!("{}: {}", key, value);
-                }
-                return;
-            }
-
-            // Generic success
-            if result.get("clicked").is_some()
-                || result.get("typed").is_some()
-                || result.get("filled").is_some()
-                || result.get("checked").is_some()
-                || result.get("unchecked").is_some()
-                || result.get("selected").is_some()
-                || result.get("hovered").is_some()
-                || result.get("focused").is_some()
-                || result.get("pressed").is_some()
-                || result.get("scrolled").is_some()
-                || result.get("cleared").is_some()
-                || result.get("set").is_some()
-                || result.get("launched").is_some()
-                || result.get("closed").is_some()
-                || result.get("switched").is_some()
-                || result.get("created").is_some()
-                || result.get("waited").is_some()
-                || result.get("found").is_some()
-            {
-                println!("\x1b[32m✓\x1b[0m Success");
-                return;
-            }
-
-            // Fall back to JSON for complex results
+            return;
+        }
+        // Payloads written with --output-file look the same for every command
+        if let Some(path) = result.get("savedTo").and_then(|v| v.as_str()) {
+            let bytes = result.get("bytes").and_then(|v| v.as_u64()).unwrap_or(0);
             println!(
-                "{}",
-                serde_json::to_string_pretty(result).unwrap_or_default()
+                "\x1b[32m✓\x1b[0m {}",
+                tf("wrote_bytes", &[&bytes.to_string(), path])
             );
-        } else {
-            println!("\x1b[32m✓\x1b[0m Success");
+            return;
         }
+        renderer(action).render(result);
+        print_dialogs(result);
     } else {
         if let Some(ref error) = resp.error {
             eprintln!("\x1b[31m✗\x1b[0m {}", error);
         } else {
-            eprintln!("\x1b[31m✗\x1b[0m Command failed");
+            eprintln!("\x1b[31m✗\x1b[0m {}", t("command_failed"));
+        }
+        if let Some(suggestions) = resp.suggestions.as_ref().and_then(|v| v.as_array()) {
+            eprintln!("  {}", t("did_you_mean"));
+            for s in suggestions {
+                let reference = s.get("ref").and_then(|v| v.as_str()).unwrap_or("");
+                let role = s.get("role").and_then(|v| v.as_str()).unwrap_or("");
+                match s.get("name").and_then(|v| v.as_str()) {
+                    Some(name) => eprintln!("    {} {} \"{}\"", reference, role, name),
+                    None => eprintln!("    {} {}", reference, role),
+                }
+            }
+        }
+        if let Some(by) = resp.details.as_ref().and_then(|d| d.get("interceptedBy")) {
+            let field = |key: &str| by.get(key).and_then(|v| v.as_str()).unwrap_or("");
+            let element = match field("selector") {
+                "" => field("tag"),
+                selector => selector,
+            };
+            eprintln!(
+                "  {} {}{} {}{}",
+                t("intercepted_by"),
+                match field("ref") {
+                    "" => String::new(),
+                    reference => format!("{} ", reference),
+                },
+                field("role"),
+                match field("name") {
+                    "" => String::new(),
+                    name => format!("\"{}\" ", name),
+                },
+                format_args!("\x1b[90m({})\x1b[0m", element)
+            );
+        }
+        if let Some(ref hint) = resp.hint {
+            eprintln!("\x1b[33m{}\x1b[0m {}", t("hint"), hint);
         }
     }
 }
 
-/// Print help message
-pub fn print_help() {
-    println!(
-        r#"
-AgentBrowser Pro - Browser automation for AI agents
-
-Usage: agentbrowser-pro <command> [options]
+/// Result fields holding a command's main payload, in order of preference
+const PAYLOAD_FIELDS: &[&str] = &["html", "tree", "xml", "nodes", "text", "result"];
+
+/// Write the main payload of a successful response to `path` and replace the
+/// result with a `{savedTo, bytes, field}` summary. Commands that write files
+/// themselves (screenshot, pdf) are given the path up front and only need
+/// their size reported.
+pub fn write_output_file(resp: &mut Response, path: &str) -> Result<(), String> {
+    let result = match resp.result.as_mut().and_then(|r| r.as_object_mut()) {
+        Some(result) => result,
+        None => return Ok(()),
+    };
+
+    let (field, bytes) = if let Some(saved) = result.get("path").and_then(|v| v.as_str()) {
+        let size = fs::metadata(saved).map(|m| m.len()).unwrap_or(0);
+        ("path".to_string(), size)
+    } else {
+        let field = PAYLOAD_FIELDS
+            .iter()
+            .find(|f| result.contains_key(**f))
+            .map(|f| f.to_string());
+        let content = match field.as_ref().and_then(|f| result.get(f)) {
+            Some(Value::String(text)) => text.clone(),
+            Some(value) => serde_json::to_string_pretty(value).unwrap_or_default(),
+            None => serde_json::to_string_pretty(&result).unwrap_or_default(),
+        };
+        fs::write(path, &content).map_err(|e| format!("Failed to write {}: {}", path, e))?;
+        (
+            field.unwrap_or_else(|| "result".to_string()),
+            content.len() as u64,
+        )
+    };
+
+    let mut summary = Map::new();
+    summary.insert("savedTo".to_string(), Value::from(path));
+    summary.insert("bytes".to_string(), Value::from(bytes));
+    summary.insert("field".to_string(), Value::from(field));
+    for key in ["total", "truncated", "nextOffset", "omitted"] {
+        if let Some(value) = result.get(key) {
+            summary.insert(key.to_string(), value.clone());
+        }
+    }
+    resp.result = Some(Value::Object(summary));
+    Ok(())
+}
 
-Commands:
-  Navigation:
-    navigate <url>        Navigate to a URL
-    back                  Go back in history
-    forward               Go forward in history
-    reload                Reload the page
+/// Decode a result's base64 `data` into `path`, leaving `path` and `bytes`
+/// in its place
+pub fn write_base64_data(resp: &mut Response, path: &str) -> Result<(), String> {
+    let result = match resp.result.as_mut().and_then(|r| r.as_object_mut()) {
+        Some(result) => result,
+        None => return Ok(()),
+    };
+    let data = match result.remove("data") {
+        Some(Value::String(data)) => data,
+        _ => return Err("Response has no data to write".to_string()),
+    };
+    let bytes = BASE64
+        .decode(&data)
+        .map_err(|e| format!("Invalid base64 data: {}", e))?;
+    fs::write(path, &bytes).map_err(|e| format!("Failed to write {}: {}", path, e))?;
+    result.insert("path".to_string(), Value::from(path));
+    result.insert("bytes".to_string(), Value::from(bytes.len()));
+    Ok(())
+}
 
-  Interaction:
-    click <selector>      Click an element
-    dblclick <selector>   Double-click an element
-    type <sel> <text>     Type text into an element
-    fill <sel> <value>    Fill an input field (clears first)
-    clear <selector>      Clear an input field
-    check <selector>      Check a checkbox/radio
-    uncheck <selector>    Uncheck a checkbox
-    select <sel> <val>    Select dropdown option
-    hover <selector>      Hover over an element
-    focus <selector>      Focus an element
-    press <key> [sel]     Press a keyboard key
-    scroll [selector]     Scroll the page or element
+/// The dialogs a command ran into, and how each was answered
+fn print_dialogs(result: &Value) {
+    for dialog in result["dialogs"].as_array().into_iter().flatten() {
+        let answer = match dialog["promptText"].as_str() {
+            Some(text) => format!("Answered \"{}\" to", text),
+            None if dialog["accepted"] == true => "Accepted".to_string(),
+            None => "Dismissed".to_string(),
+        };
+        println!(
+            "\x1b[33m⚠\x1b[0m {} {}: {}",
+            answer,
+            dialog["type"].as_str().unwrap_or("dialog"),
+            dialog["message"].as_str().unwrap_or_default()
+        );
+    }
+}
 
-  Information:
-    snapshot              Get accessibility tree with refs
-    screenshot [path]     Take a screenshot
-    title                 Get page title
-    url                   Get current URL
-    text <selector>       Get element text
-    html [selector]       Get page or element HTML
-    value <selector>      Get input value
-    count <selector>      Count matching elements
+/// Tell the user how to fetch the next page of a truncated result
+pub fn print_page_note(resp: &Response) {
+    let result = match resp.result {
+        Some(ref result) => result,
+        None => return,
+    };
+    if let Some(next) = result.get("nextOffset").and_then(|v| v.as_u64()) {
+        let total = result.get("total").and_then(|v| v.as_u64()).unwrap_or(0);
+        eprintln!(
+            "\x1b[90m… truncated at {} of {}; continue with --offset={}\x1b[0m",
+            next, total, next
+        );
+    }
+}
 
-  State:
-    visible <selector>    Check if element is visible
-    enabled <selector>    Check if element is enabled
-    checked <selector>    Check if checkbox is checked
+/// Print the payload a command would send, without executing it
+pub fn print_dry_run(
+    cmd: &CommandJson,
+    wait_before: Option<&CommandJson>,
+    wait_after: Option<&CommandJson>,
+    flags: &Flags,
+    socket: &str,
+) {
+    if flags.json {
+        let mut out = serde_json::json!({
+            "dryRun": true,
+            "session": flags.session,
+            "socket": socket,
+            "headed": flags.headed,
+            "executablePath": flags.executable_path,
+            "extensions": flags.extensions,
+            "command": cmd,
+        });
+        if let Some(wait) = wait_before {
+            out["waitBefore"] = serde_json::to_value(wait).unwrap_or_default();
+        }
+        if let Some(wait) = wait_after {
+            out["waitAfter"] = serde_json::to_value(wait).unwrap_or_default();
+        }
+        println!("{}", serde_json::to_string_pretty(&out).unwrap_or_default());
+        return;
+    }
 
-  Frames:
-    frames                List all frames
-    frame <selector>      Switch to a frame
-    mainframe             Switch to main frame
+    println!("\x1b[33mDry run\x1b[0m (nothing sent)");
+    println!("\x1b[90mSession:\x1b[0m {}", flags.session);
+    println!("\x1b[90mSocket:\x1b[0m  {}", socket);
+    if flags.headed {
+        println!("\x1b[90mHeaded:\x1b[0m  true");
+    }
+    if let Some(ref path) = flags.executable_path {
+        println!("\x1b[90mBrowser:\x1b[0m {}", path);
+    }
+    for ext in &flags.extensions {
+        println!("\x1b[90mExtension:\x1b[0m {}", ext);
+    }
+    if let Some(wait) = wait_before {
+        println!("{}", wait.to_json());
+    }
+    println!("{}", cmd.to_json());
+    if let Some(wait) = wait_after {
+        println!("{}", wait.to_json());
+    }
+}
 
-  Pages:
-    pages                 List all pages/tabs
-    newpage [url]         Open a new page
-    switchpage <idx>      Switch to a page
-    closepage             Close current page
+/// Print aliases defined in the config file
+pub fn print_aliases(config: &Config, json: bool) {
+    if json {
+        let out = serde_json::json!({ "aliases": config.aliases });
+        println!("{}", serde_json::to_string_pretty(&out).unwrap_or_default());
+        return;
+    }
 
-  JavaScript:
-    eval <script>         Execute JavaScript
+    if config.aliases.is_empty() {
+        println!("No aliases defined. Add an [aliases] table to the config file.");
+        return;
+    }
+    for (name, expansion) in &config.aliases {
+        println!("{} = {}", name, expansion);
+    }
+}
 
-  Storage:
-    cookies               Get all cookies
-    clearcookies          Clear all cookies
-    localstorage [key]    Get localStorage
-    clearlocalstorage     Clear localStorage
+/// Print the command registry as a machine-readable spec
+pub fn print_spec(json: bool) {
+    if json {
+        let spec = serde_json::json!({
+            "name": "agentbrowser-pro",
+            "version": env!("CARGO_PKG_VERSION"),
+            "globalFlags": GLOBAL_FLAGS,
+            "commands": commands().collect::<Vec<_>>(),
+        });
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&spec).unwrap_or_default()
+        );
+        return;
+    }
 
-  Other:
-    daemon                Start browser daemon
-    mcp                   Start MCP server
-    pdf [path]            Generate PDF
-    stream                Start viewport streaming
-    close                 Close browser
+    for cmd in commands() {
+        let aliases = if cmd.aliases.is_empty() {
+            String::new()
+        } else {
+            format!(" \x1b[90m({})\x1b[0m", cmd.aliases.join(", "))
+        };
+        println!("{:<40} {}{}", cmd.usage, cmd.description, aliases);
+    }
+    println!();
+    println!("Run 'agentbrowser-pro spec --json' for the full machine-readable spec.");
+}
 
-Options:
-  --session=<name>        Use named session (default: "default")
-  --headed                Run browser in headed mode
-  --json                  Output results as JSON
-  --timeout=<ms>          Set command timeout
-  --executable-path=<p>   Path to browser executable
-  --help, -h              Show this help message
-  --version, -v           Show version
+/// Print help generated from the registry in the chosen language
+pub fn print_help() {
+    println!();
+    println!("AgentBrowser Pro - {}", t("tagline"));
+    println!();
+    println!("{} agentbrowser-pro <command> [options]", t("usage"));
+    println!();
+    println!("{}", t("commands"));
+    for category in i18n::categories() {
+        println!("  {}:", i18n::category(category));
+        for spec in COMMANDS.iter().filter(|c| c.category == category) {
+            println!(
+                "    {:<20}  {}",
+                spec.name,
+                i18n::command(spec.name, spec.description)
+            );
+        }
+        println!();
+    }
+    let plugins: Vec<_> = commands().filter(|c| c.category == "Plugins").collect();
+    if !plugins.is_empty() {
+        println!("  {}:", i18n::category("Plugins"));
+        for spec in plugins {
+            println!("    {:<20}  {}", spec.name, spec.description);
+        }
+        println!();
+    }
+    println!("{}", t("global_options"));
+    for flag in GLOBAL_FLAGS {
+        let name = match flag.short {
+            Some(short) => format!("-{}, {}", short, format_flag(flag)),
+            None => format_flag(flag),
+        };
+        println!("  {:<24}{}", name, i18n::flag(flag.name, flag.description));
+    }
+    println!();
+    println!("{}", t("command_help_note"));
+    if i18n::lang() == "en" {
+        print!(
+            r##"
+Flags that take a value also accept '--session work', '-s work' and '-s=work'.
+Short flags can be combined: '-jt 5000' is '--json --timeout=5000'.
+Use '--' to pass arguments that start with a dash: type @e1 -- -5
+Large results (html, texts, network list, cookies) accept --offset=<n>,
+--limit=<n> and --max-bytes=<n>; truncated pages report the next offset.
+Read-only queries (url, title, snapshot, count) accept --cache=<ttl>, e.g.
+--cache=2s, to reuse a recent result; any other command clears the cache.
 
 Selectors:
   @e1, @e2, ...          Element refs from snapshot
@@ -261,118 +349,75 @@ Examples:
   agentbrowser-pro screenshot --full-page output.png
 
 Documentation: https://github.com/anthropics/agentbrowser-pro
-"#
-    );
+"##
+        );
+    }
 }
 
-/// Print command-specific help
+/// Print command-specific help generated from the registry
 pub fn print_command_help(command: &str) -> bool {
-    match command.to_lowercase().as_str() {
-        "navigate" | "open" | "goto" => {
-            println!(
-                r#"
-Navigate to a URL
-
-Usage: agentbrowser-pro navigate <url> [options]
-
-Arguments:
-  url                   The URL to navigate to
-
-Options:
-  --timeout=<ms>        Maximum time to wait for navigation
-
-Examples:
-  agentbrowser-pro navigate https://example.com
-  agentbrowser-pro navigate https://example.com --timeout=30000
-"#
-            );
-            true
-        }
-        "click" => {
-            println!(
-                r#"
-Click an element
-
-Usage: agentbrowser-pro click <selector> [options]
-
-Arguments:
-  selector              Element ref (@e1) or CSS selector
-
-Options:
-  --timeout=<ms>        Maximum time to wait for element
+    let spec = match find_command(command) {
+        Some(spec) => spec,
+        None => return false,
+    };
+
+    println!();
+    println!("{}", i18n::command(spec.name, spec.description));
+    println!();
+    println!("{} agentbrowser-pro {} [options]", t("usage"), spec.usage);
+
+    if !spec.aliases.is_empty() {
+        println!();
+        println!("{} {}", t("aliases"), spec.aliases.join(", "));
+    }
 
-Examples:
-  agentbrowser-pro click @e1
-  agentbrowser-pro click "button.submit"
-  agentbrowser-pro click "#login-button"
-"#
-            );
-            true
+    if !spec.args.is_empty() {
+        println!();
+        println!("{}", t("arguments"));
+        for arg in spec.args {
+            let mut note = String::new();
+            if !arg.required {
+                note.push(' ');
+                note.push_str(t("optional"));
+            }
+            if arg.variadic {
+                note.push(' ');
+                note.push_str(t("remaining_words"));
+            }
+            println!("  {:<22}{}{}", arg.name, arg.description, note);
         }
-        "snapshot" => {
-            println!(
-                r#"
-Get accessibility tree with element refs
-
-Usage: agentbrowser-pro snapshot [selector] [options]
-
-Arguments:
-  selector              Optional selector to scope snapshot
-
-Options:
-  --json                Output as JSON
-
-Output format:
-  - role "name" [ref=e1] [attributes]
-
-The refs (e1, e2, etc.) can be used with other commands:
-  agentbrowser-pro click @e1
-  agentbrowser-pro fill @e2 "text"
+    }
 
-Examples:
-  agentbrowser-pro snapshot
-  agentbrowser-pro snapshot "#form"
-  agentbrowser-pro snapshot --json
-"#
-            );
-            true
+    if !spec.flags.is_empty() {
+        println!();
+        println!("{}", t("options"));
+        for flag in spec.flags {
+            println!("  {:<22}{}", format_flag(flag), flag.description);
         }
-        "fill" => {
-            println!(
-                r#"
-Fill an input field (clears existing content first)
-
-Usage: agentbrowser-pro fill <selector> <value>
-
-Arguments:
-  selector              Element ref (@e1) or CSS selector
-  value                 Text to fill
+    }
 
-Examples:
-  agentbrowser-pro fill @e1 "hello@example.com"
-  agentbrowser-pro fill "#email" "user@example.com"
-"#
-            );
-            true
+    if !spec.examples.is_empty() {
+        println!();
+        println!("{}", t("examples"));
+        for example in spec.examples {
+            println!("  {}", example);
         }
-        "type" => {
-            println!(
-                r#"
-Type text into an element (preserves existing content)
+    }
 
-Usage: agentbrowser-pro type <selector> <text>
+    if !spec.related.is_empty() {
+        println!();
+        println!("{} {}", t("related"), spec.related.join(", "));
+    }
 
-Arguments:
-  selector              Element ref (@e1) or CSS selector
-  text                  Text to type
+    println!();
+    println!("{}", t("global_options_note"));
+    true
+}
 
-Examples:
-  agentbrowser-pro type @e1 "Hello World"
-  agentbrowser-pro type "#search" "search query"
-"#
-            );
-            true
-        }
-        _ => false,
+/// Render a flag as it is typed on the command line
+fn format_flag(flag: &FlagSpec) -> String {
+    match flag.value {
+        Some(value) => format!("{}=<{}>", flag.name, value),
+        None => flag.name.to_string(),
     }
 }
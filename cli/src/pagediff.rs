@@ -0,0 +1,480 @@
+/*!
+ * Page Snapshots and Diffs
+ *
+ * `pagediff save <name>` stores the current page (or `--selector` element)
+ * as normalized text, normalized HTML and a screenshot under
+ * `~/.agentbrowser-pro/pagediff/<name>/`. `pagediff compare <name>` captures
+ * the page again and reports what changed since:
+ *
+ * - `text`: lines of rendered text added and removed
+ * - `html`: the same for markup, with scripts, styles and comments dropped
+ * - `visual`: how many pixels differ, and the box around them
+ *
+ * Compare exits 1 when the page changed, like diff(1), so a shell loop or
+ * `if` can act on it.
+ */
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use serde_json::{json, Value};
+
+use crate::commands::CommandJson;
+use crate::config::data_dir;
+use crate::connection::{ensure_daemon, send_command};
+use crate::flags::Flags;
+use crate::pageerrors::now_ms;
+
+/// Larger line diffs match lines regardless of order instead of aligning them
+const MAX_LCS_CELLS: usize = 4_000_000;
+
+/// Per-channel difference below which pixels count as unchanged, so
+/// antialiasing and image re-encoding don't register as changes
+const PIXEL_TOLERANCE: u8 = 32;
+
+/// Changed lines shown before summarizing the rest
+const SHOWN_LINES: usize = 20;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Mode {
+    Text,
+    Html,
+    Visual,
+}
+
+impl Mode {
+    pub fn parse(mode: &str) -> Option<Mode> {
+        match mode {
+            "text" => Some(Mode::Text),
+            "html" => Some(Mode::Html),
+            "visual" => Some(Mode::Visual),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Mode::Text => "text",
+            Mode::Html => "html",
+            Mode::Visual => "visual",
+        }
+    }
+}
+
+/// Whether `name` is usable as a snapshot name (and directory name)
+pub fn valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name.starts_with('.')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'))
+}
+
+fn snapshot_dir(name: &str) -> Result<PathBuf, String> {
+    Ok(data_dir()
+        .ok_or_else(|| "Cannot locate home directory for pagediff storage".to_string())?
+        .join("pagediff")
+        .join(name))
+}
+
+/// Whether a snapshot named `name` has been saved
+pub fn exists(name: &str) -> bool {
+    snapshot_dir(name)
+        .map(|dir| dir.join("meta.json").is_file())
+        .unwrap_or(false)
+}
+
+/// Delete snapshot `name`, if there is one
+pub fn remove(name: &str) {
+    if let Ok(dir) = snapshot_dir(name) {
+        fs::remove_dir_all(dir).ok();
+    }
+}
+
+/// Read the page's text and markup, with the element's selector (if any)
+/// inlined as a JS string literal
+fn capture_script(selector: Option<&str>) -> String {
+    let selector = selector
+        .map(|s| Value::String(s.to_string()).to_string())
+        .unwrap_or_else(|| "null".to_string());
+    format!(
+        r#"(() => {{
+  const sel = {};
+  const root = sel ? document.querySelector(sel) : document.documentElement;
+  if (!root) throw new Error('No element matches ' + sel);
+  const clone = root.cloneNode(true);
+  clone.querySelectorAll('script, style, noscript, template').forEach((el) => el.remove());
+  const walker = document.createTreeWalker(clone, NodeFilter.SHOW_COMMENT);
+  const comments = [];
+  while (walker.nextNode()) comments.push(walker.currentNode);
+  comments.forEach((c) => c.remove());
+  const textRoot = sel ? root : document.body || root;
+  return {{ url: location.href, text: textRoot.innerText, html: clone.outerHTML }};
+}})()"#,
+        selector
+    )
+}
+
+/// Compare two base64 PNGs pixel by pixel in the browser, which already
+/// has an image decoder
+fn visual_script(before: &str, after: &str) -> String {
+    format!(
+        r#"(async () => {{
+  const load = (b64) =>
+    createImageBitmap(
+      new Blob([Uint8Array.from(atob(b64), (c) => c.charCodeAt(0))], {{ type: 'image/png' }})
+    );
+  const [a, b] = await Promise.all([load('{}'), load('{}')]);
+  const width = Math.max(a.width, b.width);
+  const height = Math.max(a.height, b.height);
+  const pixels = (img) => {{
+    const ctx = new OffscreenCanvas(width, height).getContext('2d');
+    ctx.drawImage(img, 0, 0);
+    return ctx.getImageData(0, 0, width, height).data;
+  }};
+  const pa = pixels(a);
+  const pb = pixels(b);
+  let diffPixels = 0, minX = width, minY = height, maxX = -1, maxY = -1;
+  for (let i = 0; i < pa.length; i += 4) {{
+    if (
+      Math.abs(pa[i] - pb[i]) > {tol} ||
+      Math.abs(pa[i + 1] - pb[i + 1]) > {tol} ||
+      Math.abs(pa[i + 2] - pb[i + 2]) > {tol} ||
+      Math.abs(pa[i + 3] - pb[i + 3]) > {tol}
+    ) {{
+      diffPixels++;
+      const x = (i / 4) % width;
+      const y = Math.floor(i / 4 / width);
+      minX = Math.min(minX, x);
+      minY = Math.min(minY, y);
+      maxX = Math.max(maxX, x);
+      maxY = Math.max(maxY, y);
+    }}
+  }}
+  return {{
+    diffPixels,
+    totalPixels: width * height,
+    bbox: maxX < 0 ? null : {{ x: minX, y: minY, width: maxX - minX + 1, height: maxY - minY + 1 }},
+    before: {{ width: a.width, height: a.height }},
+    after: {{ width: b.width, height: b.height }},
+  }};
+}})()"#,
+        before,
+        after,
+        tol = PIXEL_TOLERANCE
+    )
+}
+
+/// Run a command and return its result, or the daemon's error
+fn call(cmd: &CommandJson, flags: &Flags) -> Result<Value, String> {
+    let resp = send_command(cmd, &flags.session)?;
+    if resp.success {
+        Ok(resp.result.unwrap_or(Value::Null))
+    } else {
+        Err(resp
+            .error
+            .unwrap_or_else(|| format!("{} failed", cmd.action)))
+    }
+}
+
+fn evaluate(script: String, flags: &Flags) -> Result<Value, String> {
+    let mut cmd = CommandJson::new("evaluate");
+    cmd.script = Some(script);
+    let result = call(&cmd, flags)?;
+    Ok(result.get("result").cloned().unwrap_or(result))
+}
+
+fn screenshot(selector: Option<&str>, path: &Path, flags: &Flags) -> Result<(), String> {
+    let mut cmd = CommandJson::new("screenshot");
+    cmd.path = Some(path.to_string_lossy().into_owned());
+    match selector {
+        Some(selector) => cmd.selector = Some(selector.to_string()),
+        None => cmd.full_page = Some(true),
+    }
+    call(&cmd, flags).map(|_| ())
+}
+
+/// Rendered text as one line per non-blank line, whitespace collapsed
+fn normalize_text(text: &str) -> Vec<String> {
+    text.lines()
+        .map(|line| line.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|line| !line.is_empty())
+        .collect()
+}
+
+/// Markup as one line per tag or run of text, whitespace collapsed
+fn normalize_html(html: &str) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut current = String::new();
+    let mut flush = |current: &mut String| {
+        let line = current.split_whitespace().collect::<Vec<_>>().join(" ");
+        if !line.is_empty() {
+            lines.push(line);
+        }
+        current.clear();
+    };
+    for c in html.chars() {
+        if c == '<' {
+            flush(&mut current);
+        }
+        current.push(c);
+        if c == '>' {
+            flush(&mut current);
+        }
+    }
+    flush(&mut current);
+    lines
+}
+
+fn read_lines(path: &Path) -> Result<Vec<String>, String> {
+    fs::read_to_string(path)
+        .map(|content| content.lines().map(String::from).collect())
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))
+}
+
+fn write(path: &Path, content: &[u8]) -> Result<(), String> {
+    fs::write(path, content).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+/// Save the current page as snapshot `name`, replacing any earlier one
+pub fn save(name: &str, selector: Option<&str>, flags: &Flags) -> Result<Value, String> {
+    ensure_daemon(
+        &flags.session,
+        flags.headed,
+        flags.executable_path.as_deref(),
+        &flags.extensions,
+    )?;
+    let dir = snapshot_dir(name)?;
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+
+    let page = evaluate(capture_script(selector), flags)?;
+    let text = normalize_text(page["text"].as_str().unwrap_or(""));
+    let html = normalize_html(page["html"].as_str().unwrap_or(""));
+    screenshot(selector, &dir.join("screenshot.png"), flags)?;
+    write(&dir.join("text.txt"), text.join("\n").as_bytes())?;
+    write(&dir.join("page.html"), html.join("\n").as_bytes())?;
+
+    let meta = json!({
+        "name": name,
+        "url": page["url"],
+        "selector": selector,
+        "savedAt": now_ms(),
+    });
+    let json = serde_json::to_string_pretty(&meta).unwrap_or_default();
+    write(&dir.join("meta.json"), json.as_bytes())?;
+
+    let mut result = meta;
+    result["path"] = json!(dir.to_string_lossy());
+    result["lines"] = json!(text.len());
+    Ok(result)
+}
+
+/// Capture the page again and compare it with snapshot `name`. With
+/// `update`, the new capture replaces the snapshot afterwards.
+pub fn compare(name: &str, mode: Mode, update: bool, flags: &Flags) -> Result<Value, String> {
+    let dir = snapshot_dir(name)?;
+    let meta: Value = fs::read_to_string(dir.join("meta.json"))
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .ok_or_else(|| {
+            format!(
+                "No snapshot named '{}'; create one with 'pagediff save {}'",
+                name, name
+            )
+        })?;
+    let selector = meta["selector"].as_str();
+    ensure_daemon(
+        &flags.session,
+        flags.headed,
+        flags.executable_path.as_deref(),
+        &flags.extensions,
+    )?;
+
+    let mut result = json!({
+        "name": name,
+        "mode": mode.as_str(),
+        "savedAt": meta["savedAt"],
+        "savedUrl": meta["url"],
+    });
+
+    let changed = match mode {
+        Mode::Text | Mode::Html => {
+            let page = evaluate(capture_script(selector), flags)?;
+            let (file, lines) = if mode == Mode::Text {
+                (
+                    "text.txt",
+                    normalize_text(page["text"].as_str().unwrap_or("")),
+                )
+            } else {
+                (
+                    "page.html",
+                    normalize_html(page["html"].as_str().unwrap_or("")),
+                )
+            };
+            let (removed, added) = diff_lines(&read_lines(&dir.join(file))?, &lines);
+            result["url"] = page["url"].clone();
+            result["added"] = json!(added);
+            result["removed"] = json!(removed);
+            !added.is_empty() || !removed.is_empty()
+        }
+        Mode::Visual => {
+            let saved = dir.join("screenshot.png");
+            let before = fs::read(&saved)
+                .map_err(|e| format!("Failed to read {}: {}", saved.display(), e))?;
+            let current = dir.join("screenshot.new.png");
+            screenshot(selector, &current, flags)?;
+            let after = fs::read(&current)
+                .map_err(|e| format!("Failed to read {}: {}", current.display(), e))?;
+            fs::remove_file(&current).ok();
+            let diff = evaluate(
+                visual_script(&BASE64.encode(&before), &BASE64.encode(&after)),
+                flags,
+            )?;
+            let total = diff["totalPixels"].as_f64().unwrap_or(0.0);
+            let pixels = diff["diffPixels"].as_f64().unwrap_or(0.0);
+            let size_changed = diff["before"] != diff["after"];
+            if let (Value::Object(result), Value::Object(diff)) = (&mut result, diff) {
+                result.extend(diff);
+            }
+            result["ratio"] = json!(if total > 0.0 { pixels / total } else { 0.0 });
+            result["sizeChanged"] = json!(size_changed);
+            pixels > 0.0 || size_changed
+        }
+    };
+    result["changed"] = json!(changed);
+    if update {
+        save(name, selector, flags)?;
+    }
+    Ok(result)
+}
+
+/// Lines only in `old` and lines only in `new`, in order. Lines common to
+/// both ends are skipped first, so small edits to big pages stay cheap.
+fn diff_lines(old: &[String], new: &[String]) -> (Vec<String>, Vec<String>) {
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let a = &old[prefix..old.len() - suffix];
+    let b = &new[prefix..new.len() - suffix];
+
+    if (a.len() + 1).saturating_mul(b.len() + 1) > MAX_LCS_CELLS {
+        return (multiset_minus(a, b), multiset_minus(b, a));
+    }
+
+    // lcs[i][j]: longest common subsequence of a[i..] and b[j..]
+    let width = b.len() + 1;
+    let mut lcs = vec![0u32; (a.len() + 1) * width];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lcs[i * width + j] = if a[i] == b[j] {
+                lcs[(i + 1) * width + j + 1] + 1
+            } else {
+                lcs[(i + 1) * width + j].max(lcs[i * width + j + 1])
+            };
+        }
+    }
+
+    let (mut removed, mut added) = (Vec::new(), Vec::new());
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        if a[i] == b[j] {
+            i += 1;
+            j += 1;
+        } else if lcs[(i + 1) * width + j] >= lcs[i * width + j + 1] {
+            removed.push(a[i].clone());
+            i += 1;
+        } else {
+            added.push(b[j].clone());
+            j += 1;
+        }
+    }
+    removed.extend(a[i..].iter().cloned());
+    added.extend(b[j..].iter().cloned());
+    (removed, added)
+}
+
+/// Lines of `a` left over after matching each against an equal line of `b`
+fn multiset_minus(a: &[String], b: &[String]) -> Vec<String> {
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for line in b {
+        *counts.entry(line).or_default() += 1;
+    }
+    a.iter()
+        .filter(|line| match counts.get_mut(line.as_str()) {
+            Some(n) if *n > 0 => {
+                *n -= 1;
+                false
+            }
+            _ => true,
+        })
+        .cloned()
+        .collect()
+}
+
+/// Human-readable report for `pagediff save` and `pagediff compare`
+pub fn print(result: &Value) {
+    let name = result["name"].as_str().unwrap_or("");
+    if result.get("changed").is_none() {
+        println!(
+            "\x1b[32m✓\x1b[0m Saved snapshot '{}' of {}",
+            name,
+            result["url"].as_str().unwrap_or("")
+        );
+        return;
+    }
+    if result["changed"] != true {
+        println!("\x1b[32m✓\x1b[0m No changes since snapshot '{}'", name);
+        return;
+    }
+    if result["mode"] == "visual" {
+        println!(
+            "\x1b[33m≠\x1b[0m {} pixel(s) changed ({:.2}%) since snapshot '{}'",
+            result["diffPixels"],
+            result["ratio"].as_f64().unwrap_or(0.0) * 100.0,
+            name
+        );
+        if let Some(bbox) = result["bbox"].as_object() {
+            println!(
+                "  region: {}x{} at ({}, {})",
+                bbox["width"], bbox["height"], bbox["x"], bbox["y"]
+            );
+        }
+        if result["sizeChanged"] == true {
+            println!(
+                "  size: {}x{} → {}x{}",
+                result["before"]["width"],
+                result["before"]["height"],
+                result["after"]["width"],
+                result["after"]["height"]
+            );
+        }
+        return;
+    }
+    let lines = |field: &str| result[field].as_array().cloned().unwrap_or_default();
+    let (removed, added) = (lines("removed"), lines("added"));
+    println!(
+        "\x1b[33m≠\x1b[0m {} line(s) added, {} removed since snapshot '{}'",
+        added.len(),
+        removed.len(),
+        name
+    );
+    for (lines, sign, color) in [(&removed, '-', "31"), (&added, '+', "32")] {
+        for line in lines.iter().take(SHOWN_LINES) {
+            println!(
+                "  \x1b[{}m{} {}\x1b[0m",
+                color,
+                sign,
+                line.as_str().unwrap_or("")
+            );
+        }
+        if lines.len() > SHOWN_LINES {
+            println!("  ... {} more", lines.len() - SHOWN_LINES);
+        }
+    }
+}
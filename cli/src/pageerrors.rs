@@ -0,0 +1,82 @@
+/*!
+ * Page Errors
+ *
+ * The daemon records uncaught exceptions and unhandled promise rejections
+ * from every page. `errors --follow` streams them as they happen, and
+ * `--fail-on-page-error` turns any raised while a command ran into a failed
+ * command so silent JS crashes fail CI.
+ */
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde_json::Value;
+
+use crate::commands::CommandJson;
+use crate::connection::send_command;
+use crate::flags::Flags;
+
+/// How often `errors --follow` polls the daemon
+const FOLLOW_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Milliseconds since the epoch, the clock the daemon stamps errors with
+pub fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// Errors the session's pages raised at or after `since`
+pub fn since(session: &str, since: u64) -> Result<Vec<Value>, String> {
+    let resp = send_command(&CommandJson::new("getErrors"), session)?;
+    let errors = resp
+        .result
+        .and_then(|r| r.get("errors").and_then(|e| e.as_array()).cloned())
+        .unwrap_or_default();
+    Ok(errors
+        .into_iter()
+        .filter(|e| e["timestamp"].as_u64().unwrap_or(0) >= since)
+        .collect())
+}
+
+/// One-line description of a page error for failure messages
+pub fn summary(errors: &[Value]) -> String {
+    let first = errors[0]["message"].as_str().unwrap_or("unknown error");
+    match errors.len() {
+        1 => format!("Page error during command: {}", first),
+        n => format!("{} page errors during command, first: {}", n, first),
+    }
+}
+
+/// Print errors as they are raised until interrupted
+pub fn follow(flags: &Flags) -> Result<(), String> {
+    let mut seen = 0;
+    loop {
+        let errors = since(&flags.session, seen)?;
+        for error in &errors {
+            if flags.json {
+                println!("{}", error);
+            } else {
+                print_error(error);
+            }
+            seen = seen.max(error["timestamp"].as_u64().unwrap_or(0) + 1);
+        }
+        thread::sleep(FOLLOW_INTERVAL);
+    }
+}
+
+/// Print an error's stack, or its message when there is none
+pub fn print_error(error: &Value) {
+    let message = error["message"].as_str().unwrap_or("");
+    let url = error["url"].as_str().unwrap_or("");
+    println!("\x1b[31m✗\x1b[0m {} \x1b[90m{}\x1b[0m", message, url);
+    if let Some(stack) = error["stack"].as_str() {
+        // The first line of a V8 stack repeats the message
+        for line in stack
+            .lines()
+            .skip_while(|l| !l.trim_start().starts_with("at "))
+        {
+            println!("    \x1b[90m{}\x1b[0m", line.trim());
+        }
+    }
+}
@@ -0,0 +1,232 @@
+/*!
+ * Paginated Results
+ *
+ * `--offset`, `--limit` and `--max-bytes` window large results (page HTML,
+ * text lists and outlines, network logs, cookies, feed entries) so a heavy
+ * page can be read in pieces instead of one terminal-flooding response.
+ */
+use serde_json::Value;
+
+use crate::connection::Response;
+use crate::flags::Flags;
+
+/// Result fields that can be paged, in order of preference
+const PAGED_FIELDS: &[&str] = &[
+    "html", "outline", "requests", "cookies", "entries", "result",
+];
+
+/// Flags that control paging
+pub const PAGING_FLAGS: &[&str] = &["--offset", "--limit", "--max-bytes"];
+
+#[derive(Debug, Default)]
+pub struct Paging {
+    pub offset: usize,
+    pub limit: Option<usize>,
+    pub max_bytes: Option<usize>,
+}
+
+impl Paging {
+    /// Read paging flags; None when none were given
+    pub fn from_flags(flags: &Flags) -> Option<Self> {
+        if PAGING_FLAGS.iter().all(|f| flags.value(f).is_none()) {
+            return None;
+        }
+        let number = |name: &str| flags.value(name).and_then(|v| v.parse().ok());
+        Some(Paging {
+            offset: number("--offset").unwrap_or(0),
+            limit: number("--limit"),
+            max_bytes: number("--max-bytes"),
+        })
+    }
+}
+
+/// Cut the primary field of a result down to the requested window.
+///
+/// Strings are windowed by bytes (`--limit` and `--max-bytes` both cap the
+/// length), arrays by items. Adds `total`, `offset` and `truncated`, plus
+/// `nextOffset` when more remains.
+pub fn apply(resp: &mut Response, paging: &Paging) {
+    let result = match resp.result.as_mut().and_then(|r| r.as_object_mut()) {
+        Some(result) => result,
+        None => return,
+    };
+    let field = match PAGED_FIELDS.iter().find(|f| {
+        result
+            .get(**f)
+            .map(|v| v.is_string() || v.is_array())
+            .unwrap_or(false)
+    }) {
+        Some(field) => field.to_string(),
+        None => return,
+    };
+
+    let (window, total, end) = match result.get(&field) {
+        Some(Value::String(text)) => {
+            let (window, end) = window_str(text, paging);
+            (Value::String(window), text.len(), end)
+        }
+        Some(Value::Array(items)) => {
+            let (window, end) = window_items(items, paging);
+            (Value::Array(window), items.len(), end)
+        }
+        _ => return,
+    };
+
+    result.insert(field, window);
+    result.insert("total".to_string(), Value::from(total));
+    result.insert("offset".to_string(), Value::from(paging.offset.min(total)));
+    result.insert("truncated".to_string(), Value::Bool(end < total));
+    if end < total {
+        result.insert("nextOffset".to_string(), Value::from(end));
+    }
+}
+
+/// Byte window of a string, snapped to character boundaries
+fn window_str(text: &str, paging: &Paging) -> (String, usize) {
+    let mut start = paging.offset.min(text.len());
+    while !text.is_char_boundary(start) {
+        start += 1;
+    }
+
+    let cap = match (paging.limit, paging.max_bytes) {
+        (Some(a), Some(b)) => a.min(b),
+        (Some(a), None) | (None, Some(a)) => a,
+        (None, None) => usize::MAX,
+    };
+    let mut end = start.saturating_add(cap).min(text.len());
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    // A window narrower than the next character still takes it whole, so
+    // paging always makes progress
+    if end == start && cap > 0 {
+        end = text[start..]
+            .chars()
+            .next()
+            .map_or(start, |c| start + c.len_utf8());
+    }
+    (text[start..end].to_string(), end)
+}
+
+/// Item window of an array. At least one item is returned when any remain,
+/// so paging always makes progress even if a single item exceeds the budget.
+fn window_items(items: &[Value], paging: &Paging) -> (Vec<Value>, usize) {
+    let start = paging.offset.min(items.len());
+    let mut window = Vec::new();
+    let mut bytes = 0;
+
+    for item in items[start..]
+        .iter()
+        .take(paging.limit.unwrap_or(usize::MAX))
+    {
+        if let Some(max) = paging.max_bytes {
+            let size = serde_json::to_string(item).map(|s| s.len()).unwrap_or(0);
+            if !window.is_empty() && bytes + size > max {
+                break;
+            }
+            bytes += size;
+        }
+        window.push(item.clone());
+    }
+
+    let end = start + window.len();
+    (window, end)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn page(result: Value, offset: usize, limit: Option<usize>, max_bytes: Option<usize>) -> Value {
+        let mut resp = Response::ok("1", result);
+        let paging = Paging {
+            offset,
+            limit,
+            max_bytes,
+        };
+        apply(&mut resp, &paging);
+        resp.result.unwrap()
+    }
+
+    #[test]
+    fn strings_page_by_bytes_to_the_last_page() {
+        let html = json!({ "html": "<p>0123456789</p>" });
+        let first = page(html.clone(), 0, Some(8), None);
+        assert_eq!(first["html"], "<p>01234");
+        assert_eq!(first["total"], 17);
+        assert_eq!(first["truncated"], true);
+        assert_eq!(first["nextOffset"], 8);
+
+        let last = page(html.clone(), 16, Some(8), Some(100));
+        assert_eq!(last["html"], ">");
+        assert_eq!(last["truncated"], false);
+        assert!(last.get("nextOffset").is_none());
+
+        // A window ending exactly at the end is the last page
+        let exact = page(html, 9, Some(8), None);
+        assert_eq!(exact["html"], "6789</p>");
+        assert_eq!(exact["truncated"], false);
+    }
+
+    #[test]
+    fn offsets_past_the_end_give_an_empty_page() {
+        let result = page(json!({ "html": "abc" }), 10, Some(5), None);
+        assert_eq!(result["html"], "");
+        assert_eq!(result["offset"], 3);
+        assert_eq!(result["truncated"], false);
+
+        let result = page(json!({ "cookies": [] }), 0, Some(5), None);
+        assert_eq!(result["cookies"], json!([]));
+        assert_eq!(result["total"], 0);
+        assert_eq!(result["truncated"], false);
+    }
+
+    #[test]
+    fn multibyte_text_always_moves_forward() {
+        let text = json!({ "outline": "añb" });
+        let first = page(text.clone(), 0, Some(2), None);
+        assert_eq!(first["outline"], "a");
+        assert_eq!(first["nextOffset"], 1);
+        // Narrower than 'ñ' (two bytes), it is still returned whole
+        let second = page(text.clone(), 1, Some(1), None);
+        assert_eq!(second["outline"], "ñ");
+        assert_eq!(second["nextOffset"], 3);
+        let last = page(text, 3, Some(1), None);
+        assert_eq!(last["outline"], "b");
+        assert_eq!(last["truncated"], false);
+    }
+
+    #[test]
+    fn arrays_page_by_items_and_bytes() {
+        let requests = json!({ "requests": [1, 22, 333, 4444] });
+        let first = page(requests.clone(), 0, Some(2), None);
+        assert_eq!(first["requests"], json!([1, 22]));
+        assert_eq!(first["nextOffset"], 2);
+
+        let last = page(requests.clone(), 2, Some(2), None);
+        assert_eq!(last["requests"], json!([333, 4444]));
+        assert_eq!(last["truncated"], false);
+
+        // Bytes stop before the item that would overflow; at least one item
+        // is returned even when it alone is over
+        let bytes = page(requests.clone(), 0, None, Some(3));
+        assert_eq!(bytes["requests"], json!([1, 22]));
+        let oversized = page(requests, 3, None, Some(1));
+        assert_eq!(oversized["requests"], json!([4444]));
+        assert_eq!(oversized["truncated"], false);
+    }
+
+    #[test]
+    fn the_first_pageable_field_is_windowed() {
+        let result = page(
+            json!({ "url": "https://x.test", "entries": ["a", "b"], "result": "text" }),
+            1,
+            None,
+            None,
+        );
+        assert_eq!(result["entries"], json!(["b"]));
+        assert_eq!(result["result"], "text");
+        assert_eq!(result["url"], "https://x.test");
+    }
+}
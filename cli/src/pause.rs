@@ -0,0 +1,55 @@
+/*!
+ * Pausing for a Person
+ *
+ * `pause` blocks until someone lets the agent carry on: by clicking
+ * Continue on the banner the daemon shows in a headed browser, by pressing
+ * Enter in the terminal running the CLI, or by running `resume` from
+ * anywhere else. The daemon holds the pause and the terminal only sends it
+ * `resume`, so every way of continuing ends it the same way.
+ */
+use std::io;
+use std::thread;
+
+use crate::commands::CommandJson;
+use crate::connection::{ensure_daemon, send_command, Connection, Response};
+use crate::flags::Flags;
+
+/// How long the CLI waits for a pause without `--timeout`; the daemon
+/// itself waits indefinitely
+const UNBOUNDED_MS: u64 = 24 * 60 * 60 * 1000;
+
+pub fn run(cmd: &CommandJson, flags: &Flags) -> Result<Response, String> {
+    ensure_daemon(
+        &flags.session,
+        flags.headed,
+        flags.executable_path.as_deref(),
+        &flags.extensions,
+    )?;
+
+    if unsafe { libc::isatty(0) } == 1 {
+        eprintln!(
+            "\x1b[33m⏸\x1b[0m {}. Press Enter to continue{}.",
+            cmd.message
+                .as_deref()
+                .unwrap_or("Paused")
+                .trim_end_matches('.'),
+            if flags.headed {
+                ", or click Continue in the browser"
+            } else {
+                ""
+            }
+        );
+        let session = flags.session.clone();
+        thread::spawn(move || {
+            let mut line = String::new();
+            if io::stdin().read_line(&mut line).is_ok_and(|n| n > 0) {
+                let mut resume = CommandJson::new("resume");
+                resume.by = Some("terminal".to_string());
+                send_command(&resume, &session).ok();
+            }
+        });
+    }
+
+    Connection::open(&flags.session)?
+        .send(&cmd.to_json(), Some(cmd.timeout.unwrap_or(UNBOUNDED_MS)))
+}
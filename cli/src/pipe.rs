@@ -0,0 +1,114 @@
+/*!
+ * JSON Lines Mode
+ *
+ * `--stdin` turns the CLI into a subprocess driver: each stdin line is a
+ * daemon command object (`{"action": "navigate", "url": "..."}`), sent as is
+ * over one connection, and each response is written to stdout as one line
+ * in the same order. A missing `id` becomes the line number, so callers can
+ * match responses without numbering commands themselves. Bad lines and
+ * failed sends get an error response and the stream carries on; a lost
+ * connection is reopened for the next command, never retried for the one
+ * that failed, since it may already have run.
+ */
+use std::io::{self, BufRead, Write};
+
+use serde_json::Value;
+
+use crate::connection::{ensure_daemon, Connection, Response};
+use crate::flags::Flags;
+use crate::i18n::tf;
+use crate::policy;
+use crate::registry::{find_action, is_read_only};
+
+pub fn run(flags: &Flags) -> Result<(), String> {
+    let mut connection: Option<Connection> = None;
+    let mut out = io::stdout().lock();
+
+    for (i, line) in io::stdin().lock().lines().enumerate() {
+        let line = line.map_err(|e| format!("Failed to read stdin: {}", e))?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let id = (i + 1).to_string();
+        let response = match command(&line, &id, flags) {
+            Ok((id, command)) => send(&mut connection, &command, flags).unwrap_or_else(|e| {
+                connection = None;
+                Response::err(&id, e, Some("connection_failed"))
+            }),
+            Err((e, code)) => Response::err(&id, e, Some(code)),
+        };
+        let json = serde_json::to_string(&response).unwrap_or_default();
+        // A closed stdout means the caller is gone
+        if writeln!(out, "{}", json).and_then(|_| out.flush()).is_err() {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// The command object on a line, with its id (the line number if it had
+/// none) and the default timeout filled in, or an error and its code
+pub fn command(
+    line: &str,
+    id: &str,
+    flags: &Flags,
+) -> Result<(String, Value), (String, &'static str)> {
+    let invalid = |e: String| (e, "invalid_command");
+    let mut command: Value =
+        serde_json::from_str(line).map_err(|e| invalid(format!("Invalid JSON: {}", e)))?;
+    let Some(object) = command.as_object_mut() else {
+        return Err(invalid(
+            "Expected a JSON object like {\"action\": \"snapshot\"}".to_string(),
+        ));
+    };
+    let Some(action) = object.get("action").and_then(Value::as_str) else {
+        return Err(invalid("Missing \"action\"".to_string()));
+    };
+    if flags.read_only && !is_read_only(action) {
+        return Err((tf("read_only_blocked", &[action]), "read_only"));
+    }
+    let name = find_action(action).map_or(action, |spec| spec.name);
+    let url = object.get("url").and_then(Value::as_str);
+    policy::check(name, action, url, &flags.session)
+        .and_then(|_| match url {
+            Some(url) if matches!(action, "navigate" | "newPage") => {
+                policy::check_url_rules(url, &flags.allow_urls, &flags.deny_urls)
+            }
+            _ => Ok(()),
+        })
+        .map_err(|violation| (violation.message(), "policy_violation"))?;
+    let id = match object.get("id") {
+        Some(Value::String(id)) => id.clone(),
+        Some(Value::Number(n)) => n.to_string(),
+        _ => id.to_string(),
+    };
+    object.insert("id".to_string(), Value::String(id.clone()));
+    if let Some(timeout) = flags.timeout.or(flags.default_timeout) {
+        object
+            .entry("timeout")
+            .or_insert_with(|| Value::from(timeout));
+    }
+    Ok((id, command))
+}
+
+/// Send a command over `connection`, opening it (and starting the daemon)
+/// if there isn't one yet
+pub fn send(
+    connection: &mut Option<Connection>,
+    command: &Value,
+    flags: &Flags,
+) -> Result<Response, String> {
+    let connection = match connection {
+        Some(connection) => connection,
+        None => {
+            ensure_daemon(
+                &flags.session,
+                flags.headed,
+                flags.executable_path.as_deref(),
+                &flags.extensions,
+            )?;
+            connection.insert(Connection::open(&flags.session)?)
+        }
+    };
+    connection.send(&command.to_string(), command["timeout"].as_u64())
+}
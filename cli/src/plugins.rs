@@ -0,0 +1,407 @@
+/*!
+ * WASM Plugins
+ *
+ * Plugins are WASI modules declared in config.toml. Each one becomes a
+ * command, listed in help and `spec` under Plugins, and may also
+ * transform the results of built-in commands before they are printed:
+ *
+ * ```toml
+ * wasm_runtime = "wasmtime run"   # the default
+ *
+ * [plugins.prices]
+ * module = "plugins/prices.wasm"  # relative to the config file
+ * description = "Extract product prices"
+ * usage = "prices [selector]"
+ * transforms = ["snapshot"]
+ * ```
+ *
+ * A module runs as a WASI command under the runtime, with no filesystem or
+ * network of its own; it talks to the CLI in JSON lines on stdin/stdout.
+ * It first reads its task:
+ *
+ * - `{"type": "command", "command", "args", "flags", "session"}`
+ * - `{"type": "transform", "command", "result", "session"}`
+ *
+ * then may print daemon commands (`{"action": "getText", ...}`, as in
+ * `--stdin` mode, with the same read-only and policy checks), reading one
+ * response line for each, and ends with `{"result": ...}` or
+ * `{"error": "..."}`. Its stderr goes to the terminal.
+ */
+use std::collections::BTreeMap;
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::{mpsc, OnceLock};
+use std::thread;
+
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+
+use crate::commands::split_words;
+use crate::connection::{Connection, Response};
+use crate::flags::Flags;
+use crate::pipe;
+use crate::registry::{self, ArgSpec, CommandSpec};
+
+/// Runtime used when config.toml doesn't name one
+const DEFAULT_RUNTIME: &str = "wasmtime run";
+
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default)]
+pub struct PluginConfig {
+    /// The .wasm module; relative paths start at the config file's directory
+    pub module: PathBuf,
+    /// One line for help
+    pub description: Option<String>,
+    /// Usage line, e.g. `prices [selector]`
+    pub usage: Option<String>,
+    /// Commands whose results pass through the plugin before printing
+    pub transforms: Vec<String>,
+}
+
+struct Plugin {
+    name: String,
+    module: PathBuf,
+    transforms: Vec<String>,
+}
+
+struct Plugins {
+    runtime: String,
+    plugins: Vec<Plugin>,
+}
+
+static ACTIVE: OnceLock<Plugins> = OnceLock::new();
+
+/// Register the configured plugins' commands for the rest of the process
+pub fn register(
+    configs: &BTreeMap<String, PluginConfig>,
+    runtime: Option<&str>,
+    config_dir: &Path,
+) -> Result<(), String> {
+    let mut plugins = Vec::new();
+    let mut specs = Vec::new();
+    for (name, config) in configs {
+        let valid = !name.is_empty()
+            && name
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-');
+        if !valid {
+            return Err(format!(
+                "Plugin name '{}' must be lowercase letters, digits or '-'",
+                name
+            ));
+        }
+        if registry::find_command(name).is_some() {
+            return Err(format!(
+                "Plugin '{}' has the name of a built-in command",
+                name
+            ));
+        }
+        if config.module.as_os_str().is_empty() {
+            return Err(format!(
+                "Plugin '{}' needs a module = \"<file.wasm>\"",
+                name
+            ));
+        }
+
+        let leak = |s: String| -> &'static str { Box::leak(s.into_boxed_str()) };
+        specs.push(CommandSpec {
+            name: leak(name.clone()),
+            aliases: &[],
+            action: "plugin",
+            category: "Plugins",
+            description: leak(
+                config
+                    .description
+                    .clone()
+                    .unwrap_or_else(|| format!("Plugin {}", config.module.display())),
+            ),
+            usage: leak(
+                config
+                    .usage
+                    .clone()
+                    .unwrap_or_else(|| format!("{} [args...]", name)),
+            ),
+            args: &[ArgSpec {
+                name: "args",
+                description: "Passed to the plugin",
+                required: false,
+                variadic: true,
+            }],
+            flags: &[],
+            response: &[],
+            examples: &[],
+            related: &[],
+        });
+        plugins.push(Plugin {
+            name: name.clone(),
+            module: config_dir.join(&config.module),
+            transforms: config.transforms.clone(),
+        });
+    }
+    registry::register_plugins(specs);
+    ACTIVE
+        .set(Plugins {
+            runtime: runtime.unwrap_or(DEFAULT_RUNTIME).to_string(),
+            plugins,
+        })
+        .ok();
+    Ok(())
+}
+
+/// Run plugin `name` as a command
+pub fn run(name: &str, args: &[String], flags: &Flags) -> Result<Value, String> {
+    let active = ACTIVE.get().ok_or("No plugins are configured")?;
+    let plugin = active
+        .plugins
+        .iter()
+        .find(|p| p.name == name)
+        .ok_or_else(|| format!("No plugin named '{}'", name))?;
+    let task = json!({
+        "type": "command",
+        "command": name,
+        "args": args,
+        "flags": flag_map(&flags.extra),
+        "session": flags.session,
+    });
+    session(&active.runtime, plugin, &task, flags)
+}
+
+/// Pass a successful result of `command` (sending `action`) through each
+/// plugin transforming it
+pub fn transform(
+    command: &str,
+    action: &str,
+    resp: &mut Response,
+    flags: &Flags,
+) -> Result<(), String> {
+    let Some(active) = ACTIVE.get() else {
+        return Ok(());
+    };
+    for plugin in active
+        .plugins
+        .iter()
+        .filter(|p| p.transforms.iter().any(|t| t == command || t == action))
+    {
+        let Some(result) = resp.result.as_mut().filter(|_| resp.success) else {
+            return Ok(());
+        };
+        let task = json!({
+            "type": "transform",
+            "command": command,
+            "result": result,
+            "session": flags.session,
+        });
+        *result = session(&active.runtime, plugin, &task, flags)?;
+    }
+    Ok(())
+}
+
+/// `--name=value` and `--name` flags as an object
+fn flag_map(extra: &[String]) -> Map<String, Value> {
+    extra
+        .iter()
+        .filter_map(|flag| flag.strip_prefix("--"))
+        .map(|flag| match flag.split_once('=') {
+            Some((name, value)) => (name.to_string(), json!(value)),
+            None => (flag.to_string(), json!(true)),
+        })
+        .collect()
+}
+
+/// Run the module on `task`, answering its daemon commands until it
+/// reports a result
+fn session(runtime: &str, plugin: &Plugin, task: &Value, flags: &Flags) -> Result<Value, String> {
+    let words = split_words(runtime)?;
+    let (program, runtime_args) = words
+        .split_first()
+        .ok_or("wasm_runtime is empty in config.toml")?;
+    if !plugin.module.is_file() {
+        return Err(format!(
+            "Plugin '{}': module {} not found",
+            plugin.name,
+            plugin.module.display()
+        ));
+    }
+    let mut child = Command::new(program)
+        .args(runtime_args)
+        .arg(&plugin.module)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .map_err(|e| {
+            format!(
+                "Failed to start the WASM runtime '{}': {}. Install wasmtime or set \
+                 wasm_runtime in config.toml",
+                program, e
+            )
+        })?;
+    let mut stdin = child.stdin.take().ok_or("Plugin stdin unavailable")?;
+    let stdout = child.stdout.take().ok_or("Plugin stdout unavailable")?;
+    // Lines go to the plugin from their own thread, so one that prints
+    // while it reads a large task can't leave both sides blocked on a full
+    // pipe. A plugin that stops reading just ends the thread.
+    let (to_plugin, lines) = mpsc::channel::<String>();
+    let writer = thread::spawn(move || {
+        for line in lines {
+            if writeln!(stdin, "{}", line).is_err() {
+                break;
+            }
+        }
+    });
+    to_plugin.send(task.to_string()).ok();
+
+    let mut connection: Option<Connection> = None;
+    let mut outcome = None;
+    for (i, line) in BufReader::new(stdout).lines().enumerate() {
+        let Ok(line) = line else {
+            break;
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+        let message: Value = serde_json::from_str(&line).map_err(|_| {
+            format!(
+                "Plugin '{}' printed a line that isn't JSON: {}",
+                plugin.name, line
+            )
+        })?;
+        if let Some(error) = message.get("error") {
+            let error = error
+                .as_str()
+                .map_or_else(|| error.to_string(), String::from);
+            outcome = Some(Err(format!("Plugin '{}': {}", plugin.name, error)));
+            break;
+        }
+        if let Some(result) = message.get("result") {
+            outcome = Some(Ok(result.clone()));
+            break;
+        }
+        let id = format!("{}-{}", plugin.name, i + 1);
+        let response = match pipe::command(&line, &id, flags) {
+            Ok((id, command)) => pipe::send(&mut connection, &command, flags).unwrap_or_else(|e| {
+                connection = None;
+                Response::err(&id, e, Some("connection_failed"))
+            }),
+            Err((e, code)) => Response::err(&id, e, Some(code)),
+        };
+        to_plugin
+            .send(serde_json::to_string(&response).unwrap_or_default())
+            .ok();
+    }
+    drop(to_plugin);
+    // Done once it has answered, whether or not it exits by itself
+    if outcome.is_some() {
+        child.kill().ok();
+    }
+    let status = child
+        .wait()
+        .map_err(|e| format!("Plugin '{}' failed: {}", plugin.name, e))?;
+    writer.join().ok();
+    outcome.unwrap_or_else(|| {
+        Err(format!(
+            "Plugin '{}' exited ({}) without a result",
+            plugin.name, status
+        ))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    /// A shell script standing in for a module, with `sh` as the runtime
+    fn plugin(name: &str, script: &str) -> Plugin {
+        let dir = std::env::temp_dir().join(format!("plugins-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let module = dir.join(format!("{}.sh", name));
+        fs::write(&module, script).unwrap();
+        Plugin {
+            name: name.to_string(),
+            module,
+            transforms: Vec::new(),
+        }
+    }
+
+    fn flags() -> Flags {
+        Flags::parse(&[])
+    }
+
+    #[test]
+    fn command_task_and_result() {
+        let echo = plugin(
+            "echo",
+            "read task; printf '{\"result\": %s}\\n' \"$task\"\n",
+        );
+        let task = json!({"type": "command", "command": "echo", "args": ["a", "b"]});
+        assert_eq!(session("sh", &echo, &task, &flags()).unwrap(), task);
+    }
+
+    #[test]
+    fn error_line() {
+        let failing = plugin("failing", "read task; echo '{\"error\": \"no prices\"}'\n");
+        let err = session("sh", &failing, &json!({}), &flags()).unwrap_err();
+        assert_eq!(err, "Plugin 'failing': no prices");
+    }
+
+    #[test]
+    fn output_that_isnt_json() {
+        let chatty = plugin("chatty", "read task; echo hello\n");
+        let err = session("sh", &chatty, &json!({}), &flags()).unwrap_err();
+        assert!(err.contains("isn't JSON: hello"), "{}", err);
+    }
+
+    #[test]
+    fn exit_without_a_result() {
+        let quiet = plugin("quiet", "read task; exit 2\n");
+        let err = session("sh", &quiet, &json!({}), &flags()).unwrap_err();
+        assert!(err.contains("without a result"), "{}", err);
+    }
+
+    #[test]
+    fn missing_module_and_runtime() {
+        let mut gone = plugin("gone", "");
+        gone.module.set_extension("missing");
+        assert!(session("sh", &gone, &json!({}), &flags())
+            .unwrap_err()
+            .contains("not found"));
+        let real = plugin("real", "");
+        assert!(session("no-such-runtime-xyz", &real, &json!({}), &flags())
+            .unwrap_err()
+            .contains("Failed to start the WASM runtime"));
+    }
+
+    #[test]
+    fn daemon_commands_get_a_response_line() {
+        // Read-only mode answers a click without reaching a daemon
+        let mut flags = flags();
+        flags.read_only = true;
+        let clicker = plugin(
+            "clicker",
+            "read task\n\
+             echo '{\"action\": \"click\", \"selector\": \"#buy\"}'\n\
+             read response\n\
+             printf '{\"result\": %s}\\n' \"$response\"\n",
+        );
+        let response = session("sh", &clicker, &json!({}), &flags).unwrap();
+        assert_eq!(response["success"], false);
+        assert_eq!(response["id"], "clicker-1");
+        assert_eq!(response["code"], "read_only");
+    }
+
+    #[test]
+    fn large_result_streamed_back() {
+        // tr writes as it reads, so the task must be written while the
+        // reply is read
+        let upper = plugin(
+            "upper",
+            "printf '{\"result\": '; head -n 1 | tr x y | tr -d '\\n'; echo '}'\n",
+        );
+        let html = "x".repeat(4 << 20);
+        let task = json!({"type": "transform", "command": "html", "result": {"html": html}});
+        let result = session("sh", &upper, &task, &flags()).unwrap();
+        assert_eq!(result["result"]["html"], "y".repeat(4 << 20));
+    }
+}
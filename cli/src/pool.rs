@@ -0,0 +1,300 @@
+/*!
+ * Session Pools
+ *
+ * `pool start --size=5` brings up a set of member sessions
+ * (`pool-<name>-0` ...) and `--pool` routes a command to whichever member is
+ * free, so parallel agents stop colliding on one session. A member is busy
+ * while a lock file holding the owning CLI's pid exists; locks left by
+ * processes that died are reclaimed. Members whose daemon has crashed or
+ * stopped answering are restarted (and re-seeded from the template) before
+ * they are handed out.
+ */
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use crate::commands::CommandJson;
+use crate::connection::{daemon_pid, ensure_daemon, is_daemon_ready, send_command, stop_daemon};
+use crate::flags::Flags;
+use crate::vault;
+
+/// Members when `--size` is not given
+pub const DEFAULT_SIZE: usize = 2;
+
+/// How long to wait for a free member before giving up
+const ACQUIRE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How long a lock may go without a pid in it before it counts as abandoned
+const LOCK_GRACE: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Pool {
+    pub name: String,
+    pub members: Vec<String>,
+    /// Storage state file loaded into every new member
+    #[serde(default)]
+    pub template: Option<String>,
+}
+
+/// A member reserved for this process; released on drop
+pub struct Lease {
+    pub session: String,
+    lock: String,
+}
+
+impl Drop for Lease {
+    fn drop(&mut self) {
+        fs::remove_file(&self.lock).ok();
+    }
+}
+
+fn temp_path(name: String) -> String {
+    env::temp_dir().join(name).to_string_lossy().into_owned()
+}
+
+fn get_pool_path(name: &str) -> String {
+    temp_path(format!("agentbrowser-pro-pool-{}.json", name))
+}
+
+fn get_lock_path(session: &str) -> String {
+    temp_path(format!("agentbrowser-pro-{}.lock", session))
+}
+
+/// Load a started pool
+pub fn load(name: &str) -> Result<Pool, String> {
+    let content = fs::read_to_string(get_pool_path(name))
+        .map_err(|_| format!("Pool '{}' is not running. Start it with 'pool start'", name))?;
+    serde_json::from_str(&content).map_err(|e| format!("Corrupt pool file for '{}': {}", name, e))
+}
+
+/// Make sure a member's daemon is up and answering, restarting it if not
+fn heal(pool: &Pool, session: &str, flags: &Flags) -> Result<(), String> {
+    if daemon_pid(session).is_some() && !is_daemon_ready(session) {
+        stop_daemon(session)?;
+    }
+    let started = ensure_daemon(
+        session,
+        flags.headed,
+        flags.executable_path.as_deref(),
+        &flags.extensions,
+    )?;
+    if !started.already_running {
+        if let Some(ref template) = pool.template {
+            let mut load = CommandJson::new("loadState");
+            load.path = Some(template.clone());
+            let staged = vault::prepare(&mut load, false)?;
+            let mut resp = send_command(&load, session)?;
+            if let Some(staged) = staged {
+                vault::finish(staged, &mut resp)?;
+            }
+            if !resp.success {
+                return Err(resp
+                    .error
+                    .unwrap_or_else(|| format!("Failed to load template into {}", session)));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Start (or top up) a pool of `size` members
+pub fn start(
+    name: &str,
+    size: usize,
+    template: Option<String>,
+    flags: &Flags,
+) -> Result<Pool, String> {
+    if let Some(ref template) = template {
+        if !Path::new(template).exists() {
+            return Err(format!("Template '{}' does not exist", template));
+        }
+    }
+
+    let pool = Pool {
+        name: name.to_string(),
+        members: (0..size).map(|i| format!("pool-{}-{}", name, i)).collect(),
+        template,
+    };
+    for member in &pool.members {
+        heal(&pool, member, flags)?;
+    }
+
+    let json = serde_json::to_string_pretty(&pool).unwrap_or_default();
+    fs::write(get_pool_path(name), json).map_err(|e| format!("Failed to save pool: {}", e))?;
+    Ok(pool)
+}
+
+/// Stop every member and forget the pool
+pub fn stop(name: &str) -> Result<Pool, String> {
+    let pool = load(name)?;
+    for member in &pool.members {
+        stop_daemon(member)?;
+        fs::remove_file(get_lock_path(member)).ok();
+    }
+    fs::remove_file(get_pool_path(name)).ok();
+    Ok(pool)
+}
+
+/// Per-member health and lock state
+pub fn status(name: &str) -> Result<serde_json::Value, String> {
+    let pool = load(name)?;
+    let members: Vec<serde_json::Value> = pool
+        .members
+        .iter()
+        .map(|member| {
+            serde_json::json!({
+                "session": member,
+                "pid": daemon_pid(member),
+                "healthy": daemon_pid(member).is_some() && is_daemon_ready(member),
+                "busy": lock_owner(member).is_some(),
+            })
+        })
+        .collect();
+    Ok(serde_json::json!({
+        "name": pool.name,
+        "size": pool.members.len(),
+        "template": pool.template,
+        "members": members,
+    }))
+}
+
+/// PID holding a member's lock, if that process is still alive
+fn lock_owner(session: &str) -> Option<i32> {
+    let pid: i32 = fs::read_to_string(get_lock_path(session))
+        .ok()?
+        .trim()
+        .parse()
+        .ok()?;
+    #[cfg(unix)]
+    unsafe {
+        if libc::kill(pid, 0) == 0 {
+            return Some(pid);
+        }
+    }
+    None
+}
+
+/// Whether a member's lock was left behind: its owner has exited, or it has
+/// held no pid for longer than writing one takes
+fn is_stale(session: &str) -> bool {
+    let lock = get_lock_path(session);
+    match fs::read_to_string(&lock) {
+        Ok(content) if content.trim().parse::<i32>().is_ok() => lock_owner(session).is_none(),
+        Ok(_) => fs::metadata(&lock)
+            .and_then(|meta| meta.modified())
+            .is_ok_and(|modified| modified.elapsed().is_ok_and(|age| age > LOCK_GRACE)),
+        Err(_) => false,
+    }
+}
+
+/// Try to lock one member
+fn try_lock(session: &str) -> Option<String> {
+    let lock = get_lock_path(session);
+    if is_stale(session) {
+        // Left behind by a process that exited without releasing it
+        fs::remove_file(&lock).ok();
+    }
+    // Write the pid beside the lock and link it into place, so the lock
+    // never exists empty for another process to mistake for a stale one
+    static STAGED: AtomicUsize = AtomicUsize::new(0);
+    let staged = format!(
+        "{}.{}-{}",
+        lock,
+        std::process::id(),
+        STAGED.fetch_add(1, Ordering::Relaxed)
+    );
+    fs::write(&staged, std::process::id().to_string()).ok()?;
+    let linked = fs::hard_link(&staged, &lock);
+    fs::remove_file(&staged).ok();
+    linked.ok()?;
+    Some(lock)
+}
+
+/// Reserve a free member, healing it first, waiting while all are busy
+pub fn acquire(name: &str, flags: &Flags) -> Result<Lease, String> {
+    let pool = load(name)?;
+    let deadline = Instant::now() + ACQUIRE_TIMEOUT;
+
+    loop {
+        for member in &pool.members {
+            if let Some(lock) = try_lock(member) {
+                let lease = Lease {
+                    session: member.clone(),
+                    lock,
+                };
+                heal(&pool, member, flags)?;
+                return Ok(lease);
+            }
+        }
+        if Instant::now() >= deadline {
+            return Err(format!(
+                "All {} members of pool '{}' stayed busy for {}s",
+                pool.members.len(),
+                name,
+                ACQUIRE_TIMEOUT.as_secs()
+            ));
+        }
+        thread::sleep(Duration::from_millis(100));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Barrier};
+
+    #[test]
+    fn racing_lockers_get_one_lock() {
+        let session = format!("pool-test-race-{}", std::process::id());
+        for _ in 0..20 {
+            let barrier = Arc::new(Barrier::new(8));
+            let handles: Vec<_> = (0..8)
+                .map(|_| {
+                    let barrier = Arc::clone(&barrier);
+                    let session = session.clone();
+                    thread::spawn(move || {
+                        barrier.wait();
+                        try_lock(&session).is_some()
+                    })
+                })
+                .collect();
+            let won = handles
+                .into_iter()
+                .map(|handle| handle.join().unwrap())
+                .filter(|&won| won)
+                .count();
+            assert_eq!(won, 1);
+            assert_eq!(lock_owner(&session), Some(std::process::id() as i32));
+            fs::remove_file(get_lock_path(&session)).unwrap();
+        }
+    }
+
+    #[test]
+    fn a_lock_being_written_is_not_reclaimed() {
+        let session = format!("pool-test-empty-{}", std::process::id());
+        let lock = get_lock_path(&session);
+        fs::write(&lock, "").unwrap();
+        assert!(try_lock(&session).is_none());
+        assert!(Path::new(&lock).exists());
+        fs::remove_file(lock).unwrap();
+    }
+
+    #[test]
+    fn locks_of_dead_processes_are_reclaimed() {
+        let session = format!("pool-test-stale-{}", std::process::id());
+        // Far above any pid_max, so never a live process
+        fs::write(get_lock_path(&session), "2147483646").unwrap();
+        let lock = try_lock(&session).expect("stale lock is reclaimed");
+        assert_eq!(
+            fs::read_to_string(&lock).unwrap(),
+            std::process::id().to_string()
+        );
+        assert!(try_lock(&session).is_none());
+        fs::remove_file(lock).unwrap();
+    }
+}
@@ -0,0 +1,137 @@
+/*!
+ * Process Resource Usage
+ *
+ * Reads CPU time, resident memory and uptime for the daemon and the browser
+ * processes it spawned from /proc. On systems without /proc every lookup
+ * comes back empty and `daemon stats` reports only what the daemon knows.
+ */
+use std::fs;
+
+use serde::Serialize;
+use serde_json::{json, Value};
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProcessStats {
+    pub pid: i32,
+    pub name: String,
+    pub cpu_seconds: f64,
+    pub rss_bytes: u64,
+    pub uptime_seconds: f64,
+}
+
+/// Clock ticks per second for /proc/<pid>/stat times
+fn clock_ticks() -> f64 {
+    #[cfg(unix)]
+    unsafe {
+        let ticks = libc::sysconf(libc::_SC_CLK_TCK);
+        if ticks > 0 {
+            return ticks as f64;
+        }
+    }
+    100.0
+}
+
+/// Fields of /proc/<pid>/stat after the parenthesised command name, plus
+/// the name itself
+fn read_stat(pid: i32) -> Option<(String, Vec<String>)> {
+    let stat = fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+    let open = stat.find('(')?;
+    let close = stat.rfind(')')?;
+    let name = stat[open + 1..close].to_string();
+    let fields = stat[close + 1..]
+        .split_whitespace()
+        .map(String::from)
+        .collect();
+    Some((name, fields))
+}
+
+/// Sample one process
+pub fn sample(pid: i32) -> Option<ProcessStats> {
+    let (name, fields) = read_stat(pid)?;
+    // Field numbers from proc(5), offset by the pid and name we stripped
+    let field = |n: usize| -> f64 {
+        fields
+            .get(n - 3)
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(0.0)
+    };
+    let ticks = clock_ticks();
+
+    let system_uptime: f64 = fs::read_to_string("/proc/uptime")
+        .ok()
+        .and_then(|s| s.split_whitespace().next()?.parse().ok())
+        .unwrap_or(0.0);
+
+    let rss_bytes = fs::read_to_string(format!("/proc/{}/status", pid))
+        .ok()
+        .and_then(|status| {
+            status
+                .lines()
+                .find_map(|line| line.strip_prefix("VmRSS:"))
+                .and_then(|v| v.trim().trim_end_matches("kB").trim().parse::<u64>().ok())
+        })
+        .map(|kb| kb * 1024)
+        .unwrap_or(0);
+
+    Some(ProcessStats {
+        pid,
+        name,
+        cpu_seconds: (field(14) + field(15)) / ticks,
+        rss_bytes,
+        uptime_seconds: (system_uptime - field(22) / ticks).max(0.0),
+    })
+}
+
+/// All live descendants of a process
+pub fn descendants(root: i32) -> Vec<i32> {
+    let mut parents: Vec<(i32, i32)> = Vec::new();
+    if let Ok(entries) = fs::read_dir("/proc") {
+        for entry in entries.flatten() {
+            let pid = match entry.file_name().to_str().and_then(|n| n.parse().ok()) {
+                Some(pid) => pid,
+                None => continue,
+            };
+            if let Some((_, fields)) = read_stat(pid) {
+                if let Some(ppid) = fields.get(1).and_then(|v| v.parse().ok()) {
+                    parents.push((pid, ppid));
+                }
+            }
+        }
+    }
+
+    let mut found = vec![root];
+    let mut i = 0;
+    while i < found.len() {
+        let parent = found[i];
+        found.extend(
+            parents
+                .iter()
+                .filter(|(_, ppid)| *ppid == parent)
+                .map(|(pid, _)| *pid),
+        );
+        i += 1;
+    }
+    found.remove(0);
+    found.sort();
+    found
+}
+
+/// Resource usage for a daemon and its browser processes
+pub fn report(daemon_pid: i32) -> Value {
+    let children: Vec<ProcessStats> = descendants(daemon_pid)
+        .into_iter()
+        .filter_map(sample)
+        .collect();
+    let cpu = children.iter().fold(0.0, |sum, p| sum + p.cpu_seconds);
+    let rss: u64 = children.iter().map(|p| p.rss_bytes).sum();
+
+    json!({
+        "process": sample(daemon_pid),
+        "browser": {
+            "processes": children.len(),
+            "cpuSeconds": (cpu * 100.0).round() / 100.0,
+            "rssBytes": rss,
+        },
+    })
+}
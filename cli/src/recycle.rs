@@ -0,0 +1,140 @@
+/*!
+ * Session Recycling
+ *
+ * Long scraping runs slowly degrade as the browser leaks memory.
+ * `--recycle-after=500,30m,800mb` restarts the daemon once it has served 500
+ * commands, been up 30 minutes or grown past 800 MB, carrying cookies,
+ * localStorage and the current URL over to the fresh browser.
+ */
+use std::env;
+use std::fs;
+use std::time::Duration;
+
+use crate::cache::parse_ttl;
+use crate::commands::CommandJson;
+use crate::connection::{daemon_pid, ensure_daemon, send_command, stop_daemon};
+use crate::flags::Flags;
+use crate::procinfo;
+
+#[derive(Debug, Default)]
+pub struct RecyclePolicy {
+    pub commands: Option<u64>,
+    pub age: Option<Duration>,
+    pub rss_mb: Option<u64>,
+}
+
+/// Parse a comma-separated policy: a bare number is a command count,
+/// `30s`/`30m`/`2h` an uptime and `800mb` a memory ceiling
+pub fn parse_policy(spec: &str) -> Result<RecyclePolicy, String> {
+    let mut policy = RecyclePolicy::default();
+    for part in spec.split(',').map(|p| p.trim().to_lowercase()) {
+        let invalid = || {
+            format!(
+                "Invalid --recycle-after value '{}': expected a command count (500), \
+                 duration (30m) or memory limit (800mb)",
+                part
+            )
+        };
+        if let Some(mb) = part.strip_suffix("mb") {
+            policy.rss_mb = Some(mb.parse().map_err(|_| invalid())?);
+        } else if let Some(hours) = part.strip_suffix('h') {
+            let hours: u64 = hours.parse().map_err(|_| invalid())?;
+            policy.age = Some(Duration::from_secs(hours * 3600));
+        } else if part.ends_with('s') || part.ends_with('m') {
+            policy.age = Some(parse_ttl(&part).ok_or_else(invalid)?);
+        } else {
+            policy.commands = Some(part.parse().map_err(|_| invalid())?);
+        }
+    }
+    Ok(policy)
+}
+
+/// Why the session's daemon is due for a restart, if it is
+fn due(policy: &RecyclePolicy, session: &str) -> Option<String> {
+    let pid = daemon_pid(session)?;
+    let resp = send_command(&CommandJson::new("daemonStats"), session).ok()?;
+    let stats = resp.result?;
+
+    if let Some(limit) = policy.commands {
+        let served = stats.get("commandsServed")?.as_u64()?;
+        if served >= limit {
+            return Some(format!("{} commands", served));
+        }
+    }
+    if let Some(limit) = policy.age {
+        let uptime = Duration::from_millis(stats.get("uptimeMs")?.as_u64()?);
+        if uptime >= limit {
+            return Some(format!("{}s uptime", uptime.as_secs()));
+        }
+    }
+    if let Some(limit) = policy.rss_mb {
+        let usage = procinfo::report(pid);
+        let daemon = usage["process"]["rssBytes"].as_u64().unwrap_or(0);
+        let browser = usage["browser"]["rssBytes"].as_u64().unwrap_or(0);
+        let mb = (daemon + browser) / (1024 * 1024);
+        if mb >= limit {
+            return Some(format!("{} MB resident", mb));
+        }
+    }
+    None
+}
+
+/// Where storage state is parked while the daemon restarts
+fn get_state_path(session: &str) -> String {
+    env::temp_dir()
+        .join(format!("agentbrowser-pro-{}.recycle-state.json", session))
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// Restart the daemon if the policy says so. Returns the reason when it did.
+pub fn maybe_recycle(policy: &RecyclePolicy, flags: &Flags) -> Result<Option<String>, String> {
+    match due(policy, &flags.session) {
+        Some(reason) => restart(flags).map(|_| Some(reason)),
+        None => Ok(None),
+    }
+}
+
+/// Restart the session's daemon with the current flags, carrying cookies,
+/// localStorage and the current URL over to the new browser
+pub fn restart(flags: &Flags) -> Result<(), String> {
+    let session = &flags.session;
+
+    // Remember where we were and what we were logged in as
+    let state_path = get_state_path(session);
+    let url = send_command(&CommandJson::new("getUrl"), session)
+        .ok()
+        .and_then(|resp| resp.result)
+        .and_then(|r| r.get("url").and_then(|v| v.as_str()).map(String::from))
+        .filter(|url| url.starts_with("http"));
+    let mut save = CommandJson::new("saveState");
+    save.path = Some(state_path.clone());
+    let saved = send_command(&save, session)
+        .map(|r| r.success)
+        .unwrap_or(false);
+
+    stop_daemon(session)?;
+    ensure_daemon(
+        session,
+        flags.headed,
+        flags.executable_path.as_deref(),
+        &flags.extensions,
+    )?;
+
+    // localStorage is only restored for the page's own origin, so load the
+    // state on the old URL and reload for the page to pick it up
+    if let Some(url) = url {
+        let mut navigate = CommandJson::new("navigate");
+        navigate.url = Some(url);
+        send_command(&navigate, session)?;
+    }
+    if saved {
+        let mut load = CommandJson::new("loadState");
+        load.path = Some(state_path.clone());
+        send_command(&load, session)?;
+        send_command(&CommandJson::new("reload"), session)?;
+        fs::remove_file(&state_path).ok();
+    }
+
+    Ok(())
+}
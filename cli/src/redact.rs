@@ -0,0 +1,314 @@
+/*!
+ * Redaction
+ *
+ * `--redact` (or AGENT_BROWSER_REDACT) masks personal data before it leaves
+ * the CLI, so captured pages can be handed to an LLM or kept on disk:
+ *
+ * - `--redact=builtin:email,cc,ssn` masks email addresses, card numbers
+ *   (13-19 digits passing the Luhn check) and US social security numbers
+ * - `--redact=<regex>` masks whatever the pattern matches, a line at a
+ *   time; the `regex` crate's syntax, which runs in linear time however long
+ *   a minified line gets
+ *
+ * It applies to every response from the daemon (text, HTML, snapshots and
+ * the files written from them with `--output-file`), to the policy log, and
+ * to HAR files recorded with `har`.
+ */
+use std::fs;
+use std::sync::OnceLock;
+
+use regex::{Captures, Regex};
+use serde_json::Value;
+
+use crate::connection::Response;
+
+const BUILTINS: &[&str] = &["email", "cc", "ssn"];
+
+#[derive(Debug)]
+pub struct Redactor {
+    builtins: Vec<&'static str>,
+    pattern: Option<Regex>,
+}
+
+impl Redactor {
+    pub fn parse(spec: &str) -> Result<Redactor, String> {
+        let Some(names) = spec.strip_prefix("builtin:") else {
+            return Ok(Redactor {
+                builtins: Vec::new(),
+                pattern: Some(
+                    Regex::new(spec).map_err(|e| format!("Invalid regex '{}': {}", spec, e))?,
+                ),
+            });
+        };
+        let builtins = names
+            .split(',')
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .map(|name| {
+                BUILTINS
+                    .iter()
+                    .copied()
+                    .find(|b| *b == name)
+                    .ok_or_else(|| {
+                        format!(
+                            "Unknown redaction '{}'; builtins are {}",
+                            name,
+                            BUILTINS.join(", ")
+                        )
+                    })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        if builtins.is_empty() {
+            return Err(format!(
+                "--redact=builtin: needs one of {}",
+                BUILTINS.join(", ")
+            ));
+        }
+        Ok(Redactor {
+            builtins,
+            pattern: None,
+        })
+    }
+
+    /// `text` with every match masked
+    pub fn text(&self, text: &str) -> String {
+        let mut chars: Vec<char> = text.chars().collect();
+        for builtin in &self.builtins {
+            chars = match *builtin {
+                "email" => mask(&chars, "email", email_at),
+                "cc" => mask(&chars, "cc", card_at),
+                _ => mask(&chars, "ssn", ssn_at),
+            };
+        }
+        let text: String = chars.into_iter().collect();
+        match self.pattern {
+            Some(ref pattern) => text
+                .split('\n')
+                .map(|line| {
+                    // Empty matches (`x*` between letters) have nothing to hide
+                    let mask = |m: &Captures| if m[0].is_empty() { "" } else { "[REDACTED]" };
+                    pattern.replace_all(line, mask)
+                })
+                .collect::<Vec<_>>()
+                .join("\n"),
+            None => text,
+        }
+    }
+
+    /// Mask every string in `value`, object keys excepted
+    pub fn value(&self, value: &mut Value) {
+        match value {
+            Value::String(s) => *s = self.text(s),
+            Value::Array(items) => items.iter_mut().for_each(|item| self.value(item)),
+            Value::Object(map) => map.values_mut().for_each(|item| self.value(item)),
+            _ => {}
+        }
+    }
+}
+
+static ACTIVE: OnceLock<Redactor> = OnceLock::new();
+
+/// Redact with `spec` for the rest of the process
+pub fn init(spec: &str) -> Result<(), String> {
+    ACTIVE.set(Redactor::parse(spec)?).ok();
+    Ok(())
+}
+
+/// The redactor `--redact` set up, if any
+pub fn active() -> Option<&'static Redactor> {
+    ACTIVE.get()
+}
+
+/// Redact a response's result and error
+pub fn response(resp: &mut Response) {
+    let Some(redactor) = active() else {
+        return;
+    };
+    if let Some(ref mut result) = resp.result {
+        redactor.value(result);
+    }
+    resp.error = resp.error.as_deref().map(|error| redactor.text(error));
+}
+
+/// Redact a JSON file in place, such as a recorded HAR
+pub fn file(path: &str) -> Result<(), String> {
+    let Some(redactor) = active() else {
+        return Ok(());
+    };
+    let content =
+        fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    let mut value: Value =
+        serde_json::from_str(&content).map_err(|e| format!("Failed to parse {}: {}", path, e))?;
+    redactor.value(&mut value);
+    let content = serde_json::to_string_pretty(&value).unwrap_or_default();
+    fs::write(path, content).map_err(|e| format!("Failed to write {}: {}", path, e))
+}
+
+/// Replace each span `find` reports (its length from a start index) with
+/// `[REDACTED:<name>]`
+fn mask(chars: &[char], name: &str, find: fn(&[char], usize) -> Option<usize>) -> Vec<char> {
+    let mut out = Vec::with_capacity(chars.len());
+    let mut i = 0;
+    while i < chars.len() {
+        match find(chars, i) {
+            Some(len) => {
+                out.extend(format!("[REDACTED:{}]", name).chars());
+                i += len;
+            }
+            None => {
+                out.push(chars[i]);
+                i += 1;
+            }
+        }
+    }
+    out
+}
+
+fn is_local_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || "._%+-".contains(c)
+}
+
+/// An email address starting at `i`: a local part, `@` and a dotted domain
+fn email_at(chars: &[char], i: usize) -> Option<usize> {
+    if i > 0 && is_local_char(chars[i - 1]) {
+        return None;
+    }
+    let at = i + chars[i..].iter().take_while(|&&c| is_local_char(c)).count();
+    if at == i || chars.get(at) != Some(&'@') {
+        return None;
+    }
+    let domain = &chars[at + 1..];
+    let mut len = domain
+        .iter()
+        .take_while(|&&c| c.is_ascii_alphanumeric() || c == '.' || c == '-')
+        .count();
+    // A sentence can end right after the address
+    while len > 0 && domain[len - 1] == '.' {
+        len -= 1;
+    }
+    let domain: String = domain[..len].iter().collect();
+    let tld = domain.rsplit_once('.').map(|(_, tld)| tld)?;
+    (tld.len() >= 2 && tld.chars().all(|c| c.is_ascii_alphabetic())).then_some(at + 1 + len - i)
+}
+
+/// A card number starting at `i`: 13-19 digits, optionally grouped with
+/// single spaces or dashes, passing the Luhn check
+fn card_at(chars: &[char], i: usize) -> Option<usize> {
+    if !chars[i].is_ascii_digit() || (i > 0 && chars[i - 1].is_ascii_alphanumeric()) {
+        return None;
+    }
+    let mut digits = Vec::new();
+    let mut end = i;
+    while end < chars.len() {
+        let c = chars[end];
+        if let Some(d) = c.to_digit(10) {
+            digits.push(d);
+        } else if !(c == ' ' || c == '-') || !chars.get(end + 1).is_some_and(|c| c.is_ascii_digit())
+        {
+            break;
+        }
+        end += 1;
+    }
+    if chars.get(end).is_some_and(|c| c.is_ascii_alphanumeric()) {
+        return None;
+    }
+    ((13..=19).contains(&digits.len()) && luhn(&digits)).then_some(end - i)
+}
+
+fn luhn(digits: &[u32]) -> bool {
+    let sum: u32 = digits
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(n, &d)| match (n % 2, d * 2) {
+            (1, doubled) if doubled > 9 => doubled - 9,
+            (1, doubled) => doubled,
+            _ => d,
+        })
+        .sum();
+    sum.is_multiple_of(10)
+}
+
+/// A US social security number starting at `i`: `ddd-dd-dddd`
+fn ssn_at(chars: &[char], i: usize) -> Option<usize> {
+    const SHAPE: &str = "ddd-dd-dddd";
+    if i > 0 && chars[i - 1].is_ascii_alphanumeric() {
+        return None;
+    }
+    let span = chars.get(i..i + SHAPE.len())?;
+    let fits = span
+        .iter()
+        .zip(SHAPE.chars())
+        .all(|(&c, shape)| match shape {
+            'd' => c.is_ascii_digit(),
+            _ => c == shape,
+        });
+    let bounded = !chars
+        .get(i + SHAPE.len())
+        .is_some_and(|c| c.is_ascii_alphanumeric());
+    (fits && bounded).then_some(SHAPE.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn regex_masks_each_match() {
+        let redactor = Redactor::parse(r"\w+@\w+\.com").unwrap();
+        assert_eq!(
+            redactor.text("mail ann@example.com or bob@test.com"),
+            "mail [REDACTED] or [REDACTED]"
+        );
+    }
+
+    #[test]
+    fn regex_works_a_line_at_a_time() {
+        let redactor = Redactor::parse(r"^secret.*$").unwrap();
+        assert_eq!(
+            redactor.text("public\nsecret key\nsecretly"),
+            "public\n[REDACTED]\n[REDACTED]"
+        );
+    }
+
+    #[test]
+    fn regex_skips_empty_matches() {
+        let redactor = Redactor::parse("x*").unwrap();
+        assert_eq!(redactor.text("abxxc"), "ab[REDACTED]c");
+    }
+
+    #[test]
+    fn invalid_regex_is_an_error() {
+        let err = Redactor::parse("(unclosed").unwrap_err();
+        assert!(err.starts_with("Invalid regex '(unclosed'"), "{}", err);
+    }
+
+    #[test]
+    fn long_lines_without_a_match() {
+        let redactor = Redactor::parse(r"\w+@\w+\.com").unwrap();
+        for len in [20_000, 200_000, 2_000_000] {
+            let line = "a".repeat(len);
+            assert_eq!(redactor.text(&line), line);
+        }
+        let near_misses = "user@host.co ".repeat(50_000);
+        assert_eq!(redactor.text(&near_misses), near_misses);
+    }
+
+    #[test]
+    fn long_line_with_a_match_at_the_end() {
+        let redactor = Redactor::parse(r"\w+@\w+\.com").unwrap();
+        let line = format!("{} ann@example.com", "a".repeat(200_000));
+        assert!(redactor.text(&line).ends_with(" [REDACTED]"));
+    }
+
+    #[test]
+    fn builtins() {
+        let redactor = Redactor::parse("builtin:email,cc,ssn").unwrap();
+        assert_eq!(
+            redactor.text("ann@example.com, 4111 1111 1111 1111, 123-45-6789."),
+            "[REDACTED:email], [REDACTED:cc], [REDACTED:ssn]."
+        );
+        assert_eq!(redactor.text("4111 1111 1111 1112"), "4111 1111 1111 1112");
+        assert!(Redactor::parse("builtin:phone").is_err());
+    }
+}
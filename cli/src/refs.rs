@@ -0,0 +1,241 @@
+/*!
+ * Snapshot Ref Cache
+ *
+ * Keeps the refs from the latest snapshot of each session on disk so failed
+ * lookups can be answered with nearby candidates instead of a bare timeout.
+ */
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::commands::CommandJson;
+use crate::connection::{send_command, Response};
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct RefData {
+    pub role: String,
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub nth: Option<u32>,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct RefCache {
+    #[serde(default)]
+    pub url: Option<String>,
+    #[serde(default)]
+    pub refs: BTreeMap<String, RefData>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Suggestion {
+    #[serde(rename = "ref")]
+    pub reference: String,
+    pub role: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+}
+
+/// Get the ref cache path for a session
+fn get_cache_path(session: &str) -> String {
+    env::temp_dir()
+        .join(format!("agentbrowser-pro-{}.refs.json", session))
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// Store the refs from a snapshot result
+pub fn save(session: &str, result: &Value) {
+    let cache = RefCache {
+        url: result.get("url").and_then(|v| v.as_str()).map(String::from),
+        refs: result
+            .get("refs")
+            .and_then(|v| serde_json::from_value(v.clone()).ok())
+            .unwrap_or_default(),
+    };
+    if let Ok(json) = serde_json::to_string(&cache) {
+        fs::write(get_cache_path(session), json).ok();
+    }
+}
+
+/// Load the refs from the latest snapshot, if any
+pub fn load(session: &str) -> Option<RefCache> {
+    let content = fs::read_to_string(get_cache_path(session)).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+/// Normalize a ref argument (`@e1`, `ref=e1`, `e1`) to its key
+pub fn parse_ref(arg: &str) -> Option<&str> {
+    let key = arg
+        .strip_prefix('@')
+        .or_else(|| arg.strip_prefix("ref="))
+        .unwrap_or(arg);
+    let digits = key.strip_prefix('e')?;
+    if !digits.is_empty() && digits.chars().all(|c| c.is_ascii_digit()) {
+        Some(key)
+    } else {
+        None
+    }
+}
+
+/// Whether an error message means the selector matched nothing
+pub fn is_not_found_error(error: &str) -> bool {
+    let error = error.to_lowercase();
+    error.contains("not found") || error.contains("was removed from the page")
+}
+
+/// Attach nearest-match suggestions to a "not found" error response
+pub fn enrich_not_found(resp: &mut Response, selector: &str, session: &str) {
+    let is_not_found = resp
+        .error
+        .as_deref()
+        .map(is_not_found_error)
+        .unwrap_or(false);
+    if resp.success || !is_not_found {
+        return;
+    }
+
+    let suggestions = load(session)
+        .map(|cache| suggest(&cache, selector, 3))
+        .unwrap_or_default();
+    if !suggestions.is_empty() {
+        resp.suggestions = serde_json::to_value(&suggestions).ok();
+    }
+
+    if parse_ref(selector).is_some() {
+        resp.code = Some("ref_stale".to_string());
+        resp.hint = Some(
+            "Refs go stale when the page changes. Run 'snapshot' again, or pass \
+             --auto-resolve-refs to re-resolve automatically."
+                .to_string(),
+        );
+    } else {
+        resp.hint = Some(
+            "Run 'snapshot' to refresh refs; the page may have changed since the last one."
+                .to_string(),
+        );
+    }
+}
+
+/// Whether a failed response is worth re-resolving the ref for
+pub fn is_stale_ref_failure(resp: &Response, selector: &str) -> bool {
+    !resp.success
+        && parse_ref(selector).is_some()
+        && resp
+            .error
+            .as_deref()
+            .map(is_not_found_error)
+            .unwrap_or(false)
+}
+
+/// Take a fresh snapshot and find the element a stale ref used to point at,
+/// matching on role and name. Returns the new ref (`@eN`).
+pub fn reresolve(selector: &str, session: &str) -> Result<String, String> {
+    let key = parse_ref(selector).ok_or_else(|| format!("{} is not a ref", selector))?;
+    let old = load(session)
+        .and_then(|cache| cache.refs.get(key).cloned())
+        .ok_or_else(|| format!("Ref @{} is not in the latest snapshot", key))?;
+
+    let mut snapshot = CommandJson::new("snapshot");
+    snapshot.interactive = Some(true);
+    let resp = send_command(&snapshot, session)?;
+    let result = match (resp.success, resp.result) {
+        (true, Some(result)) => result,
+        _ => {
+            return Err(resp
+                .error
+                .unwrap_or_else(|| "Snapshot failed while re-resolving ref".to_string()))
+        }
+    };
+    save(session, &result);
+
+    let fresh = load(session).unwrap_or_default();
+    let mut matches: Vec<&String> = fresh
+        .refs
+        .iter()
+        .filter(|(_, data)| data.role == old.role && data.name == old.name)
+        .map(|(k, _)| k)
+        .collect();
+    matches.sort_by_key(|k| ref_number(k));
+
+    // Prefer the same position among duplicates, as the daemon does
+    let index = old.nth.unwrap_or(0) as usize;
+    match matches.get(index).or(matches.first()) {
+        Some(new_key) => Ok(format!("@{}", new_key)),
+        None => Err(format!(
+            "Ref @{} is stale: no {} {} on the current page",
+            key,
+            old.role,
+            old.name.map(|n| format!("\"{}\"", n)).unwrap_or_default()
+        )),
+    }
+}
+
+/// Rank refs from the latest snapshot by similarity to a failed selector
+pub fn suggest(cache: &RefCache, selector: &str, limit: usize) -> Vec<Suggestion> {
+    // For a ref that failed, search by its recorded role/name instead so a
+    // stale ref still finds where that element went
+    let own_ref = parse_ref(selector);
+    let words = match own_ref.and_then(|r| cache.refs.get(r)) {
+        Some(data) => words_of(&format!(
+            "{} {}",
+            data.role,
+            data.name.as_deref().unwrap_or("")
+        )),
+        None => words_of(selector),
+    };
+    if words.is_empty() {
+        return Vec::new();
+    }
+
+    let mut scored: Vec<(usize, &String, &RefData)> = cache
+        .refs
+        .iter()
+        .filter(|(key, _)| Some(key.as_str()) != own_ref)
+        .filter_map(|(key, data)| {
+            let haystack =
+                format!("{} {}", data.role, data.name.as_deref().unwrap_or("")).to_lowercase();
+            let score = words
+                .iter()
+                .filter(|w| haystack.contains(w.as_str()))
+                .count();
+            if score > 0 {
+                Some((score, key, data))
+            } else {
+                None
+            }
+        })
+        .collect();
+
+    // Highest score first, then by ref number so output is stable
+    scored.sort_by(|a, b| {
+        b.0.cmp(&a.0)
+            .then_with(|| ref_number(a.1).cmp(&ref_number(b.1)))
+    });
+
+    scored
+        .into_iter()
+        .take(limit)
+        .map(|(_, key, data)| Suggestion {
+            reference: format!("@{}", key),
+            role: data.role.clone(),
+            name: data.name.clone(),
+        })
+        .collect()
+}
+
+/// Lowercase alphanumeric words of at least two characters
+fn words_of(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|w| w.len() > 1)
+        .map(|w| w.to_lowercase())
+        .collect()
+}
+
+pub fn ref_number(key: &str) -> u32 {
+    key.trim_start_matches('e').parse().unwrap_or(u32::MAX)
+}
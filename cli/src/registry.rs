@@ -0,0 +1,3174 @@
+/*!
+ * Command Registry
+ *
+ * Static description of every CLI command: names, aliases, arguments,
+ * flags and response shapes. The parser checks required arguments and
+ * reports usage from it, and `spec` output, help text, completions and
+ * "did you mean" suggestions are generated from it; how each action's
+ * result is printed for humans is registered in `render.rs`. Commands from
+ * WASM plugins (see `plugins.rs`) are added at startup.
+ */
+use std::sync::OnceLock;
+
+use serde::ser::SerializeMap;
+use serde::{Serialize, Serializer};
+
+#[derive(Debug, Serialize)]
+pub struct ArgSpec {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub required: bool,
+    pub variadic: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FlagSpec {
+    pub name: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub short: Option<char>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<&'static str>,
+    pub description: &'static str,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CommandSpec {
+    pub name: &'static str,
+    pub aliases: &'static [&'static str],
+    pub action: &'static str,
+    pub category: &'static str,
+    pub description: &'static str,
+    pub usage: &'static str,
+    pub args: &'static [ArgSpec],
+    pub flags: &'static [FlagSpec],
+    #[serde(serialize_with = "serialize_fields")]
+    pub response: &'static [(&'static str, &'static str)],
+    pub examples: &'static [&'static str],
+    pub related: &'static [&'static str],
+}
+
+/// Serialize `(field, type)` pairs as a JSON object
+fn serialize_fields<S: Serializer>(
+    fields: &&'static [(&'static str, &'static str)],
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    let mut map = serializer.serialize_map(Some(fields.len()))?;
+    for (name, kind) in fields.iter() {
+        map.serialize_entry(name, kind)?;
+    }
+    map.end()
+}
+
+const fn arg(name: &'static str, description: &'static str) -> ArgSpec {
+    ArgSpec {
+        name,
+        description,
+        required: true,
+        variadic: false,
+    }
+}
+
+const fn opt_arg(name: &'static str, description: &'static str) -> ArgSpec {
+    ArgSpec {
+        name,
+        description,
+        required: false,
+        variadic: false,
+    }
+}
+
+const fn rest_arg(name: &'static str, description: &'static str) -> ArgSpec {
+    ArgSpec {
+        name,
+        description,
+        required: true,
+        variadic: true,
+    }
+}
+
+const fn opt_rest_arg(name: &'static str, description: &'static str) -> ArgSpec {
+    ArgSpec {
+        name,
+        description,
+        required: false,
+        variadic: true,
+    }
+}
+
+const fn flag(name: &'static str, description: &'static str) -> FlagSpec {
+    FlagSpec {
+        name,
+        short: None,
+        value: None,
+        description,
+    }
+}
+
+const fn value_flag(
+    name: &'static str,
+    value: &'static str,
+    description: &'static str,
+) -> FlagSpec {
+    FlagSpec {
+        name,
+        short: None,
+        value: Some(value),
+        description,
+    }
+}
+
+impl FlagSpec {
+    const fn short(mut self, short: char) -> FlagSpec {
+        self.short = Some(short);
+        self
+    }
+}
+
+const SELECTOR: ArgSpec = arg("selector", "Element ref (@e1) or CSS selector");
+const TIMEOUT: FlagSpec = value_flag("--timeout", "ms", "Maximum time to wait");
+/// Response of scrollpos and scrollto
+const SCROLL_POSITION: &[(&str, &str)] = &[
+    ("x", "number"),
+    ("y", "number"),
+    ("maxX", "number"),
+    ("maxY", "number"),
+    ("viewportWidth", "number"),
+    ("viewportHeight", "number"),
+    ("atTop", "boolean"),
+    ("atBottom", "boolean"),
+];
+const FORCE: FlagSpec = flag(
+    "--force",
+    "Skip actionability and hit-testing checks, even if another element is on top",
+);
+const CACHE: FlagSpec = value_flag(
+    "--cache",
+    "ttl",
+    "Reuse a result younger than ttl (e.g. 2s)",
+);
+const OFFSET: FlagSpec = value_flag("--offset", "n", "Skip the first n items (bytes for text)");
+const LIMIT: FlagSpec = value_flag("--limit", "n", "Return at most n items (bytes for text)");
+const MAX_BYTES: FlagSpec = value_flag("--max-bytes", "n", "Cap the result at about n bytes");
+
+/// Flags accepted by every command
+pub static GLOBAL_FLAGS: &[FlagSpec] = &[
+    value_flag(
+        "--session",
+        "name",
+        "Use named session (default: \"default\")",
+    )
+    .short('s'),
+    flag("--headed", "Run browser in headed mode"),
+    flag("--json", "Output results as JSON").short('j'),
+    value_flag(
+        "--timeout",
+        "ms",
+        "Set command timeout (default: AGENT_BROWSER_DEFAULT_TIMEOUT or default_timeout in the config file)",
+    )
+    .short('t'),
+    value_flag("--executable-path", "path", "Path to browser executable"),
+    value_flag(
+        "--extensions",
+        "paths",
+        "Comma-separated extension directories",
+    ),
+    flag(
+        "--dry-run",
+        "Print the command payload without executing it",
+    ),
+    flag(
+        "--native-daemon",
+        "Start the built-in Rust daemon (core commands, CSS selectors) instead of the Node.js one",
+    ),
+    flag(
+        "--stdin",
+        "Read JSON command objects from stdin, one per line, and print one JSON response per line",
+    ),
+    flag(
+        "--read-only",
+        "Refuse commands that could change the page or site (click, fill, eval, cookies...)",
+    ),
+    value_flag(
+        "--allow-urls",
+        "patterns",
+        "Comma-separated URL globs navigate, newpage and popups must match (e.g. *.example.com)",
+    ),
+    value_flag(
+        "--deny-urls",
+        "patterns",
+        "Comma-separated URL globs navigate, newpage and popups may never reach",
+    ),
+    value_flag(
+        "--wait-before",
+        "ms|state|selector",
+        "Wait for a duration, load state or selector before the command",
+    ),
+    value_flag(
+        "--wait-after",
+        "ms|state|selector",
+        "Wait for a duration, load state or selector after the command",
+    ),
+    flag(
+        "--auto-resolve-refs",
+        "Re-resolve stale @refs from a fresh snapshot and retry once",
+    ),
+    flag(
+        "--fail-on-page-error",
+        "Fail if the page throws an uncaught error while the command runs",
+    ),
+    value_flag(
+        "--output-file",
+        "path",
+        "Write the result payload to a file and print a summary",
+    ),
+    value_flag(
+        "--pool",
+        "name",
+        "Run on any free member of a session pool (name optional)",
+    ),
+    value_flag(
+        "--recycle-after",
+        "n|duration|mb",
+        "Restart the daemon (keeping cookies and storage) past a limit",
+    ),
+    value_flag(
+        "--run-dir",
+        "dir",
+        "Collect output files in a directory with a manifest.json of hashes",
+    ),
+    value_flag(
+        "--downloads-dir",
+        "dir",
+        "Save downloads here (default: the run directory, or the current one)",
+    ),
+    value_flag(
+        "--redact",
+        "regex|builtin:email,cc,ssn",
+        "Mask personal data in results, output files, logs and HAR files",
+    ),
+    value_flag(
+        "--lang",
+        "code",
+        "Language for help and messages: en, es, ja, zh, de (default: from LANG)",
+    ),
+    flag("--help", "Show help (also: help <command>)").short('h'),
+    flag("--version", "Show version").short('v'),
+];
+
+pub static COMMANDS: &[CommandSpec] = &[
+    // ============ Lifecycle ============
+    CommandSpec {
+        name: "daemon",
+        aliases: &[],
+        action: "daemon",
+        category: "Other",
+        description: "Start browser daemon in the foreground, or report its resource usage",
+        usage: "daemon [stats]",
+        args: &[opt_arg(
+            "subcommand",
+            "stats: CPU, memory, pages, uptime and commands served",
+        )],
+        flags: &[],
+        response: &[
+            ("pid", "number"),
+            ("uptimeMs", "number"),
+            ("commandsServed", "number"),
+            ("pages", "number"),
+            ("memory", "object"),
+            (
+                "process",
+                "{pid, name, cpuSeconds, rssBytes, uptimeSeconds}",
+            ),
+            ("browser", "{processes, cpuSeconds, rssBytes}"),
+        ],
+        examples: &[
+            "agentbrowser-pro daemon --headed",
+            "agentbrowser-pro daemon stats --json",
+        ],
+        related: &["mcp", "close"],
+    },
+    CommandSpec {
+        name: "mcp",
+        aliases: &[],
+        action: "mcp",
+        category: "Other",
+        description: "Start MCP server",
+        usage: "mcp",
+        args: &[],
+        flags: &[],
+        response: &[],
+        examples: &["agentbrowser-pro mcp"],
+        related: &["daemon"],
+    },
+    CommandSpec {
+        name: "spec",
+        aliases: &[],
+        action: "spec",
+        category: "Other",
+        description: "Print the command registry",
+        usage: "spec [--json]",
+        args: &[],
+        flags: &[],
+        response: &[],
+        examples: &["agentbrowser-pro spec --json"],
+        related: &["help"],
+    },
+    CommandSpec {
+        name: "pool",
+        aliases: &[],
+        action: "poolStatus",
+        category: "Other",
+        description: "Manage a pool of sessions that --pool routes commands to",
+        usage: "pool <start|status|stop> [--size=5] [--template=<state.json>]",
+        args: &[opt_arg("subcommand", "start, status (default) or stop")],
+        flags: &[
+            value_flag("--size", "n", "Members to start (default: 2)"),
+            value_flag(
+                "--template",
+                "path",
+                "Storage state (from 'state save') loaded into each member",
+            ),
+        ],
+        response: &[
+            ("name", "string"),
+            ("size", "number"),
+            ("template", "string?"),
+            ("members", "[{session, pid, healthy, busy}]"),
+        ],
+        examples: &[
+            "agentbrowser-pro pool start --size=5 --template=auth.json",
+            "agentbrowser-pro --pool snapshot",
+            "agentbrowser-pro --pool=scrapers pool status",
+        ],
+        related: &["worker", "daemon"],
+    },
+    CommandSpec {
+        name: "run",
+        aliases: &[],
+        action: "runScript",
+        category: "Scripts",
+        description: "Run a file of commands, one per line, with 'on <event>' hooks",
+        usage: "run <script> [--data=<csv|json>] [--parallel=N] [--continue-on-error]",
+        args: &[arg(
+            "script",
+            "File of commands; '#' starts a comment, 'on <popup|dialog|download|console-error> \
+             <command>' declares a hook, 'if visible <sel> then <cmd> [else <cmd>]' branches, \
+             'foreach <sel> do ... end' loops with $item and $index bound, \
+             'set NAME = <command>' stores a value for later ${NAME}",
+        )],
+        flags: &[
+            value_flag(
+                "--data",
+                "file",
+                "Run once per row of a CSV (with header) or JSON array, columns bound as $column",
+            ),
+            value_flag(
+                "--parallel",
+                "n",
+                "With --data, rows to run at once, each on its own session (default: 1)",
+            ),
+            flag(
+                "--continue-on-error",
+                "Run the remaining steps after one fails; the run still exits 1",
+            ),
+        ],
+        response: &[
+            ("success", "boolean"),
+            ("steps", "[{line, command, response, hook?, set?}]"),
+            ("failures", "[{line, command, error}]"),
+            ("vars", "object"),
+            ("rows", "[{row, data, success, steps, ...}] with --data"),
+            ("durationMs", "number"),
+        ],
+        examples: &[
+            "agentbrowser-pro run checkout.txt",
+            "printf 'foreach .row do click $item; text .detail; back\\n' > rows.txt && agentbrowser-pro run rows.txt",
+            "agentbrowser-pro --json --session=ci run smoke.txt",
+            "agentbrowser-pro run regression.txt --continue-on-error",
+            "agentbrowser-pro run signup.txt --data=users.csv --parallel=4",
+            "printf 'set ID = text .order-id\\nnavigate https://admin.example.com/orders/${ID}\\n' > order.txt && agentbrowser-pro run order.txt",
+        ],
+        related: &["if", "worker"],
+    },
+    CommandSpec {
+        name: "if",
+        aliases: &[],
+        action: "if",
+        category: "Scripts",
+        description: "Run a command only if an element is visible (or another if not)",
+        usage: "if visible <selector> then <command> [else <command>]",
+        args: &[
+            arg("condition", "visible <selector|ref>"),
+            rest_arg("branches", "then <command> [else <command>]"),
+        ],
+        flags: &[],
+        response: &[("branch", "null when no command ran; else the command's own response")],
+        examples: &[
+            "agentbrowser-pro if visible .promo-modal then click .promo-close",
+            "agentbrowser-pro if visible '#login' then fill '#user' me else snapshot",
+        ],
+        related: &["run", "visible"],
+    },
+    CommandSpec {
+        name: "worker",
+        aliases: &[],
+        action: "worker",
+        category: "Other",
+        description: "Run command-script jobs from a directory or redis queue",
+        usage: "worker --queue=<dir|redis-url> [--concurrency=4] [--pool=<name>] [--drain]",
+        args: &[],
+        flags: &[
+            value_flag(
+                "--queue",
+                "dir|redis-url",
+                "Job directory, or redis://[[user]:password@]host:port/list",
+            ),
+            value_flag(
+                "--concurrency",
+                "n",
+                "Jobs to run in parallel, each on a leased pool member (default: 1)",
+            ),
+            value_flag(
+                "--pool",
+                "name",
+                "Lease members of this started pool instead of starting one",
+            ),
+            flag("--drain", "Exit once the queue is empty"),
+        ],
+        response: &[
+            ("id", "string"),
+            ("success", "boolean"),
+            ("session", "string"),
+            ("steps", "array"),
+            ("durationMs", "number"),
+            ("metadata", "any"),
+        ],
+        examples: &[
+            "agentbrowser-pro worker --queue=./jobs --concurrency=4",
+            "agentbrowser-pro worker --queue=redis://localhost:6379/scrape --drain",
+        ],
+        related: &["bench", "daemon"],
+    },
+    CommandSpec {
+        name: "crawl",
+        aliases: &[],
+        action: "crawl",
+        category: "Crawling",
+        description: "Visit a site breadth-first from a seed URL, one line per page",
+        usage: "crawl <url> [--max-pages=100] [--max-depth=3] [--state=<file>] | crawl --resume <file>",
+        args: &[opt_arg(
+            "url",
+            "Seed URL; with --resume, the state file to continue from",
+        )],
+        flags: &[
+            value_flag("--max-pages", "n", "Stop after this many pages (default: 100)"),
+            value_flag("--max-depth", "n", "Links to follow away from the seed (default: 3)"),
+            flag("--all-origins", "Follow links to other origins too"),
+            flag(
+                "--seed-from-sitemap",
+                "Start from the URLs in robots.txt sitemaps (or /sitemap.xml) and honor Crawl-delay and Disallow",
+            ),
+            value_flag(
+                "--delay",
+                "ms",
+                "Pause between pages (overrides robots.txt Crawl-delay)",
+            ),
+            value_flag(
+                "--dedupe",
+                "content|url-normalized",
+                "Skip pages whose text, or URL minus tracking params, was already seen",
+            ),
+            value_flag(
+                "--state",
+                "file",
+                "Save the frontier and visited set after every page",
+            ),
+            flag("--resume", "Continue the crawl saved in the given state file"),
+        ],
+        response: &[
+            ("url", "string"),
+            ("depth", "number"),
+            ("status", "number"),
+            ("title", "string"),
+            ("links", "number"),
+            ("queued", "number"),
+            ("contentHash", "string?"),
+            ("skipped", "\"duplicate\"?"),
+            ("duplicateOf", "string?"),
+            ("error", "string?"),
+        ],
+        examples: &[
+            "agentbrowser-pro crawl https://example.com --max-pages=500 --state=crawl.json",
+            "agentbrowser-pro crawl --resume crawl.json",
+            "agentbrowser-pro crawl https://example.com --seed-from-sitemap --max-depth=0",
+            "agentbrowser-pro --json crawl https://example.com/docs --max-depth=2 > pages.ndjson",
+        ],
+        related: &["navigate", "run"],
+    },
+    CommandSpec {
+        name: "repl",
+        aliases: &["shell"],
+        action: "repl",
+        category: "Other",
+        description: "Interactive shell: Tab completes command names and @refs from the last snapshot; \
+                      ':save <file>' writes the commands run as a script, secrets redacted",
+        usage: "repl",
+        args: &[],
+        flags: &[],
+        response: &[],
+        examples: &["agentbrowser-pro repl", "agentbrowser-pro -s checkout repl"],
+        related: &["snapshot", "run"],
+    },
+    CommandSpec {
+        name: "pagediff",
+        aliases: &[],
+        action: "pagediffCompare",
+        category: "Crawling",
+        description: "Save a snapshot of the page and later report what changed",
+        usage: "pagediff <save|compare> <name> [--selector=<sel>] [--mode=text|html|visual]",
+        args: &[
+            arg("subcommand", "save or compare"),
+            arg("name", "Snapshot name (letters, digits, '_', '-' or '.')"),
+        ],
+        flags: &[
+            value_flag(
+                "--selector",
+                "sel",
+                "save: snapshot only this element (compare reuses it)",
+            ),
+            value_flag(
+                "--mode",
+                "text|html|visual",
+                "compare: rendered text lines, markup lines or pixels (default: text)",
+            ),
+            flag("--update", "compare: replace the snapshot with the new capture"),
+        ],
+        response: &[
+            ("name", "string"),
+            ("url", "string"),
+            ("savedAt", "number"),
+            ("changed", "boolean (compare)"),
+            ("added", "string[] (compare text/html)"),
+            ("removed", "string[] (compare text/html)"),
+            ("diffPixels", "number (compare visual)"),
+            ("ratio", "number (compare visual)"),
+            ("bbox", "{x,y,width,height}? (compare visual)"),
+            ("sizeChanged", "boolean (compare visual)"),
+        ],
+        examples: &[
+            "agentbrowser-pro pagediff save pricing --selector=main",
+            "agentbrowser-pro pagediff compare pricing",
+            "agentbrowser-pro pagediff compare home --mode=visual --update",
+        ],
+        related: &["crawl", "screenshot", "monitor"],
+    },
+    CommandSpec {
+        name: "monitor",
+        aliases: &[],
+        action: "monitorRun",
+        category: "Crawling",
+        description: "Watch pages for changes and notify a webhook or command",
+        usage: "monitor add <url> [--selector=<sel>] [--interval=10m] [--notify=<webhook|command>] | monitor <list|remove <id>|run [--once]>",
+        args: &[
+            arg("subcommand", "add, list, remove or run"),
+            opt_arg("target", "add: URL to watch; remove: monitor id"),
+        ],
+        flags: &[
+            value_flag("--selector", "sel", "add: watch only this element"),
+            value_flag(
+                "--interval",
+                "duration",
+                "add: time between checks, like 30s, 10m or 1h (default: 10m)",
+            ),
+            value_flag(
+                "--mode",
+                "text|html|visual",
+                "add: what counts as a change (default: text)",
+            ),
+            value_flag(
+                "--notify",
+                "webhook|command",
+                "add: URL to POST each change to, or shell command to run with it on stdin",
+            ),
+            flag("--once", "run: check every monitor now, then exit"),
+        ],
+        response: &[
+            ("id", "string"),
+            ("url", "string"),
+            ("checkedAt", "number (run)"),
+            ("baseline", "boolean? (run: first check)"),
+            ("changed", "boolean (run)"),
+            ("added", "string[] (run, text/html)"),
+            ("removed", "string[] (run, text/html)"),
+            ("diffPixels", "number (run, visual)"),
+            ("notified", "boolean? (run)"),
+            ("notifyError", "string? (run)"),
+            ("error", "string? (run)"),
+        ],
+        examples: &[
+            "agentbrowser-pro monitor add https://example.com/pricing --selector=main --interval=1h --notify=https://hooks.example.com/changes",
+            "agentbrowser-pro monitor add https://example.com/status --interval=30s --notify='jq .added | mail -s changed me@example.com'",
+            "agentbrowser-pro monitor list",
+            "agentbrowser-pro --json monitor run",
+            "agentbrowser-pro monitor run --once",
+        ],
+        related: &["pagediff"],
+    },
+    CommandSpec {
+        name: "bench",
+        aliases: &[],
+        action: "bench",
+        category: "Other",
+        description: "Measure CLI and daemon latency for a command",
+        usage: "bench <command...> [--iterations=100]",
+        args: &[rest_arg("command", "Command to run, with its arguments")],
+        flags: &[value_flag(
+            "--iterations",
+            "n",
+            "Number of timed runs (default: 100)",
+        )],
+        response: &[
+            ("command", "string"),
+            ("iterations", "number"),
+            ("failures", "number"),
+            ("cli", "{p50, p95, p99, mean} (ms)"),
+            ("daemon", "{p50, p95, p99, mean} (ms)"),
+        ],
+        examples: &[
+            "agentbrowser-pro bench url",
+            "agentbrowser-pro bench snapshot --iterations=20",
+        ],
+        related: &["spec"],
+    },
+    CommandSpec {
+        name: "help",
+        aliases: &[],
+        action: "help",
+        category: "Other",
+        description: "Show general help or help for one command",
+        usage: "help [command]",
+        args: &[opt_arg("command", "Command to describe")],
+        flags: &[],
+        response: &[],
+        examples: &["agentbrowser-pro help", "agentbrowser-pro help click"],
+        related: &["spec"],
+    },
+    CommandSpec {
+        name: "alias",
+        aliases: &[],
+        action: "aliasList",
+        category: "Other",
+        description: "List command aliases defined in the config file",
+        usage: "alias list",
+        args: &[opt_arg("subcommand", "list")],
+        flags: &[],
+        response: &[("aliases", "object")],
+        examples: &["agentbrowser-pro alias list"],
+        related: &["help"],
+    },
+    CommandSpec {
+        name: "i18n",
+        aliases: &[],
+        action: "i18nCheck",
+        category: "Other",
+        description: "Check the translation catalogs against the command registry, or print one as a template",
+        usage: "i18n <check [lang] | template <lang>>",
+        args: &[
+            arg("subcommand", "check or template"),
+            opt_arg("lang", "es, ja, zh or de (check: all of them by default)"),
+        ],
+        flags: &[],
+        response: &[(
+            "<lang>",
+            "{complete, missing: {table: [key]}, stale: {table: [key]}, placeholders: [key]} (check)",
+        )],
+        examples: &[
+            "agentbrowser-pro i18n check",
+            "agentbrowser-pro i18n template ja > cli/locales/ja.toml",
+            "agentbrowser-pro --lang=de --help",
+        ],
+        related: &["help"],
+    },
+    CommandSpec {
+        name: "launch",
+        aliases: &[],
+        action: "launch",
+        category: "Other",
+        description: "Launch the browser",
+        usage: "launch",
+        args: &[],
+        flags: &[TIMEOUT],
+        response: &[("launched", "boolean")],
+        examples: &["agentbrowser-pro launch --headed"],
+        related: &["close", "navigate"],
+    },
+    CommandSpec {
+        name: "close",
+        aliases: &[],
+        action: "close",
+        category: "Other",
+        description: "Close browser",
+        usage: "close",
+        args: &[],
+        flags: &[TIMEOUT],
+        response: &[("closed", "boolean")],
+        examples: &["agentbrowser-pro close"],
+        related: &["launch", "daemon"],
+    },
+    // ============ Navigation ============
+    CommandSpec {
+        name: "navigate",
+        aliases: &["open", "goto"],
+        action: "navigate",
+        category: "Navigation",
+        description: "Navigate to a URL",
+        usage: "navigate <url>",
+        args: &[arg("url", "The URL to navigate to")],
+        flags: &[TIMEOUT],
+        response: &[("url", "string"), ("title", "string")],
+        examples: &[
+            "agentbrowser-pro navigate https://example.com",
+            "agentbrowser-pro navigate https://example.com --timeout=30000",
+        ],
+        related: &["back", "forward", "reload", "url"],
+    },
+    CommandSpec {
+        name: "back",
+        aliases: &[],
+        action: "back",
+        category: "Navigation",
+        description: "Go back in history",
+        usage: "back",
+        args: &[],
+        flags: &[TIMEOUT],
+        response: &[("url", "string")],
+        examples: &["agentbrowser-pro back"],
+        related: &["forward", "navigate"],
+    },
+    CommandSpec {
+        name: "forward",
+        aliases: &[],
+        action: "forward",
+        category: "Navigation",
+        description: "Go forward in history",
+        usage: "forward",
+        args: &[],
+        flags: &[TIMEOUT],
+        response: &[("url", "string")],
+        examples: &["agentbrowser-pro forward"],
+        related: &["back", "navigate"],
+    },
+    CommandSpec {
+        name: "reload",
+        aliases: &["refresh"],
+        action: "reload",
+        category: "Navigation",
+        description: "Reload the page",
+        usage: "reload",
+        args: &[],
+        flags: &[TIMEOUT],
+        response: &[("url", "string")],
+        examples: &["agentbrowser-pro reload"],
+        related: &["navigate", "url"],
+    },
+    CommandSpec {
+        name: "handoff",
+        aliases: &[],
+        action: "handoff",
+        category: "Navigation",
+        description: "Open the current page in the user's browser so a person can take over",
+        usage: "handoff [--cookies|--print]",
+        args: &[],
+        flags: &[
+            flag(
+                "--cookies",
+                "Open it in a new Chrome window with a copy of the session's cookies",
+            ),
+            flag("--print", "Only print the URL"),
+        ],
+        response: &[
+            ("url", "string"),
+            ("opened", "boolean"),
+            ("profile", "string? (--cookies)"),
+            ("cookies", "number? (--cookies)"),
+        ],
+        examples: &[
+            "agentbrowser-pro handoff",
+            "agentbrowser-pro handoff --cookies",
+            "agentbrowser-pro --json handoff --print",
+        ],
+        related: &["url", "cookies", "wait"],
+    },
+    // ============ Interaction ============
+    CommandSpec {
+        name: "click",
+        aliases: &[],
+        action: "click",
+        category: "Interaction",
+        description: "Click an element",
+        usage: "click <selector|ref> [--force]",
+        args: &[SELECTOR],
+        flags: &[FORCE, TIMEOUT],
+        response: &[("clicked", "string")],
+        examples: &[
+            "agentbrowser-pro click @e1",
+            "agentbrowser-pro click \"button.submit\"",
+            "agentbrowser-pro click \"#login-button\"",
+            "agentbrowser-pro click @e5 --force",
+        ],
+        related: &["dblclick", "hover", "snapshot", "clicktext", "clickrole"],
+    },
+    CommandSpec {
+        name: "clicktext",
+        aliases: &[],
+        action: "clickByText",
+        category: "Interaction",
+        description: "Click the first visible element showing the given text",
+        usage: "clicktext <text> [--exact]",
+        args: &[rest_arg("text", "Visible text to look for")],
+        flags: &[
+            flag("--exact", "Match the whole text, case-sensitively"),
+            TIMEOUT,
+        ],
+        response: &[
+            ("clicked", "string"),
+            ("text", "string?"),
+            ("matches", "number"),
+        ],
+        examples: &[
+            "agentbrowser-pro clicktext Sign in",
+            "agentbrowser-pro clicktext \"Accept all cookies\" --exact",
+        ],
+        related: &["clickrole", "click"],
+    },
+    CommandSpec {
+        name: "clickrole",
+        aliases: &[],
+        action: "clickByRole",
+        category: "Interaction",
+        description: "Click the first visible element with an ARIA role and accessible name",
+        usage: "clickrole <role> <name> [--exact]",
+        args: &[
+            arg("role", "ARIA role: button, link, checkbox, tab, menuitem, ..."),
+            rest_arg("name", "Accessible name, as shown in the snapshot"),
+        ],
+        flags: &[
+            flag("--exact", "Match the whole name, case-sensitively"),
+            TIMEOUT,
+        ],
+        response: &[
+            ("clicked", "string"),
+            ("text", "string?"),
+            ("matches", "number"),
+        ],
+        examples: &[
+            "agentbrowser-pro clickrole button Submit",
+            "agentbrowser-pro clickrole link \"Next page\" --exact",
+        ],
+        related: &["clicktext", "click", "snapshot"],
+    },
+    CommandSpec {
+        name: "tabto",
+        aliases: &[],
+        action: "tabTo",
+        category: "Interaction",
+        description: "Press Tab until the focused element has the given role or name",
+        usage: "tabto <text|role> [--max=<n>]",
+        args: &[rest_arg(
+            "target",
+            "A role (button, link, textbox, ...) or part of an accessible name",
+        )],
+        flags: &[value_flag(
+            "--max",
+            "n",
+            "Give up after n Tab presses (default: 50)",
+        )],
+        response: &[
+            ("presses", "number"),
+            ("role", "string"),
+            ("name", "string"),
+            ("stops", "{role, name}[]"),
+        ],
+        examples: &[
+            "agentbrowser-pro tabto \"Skip to content\"",
+            "agentbrowser-pro tabto textbox --max=10",
+        ],
+        related: &["press", "focus", "clickrole"],
+    },
+    CommandSpec {
+        name: "dblclick",
+        aliases: &["doubleclick"],
+        action: "dblclick",
+        category: "Interaction",
+        description: "Double-click an element",
+        usage: "dblclick <selector|ref> [--force]",
+        args: &[SELECTOR],
+        flags: &[FORCE, TIMEOUT],
+        response: &[("doubleClicked", "string")],
+        examples: &["agentbrowser-pro dblclick @e4"],
+        related: &["click"],
+    },
+    CommandSpec {
+        name: "type",
+        aliases: &[],
+        action: "type",
+        category: "Interaction",
+        description: "Type text into an element (preserves existing content)",
+        usage: "type <selector|ref> <text>",
+        args: &[SELECTOR, rest_arg("text", "Text to type")],
+        flags: &[TIMEOUT],
+        response: &[("typed", "string")],
+        examples: &[
+            "agentbrowser-pro type @e1 \"Hello World\"",
+            "agentbrowser-pro type \"#search\" \"search query\"",
+        ],
+        related: &["fill", "press", "clear"],
+    },
+    CommandSpec {
+        name: "fill",
+        aliases: &[],
+        action: "fill",
+        category: "Interaction",
+        description: "Fill an input field (clears existing content first)",
+        usage: "fill <selector|ref> <value>",
+        args: &[SELECTOR, rest_arg("value", "Text to fill")],
+        flags: &[TIMEOUT],
+        response: &[("filled", "string")],
+        examples: &[
+            "agentbrowser-pro fill @e1 \"hello@example.com\"",
+            "agentbrowser-pro fill \"#email\" \"user@example.com\"",
+        ],
+        related: &["type", "clear", "value", "setdate", "write"],
+    },
+    CommandSpec {
+        name: "write",
+        aliases: &[],
+        action: "write",
+        category: "Interaction",
+        description: "Write text, markdown or HTML into a rich-text editor",
+        usage: "write <selector|ref> <markdown|text> [--html] [--clear]",
+        args: &[
+            SELECTOR,
+            rest_arg(
+                "content",
+                "Text or markdown (headings, lists, quotes, code, **bold**, *italic*, links)",
+            ),
+        ],
+        flags: &[
+            flag("--html", "The content is HTML, pasted as is"),
+            flag("--clear", "Replace the editor's content instead of appending to it"),
+            TIMEOUT,
+        ],
+        response: &[
+            ("method", "keyboard|paste|insertHTML"),
+            ("text", "string"),
+        ],
+        examples: &[
+            "agentbrowser-pro write \".ProseMirror\" \"Hello world\"",
+            "agentbrowser-pro write @e5 $'# Notes\\n\\n- **first**\\n- second' --clear",
+            "agentbrowser-pro write \"#editor\" \"<p>Hi <b>there</b></p>\" --html",
+        ],
+        related: &["fill", "type", "text"],
+    },
+    CommandSpec {
+        name: "paste",
+        aliases: &[],
+        action: "paste",
+        category: "Interaction",
+        description: "Paste content into an element with a real Ctrl/Cmd+V",
+        usage: "paste <selector|ref> <text|--from-clipboard|--from-file=<path>>",
+        args: &[
+            SELECTOR,
+            opt_rest_arg(
+                "text",
+                "Text to paste, unless --from-clipboard or --from-file is given",
+            ),
+        ],
+        flags: &[
+            flag(
+                "--from-clipboard",
+                "Paste what the page's clipboard already holds, e.g. after 'press Control+c'",
+            ),
+            value_flag(
+                "--from-file",
+                "path",
+                "Paste a file's contents; .html files paste as formatted content",
+            ),
+            TIMEOUT,
+        ],
+        response: &[
+            ("method", "keyboard|event"),
+            ("length", "number"),
+            ("value", "string"),
+        ],
+        examples: &[
+            "agentbrowser-pro paste \"#otp\" 493021",
+            "agentbrowser-pro paste @e4 --from-file=snippet.html",
+            "agentbrowser-pro paste \"textarea\" --from-clipboard",
+        ],
+        related: &["write", "fill", "press"],
+    },
+    CommandSpec {
+        name: "setdate",
+        aliases: &[],
+        action: "setDate",
+        category: "Interaction",
+        description: "Set a date or time field, native or a text date picker",
+        usage: "setdate <selector|ref> <YYYY-MM-DD[THH:MM]|HH:MM> [--format=<pattern>]",
+        args: &[
+            SELECTOR,
+            arg("date", "ISO date, date-time or time, e.g. 2024-03-05 or 2024-03-05T14:30"),
+        ],
+        flags: &[
+            value_flag(
+                "--format",
+                "pattern",
+                "How to type it into a text field, e.g. DD/MM/YYYY or M/D/YY HH:mm",
+            ),
+            TIMEOUT,
+        ],
+        response: &[
+            ("value", "string"),
+            ("input", "date|time|datetime-local|month|week|text"),
+            ("typed", "string?"),
+        ],
+        examples: &[
+            "agentbrowser-pro setdate \"#checkin\" 2024-03-05",
+            "agentbrowser-pro setdate @e8 2024-03-05T14:30",
+            "agentbrowser-pro setdate \".datepicker input\" 2024-03-05 --format=DD.MM.YYYY",
+        ],
+        related: &["fill", "value"],
+    },
+    CommandSpec {
+        name: "clear",
+        aliases: &[],
+        action: "clear",
+        category: "Interaction",
+        description: "Clear an input field",
+        usage: "clear <selector|ref>",
+        args: &[SELECTOR],
+        flags: &[TIMEOUT],
+        response: &[("cleared", "string")],
+        examples: &["agentbrowser-pro clear @e2"],
+        related: &["fill", "value"],
+    },
+    CommandSpec {
+        name: "check",
+        aliases: &[],
+        action: "check",
+        category: "Interaction",
+        description: "Check a checkbox/radio",
+        usage: "check <selector|ref>",
+        args: &[SELECTOR],
+        flags: &[TIMEOUT],
+        response: &[("checked", "string")],
+        examples: &["agentbrowser-pro check @e5"],
+        related: &["uncheck", "checked"],
+    },
+    CommandSpec {
+        name: "uncheck",
+        aliases: &[],
+        action: "uncheck",
+        category: "Interaction",
+        description: "Uncheck a checkbox",
+        usage: "uncheck <selector|ref>",
+        args: &[SELECTOR],
+        flags: &[TIMEOUT],
+        response: &[("unchecked", "string")],
+        examples: &["agentbrowser-pro uncheck @e5"],
+        related: &["check", "checked"],
+    },
+    CommandSpec {
+        name: "select",
+        aliases: &[],
+        action: "select",
+        category: "Interaction",
+        description: "Select dropdown option",
+        usage: "select <selector|ref> <value|label|index>",
+        args: &[SELECTOR, arg("value", "Option value, label or index")],
+        flags: &[TIMEOUT],
+        response: &[("selected", "string")],
+        examples: &["agentbrowser-pro select @e3 \"United States\""],
+        related: &["fill", "value"],
+    },
+    CommandSpec {
+        name: "hover",
+        aliases: &[],
+        action: "hover",
+        category: "Interaction",
+        description: "Hover over an element",
+        usage: "hover <selector|ref>",
+        args: &[SELECTOR],
+        flags: &[TIMEOUT],
+        response: &[("hovered", "string")],
+        examples: &["agentbrowser-pro hover @e7"],
+        related: &["click", "focus", "hoverpath"],
+    },
+    CommandSpec {
+        name: "hoverpath",
+        aliases: &[],
+        action: "hoverPath",
+        category: "Interaction",
+        description: "Hover a sequence of elements in one go, for nested hover menus",
+        usage: "hoverpath <selector|ref>... [--dwell=<ms>[,<ms>...]]",
+        args: &[rest_arg("selectors", "Elements to hover, in order")],
+        flags: &[
+            value_flag(
+                "--dwell",
+                "ms",
+                "Time on each element before the next: one value, or one per element (default: 100)",
+            ),
+            TIMEOUT,
+        ],
+        response: &[("hovered", "string[]")],
+        examples: &[
+            "agentbrowser-pro hoverpath \"nav >> text=Products\" \"text=Laptops\" @e12",
+            "agentbrowser-pro hoverpath @e3 @e9 --dwell=300,600",
+        ],
+        related: &["hover", "click"],
+    },
+    CommandSpec {
+        name: "focus",
+        aliases: &[],
+        action: "focus",
+        category: "Interaction",
+        description: "Focus an element",
+        usage: "focus <selector|ref>",
+        args: &[SELECTOR],
+        flags: &[TIMEOUT],
+        response: &[("focused", "string")],
+        examples: &["agentbrowser-pro focus @e2"],
+        related: &["press", "type"],
+    },
+    CommandSpec {
+        name: "press",
+        aliases: &[],
+        action: "press",
+        category: "Interaction",
+        description: "Press a keyboard key or chord",
+        usage: "press <key> [selector]",
+        args: &[
+            arg(
+                "key",
+                "Key name (Enter, Tab, ArrowDown, ...) or chord (Control+Shift+P)",
+            ),
+            opt_arg("selector", "Element to focus before pressing"),
+        ],
+        flags: &[TIMEOUT],
+        response: &[("pressed", "string")],
+        examples: &[
+            "agentbrowser-pro press Enter",
+            "agentbrowser-pro press Tab @e2",
+            "agentbrowser-pro press Control+Shift+P",
+        ],
+        related: &["type", "focus", "keydown"],
+    },
+    CommandSpec {
+        name: "keydown",
+        aliases: &[],
+        action: "keyDown",
+        category: "Interaction",
+        description: "Hold a key down, e.g. a modifier for the clicks that follow",
+        usage: "keydown <key>",
+        args: &[arg("key", "Key name (Shift, Control, Alt, Meta, a, ...)")],
+        flags: &[TIMEOUT],
+        response: &[("held", "string"), ("holding", "string[]")],
+        examples: &[
+            "agentbrowser-pro keydown Shift",
+            "agentbrowser-pro keydown Control && agentbrowser-pro click @e3 && agentbrowser-pro keyup Control",
+        ],
+        related: &["keyup", "press", "click"],
+    },
+    CommandSpec {
+        name: "keyup",
+        aliases: &[],
+        action: "keyUp",
+        category: "Interaction",
+        description: "Release a key held with keydown",
+        usage: "keyup <key>",
+        args: &[arg("key", "Key name")],
+        flags: &[TIMEOUT],
+        response: &[("released", "string"), ("holding", "string[]")],
+        examples: &["agentbrowser-pro keyup Shift"],
+        related: &["keydown", "press"],
+    },
+    CommandSpec {
+        name: "scroll",
+        aliases: &[],
+        action: "scroll",
+        category: "Interaction",
+        description: "Scroll the page or element",
+        usage: "scroll [selector]",
+        args: &[opt_arg("selector", "Element to scroll into view")],
+        flags: &[TIMEOUT],
+        response: &[("scrolled", "boolean")],
+        examples: &["agentbrowser-pro scroll", "agentbrowser-pro scroll @e12"],
+        related: &["screenshot", "snapshot", "scrollpos", "scrollto"],
+    },
+    CommandSpec {
+        name: "scrollpos",
+        aliases: &[],
+        action: "getScrollPosition",
+        category: "Interaction",
+        description: "Get the scroll position and how far the page or element can scroll",
+        usage: "scrollpos [selector]",
+        args: &[opt_arg("selector", "Scroll container (default: the page)")],
+        flags: &[],
+        response: SCROLL_POSITION,
+        examples: &[
+            "agentbrowser-pro scrollpos",
+            "agentbrowser-pro --json scrollpos \".feed\"",
+        ],
+        related: &["scrollto", "scroll"],
+    },
+    CommandSpec {
+        name: "scrollto",
+        aliases: &[],
+        action: "scrollTo",
+        category: "Interaction",
+        description: "Scroll the page or element to an exact position",
+        usage: "scrollto <x> <y> [selector]",
+        args: &[
+            arg("x", "Horizontal offset in pixels"),
+            arg("y", "Vertical offset in pixels (past the end scrolls to the bottom)"),
+            opt_arg("selector", "Scroll container (default: the page)"),
+        ],
+        flags: &[],
+        response: SCROLL_POSITION,
+        examples: &[
+            "agentbrowser-pro scrollto 0 1200",
+            "agentbrowser-pro scrollto 0 999999 \".feed\"",
+        ],
+        related: &["scrollpos", "scroll"],
+    },
+    CommandSpec {
+        name: "mediael",
+        aliases: &[],
+        action: "mediaElement",
+        category: "Interaction",
+        description: "Play, pause, seek or mute a <video>/<audio> element and report its playback state",
+        usage: "mediael <selector> <play|pause|seek <s>|mute|unmute|state>",
+        args: &[
+            SELECTOR,
+            arg("action", "play, pause, seek, mute, unmute or state"),
+            opt_arg("seconds", "seek: position to seek to"),
+        ],
+        flags: &[TIMEOUT],
+        response: &[
+            ("tag", "video|audio"),
+            ("src", "string|null"),
+            ("paused", "boolean"),
+            ("ended", "boolean"),
+            ("muted", "boolean"),
+            ("volume", "number"),
+            ("playbackRate", "number"),
+            ("currentTime", "number"),
+            ("duration", "number|null"),
+            ("readyState", "number"),
+            ("readyStateName", "string"),
+            ("buffered", "[[start, end]]"),
+            ("seekable", "[[start, end]]"),
+            ("error", "string|null"),
+        ],
+        examples: &[
+            "agentbrowser-pro mediael video play",
+            "agentbrowser-pro mediael @e4 seek 42.5",
+            "agentbrowser-pro --json mediael \"#player video\" state",
+        ],
+        related: &["eval", "wait"],
+    },
+    CommandSpec {
+        name: "slider",
+        aliases: &[],
+        action: "setSlider",
+        category: "Interaction",
+        description: "Drag a range input or ARIA slider to a value, then fine-tune it with the arrow keys",
+        usage: "slider <selector> <value|percent%>",
+        args: &[
+            SELECTOR,
+            arg("value", "Target value, or a percentage of the range such as 75%"),
+        ],
+        flags: &[TIMEOUT],
+        response: &[
+            ("value", "number"),
+            ("target", "number"),
+            ("min", "number"),
+            ("max", "number"),
+            ("keys", "number"),
+        ],
+        examples: &[
+            "agentbrowser-pro slider \"input[type=range]\" 40",
+            "agentbrowser-pro slider @e7 75%",
+        ],
+        related: &["reorder", "fill", "press"],
+    },
+    CommandSpec {
+        name: "reorder",
+        aliases: &[],
+        action: "reorder",
+        category: "Interaction",
+        description: "Drag an item of a sortable list to a new position",
+        usage: "reorder <item-selector> --to-index=<n>",
+        args: &[arg("item-selector", "List item to move")],
+        flags: &[
+            value_flag("--to-index", "n", "Zero-based position to move the item to"),
+            TIMEOUT,
+        ],
+        response: &[
+            ("from", "number"),
+            ("to", "number"),
+            ("index", "number"),
+            ("count", "number"),
+            ("moved", "boolean"),
+        ],
+        examples: &[
+            "agentbrowser-pro reorder \"#todo li:nth-child(4)\" --to-index=0",
+            "agentbrowser-pro reorder @e15 --to-index=2",
+        ],
+        related: &["slider", "snapshot"],
+    },
+    CommandSpec {
+        name: "drag",
+        aliases: &[],
+        action: "drag",
+        category: "Interaction",
+        description: "Drag an element onto another, or to a point on the page",
+        usage: "drag <source> <target> | drag <source> --to-x=<px> --to-y=<px>",
+        args: &[
+            arg("source", "Element to pick up"),
+            opt_arg("target", "Element to drop it on"),
+        ],
+        flags: &[
+            value_flag("--to-x", "px", "Drop at this x page coordinate instead of on a target"),
+            value_flag("--to-y", "px", "Drop at this y page coordinate instead of on a target"),
+            TIMEOUT,
+        ],
+        response: &[("dragged", "{from, to: string | {x, y}}")],
+        examples: &[
+            "agentbrowser-pro drag \"#card-42\" \"#column-done\"",
+            "agentbrowser-pro drag @e8 @e15",
+            "agentbrowser-pro drag .handle --to-x=640 --to-y=200",
+        ],
+        related: &["reorder", "slider", "hover"],
+    },
+    CommandSpec {
+        name: "dialog",
+        aliases: &[],
+        action: "handleDialog",
+        category: "Interaction",
+        description: "Choose how alerts, confirms and prompts are answered (dismissed by default)",
+        usage: "dialog accept [text] | dialog dismiss | dialog auto <accept|dismiss>",
+        args: &[
+            arg("accept|dismiss|auto", "Answer the next dialog, or set how all are answered"),
+            opt_arg("text|mode", "Text to answer a prompt with, or the mode for auto"),
+        ],
+        flags: &[],
+        response: &[
+            ("armed", "string? (accept or dismiss)"),
+            ("promptText", "string?"),
+            ("auto", "string? (accept or dismiss)"),
+        ],
+        examples: &[
+            "agentbrowser-pro dialog accept",
+            "agentbrowser-pro dialog accept \"Jane Doe\"",
+            "agentbrowser-pro dialog auto accept",
+        ],
+        related: &["click", "pause"],
+    },
+    // ============ Information ============
+    CommandSpec {
+        name: "snapshot",
+        aliases: &[],
+        action: "snapshot",
+        category: "Information",
+        description: "Get accessibility tree with element refs",
+        usage: "snapshot [selector] [--active-modal]",
+        args: &[opt_arg("selector", "Optional selector to scope snapshot")],
+        flags: &[
+            flag(
+                "--active-modal",
+                "Only the top-most open dialog or modal; its refs resolve inside it",
+            ),
+            value_flag(
+                "--output",
+                "text|json|xml",
+                "Return the tree as text (default) or structured nodes",
+            ),
+            value_flag(
+                "--max-tokens",
+                "n",
+                "Trim the tree to about n tokens, keeping interactive elements first",
+            ),
+            CACHE,
+            TIMEOUT,
+        ],
+        response: &[
+            ("tree", "string"),
+            ("refs", "object"),
+            ("url", "string"),
+            ("title", "string"),
+            ("modal", "{tag, role, name}?"),
+        ],
+        examples: &[
+            "agentbrowser-pro snapshot",
+            "agentbrowser-pro snapshot \"#form\"",
+            "agentbrowser-pro snapshot --active-modal",
+            "agentbrowser-pro snapshot --json",
+            "agentbrowser-pro snapshot --output=json",
+            "agentbrowser-pro snapshot --max-tokens=2000",
+            "agentbrowser-pro snapshot --cache=2s",
+        ],
+        related: &["click", "fill", "screenshot"],
+    },
+    CommandSpec {
+        name: "screenshot",
+        aliases: &[],
+        action: "screenshot",
+        category: "Information",
+        description: "Take a screenshot",
+        usage: "screenshot [path]",
+        args: &[opt_arg(
+            "path",
+            "File to write (base64 returned if omitted)",
+        )],
+        flags: &[
+            flag("--full-page", "Capture the full scrollable page"),
+            flag(
+                "--emulate-print",
+                "Render with print stylesheets (@media print) for a print preview",
+            ),
+            flag(
+                "--tiled",
+                "Scroll one viewport at a time and stitch the captures, showing sticky headers once",
+            ),
+            value_flag("--max-tiles", "n", "--tiled: stop after n viewports (default: 40)"),
+            TIMEOUT,
+        ],
+        response: &[
+            ("path", "string"),
+            ("data", "base64"),
+            ("width", "number?"),
+            ("height", "number?"),
+            ("tiles", "number?"),
+        ],
+        examples: &[
+            "agentbrowser-pro screenshot",
+            "agentbrowser-pro screenshot --full-page output.png",
+            "agentbrowser-pro screenshot --emulate-print --full-page print-preview.png",
+            "agentbrowser-pro screenshot --tiled --max-tiles=10 long-page.png",
+        ],
+        related: &["snapshot", "pdf", "canvas"],
+    },
+    CommandSpec {
+        name: "canvas",
+        aliases: &[],
+        action: "captureCanvas",
+        category: "Information",
+        description: "Save a <canvas> element's current bitmap (charts, games, WebGL) as a PNG",
+        usage: "canvas capture <selector> [--output=<file>]",
+        args: &[
+            arg("subcommand", "capture"),
+            arg("selector", "The canvas, or an element containing one"),
+        ],
+        flags: &[
+            value_flag(
+                "--output",
+                "file",
+                "PNG file to write (base64 returned if omitted)",
+            ),
+            TIMEOUT,
+        ],
+        response: &[
+            ("path", "string?"),
+            ("bytes", "number?"),
+            ("data", "base64?"),
+            ("width", "number"),
+            ("height", "number"),
+            ("blank", "boolean"),
+        ],
+        examples: &[
+            "agentbrowser-pro canvas capture \"#chart canvas\" --output=chart.png",
+            "agentbrowser-pro --json canvas capture @e7",
+        ],
+        related: &["screenshot"],
+    },
+    CommandSpec {
+        name: "title",
+        aliases: &["gettitle"],
+        action: "getTitle",
+        category: "Information",
+        description: "Get page title",
+        usage: "title",
+        args: &[],
+        flags: &[CACHE, TIMEOUT],
+        response: &[("title", "string")],
+        examples: &["agentbrowser-pro title"],
+        related: &["url", "snapshot"],
+    },
+    CommandSpec {
+        name: "url",
+        aliases: &["geturl"],
+        action: "getUrl",
+        category: "Information",
+        description: "Get current URL",
+        usage: "url",
+        args: &[],
+        flags: &[CACHE, TIMEOUT],
+        response: &[("url", "string")],
+        examples: &["agentbrowser-pro url"],
+        related: &["title", "navigate"],
+    },
+    CommandSpec {
+        name: "focused",
+        aliases: &["activeelement"],
+        action: "getFocused",
+        category: "Information",
+        description: "Show the focused element's role, name, selector and ref",
+        usage: "focused",
+        args: &[],
+        flags: &[],
+        response: &[
+            ("role", "string?"),
+            ("name", "string?"),
+            ("tag", "string?"),
+            ("selector", "string?"),
+            ("ref", "string? (from the last snapshot)"),
+        ],
+        examples: &[
+            "agentbrowser-pro focused",
+            "agentbrowser-pro press Tab && agentbrowser-pro focused",
+        ],
+        related: &["tabto", "focus", "snapshot"],
+    },
+    CommandSpec {
+        name: "overlays",
+        aliases: &[],
+        action: "getOverlays",
+        category: "Information",
+        description: "List fixed and sticky elements covering the viewport (cookie banners, chat widgets, modals)",
+        usage: "overlays",
+        args: &[],
+        flags: &[],
+        response: &[
+            ("viewport", "{width, height}"),
+            (
+                "overlays",
+                "{kind, tag, selector?, position, zIndex?, coverage, rect, text, refs}[]",
+            ),
+        ],
+        examples: &["agentbrowser-pro snapshot && agentbrowser-pro overlays"],
+        related: &["snapshot", "click"],
+    },
+    CommandSpec {
+        name: "structured",
+        aliases: &["structureddata"],
+        action: "getStructuredData",
+        category: "Information",
+        description: "Extract JSON-LD, OpenGraph and microdata from the page",
+        usage: "structured [--type=Product]",
+        args: &[],
+        flags: &[
+            value_flag(
+                "--type",
+                "type",
+                "Only JSON-LD and microdata items of this schema.org type",
+            ),
+            TIMEOUT,
+        ],
+        response: &[
+            ("url", "string"),
+            ("jsonLd", "object[]"),
+            ("openGraph", "object"),
+            ("twitter", "object"),
+            ("microdata", "object[]"),
+            ("errors", "string[]"),
+            ("counts", "{jsonLd, openGraph, microdata}"),
+        ],
+        examples: &[
+            "agentbrowser-pro structured",
+            "agentbrowser-pro --json structured --type=Product",
+        ],
+        related: &["text", "html", "evaluate"],
+    },
+    CommandSpec {
+        name: "seo",
+        aliases: &[],
+        action: "seoAudit",
+        category: "Information",
+        description: "Audit the page's meta/SEO basics with a pass/warn/fail verdict; exits 1 on fail",
+        usage: "seo",
+        args: &[],
+        flags: &[TIMEOUT],
+        response: &[
+            ("status", "pass|warn|fail"),
+            ("summary", "{pass, warn, fail}"),
+            ("checks", "[{check, status, message}]"),
+            ("title", "string"),
+            ("description", "string|null"),
+            ("canonical", "string[]"),
+            ("robots", "string|null"),
+            ("h1", "string[]"),
+            ("imageAltCoverage", "number"),
+            ("brokenAnchors", "string[]"),
+        ],
+        examples: &[
+            "agentbrowser-pro seo",
+            "agentbrowser-pro --json seo | jq '.result.checks[] | select(.status != \"pass\")'",
+        ],
+        related: &["structured", "title", "pwa"],
+    },
+    CommandSpec {
+        name: "pwa",
+        aliases: &[],
+        action: "pwaAudit",
+        category: "Information",
+        description: "Audit the web app manifest, installability, service worker and offline readiness; exits 1 on fail",
+        usage: "pwa",
+        args: &[],
+        flags: &[TIMEOUT],
+        response: &[
+            ("status", "pass|warn|fail"),
+            ("installable", "boolean"),
+            ("summary", "{pass, warn, fail}"),
+            ("checks", "[{check, status, message}]"),
+            ("manifestUrl", "string|null"),
+            ("manifest", "object|null"),
+            ("startUrl", "string"),
+            (
+                "serviceWorker",
+                "{supported, registered, scope, scriptUrl, state, controlling}",
+            ),
+            ("offline", "{caches, startUrlCached}"),
+        ],
+        examples: &[
+            "agentbrowser-pro pwa",
+            "agentbrowser-pro --json pwa | jq '.result.manifest.icons'",
+        ],
+        related: &["seo", "favicon"],
+    },
+    CommandSpec {
+        name: "feeds",
+        aliases: &[],
+        action: "getFeeds",
+        category: "Information",
+        description: "List RSS, Atom and JSON feeds the page links to",
+        usage: "feeds",
+        args: &[],
+        flags: &[TIMEOUT],
+        response: &[("url", "string"), ("feeds", "[{url, title, format, source}]")],
+        examples: &["agentbrowser-pro feeds"],
+        related: &["feed", "structured"],
+    },
+    CommandSpec {
+        name: "pageinfo",
+        aliases: &[],
+        action: "getPageInfo",
+        category: "Information",
+        description: "Status, headers, size and protocol of the response that loaded the page",
+        usage: "pageinfo",
+        args: &[],
+        flags: &[TIMEOUT],
+        response: &[
+            ("url", "string"),
+            ("status", "number"),
+            ("statusText", "string"),
+            ("contentType", "string?"),
+            ("protocol", "string? (http/1.1, h2, h3)"),
+            ("transferSize", "number? (bytes over the network, headers included)"),
+            ("encodedBodySize", "number?"),
+            ("decodedBodySize", "number?"),
+            ("fromServiceWorker", "boolean"),
+            ("remoteAddress", "string?"),
+            ("tls", "{protocol, issuer, subject, validTo}?"),
+            ("redirects", "[{url, status}]"),
+            ("headers", "object"),
+        ],
+        examples: &[
+            "agentbrowser-pro pageinfo",
+            "agentbrowser-pro --json pageinfo | jq .headers",
+        ],
+        related: &["navigate", "network"],
+    },
+    CommandSpec {
+        name: "feed",
+        aliases: &[],
+        action: "fetchFeed",
+        category: "Information",
+        description: "Fetch an RSS, Atom or JSON feed and return its entries",
+        usage: "feed fetch <url> [--limit=n]",
+        args: &[
+            arg("subcommand", "fetch"),
+            arg("url", "Feed URL, e.g. from 'feeds'"),
+        ],
+        flags: &[OFFSET, LIMIT, TIMEOUT],
+        response: &[
+            ("url", "string"),
+            ("format", "rss|atom|json"),
+            ("title", "string"),
+            ("entries", "[{title, link, id, published, author, summary}]"),
+        ],
+        examples: &[
+            "agentbrowser-pro feed fetch https://example.com/feed.xml --limit=10",
+            "agentbrowser-pro --json feed fetch https://example.com/atom.xml",
+        ],
+        related: &["feeds"],
+    },
+    CommandSpec {
+        name: "favicon",
+        aliases: &[],
+        action: "getFavicon",
+        category: "Information",
+        description: "Download the page's best icon (link icons, apple-touch-icon, manifest icons)",
+        usage: "favicon [--output=<file>]",
+        args: &[],
+        flags: &[
+            value_flag(
+                "--output",
+                "file",
+                "Write the icon to a file (base64 returned if omitted)",
+            ),
+            TIMEOUT,
+        ],
+        response: &[
+            ("url", "string"),
+            ("source", "icon|apple-touch-icon|manifest|favicon.ico"),
+            ("sizes", "string?"),
+            ("size", "number"),
+            ("type", "string?"),
+            ("bytes", "number"),
+            ("path", "string?"),
+            ("data", "base64?"),
+            ("candidates", "[{url, source, size}]"),
+        ],
+        examples: &[
+            "agentbrowser-pro favicon --output=icon.png",
+            "agentbrowser-pro --json favicon",
+        ],
+        related: &["pageinfo", "screenshot"],
+    },
+    CommandSpec {
+        name: "text",
+        aliases: &["gettext"],
+        action: "getText",
+        category: "Information",
+        description: "Get element text",
+        usage: "text <selector|ref> [--structured]",
+        args: &[SELECTOR],
+        flags: &[
+            flag(
+                "--structured",
+                "Outline headings, paragraphs, lists, code, quotes and tables with their links",
+            ),
+            OFFSET,
+            LIMIT,
+            MAX_BYTES,
+            TIMEOUT,
+        ],
+        response: &[("text", "string"), ("outline", "array?")],
+        examples: &[
+            "agentbrowser-pro text @e3",
+            "agentbrowser-pro text h1",
+            "agentbrowser-pro text main --structured",
+        ],
+        related: &["html", "value", "tooltip"],
+    },
+    CommandSpec {
+        name: "textdiff",
+        aliases: &[],
+        action: "textDiff",
+        category: "Information",
+        description: "Diff an element's text word by word against its last reading or a file",
+        usage: "textdiff <selector|ref> [--against=last|<file>] [--by=word|char] [--update]",
+        args: &[SELECTOR],
+        flags: &[
+            value_flag(
+                "--against",
+                "last|file",
+                "Baseline: the text seen last time (default) or a text file",
+            ),
+            value_flag(
+                "--by",
+                "word|char",
+                "Compare words (default) or characters",
+            ),
+            flag(
+                "--update",
+                "Rewrite the --against file with the new text",
+            ),
+        ],
+        response: &[
+            ("selector", "string"),
+            ("url", "string"),
+            ("changed", "boolean"),
+            ("baseline", "boolean? (first reading saved)"),
+            ("added", "number"),
+            ("removed", "number"),
+            (
+                "changes",
+                "{before,removed,added,after,removedCount,addedCount}[]",
+            ),
+        ],
+        examples: &[
+            "agentbrowser-pro textdiff '#pricing'",
+            "agentbrowser-pro textdiff main --against=tos.txt --update",
+            "agentbrowser-pro textdiff .price --by=char",
+        ],
+        related: &["pagediff", "monitor", "text"],
+    },
+    CommandSpec {
+        name: "detectlang",
+        aliases: &["detectlanguage"],
+        action: "detectLanguage",
+        category: "Information",
+        description: "Detect the language of the page or an element from its text",
+        usage: "detectlang [selector|ref]",
+        args: &[opt_arg(
+            "selector",
+            "Element to read (whole page if omitted)",
+        )],
+        flags: &[],
+        response: &[
+            ("language", "string? (ISO 639-1)"),
+            ("confidence", "number? (0-1)"),
+            ("declared", "string? (<html lang>)"),
+            ("chars", "number"),
+        ],
+        examples: &[
+            "agentbrowser-pro detectlang",
+            "agentbrowser-pro detectlang article",
+        ],
+        related: &["translate", "text"],
+    },
+    CommandSpec {
+        name: "translate",
+        aliases: &[],
+        action: "translate",
+        category: "Information",
+        description: "Translate the text of the page or an element through the configured hook",
+        usage: "translate [selector|ref] --to=<lang> [--from=<lang>] [--with=<command|url>]",
+        args: &[opt_arg(
+            "selector",
+            "Element to read (whole page if omitted)",
+        )],
+        flags: &[
+            value_flag("--to", "lang", "Language to translate into"),
+            value_flag(
+                "--from",
+                "lang",
+                "Language of the text (default: detected)",
+            ),
+            value_flag(
+                "--with",
+                "command|url",
+                "Translate hook to use instead of config.toml's translate",
+            ),
+        ],
+        response: &[
+            ("from", "string"),
+            ("to", "string"),
+            ("text", "string"),
+            ("translation", "string"),
+        ],
+        examples: &[
+            "agentbrowser-pro translate main --to=en",
+            "agentbrowser-pro translate --to=de --with='trans -b :de'",
+            "agentbrowser-pro translate .review --to=en --with=http://localhost:5000/translate",
+        ],
+        related: &["detectlang", "text"],
+    },
+    CommandSpec {
+        name: "tooltip",
+        aliases: &[],
+        action: "getTooltip",
+        category: "Information",
+        description: "Hover an element and read the tooltip or popover it shows (aria-describedby, role=tooltip, or its title)",
+        usage: "tooltip <selector|ref>",
+        args: &[SELECTOR],
+        flags: &[value_flag(
+            "--timeout",
+            "ms",
+            "How long to wait for the tooltip to appear (default: 3000)",
+        )],
+        response: &[
+            ("text", "string"),
+            ("source", "describedby|tooltip|popover|title"),
+        ],
+        examples: &[
+            "agentbrowser-pro tooltip @e12",
+            "agentbrowser-pro --json tooltip \"[data-testid=price-info]\"",
+        ],
+        related: &["hover", "text"],
+    },
+    CommandSpec {
+        name: "html",
+        aliases: &["gethtml"],
+        action: "getHtml",
+        category: "Information",
+        description: "Get page or element HTML",
+        usage: "html [selector] [--sanitized]",
+        args: &[opt_arg(
+            "selector",
+            "Element to read (whole page if omitted)",
+        )],
+        flags: &[
+            flag(
+                "--sanitized",
+                "Strip scripts, styles, comments, event handlers and data:/javascript: URLs",
+            ),
+            OFFSET,
+            LIMIT,
+            MAX_BYTES,
+            TIMEOUT,
+        ],
+        response: &[
+            ("html", "string"),
+            ("total", "number?"),
+            ("truncated", "boolean?"),
+            ("nextOffset", "number?"),
+        ],
+        examples: &[
+            "agentbrowser-pro html",
+            "agentbrowser-pro html main",
+            "agentbrowser-pro html --sanitized",
+            "agentbrowser-pro html --max-bytes=100000 --offset=100000",
+        ],
+        related: &["text", "texts", "snapshot"],
+    },
+    CommandSpec {
+        name: "texts",
+        aliases: &["alltexts"],
+        action: "evaluate",
+        category: "Information",
+        description: "Get the text of every element matching a selector",
+        usage: "texts <css-selector>",
+        args: &[arg("css-selector", "CSS selector (refs are not supported)")],
+        flags: &[OFFSET, LIMIT, MAX_BYTES, TIMEOUT],
+        response: &[
+            ("result", "string[]"),
+            ("total", "number?"),
+            ("truncated", "boolean?"),
+            ("nextOffset", "number?"),
+        ],
+        examples: &[
+            "agentbrowser-pro texts \"li.result\"",
+            "agentbrowser-pro texts \"table td\" --limit=50 --offset=50",
+        ],
+        related: &["text", "count"],
+    },
+    CommandSpec {
+        name: "value",
+        aliases: &["getvalue"],
+        action: "getValue",
+        category: "Information",
+        description: "Get input value",
+        usage: "value <selector|ref>",
+        args: &[SELECTOR],
+        flags: &[TIMEOUT],
+        response: &[("value", "string")],
+        examples: &["agentbrowser-pro value @e2"],
+        related: &["text", "fill"],
+    },
+    CommandSpec {
+        name: "count",
+        aliases: &["getcount"],
+        action: "getCount",
+        category: "Information",
+        description: "Count matching elements",
+        usage: "count <selector>",
+        args: &[arg("selector", "CSS selector")],
+        flags: &[CACHE, TIMEOUT],
+        response: &[("count", "number")],
+        examples: &["agentbrowser-pro count \"li.result\""],
+        related: &["visible", "text"],
+    },
+    // ============ State Checks ============
+    CommandSpec {
+        name: "visible",
+        aliases: &["isvisible"],
+        action: "isVisible",
+        category: "State",
+        description: "Check if element is visible",
+        usage: "visible <selector|ref>",
+        args: &[SELECTOR],
+        flags: &[TIMEOUT],
+        response: &[("visible", "boolean")],
+        examples: &["agentbrowser-pro visible @e4"],
+        related: &["enabled", "checked", "wait", "inview"],
+    },
+    CommandSpec {
+        name: "inview",
+        aliases: &["isinviewport"],
+        action: "isInViewport",
+        category: "State",
+        description: "Check how much of an element is inside the viewport and whether something covers it",
+        usage: "inview <selector|ref>",
+        args: &[SELECTOR],
+        flags: &[TIMEOUT],
+        response: &[
+            ("inView", "boolean"),
+            ("ratio", "number (0-1)"),
+            ("fullyInView", "boolean"),
+            ("obscuredBy", "string? (element at the center of the visible part)"),
+            ("rect", "{x,y,width,height}"),
+        ],
+        examples: &[
+            "agentbrowser-pro inview @e12",
+            "agentbrowser-pro --json inview \"#load-more\"",
+        ],
+        related: &["visible", "scrollpos", "overlays"],
+    },
+    CommandSpec {
+        name: "enabled",
+        aliases: &["isenabled"],
+        action: "isEnabled",
+        category: "State",
+        description: "Check if element is enabled",
+        usage: "enabled <selector|ref>",
+        args: &[SELECTOR],
+        flags: &[TIMEOUT],
+        response: &[("enabled", "boolean")],
+        examples: &["agentbrowser-pro enabled @e4"],
+        related: &["visible", "checked"],
+    },
+    CommandSpec {
+        name: "checked",
+        aliases: &["ischecked"],
+        action: "isChecked",
+        category: "State",
+        description: "Check if checkbox is checked",
+        usage: "checked <selector|ref>",
+        args: &[SELECTOR],
+        flags: &[TIMEOUT],
+        response: &[("checked", "boolean")],
+        examples: &["agentbrowser-pro checked @e5"],
+        related: &["check", "uncheck", "visible"],
+    },
+    // ============ Wait ============
+    CommandSpec {
+        name: "wait",
+        aliases: &[],
+        action: "wait",
+        category: "Wait",
+        description: "Wait for milliseconds or for a selector to appear",
+        usage: "wait [ms|selector]",
+        args: &[opt_arg(
+            "target",
+            "Milliseconds to sleep, or selector to wait for",
+        )],
+        flags: &[TIMEOUT],
+        response: &[("waited", "number"), ("found", "string")],
+        examples: &[
+            "agentbrowser-pro wait 500",
+            "agentbrowser-pro wait \"#results\"",
+        ],
+        related: &["visible", "snapshot"],
+    },
+    CommandSpec {
+        name: "waitfor",
+        aliases: &[],
+        action: "waitForResponse",
+        category: "Wait",
+        description: "Wait for a network response whose URL matches a regex",
+        usage: "waitfor response <url-pattern> [--status=200]",
+        args: &[
+            arg("subcommand", "response"),
+            arg(
+                "url-pattern",
+                "Regex matched against the response URL; responses since the previous \
+                 command count",
+            ),
+        ],
+        flags: &[
+            value_flag("--status", "code", "Only a response with this status"),
+            TIMEOUT,
+        ],
+        response: &[
+            ("url", "string"),
+            ("method", "string"),
+            ("status", "number"),
+            ("contentType", "string?"),
+            ("bytes", "number"),
+            ("body", "string (first 2000 characters)"),
+            ("truncated", "boolean"),
+        ],
+        examples: &[
+            "agentbrowser-pro click @e4 && agentbrowser-pro waitfor response /api/save --status=200",
+            "agentbrowser-pro waitfor response 'graphql' --timeout=10000",
+        ],
+        related: &["wait", "network"],
+    },
+    CommandSpec {
+        name: "pause",
+        aliases: &[],
+        action: "pause",
+        category: "Wait",
+        description: "Wait for a person to continue, from the browser or the terminal",
+        usage: "pause [--message=<text>] [--timeout=<ms>]",
+        args: &[],
+        flags: &[value_flag(
+            "--message",
+            "text",
+            "What the person should do, shown in the banner and terminal",
+        )],
+        response: &[
+            ("resumed", "boolean"),
+            ("by", "string (page, terminal or command)"),
+            ("waited", "number"),
+        ],
+        examples: &[
+            "agentbrowser-pro --headed pause --message=\"Solve the CAPTCHA, then click Continue\"",
+            "agentbrowser-pro pause --message=\"Approve the payment\" --timeout=300000",
+        ],
+        related: &["resume", "handoff", "wait"],
+    },
+    CommandSpec {
+        name: "resume",
+        aliases: &[],
+        action: "resume",
+        category: "Wait",
+        description: "Continue a session waiting in pause",
+        usage: "resume",
+        args: &[],
+        flags: &[],
+        response: &[("resumed", "boolean")],
+        examples: &["agentbrowser-pro resume"],
+        related: &["pause"],
+    },
+    CommandSpec {
+        name: "errors",
+        aliases: &[],
+        action: "getErrors",
+        category: "JavaScript",
+        description: "List uncaught exceptions and unhandled rejections from the page",
+        usage: "errors [--clear] [--follow]",
+        args: &[],
+        flags: &[
+            flag("--clear", "Clear the error log after listing"),
+            flag("--follow", "Keep printing new errors until interrupted"),
+            TIMEOUT,
+        ],
+        response: &[("errors", "[{message, stack?, url, timestamp}]")],
+        examples: &[
+            "agentbrowser-pro errors",
+            "agentbrowser-pro errors --follow",
+            "agentbrowser-pro --fail-on-page-error click @e3",
+        ],
+        related: &["eval", "network"],
+    },
+    CommandSpec {
+        name: "messages",
+        aliases: &[],
+        action: "tapMessages",
+        category: "JavaScript",
+        description: "Record window postMessage and BroadcastChannel traffic in every frame",
+        usage: "messages tap [--channel=<name>] [--follow]",
+        args: &[arg("subcommand", "tap")],
+        flags: &[
+            value_flag("--channel", "name", "Only BroadcastChannel posts on this channel"),
+            flag("--follow", "Keep printing new messages until interrupted"),
+            TIMEOUT,
+        ],
+        response: &[
+            ("tapping", "boolean"),
+            (
+                "messages",
+                "[{kind, channel, origin, frameUrl, data, timestamp}]",
+            ),
+        ],
+        examples: &[
+            "agentbrowser-pro messages tap",
+            "agentbrowser-pro messages tap --follow",
+            "agentbrowser-pro messages tap --channel=auth --json",
+        ],
+        related: &["frames", "errors", "network"],
+    },
+    // ============ Frames ============
+    CommandSpec {
+        name: "frames",
+        aliases: &["getframes"],
+        action: "getFrames",
+        category: "Frames",
+        description: "List all frames",
+        usage: "frames",
+        args: &[],
+        flags: &[TIMEOUT],
+        response: &[("frames", "array")],
+        examples: &["agentbrowser-pro frames"],
+        related: &["frame", "mainframe"],
+    },
+    CommandSpec {
+        name: "frame",
+        aliases: &["switchtoframe"],
+        action: "switchToFrame",
+        category: "Frames",
+        description: "Switch to a frame",
+        usage: "frame <selector|name|url>",
+        args: &[arg("frame", "Frame selector, name or URL")],
+        flags: &[TIMEOUT],
+        response: &[("switched", "boolean")],
+        examples: &["agentbrowser-pro frame \"#checkout-iframe\""],
+        related: &["frames", "mainframe"],
+    },
+    CommandSpec {
+        name: "mainframe",
+        aliases: &[],
+        action: "switchToMainFrame",
+        category: "Frames",
+        description: "Switch to main frame",
+        usage: "mainframe",
+        args: &[],
+        flags: &[TIMEOUT],
+        response: &[("switched", "boolean")],
+        examples: &["agentbrowser-pro mainframe"],
+        related: &["frame", "frames"],
+    },
+    // ============ Pages ============
+    CommandSpec {
+        name: "pages",
+        aliases: &["getpages"],
+        action: "getPages",
+        category: "Pages",
+        description: "List all pages/tabs",
+        usage: "pages",
+        args: &[],
+        flags: &[TIMEOUT],
+        response: &[("pages", "array")],
+        examples: &["agentbrowser-pro pages"],
+        related: &["newpage", "switchpage", "closepage"],
+    },
+    CommandSpec {
+        name: "newpage",
+        aliases: &[],
+        action: "newPage",
+        category: "Pages",
+        description: "Open a new page",
+        usage: "newpage [url]",
+        args: &[opt_arg("url", "URL to open in the new page")],
+        flags: &[TIMEOUT],
+        response: &[("created", "boolean"), ("url", "string")],
+        examples: &["agentbrowser-pro newpage https://example.com"],
+        related: &["pages", "switchpage"],
+    },
+    CommandSpec {
+        name: "switchpage",
+        aliases: &[],
+        action: "switchPage",
+        category: "Pages",
+        description: "Switch to a page",
+        usage: "switchpage <index|url|title>",
+        args: &[arg("page", "Page index, URL or title")],
+        flags: &[TIMEOUT],
+        response: &[("switched", "boolean")],
+        examples: &["agentbrowser-pro switchpage 1"],
+        related: &["pages", "newpage"],
+    },
+    CommandSpec {
+        name: "closepage",
+        aliases: &[],
+        action: "closePage",
+        category: "Pages",
+        description: "Close current page",
+        usage: "closepage",
+        args: &[],
+        flags: &[TIMEOUT],
+        response: &[("closed", "boolean")],
+        examples: &["agentbrowser-pro closepage"],
+        related: &["pages", "newpage"],
+    },
+    // ============ JavaScript ============
+    CommandSpec {
+        name: "eval",
+        aliases: &["evaluate"],
+        action: "evaluate",
+        category: "JavaScript",
+        description: "Execute JavaScript",
+        usage: "eval <script>",
+        args: &[rest_arg("script", "JavaScript expression to evaluate")],
+        flags: &[TIMEOUT],
+        response: &[("result", "any")],
+        examples: &["agentbrowser-pro eval \"document.title\""],
+        related: &["html", "text"],
+    },
+    // ============ Extensions ============
+    CommandSpec {
+        name: "extensions",
+        aliases: &[],
+        action: "listExtensions",
+        category: "Extensions",
+        description: "List extensions loaded with --extensions",
+        usage: "extensions list",
+        args: &[opt_arg("subcommand", "list (default)")],
+        flags: &[TIMEOUT],
+        response: &[
+            ("extensions", "[{path, name, version}]"),
+            ("ids", "string[] (extensions with a running worker)"),
+        ],
+        examples: &[
+            "agentbrowser-pro --headed --extensions=./my-ext navigate https://example.com",
+            "agentbrowser-pro extensions list",
+        ],
+        related: &["extension", "navigate"],
+    },
+    CommandSpec {
+        name: "extension",
+        aliases: &[],
+        action: "extensionInstall",
+        category: "Extensions",
+        description: "Install or remove a session's extensions, or open an extension popup",
+        usage: "extension <install|remove|popup> <target>",
+        args: &[
+            arg("subcommand", "install, remove or popup"),
+            arg(
+                "target",
+                "install: unpacked directory or Web Store ID; remove: ID, directory or name; \
+                 popup: extension ID",
+            ),
+        ],
+        flags: &[TIMEOUT],
+        response: &[
+            ("installed", "string (install: extension directory)"),
+            ("removed", "string (remove: extension directory)"),
+            ("restarted", "boolean (the running browser was restarted)"),
+            ("url", "string (popup: the popup page URL)"),
+        ],
+        examples: &[
+            "agentbrowser-pro --headed extension install ./my-ext",
+            "agentbrowser-pro --headed extension install cjpalhdlnbpafiamejdnhcphjbkeiagm",
+            "agentbrowser-pro extension popup cjpalhdlnbpafiamejdnhcphjbkeiagm",
+            "agentbrowser-pro extension remove my-ext",
+        ],
+        related: &["extensions"],
+    },
+    // ============ Emulation ============
+    CommandSpec {
+        name: "emulate",
+        aliases: &[],
+        action: "emulateVision",
+        category: "Emulation",
+        description: "Simulate vision deficiencies, forced colors, orientation or motion sensors",
+        usage: "emulate <vision|forced-colors|orientation|accelerometer> <value>",
+        args: &[
+            arg(
+                "subcommand",
+                "vision, forced-colors, orientation or accelerometer",
+            ),
+            arg(
+                "value",
+                "vision: deuteranopia, protanopia, tritanopia, achromatopsia, blurred, \
+                 reduced-contrast or none; forced-colors: on or off; orientation: portrait, \
+                 landscape or alpha,beta,gamma degrees; accelerometer: x,y,z m/s² or off",
+            ),
+        ],
+        flags: &[TIMEOUT],
+        response: &[
+            ("vision", "string (vision: the applied deficiency)"),
+            ("emulated", "boolean (forced-colors)"),
+            ("orientation", "string | {alpha, beta, gamma} (orientation)"),
+            ("accelerometer", "{x, y, z} | null (accelerometer)"),
+        ],
+        examples: &[
+            "agentbrowser-pro emulate vision deuteranopia",
+            "agentbrowser-pro emulate forced-colors on",
+            "agentbrowser-pro emulate orientation landscape",
+            "agentbrowser-pro emulate orientation 0,90,0",
+            "agentbrowser-pro emulate accelerometer 0,9.8,0",
+        ],
+        related: &["screenshot", "zoom"],
+    },
+    CommandSpec {
+        name: "viewport",
+        aliases: &[],
+        action: "setViewport",
+        category: "Emulation",
+        description: "Resize the viewport; clears device emulation",
+        usage: "viewport <width> <height>",
+        args: &[
+            arg("width", "CSS pixels"),
+            arg("height", "CSS pixels"),
+        ],
+        flags: &[TIMEOUT],
+        response: &[("viewport", "{width, height}")],
+        examples: &[
+            "agentbrowser-pro viewport 1280 800",
+            "agentbrowser-pro viewport 375 667 && agentbrowser-pro screenshot narrow.png",
+        ],
+        related: &["device", "zoom", "screenshot"],
+    },
+    CommandSpec {
+        name: "device",
+        aliases: &[],
+        action: "emulateDevice",
+        category: "Emulation",
+        description: "Emulate a device preset: viewport, pixel ratio, user agent and touch",
+        usage: "device <name|reset> [--landscape]",
+        args: &[arg("name", "Preset from 'devices' (iPhone 14, Pixel 7, ...) or reset")],
+        flags: &[
+            flag("--landscape", "Swap width and height"),
+            TIMEOUT,
+        ],
+        response: &[
+            ("device", "string"),
+            ("viewport", "{width, height}"),
+            ("deviceScaleFactor", "number"),
+            ("isMobile", "boolean"),
+            ("hasTouch", "boolean"),
+            ("userAgent", "string"),
+        ],
+        examples: &[
+            "agentbrowser-pro device iPhone 14",
+            "agentbrowser-pro device \"Pixel 7\" --landscape",
+            "agentbrowser-pro device reset",
+        ],
+        related: &["devices", "viewport", "emulate"],
+    },
+    CommandSpec {
+        name: "devices",
+        aliases: &[],
+        action: "listDevices",
+        category: "Emulation",
+        description: "List the device presets 'device' can emulate",
+        usage: "devices",
+        args: &[],
+        flags: &[],
+        response: &[(
+            "devices",
+            "[{name, viewport, deviceScaleFactor, isMobile, hasTouch, userAgent}]",
+        )],
+        examples: &[
+            "agentbrowser-pro devices",
+            "agentbrowser-pro --json devices | jq -r '.result.devices[].name'",
+        ],
+        related: &["device"],
+    },
+    CommandSpec {
+        name: "zoom",
+        aliases: &[],
+        action: "setZoom",
+        category: "Emulation",
+        description: "Zoom the page like Ctrl+/Ctrl- (or scale it with CSS zoom)",
+        usage: "zoom <factor|percent|reset> [--css]",
+        args: &[arg(
+            "factor",
+            "0.25 to 5, a percentage such as 150%, or reset",
+        )],
+        flags: &[flag(
+            "--css",
+            "Use CSS zoom on the current document instead of browser zoom (works in every browser, lost on navigation)",
+        )],
+        response: &[
+            ("zoom", "number"),
+            ("mode", "browser | css"),
+            ("viewport", "{width, height} (CSS pixels)"),
+        ],
+        examples: &[
+            "agentbrowser-pro zoom 200%",
+            "agentbrowser-pro zoom 0.5 && agentbrowser-pro screenshot overview.png",
+            "agentbrowser-pro zoom reset",
+        ],
+        related: &["emulate", "screenshot"],
+    },
+    CommandSpec {
+        name: "media",
+        aliases: &[],
+        action: "listMediaDevices",
+        category: "Emulation",
+        description: "Give the session a fake camera and microphone, or list the media devices the page sees",
+        usage: "media <fake [--video=<file.y4m>] [--audio=<file.wav>] | fake off | devices list>",
+        args: &[
+            arg("subcommand", "fake or devices"),
+            opt_arg("action", "fake: off to go back to real devices; devices: list"),
+        ],
+        flags: &[
+            value_flag(
+                "--video",
+                "file",
+                "fake: play this .y4m or .mjpeg as the camera (default: test pattern)",
+            ),
+            value_flag(
+                "--audio",
+                "file",
+                "fake: play this .wav as the microphone (default: beep)",
+            ),
+            TIMEOUT,
+        ],
+        response: &[
+            ("fake", "boolean"),
+            ("video", "string?"),
+            ("audio", "string?"),
+            ("restarted", "boolean (fake)"),
+            ("devices", "[{kind, label, deviceId, groupId}] (devices list)"),
+        ],
+        examples: &[
+            "agentbrowser-pro media fake",
+            "agentbrowser-pro media fake --video=face.y4m --audio=hello.wav",
+            "agentbrowser-pro media devices list",
+            "agentbrowser-pro media fake off",
+        ],
+        related: &["emulate", "launch"],
+    },
+    // ============ Assertions ============
+    CommandSpec {
+        name: "expect",
+        aliases: &[],
+        action: "expectRequest",
+        category: "Assertions",
+        description: "Wait until an element is visible, has some text, the URL matches or a \
+                      selector matches n elements, failing with expected vs actual on timeout; \
+                      or check a request was made, or a value in JSON",
+        usage: "expect visible <selector> | expect text <selector> <text> [--exact] | \
+                expect url <pattern> | expect count <selector> <n> | \
+                expect request <url-pattern> [--method=POST] [--body-contains=<text>] | \
+                expect json <path> <matcher> [value]",
+        args: &[
+            arg(
+                "subcommand",
+                "visible, text, url, count, request or json",
+            ),
+            arg(
+                "selector|pattern|path",
+                "visible, text, count: element; url: regex matched against the page URL; \
+                 request: regex matched against request URLs; json: path like $.items[0].id",
+            ),
+            opt_arg(
+                "text|n|matcher",
+                "text: text the element contains; count: number of matches; json: equals, \
+                 not-equals, contains, not-contains, matches, gt, gte, lt, lte, type, length, \
+                 exists or absent",
+            ),
+            opt_arg("value", "json: what the matcher compares against"),
+        ],
+        flags: &[
+            flag(
+                "--exact",
+                "text: the whole text must match, not just contain it",
+            ),
+            value_flag("--method", "method", "Only requests with this HTTP method"),
+            value_flag(
+                "--body-contains",
+                "text",
+                "Only requests whose body contains this text",
+            ),
+            TIMEOUT,
+        ],
+        response: &[
+            ("expected", "string (visible, text, url, count)"),
+            ("elapsed", "number (visible, text, url, count)"),
+            ("matched", "number (request)"),
+            ("request", "{url, method, status?, resourceType, timestamp} (request)"),
+            ("path", "string (json)"),
+            ("matcher", "string (json)"),
+            ("actual", "string (visible, text, url, count), any (json)"),
+        ],
+        examples: &[
+            "agentbrowser-pro expect visible \"#checkout\"",
+            "agentbrowser-pro expect text h1 \"Welcome back\" --timeout=10000",
+            "agentbrowser-pro expect url '/orders/[0-9]+$'",
+            "agentbrowser-pro expect count \".cart-item\" 3",
+            "agentbrowser-pro expect request /collect --method=POST",
+            "agentbrowser-pro expect request 'google-analytics' --body-contains=purchase",
+            "agentbrowser-pro --json eval 'fetch(\"/api/me\").then(r => r.json())' | agentbrowser-pro expect json $.result.plan equals pro",
+            "expect json $.result.items[*].price lt 100   (in a run script, after eval)",
+        ],
+        related: &["network", "waitfor"],
+    },
+    // ============ Notifications ============
+    CommandSpec {
+        name: "notifications",
+        aliases: &[],
+        action: "getNotifications",
+        category: "Notifications",
+        description: "Record Notification API and push notifications instead of dropping them",
+        usage: "notifications <capture on|off|list> [--clear]",
+        args: &[
+            opt_arg("subcommand", "capture or list (default)"),
+            opt_arg("state", "capture: on or off"),
+        ],
+        flags: &[flag(
+            "--clear",
+            "Clear the recorded notifications after listing",
+        )],
+        response: &[
+            ("capture", "boolean (capture)"),
+            (
+                "notifications",
+                "[{source, title, body?, tag?, icon?, url, timestamp}] (list)",
+            ),
+        ],
+        examples: &[
+            "agentbrowser-pro notifications capture on",
+            "agentbrowser-pro notifications list",
+            "agentbrowser-pro notifications list --clear",
+        ],
+        related: &["network"],
+    },
+    // ============ Network ============
+    CommandSpec {
+        name: "network",
+        aliases: &[],
+        action: "getRequests",
+        category: "Network",
+        description: "List captured network requests",
+        usage:
+            "network list [--filter=<regex>] [--graphql] [--operation=<name>] [--body] [--clear]",
+        args: &[opt_arg("subcommand", "list (default)")],
+        flags: &[
+            value_flag("--filter", "regex", "Only requests whose URL matches"),
+            flag(
+                "--graphql",
+                "Only GraphQL requests, with operation names and variables",
+            ),
+            value_flag(
+                "--operation",
+                "name",
+                "Only GraphQL requests running this operation",
+            ),
+            flag("--body", "Include GraphQL response bodies"),
+            flag("--clear", "Clear the request log after listing"),
+            OFFSET,
+            LIMIT,
+            MAX_BYTES,
+            TIMEOUT,
+        ],
+        response: &[
+            ("requests", "array"),
+            ("total", "number?"),
+            ("truncated", "boolean?"),
+            ("nextOffset", "number?"),
+        ],
+        examples: &[
+            "agentbrowser-pro network list",
+            "agentbrowser-pro network list --filter=/api/ --limit=20",
+            "agentbrowser-pro network list --graphql",
+            "agentbrowser-pro network list --operation=GetCart --body",
+        ],
+        related: &["cookies", "wait", "route"],
+    },
+    CommandSpec {
+        name: "route",
+        aliases: &[],
+        action: "route",
+        category: "Network",
+        description: "Block matching requests or answer them with a canned response",
+        usage: "route <url-pattern> <--abort|--fulfill=<file>|--status=<code>>",
+        args: &[arg(
+            "url-pattern",
+            "URL glob (**/api/*) or regex between slashes (/ads|tracker/)",
+        )],
+        flags: &[
+            flag("--abort", "Fail matching requests, as if blocked"),
+            value_flag(
+                "--fulfill",
+                "file",
+                "Respond with this file's contents (content type from the extension)",
+            ),
+            value_flag("--status", "code", "Respond with this status (default 200)"),
+        ],
+        response: &[(
+            "route",
+            "{url, handler, status?, contentType?, bytes?, hits}",
+        )],
+        examples: &[
+            "agentbrowser-pro route '**/analytics/**' --abort",
+            "agentbrowser-pro route '**/api/cart' --fulfill=cart.json",
+            "agentbrowser-pro route '**/api/checkout' --status=500",
+            "agentbrowser-pro route '/\\.(png|jpg)$/' --abort",
+        ],
+        related: &["unroute", "routes", "network"],
+    },
+    CommandSpec {
+        name: "unroute",
+        aliases: &[],
+        action: "unroute",
+        category: "Network",
+        description: "Stop intercepting a URL pattern, or every pattern",
+        usage: "unroute [url-pattern]",
+        args: &[opt_arg(
+            "url-pattern",
+            "Pattern as given to route (default: all routes)",
+        )],
+        flags: &[],
+        response: &[("unrouted", "string[]")],
+        examples: &[
+            "agentbrowser-pro unroute '**/api/cart'",
+            "agentbrowser-pro unroute",
+        ],
+        related: &["route", "routes"],
+    },
+    CommandSpec {
+        name: "routes",
+        aliases: &[],
+        action: "getRoutes",
+        category: "Network",
+        description: "List active routes and how many requests each has handled",
+        usage: "routes",
+        args: &[],
+        flags: &[],
+        response: &[(
+            "routes",
+            "[{url, handler, status?, contentType?, bytes?, hits}]",
+        )],
+        examples: &["agentbrowser-pro routes"],
+        related: &["route", "unroute"],
+    },
+    CommandSpec {
+        name: "har",
+        aliases: &[],
+        action: "harStop",
+        category: "Network",
+        description: "Record network traffic into a HAR file",
+        usage: "har start <path> [--content] | har stop",
+        args: &[
+            arg("subcommand", "start or stop"),
+            opt_arg("path", "start: HAR file written on stop"),
+        ],
+        flags: &[flag("--content", "start: include response bodies")],
+        response: &[
+            ("recording", "boolean (start)"),
+            ("path", "string"),
+            ("entries", "number (stop)"),
+            ("size", "number (stop)"),
+        ],
+        examples: &[
+            "agentbrowser-pro har start bug-1234.har",
+            "agentbrowser-pro har start checkout.har --content",
+            "agentbrowser-pro har stop",
+        ],
+        related: &["network", "route"],
+    },
+    CommandSpec {
+        name: "download",
+        aliases: &[],
+        action: "download",
+        category: "Network",
+        description: "Click an element and save the file it downloads",
+        usage: "download <selector|ref> [path]",
+        args: &[
+            arg("selector", "Link or button that starts the download"),
+            opt_arg(
+                "path",
+                "File to save to (default: its suggested name in --downloads-dir)",
+            ),
+        ],
+        flags: &[TIMEOUT],
+        response: &[
+            ("url", "string"),
+            ("suggestedFilename", "string"),
+            ("path", "string"),
+            ("size", "number"),
+            ("state", "string"),
+        ],
+        examples: &[
+            "agentbrowser-pro download \"text=Export CSV\"",
+            "agentbrowser-pro download @e12 ./reports/invoice.pdf",
+            "agentbrowser-pro --downloads-dir=./out download \"#export\"",
+        ],
+        related: &["downloads", "click"],
+    },
+    CommandSpec {
+        name: "downloads",
+        aliases: &[],
+        action: "getDownloads",
+        category: "Network",
+        description: "List this session's downloads, saving finished ones with --downloads-dir",
+        usage: "downloads",
+        args: &[],
+        flags: &[],
+        response: &[(
+            "downloads",
+            "[{url, suggestedFilename, state, path?, size?, error?, timestamp}]",
+        )],
+        examples: &[
+            "agentbrowser-pro downloads",
+            "agentbrowser-pro --downloads-dir=./out downloads",
+        ],
+        related: &["download"],
+    },
+    // ============ Cookies / Storage ============
+    CommandSpec {
+        name: "cookies",
+        aliases: &["getcookies"],
+        action: "getCookies",
+        category: "Storage",
+        description: "Get all cookies",
+        usage: "cookies",
+        args: &[],
+        flags: &[OFFSET, LIMIT, MAX_BYTES, TIMEOUT],
+        response: &[
+            ("cookies", "array"),
+            ("total", "number?"),
+            ("truncated", "boolean?"),
+            ("nextOffset", "number?"),
+        ],
+        examples: &[
+            "agentbrowser-pro cookies",
+            "agentbrowser-pro cookies --limit=20",
+        ],
+        related: &["clearcookies", "localstorage"],
+    },
+    CommandSpec {
+        name: "clearcookies",
+        aliases: &[],
+        action: "clearCookies",
+        category: "Storage",
+        description: "Clear all cookies",
+        usage: "clearcookies",
+        args: &[],
+        flags: &[TIMEOUT],
+        response: &[("cleared", "boolean")],
+        examples: &["agentbrowser-pro clearcookies"],
+        related: &["cookies"],
+    },
+    CommandSpec {
+        name: "localstorage",
+        aliases: &["getlocalstorage"],
+        action: "getLocalStorage",
+        category: "Storage",
+        description: "Get localStorage",
+        usage: "localstorage [key]",
+        args: &[opt_arg("key", "Single key to read")],
+        flags: &[TIMEOUT],
+        response: &[("storage", "object"), ("value", "string")],
+        examples: &[
+            "agentbrowser-pro localstorage",
+            "agentbrowser-pro localstorage token",
+        ],
+        related: &["clearlocalstorage", "cookies"],
+    },
+    CommandSpec {
+        name: "state",
+        aliases: &[],
+        action: "saveState",
+        category: "Storage",
+        description: "Save cookies and localStorage to a file, or load them back; --encrypt seals the file",
+        usage: "state save <file> [--encrypt] | state load <file> | state key",
+        args: &[
+            arg("subcommand", "save, load or key"),
+            opt_arg("file", "Storage state file"),
+        ],
+        flags: &[
+            flag(
+                "--encrypt",
+                "Encrypt with the key from AGENT_BROWSER_STATE_KEY or ~/.agentbrowser-pro/state.key",
+            ),
+            TIMEOUT,
+        ],
+        response: &[
+            ("saved", "string? (state save)"),
+            ("loaded", "string? (state load)"),
+            ("encrypted", "boolean?"),
+            ("key", "string? (state key, base64)"),
+        ],
+        examples: &[
+            "agentbrowser-pro state save auth.json",
+            "agentbrowser-pro state save auth.state --encrypt",
+            "agentbrowser-pro state load auth.state",
+            "agentbrowser-pro state key",
+        ],
+        related: &["cookies", "localstorage", "pool"],
+    },
+    CommandSpec {
+        name: "clearlocalstorage",
+        aliases: &[],
+        action: "clearLocalStorage",
+        category: "Storage",
+        description: "Clear localStorage",
+        usage: "clearlocalstorage",
+        args: &[],
+        flags: &[TIMEOUT],
+        response: &[("cleared", "boolean")],
+        examples: &["agentbrowser-pro clearlocalstorage"],
+        related: &["localstorage", "storage"],
+    },
+    CommandSpec {
+        name: "storage",
+        aliases: &[],
+        action: "getStorageUsage",
+        category: "Storage",
+        description: "Report the origin's storage quota and usage by cookies, localStorage, IndexedDB and CacheStorage",
+        usage: "storage usage",
+        args: &[arg("subcommand", "usage")],
+        flags: &[TIMEOUT],
+        response: &[
+            ("origin", "string"),
+            ("usage", "number"),
+            ("quota", "number"),
+            ("percent", "number|null"),
+            ("persisted", "boolean|null"),
+            ("source", "cdp|estimate"),
+            (
+                "breakdown",
+                "{cookies, localStorage, sessionStorage, indexedDB, cacheStorage, serviceWorkers}",
+            ),
+        ],
+        examples: &[
+            "agentbrowser-pro storage usage",
+            "agentbrowser-pro --json storage usage | jq '.result.breakdown.indexedDB'",
+        ],
+        related: &["localstorage", "cookies", "cachestorage"],
+    },
+    CommandSpec {
+        name: "cachestorage",
+        aliases: &[],
+        action: "listCaches",
+        category: "Storage",
+        description: "List the origin's CacheStorage caches, or the responses cached in one",
+        usage: "cachestorage <list|entries <cache-name>>",
+        args: &[
+            arg("subcommand", "list or entries"),
+            opt_arg("cache-name", "entries: cache to inspect, from 'cachestorage list'"),
+        ],
+        flags: &[OFFSET, LIMIT, TIMEOUT],
+        response: &[
+            ("caches", "[{name, entries}]?"),
+            ("cache", "string?"),
+            (
+                "entries",
+                "[{url, method, status, type, contentType, size, date, ageSeconds}]?",
+            ),
+        ],
+        examples: &[
+            "agentbrowser-pro cachestorage list",
+            "agentbrowser-pro cachestorage entries workbox-precache-v2 --limit=20",
+        ],
+        related: &["storage", "pwa", "network"],
+    },
+    // ============ Other ============
+    CommandSpec {
+        name: "pdf",
+        aliases: &[],
+        action: "pdf",
+        category: "Other",
+        description: "Generate PDF",
+        usage: "pdf [path]",
+        args: &[opt_arg(
+            "path",
+            "File to write (base64 returned if omitted)",
+        )],
+        flags: &[TIMEOUT],
+        response: &[("path", "string"), ("data", "base64")],
+        examples: &["agentbrowser-pro pdf page.pdf"],
+        related: &["screenshot"],
+    },
+    CommandSpec {
+        name: "stream",
+        aliases: &["startstream"],
+        action: "startStream",
+        category: "Other",
+        description: "Start viewport streaming",
+        usage: "stream",
+        args: &[],
+        flags: &[TIMEOUT],
+        response: &[("info", "string")],
+        examples: &["agentbrowser-pro stream"],
+        related: &["stopstream"],
+    },
+    CommandSpec {
+        name: "stopstream",
+        aliases: &[],
+        action: "stopStream",
+        category: "Other",
+        description: "Stop viewport streaming",
+        usage: "stopstream",
+        args: &[],
+        flags: &[TIMEOUT],
+        response: &[],
+        examples: &["agentbrowser-pro stopstream"],
+        related: &["stream"],
+    },
+];
+
+impl CommandSpec {
+    /// Whether this command accepts the given flag
+    pub fn accepts(&self, flag: &str) -> bool {
+        self.flags.iter().any(|f| f.name == flag)
+    }
+
+    /// Positional arguments that must be given
+    pub fn required_args(&self) -> usize {
+        self.args.iter().filter(|a| a.required).count()
+    }
+}
+
+/// Actions allowed under `--read-only`: they read the page, navigate with
+/// GET, capture it, wait, or only touch local files and processes. Anything
+/// missing here is refused, so a new command stays blocked until it is
+/// listed. `mcp` is left out since its tools don't go through this check.
+const READ_ONLY_ACTIONS: &[&str] = &[
+    // Navigation and pages
+    "navigate",
+    "back",
+    "forward",
+    "reload",
+    "handoff",
+    "getPages",
+    "newPage",
+    "switchPage",
+    "closePage",
+    "getFrames",
+    "switchToFrame",
+    "switchToMainFrame",
+    // Reading
+    "snapshot",
+    "getUrl",
+    "getTitle",
+    "getText",
+    "getHtml",
+    "getValue",
+    "getCount",
+    "getTooltip",
+    "getFocused",
+    "getOverlays",
+    "getPageInfo",
+    "getFeeds",
+    "fetchFeed",
+    "getFavicon",
+    "getStructuredData",
+    "seoAudit",
+    "pwaAudit",
+    "isVisible",
+    "isEnabled",
+    "isChecked",
+    "isInViewport",
+    "getScrollPosition",
+    "getCookies",
+    "getLocalStorage",
+    "getStorageUsage",
+    "listCaches",
+    "getCacheEntries",
+    "getRequests",
+    "getRoutes",
+    "getErrors",
+    "tapMessages",
+    "getNotifications",
+    "getDownloads",
+    "listExtensions",
+    "listMediaDevices",
+    // Moving around without acting
+    "scroll",
+    "scrollTo",
+    "hover",
+    "hoverPath",
+    "focus",
+    "tabTo",
+    // Capture
+    "screenshot",
+    "screenshotTiles",
+    "captureCanvas",
+    "pdf",
+    "saveState",
+    "harStart",
+    "harStop",
+    "startStream",
+    "stopStream",
+    "pagediffSave",
+    "pagediffCompare",
+    "textDiff",
+    "detectLanguage",
+    "translate",
+    // Waits and assertions
+    "wait",
+    "waitForSelector",
+    "waitForLoadState",
+    "waitForResponse",
+    "pause",
+    "resume",
+    "expectVisible",
+    "expectText",
+    "expectUrl",
+    "expectCount",
+    "expectRequest",
+    "expectJson",
+    // Local tooling; scripts, the REPL and workers check each command
+    "launch",
+    "close",
+    "daemon",
+    "daemonStats",
+    "stateKey",
+    "listDevices",
+    "poolStart",
+    "poolStatus",
+    "poolStop",
+    "crawl",
+    "monitorAdd",
+    "monitorList",
+    "monitorRemove",
+    "monitorRun",
+    "runScript",
+    "repl",
+    "worker",
+    // Each browser command a plugin sends is checked on its own
+    "plugin",
+    "if",
+    "bench",
+    "spec",
+    "help",
+    "aliasList",
+    "i18nCheck",
+    "i18nTemplate",
+];
+
+/// Whether `--read-only` lets `action` run
+pub fn is_read_only(action: &str) -> bool {
+    READ_ONLY_ACTIONS.contains(&action)
+}
+
+static PLUGINS: OnceLock<Vec<CommandSpec>> = OnceLock::new();
+
+/// Add the commands of configured plugins, once at startup
+pub fn register_plugins(specs: Vec<CommandSpec>) {
+    PLUGINS.set(specs).ok();
+}
+
+/// Every command: the built-in ones, then any plugins'
+pub fn commands() -> impl Iterator<Item = &'static CommandSpec> {
+    COMMANDS.iter().chain(PLUGINS.get().into_iter().flatten())
+}
+
+/// The command that sends `action`, preferring one named after it
+pub fn find_action(action: &str) -> Option<&'static CommandSpec> {
+    find_command(action)
+        .filter(|c| c.action == action)
+        .or_else(|| COMMANDS.iter().find(|c| c.action == action))
+}
+
+/// Look up a command by name or alias (case-insensitive)
+pub fn find_command(name: &str) -> Option<&'static CommandSpec> {
+    let name = name.to_lowercase();
+    commands().find(|c| c.name == name || c.aliases.contains(&name.as_str()))
+}
+
+/// The command name or alias closest to a mistyped one, if any is close
+/// enough to be a plausible typo
+pub fn suggest_command(name: &str) -> Option<&'static str> {
+    let name = name.to_lowercase();
+    let limit = (name.chars().count() / 3).clamp(1, 3);
+    commands()
+        .flat_map(|c| std::iter::once(c.name).chain(c.aliases.iter().copied()))
+        .map(|candidate| (edit_distance(&name, candidate), candidate))
+        .filter(|(distance, _)| *distance <= limit)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+
+/// Levenshtein distance, counting an adjacent swap as one edit
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut rows = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in rows.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    rows[0] = (0..=b.len()).collect();
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            rows[i][j] = (rows[i - 1][j] + 1)
+                .min(rows[i][j - 1] + 1)
+                .min(rows[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                rows[i][j] = rows[i][j].min(rows[i - 2][j - 2] + 1);
+            }
+        }
+    }
+    rows[a.len()][b.len()]
+}
@@ -0,0 +1,1538 @@
+/*!
+ * Human Output Renderers
+ *
+ * Each command's result is printed by the renderer registered for its
+ * action (the `action` of its entry in `registry.rs`). Actions without one
+ * get the generic renderer: single values are printed bare, acknowledgements
+ * as "Success", and anything else as pretty JSON.
+ *
+ * `--json` output and failed responses never reach a renderer.
+ */
+use serde_json::Value;
+
+use crate::i18n::t;
+
+/// Prints a successful command result for a human
+pub trait Renderer: Sync {
+    fn render(&self, result: &Value);
+}
+
+impl<F: Fn(&Value) + Sync> Renderer for F {
+    fn render(&self, result: &Value) {
+        self(result)
+    }
+}
+
+/// Renderers by action, grouped and ordered like `COMMANDS` in registry.rs;
+/// actions a command sends besides its registered one sit next to it
+static RENDERERS: &[(&str, &dyn Renderer)] = &[
+    // ============ Lifecycle ============
+    ("daemonStats", &daemon_stats),
+    ("poolStart", &pool),
+    ("poolStatus", &pool),
+    // ============ Interaction ============
+    ("clickByText", &click_by_name),
+    ("clickByRole", &click_by_name),
+    ("tabTo", &tab_to),
+    ("write", &write),
+    ("paste", &paste),
+    ("setDate", &set_date),
+    ("hoverPath", &hover_path),
+    ("keyDown", &keys),
+    ("keyUp", &keys),
+    ("getScrollPosition", &scroll_position),
+    ("scrollTo", &scroll_position),
+    ("mediaElement", &media_element),
+    ("setSlider", &slider),
+    ("reorder", &reorder),
+    ("drag", &drag),
+    ("handleDialog", &dialog),
+    ("setDialogAuto", &dialog),
+    // ============ Information ============
+    ("snapshot", &snapshot),
+    ("screenshot", &screenshot),
+    ("screenshotTiles", &screenshot_tiles),
+    ("captureCanvas", &canvas),
+    ("getFocused", &focused),
+    ("getOverlays", &overlays),
+    ("getStructuredData", &structured_data),
+    ("seoAudit", &audit),
+    ("pwaAudit", &audit),
+    ("getFeeds", &feeds),
+    ("getPageInfo", &page_info),
+    ("fetchFeed", &feed),
+    ("getFavicon", &favicon),
+    ("getText", &outline),
+    ("detectLanguage", &language),
+    ("translate", &translation),
+    // ============ State Checks ============
+    ("isInViewport", &in_viewport),
+    // ============ Wait ============
+    ("waitForResponse", &response),
+    ("pause", &pause),
+    ("getErrors", &page_errors),
+    ("tapMessages", &messages),
+    // ============ Frames ============
+    ("getFrames", &frames),
+    // ============ Pages ============
+    ("getPages", &pages),
+    // ============ Extensions ============
+    ("listExtensions", &extensions),
+    ("extensionInstall", &extension_change),
+    ("extensionRemove", &extension_change),
+    // ============ Emulation ============
+    ("setViewport", &viewport),
+    ("emulateDevice", &device),
+    ("resetDevice", &device),
+    ("listDevices", &devices),
+    ("setZoom", &zoom),
+    ("listMediaDevices", &media_devices),
+    ("mediaFake", &media_fake),
+    // ============ Assertions ============
+    ("expectRequest", &expect_request),
+    ("expectJson", &expect_json),
+    ("expectVisible", &expectation),
+    ("expectText", &expectation),
+    ("expectUrl", &expectation),
+    ("expectCount", &expectation),
+    // ============ Notifications ============
+    ("getNotifications", &notifications),
+    // ============ Network ============
+    ("getRequests", &requests),
+    ("route", &route),
+    ("unroute", &unroute),
+    ("getRoutes", &routes),
+    ("harStart", &har_start),
+    ("harStop", &har_stop),
+    ("download", &download),
+    ("getDownloads", &downloads),
+    // ============ Cookies / Storage ============
+    ("getCookies", &cookies),
+    ("getLocalStorage", &storage),
+    ("saveState", &state),
+    ("loadState", &state),
+    ("stateKey", &state),
+    ("getStorageUsage", &storage_usage),
+    ("listCaches", &caches),
+    ("getCacheEntries", &cache_entries),
+    // ============ Other ============
+    ("pdf", &pdf),
+];
+
+/// The renderer for `action`, or the generic one
+pub fn renderer(action: &str) -> &'static dyn Renderer {
+    RENDERERS
+        .iter()
+        .find(|(name, _)| *name == action)
+        .map(|(_, renderer)| *renderer)
+        .unwrap_or(&generic)
+}
+
+fn str_field<'a>(value: &'a Value, key: &str) -> &'a str {
+    value.get(key).and_then(|v| v.as_str()).unwrap_or("")
+}
+
+fn print_json(value: &Value) {
+    println!(
+        "{}",
+        serde_json::to_string_pretty(value).unwrap_or_default()
+    );
+}
+
+/// Single values, acknowledgements, then JSON
+fn generic(result: &Value) {
+    for key in ["url", "title", "text"] {
+        if let Some(s) = result.get(key).and_then(|v| v.as_str()) {
+            println!("{}", s);
+            return;
+        }
+    }
+    if let Some(value) = result.get("value") {
+        match value.as_str() {
+            Some(s) => println!("{}", s),
+            None => println!("{}", value),
+        }
+        return;
+    }
+    if let Some(html) = result.get("html").and_then(|v| v.as_str()) {
+        println!("{}", html);
+        return;
+    }
+    for key in ["visible", "enabled", "checked"] {
+        if let Some(state) = result.get(key).and_then(|v| v.as_bool()) {
+            println!("{}", state);
+            return;
+        }
+    }
+    if let Some(count) = result.get("count").and_then(|v| v.as_i64()) {
+        println!("{}", count);
+        return;
+    }
+
+    const ACKNOWLEDGEMENTS: &[&str] = &[
+        "clicked",
+        "typed",
+        "filled",
+        "checked",
+        "unchecked",
+        "selected",
+        "hovered",
+        "focused",
+        "pressed",
+        "scrolled",
+        "cleared",
+        "set",
+        "launched",
+        "closed",
+        "switched",
+        "created",
+        "waited",
+        "found",
+        "resumed",
+    ];
+    if ACKNOWLEDGEMENTS.iter().any(|key| result.get(key).is_some()) {
+        println!("\x1b[32m✓\x1b[0m {}", t("success"));
+        return;
+    }
+
+    print_json(result);
+}
+
+/// The text tree, or the structured forms of `--output`
+fn snapshot(result: &Value) {
+    if let Some(tree) = result.get("tree") {
+        if let Some(modal) = result.get("modal") {
+            let name = str_field(modal, "name");
+            println!(
+                "\x1b[90mModal:\x1b[0m {}{}",
+                modal["role"]
+                    .as_str()
+                    .unwrap_or_else(|| str_field(modal, "tag")),
+                if name.is_empty() {
+                    String::new()
+                } else {
+                    format!(" \"{}\"", name)
+                }
+            );
+        }
+        if let Some(tree_str) = tree.as_str() {
+            println!("{}", tree_str);
+            println!();
+        }
+        if let Some(url) = result.get("url").and_then(|v| v.as_str()) {
+            println!("\x1b[90mURL:\x1b[0m {}", url);
+        }
+        if let Some(title) = result.get("title").and_then(|v| v.as_str()) {
+            println!("\x1b[90mTitle:\x1b[0m {}", title);
+        }
+    } else if let Some(xml) = result.get("xml").and_then(|v| v.as_str()) {
+        println!("{}", xml);
+    } else {
+        print_json(result);
+    }
+}
+
+fn pool(result: &Value) {
+    let members = result
+        .get("members")
+        .and_then(|v| v.as_array())
+        .map(Vec::as_slice)
+        .unwrap_or(&[]);
+    println!(
+        "Pool '{}' ({} members)",
+        str_field(result, "name"),
+        members.len()
+    );
+    for member in members {
+        let healthy = member.get("healthy").and_then(|v| v.as_bool()) == Some(true);
+        let busy = member.get("busy").and_then(|v| v.as_bool()) == Some(true);
+        println!(
+            "  {} {:<24} {}",
+            if healthy {
+                "\x1b[32m●\x1b[0m"
+            } else {
+                "\x1b[31m●\x1b[0m"
+            },
+            str_field(member, "session"),
+            if busy { "busy" } else { "idle" }
+        );
+    }
+}
+
+fn daemon_stats(result: &Value) {
+    let num = |v: &Value, key: &str| v.get(key).and_then(|v| v.as_f64()).unwrap_or(0.0);
+    let mb = |bytes: f64| bytes / (1024.0 * 1024.0);
+
+    println!(
+        "Daemon     pid {}, up {:.0}s, {} commands served",
+        result.get("pid").and_then(|v| v.as_i64()).unwrap_or(0),
+        num(result, "uptimeMs") / 1000.0,
+        result
+            .get("commandsServed")
+            .and_then(|v| v.as_u64())
+            .unwrap_or(0)
+    );
+    if let Some(process) = result.get("process").filter(|p| !p.is_null()) {
+        println!(
+            "           cpu {:.2}s, rss {:.1} MB",
+            num(process, "cpuSeconds"),
+            mb(num(process, "rssBytes"))
+        );
+    }
+    if let Some(browser) = result.get("browser") {
+        println!(
+            "Browser    {} processes, cpu {:.2}s, rss {:.1} MB",
+            num(browser, "processes"),
+            num(browser, "cpuSeconds"),
+            mb(num(browser, "rssBytes"))
+        );
+    }
+    println!(
+        "Pages      {}",
+        result.get("pages").and_then(|v| v.as_u64()).unwrap_or(0)
+    );
+}
+
+fn click_by_name(result: &Value) {
+    let matches = result.get("matches").and_then(|v| v.as_u64()).unwrap_or(1);
+    println!(
+        "\x1b[32m✓\x1b[0m Clicked {}{}",
+        str_field(result, "clicked"),
+        if matches > 1 {
+            format!(" \x1b[90m(first visible of {} matches)\x1b[0m", matches)
+        } else {
+            String::new()
+        }
+    );
+}
+
+fn tab_to(result: &Value) {
+    let stops = result.get("stops").and_then(|v| v.as_array());
+    for (i, stop) in stops.into_iter().flatten().enumerate() {
+        println!(
+            "\x1b[90m{:>3}\x1b[0m {}{}",
+            i + 1,
+            str_field(stop, "role"),
+            match str_field(stop, "name") {
+                "" => String::new(),
+                name => format!(" \"{}\"", name),
+            }
+        );
+    }
+    println!(
+        "\x1b[32m✓\x1b[0m Focused {} after {} Tab press(es)",
+        match str_field(result, "name") {
+            "" => str_field(result, "role").to_string(),
+            name => format!("{} \"{}\"", str_field(result, "role"), name),
+        },
+        result.get("presses").unwrap_or(&Value::Null)
+    );
+}
+
+fn focused(result: &Value) {
+    if result.get("tag").is_none_or(Value::is_null) {
+        println!("Nothing focused (document body)");
+        return;
+    }
+    println!(
+        "{}{}{}",
+        str_field(result, "role"),
+        match str_field(result, "name") {
+            "" => String::new(),
+            name => format!(" \"{}\"", name),
+        },
+        match str_field(result, "ref") {
+            "" => String::new(),
+            r => format!(" \x1b[36m[{}]\x1b[0m", r),
+        }
+    );
+    println!("  \x1b[90m{}\x1b[0m", str_field(result, "selector"));
+}
+
+fn hover_path(result: &Value) {
+    let hovered: Vec<&str> = result
+        .get("hovered")
+        .and_then(|v| v.as_array())
+        .into_iter()
+        .flatten()
+        .filter_map(|s| s.as_str())
+        .collect();
+    println!("\x1b[32m✓\x1b[0m Hovered {}", hovered.join(" → "));
+}
+
+fn overlays(result: &Value) {
+    let overlays = result.get("overlays").and_then(|v| v.as_array());
+    if overlays.is_none_or(|o| o.is_empty()) {
+        println!("No fixed or sticky elements over the viewport");
+        return;
+    }
+    for overlay in overlays.into_iter().flatten() {
+        println!(
+            "{} \x1b[90m{} {}, z-index {}, {}% of viewport\x1b[0m",
+            str_field(overlay, "kind"),
+            match str_field(overlay, "selector") {
+                "" => str_field(overlay, "tag"),
+                selector => selector,
+            },
+            str_field(overlay, "position"),
+            match overlay.get("zIndex") {
+                Some(Value::Number(z)) => z.to_string(),
+                _ => "auto".to_string(),
+            },
+            overlay.get("coverage").unwrap_or(&Value::Null)
+        );
+        if let Some(text) = overlay
+            .get("text")
+            .and_then(|v| v.as_str())
+            .filter(|t| !t.is_empty())
+        {
+            println!("  {}", text);
+        }
+        for r in overlay
+            .get("refs")
+            .and_then(|v| v.as_array())
+            .into_iter()
+            .flatten()
+        {
+            println!(
+                "  \x1b[36m{}\x1b[0m {} \"{}\"",
+                str_field(r, "ref"),
+                str_field(r, "role"),
+                str_field(r, "name")
+            );
+        }
+    }
+}
+
+fn media_element(result: &Value) {
+    let num = |key: &str| result.get(key).and_then(|v| v.as_f64());
+    let flag = |key: &str| result.get(key).and_then(|v| v.as_bool()) == Some(true);
+    let state = if flag("ended") {
+        "ended"
+    } else if flag("paused") {
+        "paused"
+    } else {
+        "playing"
+    };
+    println!(
+        "<{}> {} at {:.2}s / {}{}",
+        str_field(result, "tag"),
+        state,
+        num("currentTime").unwrap_or(0.0),
+        num("duration").map_or("?".to_string(), |d| format!("{:.2}s", d)),
+        if flag("muted") { ", muted" } else { "" }
+    );
+    let buffered: Vec<String> = result
+        .get("buffered")
+        .and_then(|v| v.as_array())
+        .into_iter()
+        .flatten()
+        .map(|range| {
+            format!(
+                "{:.1}-{:.1}s",
+                range[0].as_f64().unwrap_or(0.0),
+                range[1].as_f64().unwrap_or(0.0)
+            )
+        })
+        .collect();
+    println!(
+        "  \x1b[90m{}, buffered {}\x1b[0m",
+        str_field(result, "readyStateName"),
+        if buffered.is_empty() {
+            "nothing".to_string()
+        } else {
+            buffered.join(", ")
+        }
+    );
+    if let Some(error) = result.get("error").and_then(|v| v.as_str()) {
+        println!("  \x1b[31m{}\x1b[0m", error);
+    }
+}
+
+fn write(result: &Value) {
+    let text = str_field(result, "text");
+    println!(
+        "\x1b[32m✓\x1b[0m Wrote via {} \x1b[90m(editor now holds {} characters)\x1b[0m",
+        str_field(result, "method"),
+        text.chars().count()
+    );
+}
+
+fn paste(result: &Value) {
+    println!(
+        "\x1b[32m✓\x1b[0m Pasted {} characters \x1b[90m(via {})\x1b[0m",
+        result.get("length").and_then(|v| v.as_u64()).unwrap_or(0),
+        if result["method"] == "keyboard" {
+            "Ctrl/Cmd+V"
+        } else {
+            "a paste event"
+        }
+    );
+}
+
+fn set_date(result: &Value) {
+    let value = str_field(result, "value");
+    match result.get("typed").and_then(|v| v.as_str()) {
+        Some(typed) if typed != value => println!(
+            "\x1b[32m✓\x1b[0m Typed {} \x1b[90m(field now reads {})\x1b[0m",
+            typed, value
+        ),
+        Some(typed) => println!("\x1b[32m✓\x1b[0m Typed {}", typed),
+        None => println!(
+            "\x1b[32m✓\x1b[0m Set {} input to {}",
+            str_field(result, "input"),
+            value
+        ),
+    }
+}
+
+fn slider(result: &Value) {
+    let num = |key: &str| result.get(key).and_then(|v| v.as_f64()).unwrap_or(0.0);
+    let (value, target) = (num("value"), num("target"));
+    if value == target {
+        println!(
+            "\x1b[32m✓\x1b[0m Slider set to {} \x1b[90m(range {} to {})\x1b[0m",
+            value,
+            num("min"),
+            num("max")
+        );
+    } else {
+        println!(
+            "\x1b[33m⚠\x1b[0m Slider stopped at {} instead of {} \x1b[90m(range {} to {})\x1b[0m",
+            value,
+            target,
+            num("min"),
+            num("max")
+        );
+    }
+}
+
+fn reorder(result: &Value) {
+    let num = |key: &str| result.get(key).and_then(|v| v.as_u64()).unwrap_or(0);
+    if result.get("moved").and_then(|v| v.as_bool()) == Some(true) {
+        println!(
+            "\x1b[32m✓\x1b[0m Moved item from position {} to {} \x1b[90m(of {})\x1b[0m",
+            num("from"),
+            num("to"),
+            num("count")
+        );
+    } else {
+        println!(
+            "\x1b[33m⚠\x1b[0m Item ended at position {} instead of {}; the list may not \
+             support pointer drags, or re-rendered while moving",
+            num("index"),
+            num("to")
+        );
+    }
+}
+
+fn drag(result: &Value) {
+    let dragged = &result["dragged"];
+    let to = match dragged["to"].as_str() {
+        Some(target) => target.to_string(),
+        None => format!(
+            "({}, {})",
+            dragged["to"]["x"].as_f64().unwrap_or(0.0),
+            dragged["to"]["y"].as_f64().unwrap_or(0.0)
+        ),
+    };
+    println!(
+        "\x1b[32m✓\x1b[0m Dragged {} → {}",
+        str_field(dragged, "from"),
+        to
+    );
+}
+
+fn keys(result: &Value) {
+    let holding: Vec<&str> = result["holding"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|key| key.as_str())
+        .collect();
+    match (result["held"].as_str(), result["released"].as_str()) {
+        (Some(key), _) => println!("\x1b[32m✓\x1b[0m Holding {}", key),
+        (None, Some(key)) => println!("\x1b[32m✓\x1b[0m Released {}", key),
+        _ => print_json(result),
+    }
+    if !holding.is_empty() {
+        println!("\x1b[90mHeld: {}\x1b[0m", holding.join(", "));
+    }
+}
+
+fn scroll_position(result: &Value) {
+    let num = |key: &str| result.get(key).and_then(|v| v.as_u64()).unwrap_or(0);
+    let edge = match (
+        result.get("atTop").and_then(|v| v.as_bool()),
+        result.get("atBottom").and_then(|v| v.as_bool()),
+    ) {
+        (_, Some(true)) => " \x1b[90m(bottom)\x1b[0m",
+        (Some(true), _) => " \x1b[90m(top)\x1b[0m",
+        _ => "",
+    };
+    println!(
+        "x={} y={} of max {},{}{}",
+        num("x"),
+        num("y"),
+        num("maxX"),
+        num("maxY"),
+        edge
+    );
+}
+
+fn in_viewport(result: &Value) {
+    let ratio = result.get("ratio").and_then(|v| v.as_f64()).unwrap_or(0.0);
+    println!(
+        "{} \x1b[90m({:.0}% in viewport{})\x1b[0m",
+        ratio > 0.0,
+        ratio * 100.0,
+        match str_field(result, "obscuredBy") {
+            "" => String::new(),
+            by => format!(", covered by {}", by),
+        }
+    );
+}
+
+fn zoom(result: &Value) {
+    let zoom = result.get("zoom").and_then(|v| v.as_f64()).unwrap_or(1.0);
+    let viewport = result.get("viewport").unwrap_or(&Value::Null);
+    println!(
+        "\x1b[32m✓\x1b[0m Zoom {:.0}% ({}), viewport {}x{}",
+        zoom * 100.0,
+        str_field(result, "mode"),
+        viewport.get("width").unwrap_or(&Value::Null),
+        viewport.get("height").unwrap_or(&Value::Null)
+    );
+}
+
+fn viewport(result: &Value) {
+    let viewport = &result["viewport"];
+    println!(
+        "\x1b[32m✓\x1b[0m Viewport {}x{}",
+        viewport["width"], viewport["height"]
+    );
+}
+
+fn device(result: &Value) {
+    let Some(name) = result["device"].as_str() else {
+        println!("\x1b[32m✓\x1b[0m Device emulation cleared");
+        return;
+    };
+    let viewport = &result["viewport"];
+    println!(
+        "\x1b[32m✓\x1b[0m Emulating {} ({}x{} @{}x{}{})",
+        name,
+        viewport["width"],
+        viewport["height"],
+        result["deviceScaleFactor"].as_f64().unwrap_or(1.0),
+        if result["isMobile"] == true {
+            ", mobile"
+        } else {
+            ""
+        },
+        if result["hasTouch"] == true {
+            ", touch"
+        } else {
+            ""
+        }
+    );
+}
+
+fn devices(result: &Value) {
+    for device in result["devices"].as_array().into_iter().flatten() {
+        let size = format!(
+            "{}x{}",
+            device["viewport"]["width"], device["viewport"]["height"]
+        );
+        let kind = match (device["isMobile"] == true, device["hasTouch"] == true) {
+            (true, _) => "mobile",
+            (false, true) => "touch",
+            (false, false) => "desktop",
+        };
+        println!(
+            "{:<20} {:>10} @{:<6} {}",
+            str_field(device, "name"),
+            size,
+            device["deviceScaleFactor"].as_f64().unwrap_or(1.0),
+            kind
+        );
+    }
+}
+
+fn storage_usage(result: &Value) {
+    let num = |v: &Value, key: &str| v.get(key).and_then(|v| v.as_f64()).unwrap_or(0.0);
+    let size = |bytes: f64| match bytes {
+        b if b >= 1024.0 * 1024.0 * 1024.0 => format!("{:.1} GB", b / (1024.0 * 1024.0 * 1024.0)),
+        b if b >= 1024.0 * 1024.0 => format!("{:.1} MB", b / (1024.0 * 1024.0)),
+        b if b >= 1024.0 => format!("{:.1} KB", b / 1024.0),
+        b => format!("{} B", b),
+    };
+
+    println!(
+        "{}  {} of {} ({}%){}",
+        str_field(result, "origin"),
+        size(num(result, "usage")),
+        size(num(result, "quota")),
+        num(result, "percent"),
+        if result.get("persisted") == Some(&Value::Bool(true)) {
+            ", persisted"
+        } else {
+            ""
+        }
+    );
+    let breakdown = result.get("breakdown").unwrap_or(&Value::Null);
+    let names = |v: &Value, key: &str| {
+        v.get(key)
+            .and_then(|v| v.as_array())
+            .map(|items| {
+                items
+                    .iter()
+                    .filter_map(|i| i.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            })
+            .unwrap_or_default()
+    };
+    let rows = [
+        (
+            "cookies",
+            format!("{} cookies", num(&breakdown["cookies"], "count")),
+        ),
+        (
+            "localStorage",
+            format!("{} keys", num(&breakdown["localStorage"], "keys")),
+        ),
+        (
+            "sessionStorage",
+            format!("{} keys", num(&breakdown["sessionStorage"], "keys")),
+        ),
+        ("indexedDB", names(&breakdown["indexedDB"], "databases")),
+        ("cacheStorage", names(&breakdown["cacheStorage"], "caches")),
+        ("serviceWorkers", String::new()),
+    ];
+    for (name, detail) in rows {
+        println!(
+            "  {:<16}{:>10}  \x1b[90m{}\x1b[0m",
+            name,
+            size(num(&breakdown[name], "bytes")),
+            detail
+        );
+    }
+}
+
+fn caches(result: &Value) {
+    let caches = result
+        .get("caches")
+        .and_then(|v| v.as_array())
+        .map(Vec::as_slice)
+        .unwrap_or(&[]);
+    if caches.is_empty() {
+        println!("No caches for {}", str_field(result, "origin"));
+    }
+    for cache in caches {
+        println!(
+            "{} \x1b[90m({} entries)\x1b[0m",
+            str_field(cache, "name"),
+            cache.get("entries").unwrap_or(&Value::Null)
+        );
+    }
+}
+
+fn cache_entries(result: &Value) {
+    let entries = result
+        .get("entries")
+        .and_then(|v| v.as_array())
+        .map(Vec::as_slice)
+        .unwrap_or(&[]);
+    if entries.is_empty() {
+        println!("{} is empty", str_field(result, "cache"));
+    }
+    for entry in entries {
+        let age = match entry.get("ageSeconds").and_then(|v| v.as_u64()) {
+            Some(secs) if secs >= 86400 => format!("{}d", secs / 86400),
+            Some(secs) if secs >= 3600 => format!("{}h", secs / 3600),
+            Some(secs) if secs >= 60 => format!("{}m", secs / 60),
+            Some(secs) => format!("{}s", secs),
+            None => "-".to_string(),
+        };
+        let status = match str_field(entry, "type") {
+            "opaque" => "opaque".to_string(),
+            _ => entry.get("status").unwrap_or(&Value::Null).to_string(),
+        };
+        println!(
+            "{:<6} {:>4} {} \x1b[90m{}\x1b[0m",
+            status,
+            age,
+            str_field(entry, "url"),
+            str_field(entry, "contentType")
+        );
+    }
+}
+
+fn screenshot_tiles(result: &Value) {
+    screenshot(result);
+    println!(
+        "  {}x{} px, stitched from {} tiles",
+        result["width"], result["height"], result["tiles"]
+    );
+}
+
+fn screenshot(result: &Value) {
+    if let Some(path) = result.get("path").and_then(|v| v.as_str()) {
+        println!("\x1b[32m✓\x1b[0m Screenshot saved to: {}", path);
+    } else {
+        println!("\x1b[32m✓\x1b[0m Screenshot captured (base64 data available)");
+    }
+}
+
+fn canvas(result: &Value) {
+    let size = format!("{}x{}", result["width"], result["height"]);
+    match result.get("path").and_then(|v| v.as_str()) {
+        Some(path) => println!(
+            "\x1b[32m✓\x1b[0m Canvas ({}) saved to: {} \x1b[90m({} bytes)\x1b[0m",
+            size, path, result["bytes"]
+        ),
+        None => println!(
+            "\x1b[32m✓\x1b[0m Canvas ({}) captured (base64 data available)",
+            size
+        ),
+    }
+    if result.get("blank").and_then(|v| v.as_bool()) == Some(true) {
+        println!(
+            "\x1b[33m⚠\x1b[0m The bitmap is empty; WebGL canvases without preserveDrawingBuffer \
+             clear between frames, so try 'screenshot' instead"
+        );
+    }
+}
+
+fn pdf(result: &Value) {
+    if let Some(path) = result.get("path").and_then(|v| v.as_str()) {
+        println!("\x1b[32m✓\x1b[0m PDF saved to: {}", path);
+    } else {
+        println!("\x1b[32m✓\x1b[0m PDF generated (base64 data available)");
+    }
+}
+
+/// A page expectation that held
+fn expectation(result: &Value) {
+    println!(
+        "\x1b[32m✓\x1b[0m {} \x1b[90m({}ms)\x1b[0m",
+        str_field(result, "expected"),
+        result.get("elapsed").and_then(|v| v.as_u64()).unwrap_or(0)
+    );
+}
+
+fn expect_json(result: &Value) {
+    println!(
+        "\x1b[32m✓\x1b[0m {} {} {}",
+        str_field(result, "path"),
+        str_field(result, "matcher"),
+        str_field(result, "expected")
+    );
+}
+
+fn expect_request(result: &Value) {
+    let request = result.get("request").unwrap_or(&Value::Null);
+    println!(
+        "\x1b[32m✓\x1b[0m {} matching request(s), first: {} {}",
+        result.get("matched").and_then(|v| v.as_u64()).unwrap_or(0),
+        str_field(request, "method"),
+        str_field(request, "url")
+    );
+}
+
+fn pause(result: &Value) {
+    let by = match str_field(result, "by") {
+        "page" => "from the browser",
+        "terminal" => "from the terminal",
+        _ => "by resume",
+    };
+    println!(
+        "\x1b[32m✓\x1b[0m Continued {} \x1b[90m(after {:.1}s)\x1b[0m",
+        by,
+        result.get("waited").and_then(|v| v.as_f64()).unwrap_or(0.0) / 1000.0
+    );
+}
+
+/// A response matched by `waitfor response`
+fn response(result: &Value) {
+    println!(
+        "{} {} {} \x1b[90m({} bytes)\x1b[0m",
+        str_field(result, "method"),
+        result.get("status").unwrap_or(&Value::Null),
+        str_field(result, "url"),
+        result.get("bytes").and_then(|v| v.as_u64()).unwrap_or(0)
+    );
+    let body = str_field(result, "body");
+    if !body.is_empty() {
+        println!("{}", body);
+    }
+    if result.get("truncated").and_then(|v| v.as_bool()) == Some(true) {
+        println!("\x1b[90m... (truncated)\x1b[0m");
+    }
+}
+
+/// The page's document response
+fn page_info(result: &Value) {
+    let text = |key: &str| str_field(result, key);
+    for redirect in result
+        .get("redirects")
+        .and_then(|v| v.as_array())
+        .into_iter()
+        .flatten()
+    {
+        println!(
+            "\x1b[90m{} {} →\x1b[0m",
+            redirect.get("status").unwrap_or(&Value::Null),
+            str_field(redirect, "url")
+        );
+    }
+    println!(
+        "{} {} {}{}",
+        result.get("status").unwrap_or(&Value::Null),
+        text("statusText"),
+        text("url"),
+        match text("protocol") {
+            "" => String::new(),
+            protocol => format!(" \x1b[90m({})\x1b[0m", protocol),
+        }
+    );
+    if let Some(bytes) = result.get("transferSize").and_then(|v| v.as_u64()) {
+        println!("  transferred: {} bytes", bytes);
+    }
+    if let Some(address) = result.get("remoteAddress").and_then(|v| v.as_str()) {
+        println!("  server: {}", address);
+    }
+    if let Some(tls) = result.get("tls").and_then(|v| v.get("protocol")) {
+        println!("  tls: {}", tls.as_str().unwrap_or(""));
+    }
+    for (name, value) in result
+        .get("headers")
+        .and_then(|v| v.as_object())
+        .into_iter()
+        .flatten()
+    {
+        println!(
+            "  \x1b[90m{}:\x1b[0m {}",
+            name,
+            value.as_str().unwrap_or("")
+        );
+    }
+}
+
+/// Feeds discovered on the page
+fn feeds(result: &Value) {
+    let feeds = result
+        .get("feeds")
+        .and_then(|v| v.as_array())
+        .map(Vec::as_slice)
+        .unwrap_or(&[]);
+    if feeds.is_empty() {
+        println!("No feeds found");
+    }
+    for feed in feeds {
+        println!(
+            "\x1b[90m{:<5}\x1b[0m {} {}",
+            str_field(feed, "format"),
+            str_field(feed, "url"),
+            str_field(feed, "title")
+        );
+    }
+}
+
+/// The chosen icon, then the others in ranked order
+fn favicon(result: &Value) {
+    let saved = match result.get("path").and_then(|v| v.as_str()) {
+        Some(path) => format!("saved to {}", path),
+        None => "base64 data available".to_string(),
+    };
+    println!(
+        "\x1b[32m✓\x1b[0m {} \x1b[90m({}, {}, {} bytes, {})\x1b[0m",
+        str_field(result, "url"),
+        str_field(result, "source"),
+        str_field(result, "type"),
+        result.get("bytes").unwrap_or(&Value::Null),
+        saved
+    );
+    for candidate in result
+        .get("candidates")
+        .and_then(|v| v.as_array())
+        .into_iter()
+        .flatten()
+        .filter(|c| c.get("url") != result.get("url"))
+    {
+        println!(
+            "  \x1b[90m{:<16} {:>5}px\x1b[0m {}",
+            str_field(candidate, "source"),
+            candidate.get("size").unwrap_or(&Value::Null),
+            str_field(candidate, "url")
+        );
+    }
+}
+
+/// A feed's entries
+fn feed(result: &Value) {
+    if let Some(title) = result.get("title").and_then(|v| v.as_str()) {
+        println!("\x1b[1m{}\x1b[0m", title);
+    }
+    for entry in result
+        .get("entries")
+        .and_then(|v| v.as_array())
+        .into_iter()
+        .flatten()
+    {
+        println!(
+            "• {} \x1b[90m{}\x1b[0m",
+            str_field(entry, "title"),
+            str_field(entry, "published")
+        );
+        if let Some(link) = entry.get("link").and_then(|v| v.as_str()) {
+            println!("  {}", link);
+        }
+    }
+}
+
+/// Pass/warn/fail checks from seo and pwa
+fn audit(result: &Value) {
+    for check in result
+        .get("checks")
+        .and_then(|v| v.as_array())
+        .into_iter()
+        .flatten()
+    {
+        let mark = match check.get("status").and_then(|v| v.as_str()) {
+            Some("pass") => "\x1b[32m✓\x1b[0m",
+            Some("warn") => "\x1b[33m⚠\x1b[0m",
+            _ => "\x1b[31m✗\x1b[0m",
+        };
+        println!(
+            "{} {:<12} {}",
+            mark,
+            str_field(check, "check"),
+            str_field(check, "message")
+        );
+    }
+    let summary = result.get("summary").unwrap_or(&Value::Null);
+    println!(
+        "\n{}: {} passed, {} warning(s), {} failed",
+        str_field(result, "status").to_uppercase(),
+        summary["pass"],
+        summary["warn"],
+        summary["fail"]
+    );
+}
+
+/// JSON-LD, microdata and OpenGraph found on the page
+fn structured_data(result: &Value) {
+    let indent = |value: &Value| {
+        serde_json::to_string_pretty(value)
+            .unwrap_or_default()
+            .replace('\n', "\n  ")
+    };
+    let json_ld = result.get("jsonLd").and_then(|v| v.as_array());
+    let microdata = result.get("microdata").and_then(|v| v.as_array());
+    let open_graph = result.get("openGraph").and_then(|v| v.as_object());
+    let sections = [("JSON-LD", json_ld), ("Microdata", microdata)];
+    for (name, items) in sections {
+        let items = match items {
+            Some(items) if !items.is_empty() => items,
+            _ => continue,
+        };
+        println!("\x1b[1m{}\x1b[0m ({})", name, items.len());
+        for item in items {
+            println!("  {}", indent(item));
+        }
+    }
+    if let Some(og) = open_graph.filter(|og| !og.is_empty()) {
+        println!("\x1b[1mOpenGraph\x1b[0m ({})", og.len());
+        for (key, value) in og {
+            let value = value
+                .as_str()
+                .map(String::from)
+                .unwrap_or_else(|| value.to_string());
+            println!("  {}: {}", key, value);
+        }
+    }
+    if let Some(errors) = result.get("errors").and_then(|v| v.as_array()) {
+        for error in errors.iter().filter_map(|e| e.as_str()) {
+            println!("\x1b[33m⚠\x1b[0m {}", error);
+        }
+    }
+    let empty = |items: Option<&Vec<Value>>| items.map(|i| i.is_empty()).unwrap_or(true);
+    if empty(json_ld) && empty(microdata) && open_graph.map(|og| og.is_empty()).unwrap_or(true) {
+        println!("No structured data found");
+    }
+}
+
+fn language(result: &Value) {
+    match result["language"].as_str() {
+        Some(lang) => println!(
+            "{} ({:.0}% confidence)",
+            lang,
+            result["confidence"].as_f64().unwrap_or(0.0) * 100.0
+        ),
+        None => println!("Unknown (not enough text)"),
+    }
+    if let Some(declared) = result["declared"].as_str() {
+        println!("  declared: {}", declared);
+    }
+}
+
+fn translation(result: &Value) {
+    println!(
+        "\x1b[2m{} → {}\x1b[0m",
+        str_field(result, "from"),
+        str_field(result, "to")
+    );
+    println!("{}", str_field(result, "translation"));
+}
+
+/// `text --structured`, as Markdown; plain text otherwise
+fn outline(result: &Value) {
+    let Some(blocks) = result.get("outline").and_then(|v| v.as_array()) else {
+        return generic(result);
+    };
+    let cells = |row: &Value| {
+        row.as_array()
+            .map(|cells| {
+                let cells: Vec<&str> = cells.iter().filter_map(|c| c.as_str()).collect();
+                cells.join(" | ")
+            })
+            .unwrap_or_default()
+    };
+    for (i, block) in blocks.iter().enumerate() {
+        if i > 0 {
+            println!();
+        }
+        let text = str_field(block, "text");
+        match str_field(block, "type") {
+            "heading" => {
+                let level = block.get("level").and_then(|v| v.as_u64()).unwrap_or(1);
+                println!("{} {}", "#".repeat(level.clamp(1, 6) as usize), text);
+            }
+            "list" => {
+                let ordered = block.get("ordered") == Some(&Value::Bool(true));
+                let items = block.get("items").and_then(|v| v.as_array());
+                for (n, item) in items.into_iter().flatten().enumerate() {
+                    let item = item.as_str().unwrap_or("");
+                    if ordered {
+                        println!("{}. {}", n + 1, item);
+                    } else {
+                        println!("- {}", item);
+                    }
+                }
+            }
+            "code" => println!("```\n{}\n```", text),
+            "quote" => println!("> {}", text),
+            "table" => {
+                let rows = block.get("rows").and_then(|v| v.as_array());
+                for row in rows.into_iter().flatten() {
+                    println!("| {} |", cells(row));
+                }
+            }
+            _ => println!("{}", text),
+        }
+        let links = block.get("links").and_then(|v| v.as_array());
+        for link in links.into_iter().flatten() {
+            println!(
+                "  \x1b[2m[{}]({})\x1b[0m",
+                str_field(link, "text"),
+                str_field(link, "href")
+            );
+        }
+    }
+    if blocks.is_empty() {
+        println!("No text found");
+    }
+}
+
+/// Captured network requests
+fn requests(result: &Value) {
+    for request in result
+        .get("requests")
+        .and_then(|v| v.as_array())
+        .into_iter()
+        .flatten()
+    {
+        let status = request
+            .get("status")
+            .and_then(|v| v.as_u64())
+            .map(|s| s.to_string())
+            .unwrap_or_else(|| "...".to_string());
+        println!(
+            "{} {} {}",
+            str_field(request, "method"),
+            status,
+            str_field(request, "url")
+        );
+        let operations = request.get("graphql").and_then(|v| v.as_array());
+        for op in operations.into_iter().flatten() {
+            let kind = op
+                .get("operationType")
+                .and_then(|v| v.as_str())
+                .unwrap_or("query");
+            let name = op
+                .get("operationName")
+                .and_then(|v| v.as_str())
+                .unwrap_or("(anonymous)");
+            match op.get("variables").filter(|v| !v.is_null()) {
+                Some(vars) => println!("  {} {} {}", kind, name, vars),
+                None => println!("  {} {}", kind, name),
+            }
+        }
+        if let Some(body) = request.get("responseBody").and_then(|v| v.as_str()) {
+            println!("{}", body);
+        }
+    }
+}
+
+/// What a route does with the requests it matches
+fn route_handling(route: &Value) -> String {
+    if route["handler"] != "fulfill" {
+        return str_field(route, "handler").to_string();
+    }
+    let status = route.get("status").and_then(|v| v.as_u64()).unwrap_or(200);
+    let mut handling = status.to_string();
+    if let Some(bytes) = route.get("bytes").and_then(|v| v.as_u64()) {
+        handling.push_str(&format!(
+            " {} ({} bytes)",
+            str_field(route, "contentType"),
+            bytes
+        ));
+    }
+    handling
+}
+
+fn route(result: &Value) {
+    let route = &result["route"];
+    println!(
+        "\x1b[32m✓\x1b[0m Routed {} → {}",
+        str_field(route, "url"),
+        route_handling(route)
+    );
+}
+
+fn unroute(result: &Value) {
+    let unrouted = result
+        .get("unrouted")
+        .and_then(|v| v.as_array())
+        .map(Vec::as_slice)
+        .unwrap_or(&[]);
+    if unrouted.is_empty() {
+        println!("No routes to remove");
+    }
+    for url in unrouted {
+        println!("\x1b[32m✓\x1b[0m Unrouted {}", url.as_str().unwrap_or(""));
+    }
+}
+
+fn routes(result: &Value) {
+    let routes = result
+        .get("routes")
+        .and_then(|v| v.as_array())
+        .map(Vec::as_slice)
+        .unwrap_or(&[]);
+    if routes.is_empty() {
+        println!("No active routes");
+    }
+    for route in routes {
+        println!(
+            "{} → {} \x1b[90m({} hits)\x1b[0m",
+            str_field(route, "url"),
+            route_handling(route),
+            route.get("hits").and_then(|v| v.as_u64()).unwrap_or(0)
+        );
+    }
+}
+
+fn dialog(result: &Value) {
+    match (result["armed"].as_str(), result["auto"].as_str()) {
+        (Some(answer), _) => println!(
+            "\x1b[32m✓\x1b[0m The next dialog will be {}{}",
+            if answer == "accept" {
+                "accepted"
+            } else {
+                "dismissed"
+            },
+            result["promptText"]
+                .as_str()
+                .map(|text| format!(" with \"{}\"", text))
+                .unwrap_or_default()
+        ),
+        (None, Some(mode)) => println!(
+            "\x1b[32m✓\x1b[0m Dialogs will be {}",
+            if mode == "accept" {
+                "accepted"
+            } else {
+                "dismissed"
+            }
+        ),
+        _ => print_json(result),
+    }
+}
+
+fn har_start(result: &Value) {
+    println!(
+        "\x1b[32m✓\x1b[0m Recording network traffic for {}",
+        str_field(result, "path")
+    );
+}
+
+fn har_stop(result: &Value) {
+    println!(
+        "\x1b[32m✓\x1b[0m Saved {} requests to {} \x1b[90m({} bytes)\x1b[0m",
+        result.get("entries").and_then(|v| v.as_u64()).unwrap_or(0),
+        str_field(result, "path"),
+        result.get("size").and_then(|v| v.as_u64()).unwrap_or(0)
+    );
+}
+
+fn download(result: &Value) {
+    println!(
+        "\x1b[32m✓\x1b[0m Saved {} \x1b[90m({} bytes)\x1b[0m",
+        str_field(result, "path"),
+        result.get("size").and_then(|v| v.as_u64()).unwrap_or(0)
+    );
+}
+
+fn downloads(result: &Value) {
+    let downloads = result
+        .get("downloads")
+        .and_then(|v| v.as_array())
+        .map(Vec::as_slice)
+        .unwrap_or(&[]);
+    if downloads.is_empty() {
+        println!("No downloads");
+    }
+    for d in downloads {
+        let size = d
+            .get("size")
+            .and_then(|v| v.as_u64())
+            .map(|n| format!(" ({} bytes)", n))
+            .unwrap_or_default();
+        println!(
+            "{} [{}]{}",
+            str_field(d, "suggestedFilename"),
+            str_field(d, "state"),
+            size
+        );
+        match d.get("path").and_then(|v| v.as_str()) {
+            Some(path) => println!("  \x1b[90m→ {}\x1b[0m", path),
+            None => println!("  \x1b[90m{}\x1b[0m", str_field(d, "url")),
+        }
+        if let Some(error) = d.get("error").and_then(|v| v.as_str()) {
+            println!("  \x1b[31m{}\x1b[0m", error);
+        }
+    }
+}
+
+fn page_errors(result: &Value) {
+    let errors = result
+        .get("errors")
+        .and_then(|v| v.as_array())
+        .map(Vec::as_slice)
+        .unwrap_or(&[]);
+    if errors.is_empty() {
+        println!("No page errors");
+    }
+    for error in errors {
+        crate::pageerrors::print_error(error);
+    }
+}
+
+fn messages(result: &Value) {
+    let messages = result
+        .get("messages")
+        .and_then(|v| v.as_array())
+        .map(Vec::as_slice)
+        .unwrap_or(&[]);
+    if messages.is_empty() {
+        println!("Tapping postMessage and BroadcastChannel; no messages yet");
+    }
+    for message in messages {
+        crate::messages::print_message(message);
+    }
+}
+
+/// Recorded notifications
+fn notifications(result: &Value) {
+    let notifications = result
+        .get("notifications")
+        .and_then(|v| v.as_array())
+        .map(Vec::as_slice)
+        .unwrap_or(&[]);
+    if notifications.is_empty() {
+        println!("No notifications recorded");
+    }
+    for n in notifications {
+        println!(
+            "\x1b[1m{}\x1b[0m \x1b[90m({})\x1b[0m",
+            str_field(n, "title"),
+            str_field(n, "source")
+        );
+        if let Some(body) = n.get("body").and_then(|v| v.as_str()) {
+            println!("  {}", body);
+        }
+    }
+}
+
+/// An extension installed or removed
+fn extension_change(result: &Value) {
+    let (verb, path) = match result.get("installed") {
+        Some(path) => ("Installed", path),
+        None => ("Removed", result.get("removed").unwrap_or(&Value::Null)),
+    };
+    let name = result.get("name").and_then(|v| v.as_str());
+    println!(
+        "\x1b[32m✓\x1b[0m {} {}",
+        verb,
+        name.unwrap_or_else(|| path.as_str().unwrap_or(""))
+    );
+    if result.get("restarted").and_then(|v| v.as_bool()) == Some(true) {
+        println!("↻ Restarted the session's browser to apply it");
+    }
+}
+
+fn media_fake(result: &Value) {
+    if result.get("fake").and_then(|v| v.as_bool()) == Some(true) {
+        let file = |key: &str, default: &'static str| {
+            result.get(key).and_then(|v| v.as_str()).unwrap_or(default)
+        };
+        println!("\x1b[32m✓\x1b[0m Fake media devices on");
+        println!("  camera      {}", file("video", "test pattern"));
+        println!("  microphone  {}", file("audio", "beep"));
+    } else {
+        println!("\x1b[32m✓\x1b[0m Fake media devices off");
+    }
+    if result.get("restarted").and_then(|v| v.as_bool()) == Some(true) {
+        println!("↻ Restarted the session's browser to apply it");
+    }
+}
+
+fn media_devices(result: &Value) {
+    let devices = result
+        .get("devices")
+        .and_then(|v| v.as_array())
+        .map(Vec::as_slice)
+        .unwrap_or(&[]);
+    if devices.is_empty() {
+        println!("No media devices");
+    }
+    for device in devices {
+        let label = match str_field(device, "label") {
+            "" => "(no label until the page is granted access)",
+            label => label,
+        };
+        println!("{:<12} {}", str_field(device, "kind"), label);
+    }
+    if result.get("fake").and_then(|v| v.as_bool()) == Some(true) {
+        println!("\x1b[90mFake devices are on ('media fake off' to use real ones)\x1b[0m");
+    }
+}
+
+fn extensions(result: &Value) {
+    let extensions = result
+        .get("extensions")
+        .and_then(|v| v.as_array())
+        .map(Vec::as_slice)
+        .unwrap_or(&[]);
+    if extensions.is_empty() {
+        println!("No extensions loaded (use --extensions=<dir> or 'extension install')");
+    }
+    for ext in extensions {
+        let name = ext
+            .get("name")
+            .and_then(|v| v.as_str())
+            .unwrap_or("(unnamed)");
+        println!(
+            "{} {}  {}",
+            name,
+            str_field(ext, "version"),
+            str_field(ext, "path")
+        );
+    }
+    match result.get("ids").and_then(|v| v.as_array()) {
+        Some(ids) if !ids.is_empty() => {
+            for id in ids {
+                println!("  running: {}", id.as_str().unwrap_or(""));
+            }
+        }
+        _ if !extensions.is_empty() => {
+            println!(
+                "\x1b[33mHint:\x1b[0m no extension is running; the headless shell \
+                 ignores extensions, so start the session with --headed"
+            );
+        }
+        _ => {}
+    }
+}
+
+fn cookies(result: &Value) {
+    for cookie in result
+        .get("cookies")
+        .and_then(|v| v.as_array())
+        .into_iter()
+        .flatten()
+    {
+        if let Some(name) = cookie.get("name").and_then(|v| v.as_str()) {
+            println!("{}: {}", name, str_field(cookie, "value"));
+        }
+    }
+}
+
+/// Open tabs
+fn pages(result: &Value) {
+    let pages = result.get("pages").and_then(|v| v.as_array());
+    for (i, page) in pages.into_iter().flatten().enumerate() {
+        println!(
+            "[{}] {} - {}",
+            i,
+            str_field(page, "title"),
+            str_field(page, "url")
+        );
+    }
+}
+
+fn frames(result: &Value) {
+    for frame in result
+        .get("frames")
+        .and_then(|v| v.as_array())
+        .into_iter()
+        .flatten()
+    {
+        let name = frame
+            .get("name")
+            .and_then(|v| v.as_str())
+            .unwrap_or("(unnamed)");
+        println!("{}: {}", name, str_field(frame, "url"));
+    }
+}
+
+/// localStorage entries, or one item's value
+fn storage(result: &Value) {
+    match result.get("storage").and_then(|v| v.as_object()) {
+        Some(storage) => {
+            for (key, value) in storage {
+                println!("{}: {}", key, value);
+            }
+        }
+        None => generic(result),
+    }
+}
+
+fn state(result: &Value) {
+    let encrypted = if result["encrypted"] == true {
+        " (encrypted)"
+    } else {
+        ""
+    };
+    if let Some(path) = result["saved"].as_str() {
+        println!("\x1b[32m✓\x1b[0m Saved state to {}{}", path, encrypted);
+    } else if let Some(path) = result["loaded"].as_str() {
+        println!("\x1b[32m✓\x1b[0m Loaded state from {}{}", path, encrypted);
+    } else if let Some(key) = result["key"].as_str() {
+        println!("{}", key);
+    } else {
+        generic(result);
+    }
+}
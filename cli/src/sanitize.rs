@@ -0,0 +1,131 @@
+/*!
+ * HTML Sanitizing
+ *
+ * `html --sanitized` strips what a reader of the markup never needs:
+ * `<script>`, `<style>` and `<iframe>` elements, comments, inline event
+ * handlers (`onclick=...`), `style` attributes, `<meta>` refreshes and any
+ * URL whose scheme isn't on ammonia's list, `data:` and `javascript:`
+ * included. What's left is smaller to hand to an LLM and safe to store or
+ * render.
+ *
+ * ammonia parses the markup the way a browser does, so entity-encoded or
+ * whitespace-split schemes (`&#106;avascript:`, `java\tscript:`) are caught
+ * where a string match would miss them. Tags outside its allow-list are
+ * unwrapped, keeping their text.
+ */
+
+use std::sync::OnceLock;
+
+use ammonia::Builder;
+
+/// Tags kept on top of ammonia's defaults, for the page structure and
+/// form controls an agent reads the markup for
+const EXTRA_TAGS: &[&str] = &[
+    "main", "section", "form", "fieldset", "legend", "label", "button", "input", "select",
+    "option", "optgroup", "textarea", "tfoot",
+];
+
+/// Attributes kept on every tag, for finding elements again
+const EXTRA_ATTRIBUTES: &[&str] = &[
+    "id",
+    "class",
+    "name",
+    "role",
+    "type",
+    "value",
+    "placeholder",
+    "alt",
+    "aria-label",
+    "aria-describedby",
+    "for",
+];
+
+/// Sanitize an HTML document or fragment
+pub fn html(source: &str) -> String {
+    static CLEANER: OnceLock<Builder<'static>> = OnceLock::new();
+    CLEANER
+        .get_or_init(|| {
+            let mut builder = Builder::default();
+            builder
+                .add_tags(EXTRA_TAGS)
+                .add_generic_attributes(EXTRA_ATTRIBUTES)
+                .add_clean_content_tags(&["iframe"])
+                .link_rel(None);
+            builder
+        })
+        .clean(source)
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strips_scripts_styles_comments_and_handlers() {
+        let out = html(
+            "<p onclick=\"x()\" style=\"color:red\" class=\"a\">Hi<!-- note --></p>\
+             <script>alert(1)</script><style>p{}</style>",
+        );
+        assert_eq!(out, "<p class=\"a\">Hi</p>");
+    }
+
+    #[test]
+    fn keeps_links_and_form_controls() {
+        let out = html(
+            "<form><label for=\"q\">Search</label><input id=\"q\" name=\"q\" type=\"text\">\
+             <button type=\"submit\">Go</button></form><a href=\"/docs\">Docs</a>",
+        );
+        assert_eq!(
+            out,
+            "<form><label for=\"q\">Search</label><input id=\"q\" name=\"q\" type=\"text\">\
+             <button type=\"submit\">Go</button></form><a href=\"/docs\">Docs</a>"
+        );
+    }
+
+    #[test]
+    fn drops_data_and_javascript_urls() {
+        assert_eq!(
+            html("<img src=\"data:image/png;base64,AAAA\" alt=\"x\">"),
+            "<img alt=\"x\">"
+        );
+        assert_eq!(html("<a href=\"javascript:alert(1)\">x</a>"), "<a>x</a>");
+    }
+
+    #[test]
+    fn drops_encoded_and_split_schemes() {
+        assert_eq!(
+            html("<a href=\"&#106;avascript:alert(1)\">x</a>"),
+            "<a>x</a>"
+        );
+        assert_eq!(html("<a href=\"java\tscript:alert(1)\">x</a>"), "<a>x</a>");
+        assert_eq!(
+            html("<a href=\" \u{1}javascript:alert(1)\">x</a>"),
+            "<a>x</a>"
+        );
+        assert_eq!(
+            html("<a href=\"JaVaScRiPt&colon;alert(1)\">x</a>"),
+            "<a>x</a>"
+        );
+    }
+
+    #[test]
+    fn drops_srcdoc_frames_and_meta_refresh() {
+        assert_eq!(
+            html("<iframe srcdoc=\"&lt;script&gt;alert(1)&lt;/script&gt;\"></iframe><p>ok</p>"),
+            "<p>ok</p>"
+        );
+        assert_eq!(
+            html("<meta http-equiv=\"refresh\" content=\"0;url=javascript:alert(1)\"><p>ok</p>"),
+            "<p>ok</p>"
+        );
+    }
+
+    #[test]
+    fn unknown_tags_keep_their_text() {
+        assert_eq!(
+            html("<custom-card><b>Price</b> 5</custom-card>"),
+            "<b>Price</b> 5"
+        );
+    }
+}
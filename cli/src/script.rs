@@ -0,0 +1,1059 @@
+/*!
+ * Command Scripts
+ *
+ * A script is a list of CLI commands, one per line, with `#` comments:
+ *
+ * ```text
+ * on popup closepage
+ * on console-error screenshot error.png
+ * navigate https://example.com
+ * click @e3
+ * ```
+ *
+ * `on <event> <command>` lines declare hooks. After every step the runner
+ * asks the daemon which popups, dialogs, downloads and console errors
+ * happened, and runs the matching hooks before the next step, so flows
+ * don't have to poll for them.
+ *
+ * `if visible <selector> then <command> [else <command>]` branches on the
+ * page without a shell conditional; it also works as a one-off command.
+ *
+ * `foreach <selector> do ... end` runs its body once per matching element
+ * with `$item` bound to that element (`<selector> >> nth=<i>`) and `$index`
+ * to its position. The elements are counted once and re-resolved by
+ * position, so the body may navigate away and back. A short body can go on
+ * one line: `foreach .row do click $item; back`.
+ *
+ * `set NAME = <command>` runs the command and stores its value (the text,
+ * title, url, count... it returns) for the rest of the run, where
+ * `$NAME` or `${NAME}` in any later step expands to it:
+ *
+ * ```text
+ * set ORDER_ID = text .order-id
+ * navigate https://admin.example.com/orders/${ORDER_ID}
+ * ```
+ *
+ * `expect json <path> <matcher> [value]` checks the previous step's JSON
+ * result (see `assertions.rs`).
+ *
+ * A failing step stops the run unless `--continue-on-error` is given, in
+ * which case the rest of the script still runs (a failure inside `foreach`
+ * moves on to the next step of the body) and the report lists every
+ * failed line.
+ *
+ * Steps that are plain daemon commands are sent over one connection kept
+ * open for the whole run, which hook events are read over too. A step the
+ * CLI handles itself or finishes off after the daemon answers (a local
+ * command, paging, a run directory...) runs through this binary instead,
+ * so it behaves exactly as it would typed at a shell.
+ *
+ * `run <file>` and worker jobs both execute scripts through this module;
+ * `run --data` (see `dataset.rs`) runs one once per row of a data file.
+ */
+use std::collections::HashMap;
+use std::env;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::time::Instant;
+
+use serde_json::{json, Value};
+
+use crate::assertions::JsonExpectation;
+use crate::commands::{parse_command, split_words, CommandJson};
+use crate::config::Config;
+use crate::connection::{ensure_daemon, Connection, Response};
+use crate::flags::{self, Flags};
+use crate::hooks::{self, ResultHooks};
+use crate::i18n::{t, tf};
+use crate::paging::PAGING_FLAGS;
+use crate::plugins;
+use crate::refs;
+use crate::registry::find_command;
+
+/// Actions the CLI runs itself, or prepares or finishes off around the
+/// daemon's answer, so a step with one goes through the binary
+const CLI_ACTIONS: &[&str] = &[
+    "spec",
+    "aliasList",
+    "i18nCheck",
+    "i18nTemplate",
+    "bench",
+    "daemon",
+    "daemonStats",
+    "mcp",
+    "mediaFake",
+    "if",
+    "runScript",
+    "worker",
+    "crawl",
+    "expectJson",
+    "repl",
+    "plugin",
+    "detectLanguage",
+    "translate",
+    "textDiff",
+    "pause",
+    "handoff",
+    "stateKey",
+    "listDevices",
+    "download",
+    "getDownloads",
+    "saveState",
+    "loadState",
+    "listExtensions",
+    "harStop",
+    "screenshotTiles",
+    "captureCanvas",
+];
+
+/// Families of local commands, by action prefix
+const CLI_ACTION_PREFIXES: &[&str] = &["pool", "extension", "monitor", "pagediff"];
+
+/// Command flags the CLI applies to the daemon's answer
+const CLI_FLAGS: &[&str] = &[
+    "--cache",
+    "--sanitized",
+    "--max-tokens",
+    "--output",
+    "--follow",
+    "--encrypt",
+];
+
+/// Events a hook can be declared for
+pub const EVENTS: &[&str] = &["popup", "dialog", "download", "console-error"];
+
+/// One command from a script, with where it came from
+#[derive(Debug, Clone)]
+pub struct Line {
+    pub number: usize,
+    pub text: String,
+    pub args: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct Hook {
+    pub event: String,
+    pub line: Line,
+}
+
+/// A script step: a plain command or a branch on the page's state
+#[derive(Debug)]
+pub enum Step {
+    Run(Line),
+    If {
+        line: Line,
+        selector: String,
+        then: Line,
+        otherwise: Option<Line>,
+    },
+    ForEach {
+        line: Line,
+        selector: String,
+        body: Vec<Step>,
+    },
+    Set {
+        name: String,
+        command: Line,
+    },
+}
+
+/// Result fields that hold a command's value, in order of preference
+const VALUE_FIELDS: &[&str] = &[
+    "text", "value", "title", "url", "count", "result", "visible", "html",
+];
+
+/// The value a `set` step stores from a command's result
+fn result_value(result: &Value) -> Option<String> {
+    let value = match result {
+        Value::Object(map) if map.len() == 1 => map.values().next()?,
+        Value::Object(map) => VALUE_FIELDS.iter().find_map(|f| map.get(*f))?,
+        other => other,
+    };
+    match value {
+        Value::Null => None,
+        Value::String(s) => Some(s.clone()),
+        other => Some(other.to_string()),
+    }
+}
+
+/// Parse `set NAME = <command>`
+fn parse_set(number: usize, args: &[String]) -> Result<Step, String> {
+    if args.len() < 4 || args[2] != "=" {
+        return Err("expected 'set NAME = <command>'".to_string());
+    }
+    let name = &args[1];
+    let valid = name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if !valid {
+        return Err(format!(
+            "invalid variable name '{}' (letters, digits and _)",
+            name
+        ));
+    }
+    Ok(Step::Set {
+        name: name.clone(),
+        command: sub_line(number, &args[3..]),
+    })
+}
+
+/// Values `$name` / `${name}` expand to in a step's arguments
+pub type Vars = HashMap<String, String>;
+
+/// Expand `$name` and `${name}` references to bound variables; unknown
+/// names are left as written
+pub fn interpolate(arg: &str, vars: &Vars) -> String {
+    let mut out = String::with_capacity(arg.len());
+    let mut rest = arg;
+    while let Some(pos) = rest.find('$') {
+        out.push_str(&rest[..pos]);
+        let after = &rest[pos + 1..];
+        let (name, len) = if let Some(braced) = after.strip_prefix('{') {
+            match braced.find('}') {
+                Some(end) => (&braced[..end], end + 2),
+                None => ("", 0),
+            }
+        } else {
+            let end = after
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                .unwrap_or(after.len());
+            (&after[..end], end)
+        };
+        match vars.get(name).filter(|_| !name.is_empty()) {
+            Some(value) => {
+                out.push_str(value);
+                rest = &after[len..];
+            }
+            None => {
+                out.push('$');
+                rest = after;
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+#[derive(Debug, Default)]
+pub struct Script {
+    pub steps: Vec<Step>,
+    pub hooks: Vec<Hook>,
+}
+
+/// A sub-command of a compound line, numbered like the line it sits on
+fn sub_line(number: usize, args: &[String]) -> Line {
+    Line {
+        number,
+        text: args.join(" "),
+        args: args.to_vec(),
+    }
+}
+
+pub const IF_USAGE: &str = "if visible <selector> then <command> [else <command>]";
+
+/// Parse `if visible <selector> then <command> [else <command>]`
+pub fn parse_if(number: usize, args: &[String]) -> Result<Step, String> {
+    let usage = || format!("expected '{}'", IF_USAGE);
+    if args.len() < 5 || args[1] != "visible" || args[3] != "then" {
+        return Err(usage());
+    }
+    let body = &args[4..];
+    let (then, otherwise) = match body.iter().position(|a| a == "else") {
+        Some(0) => return Err(usage()),
+        Some(i) if i + 1 == body.len() => return Err(usage()),
+        Some(i) => (&body[..i], Some(sub_line(number, &body[i + 1..]))),
+        None => (body, None),
+    };
+    Ok(Step::If {
+        line: sub_line(number, args),
+        selector: args[2].clone(),
+        then: sub_line(number, then),
+        otherwise,
+    })
+}
+
+/// Parse a script, skipping blank lines and `#` comments
+pub fn parse(source: &str) -> Result<Script, String> {
+    let lines: Vec<(usize, &str)> = source
+        .lines()
+        .enumerate()
+        .map(|(i, raw)| (i + 1, raw.trim()))
+        .filter(|(_, text)| !text.is_empty() && !text.starts_with('#'))
+        .collect();
+
+    let mut script = Script::default();
+    let mut next = 0;
+    script.steps = parse_block(&lines, &mut next, Some(&mut script.hooks))?;
+    if let Some((number, _)) = lines.get(next) {
+        return Err(format!("line {}: 'end' without 'foreach'", number));
+    }
+    Ok(script)
+}
+
+/// Parse steps from `lines[*next..]` up to an `end` line or the end of the
+/// script. Hooks are only allowed at the top level, where `hooks` is given.
+fn parse_block(
+    lines: &[(usize, &str)],
+    next: &mut usize,
+    mut hooks: Option<&mut Vec<Hook>>,
+) -> Result<Vec<Step>, String> {
+    let mut steps = Vec::new();
+
+    while let Some(&(number, text)) = lines.get(*next) {
+        let at = |e: String| format!("line {}: {}", number, e);
+        let mut args = split_words(text).map_err(at)?;
+
+        match args[0].as_str() {
+            "end" => return Ok(steps),
+            "on" => {
+                let hooks = match hooks {
+                    Some(ref mut hooks) => hooks,
+                    None => return Err(at("hooks must be declared outside foreach".to_string())),
+                };
+                if args.len() < 3 {
+                    return Err(at(format!(
+                        "expected 'on <{}> <command>'",
+                        EVENTS.join("|")
+                    )));
+                }
+                let event = args[1].trim_end_matches(':').to_string();
+                if !EVENTS.contains(&event.as_str()) {
+                    return Err(at(format!(
+                        "unknown event '{}' (expected {})",
+                        event,
+                        EVENTS.join(", ")
+                    )));
+                }
+                hooks.push(Hook {
+                    event,
+                    line: sub_line(number, &args.split_off(2)),
+                });
+            }
+            "if" => steps.push(parse_if(number, &args).map_err(at)?),
+            "set" => steps.push(parse_set(number, &args).map_err(at)?),
+            "foreach" => {
+                if args.len() < 3 || args[2] != "do" {
+                    return Err(at("expected 'foreach <selector> do'".to_string()));
+                }
+                let line = sub_line(number, &args[..3]);
+                let selector = args[1].clone();
+                let body = if args.len() > 3 {
+                    // One-line body: commands separated by ';'
+                    let inline = text.split_once(" do ").map(|(_, body)| body).unwrap_or("");
+                    inline
+                        .split(';')
+                        .map(str::trim)
+                        .filter(|c| !c.is_empty())
+                        .map(|c| {
+                            let args = split_words(c).map_err(at)?;
+                            Ok(Step::Run(sub_line(number, &args)))
+                        })
+                        .collect::<Result<Vec<_>, String>>()?
+                } else {
+                    *next += 1;
+                    let body = parse_block(lines, next, None)?;
+                    if *next >= lines.len() {
+                        return Err(at("'foreach' without 'end'".to_string()));
+                    }
+                    body
+                };
+                steps.push(Step::ForEach {
+                    line,
+                    selector,
+                    body,
+                });
+            }
+            _ => steps.push(Step::Run(Line {
+                number,
+                text: text.to_string(),
+                args,
+            })),
+        }
+        *next += 1;
+    }
+
+    Ok(steps)
+}
+
+/// Run one command line through this binary on `session`, returning its
+/// JSON response
+pub fn exec(args: &[String], session: &str) -> Value {
+    let exe = env::current_exe().unwrap_or_else(|_| PathBuf::from("agentbrowser-pro"));
+    let output = Command::new(&exe)
+        .arg("--json")
+        .arg(format!("--session={}", session))
+        .args(args)
+        .stdin(Stdio::null())
+        .output();
+    match output {
+        Ok(out) => serde_json::from_slice(&out.stdout).unwrap_or_else(|_| {
+            json!({
+                "success": false,
+                "error": String::from_utf8_lossy(&out.stderr).trim().to_string(),
+            })
+        }),
+        Err(e) => json!({"success": false, "error": e.to_string()}),
+    }
+}
+
+/// The command a step sends when it is nothing more than a daemon command:
+/// a registry command the CLI leaves alone, with only flags of its own
+fn daemon_command(args: &[String], flags: &Flags) -> Option<(&'static str, CommandJson)> {
+    // Artifacts are recorded by the CLI
+    if flags.run_dir.is_some() {
+        return None;
+    }
+    let (flag_args, clean) = flags::split_args(&flags::normalize_args(args));
+    let spec = find_command(clean.first()?)?;
+    let own = |arg: &String| {
+        let name = arg.split('=').next().unwrap_or(arg);
+        spec.accepts(name) && !CLI_FLAGS.contains(&name) && !PAGING_FLAGS.contains(&name)
+    };
+    if !flag_args.iter().all(own) {
+        return None;
+    }
+    let mut step = Flags::parse(&flag_args);
+    step.session = flags.session.clone();
+    step.default_timeout = flags.default_timeout;
+    // A parse error is reported the way the CLI words it
+    let cmd = parse_command(&clean, &step).ok()?;
+    let local = CLI_ACTIONS.contains(&cmd.action.as_str())
+        || CLI_ACTION_PREFIXES
+            .iter()
+            .any(|prefix| cmd.action.starts_with(prefix));
+    (!local).then_some((spec.name, cmd))
+}
+
+/// Runs a script's steps in order on one session, stopping at the first
+/// failure unless `--continue-on-error` is set
+pub struct Runner<'a> {
+    pub session: &'a str,
+    /// Print each step as it finishes
+    pub verbose: bool,
+}
+
+/// State of one run of a script
+struct Run<'a> {
+    runner: &'a Runner<'a>,
+    /// The run's flags, on the runner's session
+    flags: Flags,
+    /// Open daemon connection, reopened after a failed send
+    connection: Option<Connection>,
+    /// Config `on_result` hooks, applied to steps sent directly
+    on_result: ResultHooks,
+    hooks: &'a [Hook],
+    /// Last lifecycle event seen, when there are hooks to fire
+    cursor: Option<u64>,
+    vars: Vars,
+    steps: Vec<Value>,
+    /// Keep going after a failed step
+    keep_going: bool,
+}
+
+impl Run<'_> {
+    /// Run one command line, over the connection when it is a plain daemon
+    /// command, returning its JSON response
+    fn command(&mut self, args: &[String]) -> Value {
+        let Some((name, mut cmd)) = daemon_command(args, &self.flags) else {
+            return exec(args, self.runner.session);
+        };
+        cmd.id = (self.steps.len() + 1).to_string();
+        let mut resp = match self.send(&cmd) {
+            Ok(resp) => resp,
+            Err(e) => Response::err(&cmd.id, e, Some("connection_failed")),
+        };
+        let session = self.runner.session;
+        if resp.success {
+            if cmd.action == "snapshot" {
+                if let Some(ref result) = resp.result {
+                    refs::save(session, result);
+                }
+            }
+            let finished = plugins::transform(name, &cmd.action, &mut resp, &self.flags)
+                .and_then(|_| hooks::apply(&self.on_result, name, &cmd.action, &mut resp, session));
+            if let Err(e) = finished {
+                resp = Response::err(&cmd.id, e, None);
+            }
+        } else if let Some(ref selector) = cmd.selector {
+            refs::enrich_not_found(&mut resp, selector, session);
+        }
+        serde_json::to_value(&resp).unwrap_or_default()
+    }
+
+    /// Send a command over the run's connection, opening it (and starting
+    /// the daemon) if there isn't one. A failed send drops the connection
+    /// rather than retrying a command that may already have run.
+    fn send(&mut self, cmd: &CommandJson) -> Result<Response, String> {
+        if self.connection.is_none() {
+            ensure_daemon(
+                &self.flags.session,
+                self.flags.headed,
+                self.flags.executable_path.as_deref(),
+                &self.flags.extensions,
+            )?;
+            self.connection = Some(Connection::open(&self.flags.session)?);
+        }
+        let connection = self.connection.as_mut().expect("opened above");
+        let sent = connection.send(&cmd.to_json(), cmd.timeout);
+        if sent.is_err() {
+            self.connection = None;
+        }
+        sent
+    }
+
+    /// Lifecycle events after `cursor`, advancing it
+    fn poll_events(&mut self, cursor: &mut u64) -> Vec<Value> {
+        let mut cmd = CommandJson::new("getEvents");
+        cmd.since = Some(*cursor);
+        let result = match self.send(&cmd).ok().and_then(|r| r.result) {
+            Some(result) => result,
+            None => return Vec::new(),
+        };
+        *cursor = result["next"].as_u64().unwrap_or(*cursor);
+        result["events"].as_array().cloned().unwrap_or_default()
+    }
+
+    fn report(&self, number: usize, text: &str, hook: Option<&str>, response: &Value) {
+        if !self.runner.verbose {
+            return;
+        }
+        let ok = response["success"].as_bool().unwrap_or(false);
+        let mark = if ok {
+            "\x1b[32m✓\x1b[0m"
+        } else {
+            "\x1b[31m✗\x1b[0m"
+        };
+        let prefix = hook.map(|e| format!("  ↳ on {}: ", e)).unwrap_or_default();
+        println!("{} \x1b[90m{:>3}\x1b[0m {}{}", mark, number, prefix, text);
+        if !ok {
+            if let Some(error) = response["error"].as_str() {
+                for line in error.lines() {
+                    println!("      {}", line);
+                }
+            }
+        }
+    }
+
+    /// Run a step, recording it. Returns whether it passed.
+    fn step(&mut self, step: &Step) -> bool {
+        match step {
+            Step::Run(line) => self.line(line, None) && self.fire_hooks(),
+            Step::If {
+                line,
+                selector,
+                then,
+                otherwise,
+            } => {
+                let selector = interpolate(selector, &self.vars);
+                let response = self.command(&["visible".to_string(), selector]);
+                let ok = response["success"].as_bool().unwrap_or(false);
+                let visible = response["result"]["visible"].as_bool().unwrap_or(false);
+                let branch = match (ok, visible) {
+                    (false, _) => None,
+                    (true, true) => Some(("then", Some(then))),
+                    (true, false) => Some(("else", otherwise.as_ref())),
+                };
+                if !ok {
+                    self.report(line.number, &line.text, None, &response);
+                }
+                self.steps.push(json!({
+                    "line": line.number,
+                    "command": line.text,
+                    "response": response,
+                    "branch": branch.map(|(name, _)| name),
+                }));
+                match branch {
+                    Some((_, Some(chosen))) => self.line(chosen, None) && self.fire_hooks(),
+                    Some((_, None)) => true,
+                    None => false,
+                }
+            }
+            Step::ForEach {
+                line,
+                selector,
+                body,
+            } => {
+                let selector = interpolate(selector, &self.vars);
+                let response = self.command(&["count".to_string(), selector.clone()]);
+                let count = response["result"]["count"].as_u64();
+                if count.is_none() {
+                    self.report(line.number, &line.text, None, &response);
+                }
+                self.steps.push(json!({
+                    "line": line.number,
+                    "command": line.text,
+                    "response": response,
+                    "count": count,
+                }));
+                let count = match count {
+                    Some(count) => count,
+                    None => return false,
+                };
+
+                let saved = (
+                    self.vars.get("item").cloned(),
+                    self.vars.get("index").cloned(),
+                );
+                let mut passed = true;
+                'items: for i in 0..count {
+                    self.vars
+                        .insert("item".to_string(), format!("{} >> nth={}", selector, i));
+                    self.vars.insert("index".to_string(), i.to_string());
+                    for step in body {
+                        if !self.step(step) {
+                            passed = false;
+                            if !self.keep_going {
+                                break 'items;
+                            }
+                        }
+                    }
+                }
+                // Restore an enclosing loop's bindings
+                for (name, value) in [("item", saved.0), ("index", saved.1)] {
+                    match value {
+                        Some(value) => self.vars.insert(name.to_string(), value),
+                        None => self.vars.remove(name),
+                    };
+                }
+                passed
+            }
+            Step::Set { name, command } => {
+                if !self.line(command, None) {
+                    return false;
+                }
+                let entry = self.steps.last_mut().expect("line() records a step");
+                match result_value(&entry["response"]["result"]) {
+                    Some(value) => {
+                        entry["set"] = json!({ name.as_str(): value });
+                        self.vars.insert(name.clone(), value);
+                        self.fire_hooks()
+                    }
+                    None => {
+                        let error = format!("set {}: '{}' returned no value", name, command.text);
+                        entry["response"]["success"] = json!(false);
+                        entry["response"]["error"] = json!(error);
+                        if self.runner.verbose {
+                            println!("      {}", error);
+                        }
+                        false
+                    }
+                }
+            }
+        }
+    }
+
+    /// Run a single line, recording it. Returns whether it passed.
+    fn line(&mut self, line: &Line, hook: Option<&str>) -> bool {
+        let args: Vec<String> = line
+            .args
+            .iter()
+            .map(|a| interpolate(a, &self.vars))
+            .collect();
+        let text = if args == line.args {
+            line.text.clone()
+        } else {
+            args.join(" ")
+        };
+        let expectation = args.len() > 1 && args[0] == "expect" && args[1] == "json";
+        let response = if expectation {
+            self.expect_json(&args[2..])
+        } else {
+            self.command(&args)
+        };
+        self.report(line.number, &text, hook, &response);
+        let ok = response["success"].as_bool().unwrap_or(false);
+        let mut entry = json!({"line": line.number, "command": text, "response": response});
+        if let Some(event) = hook {
+            entry["hook"] = json!(event);
+        }
+        if expectation {
+            entry["expectation"] = json!(true);
+        }
+        self.steps.push(entry);
+        ok
+    }
+
+    /// Check `expect json` against the result of the last command before
+    /// it, so several expectations can follow one command
+    fn expect_json(&self, args: &[String]) -> Value {
+        let expectation = match JsonExpectation::parse(args) {
+            Ok(expectation) => expectation,
+            Err(e) => return json!({"success": false, "error": e.format()}),
+        };
+        let previous = self.steps.iter().rev().find(|s| s["expectation"] != true);
+        let previous = match previous {
+            Some(step) => &step["response"]["result"],
+            None => {
+                return json!({"success": false, "error": "expect json: there is no previous step to check"})
+            }
+        };
+        match expectation.check(previous) {
+            Ok(result) => json!({"success": true, "result": result}),
+            Err(e) => json!({"success": false, "error": e}),
+        }
+    }
+
+    /// Run the hooks for events since the last step. Returns whether they
+    /// all passed.
+    fn fire_hooks(&mut self) -> bool {
+        let mut cursor = match self.cursor {
+            Some(cursor) => cursor,
+            None => return true,
+        };
+        let hooks = self.hooks;
+        for event in self.poll_events(&mut cursor) {
+            let kind = event["type"].as_str().unwrap_or("");
+            for hook in hooks.iter().filter(|h| h.event == kind) {
+                if !self.line(&hook.line, Some(kind)) {
+                    return false;
+                }
+            }
+        }
+        // Events raised by the hooks themselves don't re-trigger them
+        self.poll_events(&mut cursor);
+        self.cursor = Some(cursor);
+        true
+    }
+}
+
+/// The failed steps of a run, by script line
+fn failures(steps: &[Value]) -> Vec<Value> {
+    steps
+        .iter()
+        .filter(|s| s["response"]["success"] != true)
+        .map(|s| {
+            json!({
+                "line": s["line"],
+                "command": s["command"],
+                "error": s["response"]["error"],
+            })
+        })
+        .collect()
+}
+
+/// One-line summary of a failed run, naming the script lines that failed
+pub fn failure_summary(report: &Value) -> String {
+    let failures = report["failures"].as_array().cloned().unwrap_or_default();
+    match failures.as_slice() {
+        [] => t("script_failed").to_string(),
+        [only] => tf(
+            "script_line_failed",
+            &[
+                &only["line"].to_string(),
+                only["command"].as_str().unwrap_or(""),
+            ],
+        ),
+        many => tf(
+            "script_steps_failed",
+            &[
+                &many.len().to_string(),
+                &many
+                    .iter()
+                    .map(|f| f["line"].to_string())
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            ],
+        ),
+    }
+}
+
+impl Runner<'_> {
+    pub fn run(&self, script: &Script, flags: &Flags) -> Value {
+        self.run_with(script, flags, Vars::new())
+    }
+
+    /// Run a script with variables already bound, as for a row of `--data`
+    pub fn run_with(&self, script: &Script, flags: &Flags, vars: Vars) -> Value {
+        let started = Instant::now();
+
+        let mut run = Run {
+            runner: self,
+            flags: Flags {
+                session: self.session.to_string(),
+                ..flags.clone()
+            },
+            connection: None,
+            on_result: Config::load().map(|c| c.on_result).unwrap_or_default(),
+            hooks: &script.hooks,
+            cursor: None,
+            vars,
+            steps: Vec::new(),
+            keep_going: flags.has("--continue-on-error"),
+        };
+
+        // Only events from here on count
+        if !script.hooks.is_empty() {
+            let mut start = 0;
+            let ready = ensure_daemon(
+                self.session,
+                flags.headed,
+                flags.executable_path.as_deref(),
+                &flags.extensions,
+            );
+            if let Err(e) = ready {
+                return json!({"success": false, "session": self.session, "error": e, "steps": []});
+            }
+            run.poll_events(&mut start);
+            run.cursor = Some(start);
+        }
+
+        let mut success = true;
+        for step in &script.steps {
+            if !run.step(step) {
+                success = false;
+                if !run.keep_going {
+                    break;
+                }
+            }
+        }
+
+        json!({
+            "success": success,
+            "session": self.session,
+            "failures": failures(&run.steps),
+            "steps": run.steps,
+            "vars": run.vars,
+            "durationMs": started.elapsed().as_millis() as u64,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn words(line: &str) -> Vec<String> {
+        split_words(line).unwrap()
+    }
+
+    #[test]
+    fn plain_daemon_commands_skip_the_binary() {
+        let flags = Flags::parse(&[]);
+        let (name, cmd) = daemon_command(&words("click #buy --force"), &flags).unwrap();
+        assert_eq!((name, cmd.action.as_str()), ("click", "click"));
+        assert!(daemon_command(&words("navigate https://example.com"), &flags).is_some());
+    }
+
+    #[test]
+    fn local_commands_and_cli_flags_go_through_the_binary() {
+        let flags = Flags::parse(&[]);
+        for line in [
+            "textdiff #price",
+            "pool status",
+            "text main --limit=5",
+            "html --sanitized",
+            "click #buy --session=other",
+            "nosuchcommand",
+            "click",
+        ] {
+            assert!(daemon_command(&words(line), &flags).is_none(), "{}", line);
+        }
+        let mut flags = Flags::parse(&[]);
+        flags.run_dir = Some("/tmp/run".to_string());
+        assert!(daemon_command(&words("click #buy"), &flags).is_none());
+    }
+
+    /// The line numbers and text of a block's plain steps
+    fn runs(steps: &[Step]) -> Vec<(usize, &str)> {
+        steps
+            .iter()
+            .filter_map(|step| match step {
+                Step::Run(line) => Some((line.number, line.text.as_str())),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn comments_and_blank_lines_keep_source_line_numbers() {
+        let script = parse(
+            "# login flow\n\
+             \n\
+             navigate https://example.com\n\
+             \x20\x20  # indented comment\n\
+             \x20\x20fill '#user' \"Ada Lovelace\"\n\
+             \n\
+             click #submit\n",
+        )
+        .unwrap();
+        assert_eq!(
+            runs(&script.steps),
+            vec![
+                (3, "navigate https://example.com"),
+                (5, "fill '#user' \"Ada Lovelace\""),
+                (7, "click #submit"),
+            ]
+        );
+        let Step::Run(ref fill) = script.steps[1] else {
+            panic!("fill is a plain step");
+        };
+        assert_eq!(fill.args, ["fill", "#user", "Ada Lovelace"]);
+        assert!(parse("# nothing to do\n\n").unwrap().steps.is_empty());
+    }
+
+    #[test]
+    fn hooks_are_collected_apart_from_steps() {
+        let script =
+            parse("on popup: closepage\non dialog accept\nnavigate https://x.test").unwrap();
+        assert_eq!(script.steps.len(), 1);
+        let hooks: Vec<(&str, usize, &str)> = script
+            .hooks
+            .iter()
+            .map(|h| (h.event.as_str(), h.line.number, h.line.text.as_str()))
+            .collect();
+        assert_eq!(
+            hooks,
+            vec![("popup", 1, "closepage"), ("dialog", 2, "accept")]
+        );
+    }
+
+    #[test]
+    fn errors_name_the_line() {
+        let error = |source: &str| parse(source).unwrap_err();
+        assert_eq!(
+            error("navigate https://x.test\n\non tab close"),
+            "line 3: unknown event 'tab' (expected popup, dialog, download, console-error)"
+        );
+        assert!(error("# header\nfill #q \"unclosed").starts_with("line 2: "));
+        assert_eq!(error("click #a\nend"), "line 2: 'end' without 'foreach'");
+        assert_eq!(
+            error("foreach .row do\n  click $item"),
+            "line 1: 'foreach' without 'end'"
+        );
+        assert_eq!(
+            error("foreach .row do\n  on popup closepage\nend"),
+            "line 2: hooks must be declared outside foreach"
+        );
+        assert_eq!(
+            error("\nif visible .x click .y"),
+            format!("line 2: expected '{}'", IF_USAGE)
+        );
+        assert_eq!(
+            error("set 1ST = title"),
+            "line 1: invalid variable name '1ST' (letters, digits and _)"
+        );
+        assert_eq!(
+            error("set NAME title"),
+            "line 1: expected 'set NAME = <command>'"
+        );
+    }
+
+    #[test]
+    fn if_takes_a_then_and_an_optional_else() {
+        let Step::If {
+            selector,
+            then,
+            otherwise,
+            ..
+        } = parse_if(
+            4,
+            &words("if visible '#login' then fill #user me else snapshot"),
+        )
+        .unwrap()
+        else {
+            panic!("expected an if step");
+        };
+        assert_eq!(selector, "#login");
+        assert_eq!((then.number, then.args), (4, words("fill #user me")));
+        assert_eq!(otherwise.map(|l| l.args), Some(words("snapshot")));
+
+        let Ok(Step::If { otherwise, .. }) = parse_if(1, &words("if visible .a then click .b"))
+        else {
+            panic!("expected an if step");
+        };
+        assert!(otherwise.is_none());
+        for line in [
+            "if visible .a then else click .b",
+            "if visible .a then click .b else",
+            "if hidden .a then click .b",
+            "if visible .a click .b now",
+        ] {
+            assert!(parse_if(1, &words(line)).is_err(), "{}", line);
+        }
+    }
+
+    #[test]
+    fn foreach_bodies_nest_or_sit_on_one_line() {
+        let script = parse(
+            "foreach .row do\n\
+             \x20 click $item\n\
+             \x20 foreach .cell do\n\
+             \x20   text $item\n\
+             \x20 end\n\
+             \x20 back\n\
+             end\n\
+             foreach .tab do click $item; screenshot; \n\
+             title",
+        )
+        .unwrap();
+        assert_eq!(script.steps.len(), 3);
+        let Step::ForEach {
+            ref selector,
+            ref body,
+            ..
+        } = script.steps[0]
+        else {
+            panic!("expected foreach");
+        };
+        assert_eq!(selector, ".row");
+        assert_eq!(runs(body), vec![(2, "click $item"), (6, "back")]);
+        let Step::ForEach {
+            body: ref inner, ..
+        } = body[1]
+        else {
+            panic!("expected a nested foreach");
+        };
+        assert_eq!(runs(inner), vec![(4, "text $item")]);
+
+        let Step::ForEach { ref body, .. } = script.steps[1] else {
+            panic!("expected one-line foreach");
+        };
+        assert_eq!(runs(body), vec![(8, "click $item"), (8, "screenshot")]);
+        assert_eq!(runs(&script.steps[2..]), vec![(9, "title")]);
+    }
+
+    #[test]
+    fn set_binds_a_command() {
+        let Step::Set { name, command } =
+            parse_set(3, &words("set ORDER_ID = text .order-id")).unwrap()
+        else {
+            panic!("expected set");
+        };
+        assert_eq!(name, "ORDER_ID");
+        assert_eq!((command.number, command.args), (3, words("text .order-id")));
+    }
+
+    #[test]
+    fn set_stores_the_command_value() {
+        assert_eq!(
+            result_value(&json!({"text": "A-100"})),
+            Some("A-100".to_string())
+        );
+        assert_eq!(
+            result_value(&json!({"url": "https://x.test", "title": "X"})),
+            Some("X".to_string())
+        );
+        assert_eq!(result_value(&json!({"count": 3})), Some("3".to_string()));
+        assert_eq!(result_value(&json!(true)), Some("true".to_string()));
+        assert_eq!(result_value(&json!({"text": null})), None);
+        assert_eq!(result_value(&json!({"a": 1, "b": 2})), None);
+    }
+
+    #[test]
+    fn interpolation_expands_known_names_only() {
+        let vars: Vars = [("ID", "42"), ("host", "example.com")]
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        assert_eq!(interpolate("/orders/$ID", &vars), "/orders/42");
+        assert_eq!(interpolate("${ID}th", &vars), "42th");
+        assert_eq!(interpolate("$IDth", &vars), "$IDth");
+        assert_eq!(
+            interpolate("https://$host/$ID.json", &vars),
+            "https://example.com/42.json"
+        );
+        assert_eq!(interpolate("$PRICE and $$ID", &vars), "$PRICE and $42");
+        assert_eq!(interpolate("${ID", &vars), "${ID");
+        assert_eq!(interpolate("${}", &vars), "${}");
+        assert_eq!(interpolate("cost: $", &vars), "cost: $");
+    }
+}
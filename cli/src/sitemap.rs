@@ -0,0 +1,309 @@
+/*!
+ * robots.txt and Sitemaps
+ *
+ * `crawl --seed-from-sitemap` reads the site's robots.txt for `Sitemap:`
+ * entries, falls back to `/sitemap.xml`, and expands sitemap indexes into
+ * the page URLs they list. Files are fetched with curl rather than the
+ * browser, so seeding doesn't disturb the session's page.
+ *
+ * Of robots.txt's groups, the ones naming `agentbrowser-pro` apply, or
+ * failing that the `*` ones; their `Crawl-delay` is waited between pages
+ * and their `Allow`/`Disallow` path prefixes decide which pages are
+ * crawled, the longest matching prefix winning and `Allow` winning a tie.
+ */
+use std::process::Command;
+
+use serde::{Deserialize, Serialize};
+
+/// Sitemaps followed from one index, so a broken site can't loop forever
+const MAX_SITEMAPS: usize = 50;
+
+/// The user agent robots.txt groups are matched against
+const AGENT: &str = "agentbrowser-pro";
+
+/// An `Allow` or `Disallow` line
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Rule {
+    pub allow: bool,
+    pub path: String,
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Robots {
+    pub sitemaps: Vec<String>,
+    /// Seconds between requests asked of this crawler
+    pub crawl_delay: Option<f64>,
+    pub rules: Vec<Rule>,
+}
+
+impl Robots {
+    /// Whether the rules let `url` be crawled
+    pub fn allows(&self, url: &str) -> bool {
+        let path = path_of(url);
+        self.rules
+            .iter()
+            .filter(|rule| !rule.path.is_empty() && path.starts_with(&rule.path))
+            .max_by_key(|rule| (rule.path.len(), rule.allow))
+            .is_none_or(|rule| rule.allow)
+    }
+}
+
+/// Path and query of a URL, which robots.txt rules are matched against
+fn path_of(url: &str) -> &str {
+    let url = url.split('#').next().unwrap_or(url);
+    let rest = url.split_once("://").map(|(_, rest)| rest).unwrap_or(url);
+    match rest.find(['/', '?']) {
+        Some(start) if rest[start..].starts_with('/') => &rest[start..],
+        _ => "/",
+    }
+}
+
+/// Fetch a URL's body, or None if it can't be had
+fn fetch(url: &str) -> Option<String> {
+    let output = Command::new("curl")
+        .args(["-fsSL", "--compressed", "--max-time", "30"])
+        .arg(url)
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Read `<origin>/robots.txt`; a missing file is an empty one
+pub fn robots(origin: &str) -> Robots {
+    fetch(&format!("{}/robots.txt", origin))
+        .map(|content| parse_robots(&content))
+        .unwrap_or_default()
+}
+
+/// The User-agent lines of a robots.txt group and the lines under them
+#[derive(Default)]
+struct Group {
+    agents: Vec<String>,
+    crawl_delay: Option<f64>,
+    rules: Vec<Rule>,
+}
+
+fn parse_robots(content: &str) -> Robots {
+    let mut robots = Robots::default();
+    let mut groups: Vec<Group> = Vec::new();
+    let mut in_rules = false;
+    for line in content.lines() {
+        let line = line.split('#').next().unwrap_or("").trim();
+        let (key, value) = match line.split_once(':') {
+            Some((key, value)) => (key.trim().to_lowercase(), value.trim()),
+            None => continue,
+        };
+        if key == "sitemap" {
+            robots.sitemaps.push(value.to_string());
+            continue;
+        }
+        if key == "user-agent" {
+            // Consecutive User-agent lines share the rules below them
+            if in_rules || groups.is_empty() {
+                groups.push(Group::default());
+                in_rules = false;
+            }
+            if let Some(group) = groups.last_mut() {
+                group.agents.push(value.to_lowercase());
+            }
+            continue;
+        }
+        in_rules = true;
+        let Some(group) = groups.last_mut() else {
+            continue;
+        };
+        match key.as_str() {
+            "crawl-delay" => group.crawl_delay = value.parse().ok(),
+            "allow" | "disallow" => group.rules.push(Rule {
+                allow: key == "allow",
+                path: value.to_string(),
+            }),
+            _ => {}
+        }
+    }
+
+    let named = |agent: &str| {
+        groups
+            .iter()
+            .filter(move |g| g.agents.iter().any(|a| a == agent))
+            .collect::<Vec<_>>()
+    };
+    let mut ours = named(AGENT);
+    if ours.is_empty() {
+        ours = named("*");
+    }
+    robots.crawl_delay = ours.iter().find_map(|g| g.crawl_delay);
+    robots.rules = ours.iter().flat_map(|g| g.rules.clone()).collect();
+    robots
+}
+
+/// Text of every `<loc>` element in a sitemap
+fn locs(xml: &str) -> Vec<String> {
+    xml.split("<loc>")
+        .skip(1)
+        .filter_map(|part| part.split("</loc>").next())
+        .map(|loc| {
+            loc.trim()
+                .trim_start_matches("<![CDATA[")
+                .trim_end_matches("]]>")
+                .replace("&amp;", "&")
+                .replace("&apos;", "'")
+                .replace("&quot;", "\"")
+                .replace("&lt;", "<")
+                .replace("&gt;", ">")
+        })
+        .collect()
+}
+
+/// Page URLs listed by the given sitemaps, following sitemap indexes, up to
+/// `limit` URLs
+pub fn urls(sitemaps: &[String], limit: usize) -> Vec<String> {
+    collect(sitemaps, limit, fetch)
+}
+
+fn collect(
+    sitemaps: &[String],
+    limit: usize,
+    fetch: impl Fn(&str) -> Option<String>,
+) -> Vec<String> {
+    let mut pending: Vec<String> = sitemaps.iter().rev().cloned().collect();
+    let mut fetched = 0;
+    let mut pages = Vec::new();
+
+    while let Some(sitemap) = pending.pop() {
+        if fetched >= MAX_SITEMAPS || pages.len() >= limit {
+            break;
+        }
+        fetched += 1;
+        let xml = match fetch(&sitemap) {
+            Some(xml) => xml,
+            None => continue,
+        };
+        if xml.contains("<sitemapindex") {
+            pending.extend(locs(&xml).into_iter().rev());
+        } else {
+            pages.extend(locs(&xml));
+        }
+    }
+    pages.truncate(limit);
+    pages
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn urlset(pages: &[&str]) -> String {
+        let urls: String = pages
+            .iter()
+            .map(|p| format!("<url><loc>{}</loc></url>", p))
+            .collect();
+        format!("<urlset>{}</urlset>", urls)
+    }
+
+    fn index(sitemaps: &[&str]) -> String {
+        let entries: String = sitemaps
+            .iter()
+            .map(|s| format!("<sitemap><loc>{}</loc></sitemap>", s))
+            .collect();
+        format!("<sitemapindex>{}</sitemapindex>", entries)
+    }
+
+    #[test]
+    fn indexes_are_followed_in_order() {
+        let files: HashMap<&str, String> = HashMap::from([
+            (
+                "https://x.test/sitemap.xml",
+                index(&["https://x.test/a.xml", "https://x.test/b.xml"]),
+            ),
+            ("https://x.test/a.xml", index(&["https://x.test/a1.xml"])),
+            (
+                "https://x.test/a1.xml",
+                urlset(&["https://x.test/1", "https://x.test/2?a=1&amp;b=2"]),
+            ),
+            ("https://x.test/b.xml", urlset(&["https://x.test/3"])),
+        ]);
+        let fetch = |url: &str| files.get(url).cloned();
+        let root = ["https://x.test/sitemap.xml".to_string()];
+        assert_eq!(
+            collect(&root, 10, fetch),
+            [
+                "https://x.test/1",
+                "https://x.test/2?a=1&b=2",
+                "https://x.test/3"
+            ]
+        );
+        assert_eq!(collect(&root, 2, fetch).len(), 2);
+    }
+
+    #[test]
+    fn index_loops_stop() {
+        let looped = index(&["https://x.test/sitemap.xml", "https://x.test/missing.xml"]);
+        let fetched = std::cell::Cell::new(0);
+        let fetch = |url: &str| {
+            fetched.set(fetched.get() + 1);
+            (url == "https://x.test/sitemap.xml").then(|| looped.clone())
+        };
+        assert!(collect(&["https://x.test/sitemap.xml".to_string()], 10, fetch).is_empty());
+        assert_eq!(fetched.get(), MAX_SITEMAPS);
+    }
+
+    #[test]
+    fn disallow_matches_path_prefixes() {
+        let robots = parse_robots(
+            "User-agent: *\n\
+             Disallow: /admin\n\
+             Disallow: /search?\n\
+             Allow: /admin/public\n\
+             Disallow:\n",
+        );
+        assert!(!robots.allows("https://x.test/admin"));
+        assert!(!robots.allows("https://x.test/admin/users?id=1"));
+        assert!(!robots.allows("https://x.test/administrators"));
+        assert!(robots.allows("https://x.test/admin/public/logo.png"));
+        assert!(!robots.allows("https://x.test/search?q=shoes"));
+        assert!(robots.allows("https://x.test/search/help"));
+        assert!(robots.allows("https://x.test/"));
+        assert!(robots.allows("https://x.test"));
+        assert!(robots.allows("https://x.test/#admin"));
+        // Allow wins a tie
+        let tie = parse_robots("User-agent: *\nDisallow: /page\nAllow: /page\n");
+        assert!(tie.allows("https://x.test/page"));
+    }
+
+    #[test]
+    fn our_group_wins_over_the_wildcard() {
+        let content = "Sitemap: https://x.test/sitemap.xml\n\
+                       User-agent: *\n\
+                       Crawl-delay: 10\n\
+                       Disallow: /\n\
+                       \n\
+                       User-agent: Googlebot\n\
+                       User-agent: AgentBrowser-Pro\n\
+                       Crawl-delay: 2 # seconds\n\
+                       Disallow: /private\n";
+        let robots = parse_robots(content);
+        assert_eq!(robots.sitemaps, ["https://x.test/sitemap.xml"]);
+        assert_eq!(robots.crawl_delay, Some(2.0));
+        assert!(robots.allows("https://x.test/products"));
+        assert!(!robots.allows("https://x.test/private/a"));
+
+        // Without a group of ours, the wildcard group applies
+        let robots = parse_robots(
+            "User-agent: Googlebot\nDisallow: /\n\nUser-agent: *\nCrawl-delay: 5\nDisallow: /tmp\n",
+        );
+        assert_eq!(robots.crawl_delay, Some(5.0));
+        assert!(robots.allows("https://x.test/"));
+        assert!(!robots.allows("https://x.test/tmp/x"));
+
+        // Only other crawlers named: nothing applies
+        let robots = parse_robots("User-agent: Googlebot\nCrawl-delay: 9\nDisallow: /\n");
+        assert_eq!(robots.crawl_delay, None);
+        assert!(robots.allows("https://x.test/anything"));
+    }
+}
@@ -0,0 +1,262 @@
+/*!
+ * Storage-State Files
+ *
+ * `state load` and pool templates hand the daemon a Playwright
+ * storageState file: cookies plus each origin's localStorage. A file that
+ * was hand-edited or written by another tool fails deep inside Playwright
+ * with an unhelpful message, or worse, loads half its cookies, so the CLI
+ * checks the shape first and names every field that's wrong:
+ *
+ * ```json
+ * {
+ *   "cookies": [{"name", "value", "domain", "path", "expires",
+ *                "httpOnly", "secure", "sameSite": "Strict|Lax|None"}],
+ *   "origins": [{"origin", "localStorage": [{"name", "value"}]}]
+ * }
+ * ```
+ */
+use serde_json::{Map, Value};
+
+/// Problems listed before the rest are summarized
+const SHOWN_PROBLEMS: usize = 10;
+
+const SAME_SITE: &[&str] = &["Strict", "Lax", "None"];
+
+/// Check `data` is a storageState document, naming each problem if not
+pub fn validate(data: &[u8], file: &str) -> Result<(), String> {
+    let state: Value =
+        serde_json::from_slice(data).map_err(|e| format!("{} is not valid JSON: {}", file, e))?;
+    let mut problems = Vec::new();
+    match state.as_object() {
+        Some(state) => check_state(state, &mut problems),
+        None => problems.push("expected an object with cookies and origins".to_string()),
+    }
+    if problems.is_empty() {
+        return Ok(());
+    }
+
+    let mut message = format!("{} is not a storage-state file:", file);
+    for problem in problems.iter().take(SHOWN_PROBLEMS) {
+        message.push_str("\n  ");
+        message.push_str(problem);
+    }
+    if problems.len() > SHOWN_PROBLEMS {
+        message.push_str(&format!("\n  ... {} more", problems.len() - SHOWN_PROBLEMS));
+    }
+    Err(message)
+}
+
+fn check_state(state: &Map<String, Value>, problems: &mut Vec<String>) {
+    if !state.contains_key("cookies") && !state.contains_key("origins") {
+        problems.push("expected cookies or origins".to_string());
+    }
+    for (i, cookie) in items(state, "cookies", problems).iter().enumerate() {
+        let at = format!("cookies[{}]", i);
+        let Some(cookie) = object(cookie, &at, problems) else {
+            continue;
+        };
+        for field in ["name", "value"] {
+            require(cookie, &at, field, Value::is_string, "a string", problems);
+        }
+        // Playwright accepts either a url or a domain and path
+        if !cookie.contains_key("url") {
+            require(
+                cookie,
+                &at,
+                "domain",
+                Value::is_string,
+                "a string",
+                problems,
+            );
+        }
+        for (field, is, expected) in [
+            ("url", Value::is_string as fn(&Value) -> bool, "a string"),
+            ("path", Value::is_string, "a string"),
+            (
+                "expires",
+                Value::is_number,
+                "a number (-1 for a session cookie)",
+            ),
+            ("httpOnly", Value::is_boolean, "true or false"),
+            ("secure", Value::is_boolean, "true or false"),
+        ] {
+            if cookie.contains_key(field) {
+                require(cookie, &at, field, is, expected, problems);
+            }
+        }
+        if let Some(same_site) = cookie.get("sameSite") {
+            if !same_site.as_str().is_some_and(|s| SAME_SITE.contains(&s)) {
+                problems.push(format!(
+                    "{}.sameSite: expected \"Strict\", \"Lax\" or \"None\", got {}",
+                    at,
+                    kind(same_site)
+                ));
+            }
+        }
+    }
+
+    for (i, origin) in items(state, "origins", problems).iter().enumerate() {
+        let at = format!("origins[{}]", i);
+        let Some(origin) = object(origin, &at, problems) else {
+            continue;
+        };
+        let is_origin = |v: &Value| {
+            v.as_str()
+                .is_some_and(|s| s.starts_with("http://") || s.starts_with("https://"))
+        };
+        require(
+            origin,
+            &at,
+            "origin",
+            is_origin,
+            "an http(s) origin",
+            problems,
+        );
+        for (j, entry) in items(origin, "localStorage", problems).iter().enumerate() {
+            let at = format!("{}.localStorage[{}]", at, j);
+            if let Some(entry) = object(entry, &at, problems) {
+                for field in ["name", "value"] {
+                    require(entry, &at, field, Value::is_string, "a string", problems);
+                }
+            }
+        }
+    }
+}
+
+/// The array in `field`, or none (noting it) if it's something else
+fn items<'a>(
+    parent: &'a Map<String, Value>,
+    field: &str,
+    problems: &mut Vec<String>,
+) -> &'a [Value] {
+    match parent.get(field) {
+        None => &[],
+        Some(Value::Array(items)) => items,
+        Some(other) => {
+            problems.push(format!("{}: expected an array, got {}", field, kind(other)));
+            &[]
+        }
+    }
+}
+
+fn object<'a>(
+    value: &'a Value,
+    at: &str,
+    problems: &mut Vec<String>,
+) -> Option<&'a Map<String, Value>> {
+    let object = value.as_object();
+    if object.is_none() {
+        problems.push(format!("{}: expected an object, got {}", at, kind(value)));
+    }
+    object
+}
+
+fn require(
+    parent: &Map<String, Value>,
+    at: &str,
+    field: &str,
+    is: impl Fn(&Value) -> bool,
+    expected: &str,
+    problems: &mut Vec<String>,
+) {
+    match parent.get(field) {
+        None => problems.push(format!("{}.{}: missing", at, field)),
+        Some(value) if !is(value) => problems.push(format!(
+            "{}.{}: expected {}, got {}",
+            at,
+            field,
+            expected,
+            kind(value)
+        )),
+        Some(_) => {}
+    }
+}
+
+/// How a value is described in a problem
+fn kind(value: &Value) -> String {
+    match value {
+        Value::Null => "null".to_string(),
+        Value::Bool(_) => "a boolean".to_string(),
+        Value::Number(n) => format!("the number {}", n),
+        Value::String(s) => format!("{:?}", s),
+        Value::Array(_) => "an array".to_string(),
+        Value::Object(_) => "an object".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accepts_playwright_state() {
+        let state = br#"{
+            "cookies": [
+                {"name": "sid", "value": "abc", "domain": ".example.com", "path": "/",
+                 "expires": -1, "httpOnly": true, "secure": true, "sameSite": "Lax"},
+                {"name": "theme", "value": "dark", "url": "https://example.com"}
+            ],
+            "origins": [
+                {"origin": "https://example.com",
+                 "localStorage": [{"name": "token", "value": "xyz"}]}
+            ]
+        }"#;
+        assert_eq!(validate(state, "state.json"), Ok(()));
+        assert_eq!(validate(br#"{"cookies": []}"#, "state.json"), Ok(()));
+    }
+
+    #[test]
+    fn names_every_bad_field() {
+        let state = br#"{
+            "cookies": [
+                {"value": "abc", "domain": "example.com", "expires": "never",
+                 "sameSite": "strict"},
+                "sid=abc"
+            ],
+            "origins": [
+                {"origin": "example.com", "localStorage": [{"name": "token", "value": 1}]}
+            ]
+        }"#;
+        assert_eq!(
+            validate(state, "state.json").unwrap_err(),
+            "state.json is not a storage-state file:\n  \
+             cookies[0].name: missing\n  \
+             cookies[0].expires: expected a number (-1 for a session cookie), got \"never\"\n  \
+             cookies[0].sameSite: expected \"Strict\", \"Lax\" or \"None\", got \"strict\"\n  \
+             cookies[1]: expected an object, got \"sid=abc\"\n  \
+             origins[0].origin: expected an http(s) origin, got \"example.com\"\n  \
+             origins[0].localStorage[0].value: expected a string, got the number 1"
+        );
+    }
+
+    #[test]
+    fn refuses_other_documents() {
+        assert!(validate(b"{cookies", "state.json")
+            .unwrap_err()
+            .starts_with("state.json is not valid JSON: "));
+        assert_eq!(
+            validate(b"[]", "state.json").unwrap_err(),
+            "state.json is not a storage-state file:\n  \
+             expected an object with cookies and origins"
+        );
+        assert_eq!(
+            validate(br#"{"name": "sid"}"#, "state.json").unwrap_err(),
+            "state.json is not a storage-state file:\n  expected cookies or origins"
+        );
+        assert_eq!(
+            validate(br#"{"cookies": {}}"#, "state.json").unwrap_err(),
+            "state.json is not a storage-state file:\n  \
+             cookies: expected an array, got an object"
+        );
+    }
+
+    #[test]
+    fn long_problem_lists_are_summarized() {
+        let cookies = vec![serde_json::json!({"name": "a"}); 6];
+        let state = serde_json::json!({ "cookies": cookies }).to_string();
+        let message = validate(state.as_bytes(), "state.json").unwrap_err();
+        // Each cookie lacks its value and domain
+        assert_eq!(message.lines().count(), 1 + SHOWN_PROBLEMS + 1);
+        assert!(message.ends_with("\n  ... 2 more"));
+    }
+}
@@ -0,0 +1,427 @@
+/*!
+ * Element Text Diffs
+ *
+ * `textdiff <selector>` reads an element's text and compares it word by
+ * word (or `--by=char`) with a baseline, so a changed price or a reworded
+ * clause in a terms-of-service page shows up as exactly the words that
+ * changed rather than as a whole changed line. The baseline is either the
+ * text seen the last time the same element on the same page was diffed
+ * (`--against=last`, the default, kept under `~/.agentbrowser-pro/textdiff/`)
+ * or a text file. `last` moves forward on every run; a file is only
+ * rewritten with `--update`, or created if it doesn't exist yet.
+ *
+ * Like `pagediff compare`, it exits 1 when the text changed.
+ */
+use std::fs;
+use std::path::PathBuf;
+
+use serde_json::{json, Value};
+
+use crate::artifacts::sha256_hex;
+use crate::commands::CommandJson;
+use crate::config::data_dir;
+use crate::connection::{ensure_daemon, send_command};
+use crate::flags::Flags;
+
+/// Larger diffs report the whole differing middle as one change instead of
+/// aligning it token by token
+const MAX_LCS_CELLS: usize = 4_000_000;
+
+/// Changes shown before summarizing the rest
+const SHOWN_CHANGES: usize = 20;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Unit {
+    Word,
+    Char,
+}
+
+impl Unit {
+    pub fn parse(unit: &str) -> Option<Unit> {
+        match unit {
+            "word" | "words" => Some(Unit::Word),
+            "char" | "chars" => Some(Unit::Char),
+            _ => None,
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Unit::Word => "word",
+            Unit::Char => "char",
+        }
+    }
+
+    /// Unchanged tokens shown on each side of a change
+    fn context(self) -> usize {
+        match self {
+            Unit::Word => 5,
+            Unit::Char => 24,
+        }
+    }
+
+    fn tokens(self, text: &str) -> Vec<String> {
+        let words = text.split_whitespace();
+        match self {
+            Unit::Word => words.map(String::from).collect(),
+            Unit::Char => words
+                .collect::<Vec<_>>()
+                .join(" ")
+                .chars()
+                .map(String::from)
+                .collect(),
+        }
+    }
+
+    fn join(self, tokens: &[String]) -> String {
+        tokens.join(if self == Unit::Word { " " } else { "" })
+    }
+}
+
+/// Run a command and return its result, or the daemon's error
+fn call(cmd: &CommandJson, flags: &Flags) -> Result<Value, String> {
+    let resp = send_command(cmd, &flags.session)?;
+    if resp.success {
+        Ok(resp.result.unwrap_or(Value::Null))
+    } else {
+        Err(resp
+            .error
+            .unwrap_or_else(|| format!("{} failed", cmd.action)))
+    }
+}
+
+/// The element's text, one line per block of its `text --structured`
+/// outline so words in neighbouring blocks don't run together
+pub fn read_text(selector: &str, flags: &Flags) -> Result<String, String> {
+    let mut cmd = CommandJson::new("getText");
+    cmd.selector = Some(selector.to_string());
+    cmd.structured = Some(true);
+    let result = call(&cmd, flags)?;
+    let blocks = result["outline"].as_array().cloned().unwrap_or_default();
+    let lines: Vec<String> = blocks
+        .iter()
+        .map(|block| {
+            let strings = |value: &Value| -> Vec<String> {
+                value
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .map(|v| match v {
+                        Value::String(s) => s.clone(),
+                        row => row
+                            .as_array()
+                            .into_iter()
+                            .flatten()
+                            .filter_map(|cell| cell.as_str())
+                            .collect::<Vec<_>>()
+                            .join(" "),
+                    })
+                    .collect()
+            };
+            match block["type"].as_str() {
+                Some("list") => strings(&block["items"]).join("\n"),
+                Some("table") => strings(&block["rows"]).join("\n"),
+                _ => block["text"].as_str().unwrap_or("").to_string(),
+            }
+        })
+        .collect();
+    Ok(lines.join("\n"))
+}
+
+/// Where `--against=last` keeps the text of `selector` on `url`
+fn last_path(url: &str, selector: &str) -> Result<PathBuf, String> {
+    let page = url.split('#').next().unwrap_or(url);
+    let key = sha256_hex(format!("{}\n{}", page, selector).as_bytes());
+    Ok(data_dir()
+        .ok_or_else(|| "Cannot locate home directory for textdiff storage".to_string())?
+        .join("textdiff")
+        .join(format!("{}.txt", &key[..16])))
+}
+
+fn store(path: &PathBuf, text: &str) -> Result<(), String> {
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        fs::create_dir_all(dir)
+            .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    }
+    fs::write(path, text).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+/// Read `selector`'s text and diff it against the baseline `against`
+/// (`last` or a file path)
+pub fn run(
+    selector: &str,
+    against: &str,
+    unit: Unit,
+    update: bool,
+    flags: &Flags,
+) -> Result<Value, String> {
+    ensure_daemon(
+        &flags.session,
+        flags.headed,
+        flags.executable_path.as_deref(),
+        &flags.extensions,
+    )?;
+    let url = call(&CommandJson::new("getUrl"), flags)?["url"]
+        .as_str()
+        .unwrap_or("")
+        .to_string();
+    let text = read_text(selector, flags)?;
+
+    let (path, rolling) = if against == "last" {
+        (last_path(&url, selector)?, true)
+    } else {
+        (PathBuf::from(against), false)
+    };
+    let mut result = json!({
+        "selector": selector,
+        "url": url,
+        "against": against,
+        "by": unit.as_str(),
+    });
+
+    let before = match fs::read_to_string(&path) {
+        Ok(before) => before,
+        Err(_) if !path.exists() => {
+            store(&path, &text)?;
+            result["baseline"] = json!(true);
+            result["changed"] = json!(false);
+            result["path"] = json!(path.to_string_lossy());
+            return Ok(result);
+        }
+        Err(e) => return Err(format!("Failed to read {}: {}", path.display(), e)),
+    };
+
+    let changes = diff(&unit.tokens(&before), &unit.tokens(&text), unit);
+    let count = |field: &str| -> usize {
+        changes
+            .iter()
+            .map(|change: &Value| change[field].as_u64().unwrap_or(0) as usize)
+            .sum()
+    };
+    result["removed"] = json!(count("removedCount"));
+    result["added"] = json!(count("addedCount"));
+    result["changed"] = json!(!changes.is_empty());
+    result["changes"] = Value::Array(changes);
+    if rolling || update {
+        store(&path, &text)?;
+    }
+    Ok(result)
+}
+
+/// Each run of changed tokens, with the unchanged tokens around it
+fn diff(old: &[String], new: &[String], unit: Unit) -> Vec<Value> {
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let a = &old[prefix..old.len() - suffix];
+    let b = &new[prefix..new.len() - suffix];
+
+    // Pairs of matching positions in old and new, in order, ending with
+    // one past the end of both
+    let mut matches: Vec<(usize, usize)> = (0..prefix).map(|k| (k, k)).collect();
+    if (a.len() + 1).saturating_mul(b.len() + 1) <= MAX_LCS_CELLS {
+        // lcs[i][j]: longest common subsequence of a[i..] and b[j..]
+        let width = b.len() + 1;
+        let mut lcs = vec![0u32; (a.len() + 1) * width];
+        for i in (0..a.len()).rev() {
+            for j in (0..b.len()).rev() {
+                lcs[i * width + j] = if a[i] == b[j] {
+                    lcs[(i + 1) * width + j + 1] + 1
+                } else {
+                    lcs[(i + 1) * width + j].max(lcs[i * width + j + 1])
+                };
+            }
+        }
+        let (mut i, mut j) = (0, 0);
+        while i < a.len() && j < b.len() {
+            if a[i] == b[j] {
+                matches.push((prefix + i, prefix + j));
+                i += 1;
+                j += 1;
+            } else if lcs[(i + 1) * width + j] >= lcs[i * width + j + 1] {
+                i += 1;
+            } else {
+                j += 1;
+            }
+        }
+    }
+    let (a_end, b_end) = (old.len() - suffix, new.len() - suffix);
+    matches.extend((0..=suffix).map(|k| (a_end + k, b_end + k)));
+
+    // Context stays within the unchanged runs next to a change
+    let context = unit.context();
+    let mut changes = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    let mut unchanged_from = 0;
+    for (k, &(mi, mj)) in matches.iter().enumerate() {
+        if mi > i || mj > j {
+            let run = matches[k..]
+                .iter()
+                .enumerate()
+                .take_while(|&(r, &m)| m == (mi + r, mj + r))
+                .count();
+            let after_end = (mi + run.min(context)).min(old.len());
+            let before_start = i.saturating_sub(context).max(unchanged_from);
+            changes.push(json!({
+                "before": unit.join(&old[before_start..i]),
+                "removed": unit.join(&old[i..mi]),
+                "added": unit.join(&new[j..mj]),
+                "after": unit.join(&old[mi..after_end]),
+                "removedCount": mi - i,
+                "addedCount": mj - j,
+            }));
+            unchanged_from = mi;
+        }
+        i = mi + 1;
+        j = mj + 1;
+    }
+    changes
+}
+
+/// Human-readable report for `textdiff`
+pub fn print(result: &Value) {
+    let selector = result["selector"].as_str().unwrap_or("");
+    if result["baseline"] == true {
+        println!(
+            "\x1b[32m✓\x1b[0m Saved the text of {} as the baseline ({})",
+            selector,
+            result["path"].as_str().unwrap_or("")
+        );
+        return;
+    }
+    if result["changed"] != true {
+        println!("\x1b[32m✓\x1b[0m No changes to the text of {}", selector);
+        return;
+    }
+    let unit = result["by"].as_str().unwrap_or("word");
+    let changes = result["changes"].as_array().cloned().unwrap_or_default();
+    println!(
+        "\x1b[33m≠\x1b[0m {} {}(s) added, {} removed in {}",
+        result["added"], unit, result["removed"], selector
+    );
+    let space = if unit == "word" { " " } else { "" };
+    for change in changes.iter().take(SHOWN_CHANGES) {
+        let field = |name: &str| change[name].as_str().unwrap_or("");
+        let parts: Vec<String> = [
+            ("before", "2"),
+            ("removed", "31;9"),
+            ("added", "32"),
+            ("after", "2"),
+        ]
+        .iter()
+        .filter(|(name, _)| !field(name).is_empty())
+        .map(|(name, style)| format!("\x1b[{}m{}\x1b[0m", style, field(name)))
+        .collect();
+        // Ellipses where the excerpt cuts into unchanged text
+        let open = if field("before").is_empty() {
+            ""
+        } else {
+            "…"
+        };
+        let close = if field("after").is_empty() { "" } else { "…" };
+        println!("  {}{}{}", open, parts.join(space), close);
+    }
+    if changes.len() > SHOWN_CHANGES {
+        println!("  ... {} more", changes.len() - SHOWN_CHANGES);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn changes(old: &str, new: &str, unit: Unit) -> Vec<Value> {
+        diff(&unit.tokens(old), &unit.tokens(new), unit)
+    }
+
+    #[test]
+    fn units() {
+        assert_eq!(Unit::parse("words"), Some(Unit::Word));
+        assert_eq!(Unit::parse("char"), Some(Unit::Char));
+        assert_eq!(Unit::parse("line"), None);
+        assert_eq!(Unit::Word.tokens("  a\n\tb  c "), ["a", "b", "c"]);
+        // Whitespace collapses to single spaces between characters
+        assert_eq!(Unit::Char.tokens(" a\n\n b "), ["a", " ", "b"]);
+        assert!(Unit::Word.tokens(" \n ").is_empty());
+    }
+
+    #[test]
+    fn changed_word() {
+        let found = changes(
+            "The price is $10 today",
+            "The price is $12 today",
+            Unit::Word,
+        );
+        assert_eq!(
+            found,
+            [json!({
+                "before": "The price is",
+                "removed": "$10",
+                "added": "$12",
+                "after": "today",
+                "removedCount": 1,
+                "addedCount": 1,
+            })]
+        );
+    }
+
+    #[test]
+    fn changed_character() {
+        let found = changes("Total: $10", "Total: $12", Unit::Char);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0]["before"], "Total: $1");
+        assert_eq!(found[0]["removed"], "0");
+        assert_eq!(found[0]["added"], "2");
+        assert_eq!(found[0]["after"], "");
+    }
+
+    #[test]
+    fn insertions_deletions_and_separate_changes() {
+        let found = changes(
+            "one two three four five six seven eight nine ten",
+            "one two 2.5 three four five six eight nine ten eleven",
+            Unit::Word,
+        );
+        let summary: Vec<(&str, &str)> = found
+            .iter()
+            .map(|c| (c["removed"].as_str().unwrap(), c["added"].as_str().unwrap()))
+            .collect();
+        assert_eq!(summary, [("", "2.5"), ("seven", ""), ("", "eleven")]);
+        // Context stays within the unchanged words between changes
+        assert_eq!(found[0]["before"], "one two");
+        assert_eq!(found[0]["after"], "three four five six");
+        assert_eq!(found[1]["before"], "three four five six");
+        assert_eq!(found[2]["before"], "eight nine ten");
+    }
+
+    #[test]
+    fn empty_text() {
+        assert!(changes("", "", Unit::Word).is_empty());
+        assert!(changes("same words", "same  words", Unit::Word).is_empty());
+        let added = changes("", "hello world", Unit::Word);
+        assert_eq!(added.len(), 1);
+        assert_eq!(added[0]["added"], "hello world");
+        assert_eq!(added[0]["removedCount"], 0);
+        let removed = changes("hello", "", Unit::Char);
+        assert_eq!(removed[0]["removed"], "hello");
+        assert_eq!(removed[0]["addedCount"], 0);
+    }
+
+    #[test]
+    fn large_rewrites_are_one_change() {
+        let old: Vec<String> = (0..3000).map(|i| format!("old{}", i % 10)).collect();
+        let new: Vec<String> = (0..3000).map(|i| format!("new{}", i % 10)).collect();
+        let old = format!("start {} end", old.join(" "));
+        let new = format!("start {} end", new.join(" "));
+        let found = changes(&old, &new, Unit::Word);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0]["removedCount"], 3000);
+        assert_eq!(found[0]["addedCount"], 3000);
+        assert_eq!(found[0]["before"], "start");
+        assert_eq!(found[0]["after"], "end");
+    }
+}
@@ -0,0 +1,294 @@
+/*!
+ * Tiled Screenshot Stitching
+ *
+ * `screenshot --tiled` has the daemon scroll the page one viewport at a
+ * time and send back each capture with the heights of the fixed or sticky
+ * bands along its top and bottom edges. Stitching keeps the top band from
+ * the first tile and the bottom band from the last one, and crops them out
+ * everywhere else, so a sticky header shows up once instead of on every
+ * screen. Pages that lazy-load or run scroll effects can still show seams;
+ * `--full-page` remains the faster choice when it renders correctly.
+ */
+use std::fs;
+
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use png::{BitDepth, ColorType, Decoder, Encoder, Transformations};
+use serde_json::{json, Value};
+
+use crate::connection::Response;
+
+/// An image as 8-bit RGBA rows, top to bottom
+struct Image {
+    width: usize,
+    height: usize,
+    rgba: Vec<u8>,
+}
+
+/// Decode a PNG into RGBA, whatever its color type and bit depth
+fn decode(data: &[u8]) -> Result<Image, String> {
+    let mut decoder = Decoder::new(data);
+    decoder.set_transformations(Transformations::normalize_to_color8() | Transformations::ALPHA);
+    let mut reader = decoder
+        .read_info()
+        .map_err(|e| format!("Not a PNG image: {}", e))?;
+    let mut pixels = vec![0; reader.output_buffer_size()];
+    let frame = reader
+        .next_frame(&mut pixels)
+        .map_err(|e| format!("Broken PNG image: {}", e))?;
+    pixels.truncate(frame.buffer_size());
+    let rgba = match frame.color_type {
+        ColorType::Rgba => pixels,
+        ColorType::GrayscaleAlpha => pixels
+            .chunks(2)
+            .flat_map(|p| [p[0], p[0], p[0], p[1]])
+            .collect(),
+        other => return Err(format!("Unexpected PNG color type {:?}", other)),
+    };
+    Ok(Image {
+        width: frame.width as usize,
+        height: frame.height as usize,
+        rgba,
+    })
+}
+
+fn encode(image: &Image) -> Result<Vec<u8>, String> {
+    let mut out = Vec::new();
+    let mut encoder = Encoder::new(&mut out, image.width as u32, image.height as u32);
+    encoder.set_color(ColorType::Rgba);
+    encoder.set_depth(BitDepth::Eight);
+    encoder
+        .write_header()
+        .and_then(|mut writer| writer.write_image_data(&image.rgba))
+        .map_err(|e| format!("Failed to encode PNG: {}", e))?;
+    Ok(out)
+}
+
+/// Replace the tile list with the stitched image, written to `path` or
+/// returned as base64 data
+pub fn stitch(resp: &mut Response, path: Option<&str>) -> Result<(), String> {
+    let tiles = match resp.result.as_ref().and_then(|r| r["tiles"].as_array()) {
+        Some(tiles) if !tiles.is_empty() => tiles,
+        _ => return Err("Daemon returned no screenshot tiles".to_string()),
+    };
+
+    let mut width = 0;
+    let mut rgba = Vec::new();
+    // Device-pixel row of the page that the stitched image reaches
+    let mut covered: usize = 0;
+    for (i, tile) in tiles.iter().enumerate() {
+        let data = tile["data"].as_str().unwrap_or_default();
+        let image = BASE64
+            .decode(data)
+            .map_err(|e| format!("Invalid base64 data: {}", e))
+            .and_then(|png| decode(&png))
+            .map_err(|e| format!("Tile {}: {}", i + 1, e))?;
+        if i == 0 {
+            width = image.width;
+        } else if image.width != width {
+            return Err(format!(
+                "Tile {} is {}px wide, expected {}px",
+                i + 1,
+                image.width,
+                width
+            ));
+        }
+
+        // Screenshots are in device pixels, the measurements in CSS pixels
+        let viewport = tile["viewportHeight"].as_f64().unwrap_or(0.0);
+        let scale = if viewport > 0.0 {
+            image.height as f64 / viewport
+        } else {
+            1.0
+        };
+        let px = |field: &str| (tile[field].as_f64().unwrap_or(0.0) * scale).round() as usize;
+        let offset = px("y");
+        let first = if i == 0 { 0 } else { px("top") };
+        let last = if i + 1 == tiles.len() {
+            image.height
+        } else {
+            image.height.saturating_sub(px("bottom"))
+        };
+
+        // Skip rows an earlier tile already supplied, e.g. when the last
+        // scroll was clamped at the bottom of the page
+        let first = first.max(covered.saturating_sub(offset));
+        if first >= last {
+            continue;
+        }
+        let stride = width * 4;
+        rgba.extend_from_slice(&image.rgba[first * stride..last * stride]);
+        covered = offset + last;
+    }
+
+    let image = Image {
+        width,
+        height: rgba.len() / (width * 4).max(1),
+        rgba,
+    };
+    let encoded = encode(&image)?;
+    let mut result = json!({
+        "width": image.width,
+        "height": image.height,
+        "tiles": tiles.len(),
+    });
+    match path {
+        Some(path) => {
+            fs::write(path, &encoded).map_err(|e| format!("Failed to write {}: {}", path, e))?;
+            result["path"] = Value::from(path);
+        }
+        None => result["data"] = Value::from(BASE64.encode(&encoded)),
+    }
+    resp.result = Some(result);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HEADER: u8 = 200;
+
+    /// A 2px-wide capture whose rows show `values`, `dpr` device rows each
+    fn capture(values: &[u8], dpr: usize) -> String {
+        let rgba = values
+            .iter()
+            .flat_map(|&v| [v, v, v, 255].repeat(2 * dpr))
+            .collect();
+        BASE64.encode(
+            encode(&Image {
+                width: 2,
+                height: values.len() * dpr,
+                rgba,
+            })
+            .unwrap(),
+        )
+    }
+
+    /// A viewport showing `values` from page row `y` on, with fixed bands
+    /// `top` and `bottom` rows tall
+    fn tile(values: &[u8], dpr: usize, y: usize, top: usize, bottom: usize) -> Value {
+        json!({
+            "data": capture(values, dpr),
+            "y": y,
+            "viewportHeight": values.len(),
+            "top": top,
+            "bottom": bottom,
+        })
+    }
+
+    /// The value of each row of a stitched image
+    fn rows(result: &Value) -> Vec<u8> {
+        let data = BASE64.decode(result["data"].as_str().unwrap()).unwrap();
+        let image = decode(&data).unwrap();
+        assert_eq!(image.width, 2);
+        image.rgba.chunks(8).map(|row| row[0]).collect()
+    }
+
+    fn stitched(tiles: Value) -> Result<Value, String> {
+        let mut resp = Response::ok("1", json!({ "tiles": tiles }));
+        stitch(&mut resp, None)?;
+        Ok(resp.result.unwrap())
+    }
+
+    #[test]
+    fn no_tiles() {
+        assert!(stitched(json!([])).is_err());
+        let mut resp = Response::ok("1", json!({}));
+        assert!(stitch(&mut resp, None).is_err());
+    }
+
+    #[test]
+    fn single_tile_is_kept_whole() {
+        let result = stitched(json!([tile(&[HEADER, 1, 2, 3], 1, 0, 1, 1)])).unwrap();
+        assert_eq!(result["tiles"], 1);
+        assert_eq!(result["height"], 4);
+        assert_eq!(rows(&result), [HEADER, 1, 2, 3]);
+    }
+
+    #[test]
+    fn sticky_header_appears_once() {
+        // A 10-row page seen through a 4-row viewport with a 1-row sticky
+        // header
+        let tiles = json!([
+            tile(&[HEADER, 1, 2, 3], 1, 0, 1, 0),
+            tile(&[HEADER, 4, 5, 6], 1, 3, 1, 0),
+            tile(&[HEADER, 7, 8, 9], 1, 6, 1, 0),
+        ]);
+        assert_eq!(
+            rows(&stitched(tiles).unwrap()),
+            [HEADER, 1, 2, 3, 4, 5, 6, 7, 8, 9]
+        );
+
+        // The last scroll clamped at the bottom of a 9-row page
+        let clamped = json!([
+            tile(&[HEADER, 1, 2, 3], 1, 0, 1, 0),
+            tile(&[HEADER, 4, 5, 6], 1, 3, 1, 0),
+            tile(&[HEADER, 6, 7, 8], 1, 5, 1, 0),
+        ]);
+        assert_eq!(
+            rows(&stitched(clamped).unwrap()),
+            [HEADER, 1, 2, 3, 4, 5, 6, 7, 8]
+        );
+    }
+
+    #[test]
+    fn footer_kept_from_the_last_tile_only() {
+        const FOOTER: u8 = 100;
+        let tiles = json!([
+            tile(&[0, 1, 2, FOOTER], 1, 0, 0, 1),
+            tile(&[3, 4, 5, FOOTER], 1, 3, 0, 1),
+        ]);
+        assert_eq!(rows(&stitched(tiles).unwrap()), [0, 1, 2, 3, 4, 5, FOOTER]);
+    }
+
+    #[test]
+    fn measurements_scale_to_device_pixels() {
+        let tiles = json!([
+            tile(&[HEADER, 1, 2], 2, 0, 1, 0),
+            tile(&[HEADER, 3, 4], 2, 2, 1, 0),
+        ]);
+        let result = stitched(tiles).unwrap();
+        assert_eq!(result["height"], 10);
+        assert_eq!(rows(&result), [HEADER, HEADER, 1, 1, 2, 2, 3, 3, 4, 4]);
+    }
+
+    #[test]
+    fn tiles_must_share_a_width() {
+        let narrow = BASE64.encode(
+            encode(&Image {
+                width: 1,
+                height: 1,
+                rgba: vec![0, 0, 0, 255],
+            })
+            .unwrap(),
+        );
+        let tiles = json!([
+            tile(&[1], 1, 0, 0, 0),
+            { "data": narrow, "y": 1, "viewportHeight": 1 },
+        ]);
+        assert_eq!(
+            stitched(tiles).unwrap_err(),
+            "Tile 2 is 1px wide, expected 2px"
+        );
+        let broken = json!([{ "data": BASE64.encode(b"not a png"), "y": 0 }]);
+        assert!(stitched(broken)
+            .unwrap_err()
+            .starts_with("Tile 1: Not a PNG image"));
+    }
+
+    #[test]
+    fn writes_to_a_file() {
+        let path = std::env::temp_dir().join(format!("tiles-test-{}.png", std::process::id()));
+        let path = path.to_str().unwrap();
+        let tiles = json!([tile(&[1, 2], 1, 0, 0, 0)]);
+        let mut resp = Response::ok("1", json!({ "tiles": tiles }));
+        stitch(&mut resp, Some(path)).unwrap();
+        let result = resp.result.unwrap();
+        assert_eq!(result["path"], path);
+        assert!(result.get("data").is_none());
+        let image = decode(&fs::read(path).unwrap()).unwrap();
+        fs::remove_file(path).ok();
+        assert_eq!((image.width, image.height), (2, 2));
+    }
+}
@@ -0,0 +1,367 @@
+/*!
+ * Snapshot Tree Parsing
+ *
+ * Turns the daemon's text accessibility tree (`- role "name" [ref=e1]`)
+ * into structured nodes for `snapshot --output=json|xml`, and trims it to a
+ * token budget for `snapshot --max-tokens`.
+ */
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::connection::Response;
+
+#[derive(Debug, Serialize)]
+pub struct TreeNode {
+    pub role: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(rename = "ref", skip_serializing_if = "Option::is_none")]
+    pub reference: Option<String>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub states: BTreeMap<String, String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<TreeNode>,
+}
+
+/// Parse the text tree into nested nodes (two spaces per level)
+pub fn parse_tree(text: &str) -> Vec<TreeNode> {
+    let mut roots: Vec<TreeNode> = Vec::new();
+    // Stack of (depth, node) for the current ancestor chain
+    let mut stack: Vec<(usize, TreeNode)> = Vec::new();
+
+    for line in text.lines() {
+        let trimmed = line.trim_start();
+        let body = match trimmed.strip_prefix('-') {
+            Some(body) => body.trim(),
+            None => continue,
+        };
+        let depth = (line.len() - trimmed.len()) / 2;
+        let node = match parse_line(body) {
+            Some(node) => node,
+            None => continue,
+        };
+
+        while let Some((d, _)) = stack.last() {
+            if *d < depth {
+                break;
+            }
+            let (_, done) = stack.pop().unwrap();
+            attach(&mut stack, &mut roots, done);
+        }
+        stack.push((depth, node));
+    }
+
+    while let Some((_, done)) = stack.pop() {
+        attach(&mut stack, &mut roots, done);
+    }
+    roots
+}
+
+fn attach(stack: &mut [(usize, TreeNode)], roots: &mut Vec<TreeNode>, node: TreeNode) {
+    match stack.last_mut() {
+        Some((_, parent)) => parent.children.push(node),
+        None => roots.push(node),
+    }
+}
+
+/// Parse `role "name" [ref=e1] [checked] [level=2]`
+fn parse_line(body: &str) -> Option<TreeNode> {
+    let role_end = body
+        .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .unwrap_or(body.len());
+    if role_end == 0 {
+        return None;
+    }
+    let role = body[..role_end].to_string();
+    let mut rest = body[role_end..].trim_start();
+
+    let mut name = None;
+    if let Some(after) = rest.strip_prefix('"') {
+        if let Some(end) = after.find('"') {
+            name = Some(after[..end].to_string());
+            rest = after[end + 1..].trim_start();
+        }
+    }
+
+    let mut reference = None;
+    let mut states = BTreeMap::new();
+    while let Some(after) = rest.strip_prefix('[') {
+        let end = match after.find(']') {
+            Some(end) => end,
+            None => break,
+        };
+        let attr = &after[..end];
+        match attr.split_once('=') {
+            Some(("ref", value)) => reference = Some(value.to_string()),
+            Some(("nth", _)) => {}
+            Some((key, value)) => {
+                states.insert(key.to_string(), value.to_string());
+            }
+            None => {
+                states.insert(attr.to_string(), "true".to_string());
+            }
+        }
+        rest = after[end + 1..].trim_start();
+    }
+
+    Some(TreeNode {
+        role,
+        name,
+        reference,
+        states,
+        children: Vec::new(),
+    })
+}
+
+/// Render nodes as an XML document
+pub fn to_xml(nodes: &[TreeNode], url: Option<&str>, title: Option<&str>) -> String {
+    let mut out = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<snapshot");
+    if let Some(url) = url {
+        out.push_str(&format!(" url=\"{}\"", escape_xml(url)));
+    }
+    if let Some(title) = title {
+        out.push_str(&format!(" title=\"{}\"", escape_xml(title)));
+    }
+    out.push_str(">\n");
+    for node in nodes {
+        write_xml(node, 1, &mut out);
+    }
+    out.push_str("</snapshot>");
+    out
+}
+
+fn write_xml(node: &TreeNode, depth: usize, out: &mut String) {
+    let indent = "  ".repeat(depth);
+    out.push_str(&format!("{}<{}", indent, node.role));
+    if let Some(ref name) = node.name {
+        out.push_str(&format!(" name=\"{}\"", escape_xml(name)));
+    }
+    if let Some(ref reference) = node.reference {
+        out.push_str(&format!(" ref=\"{}\"", escape_xml(reference)));
+    }
+    for (key, value) in &node.states {
+        out.push_str(&format!(" {}=\"{}\"", key, escape_xml(value)));
+    }
+
+    if node.children.is_empty() {
+        out.push_str("/>\n");
+        return;
+    }
+    out.push_str(">\n");
+    for child in &node.children {
+        write_xml(child, depth + 1, out);
+    }
+    out.push_str(&format!("{}</{}>\n", indent, node.role));
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Roles that orient a reader even when they can't be acted on
+const LANDMARK_ROLES: &[&str] = &[
+    "banner",
+    "complementary",
+    "contentinfo",
+    "dialog",
+    "alertdialog",
+    "form",
+    "heading",
+    "main",
+    "navigation",
+    "region",
+    "search",
+];
+
+/// Rough token estimate: ~4 characters per token
+fn estimate_tokens(line: &str) -> usize {
+    line.len().div_ceil(4) + 1
+}
+
+fn omitted_marker(omitted: usize, max_tokens: usize) -> String {
+    format!(
+        "- ({} elements omitted to fit {} tokens)",
+        omitted, max_tokens
+    )
+}
+
+/// Keep the most useful lines of a text tree within `max_tokens`, the
+/// omitted marker included.
+///
+/// Interactive elements (those with refs) come first, then landmarks, then
+/// everything else; ties keep document order so output is stable between
+/// runs. Returns the trimmed tree and how many lines were dropped.
+pub fn fit_budget(text: &str, max_tokens: usize) -> (String, usize) {
+    let lines: Vec<&str> = text.lines().collect();
+    let total: usize = lines.iter().map(|line| estimate_tokens(line)).sum();
+    let budget = if total <= max_tokens {
+        max_tokens
+    } else {
+        max_tokens.saturating_sub(estimate_tokens(&omitted_marker(lines.len(), max_tokens)))
+    };
+    let mut ranked: Vec<(u8, usize)> = lines
+        .iter()
+        .enumerate()
+        .map(|(i, line)| (line_priority(line), i))
+        .collect();
+    ranked.sort();
+
+    let mut used = 0;
+    let mut keep = vec![false; lines.len()];
+    for (_, i) in ranked {
+        let cost = estimate_tokens(lines[i]);
+        if used + cost > budget {
+            continue;
+        }
+        used += cost;
+        keep[i] = true;
+    }
+
+    let omitted = keep.iter().filter(|k| !**k).count();
+    let mut out: Vec<String> = lines
+        .iter()
+        .zip(&keep)
+        .filter(|(_, k)| **k)
+        .map(|(line, _)| line.to_string())
+        .collect();
+    if omitted > 0 {
+        out.push(omitted_marker(omitted, max_tokens));
+    }
+    (out.join("\n"), omitted)
+}
+
+fn line_priority(line: &str) -> u8 {
+    if line.contains("[ref=") {
+        return 0;
+    }
+    let role = line
+        .trim_start()
+        .trim_start_matches('-')
+        .trim_start()
+        .split(|c: char| !c.is_alphanumeric())
+        .next()
+        .unwrap_or("");
+    if LANDMARK_ROLES.contains(&role) {
+        1
+    } else {
+        2
+    }
+}
+
+/// Trim the text tree in a snapshot response to a token budget
+pub fn apply_budget(resp: &mut Response, max_tokens: usize) {
+    let result = match resp.result.as_mut().and_then(|r| r.as_object_mut()) {
+        Some(result) => result,
+        None => return,
+    };
+    let text = match result.get("tree").and_then(|v| v.as_str()) {
+        Some(text) => text,
+        None => return,
+    };
+
+    let (trimmed, omitted) = fit_budget(text, max_tokens);
+    result.insert("tree".to_string(), Value::String(trimmed));
+    if omitted > 0 {
+        result.insert("truncated".to_string(), Value::Bool(true));
+        result.insert("omitted".to_string(), Value::from(omitted));
+    }
+}
+
+/// Replace the text tree in a snapshot response with the requested format
+pub fn restructure(resp: &mut Response, format: &str) {
+    let result = match resp.result.as_mut().and_then(|r| r.as_object_mut()) {
+        Some(result) => result,
+        None => return,
+    };
+    let text = match result.remove("tree") {
+        Some(Value::String(text)) => text,
+        Some(other) => {
+            result.insert("tree".to_string(), other);
+            return;
+        }
+        None => return,
+    };
+    let nodes = parse_tree(&text);
+
+    match format {
+        "json" => {
+            result.insert(
+                "nodes".to_string(),
+                serde_json::to_value(&nodes).unwrap_or_default(),
+            );
+        }
+        "xml" => {
+            let url = result.get("url").and_then(|v| v.as_str());
+            let title = result.get("title").and_then(|v| v.as_str());
+            let xml = to_xml(&nodes, url, title);
+            result.insert("xml".to_string(), Value::String(xml));
+        }
+        _ => {
+            result.insert("tree".to_string(), Value::String(text));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TREE: &str = "- main\n  - heading \"Shop\" [level=1]\n  - paragraph: Free shipping on all orders\n  - button \"Buy\" [ref=e1]";
+
+    fn cost(text: &str) -> usize {
+        text.lines().map(estimate_tokens).sum()
+    }
+
+    #[test]
+    fn a_tree_within_budget_is_untouched() {
+        let exact = cost(TREE);
+        assert_eq!(fit_budget(TREE, exact), (TREE.to_string(), 0));
+        assert_eq!(fit_budget(TREE, exact * 10), (TREE.to_string(), 0));
+        assert_eq!(fit_budget("", 10), (String::new(), 0));
+    }
+
+    #[test]
+    fn truncation_keeps_refs_then_landmarks_and_fits_the_marker() {
+        let budget = cost(TREE) - 1;
+        let (trimmed, omitted) = fit_budget(TREE, budget);
+        assert_eq!(omitted, 1);
+        assert_eq!(
+            trimmed,
+            format!(
+                "- main\n  - heading \"Shop\" [level=1]\n  - button \"Buy\" [ref=e1]\n{}",
+                omitted_marker(1, budget)
+            )
+        );
+        assert!(cost(&trimmed) <= budget);
+        // Same input, same output
+        assert_eq!(fit_budget(TREE, budget).0, trimmed);
+    }
+
+    #[test]
+    fn a_budget_below_the_marker_omits_everything() {
+        let (trimmed, omitted) = fit_budget(TREE, 3);
+        assert_eq!(omitted, 4);
+        assert_eq!(trimmed, omitted_marker(4, 3));
+    }
+
+    #[test]
+    fn budget_reports_truncation_on_the_response() {
+        let mut resp = Response::ok("1", serde_json::json!({ "tree": TREE }));
+        apply_budget(&mut resp, cost(TREE) - 1);
+        let result = resp.result.unwrap();
+        assert_eq!(result["truncated"], true);
+        assert!(result["omitted"].as_u64().unwrap() > 0);
+        assert!(result["tree"].as_str().unwrap().contains("[ref=e1]"));
+
+        let mut resp = Response::ok("1", serde_json::json!({ "tree": TREE }));
+        apply_budget(&mut resp, 1000);
+        let result = resp.result.unwrap();
+        assert!(result.get("truncated").is_none());
+        assert_eq!(result["tree"], TREE);
+    }
+}
@@ -0,0 +1,283 @@
+/*!
+ * Encrypted State
+ *
+ * Storage-state files hold live session cookies, so `state save --encrypt`
+ * seals them with ChaCha20-Poly1305 (RFC 8439, from the `chacha20poly1305`
+ * crate, with `MAGIC` as associated data) before they reach a CI cache
+ * or anywhere else they might leak from. Sealed files are recognised by
+ * their header and opened transparently by `state load` and pool
+ * templates; a file that was altered or sealed with another key is refused.
+ *
+ * The key is 32 random bytes, read from AGENT_BROWSER_STATE_KEY (base64)
+ * or else from ~/.agentbrowser-pro/state.key, which is created with owner-only
+ * permissions on first use. `state key` prints it for a CI secret.
+ */
+use std::env;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use chacha20poly1305::aead::{Aead, Payload};
+use chacha20poly1305::{ChaCha20Poly1305, KeyInit};
+use serde_json::json;
+
+use crate::commands::CommandJson;
+use crate::config::data_dir;
+use crate::connection::Response;
+use crate::pageerrors::now_ms;
+use crate::storagestate;
+
+/// Start of every sealed file; also authenticated with the contents
+const MAGIC: &[u8] = b"ABPSEAL1";
+const NONCE_LEN: usize = 12;
+const TAG_LEN: usize = 16;
+
+/// Where the key lives when AGENT_BROWSER_STATE_KEY isn't set
+fn key_path() -> Result<PathBuf, String> {
+    Ok(data_dir()
+        .ok_or_else(|| "Cannot locate home directory for the state key".to_string())?
+        .join("state.key"))
+}
+
+/// The sealing key, creating the key file if there is none yet
+pub fn key() -> Result<[u8; 32], String> {
+    let (encoded, source) = match env::var("AGENT_BROWSER_STATE_KEY") {
+        Ok(encoded) => (encoded, "AGENT_BROWSER_STATE_KEY".to_string()),
+        Err(_) => {
+            let path = key_path()?;
+            if !path.exists() {
+                let key = random_bytes(32)?;
+                if let Some(dir) = path.parent() {
+                    fs::create_dir_all(dir).map_err(|e| {
+                        format!("Failed to create directory {}: {}", dir.display(), e)
+                    })?;
+                }
+                write_private(&path, BASE64.encode(key).as_bytes())?;
+            }
+            let encoded = fs::read_to_string(&path)
+                .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+            (encoded, path.display().to_string())
+        }
+    };
+    BASE64
+        .decode(encoded.trim())
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| format!("{} is not a base64-encoded 32-byte key", source))
+}
+
+/// Whether `data` was sealed by `seal`
+pub fn is_sealed(data: &[u8]) -> bool {
+    data.starts_with(MAGIC)
+}
+
+/// Encrypt `plain` under `key` with a fresh nonce
+pub fn seal(plain: &[u8], key: &[u8; 32]) -> Result<Vec<u8>, String> {
+    let nonce: [u8; NONCE_LEN] = random_bytes(NONCE_LEN)?
+        .try_into()
+        .map_err(|_| "Short read from the random source".to_string())?;
+    let sealed = ChaCha20Poly1305::new(key.into())
+        .encrypt(
+            &nonce.into(),
+            Payload {
+                msg: plain,
+                aad: MAGIC,
+            },
+        )
+        .map_err(|_| "Failed to encrypt".to_string())?;
+    Ok([MAGIC, &nonce, &sealed].concat())
+}
+
+/// Decrypt what `seal` produced, checking it wasn't altered
+pub fn open(data: &[u8], key: &[u8; 32]) -> Result<Vec<u8>, String> {
+    let rest = data
+        .strip_prefix(MAGIC)
+        .filter(|rest| rest.len() >= NONCE_LEN + TAG_LEN)
+        .ok_or_else(|| "Not a sealed file".to_string())?;
+    let (nonce, sealed) = rest.split_at(NONCE_LEN);
+    ChaCha20Poly1305::new(key.into())
+        .decrypt(
+            nonce.into(),
+            Payload {
+                msg: sealed,
+                aad: MAGIC,
+            },
+        )
+        .map_err(|_| "it was sealed with a different key or has been modified".to_string())
+}
+
+/// A state file moved through a private temporary copy while the daemon,
+/// which only reads and writes plain JSON, works on it
+pub struct Staged {
+    temp: PathBuf,
+    target: String,
+    sealing: bool,
+}
+
+/// Point a `saveState` (with `encrypt`) or a `loadState` of a sealed file at
+/// a temporary plain copy. Anything else is left as it is. A `loadState`
+/// file is checked for the storage-state shape either way.
+pub fn prepare(cmd: &mut CommandJson, encrypt: bool) -> Result<Option<Staged>, String> {
+    let Some(target) = cmd.path.clone() else {
+        return Ok(None);
+    };
+    let temp = env::temp_dir().join(format!(
+        "agentbrowser-pro-state-{}-{}.json",
+        std::process::id(),
+        now_ms()
+    ));
+    match cmd.action.as_str() {
+        "saveState" if encrypt => {
+            // Make the file ours before the daemon writes credentials to it
+            write_private(&temp, b"")?;
+            cmd.path = Some(temp.to_string_lossy().into_owned());
+            Ok(Some(Staged {
+                temp,
+                target,
+                sealing: true,
+            }))
+        }
+        "loadState" => {
+            let data =
+                fs::read(&target).map_err(|e| format!("Failed to read {}: {}", target, e))?;
+            if !is_sealed(&data) {
+                storagestate::validate(&data, &target)?;
+                return Ok(None);
+            }
+            let plain =
+                open(&data, &key()?).map_err(|e| format!("Cannot decrypt {}: {}", target, e))?;
+            storagestate::validate(&plain, &target)?;
+            write_private(&temp, &plain)?;
+            cmd.path = Some(temp.to_string_lossy().into_owned());
+            Ok(Some(Staged {
+                temp,
+                target,
+                sealing: false,
+            }))
+        }
+        _ => Ok(None),
+    }
+}
+
+/// Seal a saved state into place and remove the temporary copy, reporting
+/// the file the user named
+pub fn finish(staged: Staged, resp: &mut Response) -> Result<(), String> {
+    let sealed = if staged.sealing && resp.success {
+        fs::read(&staged.temp)
+            .map_err(|e| format!("Failed to read {}: {}", staged.temp.display(), e))
+            .and_then(|plain| seal(&plain, &key()?))
+            .and_then(|sealed| {
+                fs::write(&staged.target, sealed)
+                    .map_err(|e| format!("Failed to write {}: {}", staged.target, e))
+            })
+    } else {
+        Ok(())
+    };
+    fs::remove_file(&staged.temp).ok();
+    sealed?;
+
+    if let Some(result) = resp.result.as_mut().and_then(|r| r.as_object_mut()) {
+        let field = if staged.sealing { "saved" } else { "loaded" };
+        result.insert(field.to_string(), json!(staged.target));
+        result.insert("encrypted".to_string(), json!(true));
+    }
+    Ok(())
+}
+
+/// Create (or truncate) a file only the current user can read
+fn write_private(path: &Path, data: &[u8]) -> Result<(), String> {
+    let mut options = OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options
+        .open(path)
+        .and_then(|mut file| file.write_all(data))
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+fn random_bytes(len: usize) -> Result<Vec<u8>, String> {
+    let mut bytes = vec![0u8; len];
+    getrandom::getrandom(&mut bytes).map_err(|e| format!("Failed to get random bytes: {}", e))?;
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY: [u8; 32] = [
+        0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22, 23, 24,
+        25, 26, 27, 28, 29, 30, 31,
+    ];
+
+    fn hex(text: &str) -> Vec<u8> {
+        let digits: Vec<u8> = text
+            .bytes()
+            .filter(u8::is_ascii_hexdigit)
+            .map(|c| (c as char).to_digit(16).unwrap() as u8)
+            .collect();
+        digits
+            .chunks(2)
+            .map(|pair| pair[0] << 4 | pair[1])
+            .collect()
+    }
+
+    #[test]
+    fn opens_files_sealed_by_earlier_versions() {
+        let sealed = hex(
+            "4142505345414c31070707070707070707070707dd76fec05c5936f1cfb3de89
+             51b6b286cb9f53d087333f295f6ba145c62794",
+        );
+        assert_eq!(open(&sealed, &KEY).unwrap(), b"{\"cookies\": []}");
+    }
+
+    #[test]
+    fn seal_then_open() {
+        let plain = br#"{"cookies": [], "origins": []}"#;
+        let sealed = seal(plain, &KEY).unwrap();
+        assert!(is_sealed(&sealed));
+        assert_eq!(
+            sealed.len(),
+            MAGIC.len() + NONCE_LEN + plain.len() + TAG_LEN
+        );
+        assert_eq!(open(&sealed, &KEY).unwrap(), plain);
+        // A fresh nonce every time
+        assert_ne!(seal(plain, &KEY).unwrap(), sealed);
+        assert_eq!(open(&seal(b"", &KEY).unwrap(), &KEY).unwrap(), b"");
+    }
+
+    #[test]
+    fn wrong_key_is_refused() {
+        let sealed = seal(b"secret", &KEY).unwrap();
+        let mut other = KEY;
+        other[31] ^= 1;
+        assert!(open(&sealed, &other).is_err());
+    }
+
+    #[test]
+    fn any_altered_byte_is_refused() {
+        let sealed = seal(b"{\"cookies\": []}", &KEY).unwrap();
+        // The magic is checked as a prefix; every byte after it is
+        // authenticated, nonce included
+        for i in MAGIC.len()..sealed.len() {
+            let mut altered = sealed.clone();
+            altered[i] ^= 0x80;
+            assert!(open(&altered, &KEY).is_err(), "byte {} altered", i);
+        }
+    }
+
+    #[test]
+    fn truncated_or_plain_files_are_refused() {
+        let sealed = seal(b"secret", &KEY).unwrap();
+        assert!(open(&sealed[..sealed.len() - 1], &KEY).is_err());
+        assert!(open(&sealed[..MAGIC.len() + NONCE_LEN + TAG_LEN - 1], &KEY).is_err());
+        assert!(open(b"{\"cookies\": []}", &KEY).is_err());
+        assert!(!is_sealed(b"{\"cookies\": []}"));
+    }
+}
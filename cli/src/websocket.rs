@@ -0,0 +1,71 @@
+/*!
+ * WebSocket Client
+ *
+ * A blocking text-message channel to a local Chromium DevTools endpoint,
+ * on top of tungstenite: it performs the `ws://` handshake (checking the
+ * server's `Sec-WebSocket-Accept`), masks outgoing frames with random keys,
+ * reassembles fragments and answers pings. DevTools messages carry whole
+ * screenshots, so the message size limits are lifted.
+ */
+use std::net::TcpStream;
+use std::time::Duration;
+
+use tungstenite::error::Error;
+use tungstenite::protocol::WebSocketConfig;
+use tungstenite::Message;
+
+pub struct WebSocket {
+    socket: tungstenite::WebSocket<TcpStream>,
+}
+
+impl WebSocket {
+    /// Open `ws://host:port/path` and complete the upgrade handshake
+    pub fn connect(url: &str) -> Result<WebSocket, String> {
+        let rest = url
+            .strip_prefix("ws://")
+            .ok_or_else(|| format!("Unsupported WebSocket URL: {}", url))?;
+        let host = rest.split('/').next().unwrap_or(rest);
+        let stream = TcpStream::connect(host)
+            .map_err(|e| format!("Failed to connect to {}: {}", host, e))?;
+        stream.set_nodelay(true).ok();
+
+        let config = WebSocketConfig {
+            max_message_size: None,
+            max_frame_size: None,
+            ..WebSocketConfig::default()
+        };
+        let (socket, _) = tungstenite::client::client_with_config(url, stream, Some(config))
+            .map_err(|e| format!("WebSocket handshake failed: {}", e))?;
+        Ok(WebSocket { socket })
+    }
+
+    /// Fail reads that wait longer than `timeout` for the next frame
+    pub fn set_read_timeout(&self, timeout: Duration) {
+        self.socket.get_ref().set_read_timeout(Some(timeout)).ok();
+    }
+
+    pub fn send_text(&mut self, text: &str) -> Result<(), String> {
+        self.socket
+            .send(Message::text(text))
+            .map_err(|e| format!("WebSocket send failed: {}", e))
+    }
+
+    /// Next complete text message
+    pub fn read_text(&mut self) -> Result<String, String> {
+        loop {
+            match self.socket.read() {
+                Ok(Message::Text(text)) => return Ok(text),
+                Ok(Message::Binary(data)) => {
+                    return String::from_utf8(data)
+                        .map_err(|_| "WebSocket message is not UTF-8".to_string())
+                }
+                Ok(Message::Close(_)) | Err(Error::ConnectionClosed | Error::AlreadyClosed) => {
+                    return Err("Browser closed the DevTools connection".to_string())
+                }
+                // Pings are answered by tungstenite on the next read or write
+                Ok(_) => {}
+                Err(e) => return Err(format!("WebSocket read failed: {}", e)),
+            }
+        }
+    }
+}
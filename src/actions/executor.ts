@@ -4,10 +4,12 @@
  */
 
 import type { Page, Frame, Locator, Response as PlaywrightResponse, Download, BrowserContext } from 'playwright-core';
-import type { BrowserManager } from '../browser/manager.js';
+import type { BrowserManager, DeviceDescriptor } from '../browser/manager.js';
 import type { Command, Response } from '../core/protocol.js';
 import { successResponse, errorResponse } from '../core/protocol.js';
+import { PolicyViolationError } from '../utils/errors.js';
 import { getEnhancedSnapshot, getFullDOMTree } from '../dom/snapshot.js';
+import * as fs from 'fs';
 
 // ============================================================================
 // AI-Friendly Error Transformation
@@ -84,23 +86,116 @@ export function toAIFriendlyError(error: unknown, selector: string): Error {
   return error instanceof Error ? error : new Error(message);
 }
 
+/** Marker on the modal that 'snapshot --active-modal' scoped to */
+const ACTIVE_MODAL = '[data-agentbrowser-modal]';
+
+// ============================================================================
+// Pause Banner
+// ============================================================================
+
+/**
+ * Show the `pause` banner (once) at the top of the page and report whether
+ * its Continue button has been clicked. Runs in the page.
+ */
+function showPauseBanner(message: string): boolean {
+  let banner = document.querySelector<HTMLElement>('[data-agentbrowser-pause]');
+  if (!banner) {
+    banner = document.createElement('div');
+    banner.setAttribute('data-agentbrowser-pause', '');
+    banner.style.cssText =
+      'position:fixed;top:0;left:0;right:0;z-index:2147483647;display:flex;' +
+      'align-items:center;gap:12px;padding:10px 16px;background:#1f2937;color:#fff;' +
+      'font:14px/1.4 system-ui,sans-serif;box-shadow:0 2px 8px rgba(0,0,0,.3)';
+    const text = document.createElement('span');
+    text.style.flex = '1';
+    text.textContent = `\u23F8 ${message}`;
+    const button = document.createElement('button');
+    button.textContent = 'Continue';
+    button.style.cssText =
+      'padding:6px 14px;border:0;border-radius:4px;background:#10b981;color:#fff;' +
+      'font:inherit;cursor:pointer';
+    const target = banner;
+    button.addEventListener('click', () => {
+      target.dataset.continued = 'true';
+      target.style.display = 'none';
+    });
+    banner.append(text, button);
+    document.documentElement.appendChild(banner);
+  }
+  return banner.dataset.continued === 'true';
+}
+
+// ============================================================================
+// Structured Text
+// ============================================================================
+
+interface OutlineLink {
+  text: string;
+  href: string;
+}
+
+interface OutlineBlock {
+  type: 'heading' | 'paragraph' | 'list' | 'code' | 'quote' | 'table';
+  level?: number;
+  text?: string;
+  ordered?: boolean;
+  items?: string[];
+  rows?: string[][];
+  links?: OutlineLink[];
+}
+
+/**
+ * The page function behind `text --structured`, shared with the native
+ * daemon in src/dom/outline-text.js. Rebuilt from that source so Playwright
+ * serializes the function itself.
+ */
+const OUTLINE_TEXT = fs.readFileSync(new URL('../dom/outline-text.js', import.meta.url), 'utf8');
+const outlineText: (root: Element) => OutlineBlock[] = new Function(
+  `${OUTLINE_TEXT}\nreturn outlineText;`
+)();
+
 // ============================================================================
 // Action Executor Class
 // ============================================================================
 
 export class ActionExecutor {
+  // Start times of the current and previous command (health-check pings
+  // excluded), so waits can see responses that arrived in between
+  private currentCommandAt = 0;
+  private previousCommandAt = 0;
+  // Ends the running `pause`, saying who continued it
+  private endPause: ((by: string) => void) | null = null;
+
   constructor(private browser: BrowserManager) {}
 
   /**
    * Execute a command and return a response
    */
   async execute(command: Command): Promise<Response> {
+    if (command.id !== 'ping') {
+      this.previousCommandAt = this.currentCommandAt;
+      this.currentCommandAt = Date.now();
+    }
     try {
       const result = await this.executeAction(command);
+      // Say which dialogs the command ran into and how they were answered
+      const dialogs = command.id === 'ping' ? [] : this.browser.takeHandledDialogs();
+      if (dialogs.length > 0 && result && typeof result === 'object' && !Array.isArray(result)) {
+        return successResponse(command.id, { ...result, dialogs });
+      }
       return successResponse(command.id, result);
     } catch (error) {
+      if (error instanceof PolicyViolationError) {
+        return errorResponse(command.id, error.message, 'policy_violation');
+      }
       const selector = 'selector' in command ? String(command.selector) : '';
       const friendlyError = toAIFriendlyError(error, selector);
+      if (selector && String(error).includes('intercepts pointer events')) {
+        const interceptedBy = await this.findInterceptor(selector).catch(() => null);
+        return errorResponse(command.id, friendlyError.message, 'click_intercepted', {
+          interceptedBy,
+        });
+      }
       return errorResponse(command.id, friendlyError.message);
     }
   }
@@ -133,10 +228,18 @@ export class ActionExecutor {
 
       // ============ Navigation ============
       case 'navigate':
+        this.browser.checkUrl(command.url);
         const navResponse = await this.browser.getPage().goto(command.url, {
           waitUntil: command.waitUntil ?? 'load',
           timeout: command.timeout,
         });
+        // A redirect can still land somewhere the rules keep the session from
+        try {
+          this.browser.checkUrl(this.browser.getPage().url());
+        } catch (error) {
+          await this.browser.getPage().goto('about:blank');
+          throw error;
+        }
         return {
           url: this.browser.getPage().url(),
           status: navResponse?.status(),
@@ -264,6 +367,12 @@ export class ActionExecutor {
         }
         return { pressed: command.key };
 
+      case 'keyDown':
+        return { held: command.key, holding: await this.browser.keyDown(command.key) };
+
+      case 'keyUp':
+        return { released: command.key, holding: await this.browser.keyUp(command.key) };
+
       case 'scroll':
         if (command.selector) {
           const scrollLocator = this.browser.getLocator(command.selector);
@@ -299,16 +408,31 @@ export class ActionExecutor {
         }
         return { scrolled: true };
 
-      case 'drag':
-        await this.browser.getLocator(command.source).dragTo(
-          this.browser.getLocator(command.target),
-          {
+      case 'drag': {
+        const source = this.browser.getLocator(command.source);
+        if (command.target) {
+          await source.dragTo(this.browser.getLocator(command.target), {
             force: command.force,
             noWaitAfter: command.noWaitAfter,
             timeout: command.timeout,
-          }
-        );
-        return { dragged: { from: command.source, to: command.target } };
+          });
+          return { dragged: { from: command.source, to: command.target } };
+        }
+        if (command.toX === undefined || command.toY === undefined) {
+          throw new Error('drag needs a target selector, or both toX and toY');
+        }
+        // Coordinate drops go through the mouse, in steps so drag libraries
+        // listening for pointer moves see the gesture
+        await source.scrollIntoViewIfNeeded({ timeout: command.timeout });
+        const box = await source.boundingBox({ timeout: command.timeout });
+        if (!box) throw new Error(`Element is not visible: ${command.source}`);
+        const mouse = this.browser.getPage().mouse;
+        await mouse.move(box.x + box.width / 2, box.y + box.height / 2);
+        await mouse.down();
+        await mouse.move(command.toX, command.toY, { steps: 10 });
+        await mouse.up();
+        return { dragged: { from: command.source, to: { x: command.toX, y: command.toY } } };
+      }
 
       case 'upload':
         await this.browser.getLocator(command.selector).setInputFiles(command.files, {
@@ -318,21 +442,30 @@ export class ActionExecutor {
         return { uploaded: command.files };
 
       // ============ Information ============
-      case 'snapshot':
-        const snapshot = await getEnhancedSnapshot(this.browser.getPage(), {
-          selector: command.selector,
+      case 'snapshot': {
+        const page = this.browser.getPage();
+        const modal = command.activeModal ? await this.markActiveModal() : undefined;
+        const snapshot = await getEnhancedSnapshot(page, {
+          selector: modal ? ACTIVE_MODAL : command.selector,
           interactive: command.interactive,
           depth: command.depth,
           includeHidden: command.includeHidden,
           compact: command.compact,
         });
+        // Resolve the refs inside the modal, not against same-named
+        // elements behind it; the marker stays until the next snapshot
+        if (modal) {
+          for (const data of Object.values(snapshot.refs)) data.scope = ACTIVE_MODAL;
+        }
         this.browser.setRefMap(snapshot.refs);
         return {
           tree: snapshot.tree,
           refs: snapshot.refs,
-          url: this.browser.getPage().url(),
-          title: await this.browser.getPage().title(),
+          url: page.url(),
+          title: await page.title(),
+          ...(modal ? { modal } : {}),
         };
+      }
 
       case 'screenshot':
         const screenshotOptions: Parameters<Page['screenshot']>[0] = {
@@ -343,11 +476,21 @@ export class ActionExecutor {
           timeout: command.timeout,
         };
 
+        // Print preview: render with print stylesheets, then go back to screen
+        if (command.emulatePrint) {
+          await this.browser.getPage().emulateMedia({ media: 'print' });
+        }
         let screenshotBuffer: Buffer;
-        if (command.selector) {
-          screenshotBuffer = await this.browser.getLocator(command.selector).screenshot(screenshotOptions);
-        } else {
-          screenshotBuffer = await this.browser.getPage().screenshot(screenshotOptions);
+        try {
+          if (command.selector) {
+            screenshotBuffer = await this.browser.getLocator(command.selector).screenshot(screenshotOptions);
+          } else {
+            screenshotBuffer = await this.browser.getPage().screenshot(screenshotOptions);
+          }
+        } finally {
+          if (command.emulatePrint) {
+            await this.browser.getPage().emulateMedia({ media: null });
+          }
         }
 
         if (command.path) {
@@ -359,11 +502,96 @@ export class ActionExecutor {
         return { data: screenshotBuffer.toString('base64') };
 
       case 'getText':
+        if (command.structured) {
+          return {
+            outline: await this.browser
+              .getLocator(command.selector)
+              .evaluate(outlineText, undefined, { timeout: command.timeout }),
+          };
+        }
         const text = await this.browser.getLocator(command.selector).textContent({
           timeout: command.timeout,
         });
         return { text: text ?? '' };
 
+      // Hover, then poll for what the hover revealed: the element's
+      // aria-describedby target, a visible role=tooltip or open popover
+      // (nearest to the element wins), or its native title as a last resort
+      case 'getTooltip': {
+        const locator = this.browser.getLocator(command.selector);
+        await locator.hover({ timeout: command.timeout });
+        const deadline = Date.now() + (command.timeout ?? 3000);
+        const started = Date.now();
+        for (;;) {
+          // Native title tooltips never reach the DOM, so only settle for the
+          // title once a rendered tooltip has had time to appear
+          const allowTitle = Date.now() - started > 500 || Date.now() >= deadline;
+          const found = await locator.evaluate((el, allowTitle) => {
+            const visible = (node: Element) => {
+              const style = getComputedStyle(node);
+              const rect = node.getBoundingClientRect();
+              return (
+                style.display !== 'none' &&
+                style.visibility !== 'hidden' &&
+                Number(style.opacity) > 0 &&
+                rect.width > 0 &&
+                rect.height > 0
+              );
+            };
+            const textOf = (node: Element) =>
+              ((node as HTMLElement).innerText ?? node.textContent ?? '').trim();
+
+            // The trigger is often a wrapper around the described element
+            const described = [el, ...Array.from(el.querySelectorAll('[aria-describedby]'))];
+            for (let node: Element | null = el.parentElement; node; node = node.parentElement) {
+              if (node.hasAttribute('aria-describedby')) described.push(node);
+            }
+            for (const node of described) {
+              for (const id of (node.getAttribute('aria-describedby') ?? '').split(/\s+/)) {
+                const target = id ? document.getElementById(id) : null;
+                if (target && visible(target) && textOf(target)) {
+                  return { text: textOf(target), source: 'describedby' };
+                }
+              }
+            }
+
+            const box = el.getBoundingClientRect();
+            const distance = (node: Element) => {
+              const r = node.getBoundingClientRect();
+              const dx = Math.max(0, r.left - box.right, box.left - r.right);
+              const dy = Math.max(0, r.top - box.bottom, box.top - r.bottom);
+              return Math.hypot(dx, dy);
+            };
+            const candidates: { node: Element; source: string }[] = [];
+            for (const node of Array.from(document.querySelectorAll('[role="tooltip"]'))) {
+              candidates.push({ node, source: 'tooltip' });
+            }
+            for (const node of Array.from(document.querySelectorAll('[popover]'))) {
+              if (node.matches(':popover-open')) candidates.push({ node, source: 'popover' });
+            }
+            const nearest = candidates
+              .filter(({ node }) => visible(node) && textOf(node))
+              .sort((a, b) => distance(a.node) - distance(b.node))[0];
+            if (nearest) return { text: textOf(nearest.node), source: nearest.source };
+
+            if (allowTitle) {
+              const titled = el.closest('[title]') ?? el.querySelector('[title]');
+              const title = titled?.getAttribute('title')?.trim();
+              if (title) return { text: title, source: 'title' };
+            }
+            return null;
+          }, allowTitle);
+          if (found) return found;
+          if (Date.now() >= deadline) {
+            throw new Error(
+              `No tooltip appeared after hovering ${command.selector}. ` +
+                'It may open on click or focus instead, or need a longer --timeout.'
+            );
+          }
+          await this.browser.getPage().waitForTimeout(100);
+        }
+      }
+
       case 'getHtml':
         if (command.selector) {
           if (command.outer) {
@@ -427,6 +655,48 @@ export class ActionExecutor {
         await new Promise((resolve) => setTimeout(resolve, command.timeout));
         return { waited: command.timeout };
 
+      case 'pause': {
+        if (this.endPause) {
+          throw new Error("Already paused. Run 'resume' to continue the other pause first.");
+        }
+        const started = Date.now();
+        const state: { by: string | null } = { by: null };
+        this.endPause = (by) => {
+          state.by = by;
+        };
+        try {
+          while (state.by === null) {
+            if (command.timeout !== undefined && Date.now() - started >= command.timeout) {
+              throw new Error(`Nobody continued within ${command.timeout}ms`);
+            }
+            // Headless pages have no one to click; navigation drops the
+            // banner, so it is put back each round
+            if (this.browser.isHeaded()) {
+              const clicked = await this.browser
+                .getPage()
+                .evaluate(showPauseBanner, command.message ?? 'The agent is waiting for you')
+                .catch(() => false);
+              if (clicked) state.by = 'page';
+            }
+            await new Promise((resolve) => setTimeout(resolve, 250));
+          }
+        } finally {
+          this.endPause = null;
+          await this.browser
+            .getPage()
+            .evaluate(() => document.querySelector('[data-agentbrowser-pause]')?.remove())
+            .catch(() => {});
+        }
+        return { resumed: true, by: state.by, waited: Date.now() - started };
+      }
+
+      case 'resume':
+        if (!this.endPause) {
+          throw new Error('Nothing is paused');
+        }
+        this.endPause(command.by ?? 'command');
+        return { resumed: true };
+
       case 'waitForSelector':
         await this.browser.getLocator(command.selector).waitFor({
           state: command.state,
@@ -453,6 +723,25 @@ export class ActionExecutor {
         });
         return { url: this.browser.getPage().url() };
 
+      case 'waitForResponse': {
+        const response = await this.browser.waitForResponse(command.urlPattern, {
+          status: command.status,
+          timeout: command.timeout,
+          since: this.previousCommandAt,
+        });
+        const body = await response.text().catch(() => '');
+        const preview = 2000;
+        return {
+          url: response.url(),
+          method: response.request().method(),
+          status: response.status(),
+          contentType: response.headers()['content-type'] ?? null,
+          bytes: Buffer.byteLength(body),
+          body: body.slice(0, preview),
+          truncated: body.length > preview,
+        };
+      }
+
       case 'waitForText':
         if (command.selector) {
           await this.browser.getLocator(command.selector).filter({ hasText: command.text }).waitFor({
@@ -528,32 +817,49 @@ export class ActionExecutor {
         return { offline: command.offline };
 
       case 'route':
-        await this.browser.getPage().route(command.url, async (route) => {
-          switch (command.handler) {
-            case 'abort':
-              await route.abort();
-              break;
-            case 'continue':
-              await route.continue();
-              break;
-            case 'fulfill':
-              await route.fulfill({
-                status: command.response?.status,
-                headers: command.response?.headers,
-                body: command.response?.body,
-              });
-              break;
-          }
-        });
-        return { routed: command.url };
+        return {
+          route: await this.browser.addRoute(command.url, command.handler, command.response),
+        };
 
-      case 'unroute':
-        if (command.url) {
-          await this.browser.getPage().unroute(command.url);
-        } else {
-          await this.browser.getPage().unrouteAll();
+      case 'unroute': {
+        const unrouted = await this.browser.removeRoute(command.url);
+        if (command.url && unrouted.length === 0) {
+          throw new Error(`No route for ${command.url}. Run 'routes' to list active routes.`);
         }
-        return { unrouted: true };
+        return { unrouted };
+      }
+
+      case 'getRoutes':
+        return { routes: this.browser.getRoutes() };
+
+      case 'harStart':
+        this.browser.startHar(command.path, command.content ?? false);
+        return { recording: true, path: command.path };
+
+      case 'harStop': {
+        const fs = await import('fs');
+        const pathModule = await import('path');
+        const { path, har, entries } = await this.browser.stopHar();
+        const json = JSON.stringify(har, null, 2);
+        await fs.promises.mkdir(pathModule.dirname(path), { recursive: true });
+        await fs.promises.writeFile(path, json);
+        return { path, entries, size: Buffer.byteLength(json) };
+      }
+
+      // ============ Downloads ============
+      case 'download': {
+        const [download] = await Promise.all([
+          this.browser.getPage().waitForEvent('download', { timeout: command.timeout }),
+          this.browser.getLocator(command.selector).click({ timeout: command.timeout }),
+        ]);
+        return this.browser.saveDownload(download, {
+          file: command.path,
+          dir: command.downloadsDir,
+        });
+      }
+
+      case 'getDownloads':
+        return { downloads: await this.browser.getDownloads(command.downloadsDir) };
 
       // ============ Cookies/Storage ============
       case 'getCookies':
@@ -602,30 +908,52 @@ export class ActionExecutor {
 
       // ============ Dialog ============
       case 'handleDialog':
-        // This needs to be set up before the dialog appears
-        this.browser.getPage().once('dialog', async (dialog) => {
-          if (command.accept) {
-            await dialog.accept(command.promptText);
-          } else {
-            await dialog.dismiss();
-          }
-        });
-        return { handler: 'set' };
+        // Answers the next dialog; one already answered stays answered
+        this.browser.armDialog(command.accept, command.promptText);
+        return { armed: command.accept ? 'accept' : 'dismiss', promptText: command.promptText };
+
+      case 'setDialogAuto':
+        this.browser.setDialogAuto(command.mode);
+        return { auto: command.mode };
 
       // ============ Viewport ============
       case 'setViewport':
-        await this.browser.getPage().setViewportSize(command.viewport);
+        await this.browser.setViewport(command.viewport);
         return { viewport: command.viewport };
 
-      case 'emulateDevice':
-        // Get device from playwright
-        const playwright = await import('playwright-core');
-        const device = (playwright.devices as Record<string, any>)[command.device];
-        if (!device) {
-          throw new Error(`Unknown device: ${command.device}`);
+      case 'emulateDevice': {
+        let device: DeviceDescriptor;
+        if (command.viewport) {
+          device = {
+            name: command.device,
+            viewport: command.viewport,
+            deviceScaleFactor: command.deviceScaleFactor ?? 1,
+            isMobile: command.isMobile ?? false,
+            hasTouch: command.hasTouch ?? false,
+            userAgent: command.userAgent ?? '',
+          };
+        } else {
+          const playwright = await import('playwright-core');
+          const preset = (playwright.devices as Record<string, any>)[command.device];
+          if (!preset) {
+            throw new Error(`Unknown device: ${command.device}`);
+          }
+          device = { name: command.device, ...preset };
         }
-        await this.browser.getPage().setViewportSize(device.viewport);
-        return { device: command.device };
+        await this.browser.emulateDevice(device);
+        return {
+          device: device.name,
+          viewport: device.viewport,
+          deviceScaleFactor: device.deviceScaleFactor,
+          isMobile: device.isMobile,
+          hasTouch: device.hasTouch,
+          userAgent: device.userAgent,
+        };
+      }
+
+      case 'resetDevice':
+        await this.browser.resetDevice();
+        return { device: null };
 
       case 'setGeolocation':
         await this.browser.getPage().context().setGeolocation({
@@ -669,6 +997,15 @@ export class ActionExecutor {
       case 'stopStream':
         return { stopped: true };
 
+      // ============ Extensions ============
+      case 'listExtensions':
+        return { extensions: this.browser.getExtensions() };
+
+      // ============ Daemon ============
+      case 'daemonStats':
+        // Stats are answered by the daemon before reaching the executor
+        return { info: 'Handled by the daemon' };
+
       // ============ Agent ============
       case 'agentRun':
         // Agent is handled by the Agent service
@@ -890,6 +1227,64 @@ export class ActionExecutor {
         });
         return { emulated: true };
 
+      case 'emulateVision': {
+        // Chromium-only; the override lasts as long as the CDP session
+        const visionPage = this.browser.getPage();
+        const visionCdp = await visionPage.context().newCDPSession(visionPage);
+        await visionCdp.send('Emulation.setEmulatedVisionDeficiency', { type: command.vision });
+        return { vision: command.vision };
+      }
+
+      case 'emulateOrientation': {
+        const orientationPage = this.browser.getPage();
+        const orientationCdp = await orientationPage.context().newCDPSession(orientationPage);
+        if (command.reading) {
+          const [alpha, beta, gamma] = command.reading;
+          await orientationCdp.send('DeviceOrientation.setDeviceOrientationOverride', {
+            alpha,
+            beta,
+            gamma,
+          });
+          return { orientation: { alpha, beta, gamma } };
+        }
+        // Rotate the viewport so its long side matches the orientation
+        const size = orientationPage.viewportSize() ?? { width: 390, height: 844 };
+        const landscape = command.orientation === 'landscape';
+        const [short, long] = [size.width, size.height].sort((a, b) => a - b);
+        await orientationCdp.send('Emulation.setDeviceMetricsOverride', {
+          width: landscape ? long : short,
+          height: landscape ? short : long,
+          deviceScaleFactor: 0,
+          mobile: true,
+          screenOrientation: landscape
+            ? { type: 'landscapePrimary', angle: 90 }
+            : { type: 'portraitPrimary', angle: 0 },
+        });
+        return { orientation: command.orientation };
+      }
+
+      case 'emulateAccelerometer': {
+        const motionPage = this.browser.getPage();
+        const motionCdp = await motionPage.context().newCDPSession(motionPage);
+        if (!command.reading) {
+          await motionCdp.send('Emulation.setSensorOverrideEnabled', {
+            enabled: false,
+            type: 'accelerometer',
+          });
+          return { accelerometer: null };
+        }
+        const [x, y, z] = command.reading;
+        await motionCdp.send('Emulation.setSensorOverrideEnabled', {
+          enabled: true,
+          type: 'accelerometer',
+        });
+        await motionCdp.send('Emulation.setSensorOverrideReadings', {
+          type: 'accelerometer',
+          reading: { xyz: { x, y, z } },
+        });
+        return { accelerometer: { x, y, z } };
+      }
+
       // ============ Tier 2: Vision/Screenshot Analysis ============
       case 'analyzeScreenshot':
         const analysisScreenshot = command.selector
@@ -992,9 +1387,120 @@ export class ActionExecutor {
           requests: this.browser.getNetworkRequests({
             urlPattern: command.urlPattern,
             clear: command.clear,
+            graphql: command.graphql,
+            operation: command.operation,
+            includeBody: command.includeBody,
           }),
         };
 
+      case 'expectRequest': {
+        const { requests, urlMatches } = this.browser.findRequests(command.urlPattern, {
+          method: command.method,
+          bodyContains: command.bodyContains,
+        });
+        if (requests.length === 0) {
+          const wanted = [
+            command.method,
+            command.urlPattern,
+            command.bodyContains !== undefined ? `with body containing "${command.bodyContains}"` : '',
+          ]
+            .filter(Boolean)
+            .join(' ');
+          throw new Error(
+            urlMatches > 0
+              ? `Expected a request ${wanted}: ${urlMatches} request(s) matched the URL but not the method or body`
+              : `Expected a request ${wanted}, but none was made`
+          );
+        }
+        const { responseBody: _body, ...first } = requests[0];
+        return { matched: requests.length, request: first };
+      }
+
+      // Poll until the page meets the expectation, like a test runner's
+      // expect; on timeout the error shows what was expected against what
+      // the page last had
+      case 'expectVisible':
+      case 'expectText':
+      case 'expectUrl':
+      case 'expectCount': {
+        const page = this.browser.getPage();
+        const timeout = command.timeout ?? 5000;
+        const started = Date.now();
+        const quote = (text: string) => JSON.stringify(text.replace(/\s+/g, ' ').trim());
+        for (;;) {
+          let expected: string;
+          let actual: string;
+          let pass: boolean;
+          if (command.action === 'expectUrl') {
+            expected = `URL matching /${command.urlPattern}/`;
+            actual = page.url();
+            pass = new RegExp(command.urlPattern).test(actual);
+          } else {
+            const locator = this.browser.getLocator(command.selector);
+            const count = await locator.count();
+            if (command.action === 'expectCount') {
+              expected = `${command.count} element(s) matching ${command.selector}`;
+              actual = `${count}`;
+              pass = count === command.count;
+            } else if (command.action === 'expectVisible') {
+              const visible = await Promise.all(
+                (await locator.all()).map((el) => el.isVisible().catch(() => false))
+              );
+              expected = `${command.selector} visible`;
+              actual = count === 0 ? 'no matching element' : `${count} match(es), none visible`;
+              pass = visible.some(Boolean);
+              if (pass) actual = 'visible';
+            } else {
+              const wanted = command.text.replace(/\s+/g, ' ').trim();
+              const text = count
+                ? await locator
+                    .first()
+                    .innerText({ timeout: 1000 })
+                    .catch(() => '')
+                : null;
+              const normalized = text?.replace(/\s+/g, ' ').trim() ?? '';
+              expected = `${command.selector} ${command.exact ? 'to have text' : 'to contain'} ${quote(wanted)}`;
+              actual = text === null ? 'no matching element' : quote(normalized);
+              pass = text !== null && (command.exact ? normalized === wanted : normalized.includes(wanted));
+            }
+          }
+          const elapsed = Date.now() - started;
+          if (pass) return { expected, actual, elapsed };
+          if (elapsed >= timeout) {
+            throw new Error(
+              `Expectation failed after ${timeout}ms\n` +
+                `  - expected: ${expected}\n` +
+                `  + actual:   ${actual}`
+            );
+          }
+          await page.waitForTimeout(100);
+        }
+      }
+
+      // ============ Lifecycle Events ============
+      case 'getEvents':
+        return this.browser.getEvents(command.since);
+
+      // ============ Notifications ============
+      case 'notificationCapture':
+        await this.browser.setNotificationCapture(command.enabled);
+        return { capture: command.enabled };
+
+      case 'getNotifications':
+        return {
+          notifications: this.browser.getNotifications(command.clear),
+        };
+
+      // ============ Messages ============
+      case 'tapMessages': {
+        await this.browser.tapMessages();
+        const messages = this.browser
+          .getMessages()
+          .filter((m) => !command.channel || m.channel === command.channel)
+          .filter((m) => m.timestamp >= (command.since ?? 0));
+        return { tapping: true, messages };
+      }
+
       // ============ Tier 3: New Window Management ============
       case 'newWindow':
         // Create a new context for a separate window
@@ -1139,6 +1645,1579 @@ export class ActionExecutor {
         };
       }
 
+      // Structured data: JSON-LD, OpenGraph and microdata
+      case 'getStructuredData': {
+        const data = await this.browser.getPage().evaluate(() => {
+          type Item = Record<string, unknown>;
+
+          // JSON-LD blocks may hold one item, an array or an @graph
+          const jsonLd: Item[] = [];
+          const errors: string[] = [];
+          document.querySelectorAll('script[type="application/ld+json"]').forEach((script) => {
+            try {
+              const parsed = JSON.parse(script.textContent || '');
+              for (const block of Array.isArray(parsed) ? parsed : [parsed]) {
+                if (block && Array.isArray(block['@graph'])) {
+                  jsonLd.push(...block['@graph']);
+                } else if (block && typeof block === 'object') {
+                  jsonLd.push(block);
+                }
+              }
+            } catch (e) {
+              errors.push(`Invalid JSON-LD: ${(e as Error).message}`);
+            }
+          });
+
+          // og:*, plus the article:/product:/... properties that go with it
+          const openGraph: Record<string, string | string[]> = {};
+          const twitter: Record<string, string> = {};
+          document.querySelectorAll('meta[property], meta[name]').forEach((meta) => {
+            const key = meta.getAttribute('property') || meta.getAttribute('name') || '';
+            const content = meta.getAttribute('content') ?? '';
+            if (/^(og|article|product|book|profile|music|video):/.test(key)) {
+              const name = key.replace(/^og:/, '');
+              const existing = openGraph[name];
+              openGraph[name] =
+                existing === undefined
+                  ? content
+                  : ([] as string[]).concat(existing, content);
+            } else if (key.startsWith('twitter:')) {
+              twitter[key.slice('twitter:'.length)] = content;
+            }
+          });
+
+          const propValue = (el: Element): unknown => {
+            if (el.hasAttribute('itemscope')) return readItem(el);
+            const tag = el.tagName.toLowerCase();
+            if (tag === 'meta') return el.getAttribute('content') ?? '';
+            if (['a', 'link', 'area'].includes(tag)) return (el as HTMLAnchorElement).href;
+            if (['img', 'audio', 'video', 'source', 'iframe', 'embed'].includes(tag)) {
+              return (el as HTMLImageElement).src;
+            }
+            if (tag === 'time') return el.getAttribute('datetime') || el.textContent?.trim();
+            if (tag === 'data' || tag === 'meter') return el.getAttribute('value');
+            return el.textContent?.trim().replace(/\s+/g, ' ') ?? '';
+          };
+
+          // Properties belong to the nearest itemscope above them
+          const readItem = (scope: Element): Item => {
+            const item: Item = {};
+            const types = (scope.getAttribute('itemtype') || '')
+              .split(/\s+/)
+              .filter(Boolean)
+              .map((t) => t.replace(/^https?:\/\/schema\.org\//, ''));
+            if (types.length) item['@type'] = types.length === 1 ? types[0] : types;
+            const id = scope.getAttribute('itemid');
+            if (id) item['@id'] = id;
+            scope.querySelectorAll('[itemprop]').forEach((el) => {
+              const owner = el.parentElement?.closest('[itemscope]');
+              if (owner !== scope) return;
+              const value = propValue(el);
+              for (const name of (el.getAttribute('itemprop') || '').split(/\s+/).filter(Boolean)) {
+                const existing = item[name];
+                item[name] = existing === undefined ? value : ([] as unknown[]).concat(existing, value);
+              }
+            });
+            return item;
+          };
+          const microdata = Array.from(
+            document.querySelectorAll('[itemscope]:not([itemprop])')
+          ).map(readItem);
+
+          return { jsonLd, openGraph, twitter, microdata, errors };
+        });
+
+        if (command.itemType) {
+          const wanted = command.itemType.toLowerCase();
+          const matches = (item: Record<string, unknown>) =>
+            ([] as unknown[])
+              .concat(item['@type'] ?? [])
+              .some((t) => String(t).toLowerCase().replace(/^https?:\/\/schema\.org\//, '') === wanted);
+          data.jsonLd = data.jsonLd.filter(matches);
+          data.microdata = data.microdata.filter(matches);
+        }
+
+        return {
+          url: this.browser.getPage().url(),
+          ...data,
+          counts: {
+            jsonLd: data.jsonLd.length,
+            openGraph: Object.keys(data.openGraph).length,
+            microdata: data.microdata.length,
+          },
+        };
+      }
+
+      // Meta/SEO audit with a pass/warn/fail verdict per check
+      case 'seoAudit': {
+        const facts = await this.browser.getPage().evaluate(() => {
+          const metas = (name: string) =>
+            Array.from(document.querySelectorAll(`meta[name="${name}" i]`)).map(
+              (m) => m.getAttribute('content') ?? ''
+            );
+          const images = Array.from(document.images);
+          const anchors = Array.from(
+            document.querySelectorAll('a[href*="#"]')
+          ) as HTMLAnchorElement[];
+          const here = location.href.split('#')[0];
+          const broken = anchors
+            .filter((a) => a.href.split('#')[0] === here)
+            .map((a) => decodeURIComponent(a.hash.slice(1)))
+            .filter((id) => id && id !== 'top')
+            .filter((id) => !document.getElementById(id) && !document.getElementsByName(id).length);
+          return {
+            title: document.title,
+            description: metas('description')[0] ?? null,
+            canonical: Array.from(document.querySelectorAll('link[rel="canonical" i]')).map(
+              (l) => (l as HTMLLinkElement).href
+            ),
+            robots: [...metas('robots'), ...metas('googlebot')].join(', ') || null,
+            h1: Array.from(document.querySelectorAll('h1')).map((h) => h.textContent?.trim() ?? ''),
+            images: images.length,
+            imagesWithoutAlt: images.filter((img) => !img.hasAttribute('alt')).map((img) => img.src),
+            brokenAnchors: Array.from(new Set(broken)).map((id) => `#${id}`),
+          };
+        });
+
+        type Status = 'pass' | 'warn' | 'fail';
+        const checks: { check: string; status: Status; message: string }[] = [];
+        const add = (check: string, status: Status, message: string) =>
+          checks.push({ check, status, message });
+
+        const titleLength = facts.title.length;
+        if (!titleLength) add('title', 'fail', 'Missing <title>');
+        else if (titleLength < 30 || titleLength > 60)
+          add('title', 'warn', `${titleLength} characters (aim for 30-60)`);
+        else add('title', 'pass', `${titleLength} characters`);
+
+        const description = facts.description?.trim() ?? '';
+        if (!description) add('description', 'fail', 'Missing meta description');
+        else if (description.length < 50 || description.length > 160)
+          add('description', 'warn', `${description.length} characters (aim for 50-160)`);
+        else add('description', 'pass', `${description.length} characters`);
+
+        if (facts.canonical.length === 0) add('canonical', 'warn', 'No canonical link');
+        else if (facts.canonical.length > 1)
+          add('canonical', 'fail', `${facts.canonical.length} canonical links`);
+        else add('canonical', 'pass', facts.canonical[0]);
+
+        if (facts.robots && /noindex|none/i.test(facts.robots))
+          add('robots', 'warn', `Not indexable: ${facts.robots}`);
+        else add('robots', 'pass', facts.robots ?? 'No robots directives');
+
+        if (facts.h1.length === 0) add('h1', 'fail', 'No <h1>');
+        else if (facts.h1.length > 1) add('h1', 'warn', `${facts.h1.length} <h1> elements`);
+        else add('h1', 'pass', facts.h1[0]);
+
+        const withAlt = facts.images - facts.imagesWithoutAlt.length;
+        const coverage = facts.images ? Math.round((withAlt / facts.images) * 100) : 100;
+        if (coverage < 100) {
+          const missing = facts.imagesWithoutAlt.length;
+          add('imageAlt', 'warn', `${missing} of ${facts.images} images lack alt (${coverage}% coverage)`);
+        } else add('imageAlt', 'pass', `${facts.images} images, all with alt`);
+
+        if (facts.brokenAnchors.length)
+          add('anchors', 'fail', `Broken in-page links: ${facts.brokenAnchors.join(', ')}`);
+        else add('anchors', 'pass', 'All in-page links have targets');
+
+        const count = (status: Status) => checks.filter((c) => c.status === status).length;
+        const summary = { pass: count('pass'), warn: count('warn'), fail: count('fail') };
+        return {
+          url: this.browser.getPage().url(),
+          status: summary.fail ? 'fail' : summary.warn ? 'warn' : 'pass',
+          summary,
+          checks,
+          ...facts,
+          imageAltCoverage: coverage,
+        };
+      }
+
+      // Response that delivered the current document
+      case 'getPageInfo': {
+        const page = this.browser.getPage();
+        const response = this.browser.getDocumentResponse();
+        if (!response) {
+          throw new Error(
+            `No document response recorded for ${page.url()}. ` +
+              `Run 'navigate' or 'reload' first so the daemon sees the response.`
+          );
+        }
+        const request = response.request();
+        const headers = await response.allHeaders();
+        const sizes = await request.sizes().catch(() => null);
+        const server = await response.serverAddr().catch(() => null);
+        const security = await response.securityDetails().catch(() => null);
+        // Navigation Timing has what Playwright doesn't expose: the protocol
+        // and the bytes actually transferred
+        const timing = await page
+          .evaluate(() => {
+            const entry = performance.getEntriesByType('navigation')[0] as
+              | PerformanceNavigationTiming
+              | undefined;
+            return entry
+              ? {
+                  protocol: entry.nextHopProtocol,
+                  transferSize: entry.transferSize,
+                  encodedBodySize: entry.encodedBodySize,
+                  decodedBodySize: entry.decodedBodySize,
+                }
+              : null;
+          })
+          .catch(() => null);
+
+        const redirects: Array<{ url: string; status: number | undefined }> = [];
+        for (let from = request.redirectedFrom(); from; from = from.redirectedFrom()) {
+          const redirect = await from.response();
+          redirects.unshift({ url: from.url(), status: redirect?.status() });
+        }
+
+        return {
+          url: response.url(),
+          status: response.status(),
+          statusText: response.statusText(),
+          ok: response.ok(),
+          contentType: headers['content-type'] ?? null,
+          protocol: timing?.protocol || null,
+          transferSize:
+            timing?.transferSize ||
+            (sizes ? sizes.responseHeadersSize + sizes.responseBodySize : null),
+          encodedBodySize: timing?.encodedBodySize ?? sizes?.responseBodySize ?? null,
+          decodedBodySize: timing?.decodedBodySize ?? null,
+          fromServiceWorker: response.fromServiceWorker(),
+          remoteAddress: server ? `${server.ipAddress}:${server.port}` : null,
+          tls: security
+            ? {
+                protocol: security.protocol,
+                issuer: security.issuer,
+                subject: security.subjectName,
+                validTo: security.validTo,
+              }
+            : null,
+          redirects,
+          headers,
+        };
+      }
+
+      // RSS/Atom/JSON Feed discovery on the current page
+      case 'getFeeds': {
+        const feeds = await this.browser.getPage().evaluate(() => {
+          const types: Record<string, string> = {
+            'application/rss+xml': 'rss',
+            'application/atom+xml': 'atom',
+            'application/feed+json': 'json',
+            'application/json': 'json',
+          };
+          type Feed = { url: string; title: string; format: string; source: string };
+          const found = new Map<string, Feed>();
+          document.querySelectorAll('link[rel~="alternate" i][type][href]').forEach((el) => {
+            const link = el as HTMLLinkElement;
+            const format = types[link.type.toLowerCase()];
+            if (format && !found.has(link.href)) {
+              found.set(link.href, { url: link.href, title: link.title, format, source: 'link' });
+            }
+          });
+          // Sites that don't advertise their feed often still link to it
+          document.querySelectorAll('a[href]').forEach((el) => {
+            const a = el as HTMLAnchorElement;
+            const path = a.pathname.toLowerCase();
+            const format = /atom(\.xml)?$/.test(path)
+              ? 'atom'
+              : /(\.rss|rss\.xml|\/feed\/?|\/rss\/?|feed\.xml)$/.test(path)
+                ? 'rss'
+                : null;
+            if (format && !found.has(a.href)) {
+              const title = a.textContent?.trim() ?? '';
+              found.set(a.href, { url: a.href, title, format, source: 'anchor' });
+            }
+          });
+          return Array.from(found.values());
+        });
+        return { url: this.browser.getPage().url(), feeds };
+      }
+
+      // Fetch a feed with the browser's cookies and normalize its entries
+      case 'fetchFeed': {
+        const page = this.browser.getPage();
+        const response = await page.context().request.get(command.url, {
+          timeout: command.timeout,
+        });
+        if (!response.ok()) {
+          throw new Error(`Feed request failed: ${response.status()} ${command.url}`);
+        }
+        const body = await response.text();
+
+        if (body.trimStart().startsWith('{')) {
+          const json = JSON.parse(body);
+          const items: Record<string, unknown>[] = Array.isArray(json.items) ? json.items : [];
+          return {
+            url: command.url,
+            format: 'json',
+            title: json.title ?? null,
+            entries: items.map((item) => ({
+              title: item.title ?? null,
+              link: item.url ?? item.external_url ?? null,
+              id: item.id ?? null,
+              published: item.date_published ?? item.date_modified ?? null,
+              author: (item.author as { name?: string } | undefined)?.name ?? null,
+              summary: item.summary ?? item.content_text ?? null,
+            })),
+          };
+        }
+
+        // Parsed in the page, which has a real XML parser
+        const feed = await page.evaluate((xml: string) => {
+          const doc = new DOMParser().parseFromString(xml, 'application/xml');
+          if (doc.querySelector('parsererror')) {
+            throw new Error('Response is not an RSS, Atom or JSON feed');
+          }
+          const text = (parent: Element, ...names: string[]) => {
+            for (const name of names) {
+              const el = Array.from(parent.children).find(
+                (c) => c.localName === name || c.tagName === name
+              );
+              if (el?.textContent?.trim()) return el.textContent.trim();
+            }
+            return null;
+          };
+          const plain = (html: string | null) =>
+            html === null
+              ? null
+              : (new DOMParser().parseFromString(html, 'text/html').body.textContent ?? '')
+                  .replace(/\s+/g, ' ')
+                  .trim();
+
+          const root = doc.documentElement;
+          if (root.localName === 'feed') {
+            const entries = Array.from(root.children).filter((c) => c.localName === 'entry');
+            return {
+              format: 'atom',
+              title: text(root, 'title'),
+              entries: entries.map((entry) => {
+                const links = Array.from(entry.children).filter((c) => c.localName === 'link');
+                const link =
+                  links.find((l) => (l.getAttribute('rel') ?? 'alternate') === 'alternate') ??
+                  links[0];
+                const author = Array.from(entry.children).find((c) => c.localName === 'author');
+                return {
+                  title: text(entry, 'title'),
+                  link: link?.getAttribute('href') ?? null,
+                  id: text(entry, 'id'),
+                  published: text(entry, 'published', 'updated'),
+                  author: author ? text(author, 'name') : null,
+                  summary: plain(text(entry, 'summary', 'content')),
+                };
+              }),
+            };
+          }
+
+          const channel = root.querySelector('channel') ?? root;
+          const items = Array.from(root.getElementsByTagName('item'));
+          return {
+            format: 'rss',
+            title: text(channel, 'title'),
+            entries: items.map((item) => ({
+              title: text(item, 'title'),
+              link: text(item, 'link'),
+              id: text(item, 'guid'),
+              published: text(item, 'pubDate', 'dc:date', 'date'),
+              author: text(item, 'dc:creator', 'creator', 'author'),
+              summary: plain(text(item, 'description', 'content:encoded')),
+            })),
+          };
+        }, body);
+        return { url: command.url, ...feed };
+      }
+
+      // Click by what the user sees, resolved through the accessibility tree
+      case 'clickByText':
+        return this.clickFirstVisible(
+          this.browser.getPage().getByText(command.text, { exact: command.exact }),
+          `text "${command.text}"`,
+          command.timeout
+        );
+
+      case 'clickByRole':
+        return this.clickFirstVisible(
+          this.browser.getPage().getByRole(command.role as Parameters<Page['getByRole']>[0], {
+            name: command.name,
+            exact: command.exact,
+          }),
+          command.name ? `${command.role} "${command.name}"` : command.role,
+          command.timeout
+        );
+
+      // Keyboard-only navigation: Tab until focus lands on the target
+      case 'tabTo': {
+        const page = this.browser.getPage();
+        const wanted = command.text.toLowerCase();
+        const max = command.max ?? 50;
+        const stops: Array<{ role: string; name: string }> = [];
+        for (let presses = 1; presses <= max; presses++) {
+          await page.keyboard.press('Tab');
+          const { role, name } = await this.roleAndName(
+            this.browser.getActiveFrame().locator('*:focus').first()
+          );
+          stops.push({ role, name });
+          if (role.toLowerCase() === wanted || name.toLowerCase().includes(wanted)) {
+            return { presses, role, name, stops };
+          }
+        }
+        const last = stops[stops.length - 1];
+        throw new Error(
+          `Focus did not reach "${command.text}" after ${max} Tab presses ` +
+            `(last focused: ${last.role}${last.name ? ` "${last.name}"` : ''}). ` +
+            `The element may not be keyboard-focusable; raise --max if the page is long.`
+        );
+      }
+
+      case 'getFocused': {
+        const frame = this.browser.getActiveFrame();
+        const element = await frame.evaluate(() => {
+          const el = document.activeElement;
+          if (!el || el === document.body || el === document.documentElement) return null;
+          // Shortest selector: an id, else nth-of-type steps up to one
+          const steps: string[] = [];
+          for (let node: Element | null = el; node; node = node.parentElement) {
+            if (node.id) {
+              steps.unshift(`#${CSS.escape(node.id)}`);
+              break;
+            }
+            const tag = node.tagName;
+            const sameTag = node.parentElement
+              ? Array.from(node.parentElement.children).filter((c) => c.tagName === tag)
+              : [];
+            steps.unshift(
+              sameTag.length > 1
+                ? `${tag.toLowerCase()}:nth-of-type(${sameTag.indexOf(node) + 1})`
+                : tag.toLowerCase()
+            );
+          }
+          return { tag: el.tagName.toLowerCase(), selector: steps.join(' > ') };
+        });
+        if (!element) {
+          return { role: null, name: null, tag: null, selector: null, ref: null };
+        }
+        const { role, name } = await this.roleAndName(frame.locator('*:focus').first());
+        const ref = await this.findRef(role, name, (el) => el === document.activeElement);
+        return { role, name, ...element, ref };
+      }
+
+      // Hover a chain of elements in one round-trip, so nested hover menus
+      // don't close in the gap between separate commands
+      case 'hoverPath': {
+        const dwell = command.dwell ?? [];
+        const page = this.browser.getPage();
+        for (const [i, selector] of command.selectors.entries()) {
+          try {
+            await this.browser.getLocator(selector).hover({ timeout: command.timeout });
+          } catch (error) {
+            throw new Error(
+              `Step ${i + 1} of ${command.selectors.length}: ` +
+                toAIFriendlyError(error, selector).message
+            );
+          }
+          // Dwell before moving on; one value applies to every step
+          const ms = dwell[i] ?? dwell[dwell.length - 1] ?? 100;
+          if (i < command.selectors.length - 1 && ms > 0) {
+            await page.waitForTimeout(ms);
+          }
+        }
+        return { hovered: command.selectors };
+      }
+
+      // Fixed and sticky elements over the viewport, topmost first, with the
+      // refs inside them so the agent knows what to click to dismiss them
+      case 'getOverlays': {
+        const frame = this.browser.getActiveFrame();
+        const found = await frame.evaluate(() => {
+          const vw = window.innerWidth;
+          const vh = window.innerHeight;
+          const marked: Element[] = [];
+          const overlays = [];
+          for (const el of Array.from(document.body?.querySelectorAll('*') ?? [])) {
+            const style = getComputedStyle(el);
+            if (style.position !== 'fixed' && style.position !== 'sticky') continue;
+            if (marked.some((outer) => outer.contains(el))) continue;
+            if (style.display === 'none' || style.visibility === 'hidden') continue;
+            if (Number(style.opacity) === 0) continue;
+            const rect = el.getBoundingClientRect();
+            const width = Math.min(rect.right, vw) - Math.max(rect.left, 0);
+            const height = Math.min(rect.bottom, vh) - Math.max(rect.top, 0);
+            if (width <= 0 || height <= 0) continue;
+
+            const text = ((el as HTMLElement).innerText ?? '').replace(/\s+/g, ' ').trim();
+            const hints = `${el.id} ${el.className} ${el.getAttribute('aria-label') ?? ''} ${text}`;
+            const coverage = (width * height) / (vw * vh);
+            const role = el.getAttribute('role');
+            const kind =
+              role === 'dialog' || role === 'alertdialog' || el.getAttribute('aria-modal') === 'true'
+                ? 'modal'
+                : /cookie|consent|gdpr|privacy/i.test(hints)
+                  ? 'cookie-banner'
+                  : /chat|intercom|drift|zendesk|crisp|livechat|messenger/i.test(hints)
+                    ? 'chat'
+                    : coverage > 0.5
+                      ? 'modal'
+                      : rect.top <= 0 && rect.width >= vw * 0.9
+                        ? 'header'
+                        : rect.bottom >= vh && rect.width >= vw * 0.9
+                          ? 'bottom-bar'
+                          : 'other';
+
+            el.setAttribute('data-agentbrowser-overlay', String(marked.length));
+            marked.push(el);
+            overlays.push({
+              kind,
+              tag: el.tagName.toLowerCase(),
+              selector: el.id ? `#${CSS.escape(el.id)}` : null,
+              position: style.position,
+              zIndex: style.zIndex === 'auto' ? null : Number(style.zIndex),
+              coverage: Math.round(coverage * 1000) / 10,
+              rect: {
+                x: Math.round(rect.x),
+                y: Math.round(rect.y),
+                width: Math.round(rect.width),
+                height: Math.round(rect.height),
+              },
+              text: text.slice(0, 100),
+            });
+          }
+          return { viewport: { width: vw, height: vh }, overlays };
+        });
+
+        // Refs from the last snapshot whose role and name appear inside each overlay
+        const refEntries = Object.entries(this.browser.getRefMap());
+        const overlays = [];
+        for (const [i, overlay] of found.overlays.entries()) {
+          const aria = await frame
+            .locator(`[data-agentbrowser-overlay="${i}"]`)
+            .ariaSnapshot({ timeout: 1000 })
+            .catch(() => '');
+          const inside = new Set(
+            Array.from(
+              aria.matchAll(/- ([\w-]+)(?: "((?:[^"\\]|\\.)*)")?/g),
+              (m) => `${m[1]}:${m[2] ?? ''}`
+            )
+          );
+          const refs = refEntries
+            .filter(([, data]) => inside.has(`${data.role}:${data.name ?? ''}`))
+            .map(([key, data]) => ({ ref: `@${key}`, role: data.role, name: data.name ?? '' }));
+          overlays.push({ ...overlay, refs });
+        }
+        await frame.evaluate(() => {
+          for (const el of Array.from(document.querySelectorAll('[data-agentbrowser-overlay]'))) {
+            el.removeAttribute('data-agentbrowser-overlay');
+          }
+        });
+
+        overlays.sort((a, b) => (b.zIndex ?? 0) - (a.zIndex ?? 0) || b.coverage - a.coverage);
+        return { viewport: found.viewport, overlays };
+      }
+
+      // Scroll offsets of the page or a scroll container, so flows can
+      // restore them and extraction loops can tell they hit the bottom
+      case 'getScrollPosition':
+        return this.scrollPosition(command.selector);
+
+      case 'scrollTo':
+        return this.scrollPosition(command.selector, { x: command.x, y: command.y });
+
+      // Geometry rather than CSS: how much of the element is on screen, and
+      // whether something fixed (a sticky header) sits on its visible part
+      case 'isInViewport':
+        return this.browser.getLocator(command.selector).evaluate(
+          (el) =>
+            new Promise((resolve) => {
+              const observer = new IntersectionObserver(([entry]) => {
+                observer.disconnect();
+                const ratio = Math.round(entry.intersectionRatio * 100) / 100;
+                const visible = entry.intersectionRect;
+                let obscuredBy: string | null = null;
+                if (ratio > 0) {
+                  const hit = document.elementFromPoint(
+                    visible.x + visible.width / 2,
+                    visible.y + visible.height / 2
+                  );
+                  if (hit && !el.contains(hit) && !hit.contains(el)) {
+                    obscuredBy = hit.id
+                      ? `${hit.tagName.toLowerCase()}#${hit.id}`
+                      : hit.tagName.toLowerCase();
+                  }
+                }
+                resolve({
+                  inView: ratio > 0,
+                  ratio,
+                  fullyInView: ratio >= 0.99,
+                  obscuredBy,
+                  rect: {
+                    x: Math.round(entry.boundingClientRect.x),
+                    y: Math.round(entry.boundingClientRect.y),
+                    width: Math.round(entry.boundingClientRect.width),
+                    height: Math.round(entry.boundingClientRect.height),
+                  },
+                });
+              });
+              observer.observe(el);
+            }),
+          undefined,
+          { timeout: command.timeout }
+        );
+
+      // Browser zoom shrinks the CSS viewport and raises the device pixel
+      // ratio, so media queries and layout react as they do to Ctrl+/Ctrl-.
+      // CSS zoom only scales the current document.
+      case 'setZoom': {
+        const page = this.browser.getPage();
+        const base = page.viewportSize() ?? { width: 1280, height: 720 };
+        if (command.css) {
+          await page.evaluate((zoom) => {
+            document.documentElement.style.setProperty('zoom', zoom === 1 ? '' : String(zoom));
+          }, command.zoom);
+          return { zoom: command.zoom, mode: 'css', viewport: base };
+        }
+        if (command.zoom === 1) {
+          // Let Playwright put its own viewport override back
+          await page.setViewportSize(base);
+          return { zoom: 1, mode: 'browser', viewport: base };
+        }
+        const cdp = await this.browser.getCDPSession().catch(() => {
+          throw new Error('Browser zoom needs Chromium; use --css to scale the page instead.');
+        });
+        const viewport = {
+          width: Math.round(base.width / command.zoom),
+          height: Math.round(base.height / command.zoom),
+        };
+        await cdp.send('Emulation.setDeviceMetricsOverride', {
+          ...viewport,
+          deviceScaleFactor: command.zoom,
+          mobile: false,
+        });
+        return { zoom: command.zoom, mode: 'browser', viewport };
+      }
+
+      // Long-page capture: viewport-sized tiles the CLI stitches together.
+      // Each tile reports the fixed/sticky bands at its top and bottom edge
+      // so headers and footers appear once in the result.
+      case 'screenshotTiles': {
+        const page = this.browser.getPage();
+        const origin = await page.evaluate(() => window.scrollY);
+        const max = command.max ?? 40;
+        const tiles: Array<{
+          data: string;
+          y: number;
+          top: number;
+          bottom: number;
+          viewportHeight: number;
+          scrollHeight: number;
+        }> = [];
+        if (command.emulatePrint) {
+          await page.emulateMedia({ media: 'print' });
+        }
+        try {
+          let target = 0;
+          while (tiles.length < max) {
+            await page.evaluate((top) => window.scrollTo({ top, behavior: 'instant' }), target);
+            // Let lazy content and scroll-linked headers settle
+            await page.waitForTimeout(250);
+            const tile = await page.evaluate(() => {
+              const vw = window.innerWidth;
+              const vh = window.innerHeight;
+              let top = 0;
+              let bottom = 0;
+              for (const el of Array.from(document.querySelectorAll('body *'))) {
+                const style = getComputedStyle(el);
+                if (style.position !== 'fixed' && style.position !== 'sticky') continue;
+                if (style.visibility === 'hidden' || style.display === 'none') continue;
+                const rect = el.getBoundingClientRect();
+                if (rect.width < vw / 2 || rect.height === 0 || rect.height > vh / 3) continue;
+                if (rect.top <= 0 && rect.bottom > 0) top = Math.max(top, rect.bottom);
+                if (rect.bottom >= vh && rect.top < vh) bottom = Math.max(bottom, vh - rect.top);
+              }
+              return {
+                y: Math.round(window.scrollY),
+                top: Math.round(top),
+                bottom: Math.round(bottom),
+                viewportHeight: vh,
+                scrollHeight: document.documentElement.scrollHeight,
+              };
+            });
+            const buffer = await page.screenshot({ type: 'png', timeout: command.timeout });
+            tiles.push({ data: buffer.toString('base64'), ...tile });
+
+            const previous = tiles[tiles.length - 2];
+            if (tile.y + tile.viewportHeight >= tile.scrollHeight) break;
+            if (previous && tile.y <= previous.y) break;
+            // Overlap by the bands so content under them is captured next time
+            target = tile.y + Math.max(tile.viewportHeight - tile.top - tile.bottom, 1);
+          }
+        } finally {
+          await page.evaluate((top) => window.scrollTo({ top, behavior: 'instant' }), origin);
+          if (command.emulatePrint) {
+            await page.emulateMedia({ media: null });
+          }
+        }
+        return { tiles };
+      }
+
+      // Best icon the page declares: <link rel=icon|apple-touch-icon>, the
+      // web app manifest's icons, then /favicon.ico
+      case 'getFavicon': {
+        const page = this.browser.getPage();
+        const request = page.context().request;
+        type Icon = { url: string; source: string; sizes: string; type: string };
+        const declared = await page.evaluate(() => {
+          const icons: Icon[] = [];
+          document.querySelectorAll('link[rel][href]').forEach((el) => {
+            const link = el as HTMLLinkElement;
+            const rels = link.rel.toLowerCase().split(/\s+/);
+            const source = rels.includes('icon')
+              ? 'icon'
+              : rels.some((r) => r.startsWith('apple-touch-icon'))
+                ? 'apple-touch-icon'
+                : null;
+            if (source) {
+              icons.push({ url: link.href, source, sizes: link.getAttribute('sizes') ?? '', type: link.type });
+            }
+          });
+          const manifest = document.querySelector('link[rel~="manifest" i][href]') as HTMLLinkElement | null;
+          return {
+            icons,
+            manifest: manifest?.href ?? null,
+            fallback: location.protocol.startsWith('http') ? new URL('/favicon.ico', location.href).href : null,
+          };
+        });
+
+        const candidates: Icon[] = [...declared.icons];
+        if (declared.manifest) {
+          try {
+            const response = await request.get(declared.manifest, { timeout: command.timeout });
+            const manifest = response.ok() ? await response.json() : {};
+            for (const icon of Array.isArray(manifest.icons) ? manifest.icons : []) {
+              // Monochrome icons are masks, not something to show
+              if (typeof icon?.src !== 'string' || /monochrome/.test(icon.purpose ?? '')) continue;
+              candidates.push({
+                url: new URL(icon.src, declared.manifest).href,
+                source: 'manifest',
+                sizes: icon.sizes ?? '',
+                type: icon.type ?? '',
+              });
+            }
+          } catch {
+            // A broken manifest still leaves the <link> icons
+          }
+        }
+        if (declared.fallback && !candidates.some((c) => c.url === declared.fallback)) {
+          candidates.push({ url: declared.fallback, source: 'favicon.ico', sizes: '', type: '' });
+        }
+
+        // Largest first; scalable icons count as large, undeclared sizes
+        // as the usual default for their kind
+        const size = (icon: Icon) => {
+          if (/\bany\b/i.test(icon.sizes) || /svg/.test(icon.type) || /\.svg(\?|$)/i.test(icon.url)) return 1024;
+          const declaredSizes = Array.from(icon.sizes.matchAll(/(\d+)x(\d+)/gi), (m) => Number(m[1]));
+          if (declaredSizes.length > 0) return Math.max(...declaredSizes);
+          return icon.source === 'apple-touch-icon' ? 180 : 16;
+        };
+        const ranked = candidates
+          .map((icon, index) => ({ icon, index, size: size(icon) }))
+          .sort((a, b) => b.size - a.size || a.index - b.index);
+
+        for (const { icon, size: width } of ranked) {
+          const response = await request.get(icon.url, { timeout: command.timeout }).catch(() => null);
+          if (!response?.ok()) continue;
+          const body = await response.body();
+          if (body.length === 0) continue;
+          const result = {
+            url: icon.url,
+            source: icon.source,
+            sizes: icon.sizes || null,
+            size: width,
+            type: response.headers()['content-type'] ?? (icon.type || null),
+            bytes: body.length,
+            candidates: ranked.map(({ icon: c, size: s }) => ({ url: c.url, source: c.source, size: s })),
+          };
+          if (command.path) {
+            const fs = await import('fs');
+            await fs.promises.writeFile(command.path, body);
+            return { ...result, path: command.path };
+          }
+          return { ...result, data: body.toString('base64') };
+        }
+        throw new Error(
+          candidates.length > 0
+            ? `None of the page's ${candidates.length} icon(s) could be downloaded`
+            : 'The page declares no icons'
+        );
+      }
+
+      // Installability, service worker and offline readiness, checked the
+      // way seoAudit checks meta tags
+      case 'pwaAudit': {
+        const page = this.browser.getPage();
+        const facts = await page.evaluate(async () => {
+          const link = document.querySelector('link[rel~="manifest" i][href]') as HTMLLinkElement | null;
+          const container = 'serviceWorker' in navigator ? navigator.serviceWorker : null;
+          const registration = container ? await container.getRegistration().catch(() => undefined) : undefined;
+          const worker = registration?.active ?? registration?.waiting ?? registration?.installing ?? null;
+          return {
+            secure: window.isSecureContext,
+            manifestUrl: link?.href ?? null,
+            themeColor: document.querySelector('meta[name="theme-color" i]')?.getAttribute('content') ?? null,
+            serviceWorker: {
+              supported: container !== null,
+              registered: !!registration,
+              scope: registration?.scope ?? null,
+              scriptUrl: worker?.scriptURL ?? null,
+              state: worker?.state ?? null,
+              controlling: !!container?.controller,
+            },
+          };
+        });
+
+        type Manifest = {
+          name?: string;
+          short_name?: string;
+          start_url?: string;
+          scope?: string;
+          display?: string;
+          display_override?: string[];
+          theme_color?: string;
+          background_color?: string;
+          prefer_related_applications?: boolean;
+          icons?: { src?: string; sizes?: string; type?: string; purpose?: string }[];
+        };
+        let manifest: Manifest | null = null;
+        let manifestError: string | null = null;
+        if (facts.manifestUrl) {
+          try {
+            const response = await page.context().request.get(facts.manifestUrl, { timeout: command.timeout });
+            if (response.ok()) manifest = JSON.parse(await response.text());
+            else manifestError = `${response.status()} ${response.statusText()}`;
+          } catch (error) {
+            manifestError = error instanceof Error ? error.message : String(error);
+          }
+        }
+
+        // start_url resolves against the manifest and defaults to the page
+        const startUrl = manifest?.start_url
+          ? new URL(manifest.start_url, facts.manifestUrl ?? page.url()).href
+          : page.url();
+        const offline = await page.evaluate(async (url) => {
+          if (!('caches' in window)) return { caches: 0, startUrlCached: false };
+          const keys = await caches.keys().catch(() => [] as string[]);
+          const cached = keys.length > 0 && !!(await caches.match(url).catch(() => undefined));
+          return { caches: keys.length, startUrlCached: cached };
+        }, startUrl);
+
+        type Status = 'pass' | 'warn' | 'fail';
+        const checks: { check: string; status: Status; message: string }[] = [];
+        const add = (check: string, status: Status, message: string) =>
+          checks.push({ check, status, message });
+
+        if (facts.secure) add('https', 'pass', 'Served from a secure context');
+        else add('https', 'fail', 'Not a secure context (needs HTTPS or localhost)');
+
+        if (!facts.manifestUrl) add('manifest', 'fail', 'No <link rel="manifest">');
+        else if (!manifest) add('manifest', 'fail', `Could not load ${facts.manifestUrl}: ${manifestError}`);
+        else add('manifest', 'pass', facts.manifestUrl);
+
+        if (manifest) {
+          const name = manifest.name ?? manifest.short_name;
+          if (!name) add('name', 'fail', 'Manifest has neither name nor short_name');
+          else if (!manifest.short_name && name.length > 12)
+            add('name', 'warn', `No short_name; "${name}" may be truncated on home screens`);
+          else add('name', 'pass', name);
+
+          const icons = Array.isArray(manifest.icons) ? manifest.icons : [];
+          const largest = (size: number) =>
+            icons.some(
+              (icon) =>
+                !/monochrome/.test(icon.purpose ?? '') &&
+                (/\bany\b/i.test(icon.sizes ?? '') ||
+                  Array.from((icon.sizes ?? '').matchAll(/(\d+)x\d+/gi)).some((m) => Number(m[1]) >= size))
+            );
+          if (!largest(192)) add('icons', 'fail', `No icon of at least 192px (${icons.length} declared)`);
+          else if (!largest(512)) add('icons', 'warn', 'No 512px icon for splash screens');
+          else add('icons', 'pass', `${icons.length} icons, including 192px and 512px`);
+
+          if (!manifest.start_url) add('startUrl', 'warn', 'No start_url; the current page will be used');
+          else if (new URL(startUrl).origin !== new URL(page.url()).origin)
+            add('startUrl', 'fail', `start_url is on another origin: ${startUrl}`);
+          else add('startUrl', 'pass', startUrl);
+
+          const display = manifest.display_override?.[0] ?? manifest.display ?? 'browser';
+          if (['standalone', 'fullscreen', 'minimal-ui', 'window-controls-overlay'].includes(display))
+            add('display', 'pass', display);
+          else add('display', 'fail', `display is "${display}" (needs standalone, fullscreen or minimal-ui)`);
+
+          if (manifest.prefer_related_applications)
+            add('related', 'warn', 'prefer_related_applications steers users to a native app instead');
+        }
+
+        const themeColor = manifest?.theme_color ?? facts.themeColor;
+        if (themeColor) add('themeColor', 'pass', themeColor);
+        else add('themeColor', 'warn', 'No theme_color or <meta name="theme-color">');
+
+        const worker = facts.serviceWorker;
+        if (!worker.supported) add('worker', 'fail', 'Service workers are unavailable here');
+        else if (!worker.registered) add('worker', 'warn', 'No service worker registered');
+        else if (!worker.controlling)
+          add('worker', 'warn', `Registered (${worker.state}) but not controlling this page yet; reload to check`);
+        else add('worker', 'pass', `${worker.scriptUrl} controls ${worker.scope}`);
+
+        if (offline.startUrlCached) add('offline', 'pass', `start_url is in Cache Storage (${offline.caches} caches)`);
+        else if (offline.caches > 0) add('offline', 'warn', `${offline.caches} caches, but start_url is not cached`);
+        else add('offline', 'warn', 'Nothing in Cache Storage; the app will not load offline');
+
+        const count = (status: Status) => checks.filter((c) => c.status === status).length;
+        const summary = { pass: count('pass'), warn: count('warn'), fail: count('fail') };
+        return {
+          url: page.url(),
+          status: summary.fail ? 'fail' : summary.warn ? 'warn' : 'pass',
+          installable: checks.every((c) => c.status !== 'fail'),
+          summary,
+          checks,
+          manifestUrl: facts.manifestUrl,
+          manifest,
+          startUrl,
+          serviceWorker: worker,
+          offline,
+        };
+      }
+
+      // Quota and usage for the page's origin. Chromium's per-type
+      // breakdown comes from CDP; other browsers only give the totals
+      // navigator.storage.estimate() reports.
+      case 'getStorageUsage': {
+        const page = this.browser.getPage();
+        const local = await page.evaluate(async () => {
+          // Web storage is UTF-16, two bytes per character
+          const measure = (storage: Storage) => {
+            let bytes = 0;
+            for (let i = 0; i < storage.length; i++) {
+              const key = storage.key(i) ?? '';
+              bytes += (key.length + (storage.getItem(key) ?? '').length) * 2;
+            }
+            return { bytes, keys: storage.length };
+          };
+          const estimate = navigator.storage
+            ? await navigator.storage.estimate().catch(() => null)
+            : null;
+          const persisted = navigator.storage?.persisted
+            ? await navigator.storage.persisted().catch(() => null)
+            : null;
+          const databases = indexedDB.databases
+            ? (await indexedDB.databases().catch(() => [])).map((db) => db.name ?? '')
+            : [];
+          const cacheNames = 'caches' in window ? await caches.keys().catch(() => [] as string[]) : [];
+          return {
+            origin: location.origin,
+            localStorage: measure(localStorage),
+            sessionStorage: measure(sessionStorage),
+            estimate: estimate
+              ? {
+                  usage: estimate.usage ?? 0,
+                  quota: estimate.quota ?? 0,
+                  details: (estimate as { usageDetails?: Record<string, number> }).usageDetails ?? {},
+                }
+              : null,
+            persisted,
+            databases,
+            cacheNames,
+          };
+        });
+        if (local.origin === 'null') {
+          throw new Error('The current page has no origin; navigate to a site first');
+        }
+
+        let usage = local.estimate?.usage ?? 0;
+        let quota = local.estimate?.quota ?? 0;
+        const byType: Record<string, number> = { ...local.estimate?.details };
+        let source = 'estimate';
+        const cdp = await this.browser.getCDPSession().catch(() => null);
+        if (cdp) {
+          const result = await cdp
+            .send('Storage.getUsageAndQuota', { origin: local.origin })
+            .catch(() => null);
+          if (result) {
+            usage = result.usage;
+            quota = result.quota;
+            source = 'cdp';
+            for (const entry of result.usageBreakdown) {
+              if (entry.usage > 0) byType[entry.storageType] = entry.usage;
+            }
+          }
+        }
+
+        const cookies = await page.context().cookies(page.url());
+        const cookieBytes = cookies.reduce((sum, c) => sum + c.name.length + c.value.length, 0);
+        const pick = (...keys: string[]) => keys.reduce((sum, key) => sum + (byType[key] ?? 0), 0);
+        return {
+          origin: local.origin,
+          usage,
+          quota,
+          percent: quota ? Math.round((usage / quota) * 10000) / 100 : null,
+          persisted: local.persisted,
+          source,
+          breakdown: {
+            cookies: { bytes: cookieBytes, count: cookies.length },
+            localStorage: local.localStorage,
+            sessionStorage: local.sessionStorage,
+            indexedDB: { bytes: pick('indexeddb', 'indexedDB'), databases: local.databases },
+            cacheStorage: { bytes: pick('cache_storage', 'caches'), caches: local.cacheNames },
+            serviceWorkers: { bytes: pick('service_workers', 'serviceWorkerRegistrations') },
+          },
+        };
+      }
+
+      // CacheStorage as the page sees it, i.e. what its service worker cached
+      case 'listCaches': {
+        const page = this.browser.getPage();
+        const found = await page.evaluate(async () => {
+          if (!('caches' in window)) {
+            throw new Error('CacheStorage is unavailable here (it needs a secure context)');
+          }
+          const names = await caches.keys();
+          return Promise.all(
+            names.map(async (name) => ({ name, entries: (await (await caches.open(name)).keys()).length }))
+          );
+        });
+        return { origin: new URL(page.url()).origin, caches: found };
+      }
+
+      case 'getCacheEntries': {
+        const entries = await this.browser.getPage().evaluate(async (name) => {
+          if (!('caches' in window)) {
+            throw new Error('CacheStorage is unavailable here (it needs a secure context)');
+          }
+          if (!(await caches.has(name))) {
+            throw new Error(`No cache named "${name}"; 'cachestorage list' shows the caches`);
+          }
+          const cache = await caches.open(name);
+          const now = Date.now();
+          return Promise.all(
+            (await cache.keys()).map(async (request) => {
+              const response = await cache.match(request);
+              // Opaque (cross-origin no-cors) responses hide status and headers
+              const header = (key: string) => response?.headers.get(key) ?? null;
+              const date = header('date');
+              const stored = date ? Date.parse(date) : NaN;
+              const length = header('content-length');
+              return {
+                url: request.url,
+                method: request.method,
+                status: response?.status ?? null,
+                type: response?.type ?? null,
+                contentType: header('content-type'),
+                size: length === null ? null : Number(length),
+                date,
+                ageSeconds: Number.isNaN(stored) ? null : Math.max(0, Math.round((now - stored) / 1000)),
+              };
+            })
+          );
+        }, command.name);
+        return { cache: command.name, count: entries.length, entries };
+      }
+
+      case 'listMediaDevices': {
+        const devices = await this.browser.getPage().evaluate(async () => {
+          if (!navigator.mediaDevices?.enumerateDevices) {
+            throw new Error('Media devices are unavailable here (they need a secure context)');
+          }
+          return (await navigator.mediaDevices.enumerateDevices()).map((d) => ({
+            kind: d.kind,
+            label: d.label,
+            deviceId: d.deviceId,
+            groupId: d.groupId,
+          }));
+        });
+        return { fake: this.browser.hasFakeMedia(), devices };
+      }
+
+      // Drive a <video>/<audio> element (or the first one inside the match)
+      // and report its playback state afterwards
+      case 'mediaElement': {
+        const locator = this.browser.getLocator(command.selector);
+        await locator.waitFor({ state: 'attached', timeout: command.timeout });
+        return await locator.evaluate(
+          async (el, { op, time }) => {
+            const media =
+              el instanceof HTMLMediaElement ? el : el.querySelector<HTMLMediaElement>('video, audio');
+            if (!media) throw new Error(`Element is a <${el.localName}>, not a <video> or <audio>`);
+
+            // Settle on the event the operation fires, or give up after 5s
+            const settle = (event: string) =>
+              new Promise<void>((resolve) => {
+                const timer = setTimeout(resolve, 5000);
+                media.addEventListener(event, () => (clearTimeout(timer), resolve()), { once: true });
+              });
+            let blocked: string | null = null;
+            if (op === 'play') {
+              // Autoplay policy rejects unmuted play() without a user gesture
+              await media.play().catch((e: Error) => (blocked = e.message));
+            } else if (op === 'pause') {
+              media.pause();
+            } else if (op === 'seek') {
+              const seeked = settle('seeked');
+              media.currentTime = time ?? 0;
+              await seeked;
+            } else if (op === 'mute' || op === 'unmute') {
+              media.muted = op === 'mute';
+            }
+
+            const states = ['HAVE_NOTHING', 'HAVE_METADATA', 'HAVE_CURRENT_DATA', 'HAVE_FUTURE_DATA', 'HAVE_ENOUGH_DATA'];
+            const ranges = (list: TimeRanges) =>
+              Array.from({ length: list.length }, (_, i) => [list.start(i), list.end(i)]);
+            return {
+              tag: media.localName,
+              src: media.currentSrc || null,
+              paused: media.paused,
+              ended: media.ended,
+              muted: media.muted,
+              volume: media.volume,
+              playbackRate: media.playbackRate,
+              currentTime: media.currentTime,
+              duration: Number.isFinite(media.duration) ? media.duration : null,
+              readyState: media.readyState,
+              readyStateName: states[media.readyState],
+              buffered: ranges(media.buffered),
+              seekable: ranges(media.seekable),
+              error: media.error ? media.error.message || `MediaError code ${media.error.code}` : blocked,
+            };
+          },
+          { op: command.value, time: command.time }
+        );
+      }
+
+      // A canvas's own bitmap, at its backing-store resolution rather than
+      // as scaled on screen; the CLI decodes and writes the PNG
+      case 'captureCanvas': {
+        const locator = this.browser.getLocator(command.selector);
+        await locator.waitFor({ state: 'attached', timeout: command.timeout });
+        const canvas = await locator.evaluate((el) => {
+          const canvas = el instanceof HTMLCanvasElement ? el : el.querySelector('canvas');
+          if (!canvas) throw new Error(`Element is a <${el.localName}> with no <canvas> inside`);
+          let dataUrl: string;
+          try {
+            dataUrl = canvas.toDataURL('image/png');
+          } catch {
+            throw new Error('Canvas is tainted by cross-origin images and cannot be read');
+          }
+          // Sample a thumbnail to spot an empty bitmap, which is what WebGL
+          // canvases without preserveDrawingBuffer return between frames
+          const probe = document.createElement('canvas');
+          probe.width = probe.height = 32;
+          const ctx = probe.getContext('2d');
+          let blank = false;
+          if (ctx && canvas.width && canvas.height) {
+            ctx.drawImage(canvas, 0, 0, 32, 32);
+            const alpha = ctx.getImageData(0, 0, 32, 32).data.filter((_, i) => i % 4 === 3);
+            blank = alpha.every((a) => a === 0);
+          }
+          return { dataUrl, width: canvas.width, height: canvas.height, blank };
+        });
+        return {
+          data: canvas.dataUrl.slice(canvas.dataUrl.indexOf(',') + 1),
+          width: canvas.width,
+          height: canvas.height,
+          blank: canvas.blank,
+        };
+      }
+
+      // Work out where the thumb has to go for the target value, drag it
+      // there, then settle the remainder with arrow keys since thumb
+      // geometry and step snapping make the drag approximate
+      case 'setSlider': {
+        const page = this.browser.getPage();
+        const locator = this.browser.getLocator(command.selector);
+        await locator.waitFor({ state: 'visible', timeout: command.timeout });
+        const handle = await locator.evaluateHandle((el) => {
+          if (el instanceof HTMLInputElement && el.type === 'range') return el;
+          const found = el.getAttribute('role') === 'slider' ? el : el.querySelector('[role="slider"]');
+          if (!found) throw new Error(`Element is a <${el.localName}>, not a range input or role="slider"`);
+          return found;
+        });
+        const slider = handle.asElement()!;
+        const read = () =>
+          slider.evaluate((el) => {
+            const num = (v: string | null, fallback: number) => {
+              const n = parseFloat(v ?? '');
+              return Number.isFinite(n) ? n : fallback;
+            };
+            if (el instanceof HTMLInputElement) {
+              return {
+                value: el.valueAsNumber,
+                min: num(el.min, 0),
+                max: num(el.max, 100),
+                step: el.step === 'any' ? 0 : num(el.step, 1),
+              };
+            }
+            const min = num(el.getAttribute('aria-valuemin'), 0);
+            return {
+              value: num(el.getAttribute('aria-valuenow'), min),
+              min,
+              max: num(el.getAttribute('aria-valuemax'), 100),
+              step: 0,
+            };
+          });
+
+        const range = await read();
+        let target = command.percent
+          ? range.min + ((range.max - range.min) * command.amount) / 100
+          : command.amount;
+        if (target < range.min || target > range.max) {
+          throw new Error(`Value ${target} is outside the slider's range ${range.min} to ${range.max}`);
+        }
+        if (range.step > 0) {
+          target = range.min + Math.round((target - range.min) / range.step) * range.step;
+          target = Math.min(parseFloat(target.toFixed(10)), range.max);
+        }
+
+        // A native input is its own track; an ARIA thumb travels along the
+        // nearest ancestor that is clearly longer than it
+        const geometry = await slider.evaluate((el) => {
+          const rect = el.getBoundingClientRect();
+          const vertical =
+            el.getAttribute('aria-orientation') === 'vertical' ||
+            getComputedStyle(el).writingMode.startsWith('vertical');
+          const long = (r: DOMRect) => (vertical ? r.height : r.width);
+          let track = rect;
+          if (!(el instanceof HTMLInputElement)) {
+            for (let node = el.parentElement; node; node = node.parentElement) {
+              const r = node.getBoundingClientRect();
+              if (long(r) >= long(rect) * 2) {
+                track = r;
+                break;
+              }
+            }
+          }
+          const native = el instanceof HTMLInputElement;
+          return {
+            native,
+            vertical,
+            track: { x: track.left - rect.left, y: track.top - rect.top, width: track.width, height: track.height },
+            // Native thumbs stop half their width short of each end
+            inset: native ? Math.min(rect.width, rect.height) / 2 : 0,
+          };
+        });
+        const box = await slider.boundingBox();
+        if (!box) throw new Error('Slider is not visible');
+        const trackX = box.x + geometry.track.x;
+        const trackY = box.y + geometry.track.y;
+        const point = (value: number) => {
+          const fraction = range.max > range.min ? (value - range.min) / (range.max - range.min) : 0;
+          const span = (geometry.vertical ? geometry.track.height : geometry.track.width) - geometry.inset * 2;
+          return geometry.vertical
+            ? { x: trackX + geometry.track.width / 2, y: trackY + geometry.inset + span * (1 - fraction) }
+            : { x: trackX + geometry.inset + span * fraction, y: trackY + geometry.track.height / 2 };
+        };
+        // Grab the thumb where it currently sits; an ARIA thumb is the element
+        const start = geometry.native
+          ? point(range.value)
+          : { x: box.x + box.width / 2, y: box.y + box.height / 2 };
+        const end = point(target);
+        await page.mouse.move(start.x, start.y);
+        await page.mouse.down();
+        await page.mouse.move(end.x, end.y, { steps: 10 });
+        await page.mouse.up();
+
+        // ArrowUp/ArrowDown raise and lower both native and ARIA sliders
+        // regardless of orientation or text direction
+        let keys = 0;
+        let current = (await read()).value;
+        while (current !== target && keys < 100) {
+          await slider.press(current < target ? 'ArrowUp' : 'ArrowDown');
+          keys++;
+          const next = (await read()).value;
+          if (next === current) break;
+          if (Math.abs(target - next) > Math.abs(target - current)) {
+            await slider.press(next < target ? 'ArrowUp' : 'ArrowDown');
+            keys++;
+            current = (await read()).value;
+            break;
+          }
+          current = next;
+        }
+        return { value: current, target, min: range.min, max: range.max, keys };
+      }
+
+      // Drag one item of a sortable list onto the slot it should end up in,
+      // with pointer moves slow enough for libraries that wait for a drag
+      // threshold, and report where the item actually landed
+      case 'reorder': {
+        const page = this.browser.getPage();
+        const locator = this.browser.getLocator(command.selector);
+        await locator.waitFor({ state: 'visible', timeout: command.timeout });
+        const item = await locator.elementHandle();
+        if (!item) throw new Error(`Element not found: ${command.selector}`);
+        const list = await item.evaluateHandle((el) => {
+          if (!el.parentElement) throw new Error('Item has no parent list');
+          return el.parentElement;
+        });
+        const positionOf = () =>
+          list.evaluate((list, el) => {
+            const items = Array.from(list.children).filter((c) => c.getClientRects().length > 0);
+            return { index: items.indexOf(el as Element), count: items.length };
+          }, item);
+
+        const { index: from, count } = await positionOf();
+        const to = command.index;
+        if (from < 0) throw new Error('Item is hidden');
+        if (to >= count) {
+          throw new Error(`List has ${count} items, so --to-index must be below ${count}`);
+        }
+        if (from === to) return { from, to, index: from, count, moved: true };
+
+        // Drop past the middle of the target slot so the library commits to
+        // swapping with it rather than with its neighbour
+        const drop = await list.evaluate(
+          (list, { el, to, later }) => {
+            const items = Array.from(list.children).filter((c) => c.getClientRects().length > 0);
+            const origin = (el as Element).getBoundingClientRect();
+            const slot = items[to].getBoundingClientRect();
+            const first = items[0].getBoundingClientRect();
+            const last = items[items.length - 1].getBoundingClientRect();
+            const vertical = Math.abs(last.top - first.top) >= Math.abs(last.left - first.left);
+            const along = later ? 0.75 : 0.25;
+            return {
+              x: slot.left - origin.left + slot.width * (vertical ? 0.5 : along),
+              y: slot.top - origin.top + slot.height * (vertical ? along : 0.5),
+            };
+          },
+          { el: item, to, later: to > from }
+        );
+        const box = await item.boundingBox();
+        if (!box) throw new Error('Item is not visible');
+        const startX = box.x + box.width / 2;
+        const startY = box.y + box.height / 2;
+        await page.mouse.move(startX, startY);
+        await page.mouse.down();
+        // Nudge past activation distances before travelling
+        await page.mouse.move(startX + 2, startY + 8, { steps: 4 });
+        await page.mouse.move(box.x + drop.x, box.y + drop.y, { steps: 20 });
+        await page.waitForTimeout(100);
+        await page.mouse.move(box.x + drop.x, box.y + drop.y + 1);
+        await page.mouse.up();
+        await page.waitForTimeout(250);
+
+        const connected = await item.evaluate((el) => el.isConnected);
+        if (!connected) {
+          throw new Error('The list re-rendered during the drag; check the new order with snapshot');
+        }
+        const { index } = await positionOf();
+        return { from, to, index, count, moved: index === to };
+      }
+
+      // Native date/time inputs take a fixed ISO-style value that fill() can
+      // miss silently, so set it directly; text-based pickers get the date
+      // typed the way the page's locale (or --format) writes it
+      case 'setDate': {
+        const locator = this.browser.getLocator(command.selector);
+        await locator.waitFor({ state: 'visible', timeout: command.timeout });
+        const plan = await locator.evaluate(
+          (el, { iso, format }) => {
+            const m =
+              /^(?:(\d{4})-(\d{2})-(\d{2}))?T?(?:(\d{2}):(\d{2})(?::(\d{2}))?)?$/.exec(iso) ?? [];
+            const [, year, month, day, hour, minute, second] = m;
+            const pad = (n: number) => String(n).padStart(2, '0');
+            const date = year ? `${year}-${month}-${day}` : null;
+            const time = hour ? `${hour}:${minute}${second ? `:${second}` : ''}` : null;
+
+            const kind = el instanceof HTMLInputElement ? el.type : 'text';
+            const native: Record<string, () => string | null> = {
+              date: () => date,
+              time: () => time,
+              'datetime-local': () => (date ? `${date}T${time ?? '00:00'}` : null),
+              month: () => (date ? `${year}-${month}` : null),
+              week: () => {
+                if (!date) return null;
+                // ISO week: the week holding the Thursday of this date's week
+                const d = new Date(Date.UTC(+year, +month - 1, +day));
+                d.setUTCDate(d.getUTCDate() + 4 - (d.getUTCDay() || 7));
+                const start = Date.UTC(d.getUTCFullYear(), 0, 1);
+                const week = Math.ceil(((d.getTime() - start) / 86400000 + 1) / 7);
+                return `${d.getUTCFullYear()}-W${pad(week)}`;
+              },
+            };
+            if (kind in native) {
+              const value = native[kind]();
+              if (!value) throw new Error(`A ${kind} input needs ${kind === 'time' ? 'a time' : 'a date'}, got '${iso}'`);
+              // Go through the prototype setter so frameworks tracking the
+              // value (React) see the change
+              const input = el as HTMLInputElement;
+              Object.getOwnPropertyDescriptor(HTMLInputElement.prototype, 'value')!.set!.call(input, value);
+              input.dispatchEvent(new Event('input', { bubbles: true }));
+              input.dispatchEvent(new Event('change', { bubbles: true }));
+              if (input.value !== value) {
+                throw new Error(`The ${kind} input rejected '${value}'; check its min, max and step`);
+              }
+              return { native: true, kind, value };
+            }
+
+            if (format) {
+              const tokens: Record<string, string> = {
+                YYYY: year ?? '',
+                YY: year?.slice(2) ?? '',
+                MM: month ?? '',
+                M: month ? String(+month) : '',
+                DD: day ?? '',
+                D: day ? String(+day) : '',
+                HH: hour ?? '',
+                mm: minute ?? '',
+                ss: second ?? '00',
+              };
+              return { native: false, kind, value: format.replace(/YYYY|YY|MM|M|DD|D|HH|mm|ss/g, (t) => tokens[t]) };
+            }
+            const lang = el.closest('[lang]')?.getAttribute('lang') || navigator.language;
+            const when = new Date(+(year ?? 1970), +(month ?? 1) - 1, +(day ?? 1), +(hour ?? 0), +(minute ?? 0));
+            const parts: Intl.DateTimeFormatOptions = {
+              ...(date ? { year: 'numeric', month: '2-digit', day: '2-digit' } : {}),
+              ...(time ? { hour: '2-digit', minute: '2-digit', hourCycle: 'h23' } : {}),
+            };
+            return { native: false, kind, value: new Intl.DateTimeFormat(lang, parts).format(when) };
+          },
+          { iso: command.value, format: command.format }
+        );
+        if (plan.native) return { value: plan.value, input: plan.kind };
+
+        await locator.fill(plan.value, { timeout: command.timeout });
+        // Most pickers parse what was typed when the field loses focus
+        await locator.blur();
+        const value = await locator.inputValue().catch(() => plan.value);
+        return { value, input: 'text', typed: plan.value };
+      }
+
+      // Rich-text editors keep their own document model and ignore value
+      // changes, so go through the events they listen to: typed text via
+      // beforeinput/input with Enter between lines, formatted content as a
+      // paste carrying text/html
+      case 'write': {
+        const page = this.browser.getPage();
+        const locator = this.browser.getLocator(command.selector);
+        await locator.click({ timeout: command.timeout });
+        const handle = await locator.evaluateHandle((el) => {
+          const root = (el as HTMLElement).isContentEditable
+            ? el
+            : el.querySelector('[contenteditable]:not([contenteditable="false"])');
+          if (!root) throw new Error(`<${el.localName}> is not a contenteditable editor; use fill for form fields`);
+          return root as HTMLElement;
+        });
+        const root = handle.asElement()!;
+        await root.evaluate((el, clear) => {
+          el.focus();
+          const range = document.createRange();
+          range.selectNodeContents(el);
+          if (!clear) range.collapse(false);
+          const selection = window.getSelection()!;
+          selection.removeAllRanges();
+          selection.addRange(range);
+        }, command.clear ?? false);
+        // A real keypress so the editor records the deletion itself
+        if (command.clear) await page.keyboard.press('Delete');
+
+        let method: string;
+        if (command.html) {
+          method = await root.evaluate((el, html) => {
+            const data = new DataTransfer();
+            data.setData('text/html', html);
+            data.setData('text/plain', new DOMParser().parseFromString(html, 'text/html').body.textContent ?? '');
+            const paste = new ClipboardEvent('paste', { clipboardData: data, bubbles: true, cancelable: true });
+            el.dispatchEvent(paste);
+            // Plain contenteditable has no paste handler of its own
+            if (paste.defaultPrevented) return 'paste';
+            document.execCommand('insertHTML', false, html);
+            return 'insertHTML';
+          }, command.html);
+        } else {
+          method = 'keyboard';
+          const lines = (command.text ?? '').split('\n');
+          for (const [i, line] of lines.entries()) {
+            if (line) await page.keyboard.insertText(line);
+            if (i < lines.length - 1) await page.keyboard.press('Enter');
+          }
+        }
+        return { method, text: await root.evaluate((el) => el.innerText) };
+      }
+
+      // Load the clipboard and paste with the real shortcut so the page's
+      // own paste handling (sanitizers, OTP splitters, editors) runs. When
+      // the browser won't deliver the keystroke as a paste, dispatch the
+      // paste event directly with the same data.
+      case 'paste': {
+        const page = this.browser.getPage();
+        const locator = this.browser.getLocator(command.selector);
+        await locator.focus({ timeout: command.timeout });
+        const origin = new URL(page.url()).origin;
+        // Only Chromium knows these permissions; elsewhere the fallback runs
+        await page
+          .context()
+          .grantPermissions(['clipboard-read', 'clipboard-write'], { origin })
+          .catch(() => {});
+
+        const html = command.html;
+        let text = command.text ?? '';
+        if (html) {
+          text = await page.evaluate(
+            (html) => new DOMParser().parseFromString(html, 'text/html').body.textContent ?? '',
+            html
+          );
+        }
+        if (command.fromClipboard) {
+          text = await page.evaluate(() => navigator.clipboard.readText()).catch(() => {
+            throw new Error("The page's clipboard can't be read here; pass the text instead");
+          });
+        } else {
+          await page
+            .evaluate(
+              async ({ text, html }) => {
+                if (!html) return navigator.clipboard.writeText(text);
+                await navigator.clipboard.write([
+                  new ClipboardItem({
+                    'text/html': new Blob([html], { type: 'text/html' }),
+                    'text/plain': new Blob([text], { type: 'text/plain' }),
+                  }),
+                ]);
+              },
+              { text, html }
+            )
+            .catch(() => {});
+        }
+
+        await page.evaluate(() => {
+          const w = window as unknown as { __abpPasted?: boolean; __abpPasteWatch?: () => void };
+          w.__abpPasted = false;
+          w.__abpPasteWatch = () => (w.__abpPasted = true);
+          document.addEventListener('paste', w.__abpPasteWatch, { capture: true });
+        });
+        await page.keyboard.press(process.platform === 'darwin' ? 'Meta+v' : 'Control+v');
+        const delivered = await page.evaluate(() => {
+          const w = window as unknown as { __abpPasted?: boolean; __abpPasteWatch?: () => void };
+          if (w.__abpPasteWatch) document.removeEventListener('paste', w.__abpPasteWatch, { capture: true });
+          return w.__abpPasted === true;
+        });
+
+        if (!delivered) {
+          await locator.evaluate(
+            (el, { text, html }) => {
+              const target = (document.activeElement as HTMLElement | null) ?? el;
+              const data = new DataTransfer();
+              data.setData('text/plain', text);
+              if (html) data.setData('text/html', html);
+              const paste = new ClipboardEvent('paste', { clipboardData: data, bubbles: true, cancelable: true });
+              if (target.dispatchEvent(paste)) {
+                // Nothing handled it, so insert the way a default paste would
+                document.execCommand(html && target.isContentEditable ? 'insertHTML' : 'insertText', false, html && target.isContentEditable ? html : text);
+              }
+            },
+            { text, html }
+          );
+        }
+        const value = await locator.evaluate((el) =>
+          el instanceof HTMLInputElement || el instanceof HTMLTextAreaElement ? el.value : (el as HTMLElement).innerText
+        );
+        return { method: delivered ? 'keyboard' : 'event', length: text.length, value };
+      }
+
       // Get Dropdown Options (from browser-use)
       case 'getDropdownOptions': {
         const locator = this.browser.getLocator(command.selector);
@@ -1653,6 +3732,203 @@ export class ActionExecutor {
         throw new Error(`Unknown action: ${(command as any).action}`);
     }
   }
+
+  /**
+   * Role and accessible name of an element, read from its ARIA snapshot so
+   * they match what 'snapshot' shows
+   */
+  private async roleAndName(locator: Locator): Promise<{ role: string; name: string }> {
+    const aria = await locator.ariaSnapshot({ timeout: 1000 }).catch(() => '');
+    const [, role = 'none', name = ''] =
+      /^- ([\w-]+)(?: "((?:[^"\\]|\\.)*)")?/.exec(aria.split('\n')[0]) ?? [];
+    return { role, name };
+  }
+
+  /**
+   * The ref from the last snapshot with this role and name whose element
+   * passes `isTarget` (run in the page), or null
+   */
+  private async findRef(
+    role: string,
+    name: string,
+    isTarget: (el: Element) => boolean
+  ): Promise<string | null> {
+    for (const [key, data] of Object.entries(this.browser.getRefMap())) {
+      if (data.role !== role || (data.name ?? '') !== name) continue;
+      const matches = await this.browser
+        .getLocatorFromRef(key)
+        ?.evaluate(isTarget, undefined, { timeout: 1000 })
+        .catch(() => false);
+      if (matches) return `@${key}`;
+    }
+    return null;
+  }
+
+  /**
+   * The element that receives pointer events at the center of `selector`
+   * instead of it, for "intercepts pointer events" failures
+   */
+  private async findInterceptor(selector: string): Promise<Record<string, unknown> | null> {
+    const locator = this.browser.getLocator(selector);
+    await locator.scrollIntoViewIfNeeded({ timeout: 1000 }).catch(() => {});
+    const hit = await locator
+      .evaluate(
+        (target) => {
+          const rect = target.getBoundingClientRect();
+          const el = document.elementFromPoint(
+            rect.x + rect.width / 2,
+            rect.y + rect.height / 2
+          );
+          if (!el || target.contains(el)) return null;
+          el.setAttribute('data-agentbrowser-hit', '');
+          const text = ((el as HTMLElement).innerText ?? '').replace(/\s+/g, ' ').trim();
+          return {
+            tag: el.tagName.toLowerCase(),
+            selector: el.id ? `#${CSS.escape(el.id)}` : null,
+            text: text.slice(0, 80),
+          };
+        },
+        undefined,
+        { timeout: 1000 }
+      )
+      .catch(() => null);
+    if (!hit) return null;
+
+    const frame = this.browser.getActiveFrame();
+    const { role, name } = await this.roleAndName(frame.locator('[data-agentbrowser-hit]'));
+    const ref = await this.findRef(role, name, (el) => el.hasAttribute('data-agentbrowser-hit'));
+    await frame
+      .evaluate(() => {
+        document.querySelector('[data-agentbrowser-hit]')?.removeAttribute('data-agentbrowser-hit');
+      })
+      .catch(() => {});
+    return { ref, role, name, ...hit };
+  }
+
+  /**
+   * Mark the top-most open dialog or modal with ACTIVE_MODAL and describe it.
+   * Candidates not covered by anything else win, then true modals (aria-modal
+   * or showModal()), then the innermost and latest in the document.
+   */
+  private async markActiveModal(): Promise<{ tag: string; role: string; name: string }> {
+    const modal = await this.browser.getPage().evaluate(() => {
+      for (const el of Array.from(document.querySelectorAll('[data-agentbrowser-modal]'))) {
+        el.removeAttribute('data-agentbrowser-modal');
+      }
+      const candidates = Array.from(
+        document.querySelectorAll(
+          'dialog[open], [role="dialog"], [role="alertdialog"], [aria-modal="true"]'
+        )
+      ).filter((el) => {
+        const style = getComputedStyle(el);
+        const rect = el.getBoundingClientRect();
+        return (
+          style.display !== 'none' &&
+          style.visibility !== 'hidden' &&
+          rect.width > 0 &&
+          rect.height > 0
+        );
+      });
+      const onTop = (el: Element) => {
+        const rect = el.getBoundingClientRect();
+        const hit = document.elementFromPoint(
+          Math.min(Math.max(rect.x + rect.width / 2, 0), window.innerWidth - 1),
+          Math.min(Math.max(rect.y + rect.height / 2, 0), window.innerHeight - 1)
+        );
+        return hit !== null && el.contains(hit);
+      };
+      const isModal = (el: Element) =>
+        el.getAttribute('aria-modal') === 'true' || (el instanceof HTMLDialogElement && el.matches(':modal'));
+      const ranked = candidates
+        .map((el, order) => ({ el, order, top: onTop(el), modal: isModal(el) }))
+        .sort(
+          (a, b) =>
+            Number(b.top) - Number(a.top) || Number(b.modal) - Number(a.modal) || b.order - a.order
+        );
+      const found = ranked[0]?.el;
+      if (!found) return null;
+      found.setAttribute('data-agentbrowser-modal', '');
+      const labelledBy = (found.getAttribute('aria-labelledby') ?? '')
+        .split(/\s+/)
+        .map((id) => (id ? document.getElementById(id)?.textContent ?? '' : ''))
+        .join(' ');
+      return {
+        tag: found.tagName.toLowerCase(),
+        role: found.getAttribute('role') ?? (found instanceof HTMLDialogElement ? 'dialog' : ''),
+        name: (found.getAttribute('aria-label') ?? labelledBy).replace(/\s+/g, ' ').trim(),
+      };
+    });
+    if (!modal) {
+      throw new Error(
+        "No open dialog or modal found (role=dialog, role=alertdialog, aria-modal or <dialog open>). " +
+          "Run 'snapshot' without --active-modal, or 'overlays' to find banners and popups."
+      );
+    }
+    return modal;
+  }
+
+  /**
+   * Scroll offsets and limits of the page (or of `selector`), after
+   * scrolling to `to` if given. Positions past the end are clamped.
+   */
+  private async scrollPosition(
+    selector?: string,
+    to?: { x: number; y: number }
+  ): Promise<unknown> {
+    const locator = selector
+      ? this.browser.getLocator(selector)
+      : this.browser.getActiveFrame().locator(':root');
+    return locator.evaluate(
+      (el, to) => {
+        const target = el === document.documentElement ? (document.scrollingElement ?? el) : el;
+        if (to) target.scrollTo({ left: to.x, top: to.y, behavior: 'instant' });
+        const maxX = Math.max(0, target.scrollWidth - target.clientWidth);
+        const maxY = Math.max(0, target.scrollHeight - target.clientHeight);
+        const x = Math.round(target.scrollLeft);
+        const y = Math.round(target.scrollTop);
+        return {
+          x,
+          y,
+          maxX,
+          maxY,
+          viewportWidth: target.clientWidth,
+          viewportHeight: target.clientHeight,
+          atTop: y <= 0,
+          // Fractional zoom can leave the last pixel unreachable
+          atBottom: y >= maxY - 1,
+        };
+      },
+      to ?? null
+    );
+  }
+
+  /**
+   * Click the first visible element of a semantic locator. Several matches
+   * are normal (a "Save" button in a header and a footer), so this doesn't
+   * use strict mode; the match count is returned so callers can tell.
+   */
+  private async clickFirstVisible(
+    locator: Locator,
+    description: string,
+    timeout?: number
+  ): Promise<unknown> {
+    const matches = await locator.count();
+    if (matches === 0) {
+      throw new Error(
+        `No element matches ${description}. Run 'snapshot' to see the names on the page.`
+      );
+    }
+    const target = locator.filter({ visible: true }).first();
+    if ((await target.count()) === 0) {
+      throw new Error(
+        `${matches} element(s) match ${description} but none is visible. ` +
+          `Scroll or open the containing menu first.`
+      );
+    }
+    const text = (await target.textContent().catch(() => null))?.trim().slice(0, 80) ?? null;
+    await target.click({ timeout });
+    return { clicked: description, text, matches };
+  }
 }
 
 export default ActionExecutor;
@@ -9,6 +9,11 @@ import {
   Page,
   Frame,
   Locator,
+  Request,
+  Response,
+  Route,
+  Download,
+  Dialog,
   CDPSession,
   BrowserType,
   chromium,
@@ -24,6 +29,7 @@ import type {
   FrameMetadata,
 } from '../core/types.js';
 import { parseRef } from '../dom/snapshot.js';
+import { PolicyViolationError } from '../utils/errors.js';
 
 // ============================================================================
 // Browser Launch Options
@@ -49,6 +55,8 @@ export interface BrowserLaunchOptions {
   locale?: string;
   /** Timezone ID (e.g., 'America/New_York') */
   timezoneId?: string;
+  /** Fake camera and microphone, optionally fed from files (Chromium only) */
+  fakeMedia?: { video?: string | null; audio?: string | null };
 }
 
 // ============================================================================
@@ -98,6 +106,214 @@ interface ScreencastFrame {
 
 type ScreencastCallback = (frame: ScreencastFrame) => void;
 
+// ============================================================================
+// Device Emulation Types
+// ============================================================================
+
+export interface DeviceDescriptor {
+  name: string;
+  viewport: Viewport;
+  deviceScaleFactor: number;
+  isMobile: boolean;
+  hasTouch: boolean;
+  userAgent: string;
+}
+
+// ============================================================================
+// GraphQL Detection
+// ============================================================================
+
+export interface GraphQLOperation {
+  operationName: string | null;
+  operationType: 'query' | 'mutation' | 'subscription';
+  variables?: unknown;
+}
+
+/**
+ * Extract the GraphQL operations from a request: a JSON POST body (or batch
+ * of bodies) with a `query` or persisted-query hash, or a GET with `query`
+ * in the URL. Returns undefined for anything else.
+ */
+function parseGraphQL(request: Request): GraphQLOperation[] | undefined {
+  let payloads: unknown[];
+  try {
+    if (request.method() === 'GET') {
+      const params = new URL(request.url()).searchParams;
+      if (!params.has('query') && !params.has('extensions')) return undefined;
+      const variables = params.get('variables');
+      payloads = [
+        {
+          query: params.get('query') ?? undefined,
+          operationName: params.get('operationName') ?? undefined,
+          variables: variables ? JSON.parse(variables) : undefined,
+          extensions: params.has('extensions') ? {} : undefined,
+        },
+      ];
+    } else {
+      const body = request.postDataJSON();
+      payloads = Array.isArray(body) ? body : [body];
+    }
+  } catch {
+    return undefined;
+  }
+
+  const operations: GraphQLOperation[] = [];
+  for (const payload of payloads) {
+    if (!payload || typeof payload !== 'object') return undefined;
+    const { query, operationName, variables, extensions } = payload as Record<string, any>;
+    if (typeof query !== 'string' && !(extensions && operationName)) return undefined;
+    const header = typeof query === 'string'
+      ? /^\s*(query|mutation|subscription)\b\s*(\w+)?/.exec(query)
+      : null;
+    operations.push({
+      operationName: operationName ?? header?.[2] ?? null,
+      operationType: (header?.[1] as GraphQLOperation['operationType']) ?? 'query',
+      variables,
+    });
+  }
+  return operations.length > 0 ? operations : undefined;
+}
+
+// ============================================================================
+// HAR Entries
+// ============================================================================
+
+export interface HarEntry {
+  startedDateTime: string;
+  time: number;
+  request: Record<string, unknown>;
+  response: Record<string, unknown>;
+  cache: Record<string, never>;
+  timings: Record<string, number>;
+  _resourceType: string;
+  _failureText?: string;
+}
+
+// Response bodies kept as text in a HAR; the rest are base64-encoded
+const TEXT_MIME = /^text\/|[+/](json|xml|javascript|ecmascript)\b|urlencoded/;
+
+/**
+ * The HAR 1.2 entry for a finished or failed request. Playwright's timings
+ * are offsets from the request start, -1 where a phase didn't happen.
+ */
+async function harEntry(request: Request, withContent: boolean): Promise<HarEntry> {
+  const timing = request.timing();
+  const phase = (start: number, end: number) => (start >= 0 && end >= 0 ? end - start : -1);
+  const timings = {
+    blocked: -1,
+    dns: phase(timing.domainLookupStart, timing.domainLookupEnd),
+    connect: phase(timing.connectStart, timing.connectEnd),
+    ssl: phase(timing.secureConnectionStart, timing.connectEnd),
+    send: 0,
+    wait: phase(timing.requestStart, timing.responseStart),
+    receive: phase(timing.responseStart, timing.responseEnd),
+  };
+  const nameValues = (headers: { name: string; value: string }[]) =>
+    headers.map(({ name, value }) => ({ name, value }));
+  const url = new URL(request.url());
+  const postData = request.postData();
+  const requestHeaders = await request.headersArray();
+
+  const response = await request.response();
+  let responseEntry: Record<string, unknown> = {
+    status: 0,
+    statusText: '',
+    httpVersion: '',
+    cookies: [],
+    headers: [],
+    content: { size: 0, mimeType: 'x-unknown' },
+    redirectURL: '',
+    headersSize: -1,
+    bodySize: -1,
+  };
+  if (response) {
+    const headers = await response.headersArray();
+    const mimeType = (await response.headerValue('content-type')) ?? 'x-unknown';
+    const sizes = await request.sizes().catch(() => null);
+    const body = withContent ? await response.body().catch(() => null) : null;
+    const content: Record<string, unknown> = {
+      size: sizes?.responseBodySize ?? body?.length ?? -1,
+      mimeType,
+    };
+    if (body && TEXT_MIME.test(mimeType)) {
+      content.text = body.toString('utf8');
+    } else if (body) {
+      content.text = body.toString('base64');
+      content.encoding = 'base64';
+    }
+    responseEntry = {
+      ...responseEntry,
+      status: response.status(),
+      statusText: response.statusText(),
+      httpVersion: 'HTTP/1.1',
+      headers: nameValues(headers),
+      content,
+      redirectURL: (await response.headerValue('location')) ?? '',
+      headersSize: sizes?.responseHeadersSize ?? -1,
+      bodySize: sizes?.responseBodySize ?? -1,
+    };
+  }
+
+  const failure = request.failure();
+  return {
+    startedDateTime: new Date(timing.startTime).toISOString(),
+    time: timing.responseEnd >= 0 ? timing.responseEnd : 0,
+    request: {
+      method: request.method(),
+      url: request.url(),
+      httpVersion: 'HTTP/1.1',
+      cookies: [],
+      headers: nameValues(requestHeaders),
+      queryString: [...url.searchParams].map(([name, value]) => ({ name, value })),
+      ...(postData !== null && {
+        postData: {
+          mimeType: (await request.headerValue('content-type')) ?? '',
+          text: postData,
+        },
+      }),
+      headersSize: -1,
+      bodySize: postData !== null ? Buffer.byteLength(postData) : 0,
+    },
+    response: responseEntry,
+    cache: {},
+    timings,
+    _resourceType: request.resourceType(),
+    ...(failure && { _failureText: failure.errorText }),
+  };
+}
+
+/**
+ * Whether `url` falls under a URL rule: a glob matched against the host, or
+ * against the whole URL when it contains a `/`. `*.bank.com` covers
+ * `bank.com` itself as well as its subdomains. The host is compared without
+ * userinfo, a default port or a trailing dot, so none of them slips a URL
+ * past a rule.
+ */
+function matchesUrlPattern(pattern: string, url: string): boolean {
+  const glob = (p: string, text: string) =>
+    new RegExp(
+      `^${p.toLowerCase().replace(/[.+?^${}()|[\]\\]/g, '\\$&').replace(/\*/g, '.*')}$`
+    ).test(text.toLowerCase());
+  let parsed: URL;
+  try {
+    parsed = new URL(url);
+  } catch {
+    return pattern.includes('/') && glob(pattern, url);
+  }
+  const host = parsed.hostname.replace(/\.+$/, '');
+  if (pattern.includes('/')) {
+    const port = parsed.port ? `:${parsed.port}` : '';
+    const bare = parsed.host
+      ? `${parsed.protocol}//${host}${port}${parsed.pathname}${parsed.search}${parsed.hash}`
+      : url;
+    return glob(pattern, bare);
+  }
+  const hostPattern = pattern.replace(/\.+$/, '');
+  return (
+    glob(hostPattern, host) || (hostPattern.startsWith('*.') && glob(hostPattern.slice(2), host))
+  );
+}
+
 // ============================================================================
 // Browser Manager Class
 // ============================================================================
@@ -127,9 +343,11 @@ export class BrowserManager {
     timestamp: number;
     location?: { url: string; lineNumber: number; columnNumber: number };
   }> = [];
+  // Uncaught exceptions and unhandled promise rejections
   private pageErrors: Array<{
     message: string;
     stack?: string;
+    url: string;
     timestamp: number;
   }> = [];
 
@@ -141,6 +359,117 @@ export class BrowserManager {
     resourceType: string;
     timestamp: number;
     responseHeaders?: Record<string, string>;
+    // GraphQL operations in the request (several for batched requests) and,
+    // for those requests only, the response body
+    graphql?: GraphQLOperation[];
+    responseBody?: string;
+  }> = [];
+  private requestRecords = new WeakMap<Request, (typeof this.networkRequests)[number]>();
+  private requestObjects = new WeakMap<object, Request>();
+  private responseObjects = new WeakMap<object, Response>();
+  // Latest main-frame document response of each page
+  private documentResponses = new WeakMap<Page, Response>();
+
+  // Downloads from any tab, for `downloads` and `download`; `path` is set
+  // once a download is saved out of Playwright's temporary directory, which
+  // only lasts as long as the browser
+  private downloads: Array<{
+    url: string;
+    suggestedFilename: string;
+    state: 'in progress' | 'complete' | 'failed' | 'canceled';
+    path?: string;
+    size?: number;
+    error?: string;
+    timestamp: number;
+  }> = [];
+  private downloadObjects = new WeakMap<(typeof this.downloads)[number], Download>();
+  private downloadRecords = new WeakMap<Download, (typeof this.downloads)[number]>();
+
+  // Where navigation may go (--allow-urls/--deny-urls), enforced on
+  // navigate, newpage and popups whatever the command policy says
+  private urlRules: { allow: string[]; deny: string[] } = { allow: [], deny: [] };
+
+  // The preset `device` emulates, and the user agent to put back when it's
+  // cleared
+  private emulatedDevice: DeviceDescriptor | null = null;
+  private defaultUserAgent: string | null = null;
+
+  // Keys held with `keydown` until their `keyup`; Playwright applies held
+  // modifiers to the clicks in between
+  private heldKeys = new Set<string>();
+
+  // Dialogs are answered as they open, so they never stall a command: once
+  // as armed by `dialog accept|dismiss`, otherwise by the `dialog auto`
+  // mode. Those answered since the last command are reported with its result.
+  private dialogAuto: 'accept' | 'dismiss' = 'dismiss';
+  private dialogNext: { accept: boolean; promptText?: string } | null = null;
+  private handledDialogs: Array<{
+    type: string;
+    message: string;
+    accepted: boolean;
+    promptText?: string;
+    url: string;
+    timestamp: number;
+  }> = [];
+
+  // HAR recording started with `har start`: entries are built as requests
+  // finish and collected on `har stop`
+  private harRecording: {
+    path: string;
+    content: boolean;
+    entries: Promise<HarEntry | null>[];
+  } | null = null;
+
+  // Request routes added with `route`, in the order they were added, and
+  // what each installed so `unroute` can take it off again
+  private routes: Array<{
+    url: string;
+    handler: 'abort' | 'continue' | 'fulfill';
+    status?: number;
+    contentType?: string;
+    bytes?: number;
+    hits: number;
+  }> = [];
+  private routeHandlers = new WeakMap<
+    (typeof this.routes)[number],
+    { context: BrowserContext; match: string | RegExp; handler: (route: Route) => Promise<void> }
+  >();
+
+  // Page lifecycle events (popups, dialogs, downloads, console errors) for
+  // script-mode hooks, numbered so a runner can ask for what it hasn't seen
+  private events: Array<{
+    seq: number;
+    type: 'popup' | 'dialog' | 'download' | 'console-error';
+    url: string;
+    detail?: string;
+    timestamp: number;
+  }> = [];
+  private eventSeq = 0;
+
+  // Notification capture: Notification API and showNotification() calls
+  // are recorded here while enabled
+  private notificationCapture = false;
+  private notificationContexts = new WeakSet<BrowserContext>();
+  private notifications: Array<{
+    source: 'Notification' | 'serviceWorker';
+    title: string;
+    body?: string;
+    tag?: string;
+    icon?: string;
+    url: string;
+    timestamp: number;
+  }> = [];
+
+  // window.postMessage and BroadcastChannel traffic, recorded once tapped.
+  // Chatty widgets post constantly, so only the latest are kept.
+  private messageContexts = new WeakSet<BrowserContext>();
+  private messages: Array<{
+    kind: 'postMessage' | 'broadcast';
+    channel: string | null;
+    origin: string | null;
+    frameUrl: string;
+    data: string;
+    timestamp: number;
   }> = [];
 
   // GIF recording state
@@ -192,6 +521,17 @@ export class BrowserManager {
       launchArgs.push(`--load-extension=${options.extensions.join(',')}`);
     }
 
+    // Fake media devices, with permission prompts auto-accepted
+    if (options.fakeMedia && this.browserType === 'chromium') {
+      launchArgs.push('--use-fake-device-for-media-stream', '--use-fake-ui-for-media-stream');
+      if (options.fakeMedia.video) {
+        launchArgs.push(`--use-file-for-fake-video-capture=${options.fakeMedia.video}`);
+      }
+      if (options.fakeMedia.audio) {
+        launchArgs.push(`--use-file-for-fake-audio-capture=${options.fakeMedia.audio}`);
+      }
+    }
+
     // Context options shared between persistent and non-persistent contexts
     const contextOptions = {
       viewport: options.viewport ?? { width: 1280, height: 720 },
@@ -202,10 +542,20 @@ export class BrowserManager {
       timezoneId: options.timezoneId,
     };
 
+    // Chromium only loads extensions into a persistent context, so give
+    // extension sessions a throwaway profile when none was provided
+    let userDataDir = options.userDataDir;
+    if (!userDataDir && options.extensions?.length && this.browserType === 'chromium') {
+      const fs = await import('fs');
+      const os = await import('os');
+      const path = await import('path');
+      userDataDir = fs.mkdtempSync(path.join(os.tmpdir(), 'agentbrowser-pro-ext-'));
+    }
+
     // Use persistent context if userDataDir is provided
-    if (options.userDataDir) {
+    if (userDataDir) {
       this.isPersistentContext = true;
-      const context = await browserTypeInstance.launchPersistentContext(options.userDataDir, {
+      const context = await browserTypeInstance.launchPersistentContext(userDataDir, {
         headless: options.headless ?? true,
         ...contextOptions,
         executablePath: options.executablePath,
@@ -348,6 +698,9 @@ export class BrowserManager {
   private setupPageListeners(page: Page): void {
     // Console message tracking
     page.on('console', (msg) => {
+      if (msg.type() === 'error') {
+        this.recordEvent('console-error', page, msg.text());
+      }
       this.consoleMessages.push({
         type: msg.type(),
         text: msg.text(),
@@ -360,33 +713,72 @@ export class BrowserManager {
       });
     });
 
+    // Lifecycle events for script hooks
+    page.on('popup', (popup) => {
+      const violation = this.urlViolation(popup.url());
+      if (violation) {
+        this.recordEvent('popup', popup, `${popup.url()} (closed: it ${violation})`);
+        popup.close().catch(() => {});
+        return;
+      }
+      this.recordEvent('popup', popup, popup.url());
+    });
+    page.on('download', (download) => {
+      this.recordEvent('download', page, download.suggestedFilename());
+      this.trackDownload(download);
+    });
+    page.on('dialog', (dialog) => {
+      this.recordEvent('dialog', page, `${dialog.type()}: ${dialog.message()}`);
+      this.answerDialog(dialog, page);
+    });
+
     // Page error tracking
     page.on('pageerror', (error) => {
       this.pageErrors.push({
         message: error.message,
         stack: error.stack,
+        url: page.url(),
         timestamp: Date.now(),
       });
     });
 
     // Network request tracking
     page.on('request', (request) => {
-      this.networkRequests.push({
+      const record = {
         url: request.url(),
         method: request.method(),
         resourceType: request.resourceType(),
         timestamp: Date.now(),
-      });
+        graphql: parseGraphQL(request),
+      };
+      this.networkRequests.push(record);
+      this.requestRecords.set(request, record);
+      this.requestObjects.set(record, request);
     });
 
+    page.on('requestfinished', (request) => this.recordHarEntry(request));
+    page.on('requestfailed', (request) => this.recordHarEntry(request));
+
     page.on('response', (response) => {
+      const request = response.request();
+      if (request.isNavigationRequest() && request.frame() === page.mainFrame()) {
+        this.documentResponses.set(page, response);
+      }
+
       // Update the matching request with response info
-      const requestIndex = this.networkRequests.findIndex(
-        (r) => r.url === response.url() && !r.status
-      );
-      if (requestIndex !== -1) {
-        this.networkRequests[requestIndex].status = response.status();
-        this.networkRequests[requestIndex].responseHeaders = response.headers();
+      const record = this.requestRecords.get(response.request());
+      if (record) {
+        record.status = response.status();
+        record.responseHeaders = response.headers();
+        this.responseObjects.set(record, response);
+        if (record.graphql) {
+          response
+            .text()
+            .then((body) => {
+              record.responseBody = body;
+            })
+            .catch(() => {});
+        }
       }
     });
   }
@@ -420,6 +812,10 @@ export class BrowserManager {
     return this.browser !== null || this.isPersistentContext;
   }
 
+  isHeaded(): boolean {
+    return this.launchOptions.headless === false;
+  }
+
   // ============================================================================
   // Page Management
   // ============================================================================
@@ -436,6 +832,9 @@ export class BrowserManager {
   }
 
   async newPage(url?: string): Promise<Page> {
+    if (url) {
+      this.checkUrl(url);
+    }
     const context = this.getContext();
     const page = await context.newPage();
     this.pages.push(page);
@@ -550,6 +949,31 @@ export class BrowserManager {
     this.activeFrame = null;
   }
 
+  /**
+   * Extension directories passed at launch, and the IDs of extensions with a
+   * running service worker or background page
+   */
+  getExtensions(): { paths: string[]; ids: string[] } {
+    const ids = new Set<string>();
+    for (const context of this.contexts) {
+      const workers = [...context.serviceWorkers(), ...context.backgroundPages()];
+      for (const worker of workers) {
+        const match = worker.url().match(/^chrome-extension:\/\/([a-p]{32})\//);
+        if (match) {
+          ids.add(match[1]);
+        }
+      }
+    }
+    return { paths: this.launchOptions.extensions ?? [], ids: [...ids] };
+  }
+
+  /**
+   * Whether the browser was launched with fake media devices
+   */
+  hasFakeMedia(): boolean {
+    return !!this.launchOptions.fakeMedia && this.browserType === 'chromium';
+  }
+
   getFrames(): Array<{ name: string; url: string }> {
     const page = this.getPage();
     return page.frames().map((frame) => ({
@@ -594,16 +1018,17 @@ export class BrowserManager {
     if (!refData) return null;
 
     const frame = this.getActiveFrame();
+    const root = refData.scope ? frame.locator(refData.scope) : frame;
 
     // Build locator with exact: true to avoid substring matches
     let locator: Locator;
     if (refData.name) {
-      locator = frame.getByRole(refData.role as Parameters<typeof frame.getByRole>[0], {
+      locator = root.getByRole(refData.role as Parameters<typeof frame.getByRole>[0], {
         name: refData.name,
         exact: true,
       });
     } else {
-      locator = frame.getByRole(refData.role as Parameters<typeof frame.getByRole>[0]);
+      locator = root.getByRole(refData.role as Parameters<typeof frame.getByRole>[0]);
     }
 
     // If an nth index is stored (for disambiguation), use it
@@ -630,6 +1055,54 @@ export class BrowserManager {
     return this.cdpSession;
   }
 
+  // ============================================================================
+  // Device Emulation
+  // ============================================================================
+
+  /**
+   * Emulate a device on the current page: viewport, pixel ratio, mobile
+   * layout, touch and user agent (Chromium only)
+   */
+  async emulateDevice(device: DeviceDescriptor): Promise<void> {
+    const page = this.getPage();
+    const cdp = await this.getCDPSession();
+    this.defaultUserAgent ??= await page.evaluate(() => navigator.userAgent);
+    // Keep Playwright's idea of the viewport in step for screenshots
+    await page.setViewportSize(device.viewport);
+    await cdp.send('Emulation.setDeviceMetricsOverride', {
+      width: device.viewport.width,
+      height: device.viewport.height,
+      deviceScaleFactor: device.deviceScaleFactor,
+      mobile: device.isMobile,
+    });
+    await cdp.send('Emulation.setTouchEmulationEnabled', {
+      enabled: device.hasTouch,
+      maxTouchPoints: device.hasTouch ? 5 : 0,
+    });
+    await cdp.send('Emulation.setUserAgentOverride', { userAgent: device.userAgent });
+    this.emulatedDevice = device;
+  }
+
+  /**
+   * Stop emulating a device; the viewport keeps its size
+   */
+  async resetDevice(): Promise<void> {
+    if (!this.emulatedDevice) return;
+    const cdp = await this.getCDPSession();
+    await cdp.send('Emulation.clearDeviceMetricsOverride');
+    await cdp.send('Emulation.setTouchEmulationEnabled', { enabled: false });
+    await cdp.send('Emulation.setUserAgentOverride', { userAgent: this.defaultUserAgent ?? '' });
+    this.emulatedDevice = null;
+  }
+
+  /**
+   * Resize the viewport, leaving any device emulation behind
+   */
+  async setViewport(viewport: Viewport): Promise<void> {
+    await this.resetDevice();
+    await this.getPage().setViewportSize(viewport);
+  }
+
   // ============================================================================
   // Screencast (for pair browsing)
   // ============================================================================
@@ -819,12 +1292,29 @@ export class BrowserManager {
   // Network Request Methods
   // ============================================================================
 
-  getNetworkRequests(options?: { urlPattern?: string; clear?: boolean }): typeof this.networkRequests {
+  getNetworkRequests(options?: {
+    urlPattern?: string;
+    clear?: boolean;
+    graphql?: boolean;
+    operation?: string;
+    includeBody?: boolean;
+  }): typeof this.networkRequests {
     let requests = this.networkRequests;
     if (options?.urlPattern) {
       const pattern = new RegExp(options.urlPattern);
       requests = requests.filter((r) => pattern.test(r.url));
     }
+    if (options?.graphql || options?.operation) {
+      requests = requests.filter((r) => r.graphql);
+    }
+    if (options?.operation) {
+      requests = requests.filter((r) =>
+        r.graphql?.some((op) => op.operationName === options.operation)
+      );
+    }
+    if (!options?.includeBody) {
+      requests = requests.map(({ responseBody: _body, ...r }) => r);
+    }
     if (options?.clear) {
       this.networkRequests = [];
     }
@@ -835,6 +1325,566 @@ export class BrowserManager {
     this.networkRequests = [];
   }
 
+  /**
+   * Captured requests whose URL matches `urlPattern` (a regex), narrowed by
+   * method and a substring of the request body. `urlMatches` counts the
+   * requests that matched the URL alone, to explain near misses.
+   */
+  findRequests(
+    urlPattern: string,
+    options: { method?: string; bodyContains?: string }
+  ): { requests: typeof this.networkRequests; urlMatches: number } {
+    const pattern = new RegExp(urlPattern);
+    const byUrl = this.networkRequests.filter((r) => pattern.test(r.url));
+    const requests = byUrl.filter((r) => {
+      if (options.method && r.method.toUpperCase() !== options.method.toUpperCase()) {
+        return false;
+      }
+      if (options.bodyContains !== undefined) {
+        const body = this.requestObjects.get(r)?.postData() ?? '';
+        return body.includes(options.bodyContains);
+      }
+      return true;
+    });
+    return { requests, urlMatches: byUrl.length };
+  }
+
+  /**
+   * Wait for a response whose URL matches `urlPattern` (a regex). A matching
+   * response to a request made at or after `since` that already arrived is
+   * returned straight away, so a wait issued after the triggering click
+   * can't miss a fast response.
+   */
+  /**
+   * Response that delivered the active page's current document, if it was
+   * loaded while the daemon was listening
+   */
+  getDocumentResponse(): Response | null {
+    return this.documentResponses.get(this.getPage()) ?? null;
+  }
+
+  async waitForResponse(
+    urlPattern: string,
+    options: { status?: number; timeout?: number; since: number }
+  ): Promise<Response> {
+    const pattern = new RegExp(urlPattern);
+    const matches = (url: string, status?: number) =>
+      pattern.test(url) && (options.status === undefined || status === options.status);
+
+    for (let i = this.networkRequests.length - 1; i >= 0; i--) {
+      const record = this.networkRequests[i];
+      if (record.timestamp < options.since) break;
+      const response = this.responseObjects.get(record);
+      if (response && matches(record.url, record.status)) {
+        return response;
+      }
+    }
+
+    return this.getPage().waitForResponse((r) => matches(r.url(), r.status()), {
+      timeout: options.timeout,
+    });
+  }
+
+  // ============================================================================
+  // URL Rule Methods
+  // ============================================================================
+
+  setUrlRules(allow: string[], deny: string[]): void {
+    this.urlRules = { allow, deny };
+  }
+
+  /**
+   * Why the URL rules keep the session away from `url`, if they do
+   */
+  private urlViolation(url: string): string | null {
+    if (url === 'about:blank') {
+      return null;
+    }
+    const denied = this.urlRules.deny.find((pattern) => matchesUrlPattern(pattern, url));
+    if (denied) {
+      return `is blocked by the policy pattern '${denied}'`;
+    }
+    const { allow } = this.urlRules;
+    if (allow.length > 0 && !allow.some((pattern) => matchesUrlPattern(pattern, url))) {
+      return `isn't among the allowed URLs (${allow.join(', ')})`;
+    }
+    return null;
+  }
+
+  /**
+   * Throw a PolicyViolationError if the URL rules keep the session away
+   * from `url`
+   */
+  checkUrl(url: string): void {
+    const violation = this.urlViolation(url);
+    if (violation) {
+      throw new PolicyViolationError(url, violation);
+    }
+  }
+
+  // ============================================================================
+  // Keyboard Methods
+  // ============================================================================
+
+  /**
+   * Hold a key down; returns every key now held
+   */
+  async keyDown(key: string): Promise<string[]> {
+    await this.getPage().keyboard.down(key);
+    this.heldKeys.add(key);
+    return [...this.heldKeys];
+  }
+
+  /**
+   * Release a held key; returns the keys still held
+   */
+  async keyUp(key: string): Promise<string[]> {
+    await this.getPage().keyboard.up(key);
+    this.heldKeys.delete(key);
+    return [...this.heldKeys];
+  }
+
+  // ============================================================================
+  // Dialog Methods
+  // ============================================================================
+
+  private answerDialog(dialog: Dialog, page: Page): void {
+    const answer = this.dialogNext ?? { accept: this.dialogAuto === 'accept' };
+    this.dialogNext = null;
+    // A prompt accepted without text keeps its default value
+    const promptText =
+      dialog.type() === 'prompt' && answer.accept
+        ? (answer.promptText ?? dialog.defaultValue())
+        : undefined;
+    this.handledDialogs.push({
+      type: dialog.type(),
+      message: dialog.message(),
+      accepted: answer.accept,
+      promptText,
+      url: page.url(),
+      timestamp: Date.now(),
+    });
+    (answer.accept ? dialog.accept(promptText) : dialog.dismiss()).catch(() => {});
+  }
+
+  /**
+   * Answer the next dialog this way instead of by the auto mode
+   */
+  armDialog(accept: boolean, promptText?: string): void {
+    this.dialogNext = { accept, promptText };
+  }
+
+  /**
+   * Answer dialogs this way from now on; clears any armed answer
+   */
+  setDialogAuto(mode: 'accept' | 'dismiss'): void {
+    this.dialogAuto = mode;
+    this.dialogNext = null;
+  }
+
+  /**
+   * Dialogs answered since the last call
+   */
+  takeHandledDialogs(): typeof this.handledDialogs {
+    return this.handledDialogs.splice(0);
+  }
+
+  // ============================================================================
+  // Download Methods
+  // ============================================================================
+
+  private trackDownload(download: Download): void {
+    const record: (typeof this.downloads)[number] = {
+      url: download.url(),
+      suggestedFilename: download.suggestedFilename(),
+      state: 'in progress',
+      timestamp: Date.now(),
+    };
+    this.downloads.push(record);
+    this.downloadObjects.set(record, download);
+    this.downloadRecords.set(download, record);
+    download
+      .path()
+      .then(async (tmp) => {
+        const fs = await import('fs');
+        record.size = (await fs.promises.stat(tmp)).size;
+        record.state = 'complete';
+      })
+      .catch(async () => {
+        const failure = await download.failure().catch(() => null);
+        record.state = failure === 'canceled' ? 'canceled' : 'failed';
+        record.error = failure ?? undefined;
+      });
+  }
+
+  /**
+   * Save a download to `target` (a file, or a directory to keep its
+   * suggested name in), waiting for it to finish. An existing file is
+   * never overwritten: the name gets a " (1)", " (2)"... suffix instead.
+   */
+  async saveDownload(
+    download: Download,
+    target: { file?: string; dir?: string }
+  ): Promise<(typeof this.downloads)[number]> {
+    const fs = await import('fs');
+    const path = await import('path');
+    const record = this.downloadRecords.get(download);
+    if (!record) {
+      throw new Error(`Download of ${download.url()} was not tracked`);
+    }
+    const failure = await download.failure();
+    if (failure) {
+      throw new Error(`Download of ${record.suggestedFilename} failed: ${failure}`);
+    }
+
+    const wanted = target.file ?? path.join(target.dir ?? '.', record.suggestedFilename);
+    const { dir, name, ext } = path.parse(wanted);
+    let file = wanted;
+    for (let n = 1; fs.existsSync(file); n++) {
+      file = path.join(dir, `${name} (${n})${ext}`);
+    }
+    await fs.promises.mkdir(dir, { recursive: true });
+    await download.saveAs(file);
+    record.path = file;
+    record.size = (await fs.promises.stat(file)).size;
+    record.state = 'complete';
+    return record;
+  }
+
+  /**
+   * Downloads seen this session. With `dir`, finished downloads not saved
+   * yet are saved there first.
+   */
+  async getDownloads(dir?: string): Promise<typeof this.downloads> {
+    if (dir) {
+      for (const record of this.downloads) {
+        const download = this.downloadObjects.get(record);
+        if (download && record.state === 'complete' && !record.path) {
+          await this.saveDownload(download, { dir }).catch(() => {});
+        }
+      }
+    }
+    return this.downloads;
+  }
+
+  // ============================================================================
+  // HAR Recording
+  // ============================================================================
+
+  /**
+   * Start recording every request that completes (or fails) in any tab into
+   * an in-memory HAR, written out by `stopHar`. With `content`, response
+   * bodies are kept too: text as is, anything else base64-encoded.
+   */
+  startHar(path: string, content: boolean): void {
+    if (this.harRecording) {
+      throw new Error(
+        `Already recording a HAR to ${this.harRecording.path}. Run 'har stop' first.`
+      );
+    }
+    this.harRecording = { path, content, entries: [] };
+  }
+
+  /**
+   * Stop recording and return the HAR log with the file it was started for.
+   * Requests still in flight are left out.
+   */
+  async stopHar(): Promise<{ path: string; har: object; entries: number }> {
+    const recording = this.harRecording;
+    if (!recording) {
+      throw new Error("No HAR recording in progress. Start one with 'har start <path>'.");
+    }
+    this.harRecording = null;
+    const entries = (await Promise.all(recording.entries))
+      .filter((e): e is HarEntry => e !== null)
+      .sort((a, b) => a.startedDateTime.localeCompare(b.startedDateTime));
+    const har = {
+      log: {
+        version: '1.2',
+        creator: { name: 'agentbrowser-pro', version: '1.0.0' },
+        pages: [],
+        entries,
+      },
+    };
+    return { path: recording.path, har, entries: entries.length };
+  }
+
+  private recordHarEntry(request: Request): void {
+    const recording = this.harRecording;
+    if (!recording) return;
+    recording.entries.push(harEntry(request, recording.content).catch(() => null));
+  }
+
+  // ============================================================================
+  // Route Methods
+  // ============================================================================
+
+  /**
+   * Intercept requests matching a URL glob (or regex, written `/.../`) in
+   * every tab of the context: abort them, or fulfill them with the given
+   * response. Routing a pattern again replaces its previous handler.
+   */
+  async addRoute(
+    url: string,
+    handler: 'abort' | 'continue' | 'fulfill',
+    response?: { status?: number; headers?: Record<string, string>; body?: string }
+  ): Promise<(typeof this.routes)[number]> {
+    await this.removeRoute(url);
+    const context = this.getPage().context();
+    const match =
+      url.length > 2 && url.startsWith('/') && url.endsWith('/')
+        ? new RegExp(url.slice(1, -1))
+        : url;
+    const record = {
+      url,
+      handler,
+      status: handler === 'fulfill' ? (response?.status ?? 200) : undefined,
+      contentType: response?.headers?.['content-type'],
+      bytes: response?.body !== undefined ? Buffer.byteLength(response.body) : undefined,
+      hits: 0,
+    };
+    const routeHandler = async (route: Route) => {
+      record.hits++;
+      switch (handler) {
+        case 'abort':
+          await route.abort();
+          break;
+        case 'continue':
+          await route.continue();
+          break;
+        case 'fulfill':
+          await route.fulfill({
+            status: record.status,
+            headers: response?.headers,
+            body: response?.body,
+          });
+          break;
+      }
+    };
+    await context.route(match, routeHandler);
+    this.routes.push(record);
+    this.routeHandlers.set(record, { context, match, handler: routeHandler });
+    return record;
+  }
+
+  /**
+   * Stop intercepting one pattern, or every routed pattern when none is
+   * given. Returns the patterns removed.
+   */
+  async removeRoute(url?: string): Promise<string[]> {
+    const removed = this.routes.filter((r) => url === undefined || r.url === url);
+    for (const record of removed) {
+      const installed = this.routeHandlers.get(record);
+      // The context may be gone with a closed window
+      await installed?.context.unroute(installed.match, installed.handler).catch(() => {});
+    }
+    this.routes = this.routes.filter((r) => !removed.includes(r));
+    return removed.map((r) => r.url);
+  }
+
+  getRoutes(): typeof this.routes {
+    return this.routes;
+  }
+
+  // ============================================================================
+  // Lifecycle Event Methods
+  // ============================================================================
+
+  private recordEvent(
+    type: (typeof this.events)[number]['type'],
+    page: Page,
+    detail?: string
+  ): void {
+    this.events.push({
+      seq: ++this.eventSeq,
+      type,
+      url: page.url(),
+      detail,
+      timestamp: Date.now(),
+    });
+    // Only recent events matter to a running script
+    if (this.events.length > 1000) {
+      this.events.splice(0, this.events.length - 1000);
+    }
+  }
+
+  /**
+   * Events after sequence number `since`, and the cursor to pass next time
+   */
+  getEvents(since = 0): { events: typeof this.events; next: number } {
+    return {
+      events: this.events.filter((e) => e.seq > since),
+      next: this.eventSeq,
+    };
+  }
+
+  // ============================================================================
+  // Notification Methods
+  // ============================================================================
+
+  /**
+   * Start or stop recording notifications. The first call swaps in a shim
+   * for every page (current and future) that grants permission and reports
+   * each notification instead of showing it; turning capture off only stops
+   * recording, pages keep the shim until the session restarts.
+   */
+  async setNotificationCapture(enabled: boolean): Promise<void> {
+    this.notificationCapture = enabled;
+    if (!enabled) return;
+
+    const shim = () => {
+      const w = window as any;
+      if (w.__abpNotificationShim) return;
+      w.__abpNotificationShim = true;
+
+      const record = (source: string, title: unknown, options: any = {}) => {
+        try {
+          w.__abpNotify({
+            source,
+            title: String(title),
+            body: options.body,
+            tag: options.tag,
+            icon: options.icon,
+          });
+        } catch {
+          // Binding not available in this frame
+        }
+      };
+
+      class CapturedNotification extends EventTarget {
+        static permission = 'granted';
+        static requestPermission = async () => 'granted';
+        title: string;
+        onclick: unknown = null;
+        onclose: unknown = null;
+        onerror: unknown = null;
+        onshow: unknown = null;
+        constructor(title: string, options: any = {}) {
+          super();
+          this.title = title;
+          Object.assign(this, options);
+          record('Notification', title, options);
+        }
+        close() {}
+      }
+      w.Notification = CapturedNotification;
+
+      if (w.ServiceWorkerRegistration) {
+        w.ServiceWorkerRegistration.prototype.showNotification = async function (
+          title: string,
+          options?: object
+        ) {
+          record('serviceWorker', title, options);
+        };
+      }
+    };
+
+    for (const context of this.contexts) {
+      if (this.notificationContexts.has(context)) continue;
+      this.notificationContexts.add(context);
+      await context.exposeBinding('__abpNotify', (source, n: any) => {
+        if (!this.notificationCapture) return;
+        this.notifications.push({
+          source: n.source,
+          title: n.title,
+          body: n.body,
+          tag: n.tag,
+          icon: n.icon,
+          url: source.page?.url() ?? '',
+          timestamp: Date.now(),
+        });
+      });
+      await context.addInitScript(shim);
+    }
+    for (const page of this.pages) {
+      await page.evaluate(shim).catch(() => {});
+    }
+  }
+
+  getNotifications(clear?: boolean): typeof this.notifications {
+    const notifications = this.notifications;
+    if (clear) {
+      this.notifications = [];
+    }
+    return notifications;
+  }
+
+  // ============================================================================
+  // Message Tap Methods
+  // ============================================================================
+
+  /**
+   * Start recording messages in every frame: 'message' events delivered to
+   * a window (which covers postMessage between a page and its iframes) and
+   * BroadcastChannel posts. Once tapped, pages stay tapped until the session
+   * restarts.
+   */
+  async tapMessages(): Promise<void> {
+    const tap = () => {
+      const w = window as any;
+      if (w.__abpMessageTap) return;
+      w.__abpMessageTap = true;
+
+      const preview = (data: unknown) => {
+        let text: string;
+        try {
+          text = typeof data === 'string' ? data : (JSON.stringify(data) ?? String(data));
+        } catch {
+          text = Object.prototype.toString.call(data);
+        }
+        return text.length > 2000 ? `${text.slice(0, 2000)}…` : text;
+      };
+      const record = (kind: string, channel: string | null, origin: string | null, data: unknown) => {
+        try {
+          w.__abpMessage({ kind, channel, origin, data: preview(data) });
+        } catch {
+          // Binding not available in this frame
+        }
+      };
+
+      window.addEventListener(
+        'message',
+        (event) => record('postMessage', null, event.origin || null, event.data),
+        true
+      );
+      if (w.BroadcastChannel) {
+        const post = w.BroadcastChannel.prototype.postMessage;
+        w.BroadcastChannel.prototype.postMessage = function (this: BroadcastChannel, message: unknown) {
+          record('broadcast', this.name, location.origin, message);
+          return post.call(this, message);
+        };
+      }
+    };
+
+    for (const context of this.contexts) {
+      if (this.messageContexts.has(context)) continue;
+      this.messageContexts.add(context);
+      await context.exposeBinding('__abpMessage', (source, m: any) => {
+        this.messages.push({
+          kind: m.kind,
+          channel: m.channel,
+          origin: m.origin,
+          frameUrl: source.frame.url(),
+          data: m.data,
+          timestamp: Date.now(),
+        });
+        if (this.messages.length > 1000) {
+          this.messages.splice(0, this.messages.length - 1000);
+        }
+      });
+      await context.addInitScript(tap);
+    }
+    for (const page of this.pages) {
+      for (const frame of page.frames()) {
+        await frame.evaluate(tap).catch(() => {});
+      }
+    }
+  }
+
+  getMessages(): typeof this.messages {
+    return this.messages;
+  }
+
   // ============================================================================
   // State Save/Load Methods (Auth Persistence)
   // ============================================================================
@@ -119,9 +119,17 @@ export async function startDaemon(options: DaemonOptions = {}): Promise<void> {
   cleanupSocket();
 
   const browser = new BrowserManager();
+  const urlRules = (name: string) =>
+    (process.env[name] ?? '')
+      .split(',')
+      .map((p) => p.trim())
+      .filter(Boolean);
+  browser.setUrlRules(urlRules('AGENT_BROWSER_ALLOW_URLS'), urlRules('AGENT_BROWSER_DENY_URLS'));
   const executor = new ActionExecutor(browser);
   let streamServer: StreamServer | null = null;
   let shuttingDown = false;
+  const startedAt = Date.now();
+  let commandsServed = 0;
 
   const server = net.createServer((socket) => {
     let buffer = '';
@@ -146,6 +154,28 @@ export async function startDaemon(options: DaemonOptions = {}): Promise<void> {
             continue;
           }
 
+          // Stats are answered without touching (or launching) the browser,
+          // and don't count as served commands
+          if (parseResult.command.action === 'daemonStats') {
+            socket.write(
+              serializeResponse({
+                id: parseResult.command.id,
+                success: true,
+                result: {
+                  pid: process.pid,
+                  uptimeMs: Date.now() - startedAt,
+                  commandsServed,
+                  browserLaunched: browser.isLaunched(),
+                  pages: browser.isLaunched() ? browser.getPages().length : 0,
+                  memory: process.memoryUsage(),
+                },
+              }) + '\n'
+            );
+            continue;
+          }
+
+          commandsServed++;
+
           // Auto-launch browser if needed
           if (
             !browser.isLaunched() &&
@@ -162,6 +192,9 @@ export async function startDaemon(options: DaemonOptions = {}): Promise<void> {
               headless: !(options.headed || process.env.AGENT_BROWSER_HEADED === '1'),
               executablePath: options.executablePath ?? process.env.AGENT_BROWSER_EXECUTABLE_PATH,
               extensions,
+              fakeMedia: process.env.AGENT_BROWSER_FAKE_MEDIA
+                ? JSON.parse(process.env.AGENT_BROWSER_FAKE_MEDIA)
+                : undefined,
             });
           }
 
@@ -181,6 +181,16 @@ const pressSchema = baseCommandSchema.extend({
   timeout: z.number().positive().optional(),
 });
 
+const keyDownSchema = baseCommandSchema.extend({
+  action: z.literal('keyDown'),
+  key: z.string(),
+});
+
+const keyUpSchema = baseCommandSchema.extend({
+  action: z.literal('keyUp'),
+  key: z.string(),
+});
+
 const scrollSchema = baseCommandSchema.extend({
   action: z.literal('scroll'),
   selector: z.string().optional(),
@@ -196,7 +206,10 @@ const scrollSchema = baseCommandSchema.extend({
 const dragSchema = baseCommandSchema.extend({
   action: z.literal('drag'),
   source: z.string(),
-  target: z.string(),
+  // Drop on an element, or at page coordinates with toX/toY
+  target: z.string().optional(),
+  toX: z.number().optional(),
+  toY: z.number().optional(),
   force: z.boolean().optional(),
   noWaitAfter: z.boolean().optional(),
   timeout: z.number().positive().optional(),
@@ -221,6 +234,7 @@ const snapshotSchema = baseCommandSchema.extend({
   depth: z.number().positive().optional(),
   includeHidden: z.boolean().optional(),
   compact: z.boolean().optional(),
+  activeModal: z.boolean().optional(),
 });
 
 const screenshotSchema = baseCommandSchema.extend({
@@ -231,6 +245,7 @@ const screenshotSchema = baseCommandSchema.extend({
   quality: z.number().min(0).max(100).optional(),
   type: z.enum(['png', 'jpeg']).optional(),
   omitBackground: z.boolean().optional(),
+  emulatePrint: z.boolean().optional(),
   timeout: z.number().positive().optional(),
 });
 
@@ -238,6 +253,13 @@ const getTextSchema = baseCommandSchema.extend({
   action: z.literal('getText'),
   selector: z.string(),
   timeout: z.number().positive().optional(),
+  structured: z.boolean().optional(),
+});
+
+const getTooltipSchema = baseCommandSchema.extend({
+  action: z.literal('getTooltip'),
+  selector: z.string(),
+  timeout: z.number().positive().optional(),
 });
 
 const getHtmlSchema = baseCommandSchema.extend({
@@ -316,6 +338,17 @@ const waitSchema = baseCommandSchema.extend({
   timeout: z.number().positive(),
 });
 
+const pauseSchema = baseCommandSchema.extend({
+  action: z.literal('pause'),
+  message: z.string().optional(),
+  timeout: z.number().positive().optional(),
+});
+
+const resumeSchema = baseCommandSchema.extend({
+  action: z.literal('resume'),
+  by: z.string().optional(),
+});
+
 const waitForSelectorSchema = baseCommandSchema.extend({
   action: z.literal('waitForSelector'),
   selector: z.string(),
@@ -342,6 +375,13 @@ const waitForUrlSchema = baseCommandSchema.extend({
   timeout: z.number().positive().optional(),
 });
 
+const waitForResponseSchema = baseCommandSchema.extend({
+  action: z.literal('waitForResponse'),
+  urlPattern: z.string(),
+  status: z.number().int().optional(),
+  timeout: z.number().positive().optional(),
+});
+
 const waitForTextSchema = baseCommandSchema.extend({
   action: z.literal('waitForText'),
   text: z.string(),
@@ -554,6 +594,37 @@ const unrouteSchema = baseCommandSchema.extend({
   url: z.string().optional(),
 });
 
+const getRoutesSchema = baseCommandSchema.extend({
+  action: z.literal('getRoutes'),
+});
+
+const harStartSchema = baseCommandSchema.extend({
+  action: z.literal('harStart'),
+  path: z.string().min(1),
+  content: z.boolean().optional(),
+});
+
+const harStopSchema = baseCommandSchema.extend({
+  action: z.literal('harStop'),
+});
+
+// ============================================================================
+// Download Commands
+// ============================================================================
+
+const downloadSchema = baseCommandSchema.extend({
+  action: z.literal('download'),
+  selector: z.string().min(1),
+  path: z.string().optional(),
+  downloadsDir: z.string().optional(),
+  timeout: z.number().positive().optional(),
+});
+
+const getDownloadsSchema = baseCommandSchema.extend({
+  action: z.literal('getDownloads'),
+  downloadsDir: z.string().optional(),
+});
+
 // ============================================================================
 // Cookie/Storage Commands
 // ============================================================================
@@ -607,6 +678,11 @@ const handleDialogSchema = baseCommandSchema.extend({
   promptText: z.string().optional(),
 });
 
+const setDialogAutoSchema = baseCommandSchema.extend({
+  action: z.literal('setDialogAuto'),
+  mode: z.enum(['accept', 'dismiss']),
+});
+
 // ============================================================================
 // Viewport Commands
 // ============================================================================
@@ -616,9 +692,20 @@ const setViewportSchema = baseCommandSchema.extend({
   viewport: ViewportSchema,
 });
 
+// The CLI sends the whole preset from its device registry; a bare name is
+// looked up among Playwright's devices
 const emulateDeviceSchema = baseCommandSchema.extend({
   action: z.literal('emulateDevice'),
   device: z.string(),
+  viewport: ViewportSchema.optional(),
+  deviceScaleFactor: z.number().positive().optional(),
+  isMobile: z.boolean().optional(),
+  hasTouch: z.boolean().optional(),
+  userAgent: z.string().optional(),
+});
+
+const resetDeviceSchema = baseCommandSchema.extend({
+  action: z.literal('resetDevice'),
 });
 
 const setGeolocationSchema = baseCommandSchema.extend({
@@ -673,6 +760,22 @@ const stopStreamSchema = baseCommandSchema.extend({
   action: z.literal('stopStream'),
 });
 
+// ============================================================================
+// Daemon Commands
+// ============================================================================
+
+const daemonStatsSchema = baseCommandSchema.extend({
+  action: z.literal('daemonStats'),
+});
+
+// ============================================================================
+// Extension Commands
+// ============================================================================
+
+const listExtensionsSchema = baseCommandSchema.extend({
+  action: z.literal('listExtensions'),
+});
+
 // ============================================================================
 // Agent Commands
 // ============================================================================
@@ -767,6 +870,32 @@ const emulateMediaSchema = baseCommandSchema.extend({
   forcedColors: z.enum(['active', 'none', 'null']).optional(),
 });
 
+const emulateOrientationSchema = baseCommandSchema.extend({
+  action: z.literal('emulateOrientation'),
+  orientation: z.enum(['portrait', 'landscape']).optional(),
+  // alpha, beta, gamma in degrees
+  reading: z.array(z.number()).length(3).optional(),
+});
+
+const emulateAccelerometerSchema = baseCommandSchema.extend({
+  action: z.literal('emulateAccelerometer'),
+  // x, y, z in m/s²; omitted to clear the override
+  reading: z.array(z.number()).length(3).optional(),
+});
+
+const emulateVisionSchema = baseCommandSchema.extend({
+  action: z.literal('emulateVision'),
+  vision: z.enum([
+    'none',
+    'blurredVision',
+    'reducedContrast',
+    'achromatopsia',
+    'deuteranopia',
+    'protanopia',
+    'tritanopia',
+  ]),
+});
+
 // ============================================================================
 // Tier 2: Vision/Screenshot Analysis
 // ============================================================================
@@ -857,6 +986,180 @@ const extractSchema = baseCommandSchema.extend({
   maxLength: z.number().optional().default(60000).describe('Max characters to return'),
 });
 
+const getStructuredDataSchema = baseCommandSchema.extend({
+  action: z.literal('getStructuredData'),
+  /** Only JSON-LD and microdata items of this schema.org type */
+  itemType: z.string().optional(),
+});
+
+const seoAuditSchema = baseCommandSchema.extend({
+  action: z.literal('seoAudit'),
+});
+
+const getFeedsSchema = baseCommandSchema.extend({
+  action: z.literal('getFeeds'),
+});
+
+const getPageInfoSchema = baseCommandSchema.extend({
+  action: z.literal('getPageInfo'),
+});
+
+const fetchFeedSchema = baseCommandSchema.extend({
+  action: z.literal('fetchFeed'),
+  url: z.string().url(),
+});
+
+const clickByTextSchema = baseCommandSchema.extend({
+  action: z.literal('clickByText'),
+  text: z.string().min(1),
+  exact: z.boolean().optional(),
+  timeout: z.number().positive().optional(),
+});
+
+const clickByRoleSchema = baseCommandSchema.extend({
+  action: z.literal('clickByRole'),
+  role: z.string().min(1),
+  name: z.string().optional(),
+  exact: z.boolean().optional(),
+  timeout: z.number().positive().optional(),
+});
+
+const tabToSchema = baseCommandSchema.extend({
+  action: z.literal('tabTo'),
+  text: z.string().min(1),
+  max: z.number().int().positive().optional(),
+});
+
+const getFocusedSchema = baseCommandSchema.extend({
+  action: z.literal('getFocused'),
+});
+
+const getOverlaysSchema = baseCommandSchema.extend({
+  action: z.literal('getOverlays'),
+});
+
+const getScrollPositionSchema = baseCommandSchema.extend({
+  action: z.literal('getScrollPosition'),
+  selector: z.string().optional(),
+});
+
+const scrollToSchema = baseCommandSchema.extend({
+  action: z.literal('scrollTo'),
+  x: z.number().nonnegative(),
+  y: z.number().nonnegative(),
+  selector: z.string().optional(),
+});
+
+const isInViewportSchema = baseCommandSchema.extend({
+  action: z.literal('isInViewport'),
+  selector: z.string(),
+  timeout: z.number().positive().optional(),
+});
+
+const setZoomSchema = baseCommandSchema.extend({
+  action: z.literal('setZoom'),
+  zoom: z.number().min(0.25).max(5),
+  css: z.boolean().optional(),
+});
+
+const hoverPathSchema = baseCommandSchema.extend({
+  action: z.literal('hoverPath'),
+  selectors: z.array(z.string()).min(1),
+  dwell: z.array(z.number().nonnegative()).optional(),
+  timeout: z.number().positive().optional(),
+});
+
+const screenshotTilesSchema = baseCommandSchema.extend({
+  action: z.literal('screenshotTiles'),
+  max: z.number().int().positive().optional(),
+  emulatePrint: z.boolean().optional(),
+  timeout: z.number().positive().optional(),
+});
+
+const getFaviconSchema = baseCommandSchema.extend({
+  action: z.literal('getFavicon'),
+  path: z.string().optional(),
+  timeout: z.number().positive().optional(),
+});
+
+const pwaAuditSchema = baseCommandSchema.extend({
+  action: z.literal('pwaAudit'),
+  timeout: z.number().positive().optional(),
+});
+
+const getStorageUsageSchema = baseCommandSchema.extend({
+  action: z.literal('getStorageUsage'),
+  timeout: z.number().positive().optional(),
+});
+
+const listCachesSchema = baseCommandSchema.extend({
+  action: z.literal('listCaches'),
+});
+
+const getCacheEntriesSchema = baseCommandSchema.extend({
+  action: z.literal('getCacheEntries'),
+  name: z.string().min(1),
+});
+
+const listMediaDevicesSchema = baseCommandSchema.extend({
+  action: z.literal('listMediaDevices'),
+});
+
+const mediaElementSchema = baseCommandSchema.extend({
+  action: z.literal('mediaElement'),
+  selector: z.string(),
+  value: z.enum(['play', 'pause', 'seek', 'mute', 'unmute', 'state']),
+  time: z.number().nonnegative().optional(),
+  timeout: z.number().positive().optional(),
+});
+
+const captureCanvasSchema = baseCommandSchema.extend({
+  action: z.literal('captureCanvas'),
+  selector: z.string(),
+  timeout: z.number().positive().optional(),
+});
+
+const setSliderSchema = baseCommandSchema.extend({
+  action: z.literal('setSlider'),
+  selector: z.string(),
+  amount: z.number(),
+  percent: z.boolean().optional(),
+  timeout: z.number().positive().optional(),
+});
+
+const reorderSchema = baseCommandSchema.extend({
+  action: z.literal('reorder'),
+  selector: z.string(),
+  index: z.number().int().nonnegative(),
+  timeout: z.number().positive().optional(),
+});
+
+const writeSchema = baseCommandSchema.extend({
+  action: z.literal('write'),
+  selector: z.string(),
+  text: z.string().optional(),
+  html: z.string().optional(),
+  clear: z.boolean().optional(),
+  timeout: z.number().positive().optional(),
+});
+
+const pasteSchema = baseCommandSchema.extend({
+  action: z.literal('paste'),
+  selector: z.string(),
+  text: z.string().optional(),
+  html: z.string().optional(),
+  fromClipboard: z.boolean().optional(),
+  timeout: z.number().positive().optional(),
+});
+
+const setDateSchema = baseCommandSchema.extend({
+  action: z.literal('setDate'),
+  selector: z.string(),
+  value: z.string(),
+  format: z.string().optional(),
+  timeout: z.number().positive().optional(),
+});
+
 // ============================================================================
 // Tier 3: Dropdown Options Retrieval (from browser-use)
 // ============================================================================
@@ -947,6 +1250,70 @@ const multiClearSchema = baseCommandSchema.extend({
   triggerFrameworkEvents: z.boolean().optional().default(true),
 });
 
+const expectVisibleSchema = baseCommandSchema.extend({
+  action: z.literal('expectVisible'),
+  selector: z.string(),
+  timeout: z.number().positive().optional(),
+});
+
+const expectTextSchema = baseCommandSchema.extend({
+  action: z.literal('expectText'),
+  selector: z.string(),
+  text: z.string(),
+  exact: z.boolean().optional(),
+  timeout: z.number().positive().optional(),
+});
+
+const expectUrlSchema = baseCommandSchema.extend({
+  action: z.literal('expectUrl'),
+  urlPattern: z.string(),
+  timeout: z.number().positive().optional(),
+});
+
+const expectCountSchema = baseCommandSchema.extend({
+  action: z.literal('expectCount'),
+  selector: z.string(),
+  count: z.number().int().nonnegative(),
+  timeout: z.number().positive().optional(),
+});
+
+const expectRequestSchema = baseCommandSchema.extend({
+  action: z.literal('expectRequest'),
+  urlPattern: z.string(),
+  method: z.string().optional(),
+  bodyContains: z.string().optional(),
+});
+
+// ============================================================================
+// Lifecycle Event Commands
+// ============================================================================
+
+const getEventsSchema = baseCommandSchema.extend({
+  action: z.literal('getEvents'),
+  /** Sequence number of the last event already seen */
+  since: z.number().int().nonnegative().optional(),
+});
+
+// ============================================================================
+// Notification Commands
+// ============================================================================
+
+const notificationCaptureSchema = baseCommandSchema.extend({
+  action: z.literal('notificationCapture'),
+  enabled: z.boolean(),
+});
+
+const getNotificationsSchema = baseCommandSchema.extend({
+  action: z.literal('getNotifications'),
+  clear: z.boolean().optional(),
+});
+
+const tapMessagesSchema = baseCommandSchema.extend({
+  action: z.literal('tapMessages'),
+  channel: z.string().optional(),
+  since: z.number().nonnegative().optional(),
+});
+
 // ============================================================================
 // Tier 3: Network Request Viewing
 // ============================================================================
@@ -955,6 +1322,12 @@ const getRequestsSchema = baseCommandSchema.extend({
   action: z.literal('getRequests'),
   urlPattern: z.string().optional(),
   clear: z.boolean().optional(),
+  /** Only GraphQL requests, with their operations */
+  graphql: z.boolean().optional(),
+  /** Only GraphQL requests running this operation */
+  operation: z.string().optional(),
+  /** Include captured GraphQL response bodies */
+  includeBody: z.boolean().optional(),
 });
 
 // ============================================================================
@@ -995,6 +1368,8 @@ export const commandSchema = z.discriminatedUnion('action', [
   hoverSchema,
   focusSchema,
   pressSchema,
+  keyDownSchema,
+  keyUpSchema,
   scrollSchema,
   dragSchema,
   uploadSchema,
@@ -1002,6 +1377,7 @@ export const commandSchema = z.discriminatedUnion('action', [
   snapshotSchema,
   screenshotSchema,
   getTextSchema,
+  getTooltipSchema,
   getHtmlSchema,
   getAttributeSchema,
   getValueSchema,
@@ -1017,10 +1393,13 @@ export const commandSchema = z.discriminatedUnion('action', [
   isHiddenSchema,
   // Wait
   waitSchema,
+  pauseSchema,
+  resumeSchema,
   waitForSelectorSchema,
   waitForNavigationSchema,
   waitForLoadStateSchema,
   waitForUrlSchema,
+  waitForResponseSchema,
   waitForTextSchema,
   waitForFunctionSchema,
   // Frames
@@ -1040,7 +1419,21 @@ export const commandSchema = z.discriminatedUnion('action', [
   setOfflineSchema,
   routeSchema,
   unrouteSchema,
+  getRoutesSchema,
+  harStartSchema,
+  harStopSchema,
+  downloadSchema,
+  getDownloadsSchema,
   getRequestsSchema,
+  expectRequestSchema,
+  expectVisibleSchema,
+  expectTextSchema,
+  expectUrlSchema,
+  expectCountSchema,
+  getEventsSchema,
+  notificationCaptureSchema,
+  getNotificationsSchema,
+  tapMessagesSchema,
   // Cookies/Storage
   getCookiesSchema,
   setCookiesSchema,
@@ -1053,9 +1446,11 @@ export const commandSchema = z.discriminatedUnion('action', [
   clearSessionStorageSchema,
   // Dialog
   handleDialogSchema,
+  setDialogAutoSchema,
   // Viewport
   setViewportSchema,
   emulateDeviceSchema,
+  resetDeviceSchema,
   setGeolocationSchema,
   // Recording
   startRecordingSchema,
@@ -1064,6 +1459,10 @@ export const commandSchema = z.discriminatedUnion('action', [
   // Streaming
   startStreamSchema,
   stopStreamSchema,
+  // Daemon
+  daemonStatsSchema,
+  // Extensions
+  listExtensionsSchema,
   // Agent
   agentRunSchema,
   agentStepSchema,
@@ -1099,6 +1498,9 @@ export const commandSchema = z.discriminatedUnion('action', [
   setLocaleSchema,
   setPermissionsSchema,
   emulateMediaSchema,
+  emulateVisionSchema,
+  emulateOrientationSchema,
+  emulateAccelerometerSchema,
   // Tier 2: Vision/Screenshot Analysis
   analyzeScreenshotSchema,
   // Tier 2: Element Highlighting Demo Mode
@@ -1118,6 +1520,35 @@ export const commandSchema = z.discriminatedUnion('action', [
   // Tier 3: Browser-Use Features
   searchSchema,
   extractSchema,
+  getStructuredDataSchema,
+  seoAuditSchema,
+  getFeedsSchema,
+  getPageInfoSchema,
+  fetchFeedSchema,
+  clickByTextSchema,
+  clickByRoleSchema,
+  tabToSchema,
+  getFocusedSchema,
+  hoverPathSchema,
+  getOverlaysSchema,
+  getScrollPositionSchema,
+  scrollToSchema,
+  isInViewportSchema,
+  setZoomSchema,
+  screenshotTilesSchema,
+  getFaviconSchema,
+  pwaAuditSchema,
+  getStorageUsageSchema,
+  listCachesSchema,
+  getCacheEntriesSchema,
+  listMediaDevicesSchema,
+  mediaElementSchema,
+  captureCanvasSchema,
+  setSliderSchema,
+  reorderSchema,
+  setDateSchema,
+  writeSchema,
+  pasteSchema,
   getDropdownOptionsSchema,
   detectPaginationSchema,
   findTextOnPageSchema,
@@ -1182,14 +1613,23 @@ export interface Response {
   success: boolean;
   result?: unknown;
   error?: string;
+  /** Machine-readable error code, e.g. "click_intercepted" */
+  code?: string;
+  /** Structured error context, e.g. the element that intercepted a click */
+  details?: Record<string, unknown>;
 }
 
 export function successResponse(id: string, result?: unknown): Response {
   return { id, success: true, result };
 }
 
-export function errorResponse(id: string, error: string): Response {
-  return { id, success: false, error };
+export function errorResponse(
+  id: string,
+  error: string,
+  code?: string,
+  details?: Record<string, unknown>
+): Response {
+  return { id, success: false, error, code, details };
 }
 
 export function serializeResponse(response: Response): string {
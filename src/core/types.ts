@@ -39,6 +39,8 @@ export interface RefData {
   role: string;
   name?: string;
   nth?: number;
+  /** Selector of the element the ref was found in, for scoped snapshots */
+  scope?: string;
 }
 
 export type RefMap = Record<string, RefData>;
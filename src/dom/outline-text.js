@@ -0,0 +1,114 @@
+/**
+ * Outline the text under `root` for `text --structured`: headings,
+ * paragraphs, lists, code, quotes and tables in reading order, each with the
+ * links inside it. Hidden elements are skipped.
+ *
+ * Runs in the page, so everything it needs is declared inside it. Plain
+ * JavaScript because both daemons load this one file: the Node daemon reads
+ * it at startup (the build copies it into dist/dom/), and the native daemon
+ * embeds it with `include_str!`.
+ *
+ * @param {Element} root
+ * @returns {Array<{type: string, level?: number, text?: string, ordered?: boolean,
+ *   items?: string[], rows?: string[][], links?: Array<{text: string, href: string}>}>}
+ */
+function outlineText(root) {
+  const BLOCKS = new Set(
+    ('ADDRESS ARTICLE ASIDE BLOCKQUOTE DD DETAILS DIV DL DT FIELDSET FIGCAPTION FIGURE ' +
+      'FOOTER FORM H1 H2 H3 H4 H5 H6 HEADER HR LI MAIN NAV OL P PRE SECTION SUMMARY TABLE UL'
+    ).split(' ')
+  );
+  const SKIPPED = new Set(['SCRIPT', 'STYLE', 'NOSCRIPT', 'TEMPLATE', 'SVG', 'CANVAS']);
+  const blocks = [];
+
+  const clean = (text) => (text ?? '').replace(/\s+/g, ' ').trim();
+  const hidden = (el) => {
+    if (SKIPPED.has(el.tagName.toUpperCase())) return true;
+    const style = getComputedStyle(el);
+    return style.display === 'none' || style.visibility === 'hidden';
+  };
+  const textOf = (el) => clean(el.innerText ?? el.textContent);
+  const linksIn = (els) =>
+    els
+      .flatMap((el) => [
+        ...(el.matches('a[href]') ? [el] : []),
+        ...Array.from(el.querySelectorAll('a[href]')),
+      ])
+      .filter((a) => !hidden(a))
+      .map((a) => ({ text: textOf(a), href: a.href }))
+      .filter((link) => link.text);
+  const push = (block, ...els) => {
+    const links = linksIn(els);
+    blocks.push(links.length ? { ...block, links } : block);
+  };
+
+  const walk = (el) => {
+    if (hidden(el)) return;
+    const tag = el.tagName.toUpperCase();
+
+    if (/^H[1-6]$/.test(tag)) {
+      const text = textOf(el);
+      if (text) push({ type: 'heading', level: Number(tag[1]), text }, el);
+      return;
+    }
+    if (tag === 'UL' || tag === 'OL') {
+      const items = Array.from(el.children)
+        .filter((li) => li.tagName === 'LI' && !hidden(li))
+        .map(textOf)
+        .filter(Boolean);
+      if (items.length) push({ type: 'list', ordered: tag === 'OL', items }, el);
+      return;
+    }
+    if (tag === 'PRE') {
+      const text = el.textContent ?? '';
+      if (text.trim()) blocks.push({ type: 'code', text: text.replace(/\n+$/, '') });
+      return;
+    }
+    if (tag === 'BLOCKQUOTE') {
+      const text = textOf(el);
+      if (text) push({ type: 'quote', text }, el);
+      return;
+    }
+    if (tag === 'TABLE') {
+      const rows = Array.from(el.rows)
+        .map((row) => Array.from(row.cells).map(textOf))
+        .filter((cells) => cells.some(Boolean));
+      if (rows.length) push({ type: 'table', rows }, el);
+      return;
+    }
+
+    // A container of blocks: outline each child. Runs of inline content
+    // between them become their own paragraphs.
+    const children = Array.from(el.children);
+    if (!children.some((child) => BLOCKS.has(child.tagName.toUpperCase()))) {
+      const text = textOf(el);
+      if (text) push({ type: 'paragraph', text }, el);
+      return;
+    }
+    let inline = '';
+    let inlineEls = [];
+    const flush = () => {
+      const text = clean(inline);
+      if (text) push({ type: 'paragraph', text }, ...inlineEls);
+      inline = '';
+      inlineEls = [];
+    };
+    for (const node of Array.from(el.childNodes)) {
+      if (node.nodeType === Node.TEXT_NODE) {
+        inline += ` ${node.textContent}`;
+      } else if (node instanceof Element && !hidden(node)) {
+        if (BLOCKS.has(node.tagName.toUpperCase())) {
+          flush();
+          walk(node);
+        } else {
+          inline += ` ${textOf(node)}`;
+          inlineEls.push(node);
+        }
+      }
+    }
+    flush();
+  };
+
+  walk(root);
+  return blocks;
+}
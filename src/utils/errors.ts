@@ -137,6 +137,17 @@ export class SessionNotFoundError extends BrowserError {
   }
 }
 
+export class PolicyViolationError extends BrowserError {
+  constructor(url: string, reason: string) {
+    super(
+      `${url} ${reason}`,
+      'POLICY_VIOLATION',
+      `The session's --allow-urls/--deny-urls rules keep it away from this URL.`
+    );
+    this.name = 'PolicyViolationError';
+  }
+}
+
 // ============================================================================
 // Error Mapping
 // ============================================================================